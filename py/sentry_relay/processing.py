@@ -26,10 +26,14 @@ __all__ = [
     "convert_datascrubbing_config",
     "pii_strip_event",
     "pii_selector_suggestions_from_event",
+    "pii_selector_suggestions",
+    "pii_audit",
     "VALID_PLATFORMS",
     "validate_rule_condition",
     "validate_sampling_condition",
     "validate_sampling_configuration",
+    "run_dynamic_sampling",
+    "run_inbound_filters",
     "normalize_project_config",
     "normalize_cardinality_limit_config",
     "normalize_global_config",
@@ -251,6 +255,58 @@ def pii_selector_suggestions_from_event(
     return json_loads(decode_str(raw_rv, free=True))
 
 
+def pii_selector_suggestions(
+    item_type,
+    item,
+    json_dumps: Callable[[Any], Any] = json.dumps,
+    json_loads: Callable[[str | bytes], Any] = json.loads,
+):
+    """
+    Walk through an item and collect selectors that can be applied to it in a
+    PII config. Supported item types are ``event``, ``span``, ``log`` and
+    ``replay_event``.
+    """
+    raw_rv = rustcall(
+        lib.relay_pii_selector_suggestions,
+        encode_str(item_type),
+        encode_str(json_dumps(item)),
+    )
+    return json_loads(decode_str(raw_rv, free=True))
+
+
+def pii_audit(
+    config,
+    item_type,
+    item,
+    filename="",
+    json_dumps: Callable[[Any], Any] = json.dumps,
+    json_loads: Callable[[str | bytes], Any] = json.loads,
+):
+    """
+    Run a PII config over an item and report which rules matched, without
+    scrubbing it.
+
+    Supported item types are ``event``, ``span``, ``log``, ``replay_event``,
+    ``check_in``, ``replay_recording`` and ``attachment``. The last two expect
+    ``item`` as raw bytes, all others as JSON-serializable value. The
+    ``filename`` is only used for attachments.
+
+    Check-ins are not scrubbed by Relay. Their report lists the rules that
+    would apply and has ``scrubbed`` set to ``False``.
+    """
+    if not isinstance(item, (bytes, bytearray)):
+        item = json_dumps(item).encode("utf-8")
+
+    raw_rv = rustcall(
+        lib.relay_pii_audit,
+        encode_str(json_dumps(config)),
+        encode_str(item_type),
+        encode_str(filename),
+        make_buf(item),
+    )
+    return json_loads(decode_str(raw_rv, free=True))
+
+
 def parse_release(release, json_loads: Callable[[str | bytes], Any] = json.loads):
     """Parses a release string into a dictionary of its components."""
     return json_loads(
@@ -298,6 +354,62 @@ def validate_sampling_configuration(condition):
         raise ValueError(error)
 
 
+def _encode_optional(value, json_dumps):
+    return encode_str("" if value is None else json_dumps(value))
+
+
+def run_dynamic_sampling(
+    sampling_config=None,
+    root_sampling_config=None,
+    dsc=None,
+    event=None,
+    json_dumps: Callable[[Any], Any] = json.dumps,
+    json_loads: Callable[[str | bytes], Any] = json.loads,
+):
+    """
+    Evaluate dynamic sampling rules the same way Relay does.
+
+    Transaction rules of ``sampling_config`` are matched against ``event`` and trace
+    rules of ``root_sampling_config`` against the dynamic sampling context ``dsc``.
+    Returns ``None`` if no rule matched, otherwise a dict with ``sample_rate``,
+    ``matched_rule_ids`` and ``decision``.
+    """
+    raw_rv = rustcall(
+        lib.relay_run_dynamic_sampling,
+        _encode_optional(sampling_config, json_dumps),
+        _encode_optional(root_sampling_config, json_dumps),
+        _encode_optional(dsc, json_dumps),
+        _encode_optional(event, json_dumps),
+    )
+    return json_loads(decode_str(raw_rv, free=True))
+
+
+def run_inbound_filters(
+    config,
+    event,
+    global_config=None,
+    client_ip=None,
+    json_dumps: Callable[[Any], Any] = json.dumps,
+    json_loads: Callable[[str | bytes], Any] = json.loads,
+):
+    """
+    Run inbound filters of a project on an event.
+
+    ``config`` is the ``filterSettings`` of a project config and ``global_config`` the
+    generic ``filters`` of the global config. Returns ``None`` if the event passes all
+    filters, otherwise the identifier of the filter that matched, such as
+    ``"release-version"``.
+    """
+    raw_rv = rustcall(
+        lib.relay_run_inbound_filters,
+        encode_str(json_dumps(config)),
+        _encode_optional(global_config, json_dumps),
+        encode_str(client_ip or ""),
+        encode_str(json_dumps(event)),
+    )
+    return json_loads(decode_str(raw_rv, free=True))
+
+
 def normalize_project_config(
     config,
     json_dumps: Callable[[Any], Any] = json.dumps,
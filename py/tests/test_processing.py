@@ -319,6 +319,85 @@ def test_validate_sampling_configuration():
     sentry_relay.validate_sampling_configuration(config)
 
 
+def test_pii_audit():
+    config = {"applications": {"$string": ["@ip:replace"]}}
+
+    report = sentry_relay.pii_audit(
+        config, "log", {"level": "info", "body": "from 127.0.0.1"}
+    )
+    assert report == {
+        "matches": [
+            {
+                "rule_id": "@ip:replace",
+                "path": "body",
+                "redaction": "replace",
+                "count": 1,
+            }
+        ],
+        "scrubbed": True,
+    }
+
+    report = sentry_relay.pii_audit(
+        config, "check_in", {"monitor_slug": "my-monitor", "environment": "127.0.0.1"}
+    )
+    assert report["matches"][0]["path"] == "environment"
+    assert report["scrubbed"] is False
+
+    report = sentry_relay.pii_audit(
+        {"applications": {"$binary": ["@ip:replace"]}},
+        "attachment",
+        b"from 127.0.0.1",
+        filename="log.txt",
+    )
+    assert report["matches"][0]["path"] == "log.txt"
+
+
+def test_run_dynamic_sampling():
+    sampling_config = {
+        "rules": [
+            {
+                "id": 1,
+                "type": "trace",
+                "samplingValue": {"type": "sampleRate", "value": 0.25},
+                "condition": {"op": "eq", "name": "trace.release", "value": "1.0"},
+            }
+        ]
+    }
+    dsc = {
+        "trace_id": "67e5504410b1426f9247bb680e5fe0c8",
+        "public_key": "abd0f232775f45feab79864e580d160b",
+        "release": "1.0",
+    }
+
+    result = sentry_relay.run_dynamic_sampling(
+        root_sampling_config=sampling_config, dsc=dsc
+    )
+    assert result["sample_rate"] == 0.25
+    assert result["matched_rule_ids"] == [1]
+    assert result["decision"] in ("keep", "drop")
+
+    dsc["release"] = "2.0"
+    result = sentry_relay.run_dynamic_sampling(
+        root_sampling_config=sampling_config, dsc=dsc
+    )
+    assert result is None
+
+
+def test_run_inbound_filters():
+    config = {
+        "releases": {"releases": ["1.*"]},
+        "clientIps": {"blacklistedIps": ["127.0.0.1"]},
+    }
+
+    assert sentry_relay.run_inbound_filters(config, {"release": "2.0"}) is None
+    result = sentry_relay.run_inbound_filters(config, {"release": "1.2.3"})
+    assert result == "release-version"
+    result = sentry_relay.run_inbound_filters(
+        config, {"release": "2.0"}, client_ip="127.0.0.1"
+    )
+    assert result == "ip-address"
+
+
 def test_normalize_project_config():
     config = {"allowedDomains": ["*"], "trustedRelays": [], "piiConfig": None}
     normalized = sentry_relay.normalize_project_config(config)
@@ -15,6 +15,13 @@
 //! let relay_id = relay_auth::generate_relay_id();
 //! let (private_key, public_key) = relay_auth::generate_key_pair();
 //! ```
+//!
+//! # Key Rotation
+//!
+//! Every signature carries the [`KeyId`] of the signing key in its [`SignatureHeader`]. A Relay
+//! can be known with more than one public key, in which case [`verify_any`] selects the key to
+//! verify with based on this identifier. This allows a Relay to switch to a new key pair while the
+//! upstream accepts both the old and the new key.
 
 #![warn(missing_docs)]
 #![doc(
@@ -34,7 +41,7 @@ use rand::{RngCore, thread_rng};
 use relay_common::time::UnixTimestamp;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use sha2::Sha512;
+use sha2::{Digest, Sha512};
 use uuid::Uuid;
 
 include!(concat!(env!("OUT_DIR"), "/constants.gen.rs"));
@@ -147,6 +154,26 @@ pub enum UnpackError {
     SignatureExpired,
 }
 
+/// A short identifier of a [`PublicKey`].
+///
+/// The identifier is derived from the public key and embedded in signature headers, so that the
+/// verifying side can pick the right key when a Relay is known with multiple keys.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct KeyId(String);
+
+impl KeyId {
+    /// Returns the string representation of the key identifier.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// A wrapper around packed data that adds a timestamp.
 ///
 /// This is internally automatically used when data is signed.
@@ -155,6 +182,12 @@ pub struct SignatureHeader {
     /// The timestamp of when the data was packed and signed.
     #[serde(rename = "t", skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<DateTime<Utc>>,
+
+    /// The identifier of the key that was used to sign the data.
+    ///
+    /// Signatures created by older Relays do not contain a key identifier.
+    #[serde(rename = "k", default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<KeyId>,
 }
 
 impl SignatureHeader {
@@ -166,12 +199,22 @@ impl SignatureHeader {
             false
         }
     }
+
+    /// Reads the header of a signature without verifying it.
+    ///
+    /// The contents of the header must not be trusted before the signature has been verified.
+    fn peek(sig: &str) -> Option<Self> {
+        let (_, header_encoded) = sig.split_once('.')?;
+        let header = BASE64URL_NOPAD.decode(header_encoded.as_bytes()).ok()?;
+        serde_json::from_slice(&header).ok()
+    }
 }
 
 impl Default for SignatureHeader {
     fn default() -> SignatureHeader {
         SignatureHeader {
             timestamp: Some(Utc::now()),
+            key_id: None,
         }
     }
 }
@@ -193,11 +236,33 @@ pub struct Registration {
 }
 
 impl SecretKey {
+    /// Returns the public key corresponding to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            inner: self.inner.verifying_key(),
+        }
+    }
+
+    /// Returns the identifier of the key pair, see [`PublicKey::key_id`].
+    pub fn key_id(&self) -> KeyId {
+        self.public_key().key_id()
+    }
+
+    /// Returns the default header for signatures created with this key.
+    ///
+    /// The header contains the current timestamp and the identifier of this key.
+    fn default_header(&self) -> SignatureHeader {
+        SignatureHeader {
+            key_id: Some(self.key_id()),
+            ..Default::default()
+        }
+    }
+
     /// Signs some data with the secret key and returns the signature.
     ///
     /// This is will sign with the default header.
     pub fn sign(&self, data: &[u8]) -> String {
-        self.sign_with_header(data, &SignatureHeader::default())
+        self.sign_with_header(data, &self.default_header())
     }
 
     /// Signs some data with the secret key and a specific header and
@@ -220,7 +285,7 @@ impl SecretKey {
 
     /// Packs some serializable data into JSON and signs it with the default header.
     pub fn pack<S: Serialize>(&self, data: S) -> (Vec<u8>, String) {
-        self.pack_with_header(data, &SignatureHeader::default())
+        self.pack_with_header(data, &self.default_header())
     }
 
     /// Packs some serializable data into JSON and signs it with the specified header.
@@ -300,6 +365,15 @@ pub struct PublicKey {
 }
 
 impl PublicKey {
+    /// Returns the identifier of this key.
+    ///
+    /// The identifier is a URL-safe base64 encoding of the first bytes of the SHA512 hash of the
+    /// key. It is stable for the same key.
+    pub fn key_id(&self) -> KeyId {
+        let digest = Sha512::digest(self.inner.as_bytes());
+        KeyId(BASE64URL_NOPAD.encode(&digest[..6]))
+    }
+
     /// Verifies the signature and returns the embedded signature
     /// header.
     pub fn verify_meta(&self, data: &[u8], sig: &str) -> Option<SignatureHeader> {
@@ -402,6 +476,26 @@ impl fmt::Debug for PublicKey {
 
 relay_common::impl_str_serde!(PublicKey, "a public key");
 
+/// Verifies a signature against any of the given public keys.
+///
+/// If the signature carries a [`KeyId`], only the key with the matching identifier is tried.
+/// Signatures without a key identifier are verified against all keys in order. Returns the key
+/// that verified the signature along with the embedded signature header.
+pub fn verify_any<'a, I>(
+    keys: I,
+    data: &[u8],
+    sig: &str,
+) -> Option<(&'a PublicKey, SignatureHeader)>
+where
+    I: IntoIterator<Item = &'a PublicKey>,
+{
+    let key_id = SignatureHeader::peek(sig).and_then(|header| header.key_id);
+
+    keys.into_iter()
+        .filter(|key| key_id.as_ref().is_none_or(|id| *id == key.key_id()))
+        .find_map(|key| Some((key, key.verify_meta(data, sig)?)))
+}
+
 /// Generates an Relay ID.
 pub fn generate_relay_id() -> RelayId {
     Uuid::new_v4()
@@ -517,6 +611,8 @@ pub struct RegisterState {
     timestamp: UnixTimestamp,
     relay_id: RelayId,
     public_key: PublicKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_public_key: Option<PublicKey>,
     rand: String,
 }
 
@@ -535,6 +631,11 @@ impl RegisterState {
     pub fn public_key(&self) -> &PublicKey {
         &self.public_key
     }
+
+    /// Returns the staged next public key of the requesting downstream Relay, if any.
+    pub fn next_public_key(&self) -> Option<&PublicKey> {
+        self.next_public_key.as_ref()
+    }
 }
 
 /// Generates a new random token for the register state.
@@ -554,6 +655,8 @@ fn nonce() -> String {
 pub struct RegisterRequest {
     relay_id: RelayId,
     public_key: PublicKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_public_key: Option<PublicKey>,
     #[serde(default)]
     version: RelayVersion,
 }
@@ -564,10 +667,19 @@ impl RegisterRequest {
         RegisterRequest {
             relay_id: *relay_id,
             public_key: public_key.clone(),
+            next_public_key: None,
             version: RelayVersion::current(),
         }
     }
 
+    /// Announces a staged key that the Relay will switch to after a key rotation.
+    ///
+    /// The upstream should accept signatures from both the current and the next key.
+    pub fn with_next_public_key(mut self, next_public_key: Option<PublicKey>) -> Self {
+        self.next_public_key = next_public_key;
+        self
+    }
+
     /// Unpacks a signed register request for bootstrapping.
     ///
     /// This unpacks the embedded public key first, then verifies if the
@@ -593,12 +705,18 @@ impl RegisterRequest {
         &self.public_key
     }
 
+    /// Returns the staged next public key of the registering Relay, if any.
+    pub fn next_public_key(&self) -> Option<&PublicKey> {
+        self.next_public_key.as_ref()
+    }
+
     /// Creates a register challenge for this request.
     pub fn into_challenge(self, secret: &[u8]) -> RegisterChallenge {
         let state = RegisterState {
             timestamp: UnixTimestamp::now(),
             relay_id: self.relay_id,
             public_key: self.public_key,
+            next_public_key: self.next_public_key,
             rand: nonce(),
         };
 
@@ -759,6 +877,47 @@ mod tests {
         assert!(!pk.verify(data, bad_sig));
     }
 
+    #[test]
+    fn test_key_id() {
+        let sk: SecretKey =
+        "OvXFVm1tIUi8xDTuyHX1SSqdMc8nCt2qU9IUaH5p7oUk5pHZsdnfXNiMWiMLtSE86J3N9Peo5CBP1YQHDUkApQ"
+            .parse()
+            .unwrap();
+        let pk: PublicKey = "JOaR2bHZ31zYjFojC7UhPOidzfT3qOQgT9WEBw1JAKU"
+            .parse()
+            .unwrap();
+
+        assert_eq!(sk.public_key(), pk);
+        assert_eq!(sk.key_id(), pk.key_id());
+        assert_eq!(pk.key_id().as_str().len(), 8);
+
+        let header = pk.verify_meta(b"data", &sk.sign(b"data")).unwrap();
+        assert_eq!(header.key_id, Some(pk.key_id()));
+    }
+
+    #[test]
+    fn test_verify_any() {
+        let (current_sk, current_pk) = generate_key_pair();
+        let (next_sk, next_pk) = generate_key_pair();
+        let (other_sk, _) = generate_key_pair();
+        let data = b"Hello World!";
+        let keys = [&current_pk, &next_pk];
+
+        let (key, _) = verify_any(keys, data, &current_sk.sign(data)).unwrap();
+        assert_eq!(key, &current_pk);
+
+        let (key, _) = verify_any(keys, data, &next_sk.sign(data)).unwrap();
+        assert_eq!(key, &next_pk);
+
+        assert!(verify_any(keys, data, &other_sk.sign(data)).is_none());
+
+        // Signatures of older Relays do not contain a key id.
+        let legacy_sig = next_sk.sign_with_header(data, &SignatureHeader::default());
+        let (key, header) = verify_any(keys, data, &legacy_sig).unwrap();
+        assert_eq!(key, &next_pk);
+        assert!(header.key_id.is_none());
+    }
+
     #[test]
     fn test_registration() {
         let max_age = Duration::minutes(15);
@@ -811,6 +970,27 @@ mod tests {
         assert_eq!(response.version, LATEST_VERSION);
     }
 
+    #[test]
+    fn test_registration_next_key() {
+        let relay_id = generate_relay_id();
+        let (sk, pk) = generate_key_pair();
+        let (_, next_pk) = generate_key_pair();
+
+        let request =
+            RegisterRequest::new(&relay_id, &pk).with_next_public_key(Some(next_pk.clone()));
+        let (request_bytes, request_sig) = sk.pack(request);
+
+        let request =
+            RegisterRequest::bootstrap_unpack(&request_bytes, &request_sig, None).unwrap();
+        assert_eq!(request.next_public_key(), Some(&next_pk));
+
+        let upstream_secret = b"secret";
+        let challenge = request.into_challenge(upstream_secret);
+        let state = SignedRegisterState(challenge.token().to_owned());
+        let register_state = state.unpack(upstream_secret, None).unwrap();
+        assert_eq!(register_state.next_public_key(), Some(&next_pk));
+    }
+
     /// This is a pseudo-test to easily generate the strings used by test_auth.py
     /// You can copy the output to the top of the test_auth.py when there are changes in the
     /// exchanged authentication structures.
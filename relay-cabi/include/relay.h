@@ -675,6 +675,31 @@ struct RelayStr relay_pii_strip_event(const struct RelayStr *config,
  */
 struct RelayStr relay_pii_selector_suggestions_from_event(const struct RelayStr *event);
 
+/**
+ * Runs a PII config over an item and reports which rules matched, without scrubbing the item.
+ *
+ * Supported item types are `event`, `span`, `log`, `replay_event`, `replay_recording`,
+ * `attachment`, and `check_in`. The `filename` is only used for attachments. Check-ins are not
+ * scrubbed by Relay, so their report lists the rules that would apply to any field.
+ *
+ * Returns a JSON report with a list of `matches`, each containing the `rule_id`, the `path` of the
+ * matched value, the `redaction`, and the `count` of matches. The `scrubbed` flag is `false` for
+ * item types that Relay does not scrub.
+ */
+struct RelayStr relay_pii_audit(const struct RelayStr *config,
+                                const struct RelayStr *item_type,
+                                const struct RelayStr *filename,
+                                const struct RelayBuf *item);
+
+/**
+ * Walk through an item and collect selectors that can be applied to it in a PII config.
+ *
+ * Like [`relay_pii_selector_suggestions_from_event`], but supports the item types `event`,
+ * `span`, `log`, and `replay_event`.
+ */
+struct RelayStr relay_pii_selector_suggestions(const struct RelayStr *item_type,
+                                               const struct RelayStr *item);
+
 /**
  * A test function that always panics.
  */
@@ -705,6 +730,31 @@ struct RelayStr relay_validate_rule_condition(const struct RelayStr *value);
  */
 struct RelayStr relay_validate_sampling_configuration(const struct RelayStr *value);
 
+/**
+ * Runs dynamic sampling on an event and its dynamic sampling context.
+ *
+ * All arguments are JSON strings and may be empty if not available: the sampling config of the
+ * event's project, the sampling config of the trace root project, the DSC, and the event. Returns
+ * `null` if no rule matched, or an object with the effective `sample_rate`, the
+ * `matched_rule_ids`, and the `decision`. Configs with unsupported rules never match.
+ */
+struct RelayStr relay_run_dynamic_sampling(const struct RelayStr *sampling_config,
+                                           const struct RelayStr *root_sampling_config,
+                                           const struct RelayStr *dsc,
+                                           const struct RelayStr *event);
+
+/**
+ * Runs inbound filters on an event.
+ *
+ * Takes the project's filter config, the optional generic filters from the global config, the
+ * optional client IP address of the request, and the event. Returns `null` if the event passes
+ * all filters, or the identifier of the first filter that matched, such as `"release-version"`.
+ */
+struct RelayStr relay_run_inbound_filters(const struct RelayStr *config,
+                                          const struct RelayStr *global_config,
+                                          const struct RelayStr *client_ip,
+                                          const struct RelayStr *event);
+
 /**
  * Normalize a project config.
  */
@@ -5,9 +5,14 @@
 
 use std::cmp::Ordering;
 use std::ffi::CStr;
+use std::fmt;
+use std::net::IpAddr as NetIpAddr;
+use std::ops::ControlFlow;
 use std::os::raw::c_char;
+use std::pin::pin;
 use std::slice;
 use std::sync::OnceLock;
+use std::task::{Context, Poll, Waker};
 
 use chrono::{DateTime, Utc};
 use relay_cardinality::CardinalityLimit;
@@ -17,17 +22,22 @@ use relay_event_normalization::{
     RawUserAgentInfo, normalize_event, validate_event,
 };
 use relay_event_schema::processor::{ProcessingState, process_value, split_chunks};
-use relay_event_schema::protocol::{Event, IpAddr, VALID_PLATFORMS};
+use relay_event_schema::protocol::{Event, IpAddr, OurLog, Replay, Span, VALID_PLATFORMS};
+use relay_filter::{GenericFiltersConfig, ProjectFiltersConfig};
 use relay_pii::{
     DataScrubbingConfig, InvalidSelectorError, PiiConfig, PiiConfigError, PiiProcessor,
     SelectorSpec, selector_suggestions_from_value,
 };
 use relay_protocol::{Annotated, Remark, RuleCondition};
-use relay_sampling::SamplingConfig;
+use relay_replays::audit::audit_item;
+use relay_sampling::config::{RuleId, RuleType};
+use relay_sampling::evaluation::{SamplingEvaluator, SamplingMatch};
+use relay_sampling::{DynamicSamplingContext, SamplingConfig};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::core::RelayStr;
+use crate::core::{RelayBuf, RelayStr};
 
 /// Configuration for the store step -- validation and normalization.
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -266,10 +276,13 @@ pub unsafe extern "C" fn relay_store_normalizer_normalize_event(
         enrich_spans: false,
         max_tag_value_length: usize::MAX,
         span_description_rules: None,
+        span_description_normalization_rules: &[], // only supported in relay
         performance_score: None,
-        geoip_lookup: None,   // only supported in relay
-        ai_model_costs: None, // only supported in relay
+        geoip_lookup: None,           // only supported in relay
+        ai_model_costs: None,         // only supported in relay
+        ai_model_cost_overrides: &[], // only supported in relay
         enable_trimming: config.enable_trimming.unwrap_or_default(),
+        trimming: None, // only supported in relay
         measurements: None,
         normalize_spans: config.normalize_spans,
         replay_id: config.replay_id,
@@ -363,6 +376,73 @@ pub unsafe extern "C" fn relay_pii_selector_suggestions_from_event(
     RelayStr::from_string(serde_json::to_string(&rv)?)
 }
 
+/// Runs a PII config over an item and reports which rules matched, without scrubbing the item.
+///
+/// Supported item types are `event`, `span`, `log`, `replay_event`, `replay_recording`,
+/// `attachment`, and `check_in`. The `filename` is only used for attachments. Check-ins are not
+/// scrubbed by Relay, so their report lists the rules that would apply to any field.
+///
+/// Returns a JSON report with a list of `matches`, each containing the `rule_id`, the `path` of the
+/// matched value, the `redaction`, and the `count` of matches. The `scrubbed` flag is `false` for
+/// item types that Relay does not scrub.
+#[unsafe(no_mangle)]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_pii_audit(
+    config: *const RelayStr,
+    item_type: *const RelayStr,
+    filename: *const RelayStr,
+    item: *const RelayBuf,
+) -> RelayStr {
+    let config = serde_json::from_str::<PiiConfig>(unsafe { (*config).as_str() })?;
+    let item = unsafe { (*item).as_bytes() };
+
+    let report = audit_item(
+        &config,
+        unsafe { (*item_type).as_str() },
+        unsafe { (*filename).as_str() },
+        item,
+    )?;
+
+    RelayStr::from_string(serde_json::to_string(&report)?)
+}
+
+/// Walk through an item and collect selectors that can be applied to it in a PII config.
+///
+/// Like [`relay_pii_selector_suggestions_from_event`], but supports the item types `event`,
+/// `span`, `log`, and `replay_event`.
+#[unsafe(no_mangle)]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_pii_selector_suggestions(
+    item_type: *const RelayStr,
+    item: *const RelayStr,
+) -> RelayStr {
+    let item = unsafe { (*item).as_str() };
+
+    let rv = match unsafe { (*item_type).as_str() } {
+        "event" => selector_suggestions_from_value(&mut Annotated::<Event>::from_json(item)?),
+        "span" => selector_suggestions_from_value(&mut Annotated::<Span>::from_json(item)?),
+        "log" => selector_suggestions_from_value(&mut Annotated::<OurLog>::from_json(item)?),
+        "replay_event" => {
+            selector_suggestions_from_value(&mut Annotated::<Replay>::from_json(item)?)
+        }
+        other => return Err(UnknownItemType(other.to_owned()).into()),
+    };
+
+    RelayStr::from_string(serde_json::to_string(&rv)?)
+}
+
+/// Error returned for item types that are not supported by a function.
+#[derive(Debug)]
+struct UnknownItemType(String);
+
+impl fmt::Display for UnknownItemType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported item type: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownItemType {}
+
 /// A test function that always panics.
 #[unsafe(no_mangle)]
 #[relay_ffi::catch_unwind]
@@ -430,6 +510,153 @@ pub unsafe extern "C" fn relay_validate_sampling_configuration(value: *const Rel
     }
 }
 
+/// Parses an optional JSON argument, where an empty string denotes a missing value.
+fn parse_optional<T: DeserializeOwned>(value: &str) -> serde_json::Result<Option<T>> {
+    match value {
+        "" => Ok(None),
+        json => serde_json::from_str(json).map(Some),
+    }
+}
+
+/// The result of [`relay_run_dynamic_sampling`] if a sampling rule matched.
+#[derive(Debug, Serialize)]
+struct SamplingEvaluation {
+    /// The effective sample rate after applying all matched rules.
+    sample_rate: f64,
+    /// Ids of all rules that contributed to the sample rate.
+    matched_rule_ids: Vec<RuleId>,
+    /// Whether the item is kept or dropped, either `"keep"` or `"drop"`.
+    decision: &'static str,
+}
+
+impl From<SamplingMatch> for SamplingEvaluation {
+    fn from(sampling_match: SamplingMatch) -> Self {
+        Self {
+            sample_rate: sampling_match.sample_rate(),
+            decision: sampling_match.decision().as_str(),
+            matched_rule_ids: sampling_match.into_matched_rules().0,
+        }
+    }
+}
+
+/// Evaluates dynamic sampling rules in the same order as Relay does.
+///
+/// Transaction rules of `sampling_config` are matched against the event first, followed by trace
+/// rules of `root_sampling_config` matched against the DSC.
+fn evaluate_sampling(
+    sampling_config: Option<&SamplingConfig>,
+    root_sampling_config: Option<&SamplingConfig>,
+    dsc: Option<&DynamicSamplingContext>,
+    event: Option<&Event>,
+) -> Option<SamplingMatch> {
+    if sampling_config.is_some_and(|config| config.unsupported())
+        || root_sampling_config.is_some_and(|config| config.unsupported())
+    {
+        return None;
+    }
+
+    // Without a reservoir, matching rules never suspends and completes on the first poll.
+    let run = async {
+        let mut evaluator = SamplingEvaluator::new(Utc::now());
+
+        let seed = event.and_then(|event| event.id.value()).map(|id| id.0);
+        if let (Some(event), Some(seed), Some(config)) = (event, seed, sampling_config) {
+            let rules = config.filter_rules(RuleType::Transaction);
+            evaluator = match evaluator.match_rules(seed, event, rules).await {
+                ControlFlow::Continue(evaluator) => evaluator,
+                ControlFlow::Break(sampling_match) => return Some(sampling_match),
+            };
+        }
+
+        if let (Some(dsc), Some(config)) = (dsc, root_sampling_config) {
+            let rules = config.filter_rules(RuleType::Trace);
+            if let ControlFlow::Break(sampling_match) =
+                evaluator.match_rules(*dsc.trace_id, dsc, rules).await
+            {
+                return Some(sampling_match);
+            }
+        }
+
+        None
+    };
+
+    match pin!(run).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(sampling_match) => sampling_match,
+        Poll::Pending => unreachable!("sampling without reservoir must not suspend"),
+    }
+}
+
+/// Runs dynamic sampling on an event and its dynamic sampling context.
+///
+/// All arguments are JSON strings and may be empty if not available: the sampling config of the
+/// event's project, the sampling config of the trace root project, the DSC, and the event. Returns
+/// `null` if no rule matched, or an object with the effective `sample_rate`, the
+/// `matched_rule_ids`, and the `decision`. Configs with unsupported rules never match.
+#[unsafe(no_mangle)]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_run_dynamic_sampling(
+    sampling_config: *const RelayStr,
+    root_sampling_config: *const RelayStr,
+    dsc: *const RelayStr,
+    event: *const RelayStr,
+) -> RelayStr {
+    let mut sampling_config =
+        parse_optional::<SamplingConfig>(unsafe { (*sampling_config).as_str() })?;
+    let mut root_sampling_config =
+        parse_optional::<SamplingConfig>(unsafe { (*root_sampling_config).as_str() })?;
+    let dsc = parse_optional::<DynamicSamplingContext>(unsafe { (*dsc).as_str() })?;
+    let event = match unsafe { (*event).as_str() } {
+        "" => Annotated::empty(),
+        json => Annotated::<Event>::from_json(json)?,
+    };
+
+    for config in sampling_config.iter_mut().chain(&mut root_sampling_config) {
+        config.normalize();
+    }
+
+    let evaluation = evaluate_sampling(
+        sampling_config.as_ref(),
+        root_sampling_config.as_ref(),
+        dsc.as_ref(),
+        event.value(),
+    )
+    .map(SamplingEvaluation::from);
+
+    RelayStr::from_string(serde_json::to_string(&evaluation)?)
+}
+
+/// Runs inbound filters on an event.
+///
+/// Takes the project's filter config, the optional generic filters from the global config, the
+/// optional client IP address of the request, and the event. Returns `null` if the event passes
+/// all filters, or the identifier of the first filter that matched, such as `"release-version"`.
+#[unsafe(no_mangle)]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_run_inbound_filters(
+    config: *const RelayStr,
+    global_config: *const RelayStr,
+    client_ip: *const RelayStr,
+    event: *const RelayStr,
+) -> RelayStr {
+    let config = serde_json::from_str::<ProjectFiltersConfig>(unsafe { (*config).as_str() })?;
+    let global_config =
+        parse_optional::<GenericFiltersConfig>(unsafe { (*global_config).as_str() })?;
+    let client_ip = match unsafe { (*client_ip).as_str() } {
+        "" => None,
+        ip => Some(ip.parse::<NetIpAddr>()?),
+    };
+    let event = Annotated::<Event>::from_json(unsafe { (*event).as_str() })?;
+
+    let filter = match event.value() {
+        Some(event) => {
+            relay_filter::should_filter(event, client_ip, &config, global_config.as_ref()).err()
+        }
+        None => None,
+    };
+
+    RelayStr::from_string(serde_json::to_string(&filter.map(|key| key.name()))?)
+}
+
 /// Normalize a project config.
 #[unsafe(no_mangle)]
 #[relay_ffi::catch_unwind]
@@ -520,4 +747,113 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_run_dynamic_sampling() {
+        let sampling_config = r#"{
+            "rules": [{
+                "id": 1,
+                "type": "transaction",
+                "samplingValue": {"type": "factor", "value": 2.0},
+                "condition": {"op": "eq", "name": "event.transaction", "value": "/checkout"}
+            }]
+        }"#;
+        let root_sampling_config = r#"{
+            "rules": [{
+                "id": 2,
+                "type": "trace",
+                "samplingValue": {"type": "sampleRate", "value": 0.25},
+                "condition": {"op": "and", "inner": []}
+            }]
+        }"#;
+        let dsc = r#"{
+            "trace_id": "67e5504410b1426f9247bb680e5fe0c8",
+            "public_key": "abd0f232775f45feab79864e580d160b"
+        }"#;
+        let event = r#"{
+            "event_id": "52df9022835246eeb317dbd739ccd059",
+            "type": "transaction",
+            "transaction": "/checkout"
+        }"#;
+
+        let result = unsafe {
+            relay_run_dynamic_sampling(
+                &RelayStr::from(sampling_config),
+                &RelayStr::from(root_sampling_config),
+                &RelayStr::from(dsc),
+                &RelayStr::from(event),
+            )
+        };
+        let result: serde_json::Value = serde_json::from_str(unsafe { result.as_str() }).unwrap();
+        assert_eq!(result["sample_rate"], 0.5);
+        assert_eq!(result["matched_rule_ids"], serde_json::json!([1, 2]));
+
+        // Without a root sampling config, the factor rule alone does not produce a match.
+        let result = unsafe {
+            relay_run_dynamic_sampling(
+                &RelayStr::from(sampling_config),
+                &RelayStr::from(""),
+                &RelayStr::from(dsc),
+                &RelayStr::from(event),
+            )
+        };
+        assert_eq!(unsafe { result.as_str() }, "null");
+    }
+
+    #[test]
+    fn test_run_inbound_filters() {
+        let config = r#"{"releases": {"releases": ["1.*"]}}"#;
+
+        let result = unsafe {
+            relay_run_inbound_filters(
+                &RelayStr::from(config),
+                &RelayStr::from(""),
+                &RelayStr::from(""),
+                &RelayStr::from(r#"{"release": "1.2.3"}"#),
+            )
+        };
+        assert_eq!(unsafe { result.as_str() }, r#""release-version""#);
+
+        let result = unsafe {
+            relay_run_inbound_filters(
+                &RelayStr::from(config),
+                &RelayStr::from(""),
+                &RelayStr::from(""),
+                &RelayStr::from(r#"{"release": "2.0.0"}"#),
+            )
+        };
+        assert_eq!(unsafe { result.as_str() }, "null");
+    }
+
+    #[test]
+    fn test_pii_audit_span() {
+        let config = r#"{"applications": {"$string": ["@email:mask"]}}"#;
+        let span =
+            r#"{"description": "mail to foo@example.com", "data": {"to": "foo@example.com"}}"#;
+        let item = RelayBuf {
+            data: span.as_ptr() as *mut u8,
+            len: span.len(),
+            owned: false,
+        };
+
+        let result = unsafe {
+            relay_pii_audit(
+                &RelayStr::from(config),
+                &RelayStr::from("span"),
+                &RelayStr::from(""),
+                &item,
+            )
+        };
+        let report: serde_json::Value = serde_json::from_str(unsafe { result.as_str() }).unwrap();
+        // The span description is not scrubbed by `$string` selectors.
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "matches": [
+                    {"rule_id": "@email:mask", "path": "data.to", "redaction": "mask", "count": 1},
+                ],
+                "scrubbed": true
+            })
+        );
+    }
 }
@@ -36,6 +36,7 @@ async fn build_limiter(client: AsyncRedisClient, reset_redis: bool) -> RedisSetL
     RedisSetLimiter::new(
         RedisSetLimiterOptions {
             cache_vacuum_interval: Duration::from_secs(180),
+            storage: Default::default(),
         },
         client,
     )
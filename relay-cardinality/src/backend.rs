@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+
+use crate::limiter::{Entry, Limiter, Reporter, Scoping};
+use crate::{CardinalityLimit, MemoryLimiter, RedisSetLimiter, Result};
+
+/// A [`Limiter`] which dispatches to one of the available backends.
+pub enum CardinalityBackend {
+    /// Tracks cardinality in Redis, see [`RedisSetLimiter`].
+    Redis(RedisSetLimiter),
+    /// Tracks cardinality in memory, see [`MemoryLimiter`].
+    Memory(MemoryLimiter),
+}
+
+#[async_trait]
+impl Limiter for CardinalityBackend {
+    async fn check_cardinality_limits<'a, 'b, E, R>(
+        &self,
+        scoping: Scoping,
+        limits: &'a [CardinalityLimit],
+        entries: E,
+        reporter: &mut R,
+    ) -> Result<()>
+    where
+        E: IntoIterator<Item = Entry<'b>> + Send,
+        R: Reporter<'a> + Send,
+    {
+        match self {
+            Self::Redis(limiter) => {
+                limiter
+                    .check_cardinality_limits(scoping, limits, entries, reporter)
+                    .await
+            }
+            Self::Memory(limiter) => {
+                limiter
+                    .check_cardinality_limits(scoping, limits, entries, reporter)
+                    .await
+            }
+        }
+    }
+}
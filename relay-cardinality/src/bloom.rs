@@ -0,0 +1,163 @@
+//! A Bloom filter to check whether a hash was seen before.
+
+use hashbrown::HashSet;
+
+use crate::hll;
+
+/// Number of bits per hash in the filter.
+///
+/// Together with [`NUM_HASHES`], this results in a false positive rate of about 1% once the filter
+/// holds as many hashes as its capacity.
+const BITS_PER_HASH: f64 = 9.6;
+/// Number of bits set for every hash.
+const NUM_HASHES: u64 = 7;
+/// Approximate number of bytes a hash takes in the sparse representation.
+const SPARSE_BYTES_PER_HASH: usize = 8;
+
+/// Hashes of a [`BloomFilter`].
+#[derive(Clone, Debug)]
+enum Bits {
+    /// Exact set of hashes, used while it takes less memory than the bit array.
+    Sparse(HashSet<u32>),
+    /// Bit array of the filter.
+    Dense(Box<[u64]>),
+}
+
+/// A Bloom filter checking membership of hashes.
+///
+/// The filter is sized for a fixed capacity and has a false positive rate of about 1% once it holds
+/// that many hashes. Hashes that were inserted are always reported as contained. Small filters
+/// store their hashes exactly and switch to the bit array once it takes less memory, which takes
+/// about 1.2 bytes per hash of capacity.
+#[derive(Clone, Debug)]
+pub struct BloomFilter {
+    num_bits: u64,
+    bits: Bits,
+}
+
+impl BloomFilter {
+    /// Creates an empty filter for the given number of hashes.
+    pub fn with_capacity(capacity: u32) -> Self {
+        let num_bits = (f64::from(capacity) * BITS_PER_HASH).ceil() as u64;
+        Self {
+            // Round up to full words, so no bits of the array are wasted.
+            num_bits: num_bits.max(1).div_ceil(64) * 64,
+            bits: Bits::Sparse(HashSet::new()),
+        }
+    }
+
+    /// Inserts a hash into the filter.
+    ///
+    /// Returns `true` if the hash was not contained in the filter before.
+    pub fn insert(&mut self, hash: u32) -> bool {
+        match &mut self.bits {
+            Bits::Sparse(hashes) => {
+                if !hashes.insert(hash) {
+                    return false;
+                }
+
+                if hashes.len() * SPARSE_BYTES_PER_HASH > self.num_bits as usize / 8 {
+                    self.densify();
+                }
+
+                true
+            }
+            Bits::Dense(words) => {
+                let mut modified = false;
+                for bit in bit_indexes(hash, self.num_bits) {
+                    let (word, mask) = word_and_mask(bit);
+                    modified |= words[word] & mask == 0;
+                    words[word] |= mask;
+                }
+                modified
+            }
+        }
+    }
+
+    /// Returns `true` if the hash was inserted into the filter.
+    ///
+    /// Other hashes are falsely reported as contained with a probability of about 1% once the
+    /// filter holds as many hashes as its capacity.
+    pub fn contains(&self, hash: u32) -> bool {
+        match &self.bits {
+            Bits::Sparse(hashes) => hashes.contains(&hash),
+            Bits::Dense(words) => bit_indexes(hash, self.num_bits).all(|bit| {
+                let (word, mask) = word_and_mask(bit);
+                words[word] & mask != 0
+            }),
+        }
+    }
+
+    fn densify(&mut self) {
+        if let Bits::Sparse(hashes) = &self.bits {
+            let mut words = vec![0; (self.num_bits / 64) as usize].into_boxed_slice();
+            for &hash in hashes {
+                for bit in bit_indexes(hash, self.num_bits) {
+                    let (word, mask) = word_and_mask(bit);
+                    words[word] |= mask;
+                }
+            }
+            self.bits = Bits::Dense(words);
+        }
+    }
+}
+
+/// Returns the indexes of all bits set for a hash using double hashing.
+fn bit_indexes(hash: u32, num_bits: u64) -> impl Iterator<Item = u64> {
+    let hash = hll::mix(hash);
+    let h1 = hash & u64::from(u32::MAX);
+    // An odd step never cycles back to the first bit early.
+    let h2 = (hash >> 32) | 1;
+    (0..NUM_HASHES).map(move |i| (h1 + i * h2) % num_bits)
+}
+
+fn word_and_mask(bit: u64) -> (usize, u64) {
+    ((bit / 64) as usize, 1 << (bit % 64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_contains() {
+        let mut filter = BloomFilter::with_capacity(100);
+        assert!(!filter.contains(42));
+        assert!(filter.insert(42));
+        assert!(!filter.insert(42));
+        assert!(filter.contains(42));
+    }
+
+    #[test]
+    fn test_dense_matches_sparse() {
+        let mut sparse = BloomFilter::with_capacity(10_000);
+        for hash in 0..100 {
+            sparse.insert(hash);
+        }
+        assert!(matches!(sparse.bits, Bits::Sparse(_)));
+
+        let mut dense = sparse.clone();
+        dense.densify();
+        assert!(matches!(dense.bits, Bits::Dense(_)));
+        assert!((0..100).all(|hash| dense.contains(hash)));
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut filter = BloomFilter::with_capacity(100_000);
+        for hash in 0..100_000 {
+            filter.insert(hash);
+        }
+
+        assert!(matches!(filter.bits, Bits::Dense(_)));
+        assert!((0..100_000).all(|hash| filter.contains(hash)));
+
+        let false_positives = (100_000..1_100_000)
+            .filter(|&hash| filter.contains(hash))
+            .count();
+        assert!(
+            false_positives < 15_000,
+            "{false_positives} false positives"
+        );
+    }
+}
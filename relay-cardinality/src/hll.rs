@@ -0,0 +1,228 @@
+//! A HyperLogLog sketch to approximate the cardinality of hashes.
+
+/// Number of bits of the hash used to select a register.
+const PRECISION: u32 = 12;
+/// Number of registers in the sketch.
+const NUM_REGISTERS: usize = 1 << PRECISION;
+/// Maximum number of sparse registers before the sketch switches to the dense representation.
+///
+/// A sparse register takes 3 bytes compared to 1 byte of a dense register.
+const MAX_SPARSE: usize = NUM_REGISTERS / 4;
+
+/// Registers of a [`HyperLogLog`] sketch.
+#[derive(Clone, Debug)]
+enum Registers {
+    /// Sorted list of non-zero registers, used while the cardinality is small.
+    Sparse(Vec<(u16, u8)>),
+    /// All registers.
+    Dense(Box<[u8; NUM_REGISTERS]>),
+}
+
+/// A HyperLogLog sketch approximating the number of distinct hashes inserted into it.
+///
+/// The sketch uses 4096 registers with a standard error of about 1.6%. Small sketches are stored
+/// sparsely and take only a few bytes per inserted hash, the memory of a sketch is bounded by
+/// 4 KiB.
+///
+/// The sketch cannot tell whether a specific hash was inserted, use a
+/// [`BloomFilter`](crate::bloom::BloomFilter) to check membership.
+#[derive(Clone, Debug)]
+pub struct HyperLogLog {
+    registers: Registers,
+}
+
+impl HyperLogLog {
+    /// Creates an empty sketch.
+    pub fn new() -> Self {
+        Self {
+            registers: Registers::Sparse(Vec::new()),
+        }
+    }
+
+    /// Inserts a hash into the sketch.
+    ///
+    /// Returns `true` if the sketch was modified.
+    pub fn insert(&mut self, hash: u32) -> bool {
+        let (index, rank) = index_and_rank(hash);
+
+        match &mut self.registers {
+            Registers::Sparse(registers) => {
+                match registers.binary_search_by_key(&index, |&(i, _)| i) {
+                    Ok(pos) if registers[pos].1 >= rank => return false,
+                    Ok(pos) => registers[pos].1 = rank,
+                    Err(pos) => registers.insert(pos, (index, rank)),
+                }
+
+                if registers.len() > MAX_SPARSE {
+                    self.densify();
+                }
+
+                true
+            }
+            Registers::Dense(registers) => {
+                let register = &mut registers[usize::from(index)];
+                if *register >= rank {
+                    return false;
+                }
+                *register = rank;
+                true
+            }
+        }
+    }
+
+    /// Returns the estimated number of distinct hashes inserted into the sketch.
+    pub fn count(&self) -> u32 {
+        let mut sum = 0.0;
+        let mut zeros = NUM_REGISTERS;
+
+        match &self.registers {
+            Registers::Sparse(registers) => {
+                for &(_, rank) in registers {
+                    sum += 2f64.powi(-i32::from(rank));
+                }
+                zeros -= registers.len();
+                sum += zeros as f64;
+            }
+            Registers::Dense(registers) => {
+                for &rank in registers.iter() {
+                    sum += 2f64.powi(-i32::from(rank));
+                    if rank == 0 {
+                        continue;
+                    }
+                    zeros -= 1;
+                }
+            }
+        }
+
+        let m = NUM_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let estimate = alpha * m * m / sum;
+
+        // Linear counting is more accurate for small cardinalities.
+        let estimate = if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        };
+
+        estimate.round() as u32
+    }
+
+    fn register(&self, index: u16) -> u8 {
+        match &self.registers {
+            Registers::Sparse(registers) => registers
+                .binary_search_by_key(&index, |&(i, _)| i)
+                .map_or(0, |pos| registers[pos].1),
+            Registers::Dense(registers) => registers[usize::from(index)],
+        }
+    }
+
+    fn densify(&mut self) {
+        if let Registers::Sparse(sparse) = &self.registers {
+            let mut dense = Box::new([0; NUM_REGISTERS]);
+            for &(index, rank) in sparse {
+                dense[usize::from(index)] = rank;
+            }
+            self.registers = Registers::Dense(dense);
+        }
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for HyperLogLog {
+    fn eq(&self, other: &Self) -> bool {
+        (0..NUM_REGISTERS as u16).all(|index| self.register(index) == other.register(index))
+    }
+}
+
+/// Returns the register index and the rank of a hash.
+///
+/// The rank is the position of the first set bit in the remaining bits of the hash. Since the
+/// cardinality limiter hashes are only 32 bits wide, they are mixed into 64 bits first to leave
+/// enough bits for the rank.
+fn index_and_rank(hash: u32) -> (u16, u8) {
+    let hash = mix(hash);
+    let index = (hash >> (64 - PRECISION)) as u16;
+    // Set a guard bit, so the rank is bounded even if all remaining bits are zero.
+    let remaining = (hash << PRECISION) | (1 << (PRECISION - 1));
+    let rank = remaining.leading_zeros() as u8 + 1;
+    (index, rank)
+}
+
+/// Finalizer of SplitMix64, distributes the bits of the hash over 64 bits.
+pub(crate) fn mix(hash: u32) -> u64 {
+    let mut z = u64::from(hash).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_estimate(hll: &HyperLogLog, expected: u32, tolerance: f64) {
+        let count = f64::from(hll.count());
+        let expected = f64::from(expected);
+        let error = (count - expected).abs() / expected;
+        assert!(
+            error <= tolerance,
+            "estimate {count} off by {error} from {expected}"
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let hll = HyperLogLog::new();
+        assert_eq!(hll.count(), 0);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut hll = HyperLogLog::new();
+        assert!(hll.insert(42));
+        assert!(!hll.insert(42));
+        assert_eq!(hll.count(), 1);
+    }
+
+    #[test]
+    fn test_small_cardinality() {
+        let mut hll = HyperLogLog::new();
+        for hash in 0..100 {
+            hll.insert(hash);
+        }
+        assert_estimate(&hll, 100, 0.05);
+        assert!(matches!(hll.registers, Registers::Sparse(_)));
+    }
+
+    #[test]
+    fn test_large_cardinality() {
+        let mut hll = HyperLogLog::new();
+        for hash in 0..100_000 {
+            hll.insert(hash);
+        }
+
+        assert!(matches!(hll.registers, Registers::Dense(_)));
+        assert_estimate(&hll, 100_000, 0.05);
+    }
+
+    #[test]
+    fn test_dense_matches_sparse() {
+        let mut sparse = HyperLogLog::new();
+        for hash in 0..500 {
+            sparse.insert(hash);
+        }
+
+        let mut dense = sparse.clone();
+        dense.densify();
+
+        assert_eq!(sparse, dense);
+        assert_eq!(sparse.count(), dense.count());
+        assert_estimate(&sparse, 500, 0.02);
+    }
+}
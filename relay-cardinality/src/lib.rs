@@ -6,23 +6,32 @@
     html_favicon_url = "https://raw.githubusercontent.com/getsentry/relay/master/artwork/relay-icon.png"
 )]
 
+#[cfg(feature = "redis")]
+mod backend;
+mod bloom;
 mod config;
 mod error;
+mod hll;
 pub mod limiter;
+mod memory;
+mod quota;
 #[cfg(feature = "redis")]
 mod redis;
 mod statsd;
 mod window;
 
+#[cfg(feature = "redis")]
+pub use self::backend::CardinalityBackend;
 pub use self::config::*;
 pub use self::error::*;
 pub use self::limiter::{
     CardinalityItem, CardinalityLimits, CardinalityLimitsSplit, CardinalityReport, Scoping,
 };
+pub use self::memory::{MemoryLimiter, MemoryLimiterOptions};
 #[cfg(feature = "redis")]
-pub use self::redis::{RedisSetLimiter, RedisSetLimiterOptions};
+pub use self::redis::{RedisSetLimiter, RedisSetLimiterOptions, RedisStorage};
 pub use self::window::SlidingWindow;
 
-/// Redis Set based cardinality limiter.
+/// Cardinality limiter backed by Redis or in-memory sketches.
 #[cfg(feature = "redis")]
-pub type CardinalityLimiter = self::limiter::CardinalityLimiter<RedisSetLimiter>;
+pub type CardinalityLimiter = self::limiter::CardinalityLimiter<CardinalityBackend>;
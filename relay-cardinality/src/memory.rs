@@ -0,0 +1,401 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hashbrown::HashMap;
+use parking_lot::Mutex;
+use relay_common::time::UnixTimestamp;
+
+use crate::bloom::BloomFilter;
+use crate::hll::HyperLogLog;
+use crate::limiter::{CardinalityReport, Entry, Limiter, Reporter, Scoping};
+use crate::quota::{PartialQuotaScoping, QuotaScoping};
+use crate::window::Slot;
+use crate::{CardinalityLimit, Result};
+
+/// Configuration options for the [`MemoryLimiter`].
+pub struct MemoryLimiterOptions {
+    /// Interval in which sketches of expired slots are removed.
+    pub vacuum_interval: Duration,
+}
+
+/// Sketches of a single slot of a sliding window.
+#[derive(Debug)]
+struct SlotSketch {
+    /// Hashes accepted in the slot.
+    members: BloomFilter,
+    /// Approximate number of hashes accepted in the slot.
+    cardinality: HyperLogLog,
+}
+
+impl SlotSketch {
+    fn new(limit: &CardinalityLimit) -> Self {
+        Self {
+            members: BloomFilter::with_capacity(limit.limit),
+            cardinality: HyperLogLog::new(),
+        }
+    }
+
+    /// Inserts a hash into the slot.
+    ///
+    /// Returns `true` if the hash was not contained in the slot before.
+    fn insert(&mut self, hash: u32) -> bool {
+        self.cardinality.insert(hash);
+        self.members.insert(hash)
+    }
+}
+
+/// Sketches of all slots of a sliding window for a single scope.
+type WindowSketches = BTreeMap<Slot, SlotSketch>;
+
+struct Inner {
+    sketches: HashMap<QuotaScoping, WindowSketches>,
+    vacuum_interval: Duration,
+    last_vacuum: UnixTimestamp,
+}
+
+impl Inner {
+    /// Removes sketches of slots which are no longer part of their sliding window.
+    fn vacuum(&mut self, timestamp: UnixTimestamp) {
+        if timestamp.as_secs() < self.last_vacuum.as_secs() + self.vacuum_interval.as_secs() {
+            return;
+        }
+        self.last_vacuum = timestamp;
+
+        self.sketches.retain(|scope, slots| {
+            let active_slot = scope.active_slot(timestamp);
+            slots.retain(|slot, _| *slot >= active_slot);
+            !slots.is_empty()
+        });
+    }
+}
+
+/// A cardinality limiter that tracks cardinality in memory using HyperLogLog sketches.
+///
+/// The limiter implements the same sliding window semantics as the
+/// [`RedisSetLimiter`](crate::RedisSetLimiter) for Relays without access to Redis. Cardinality is
+/// tracked separately in every Relay instance.
+///
+/// Every slot counts its hashes with a HyperLogLog sketch and checks whether a hash was accepted
+/// before with a Bloom filter sized for the limit. The enforced limit is therefore not exact: the
+/// cardinality has a standard error of about 1.6%, and after the limit has been reached about 1% of
+/// new hashes are falsely accepted as already seen.
+pub struct MemoryLimiter {
+    inner: Mutex<Inner>,
+}
+
+impl MemoryLimiter {
+    /// Creates a new [`MemoryLimiter`].
+    pub fn new(options: MemoryLimiterOptions) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                sketches: HashMap::new(),
+                vacuum_interval: options.vacuum_interval,
+                last_vacuum: UnixTimestamp::from_secs(0),
+            }),
+        }
+    }
+
+    fn check_cardinality_limits_at<'a, 'b, E, R>(
+        &self,
+        scoping: Scoping,
+        limits: &'a [CardinalityLimit],
+        entries: E,
+        reporter: &mut R,
+        timestamp: UnixTimestamp,
+    ) where
+        E: IntoIterator<Item = Entry<'b>>,
+        R: Reporter<'a>,
+    {
+        let limits: Vec<_> = limits
+            .iter()
+            .filter_map(|limit| Some((limit, PartialQuotaScoping::new(scoping, limit)?)))
+            .collect();
+
+        let mut scopes = Vec::new();
+        scopes.resize_with(limits.len(), BTreeMap::<QuotaScoping, Vec<Entry<'b>>>::new);
+
+        for entry in entries {
+            for ((_, partial_scope), scopes) in limits.iter().zip(&mut scopes) {
+                if partial_scope.matches(&entry) {
+                    let scope = partial_scope.complete(entry);
+                    scopes.entry(scope).or_default().push(entry);
+                }
+            }
+        }
+
+        let mut inner = self.inner.lock();
+        inner.vacuum(timestamp);
+
+        for ((limit, _), scopes) in limits.into_iter().zip(scopes) {
+            for (scope, entries) in scopes {
+                let cardinality = check_scope(
+                    &mut inner.sketches,
+                    limit,
+                    &scope,
+                    entries,
+                    reporter,
+                    timestamp,
+                );
+
+                reporter.report_cardinality(
+                    limit,
+                    CardinalityReport {
+                        timestamp,
+                        organization_id: scope.organization_id,
+                        project_id: scope.project_id,
+                        metric_type: scope.metric_type,
+                        metric_name: scope.metric_name.clone(),
+                        cardinality,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Checks all entries of a single scope against the limit.
+///
+/// Returns the cardinality of the active slot after all entries have been checked.
+fn check_scope<'a, R>(
+    sketches: &mut HashMap<QuotaScoping, WindowSketches>,
+    limit: &'a CardinalityLimit,
+    scope: &QuotaScoping,
+    entries: Vec<Entry<'_>>,
+    reporter: &mut R,
+    timestamp: UnixTimestamp,
+) -> u32
+where
+    R: Reporter<'a>,
+{
+    let mut slots = scope.slots(timestamp);
+    let active_slot = slots.next().unwrap_or_default();
+
+    let window = sketches.entry_ref(scope).or_default();
+    // Slots before the active slot have left the sliding window.
+    *window = window.split_off(&active_slot);
+
+    let working_set = window
+        .entry(active_slot)
+        .or_insert_with(|| SlotSketch::new(limit));
+    let mut cardinality = working_set.cardinality.count();
+    let mut accepted = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        if working_set.members.contains(entry.hash) {
+            accepted.push(entry.hash);
+        } else if cardinality < limit.limit {
+            working_set.insert(entry.hash);
+            cardinality += 1;
+            accepted.push(entry.hash);
+        } else {
+            reporter.reject(limit, entry.id);
+        }
+    }
+
+    let cardinality = working_set.cardinality.count();
+
+    // Make sure accepted hashes are inserted into future slots of the window.
+    for slot in slots {
+        let sketch = window.entry(slot).or_insert_with(|| SlotSketch::new(limit));
+        for &hash in &accepted {
+            sketch.insert(hash);
+        }
+    }
+
+    cardinality
+}
+
+#[async_trait]
+impl Limiter for MemoryLimiter {
+    async fn check_cardinality_limits<'a, 'b, E, R>(
+        &self,
+        scoping: Scoping,
+        limits: &'a [CardinalityLimit],
+        entries: E,
+        reporter: &mut R,
+    ) -> Result<()>
+    where
+        E: IntoIterator<Item = Entry<'b>> + Send,
+        R: Reporter<'a> + Send,
+    {
+        self.check_cardinality_limits_at(scoping, limits, entries, reporter, UnixTimestamp::now());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use relay_base_schema::metrics::{MetricName, MetricNamespace};
+    use relay_base_schema::organization::OrganizationId;
+    use relay_base_schema::project::ProjectId;
+
+    use super::*;
+    use crate::limiter::EntryId;
+    use crate::{CardinalityScope, SlidingWindow};
+
+    #[derive(Debug, Default)]
+    struct TestReporter {
+        rejected: HashSet<EntryId>,
+        reports: Vec<CardinalityReport>,
+    }
+
+    impl<'a> Reporter<'a> for TestReporter {
+        fn reject(&mut self, _limit: &'a CardinalityLimit, entry_id: EntryId) {
+            self.rejected.insert(entry_id);
+        }
+
+        fn report_cardinality(&mut self, _limit: &'a CardinalityLimit, report: CardinalityReport) {
+            self.reports.push(report);
+        }
+    }
+
+    fn build_limiter() -> MemoryLimiter {
+        MemoryLimiter::new(MemoryLimiterOptions {
+            vacuum_interval: Duration::from_secs(60),
+        })
+    }
+
+    fn limit(scope: CardinalityScope, limit: u32) -> CardinalityLimit {
+        CardinalityLimit {
+            id: "limit".to_owned(),
+            passive: false,
+            report: false,
+            window: SlidingWindow {
+                window_seconds: 3600,
+                granularity_seconds: 360,
+            },
+            limit,
+            scope,
+            namespace: Some(MetricNamespace::Custom),
+        }
+    }
+
+    fn scoping() -> Scoping {
+        Scoping {
+            organization_id: OrganizationId::new(1),
+            project_id: ProjectId::new(1),
+        }
+    }
+
+    fn check(
+        limiter: &MemoryLimiter,
+        limits: &[CardinalityLimit],
+        entries: &[Entry<'_>],
+        timestamp: UnixTimestamp,
+    ) -> TestReporter {
+        let mut reporter = TestReporter::default();
+        limiter.check_cardinality_limits_at(
+            scoping(),
+            limits,
+            entries.iter().copied(),
+            &mut reporter,
+            timestamp,
+        );
+        reporter
+    }
+
+    fn entries(name: &MetricName, hashes: std::ops::Range<u32>) -> Vec<Entry<'_>> {
+        hashes
+            .map(|hash| Entry::new(EntryId(hash as usize), MetricNamespace::Custom, name, hash))
+            .collect()
+    }
+
+    /// Fills up a limit and returns the fraction of new hashes accepted afterwards.
+    fn over_admission(limit_value: u32) -> f64 {
+        let limiter = build_limiter();
+        let limits = &[limit(CardinalityScope::Organization, limit_value)];
+        let name = MetricName::from("foo");
+        let timestamp = UnixTimestamp::from_secs(3600);
+
+        let accepted = entries(&name, 0..limit_value);
+        let reporter = check(&limiter, limits, &accepted, timestamp);
+        assert!(reporter.rejected.is_empty());
+
+        // Accepted hashes are still accepted.
+        let reporter = check(&limiter, limits, &accepted, timestamp);
+        assert!(reporter.rejected.is_empty());
+
+        let num_new = 100_000;
+        let new = entries(&name, limit_value..limit_value + num_new);
+        let reporter = check(&limiter, limits, &new, timestamp);
+        f64::from(num_new - reporter.rejected.len() as u32) / f64::from(num_new)
+    }
+
+    #[test]
+    fn test_limit_10k() {
+        let rate = over_admission(10_000);
+        assert!(rate < 0.02, "accepted {rate} of new hashes");
+    }
+
+    #[test]
+    fn test_limit_100k() {
+        let rate = over_admission(100_000);
+        assert!(rate < 0.02, "accepted {rate} of new hashes");
+    }
+
+    #[test]
+    fn test_limit_other_namespace() {
+        let limiter = build_limiter();
+        let limits = &[limit(CardinalityScope::Organization, 10_000)];
+        let name = MetricName::from("foo");
+        let timestamp = UnixTimestamp::from_secs(3600);
+
+        // Entries of other namespaces are not limited.
+        let other = [Entry::new(EntryId(0), MetricNamespace::Spans, &name, 100)];
+        let reporter = check(&limiter, limits, &other, timestamp);
+        assert!(reporter.rejected.is_empty());
+        assert!(reporter.reports.is_empty());
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let limiter = build_limiter();
+        let limits = &[limit(CardinalityScope::Organization, 1)];
+        let name = MetricName::from("foo");
+        let timestamp = UnixTimestamp::from_secs(3600);
+
+        let first = [Entry::new(EntryId(0), MetricNamespace::Custom, &name, 1)];
+        let second = [Entry::new(EntryId(0), MetricNamespace::Custom, &name, 2)];
+
+        assert!(
+            check(&limiter, limits, &first, timestamp)
+                .rejected
+                .is_empty()
+        );
+
+        // Still within the window of the first hash.
+        let later = timestamp + Duration::from_secs(3000);
+        assert!(!check(&limiter, limits, &second, later).rejected.is_empty());
+        assert!(check(&limiter, limits, &first, later).rejected.is_empty());
+
+        // The first hash has left the window after it was last seen.
+        let much_later = later + Duration::from_secs(3600);
+        assert!(
+            check(&limiter, limits, &second, much_later)
+                .rejected
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_name_scope() {
+        let limiter = build_limiter();
+        let limits = &[limit(CardinalityScope::Name, 1)];
+        let foo = MetricName::from("foo");
+        let bar = MetricName::from("bar");
+        let timestamp = UnixTimestamp::from_secs(3600);
+
+        let entries = [
+            Entry::new(EntryId(0), MetricNamespace::Custom, &foo, 1),
+            Entry::new(EntryId(1), MetricNamespace::Custom, &bar, 2),
+            Entry::new(EntryId(2), MetricNamespace::Custom, &foo, 3),
+        ];
+
+        let reporter = check(&limiter, limits, &entries, timestamp);
+        assert_eq!(reporter.rejected, HashSet::from([EntryId(2)]));
+        assert_eq!(reporter.reports.len(), 2);
+    }
+}
@@ -1,6 +1,4 @@
-use hash32::Hasher;
-use std::fmt::{self, Write};
-use std::hash::Hash;
+use std::fmt;
 
 use relay_base_schema::metrics::{MetricName, MetricNamespace, MetricType};
 use relay_base_schema::organization::OrganizationId;
@@ -8,7 +6,8 @@ use relay_base_schema::project::ProjectId;
 use relay_common::time::UnixTimestamp;
 
 use crate::limiter::Entry;
-use crate::redis::{KEY_PREFIX, KEY_VERSION};
+#[cfg(feature = "redis")]
+use crate::redis::{KEY_VERSION, RedisStorage};
 use crate::window::Slot;
 use crate::{CardinalityLimit, CardinalityScope, Scoping, SlidingWindow};
 
@@ -112,6 +111,7 @@ pub struct QuotaScoping {
     pub metric_name: Option<MetricName>,
 }
 
+#[cfg(feature = "redis")]
 impl QuotaScoping {
     /// Returns the minimum TTL for a Redis key created by [`Self::to_redis_key`].
     pub fn redis_key_ttl(&self) -> u64 {
@@ -119,7 +119,12 @@ impl QuotaScoping {
     }
 
     /// Turns the scoping into a Redis key for the passed slot.
-    pub fn to_redis_key(&self, slot: Slot) -> String {
+    ///
+    /// Keys of different storages never overlap, since they hold different Redis data types.
+    pub fn to_redis_key(&self, storage: RedisStorage, slot: Slot) -> String {
+        use std::fmt::Write;
+
+        let key_prefix = storage.key_prefix();
         let organization_id = self.organization_id.unwrap_or(OrganizationId::new(0));
         let project_id = self.project_id.map(|p| p.value()).unwrap_or(0);
         let namespace = self.namespace.map(|ns| ns.as_str()).unwrap_or("");
@@ -132,7 +137,7 @@ impl QuotaScoping {
         let mut result = String::with_capacity(200);
         write!(
             &mut result,
-            "{KEY_PREFIX}:{KEY_VERSION}:scope-{{{organization_id}-{project_id}-{namespace}}}-{metric_type}{metric_name}{slot}"
+            "{key_prefix}:{KEY_VERSION}:scope-{{{organization_id}-{project_id}-{namespace}}}-{metric_type}{metric_name}{slot}"
         )
         .expect("formatting into a string never fails");
 
@@ -177,8 +182,10 @@ impl fmt::Debug for QuotaScoping {
     }
 }
 
+#[cfg(feature = "redis")]
 struct DisplayOptMinus<T>(Option<T>);
 
+#[cfg(feature = "redis")]
 impl<T: fmt::Display> fmt::Display for DisplayOptMinus<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(inner) = self.0.as_ref() {
@@ -189,7 +196,11 @@ impl<T: fmt::Display> fmt::Display for DisplayOptMinus<T> {
     }
 }
 
+#[cfg(feature = "redis")]
 fn fnv32(s: &str) -> u32 {
+    use hash32::Hasher;
+    use std::hash::Hash;
+
     let mut hasher = hash32::FnvHasher::default();
     s.hash(&mut hasher);
     hasher.finish32()
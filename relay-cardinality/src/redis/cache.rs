@@ -5,7 +5,7 @@ use parking_lot::{MappedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGu
 use relay_common::time::UnixTimestamp;
 use relay_statsd::metric;
 
-use crate::redis::quota::QuotaScoping;
+use crate::quota::QuotaScoping;
 use crate::statsd::{CardinalityLimiterCounters, CardinalityLimiterTimers};
 use crate::window::Slot;
 
@@ -200,7 +200,7 @@ mod tests {
     use relay_base_schema::project::ProjectId;
 
     use crate::limiter::{Entry, EntryId};
-    use crate::redis::quota::PartialQuotaScoping;
+    use crate::quota::PartialQuotaScoping;
     use crate::{CardinalityLimit, CardinalityScope, Scoping, SlidingWindow};
 
     use super::*;
@@ -6,9 +6,10 @@ use std::time::Duration;
 use crate::{
     CardinalityLimit, Result,
     limiter::{CardinalityReport, Entry, Limiter, Reporter, Scoping},
+    quota::QuotaScoping,
     redis::{
+        HLL_KEY_PREFIX, KEY_PREFIX,
         cache::{Cache, CacheOutcome},
-        quota::QuotaScoping,
         script::{CardinalityScript, CardinalityScriptResult, Status},
         state::{LimitState, RedisEntry},
     },
@@ -16,17 +17,61 @@ use crate::{
 };
 use relay_common::time::UnixTimestamp;
 
+/// The Redis data type used to track cardinality.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedisStorage {
+    /// Exact tracking with Redis sets containing every hash.
+    #[default]
+    Set,
+    /// Approximate tracking with Redis HyperLogLog sketches (`PFADD`/`PFCOUNT`).
+    ///
+    /// Each sketch uses at most 12 KiB of memory regardless of the cardinality, at the cost of a
+    /// standard error of about 0.81% in the counted cardinality. Whether a hash was seen before is
+    /// checked with a Bloom filter sized for the limit, which takes about 1.2 bytes per hash of the
+    /// limit and falsely accepts about 1% of new hashes after the limit has been reached.
+    HyperLogLog,
+}
+
+impl RedisStorage {
+    /// Returns the prefix for all Redis keys of this storage.
+    pub(crate) fn key_prefix(self) -> &'static str {
+        match self {
+            Self::Set => KEY_PREFIX,
+            Self::HyperLogLog => HLL_KEY_PREFIX,
+        }
+    }
+
+    /// Returns all keys the script accesses for the key of a slot.
+    ///
+    /// HyperLogLog sketches track their members in a set and a Bloom filter. The keys share the
+    /// hash tag of the slot key and are passed to the script, since scripts may only access keys
+    /// they declare.
+    pub(crate) fn script_keys(self, key: String) -> Vec<String> {
+        match self {
+            Self::Set => vec![key],
+            Self::HyperLogLog => {
+                let members = format!("{key}:members");
+                let bloom = format!("{key}:bloom");
+                vec![key, members, bloom]
+            }
+        }
+    }
+}
+
 /// Configuration options for the [`RedisSetLimiter`].
 pub struct RedisSetLimiterOptions {
     /// Cache vacuum interval for the in memory cache.
     ///
     /// The cache will scan for expired values based on this interval.
     pub cache_vacuum_interval: Duration,
+    /// The Redis data type used to track cardinality.
+    pub storage: RedisStorage,
 }
 
-/// Implementation uses Redis sets to keep track of cardinality.
+/// Implementation uses Redis sets or HyperLogLog sketches to keep track of cardinality.
 pub struct RedisSetLimiter {
     redis: AsyncRedisClient,
+    storage: RedisStorage,
     script: CardinalityScript,
     cache: Cache,
     #[cfg(test)]
@@ -41,7 +86,8 @@ impl RedisSetLimiter {
     pub fn new(options: RedisSetLimiterOptions, redis: AsyncRedisClient) -> Self {
         Self {
             redis,
-            script: CardinalityScript::load(),
+            storage: options.storage,
+            script: CardinalityScript::load(options.storage),
             cache: Cache::new(options.cache_vacuum_interval),
             #[cfg(test)]
             timestamp: UnixTimestamp::now(),
@@ -67,7 +113,10 @@ impl RedisSetLimiter {
 
         let mut pipeline = self.script.pipe();
         for (scope, entries) in &scopes {
-            let keys = scope.slots(timestamp).map(|slot| scope.to_redis_key(slot));
+            let keys = scope.slots(timestamp).flat_map(|slot| {
+                self.storage
+                    .script_keys(scope.to_redis_key(self.storage, slot))
+            });
 
             let hashes = entries.iter().map(|entry| entry.hash);
             num_hashes += hashes.len() as u64;
@@ -295,6 +344,7 @@ mod tests {
         RedisSetLimiter::new(
             RedisSetLimiterOptions {
                 cache_vacuum_interval: Duration::from_secs(5),
+                storage: RedisStorage::Set,
             },
             redis,
         )
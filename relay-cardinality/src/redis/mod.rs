@@ -1,15 +1,16 @@
 mod cache;
 mod limiter;
-mod quota;
 mod script;
 mod state;
 
-pub use self::limiter::{RedisSetLimiter, RedisSetLimiterOptions};
+pub use self::limiter::{RedisSetLimiter, RedisSetLimiterOptions, RedisStorage};
 
 /// Key prefix used for Redis keys.
 const KEY_PREFIX: &str = "relay:cardinality";
+/// Key prefix used for Redis keys of HyperLogLog sketches.
+const HLL_KEY_PREFIX: &str = "relay:cardinality:hll";
 /// Redis key version.
 ///
 /// The version is embedded in the key as a static segment, increment the version whenever there are
 /// breaking changes made to the keys or storage format in Redis.
-const KEY_VERSION: u32 = 1;
+pub(crate) const KEY_VERSION: u32 = 1;
@@ -4,6 +4,7 @@ use relay_redis::{
 };
 
 use crate::Result;
+use crate::redis::RedisStorage;
 
 /// Status wether an entry/bucket is accepted or rejected by the cardinality limiter.
 #[derive(Debug, Clone, Copy)]
@@ -93,15 +94,18 @@ impl FromRedisValue for CardinalityScriptResult {
     }
 }
 
-/// Abstraction over the `cardinality.lua` lua Redis script.
+/// Abstraction over the `cardinality.lua` and `cardinality_hll.lua` lua Redis scripts.
 pub struct CardinalityScript(&'static Script);
 
 impl CardinalityScript {
-    /// Loads the script.
+    /// Loads the script for the passed storage.
     ///
     /// This is somewhat costly and shouldn't be done often.
-    pub fn load() -> Self {
-        Self(RedisScripts::load_cardinality())
+    pub fn load(storage: RedisStorage) -> Self {
+        match storage {
+            RedisStorage::Set => Self(RedisScripts::load_cardinality()),
+            RedisStorage::HyperLogLog => Self(RedisScripts::load_cardinality_hll()),
+        }
     }
 
     /// Creates a new pipeline to batch multiple script invocations.
@@ -236,6 +240,13 @@ mod tests {
             .into_iter()
     }
 
+    fn hll_keys(prefix: Uuid, keys: &[&str]) -> impl Iterator<Item = String> {
+        self::keys(prefix, keys)
+            .flat_map(|key| RedisStorage::HyperLogLog.script_keys(key))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
     async fn assert_ttls(connection: &mut AsyncRedisConnection, prefix: Uuid) {
         let keys = redis::cmd("KEYS")
             .arg(format!("{prefix}-*"))
@@ -259,7 +270,7 @@ mod tests {
         let client = build_redis_client();
         let mut connection = client.get_connection().await.unwrap();
 
-        let script = CardinalityScript::load();
+        let script = CardinalityScript::load(RedisStorage::Set);
 
         let prefix = Uuid::new_v4();
         let k1 = &["a", "b", "c"];
@@ -283,7 +294,7 @@ mod tests {
         let client = build_redis_client();
         let mut connection = client.get_connection().await.unwrap();
 
-        let script = CardinalityScript::load();
+        let script = CardinalityScript::load(RedisStorage::Set);
         let keys = keys(Uuid::new_v4(), &["a", "b", "c"]);
 
         redis::cmd("SCRIPT")
@@ -302,7 +313,7 @@ mod tests {
         let client = build_redis_client();
         let mut connection = client.get_connection().await.unwrap();
 
-        let script = CardinalityScript::load();
+        let script = CardinalityScript::load(RedisStorage::Set);
         let k2 = keys(Uuid::new_v4(), &["a", "b", "c"]);
         let k1 = keys(Uuid::new_v4(), &["a", "b", "c"]);
 
@@ -316,4 +327,89 @@ mod tests {
 
         assert_eq!(results.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_hll_limit() {
+        let client = build_redis_client();
+        let mut connection = client.get_connection().await.unwrap();
+
+        let script = CardinalityScript::load(RedisStorage::HyperLogLog);
+        let prefix = Uuid::new_v4();
+        let limit = 10_000;
+
+        let result = script
+            .invoke_one(
+                &mut connection,
+                limit,
+                3600,
+                0..limit,
+                hll_keys(prefix, &["a", "b"]),
+            )
+            .await
+            .unwrap();
+
+        assert!(result.statuses.iter().all(|s| !s.is_rejected()));
+        assert!(result.cardinality.abs_diff(limit) < limit / 20);
+
+        // Previously accepted hashes are still accepted.
+        let result = script
+            .invoke_one(
+                &mut connection,
+                limit,
+                3600,
+                0..limit,
+                hll_keys(prefix, &["a", "b"]),
+            )
+            .await
+            .unwrap();
+        assert!(result.statuses.iter().all(|s| !s.is_rejected()));
+
+        // Only a bounded fraction of new hashes is accepted after the limit has been reached.
+        let num_new = 100_000;
+        let hashes = limit..limit + num_new;
+        let result = script
+            .invoke_one(
+                &mut connection,
+                limit,
+                3600,
+                hashes,
+                hll_keys(prefix, &["a", "b"]),
+            )
+            .await
+            .unwrap();
+
+        let accepted = result.statuses.iter().filter(|s| !s.is_rejected()).count();
+        assert!(
+            accepted < num_new as usize / 50,
+            "accepted {accepted} new hashes"
+        );
+
+        assert_ttls(&mut connection, prefix).await;
+    }
+
+    #[tokio::test]
+    async fn test_hll_limit_rejected_hash_repeated() {
+        let client = build_redis_client();
+        let mut connection = client.get_connection().await.unwrap();
+
+        let script = CardinalityScript::load(RedisStorage::HyperLogLog);
+        let prefix = Uuid::new_v4();
+
+        // The same over-limit hash is rejected every time it is passed.
+        let hashes = [1, 2, 3, 42, 42, 2, 42].into_iter();
+        let result = script
+            .invoke_one(
+                &mut connection,
+                3,
+                3600,
+                hashes,
+                hll_keys(prefix, &["a", "b"]),
+            )
+            .await
+            .unwrap();
+
+        let statuses: Vec<_> = result.statuses.iter().map(|s| s.is_rejected()).collect();
+        assert_eq!(statuses, [false, false, false, true, true, false, true]);
+        assert_eq!(result.cardinality, 3);
+    }
 }
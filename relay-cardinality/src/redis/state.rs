@@ -5,7 +5,7 @@ use relay_statsd::metric;
 use crate::{
     CardinalityLimit,
     limiter::{Entry, EntryId, Scoping},
-    redis::quota::{PartialQuotaScoping, QuotaScoping},
+    quota::{PartialQuotaScoping, QuotaScoping},
     statsd::{CardinalityLimiterCounters, CardinalityLimiterSets},
 };
 
@@ -50,7 +50,7 @@ impl SlidingWindow {
 }
 
 /// A single slot from a [`SlidingWindow`].
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Slot(u64);
 
 impl fmt::Display for Slot {
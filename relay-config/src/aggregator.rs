@@ -167,4 +167,25 @@ mod tests {
         assert!(condition.matches(Some(MetricNamespace::Custom)));
         assert!(!condition.matches(Some(MetricNamespace::Transactions)));
     }
+
+    #[test]
+    fn organization_budgets() {
+        let yaml = r#"
+max_organization_bucket_bytes: 1000
+organization_weights:
+  42: 3
+fair_share_eviction: true
+"#;
+
+        let config = serde_yaml::from_str::<AggregatorServiceConfig>(yaml).unwrap();
+        assert_eq!(config.aggregator.max_organization_bucket_bytes, Some(1000));
+        assert_debug_snapshot!(config.aggregator.organization_weights, @r###"
+        {
+            OrganizationId(
+                42,
+            ): 3,
+        }
+        "###);
+        assert!(config.aggregator.fair_share_eviction);
+    }
 }
@@ -12,8 +12,8 @@ use anyhow::Context;
 use relay_auth::{PublicKey, RelayId, SecretKey, generate_key_pair, generate_relay_id};
 use relay_common::Dsn;
 use relay_kafka::{
-    ConfigError as KafkaConfigError, KafkaConfigParam, KafkaTopic, KafkaTopicConfig,
-    TopicAssignments,
+    ConfigError as KafkaConfigError, KafkaConfigParam, KafkaSpoolConfig, KafkaTopic,
+    KafkaTopicConfig, TopicAssignments,
 };
 use relay_metrics::MetricNamespace;
 use serde::de::{DeserializeOwned, Unexpected, Visitor};
@@ -271,6 +271,12 @@ pub struct Credentials {
     pub public_key: PublicKey,
     /// The globally unique ID of the relay.
     pub id: RelayId,
+    /// A key pair staged for rotation, which replaces the current key pair once activated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<KeyPair>,
+    /// The public key that was active before the last rotation, until it is retired.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_public_key: Option<PublicKey>,
 }
 
 impl Credentials {
@@ -282,6 +288,8 @@ impl Credentials {
             secret_key: sk,
             public_key: pk,
             id: generate_relay_id(),
+            next: None,
+            previous_public_key: None,
         }
     }
 
@@ -290,6 +298,46 @@ impl Credentials {
         serde_json::to_string(self)
             .with_context(|| ConfigError::new(ConfigErrorKind::CouldNotWriteFile))
     }
+
+    /// Generates a new key pair and stages it for rotation.
+    ///
+    /// The current key pair remains in use until [`Self::activate_next`] is called. A previously
+    /// staged key pair is replaced.
+    pub fn stage_next(&mut self) -> &KeyPair {
+        let (secret_key, public_key) = generate_key_pair();
+        self.next.insert(KeyPair {
+            secret_key,
+            public_key,
+        })
+    }
+
+    /// Makes the staged key pair the current key pair.
+    ///
+    /// The current public key is retained as previous public key until it is retired with
+    /// [`Self::retire_previous`]. Returns `false` if no key pair is staged.
+    pub fn activate_next(&mut self) -> bool {
+        let Some(next) = self.next.take() else {
+            return false;
+        };
+
+        self.secret_key = next.secret_key;
+        self.previous_public_key = Some(std::mem::replace(&mut self.public_key, next.public_key));
+        true
+    }
+
+    /// Removes the previous public key after a completed rotation and returns it.
+    pub fn retire_previous(&mut self) -> Option<PublicKey> {
+        self.previous_public_key.take()
+    }
+}
+
+/// A secret and public key pair.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyPair {
+    /// The secret key.
+    pub secret_key: SecretKey,
+    /// The public key.
+    pub public_key: PublicKey,
 }
 
 impl ConfigObject for Credentials {
@@ -308,6 +356,12 @@ pub struct RelayInfo {
     /// The public key that this Relay uses to authenticate and sign requests.
     pub public_key: PublicKey,
 
+    /// A public key that this Relay is rotating to.
+    ///
+    /// Signatures of both the current and the next public key are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_public_key: Option<PublicKey>,
+
     /// Marks an internal relay that has privileged access to more project configuration.
     #[serde(default)]
     pub internal: bool,
@@ -318,9 +372,20 @@ impl RelayInfo {
     pub fn new(public_key: PublicKey) -> Self {
         Self {
             public_key,
+            next_public_key: None,
             internal: false,
         }
     }
+
+    /// Returns all public keys this Relay may sign requests with.
+    pub fn public_keys(&self) -> impl Iterator<Item = &PublicKey> {
+        std::iter::once(&self.public_key).chain(&self.next_public_key)
+    }
+
+    /// Verifies a signature of this Relay using any of its [public keys](Self::public_keys).
+    pub fn verify(&self, data: &[u8], signature: &str) -> bool {
+        relay_auth::verify_any(self.public_keys(), data, signature).is_some()
+    }
 }
 
 /// The operation mode of a relay.
@@ -611,6 +676,13 @@ pub struct Limits {
     ///
     /// The concurrency of queries is additionally constrained by `max_concurrent_requests`.
     pub max_concurrent_queries: usize,
+    /// How many low priority requests can be sent concurrently from Relay to the upstream.
+    ///
+    /// Low priority requests include envelopes and metrics. Limiting them reserves the remaining
+    /// capacity of `max_concurrent_requests` for high priority requests, so that they are never
+    /// starved. At least one request is always reserved. Defaults to 80% of
+    /// `max_concurrent_requests`.
+    pub max_concurrent_low_priority_requests: Option<usize>,
     /// The maximum payload size for events.
     pub max_event_size: ByteSize,
     /// The maximum size for each attachment.
@@ -704,6 +776,7 @@ impl Default for Limits {
         Limits {
             max_concurrent_requests: 100,
             max_concurrent_queries: 5,
+            max_concurrent_low_priority_requests: None,
             max_event_size: ByteSize::mebibytes(1),
             max_attachment_size: ByteSize::mebibytes(100),
             max_attachments_size: ByteSize::mebibytes(100),
@@ -813,6 +886,24 @@ impl HttpEncoding {
     }
 }
 
+/// HTTP protocol version used for connections to the upstream.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpProtocol {
+    /// Only use HTTP/1.1.
+    #[default]
+    Http1,
+    /// Negotiate the protocol with the upstream via ALPN during the TLS handshake.
+    ///
+    /// Uses HTTP/2 if the upstream supports it and falls back to HTTP/1.1 otherwise. Plain-text
+    /// connections always use HTTP/1.1.
+    Auto,
+    /// Use HTTP/2 with prior knowledge, including for plain-text connections.
+    ///
+    /// Requests fail if the upstream does not support HTTP/2.
+    Http2,
+}
+
 /// Controls authentication with upstream.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
@@ -878,6 +969,29 @@ pub struct Http {
     ///
     /// This option does not have any effect on processing mode.
     pub global_metrics: bool,
+    /// The HTTP protocol version for upstream connections.
+    ///
+    /// Available options are:
+    ///
+    ///  - `http1` (default): Only use HTTP/1.1.
+    ///  - `auto`: Negotiate HTTP/2 via ALPN for TLS connections, falling back to HTTP/1.1.
+    ///  - `http2`: Use HTTP/2 with prior knowledge.
+    ///
+    /// With HTTP/2, requests to the upstream are multiplexed over a single connection.
+    pub protocol: HttpProtocol,
+    /// Maximum number of idle connections kept open to the upstream per connection pool.
+    ///
+    /// Defaults to no limit.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Time in seconds after which idle connections to the upstream are closed.
+    ///
+    /// Defaults to `90`.
+    pub pool_idle_timeout: u64,
+    /// Use separate connection pools for high and low priority upstream requests.
+    ///
+    /// When enabled, low priority requests such as envelopes cannot occupy the connections needed
+    /// by high priority requests such as project config queries. Defaults to `false`.
+    pub priority_pools: bool,
 }
 
 impl Default for Http {
@@ -893,6 +1007,10 @@ impl Default for Http {
             project_failure_interval: default_project_failure_interval(),
             encoding: HttpEncoding::Zstd,
             global_metrics: false,
+            protocol: HttpProtocol::default(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: 90,
+            priority_pools: false,
         }
     }
 }
@@ -1136,6 +1254,41 @@ fn default_max_rate_limit() -> Option<u32> {
     Some(300) // 5 minutes
 }
 
+/// Disk spool for messages that failed to produce to Kafka.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KafkaSpool {
+    /// The directory in which failed messages are spooled.
+    ///
+    /// If not set, messages that fail to produce because of broker or producer errors are dropped.
+    pub path: Option<PathBuf>,
+    /// The maximum size of all spooled messages on disk.
+    ///
+    /// When the spool reaches this size, messages that fail to produce are dropped.
+    ///
+    /// Defaults to 500MiB.
+    pub max_disk_size: ByteSize,
+    /// Initial interval between attempts to replay spooled messages, in milliseconds.
+    ///
+    /// The interval doubles while producing keeps failing. Defaults to 1 second.
+    pub min_backoff_ms: u64,
+    /// Maximum interval between attempts to replay spooled messages, in milliseconds.
+    ///
+    /// Defaults to 1 minute.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for KafkaSpool {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_disk_size: ByteSize::mebibytes(500),
+            min_backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
 /// Controls Sentry-internal event processing.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Processing {
@@ -1182,6 +1335,9 @@ pub struct Processing {
     /// Whether to validate the supplied topics by calling Kafka's metadata endpoints.
     #[serde(default)]
     pub kafka_validate_topics: bool,
+    /// Disk spool for messages that could not be produced to Kafka.
+    #[serde(default)]
+    pub kafka_spool: KafkaSpool,
     /// Redis hosts to connect to for storing state for rate limits.
     #[serde(default)]
     pub redis: Option<RedisConfigs>,
@@ -1208,6 +1364,7 @@ impl Default for Processing {
             secondary_kafka_configs: BTreeMap::new(),
             topics: TopicAssignments::default(),
             kafka_validate_topics: false,
+            kafka_spool: KafkaSpool::default(),
             redis: None,
             attachment_chunk_size: default_chunk_size(),
             projectconfig_cache_prefix: default_projectconfig_cache_prefix(),
@@ -1243,6 +1400,47 @@ pub struct Normalization {
     /// Level of normalization for Relay to apply to incoming data.
     #[serde(default)]
     pub level: NormalizationLevel,
+    /// Local clustering of high-cardinality URL transaction names.
+    #[serde(default)]
+    pub transaction_clustering: TransactionClustering,
+}
+
+/// Configuration for clustering URL transaction names within Relay.
+///
+/// Sentry discovers rules for high-cardinality transaction names and sends them to Relay in the
+/// project config. Relays without access to Sentry's transaction clusterer can enable local
+/// clustering instead. Rules discovered locally are applied after the rules from the project
+/// config.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransactionClustering {
+    /// Enables local clustering of transaction names.
+    ///
+    /// Defaults to `false`.
+    pub enabled: bool,
+    /// Number of distinct values of a path segment after which the segment is replaced by `*`.
+    ///
+    /// Defaults to `200`.
+    pub merge_threshold: usize,
+    /// Maximum number of path segments kept per project.
+    ///
+    /// Defaults to `10000`.
+    pub max_nodes: usize,
+    /// Time in seconds after which a rule expires if no transaction matches it.
+    ///
+    /// Defaults to 90 days.
+    pub rule_ttl: u64,
+}
+
+impl Default for TransactionClustering {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            merge_threshold: 200,
+            max_nodes: 10_000,
+            rule_ttl: 90 * 24 * 3600,
+        }
+    }
 }
 
 /// Configuration for the level of normalization this Relay should do.
@@ -1280,6 +1478,45 @@ impl Default for OutcomeAggregatorConfig {
     }
 }
 
+/// Configuration values for the local outcome store.
+///
+/// The store keeps aggregated outcomes of this Relay in a SQLite database, where they can be
+/// queried through the internal `/api/relay/outcomes/` endpoint. Queries must authenticate with the
+/// configured [`query_token`](Self::query_token).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct OutcomeStoreConfig {
+    /// Enables the outcome store.
+    pub enabled: bool,
+    /// The path of the SQLite database.
+    ///
+    /// Defaults to `outcomes.db` in the directory of the envelope spool.
+    pub path: Option<PathBuf>,
+    /// Defines the width of the time buckets in which outcomes are stored, in seconds.
+    pub bucket_interval: u64,
+    /// Defines how often outcomes are written to the database, in seconds.
+    pub flush_interval: u64,
+    /// The number of days outcomes are kept in the database.
+    pub retention_days: u16,
+    /// Token required to query outcomes, passed as `Authorization: Bearer <token>` header.
+    ///
+    /// The query endpoint rejects all requests if no token is configured.
+    pub query_token: Option<String>,
+}
+
+impl Default for OutcomeStoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            bucket_interval: 3600,
+            flush_interval: 60,
+            retention_days: 30,
+            query_token: None,
+        }
+    }
+}
+
 /// Determines how to emit outcomes.
 /// For compatibility reasons, this can either be true, false or AsClientReports
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -1376,6 +1613,8 @@ pub struct Outcomes {
     pub source: Option<String>,
     /// Configures the outcome aggregator.
     pub aggregator: OutcomeAggregatorConfig,
+    /// Configures the local outcome store.
+    pub store: OutcomeStoreConfig,
 }
 
 impl Default for Outcomes {
@@ -1387,6 +1626,7 @@ impl Default for Outcomes {
             batch_interval: 500,
             source: None,
             aggregator: OutcomeAggregatorConfig::default(),
+            store: OutcomeStoreConfig::default(),
         }
     }
 }
@@ -1430,6 +1670,8 @@ mod config_relay_info {
     #[derive(Debug, Serialize, Deserialize, Clone)]
     struct RelayInfoConfig {
         public_key: PublicKey,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        next_public_key: Option<PublicKey>,
         #[serde(default)]
         internal: bool,
     }
@@ -1438,6 +1680,7 @@ mod config_relay_info {
         fn from(v: RelayInfoConfig) -> Self {
             RelayInfo {
                 public_key: v.public_key,
+                next_public_key: v.next_public_key,
                 internal: v.internal,
             }
         }
@@ -1447,6 +1690,7 @@ mod config_relay_info {
         fn from(v: RelayInfo) -> Self {
             RelayInfoConfig {
                 public_key: v.public_key,
+                next_public_key: v.next_public_key,
                 internal: v.internal,
             }
         }
@@ -1493,6 +1737,15 @@ pub struct GeoIpConfig {
     pub path: Option<PathBuf>,
 }
 
+/// User agent parser configuration options.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UserAgentConfig {
+    /// The path to a `regexes.yaml` file in the uap-core format.
+    ///
+    /// If not set or if the file cannot be loaded, Relay uses its built-in user agent database.
+    pub regexes_path: Option<PathBuf>,
+}
+
 /// Cardinality Limiter configuration options.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
@@ -1503,12 +1756,68 @@ pub struct CardinalityLimiter {
     ///
     /// Defaults to 180 seconds, 3 minutes.
     pub cache_vacuum_interval: u64,
+    /// The storage used to track cardinality.
+    ///
+    /// Defaults to [`CardinalityLimiterBackend::RedisSet`].
+    pub backend: CardinalityLimiterBackend,
 }
 
 impl Default for CardinalityLimiter {
     fn default() -> Self {
         Self {
             cache_vacuum_interval: 180,
+            backend: CardinalityLimiterBackend::default(),
+        }
+    }
+}
+
+/// The storage used by the cardinality limiter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardinalityLimiterBackend {
+    /// Tracks exact cardinality with Redis sets.
+    ///
+    /// Requires the cardinality Redis to be configured.
+    #[default]
+    RedisSet,
+    /// Tracks approximate cardinality with Redis HyperLogLog sketches.
+    ///
+    /// Uses far less Redis memory than sets at the cost of a small error in the enforced limit.
+    /// Requires the cardinality Redis to be configured.
+    RedisHyperloglog,
+    /// Tracks approximate cardinality with HyperLogLog sketches in the memory of this Relay.
+    ///
+    /// Does not require Redis, but cardinality is tracked separately by every Relay instance.
+    Memory,
+}
+
+/// Configuration for enforcing quotas in the memory of a Relay.
+///
+/// Processing Relays enforce quotas from the project config consistently with Redis. Other Relays
+/// only enforce rate limits returned by their upstream, unless local enforcement is enabled. Local
+/// quotas are counted separately by every Relay instance.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct LocalQuotas {
+    /// Enables enforcement of quotas from the project config in non-processing Relays.
+    ///
+    /// Defaults to `false`.
+    pub enabled: bool,
+    /// Fraction of each quota's limit that is enforced by this instance.
+    ///
+    /// When several Relays share the traffic of the same projects, setting this to the share of
+    /// traffic each Relay receives approximately enforces the limit across all instances. For
+    /// example, use `0.25` for four instances behind a round-robin load balancer.
+    ///
+    /// Defaults to `1.0`.
+    pub limit_fraction: f64,
+}
+
+impl Default for LocalQuotas {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            limit_fraction: 1.0,
         }
     }
 }
@@ -1549,6 +1858,13 @@ pub struct Health {
     /// The implementation of memory stats guarantees that the refresh will happen at
     /// least every `x` ms since memory readings are lazy and are updated only if needed.
     pub memory_stat_refresh_frequency_ms: u64,
+    /// Reports Relay as not ready while the circuit breaker of a Redis client is open.
+    ///
+    /// Only applies to processing Relays. Consumers of Redis fall back to their degraded behavior
+    /// while the circuit breaker is open, so by default this does not affect readiness.
+    ///
+    /// Defaults to `false`.
+    pub redis_circuit_breaker: bool,
 }
 
 impl Default for Health {
@@ -1559,6 +1875,7 @@ impl Default for Health {
             max_memory_percent: 0.95,
             probe_timeout_ms: 900,
             memory_stat_refresh_frequency_ms: 100,
+            redis_circuit_breaker: false,
         }
     }
 }
@@ -1625,10 +1942,14 @@ struct ConfigValues {
     #[serde(default)]
     geoip: GeoIpConfig,
     #[serde(default)]
+    user_agent: UserAgentConfig,
+    #[serde(default)]
     normalization: Normalization,
     #[serde(default)]
     cardinality_limiter: CardinalityLimiter,
     #[serde(default)]
+    local_quotas: LocalQuotas,
+    #[serde(default)]
     health: Health,
     #[serde(default)]
     cogs: Cogs,
@@ -1824,6 +2145,8 @@ impl Config {
                         secret_key,
                         public_key,
                         id,
+                        next: None,
+                        previous_public_key: None,
                     })
                 }
                 (None, None, None) => {
@@ -1927,6 +2250,14 @@ impl Config {
         self.credentials.as_ref().map(|x| &x.public_key)
     }
 
+    /// Returns the public key staged for rotation if set.
+    pub fn next_public_key(&self) -> Option<&PublicKey> {
+        self.credentials
+            .as_ref()
+            .and_then(|x| x.next.as_ref())
+            .map(|x| &x.public_key)
+    }
+
     /// Returns the relay ID.
     pub fn relay_id(&self) -> Option<&RelayId> {
         self.credentials.as_ref().map(|x| &x.id)
@@ -2081,6 +2412,29 @@ impl Config {
         &self.values.outcomes.aggregator
     }
 
+    /// Returns the configuration of the local outcome store.
+    pub fn outcome_store(&self) -> &OutcomeStoreConfig {
+        &self.values.outcomes.store
+    }
+
+    /// Returns the path of the local outcome store database.
+    ///
+    /// Returns `None` if the store is disabled, or if neither a path nor an envelope spool path is
+    /// configured.
+    pub fn outcome_store_path(&self) -> Option<PathBuf> {
+        let store = &self.values.outcomes.store;
+        if !store.enabled {
+            return None;
+        }
+
+        if let Some(path) = &store.path {
+            return Some(path.clone());
+        }
+
+        let spool_path = self.values.spool.envelopes.path.as_ref()?;
+        Some(spool_path.with_file_name("outcomes.db"))
+    }
+
     /// Returns logging configuration.
     pub fn logging(&self) -> &relay_log::LogConfig {
         &self.values.logging
@@ -2157,6 +2511,26 @@ impl Config {
         Duration::from_secs(self.values.http.connection_timeout.into())
     }
 
+    /// Returns the HTTP protocol version for upstream connections.
+    pub fn http_protocol(&self) -> HttpProtocol {
+        self.values.http.protocol
+    }
+
+    /// Returns the maximum number of idle upstream connections per connection pool.
+    pub fn http_pool_max_idle_per_host(&self) -> Option<usize> {
+        self.values.http.pool_max_idle_per_host
+    }
+
+    /// Returns the time after which idle upstream connections are closed.
+    pub fn http_pool_idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.values.http.pool_idle_timeout)
+    }
+
+    /// Returns `true` if high and low priority upstream requests use separate connection pools.
+    pub fn http_priority_pools(&self) -> bool {
+        self.values.http.priority_pools
+    }
+
     /// Returns the failed upstream request retry interval.
     pub fn http_max_retry_interval(&self) -> Duration {
         Duration::from_secs(self.values.http.max_retry_interval.into())
@@ -2414,6 +2788,20 @@ impl Config {
         self.values.limits.max_concurrent_requests
     }
 
+    /// Returns the maximum number of active low priority requests.
+    ///
+    /// At least one of the [`max_concurrent_requests`](Self::max_concurrent_requests) is reserved
+    /// for high priority requests, unless only a single request is allowed.
+    pub fn max_concurrent_low_priority_requests(&self) -> usize {
+        let max = self.max_concurrent_requests();
+        let max_low = max.saturating_sub(1).max(1);
+        self.values
+            .limits
+            .max_concurrent_low_priority_requests
+            .unwrap_or(max * 4 / 5)
+            .clamp(1, max_low)
+    }
+
     /// Returns the maximum number of active queries
     pub fn max_concurrent_queries(&self) -> usize {
         self.values.limits.max_concurrent_queries
@@ -2482,6 +2870,11 @@ impl Config {
         self.values.normalization.level
     }
 
+    /// Configuration for clustering URL transaction names locally.
+    pub fn transaction_clustering(&self) -> &TransactionClustering {
+        &self.values.normalization.transaction_clustering
+    }
+
     /// The path to the GeoIp database required for event processing.
     pub fn geoip_path(&self) -> Option<&Path> {
         self.values
@@ -2491,6 +2884,11 @@ impl Config {
             .or(self.values.processing.geoip_path.as_deref())
     }
 
+    /// The path to a user agent database that replaces the built-in database.
+    pub fn user_agent_regexes_path(&self) -> Option<&Path> {
+        self.values.user_agent.regexes_path.as_deref()
+    }
+
     /// Maximum future timestamp of ingested data.
     ///
     /// Events past this timestamp will be adjusted to `now()`. Sessions will be dropped.
@@ -2519,6 +2917,19 @@ impl Config {
         self.values.processing.kafka_validate_topics
     }
 
+    /// Configuration of the disk spool for messages that failed to produce to Kafka.
+    ///
+    /// Returns `None` if no spool directory is configured.
+    pub fn kafka_spool(&self) -> Option<KafkaSpoolConfig> {
+        let spool = &self.values.processing.kafka_spool;
+        Some(KafkaSpoolConfig {
+            path: spool.path.clone()?,
+            max_disk_size: spool.max_disk_size.as_bytes() as u64,
+            min_backoff: Duration::from_millis(spool.min_backoff_ms),
+            max_backoff: Duration::from_millis(spool.max_backoff_ms),
+        })
+    }
+
     /// All unused but configured topic assignments.
     pub fn unused_topic_assignments(&self) -> &relay_kafka::Unused {
         &self.values.processing.topics.unused
@@ -2563,6 +2974,19 @@ impl Config {
         Duration::from_secs(self.values.cardinality_limiter.cache_vacuum_interval)
     }
 
+    /// The storage used by the cardinality limiter.
+    pub fn cardinality_limiter_backend(&self) -> CardinalityLimiterBackend {
+        self.values.cardinality_limiter.backend
+    }
+
+    /// Returns the fraction of quota limits to enforce in memory, if local quotas are enabled.
+    ///
+    /// Local quotas are never enforced in processing Relays, which enforce quotas with Redis.
+    pub fn local_quota_fraction(&self) -> Option<f64> {
+        let local_quotas = &self.values.local_quotas;
+        (local_quotas.enabled && !self.processing_enabled()).then_some(local_quotas.limit_fraction)
+    }
+
     /// Interval to refresh internal health checks.
     pub fn health_refresh_interval(&self) -> Duration {
         Duration::from_millis(self.values.health.refresh_interval_ms)
@@ -2587,6 +3011,11 @@ impl Config {
         Duration::from_millis(self.values.health.probe_timeout_ms)
     }
 
+    /// Returns `true` if an open Redis circuit breaker fails the readiness health check.
+    pub fn health_redis_circuit_breaker(&self) -> bool {
+        self.values.health.redis_circuit_breaker
+    }
+
     /// Refresh frequency for polling new memory stats.
     pub fn memory_stat_refresh_frequency_ms(&self) -> u64 {
         self.values.health.memory_stat_refresh_frequency_ms
@@ -2690,4 +3119,93 @@ cache:
     fn test_emit_outcomes_invalid() {
         assert!(serde_json::from_str::<EmitOutcomes>("asdf").is_err());
     }
+
+    #[test]
+    fn test_credentials_rotation() {
+        let mut credentials = Credentials::generate();
+        let initial_key = credentials.public_key.clone();
+
+        assert!(!credentials.activate_next());
+
+        let next_key = credentials.stage_next().public_key.clone();
+        assert_eq!(credentials.public_key, initial_key);
+
+        assert!(credentials.activate_next());
+        assert_eq!(credentials.public_key, next_key);
+        assert_eq!(credentials.secret_key.public_key(), next_key);
+        assert_eq!(credentials.previous_public_key, Some(initial_key.clone()));
+        assert!(credentials.next.is_none());
+
+        assert_eq!(credentials.retire_previous(), Some(initial_key));
+        assert_eq!(credentials.retire_previous(), None);
+    }
+
+    #[test]
+    fn test_relay_info_next_public_key() {
+        let (current_sk, current_pk) = generate_key_pair();
+        let (next_sk, next_pk) = generate_key_pair();
+        let (other_sk, _) = generate_key_pair();
+
+        let json = format!(r#"{{"publicKey":"{current_pk}","nextPublicKey":"{next_pk}"}}"#);
+        let info: RelayInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(info.next_public_key, Some(next_pk));
+
+        assert!(info.verify(b"data", &current_sk.sign(b"data")));
+        assert!(info.verify(b"data", &next_sk.sign(b"data")));
+        assert!(!info.verify(b"data", &other_sk.sign(b"data")));
+    }
+
+    #[test]
+    fn test_outcome_store_path() {
+        let config = Config::from_json_value(serde_json::json!({
+            "spool": {"envelopes": {"path": "/var/lib/relay/spool.db"}},
+            "outcomes": {"store": {"enabled": true}}
+        }))
+        .unwrap();
+        assert_eq!(
+            config.outcome_store_path(),
+            Some(PathBuf::from("/var/lib/relay/outcomes.db"))
+        );
+
+        let config = Config::from_json_value(serde_json::json!({
+            "spool": {"envelopes": {"path": "/var/lib/relay/spool.db"}},
+            "outcomes": {"store": {"enabled": false, "path": "/tmp/outcomes.db"}}
+        }))
+        .unwrap();
+        assert_eq!(config.outcome_store_path(), None);
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {"store": {"enabled": true}}
+        }))
+        .unwrap();
+        assert_eq!(config.outcome_store_path(), None);
+    }
+
+    #[test]
+    fn test_upstream_connection_settings() {
+        let config = Config::from_json_value(serde_json::json!({
+            "http": {
+                "protocol": "auto",
+                "pool_max_idle_per_host": 10,
+                "pool_idle_timeout": 30,
+                "priority_pools": true
+            },
+            "limits": {
+                "max_concurrent_requests": 20,
+                "max_concurrent_low_priority_requests": 50
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(config.http_protocol(), HttpProtocol::Auto);
+        assert_eq!(config.http_pool_max_idle_per_host(), Some(10));
+        assert_eq!(config.http_pool_idle_timeout(), Duration::from_secs(30));
+        assert!(config.http_priority_pools());
+        assert_eq!(config.max_concurrent_low_priority_requests(), 19);
+
+        let config = Config::default();
+        assert_eq!(config.http_protocol(), HttpProtocol::Http1);
+        assert!(!config.http_priority_pools());
+        assert_eq!(config.max_concurrent_low_priority_requests(), 80);
+    }
 }
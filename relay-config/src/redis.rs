@@ -1,4 +1,4 @@
-use relay_redis::RedisConfigOptions;
+use relay_redis::{RedisCircuitBreakerConfig, RedisConfigOptions, RedisTlsConfig};
 use serde::{Deserialize, Serialize};
 
 /// For small setups, `2 x limits.max_thread_count` does not leave enough headroom.
@@ -45,6 +45,20 @@ pub struct PartialRedisConfigOptions {
     /// A frequency of 1, means that the connection will check whether it is active every time it
     /// is recycled.
     pub recycle_check_frequency: usize,
+    /// TLS configuration for connections using `rediss://` urls.
+    ///
+    /// Allows to configure a custom certificate authority and a client certificate for mutual
+    /// TLS. Without this option, the server certificate is verified with the root certificates of
+    /// the operating system.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<RedisTlsConfig>,
+    /// Configuration of the circuit breaker around this client.
+    ///
+    /// After consecutive failures to reach Redis, the circuit breaker opens and requests fail
+    /// immediately until Redis recovers. Defaults to opening after 20 consecutive failures and
+    /// probing Redis every 5 seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit_breaker: Option<RedisCircuitBreakerConfig>,
 }
 
 impl Default for PartialRedisConfigOptions {
@@ -56,6 +70,8 @@ impl Default for PartialRedisConfigOptions {
             recycle_timeout: Some(2),
             wait_timeout: None,
             recycle_check_frequency: 100,
+            tls: None,
+            circuit_breaker: None,
         }
     }
 }
@@ -106,6 +122,22 @@ pub enum RedisConfig {
         #[serde(flatten)]
         options: PartialRedisConfigOptions,
     },
+    /// Connect to a Redis master monitored by Redis Sentinel.
+    ///
+    /// The address of the master is discovered through the sentinels and rediscovered after a
+    /// failover.
+    Sentinel {
+        /// Name of the master as configured in the sentinels.
+        master_name: String,
+        /// Redis urls of the sentinels.
+        ///
+        /// Credentials, the database and the `rediss://` scheme of the first url also apply to
+        /// the connections to the master.
+        sentinel_nodes: Vec<String>,
+        /// Options of the Redis config.
+        #[serde(flatten)]
+        options: PartialRedisConfigOptions,
+    },
     /// Connect to a single Redis instance.
     Single(SingleRedisConfig),
 }
@@ -193,6 +225,15 @@ pub enum RedisConfigRef<'a> {
         /// Options of the Redis config.
         options: RedisConfigOptions,
     },
+    /// Connect to a Redis master monitored by Redis Sentinel.
+    Sentinel {
+        /// Reference to the name of the master.
+        master_name: &'a String,
+        /// Reference to the Redis urls of the sentinels.
+        sentinel_nodes: &'a Vec<String>,
+        /// Options of the Redis config.
+        options: RedisConfigOptions,
+    },
 }
 
 /// Helper struct bundling connections and options for the various Redis pools.
@@ -225,6 +266,8 @@ fn build_redis_config_options(
         recycle_timeout: options.recycle_timeout,
         wait_timeout: options.wait_timeout,
         recycle_check_frequency: options.recycle_check_frequency,
+        tls: options.tls.clone(),
+        circuit_breaker: options.circuit_breaker.clone().unwrap_or_default(),
     }
 }
 
@@ -243,6 +286,15 @@ pub(super) fn build_redis_config(
             cluster_nodes,
             options: build_redis_config_options(options, default_connections),
         },
+        RedisConfig::Sentinel {
+            master_name,
+            sentinel_nodes,
+            options,
+        } => RedisConfigRef::Sentinel {
+            master_name,
+            sentinel_nodes,
+            options: build_redis_config_options(options, default_connections),
+        },
         RedisConfig::Single(SingleRedisConfig::Detailed { server, options }) => {
             RedisConfigRef::Single {
                 server,
@@ -577,6 +629,192 @@ max_connections: 20
         "#);
     }
 
+    #[test]
+    fn test_redis_sentinel_opts() {
+        let yaml = r#"
+master_name: "mymaster"
+sentinel_nodes:
+    - "redis://127.0.0.1:26379"
+    - "redis://127.0.0.2:26379"
+max_connections: 10
+"#;
+
+        let config: RedisConfig = serde_yaml::from_str(yaml)
+            .expect("Parsed processing redis config: sentinel with options");
+
+        assert_eq!(
+            config,
+            RedisConfig::Sentinel {
+                master_name: "mymaster".to_owned(),
+                sentinel_nodes: vec![
+                    "redis://127.0.0.1:26379".to_owned(),
+                    "redis://127.0.0.2:26379".to_owned()
+                ],
+                options: PartialRedisConfigOptions {
+                    max_connections: Some(10),
+                    ..Default::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_redis_sentinel_serialize() {
+        let config = RedisConfig::Sentinel {
+            master_name: "mymaster".to_owned(),
+            sentinel_nodes: vec!["redis://127.0.0.1:26379".to_owned()],
+            options: Default::default(),
+        };
+
+        assert_json_snapshot!(config, @r#"
+        {
+          "master_name": "mymaster",
+          "sentinel_nodes": [
+            "redis://127.0.0.1:26379"
+          ],
+          "idle_timeout": 60,
+          "create_timeout": 3,
+          "recycle_timeout": 2,
+          "recycle_check_frequency": 100
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_redis_tls_individual() {
+        let yaml = r#"
+project_configs:
+    server: "rediss://127.0.0.1:6379"
+    tls:
+        ca_cert: "/etc/redis/ca.pem"
+cardinality:
+    cluster_nodes:
+        - "rediss://127.0.0.1:6379"
+    tls:
+        ca_cert: "/etc/redis/ca.pem"
+        client_cert: "/etc/redis/client.pem"
+        client_key: "/etc/redis/client.key"
+quotas:
+    master_name: "mymaster"
+    sentinel_nodes:
+        - "rediss://127.0.0.1:26379"
+    tls:
+        client_cert: "/etc/redis/client.pem"
+        client_key: "/etc/redis/client.key"
+"#;
+
+        let configs: RedisConfigs =
+            serde_yaml::from_str(yaml).expect("Parsed processing redis configs: tls");
+
+        let ca_tls = RedisTlsConfig {
+            ca_cert: Some("/etc/redis/ca.pem".into()),
+            ..Default::default()
+        };
+        let client_tls = RedisTlsConfig {
+            client_cert: Some("/etc/redis/client.pem".into()),
+            client_key: Some("/etc/redis/client.key".into()),
+            ..Default::default()
+        };
+
+        let expected = RedisConfigs::Individual {
+            project_configs: Box::new(RedisConfig::Single(SingleRedisConfig::Detailed {
+                server: "rediss://127.0.0.1:6379".to_owned(),
+                options: PartialRedisConfigOptions {
+                    tls: Some(ca_tls.clone()),
+                    ..Default::default()
+                },
+            })),
+            cardinality: Box::new(RedisConfig::Cluster {
+                cluster_nodes: vec!["rediss://127.0.0.1:6379".to_owned()],
+                options: PartialRedisConfigOptions {
+                    tls: Some(RedisTlsConfig {
+                        ca_cert: ca_tls.ca_cert,
+                        ..client_tls.clone()
+                    }),
+                    ..Default::default()
+                },
+            }),
+            quotas: Box::new(RedisConfig::Sentinel {
+                master_name: "mymaster".to_owned(),
+                sentinel_nodes: vec!["rediss://127.0.0.1:26379".to_owned()],
+                options: PartialRedisConfigOptions {
+                    tls: Some(client_tls.clone()),
+                    ..Default::default()
+                },
+            }),
+        };
+
+        assert_eq!(configs, expected);
+
+        let RedisConfigsRef::Individual { quotas, .. } = build_redis_configs(&configs, 1) else {
+            panic!("expected individual redis configs");
+        };
+        let RedisConfigRef::Sentinel { options, .. } = quotas else {
+            panic!("expected sentinel redis config");
+        };
+        assert_eq!(options.tls, Some(client_tls));
+    }
+
+    #[test]
+    fn test_redis_tls_server_name() {
+        let yaml = r#"
+sentinel_nodes:
+    - "rediss://127.0.0.1:26379"
+master_name: mymaster
+tls:
+    ca_cert: "/etc/redis/ca.pem"
+    server_name: "redis.internal"
+"#;
+
+        let config: RedisConfig = serde_yaml::from_str(yaml)
+            .expect("Parsed processing redis config: sentinel with tls server name");
+
+        let RedisConfigRef::Sentinel { options, .. } = build_redis_config(&config, 24) else {
+            panic!("expected sentinel redis config");
+        };
+        assert_eq!(
+            options.tls,
+            Some(RedisTlsConfig {
+                ca_cert: Some("/etc/redis/ca.pem".into()),
+                server_name: Some("redis.internal".to_owned()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_redis_circuit_breaker() {
+        let yaml = r#"
+server: "redis://127.0.0.1:6379"
+circuit_breaker:
+    failure_threshold: 5
+"#;
+
+        let config: RedisConfig = serde_yaml::from_str(yaml)
+            .expect("Parsed processing redis config: single with circuit breaker");
+
+        let RedisConfigRef::Single { options, .. } = build_redis_config(&config, 24) else {
+            panic!("expected single redis config");
+        };
+        assert_eq!(
+            options.circuit_breaker,
+            RedisCircuitBreakerConfig {
+                failure_threshold: 5,
+                ..Default::default()
+            }
+        );
+
+        // Clients without a circuit breaker config use the default.
+        let config = RedisConfig::single("redis://127.0.0.1:6379".to_owned());
+        let RedisConfigRef::Single { options, .. } = build_redis_config(&config, 24) else {
+            panic!("expected single redis config");
+        };
+        assert_eq!(
+            options.circuit_breaker,
+            RedisCircuitBreakerConfig::default()
+        );
+    }
+
     #[test]
     fn test_redis_serialize_individual() {
         let configs = RedisConfigs::Individual {
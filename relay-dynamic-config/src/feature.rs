@@ -100,6 +100,11 @@ pub enum Feature {
     /// Serialized as `organizations:ourlogs-ingestion`.
     #[serde(rename = "organizations:ourlogs-ingestion")]
     OurLogsIngestion,
+    /// Enables extraction of metrics from Network Error Logging (NEL) reports.
+    ///
+    /// Serialized as `organizations:nel-metrics-extraction`.
+    #[serde(rename = "organizations:nel-metrics-extraction")]
+    NelMetricsExtraction,
     /// This feature has graduated and is hard-coded for external Relays.
     #[doc(hidden)]
     #[serde(rename = "projects:profiling-ingest-unsampled-profiles")]
@@ -5,6 +5,7 @@ use std::io::BufReader;
 use std::path::Path;
 
 use relay_base_schema::metrics::MetricNamespace;
+use relay_base_schema::organization::OrganizationId;
 use relay_event_normalization::{MeasurementsConfig, ModelCosts, SpanOpDefaults};
 use relay_filter::GenericFiltersConfig;
 use relay_quotas::Quota;
@@ -50,6 +51,12 @@ pub struct GlobalConfig {
     #[serde(skip_serializing_if = "is_model_costs_empty")]
     pub ai_model_costs: ErrorBoundary<ModelCosts>,
 
+    /// Organization-specific AI model costs, such as negotiated rates.
+    ///
+    /// These take precedence over `ai_model_costs`, but not over model costs in project configs.
+    #[serde(skip_serializing_if = "is_ok_and_empty_map")]
+    pub ai_model_cost_overrides: ErrorBoundary<HashMap<OrganizationId, ModelCosts>>,
+
     /// Configuration to derive the `span.op` from other span fields.
     #[serde(
         deserialize_with = "default_on_error",
@@ -82,6 +89,14 @@ impl GlobalConfig {
         }
     }
 
+    /// Returns the AI model costs specific to an organization.
+    pub fn ai_model_cost_overrides(&self, organization_id: OrganizationId) -> Option<&ModelCosts> {
+        match &self.ai_model_cost_overrides {
+            ErrorBoundary::Err(_) => None,
+            ErrorBoundary::Ok(overrides) => overrides.get(&organization_id),
+        }
+    }
+
     /// Modifies the global config after deserialization.
     ///
     /// - Adds hard-coded groups to metrics extraction configs.
@@ -364,6 +379,10 @@ fn is_model_costs_empty(value: &ErrorBoundary<ModelCosts>) -> bool {
     matches!(value, ErrorBoundary::Ok(model_costs) if model_costs.is_empty())
 }
 
+fn is_ok_and_empty_map<K, V>(value: &ErrorBoundary<HashMap<K, V>>) -> bool {
+    matches!(value, ErrorBoundary::Ok(map) if map.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -429,6 +448,29 @@ mod tests {
         assert_eq!(json, serialized.as_str());
     }
 
+    #[test]
+    fn test_ai_model_cost_overrides_roundtrip() {
+        let json = r#"{"aiModelCostOverrides":{"42":{"version":2,"models":{"gpt-4o":{"inputPerToken":1.0,"outputPerToken":2.0,"outputReasoningPerToken":0.0,"inputCachedPerToken":0.0}}}}}"#;
+        let config: GlobalConfig = serde_json::from_str(json).unwrap();
+
+        let costs = config
+            .ai_model_cost_overrides(OrganizationId::new(42))
+            .and_then(|costs| costs.cost_per_token("gpt-4o"))
+            .unwrap();
+        assert_eq!(costs.input_per_token, 1.0);
+        assert!(
+            config
+                .ai_model_cost_overrides(OrganizationId::new(1))
+                .is_none()
+        );
+
+        let serialized = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            serialized["aiModelCostOverrides"]["42"]["models"]["gpt-4o"]["outputPerToken"],
+            2.0
+        );
+    }
+
     #[test]
     fn test_global_config_invalid_value_is_default() {
         let options: Options = serde_json::from_str(
@@ -408,6 +408,9 @@ impl_str_serde!(GroupKey, "a metrics extraction group key");
 #[serde(rename_all = "camelCase")]
 pub struct MetricSpec {
     /// Category of data to extract this metric for.
+    ///
+    /// Besides events and spans, metrics can be extracted from logs (`log_item`), monitor
+    /// check-ins (`monitor`) and replay events (`replay`).
     pub category: DataCategory,
 
     /// The Metric Resource Identifier (MRI) of the metric to extract.
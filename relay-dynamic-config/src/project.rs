@@ -1,7 +1,7 @@
 use relay_auth::PublicKey;
 use relay_event_normalization::{
-    BreakdownsConfig, MeasurementsConfig, PerformanceScoreConfig, SpanDescriptionRule,
-    TransactionNameRule,
+    BreakdownsConfig, ClockDriftConfig, MeasurementsConfig, ModelCosts, PerformanceScoreConfig,
+    SpanDescriptionNormalizationRule, SpanDescriptionRule, TransactionNameRule,
 };
 use relay_filter::ProjectFiltersConfig;
 use relay_pii::{DataScrubbingConfig, PiiConfig};
@@ -16,6 +16,7 @@ use crate::metrics::{
     self, MetricExtractionConfig, Metrics, SessionMetricsConfig, TaggingRule,
     TransactionMetricsConfig,
 };
+use crate::trimming::TrimmingConfig;
 use crate::{GRADUATED_FEATURE_FLAGS, defaults};
 
 /// Dynamic, per-DSN configuration passed down from Sentry.
@@ -83,6 +84,24 @@ pub struct ProjectConfig {
     /// relays that might still need them.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub span_description_rules: Option<Vec<SpanDescriptionRule>>,
+    /// Rules for normalizing span descriptions, applied before the built-in scrubbers.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub span_description_normalization_rules: Vec<SpanDescriptionNormalizationRule>,
+    /// Policy for correcting clock drift between SDKs and Relay.
+    ///
+    /// If not present, Relay corrects drift of events and sessions above a default threshold.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_drift: Option<ClockDriftConfig>,
+    /// Project-specific size limits for trimming events.
+    ///
+    /// If not present, events are trimmed according to the limits in the event schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trimming: Option<TrimmingConfig>,
+    /// Project-specific costs of AI models, such as negotiated rates.
+    ///
+    /// These take precedence over the AI model costs in the global config.
+    #[serde(default, skip_serializing_if = "skip_ai_model_costs")]
+    pub ai_model_costs: ErrorBoundary<ModelCosts>,
     /// Configuration for metrics.
     #[serde(default, skip_serializing_if = "skip_metrics")]
     pub metrics: ErrorBoundary<Metrics>,
@@ -146,6 +165,10 @@ impl Default for ProjectConfig {
             tx_name_rules: Vec::new(),
             tx_name_ready: false,
             span_description_rules: None,
+            span_description_normalization_rules: Vec::new(),
+            clock_drift: None,
+            trimming: None,
+            ai_model_costs: Default::default(),
             metrics: Default::default(),
         }
     }
@@ -158,6 +181,13 @@ fn skip_metrics_extraction(boundary: &ErrorBoundary<MetricExtractionConfig>) ->
     }
 }
 
+fn skip_ai_model_costs(boundary: &ErrorBoundary<ModelCosts>) -> bool {
+    match boundary {
+        ErrorBoundary::Err(_) => true,
+        ErrorBoundary::Ok(model_costs) => model_costs.is_empty(),
+    }
+}
+
 fn skip_metrics(boundary: &ErrorBoundary<Metrics>) -> bool {
     match boundary {
         ErrorBoundary::Err(_) => true,
@@ -208,6 +238,15 @@ pub struct LimitedProjectConfig {
     /// relays that might still need them.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub span_description_rules: Option<Vec<SpanDescriptionRule>>,
+    /// Rules for normalizing span descriptions, applied before the built-in scrubbers.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub span_description_normalization_rules: Vec<SpanDescriptionNormalizationRule>,
+    /// Policy for correcting clock drift between SDKs and Relay.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_drift: Option<ClockDriftConfig>,
+    /// Project-specific size limits for trimming events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trimming: Option<TrimmingConfig>,
 }
 
 fn is_false(value: &bool) -> bool {
@@ -227,4 +266,15 @@ mod tests {
         project_config.sanitize();
         assert!(project_config.features.has(Feature::UserReportV2Ingest));
     }
+
+    #[test]
+    fn ai_model_cost_patterns_roundtrip() {
+        let json = r#"{"aiModelCosts":{"version":2,"patterns":[{"modelId":{"type":"glob","value":"claude-*"},"inputPerToken":1.0,"outputPerToken":2.0,"outputReasoningPerToken":0.0,"inputCachedPerToken":0.0}]}}"#;
+        let config: ProjectConfig = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_value(&config).unwrap();
+
+        let patterns = &serialized["aiModelCosts"]["patterns"];
+        assert_eq!(patterns[0]["modelId"]["value"], "claude-*");
+        assert_eq!(patterns[0]["inputPerToken"], 1.0);
+    }
 }
@@ -0,0 +1,278 @@
+//! Per-project configuration for trimming events.
+
+use std::sync::LazyLock;
+
+use relay_event_normalization::{TrimmingLimits, TrimmingPolicy};
+use relay_event_schema::processor::ProcessingState;
+use relay_pii::SelectorSpec;
+use serde::{Deserialize, Serialize};
+
+/// Fields dropped from events exceeding [`TrimmingConfig::max_event_bytes`], in order.
+///
+/// Used if the config does not declare a [`TrimmingConfig::drop_order`].
+static DEFAULT_DROP_ORDER: LazyLock<Vec<SelectorSpec>> = LazyLock::new(|| {
+    [
+        "extra",
+        "breadcrumbs",
+        "request.data",
+        "contexts.* && !contexts.trace",
+    ]
+    .iter()
+    .map(|s| s.parse().unwrap())
+    .collect()
+});
+
+/// Limits for fields matching a selector, overriding the limits declared in the event schema.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimmingRule {
+    /// A PII-style selector for the fields this rule applies to, for example `extra.*`.
+    pub selector: SelectorSpec,
+    /// The maximum size in bytes of matching databags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+    /// The maximum depth of matching databags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    /// The maximum number of characters of matching strings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_chars: Option<usize>,
+}
+
+/// Per-project configuration for trimming events.
+///
+/// Selectors match regardless of the PII attributes of fields, but fields that can never be
+/// trimmed, such as the event ID, are never affected.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TrimmingConfig {
+    /// The maximum size of the entire event in bytes.
+    ///
+    /// If the event exceeds this budget after trimming, fields are dropped in the order declared
+    /// in [`drop_order`](Self::drop_order) until the event fits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_event_bytes: Option<usize>,
+    /// Limits for specific fields.
+    ///
+    /// If multiple rules match a field, the first one applies.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<TrimmingRule>,
+    /// Selectors of fields to drop first if the event exceeds its budget.
+    ///
+    /// Defaults to extra data, breadcrumbs, request bodies, and contexts other than the trace
+    /// context.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub drop_order: Vec<SelectorSpec>,
+}
+
+impl TrimmingConfig {
+    fn drop_order(&self) -> &[SelectorSpec] {
+        match self.drop_order.is_empty() {
+            true => &DEFAULT_DROP_ORDER,
+            false => &self.drop_order,
+        }
+    }
+}
+
+impl TrimmingPolicy for TrimmingConfig {
+    fn limits(&self, state: &ProcessingState<'_>) -> Option<TrimmingLimits> {
+        if self.rules.is_empty() {
+            return None;
+        }
+
+        let path = state.path();
+        let rule = self
+            .rules
+            .iter()
+            .find(|rule| rule.selector.matches_path_ignoring_pii(&path))?;
+
+        Some(TrimmingLimits {
+            max_bytes: rule.max_bytes,
+            max_depth: rule.max_depth,
+            max_chars: rule.max_chars,
+        })
+    }
+
+    fn max_event_bytes(&self) -> Option<usize> {
+        self.max_event_bytes
+    }
+
+    fn drop_steps(&self) -> usize {
+        self.drop_order().len()
+    }
+
+    fn drops(&self, step: usize, state: &ProcessingState<'_>) -> bool {
+        self.drop_order()
+            .get(step)
+            .is_some_and(|selector| selector.matches_path_ignoring_pii(&state.path()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_event_normalization::TrimmingProcessor;
+    use relay_event_schema::processor;
+    use relay_event_schema::protocol::{Event, ExtraValue, TraceContext};
+    use relay_protocol::{Annotated, Object, RemarkType, Value, get_value};
+
+    use super::*;
+
+    fn trimming_config(json: &str) -> TrimmingConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_config_rule_max_bytes() {
+        let config = trimming_config(r#"{"rules": [{"selector": "extra.*", "maxBytes": 65536}]}"#);
+
+        let mut extra = Object::new();
+        extra.insert(
+            "large".to_owned(),
+            Annotated::new(ExtraValue(Value::String("x".repeat(30_000)))),
+        );
+        let mut event = Annotated::new(Event {
+            extra: Annotated::new(extra),
+            ..Default::default()
+        });
+
+        // Without the rule, the value exceeds the schema limit of 16KiB.
+        let mut trimmed = event.clone();
+        let mut processor = TrimmingProcessor::new();
+        processor::process_value(&mut trimmed, &mut processor, ProcessingState::root()).unwrap();
+        let value = get_value!(trimmed.extra["large"]!);
+        assert!(value.0.as_str().unwrap().len() < 30_000);
+
+        let mut processor = TrimmingProcessor::with_policy(&config);
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+        let value = get_value!(event.extra["large"]!);
+        assert_eq!(value.0.as_str().unwrap().len(), 30_000);
+    }
+
+    #[test]
+    fn test_config_rule_raises_enclosing_limit() {
+        let config =
+            trimming_config(r#"{"rules": [{"selector": "request.data.body", "maxBytes": 32768}]}"#);
+
+        let json = format!(
+            r#"{{"request": {{"data": {{"body": "{}", "other": "{}"}}}}}}"#,
+            "x".repeat(20_000),
+            "y".repeat(20_000),
+        );
+        let mut event = Annotated::<Event>::from_json(&json).unwrap();
+
+        let mut processor = TrimmingProcessor::with_policy(&config);
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let Value::Object(data) = get_value!(event.request.data!) else {
+            panic!("request data must be an object");
+        };
+
+        // The rule replaces the schema limit of 8KiB for `request.data`.
+        let body = data["body"].as_str().unwrap();
+        assert_eq!(body.len(), 20_000);
+
+        // Other fields are still subject to the schema limit.
+        let other = data.get("other").and_then(|other| other.as_str());
+        assert!(other.is_none_or(|other| other.len() < 20_000));
+    }
+
+    #[test]
+    fn test_config_rule_max_chars() {
+        let config = trimming_config(r#"{"rules": [{"selector": "logger", "maxChars": 10}]}"#);
+
+        let mut event = Annotated::new(Event {
+            logger: Annotated::new("x".repeat(20)),
+            ..Default::default()
+        });
+
+        let mut processor = TrimmingProcessor::with_policy(&config);
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let logger = &event.value().unwrap().logger;
+        assert_eq!(logger.as_str(), Some("xxxxxxx..."));
+        assert_eq!(logger.meta().original_length(), Some(20));
+    }
+
+    #[test]
+    fn test_config_rule_max_depth() {
+        let config = trimming_config(r#"{"rules": [{"selector": "extra.*", "maxDepth": 2}]}"#);
+
+        let value = Annotated::<Value>::from_json(r#"{"a": {"b": {"c": "d"}}}"#).unwrap();
+        let mut extra = Object::new();
+        extra.insert("nested".to_owned(), value.map_value(ExtraValue));
+        let mut event = Annotated::new(Event {
+            extra: Annotated::new(extra),
+            ..Default::default()
+        });
+
+        let mut processor = TrimmingProcessor::with_policy(&config);
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let nested = event.value().unwrap().extra.value().unwrap()["nested"].clone();
+        assert_eq!(
+            nested.to_json().unwrap(),
+            r#"{"a":"{\"b\":{\"c\":\"d\"}}"}"#
+        );
+    }
+
+    #[test]
+    fn test_config_event_budget() {
+        let config = trimming_config(
+            r#"{"maxEventBytes": 1000, "dropOrder": ["breadcrumbs", "extra", "logger"]}"#,
+        );
+
+        let mut event = Annotated::<Event>::from_json(&format!(
+            r#"{{
+                "logger": "my.logger",
+                "breadcrumbs": {{"values": [{{"message": "{}"}}]}},
+                "extra": {{"data": "{}"}}
+            }}"#,
+            "b".repeat(1500),
+            "e".repeat(1500),
+        ))
+        .unwrap();
+
+        let mut processor = TrimmingProcessor::with_policy(&config);
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        // Breadcrumbs and extra are dropped in order until the event fits.
+        let event = event.value().unwrap();
+        assert!(event.breadcrumbs.value().is_none());
+        assert!(event.extra.value().is_none());
+        assert_eq!(event.logger.as_str(), Some("my.logger"));
+
+        let remark = event.extra.meta().iter_remarks().next().unwrap();
+        assert_eq!(remark.rule_id(), "!limit");
+        assert_eq!(remark.ty(), RemarkType::Removed);
+    }
+
+    #[test]
+    fn test_config_event_budget_default_order() {
+        let config = trimming_config(r#"{"maxEventBytes": 1000}"#);
+
+        let mut event = Annotated::<Event>::from_json(&format!(
+            r#"{{
+                "event_id": "52df9022835246eeb317dbd739ccd059",
+                "extra": {{"data": "{}"}},
+                "contexts": {{
+                    "custom": {{"data": "{}"}},
+                    "trace": {{"trace_id": "4c79f60c11214eb38604f4ae0781bfb2", "span_id": "fa90fdead5f74053"}}
+                }}
+            }}"#,
+            "e".repeat(800),
+            "c".repeat(800),
+        ))
+        .unwrap();
+
+        let mut processor = TrimmingProcessor::with_policy(&config);
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let event = event.value().unwrap();
+        assert!(event.id.value().is_some());
+        assert!(event.extra.value().is_none());
+
+        let contexts = event.contexts.value().unwrap();
+        assert!(contexts.get_key("custom").is_none());
+        assert!(contexts.get::<TraceContext>().is_some());
+    }
+}
@@ -1,10 +1,79 @@
+use std::collections::BTreeSet;
 use std::time::Duration;
 
 use chrono::{DateTime, Duration as SignedDuration, Utc};
 use relay_common::time::UnixTimestamp;
 use relay_event_schema::processor::{ProcessValue, ProcessingResult, ProcessingState, Processor};
-use relay_event_schema::protocol::{Event, Timestamp};
-use relay_protocol::{Error, ErrorKind, Meta};
+use relay_event_schema::protocol::{ClockDriftContext, Contexts, Event, Timestamp};
+use relay_protocol::{Annotated, Error, ErrorKind, Meta};
+use serde::{Deserialize, Serialize};
+
+/// The default minimum clock drift that is corrected.
+///
+/// This compensates for network latency and small clock drift on the sender's machine, but allows
+/// to detect timezone differences.
+pub const DEFAULT_CLOCK_DRIFT_THRESHOLD: Duration = Duration::from_secs(55 * 60);
+
+/// What to do with timestamps once clock drift has been detected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockDriftMode {
+    /// Shift all timestamps by the detected drift.
+    #[default]
+    Correct,
+    /// Leave timestamps unchanged and only record the detected drift.
+    Annotate,
+}
+
+/// Item types to which a [`ClockDriftConfig`] can apply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockDriftItemType {
+    /// Errors, transactions and other events.
+    Event,
+    /// Standalone spans.
+    Span,
+    /// Logs.
+    Log,
+    /// Sessions and session aggregates.
+    Session,
+}
+
+/// Per-project policy for clock drift correction.
+///
+/// Without a policy, Relay corrects the timestamps of events and sessions once the drift exceeds
+/// [`DEFAULT_CLOCK_DRIFT_THRESHOLD`]. A policy replaces this behavior for the configured item
+/// types, and additionally allows to handle drift on standalone spans and logs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ClockDriftConfig {
+    /// Minimum drift in seconds before the policy applies.
+    pub threshold: u64,
+    /// Whether to correct timestamps or only annotate them.
+    pub mode: ClockDriftMode,
+    /// Item types this policy applies to.
+    ///
+    /// If empty, the policy applies to all item types.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub item_types: BTreeSet<ClockDriftItemType>,
+}
+
+impl ClockDriftConfig {
+    /// Returns `true` if this policy applies to the given item type.
+    pub fn applies_to(&self, item_type: ClockDriftItemType) -> bool {
+        self.item_types.is_empty() || self.item_types.contains(&item_type)
+    }
+}
+
+impl Default for ClockDriftConfig {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_CLOCK_DRIFT_THRESHOLD.as_secs(),
+            mode: ClockDriftMode::default(),
+            item_types: BTreeSet::new(),
+        }
+    }
+}
 
 /// A signed correction that contains the sender's timestamp as well as the drift to the receiver.
 #[derive(Clone, Copy, Debug)]
@@ -19,12 +88,8 @@ impl ClockCorrection {
         Self { sent_at, drift }
     }
 
-    fn at_least(self, lower_bound: Duration) -> Option<Self> {
-        if self.drift.num_seconds().unsigned_abs() >= lower_bound.as_secs() {
-            Some(self)
-        } else {
-            None
-        }
+    fn exceeds(&self, lower_bound: Duration) -> bool {
+        self.drift.num_seconds().unsigned_abs() >= lower_bound.as_secs()
     }
 }
 
@@ -33,9 +98,9 @@ impl ClockCorrection {
 /// Clock drift correction applies to all timestamps in the event protocol. This includes especially
 /// the event's timestamp, breadcrumbs and spans.
 ///
-/// There is a minimum clock drift of _55 minutes_ to compensate for network latency and small clock
-/// drift on the sender's machine, but allow to detect timezone differences. For differences lower
-/// than that, no correction is performed.
+/// A minimum clock drift can be configured with [`at_least`](Self::at_least). Relay uses
+/// [`DEFAULT_CLOCK_DRIFT_THRESHOLD`] unless a project configures a [`ClockDriftConfig`]. For
+/// differences lower than that, no correction is performed.
 ///
 /// Clock drift is corrected in both ways:
 ///
@@ -46,10 +111,16 @@ impl ClockCorrection {
 /// - The drift is subtracted from timestamps if the received time is before the send time. This
 ///   indicates that the sender's clock was running ahead. For instance, if an event was received
 ///   with tomorrow's timestamp, one day is subtracted from all timestamps.
+///
+/// When clock drift is detected, the event receives a [`ClockDriftContext`] with the original
+/// timestamp, the corrected timestamp, the drift, and whether the correction was applied. In
+/// [`ClockDriftMode::Annotate`], timestamps are left unchanged and the drift is only recorded.
 #[derive(Debug)]
 pub struct ClockDriftProcessor {
     received_at: DateTime<Utc>,
     correction: Option<ClockCorrection>,
+    lower_bound: Duration,
+    mode: ClockDriftMode,
     kind: ErrorKind,
 }
 
@@ -64,6 +135,8 @@ impl ClockDriftProcessor {
         Self {
             received_at,
             correction,
+            lower_bound: Duration::ZERO,
+            mode: ClockDriftMode::Correct,
             kind: ErrorKind::ClockDrift,
         }
     }
@@ -74,7 +147,23 @@ impl ClockDriftProcessor {
     /// `is_drifted` returns `false`. By default, there is no lower bound and every drift is
     /// corrected.
     pub fn at_least(mut self, lower_bound: Duration) -> Self {
-        self.correction = self.correction.and_then(|c| c.at_least(lower_bound));
+        self.lower_bound = lower_bound;
+        self
+    }
+
+    /// Applies the project's clock drift policy for the given item type.
+    ///
+    /// The policy's threshold replaces the lower bound set by [`at_least`](Self::at_least). If
+    /// there is no policy or it does not apply to the item type, the processor is unchanged.
+    pub fn policy(
+        mut self,
+        config: Option<&ClockDriftConfig>,
+        item_type: ClockDriftItemType,
+    ) -> Self {
+        if let Some(config) = config.filter(|c| c.applies_to(item_type)) {
+            self.lower_bound = Duration::from_secs(config.threshold);
+            self.mode = config.mode;
+        }
         self
     }
 
@@ -86,13 +175,38 @@ impl ClockDriftProcessor {
     }
 
     /// Returns `true` if the clocks are significantly drifted.
+    ///
+    /// This is independent of the [`ClockDriftMode`]. Use [`is_correcting`](Self::is_correcting)
+    /// to check whether timestamps are modified.
     pub fn is_drifted(&self) -> bool {
-        self.correction.is_some()
+        self.correction().is_some()
+    }
+
+    /// Returns `true` if the clocks are significantly drifted and timestamps are corrected.
+    pub fn is_correcting(&self) -> bool {
+        self.mode == ClockDriftMode::Correct && self.is_drifted()
+    }
+
+    /// Returns the detected clock drift if it exceeds the lower bound.
+    pub fn drift(&self) -> Option<SignedDuration> {
+        self.correction().map(|c| c.drift)
+    }
+
+    fn correction(&self) -> Option<ClockCorrection> {
+        self.correction.filter(|c| c.exceeds(self.lower_bound))
+    }
+
+    /// Returns the correction to apply to timestamps, if any.
+    fn applied_correction(&self) -> Option<ClockCorrection> {
+        match self.mode {
+            ClockDriftMode::Correct => self.correction(),
+            ClockDriftMode::Annotate => None,
+        }
     }
 
     /// Processes the given `UnixTimestamp` by applying clock drift correction.
     pub fn process_timestamp(&self, timestamp: &mut UnixTimestamp) {
-        if let Some(correction) = self.correction {
+        if let Some(correction) = self.applied_correction() {
             let secs = correction.drift.num_seconds();
             *timestamp = if secs > 0 {
                 UnixTimestamp::from_secs(timestamp.as_secs() + secs as u64)
@@ -104,10 +218,29 @@ impl ClockDriftProcessor {
 
     /// Processes the given [`DateTime`].
     pub fn process_datetime(&self, datetime: &mut DateTime<Utc>) {
-        if let Some(correction) = self.correction {
+        if let Some(correction) = self.applied_correction() {
             *datetime += correction.drift;
         }
     }
+
+    /// Adds the clock drift error to the given timestamp's meta.
+    fn add_error(&self, meta: &mut Meta, correction: ClockCorrection) {
+        meta.add_error(Error::with(self.kind.clone(), |e| {
+            e.insert("sdk_time", correction.sent_at.to_rfc3339());
+            e.insert("server_time", self.received_at.to_rfc3339());
+        }));
+    }
+
+    /// Annotates the timestamp of a standalone item, such as a span or a log.
+    ///
+    /// Unlike events, these items have no context to store the drift in. Instead, the sender's and
+    /// receiver's time are recorded in the meta of the timestamp. Apply the processor to the item
+    /// before calling this to correct its timestamps.
+    pub fn annotate(&self, timestamp: &mut Annotated<Timestamp>) {
+        if let Some(correction) = self.correction() {
+            self.add_error(timestamp.meta_mut(), correction);
+        }
+    }
 }
 
 impl Processor for ClockDriftProcessor {
@@ -117,15 +250,44 @@ impl Processor for ClockDriftProcessor {
         _meta: &mut Meta,
         state: &ProcessingState<'_>,
     ) -> ProcessingResult {
-        if let Some(correction) = self.correction {
-            event.process_child_values(self, state)?;
+        let Some(correction) = self.correction() else {
+            return Ok(());
+        };
 
-            let timestamp_meta = event.timestamp.meta_mut();
-            timestamp_meta.add_error(Error::with(self.kind.clone(), |e| {
-                e.insert("sdk_time", correction.sent_at.to_rfc3339());
-                e.insert("server_time", self.received_at.to_rfc3339());
-            }));
+        // Timestamps recorded in an existing clock drift context must not be shifted again.
+        let existing = event
+            .contexts
+            .value_mut()
+            .as_mut()
+            .and_then(|contexts| contexts.remove::<ClockDriftContext>());
+
+        let original_timestamp = event.timestamp.value().copied();
+
+        let applied = self.applied_correction().is_some();
+        if applied {
+            event.process_child_values(self, state)?;
         }
+        self.add_error(event.timestamp.meta_mut(), correction);
+
+        let context = match existing {
+            Some(context) => context,
+            // Only record clock drift, and not timestamps clamped by validation.
+            None if self.kind == ErrorKind::ClockDrift => ClockDriftContext {
+                original_timestamp: Annotated::from(original_timestamp),
+                corrected_timestamp: Annotated::from(
+                    original_timestamp.map(|t| t + correction.drift),
+                ),
+                drift: Annotated::new(correction.drift.num_milliseconds() as f64 / 1000.0),
+                corrected: Annotated::new(applied),
+                ..Default::default()
+            },
+            None => return Ok(()),
+        };
+
+        event
+            .contexts
+            .get_or_insert_with(Contexts::new)
+            .add(context);
 
         Ok(())
     }
@@ -136,7 +298,7 @@ impl Processor for ClockDriftProcessor {
         _meta: &mut Meta,
         _state: &ProcessingState<'_>,
     ) -> ProcessingResult {
-        if let Some(correction) = self.correction {
+        if let Some(correction) = self.applied_correction() {
             // NB: We're not setting the original value here, as this could considerably increase
             // the event's size. Instead, attach an error message to the top-level event.
             *timestamp = *timestamp + correction.drift;
@@ -292,4 +454,116 @@ mod tests {
             Utc.with_ymd_and_hms(2021, 11, 30, 0, 0, 0).unwrap()
         );
     }
+
+    #[test]
+    fn test_clock_drift_context() {
+        let start = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2000, 1, 2, 0, 0, 0).unwrap();
+
+        let drift = SignedDuration::days(1);
+        let now = end + drift;
+
+        let mut processor = ClockDriftProcessor::new(Some(end), now);
+        let mut event = create_transaction(start, end);
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let context = event
+            .value()
+            .unwrap()
+            .context::<ClockDriftContext>()
+            .unwrap();
+        assert_eq!(context.original_timestamp.value().unwrap(), &end);
+        assert_eq!(context.corrected_timestamp.value().unwrap(), &now);
+        assert_eq!(context.drift.value(), Some(&86400.0));
+        assert_eq!(context.corrected.value(), Some(&true));
+
+        // Processing the event again does not shift the recorded timestamps.
+        let mut processor = ClockDriftProcessor::new(Some(end), now);
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let context = event
+            .value()
+            .unwrap()
+            .context::<ClockDriftContext>()
+            .unwrap();
+        assert_eq!(context.original_timestamp.value().unwrap(), &end);
+        assert_eq!(context.corrected_timestamp.value().unwrap(), &now);
+    }
+
+    #[test]
+    fn test_clock_drift_annotate() {
+        let start = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2000, 1, 2, 0, 0, 0).unwrap();
+
+        let drift = SignedDuration::days(1);
+        let now = end + drift;
+
+        let config = ClockDriftConfig {
+            mode: ClockDriftMode::Annotate,
+            ..Default::default()
+        };
+        let mut processor = ClockDriftProcessor::new(Some(end), now)
+            .policy(Some(&config), ClockDriftItemType::Event);
+        assert!(processor.is_drifted());
+        assert!(!processor.is_correcting());
+
+        let mut event = create_transaction(start, end);
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let event = event.value().unwrap();
+        assert_eq!(*event.timestamp.value().unwrap(), end);
+        assert_eq!(*event.start_timestamp.value().unwrap(), start);
+
+        // The drift is recorded, but not applied.
+        let context = event.context::<ClockDriftContext>().unwrap();
+        assert_eq!(context.original_timestamp.value().unwrap(), &end);
+        assert_eq!(context.corrected_timestamp.value().unwrap(), &now);
+        assert_eq!(context.drift.value(), Some(&86400.0));
+        assert_eq!(context.corrected.value(), Some(&false));
+        assert!(event.timestamp.meta().has_errors());
+
+        let mut datetime = start;
+        processor.process_datetime(&mut datetime);
+        assert_eq!(datetime, start);
+    }
+
+    #[test]
+    fn test_clock_drift_policy_threshold() {
+        let sent_at = Utc.with_ymd_and_hms(2000, 1, 2, 0, 0, 0).unwrap();
+        let now = sent_at + SignedDuration::minutes(10);
+
+        let config = ClockDriftConfig {
+            threshold: 300,
+            item_types: [ClockDriftItemType::Session].into(),
+            ..Default::default()
+        };
+
+        // The policy lowers the default threshold for sessions.
+        let processor = ClockDriftProcessor::new(Some(sent_at), now)
+            .at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD)
+            .policy(Some(&config), ClockDriftItemType::Session);
+        assert!(processor.is_correcting());
+        assert_eq!(processor.drift(), Some(SignedDuration::minutes(10)));
+
+        // Events are not covered by the policy and keep the default threshold.
+        let processor = ClockDriftProcessor::new(Some(sent_at), now)
+            .at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD)
+            .policy(Some(&config), ClockDriftItemType::Event);
+        assert!(!processor.is_drifted());
+    }
+
+    #[test]
+    fn test_clock_drift_config_serde() {
+        let json = r#"{"threshold": 86400, "mode": "annotate", "itemTypes": ["log", "span"]}"#;
+        let config: ClockDriftConfig = serde_json::from_str(json).unwrap();
+
+        assert_eq!(config.threshold, 86400);
+        assert_eq!(config.mode, ClockDriftMode::Annotate);
+        assert!(config.applies_to(ClockDriftItemType::Log));
+        assert!(!config.applies_to(ClockDriftItemType::Event));
+
+        let config: ClockDriftConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, ClockDriftConfig::default());
+        assert!(config.applies_to(ClockDriftItemType::Event));
+    }
 }
@@ -33,9 +33,9 @@ use crate::span::tag_extraction::extract_span_tags_from_event;
 use crate::utils::{self, MAX_DURATION_MOBILE_MS, get_event_user_tag};
 use crate::{
     BorrowedSpanOpDefaults, BreakdownsConfig, CombinedMeasurementsConfig, GeoIpLookup, MaxChars,
-    ModelCosts, PerformanceScoreConfig, RawUserAgentInfo, SpanDescriptionRule,
-    TransactionNameConfig, breakdowns, event_error, legacy, mechanism, remove_other, schema, span,
-    stacktrace, transactions, trimming, user_agent,
+    ModelCosts, PerformanceScoreConfig, RawUserAgentInfo, SpanDescriptionNormalizationRule,
+    SpanDescriptionRule, TransactionNameConfig, TrimmingPolicy, breakdowns, event_error, legacy,
+    mechanism, remove_other, schema, span, stacktrace, transactions, trimming, user_agent,
 };
 
 /// Configuration for [`normalize_event`].
@@ -134,12 +134,22 @@ pub struct NormalizationConfig<'a> {
     /// This is similar to `transaction_name_config`, but applies to span descriptions.
     pub span_description_rules: Option<&'a Vec<SpanDescriptionRule>>,
 
+    /// Project-defined rules for normalizing span descriptions.
+    ///
+    /// These rules take precedence over the built-in scrubbers during span tag extraction.
+    pub span_description_normalization_rules: &'a [SpanDescriptionNormalizationRule],
+
     /// Configuration for generating performance score measurements for web vitals
     pub performance_score: Option<&'a PerformanceScoreConfig>,
 
     /// Configuration for calculating the cost of AI model runs
     pub ai_model_costs: Option<&'a ModelCosts>,
 
+    /// Project- or organization-specific costs of AI models, in order of precedence.
+    ///
+    /// These take precedence over `ai_model_costs`.
+    pub ai_model_cost_overrides: &'a [&'a ModelCosts],
+
     /// An initialized GeoIP lookup.
     pub geoip_lookup: Option<&'a GeoIpLookup>,
 
@@ -148,6 +158,11 @@ pub struct NormalizationConfig<'a> {
     /// See the event schema for size declarations.
     pub enable_trimming: bool,
 
+    /// Project-specific limits applied during trimming.
+    ///
+    /// Only takes effect if `enable_trimming` is set.
+    pub trimming: Option<&'a dyn TrimmingPolicy>,
+
     /// Controls whether spans should be normalized (e.g. normalizing the exclusive time).
     ///
     /// To normalize spans, `is_renormalize` must be disabled _and_ `normalize_spans` enabled.
@@ -191,10 +206,13 @@ impl Default for NormalizationConfig<'_> {
             enrich_spans: Default::default(),
             max_tag_value_length: usize::MAX,
             span_description_rules: Default::default(),
+            span_description_normalization_rules: Default::default(),
             performance_score: Default::default(),
             geoip_lookup: Default::default(),
             ai_model_costs: Default::default(),
+            ai_model_cost_overrides: Default::default(),
             enable_trimming: false,
+            trimming: None,
             measurements: None,
             normalize_spans: true,
             replay_id: Default::default(),
@@ -228,8 +246,11 @@ pub fn normalize_event(event: &mut Annotated<Event>, config: &NormalizationConfi
 
     if config.enable_trimming {
         // Trim large strings and databags down
-        let _ =
-            trimming::TrimmingProcessor::new().process_event(event, meta, ProcessingState::root());
+        let mut processor = match config.trimming {
+            Some(trimming) => trimming::TrimmingProcessor::with_policy(trimming),
+            None => trimming::TrimmingProcessor::new(),
+        };
+        let _ = processor.process_event(event, meta, ProcessingState::root());
     }
 
     if config.remove_other {
@@ -323,7 +344,7 @@ fn normalize(event: &mut Event, meta: &mut Meta, config: &NormalizationConfig) {
             .get_or_default::<PerformanceScoreContext>()
             .score_profile_version = Annotated::new(version);
     }
-    enrich_ai_span_data(event, config.ai_model_costs);
+    enrich_ai_span_data(event, config.ai_model_costs, config.ai_model_cost_overrides);
     normalize_breakdowns(event, config.breakdowns_config); // Breakdowns are part of the metric extraction too
     normalize_default_attributes(event, meta, config);
     normalize_trace_context_tags(event);
@@ -351,6 +372,7 @@ fn normalize(event: &mut Event, meta: &mut Meta, config: &NormalizationConfig) {
             event,
             config.max_tag_value_length,
             config.span_allowed_hosts,
+            config.span_description_normalization_rules,
         );
     }
 
@@ -2273,6 +2295,7 @@ mod tests {
                                 output_per_token: 0.02,
                                 output_reasoning_per_token: 0.03,
                                 input_cached_per_token: 0.0,
+                                ..Default::default()
                             },
                         ),
                         (
@@ -2282,9 +2305,11 @@ mod tests {
                                 output_per_token: 0.03,
                                 output_reasoning_per_token: 0.04,
                                 input_cached_per_token: 0.0,
+                                ..Default::default()
                             },
                         ),
                     ]),
+                    ..Default::default()
                 }),
                 ..NormalizationConfig::default()
             },
@@ -2365,6 +2390,7 @@ mod tests {
                                 output_per_token: 0.02,
                                 output_reasoning_per_token: 0.03,
                                 input_cached_per_token: 0.0,
+                                ..Default::default()
                             },
                         ),
                         (
@@ -2374,9 +2400,11 @@ mod tests {
                                 output_per_token: 0.05,
                                 output_reasoning_per_token: 0.06,
                                 input_cached_per_token: 0.0,
+                                ..Default::default()
                             },
                         ),
                     ]),
+                    ..Default::default()
                 }),
                 ..NormalizationConfig::default()
             },
@@ -2446,8 +2474,10 @@ mod tests {
                             output_per_token: 0.02,
                             output_reasoning_per_token: 0.03,
                             input_cached_per_token: 0.0,
+                            ..Default::default()
                         },
                     )]),
+                    ..Default::default()
                 }),
                 ..NormalizationConfig::default()
             },
@@ -2531,6 +2561,7 @@ mod tests {
                                 output_per_token: 0.02,
                                 output_reasoning_per_token: 0.03,
                                 input_cached_per_token: 0.0,
+                                ..Default::default()
                             },
                         ),
                         (
@@ -2540,9 +2571,11 @@ mod tests {
                                 output_per_token: 0.05,
                                 output_reasoning_per_token: 0.06,
                                 input_cached_per_token: 0.0,
+                                ..Default::default()
                             },
                         ),
                     ]),
+                    ..Default::default()
                 }),
                 ..NormalizationConfig::default()
             },
@@ -2576,6 +2609,142 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ai_data_with_overrides_and_units() {
+        let json = r#"
+            {
+                "spans": [
+                    {
+                        "timestamp": 1702474613.0495,
+                        "start_timestamp": 1702474613.0175,
+                        "op": "gen_ai.chat",
+                        "span_id": "9c01bd820a083e63",
+                        "parent_span_id": "a1e13f3f06239d69",
+                        "trace_id": "922dda2462ea4ac2b6a4b339bee90863",
+                        "data": {
+                            "gen_ai.usage.input_tokens": 1000,
+                            "gen_ai.usage.input_tokens.cache_write": 100,
+                            "gen_ai.usage.output_tokens": 2000,
+                            "gen_ai.usage.images": 2,
+                            "gen_ai.usage.tool_calls": 3,
+                            "gen_ai.request.model": "claude-2.1-20240620"
+                        }
+                    },
+                    {
+                        "timestamp": 1702474613.0495,
+                        "start_timestamp": 1702474613.0175,
+                        "op": "gen_ai.generate_speech",
+                        "span_id": "ac01bd820a083e63",
+                        "parent_span_id": "a1e13f3f06239d69",
+                        "trace_id": "922dda2462ea4ac2b6a4b339bee90863",
+                        "data": {
+                            "gen_ai.usage.audio_seconds": 30,
+                            "gen_ai.request.model": "tts-1"
+                        }
+                    }
+                ]
+            }
+        "#;
+
+        let mut event = Annotated::<Event>::from_json(json).unwrap();
+
+        let global_costs = ModelCosts {
+            version: 2,
+            models: HashMap::from([
+                (
+                    "claude-2.1".to_owned(),
+                    ModelCostV2 {
+                        input_per_token: 0.01,
+                        output_per_token: 0.02,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "tts-1".to_owned(),
+                    ModelCostV2 {
+                        per_audio_second: 0.5,
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let project_costs = ModelCosts {
+            version: 2,
+            models: HashMap::from([(
+                "claude-2.1".to_owned(),
+                ModelCostV2 {
+                    input_per_token: 0.005,
+                    output_per_token: 0.01,
+                    input_cache_write_per_token: 0.02,
+                    per_image: 1.0,
+                    per_tool_call: 0.5,
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let organization_costs = ModelCosts {
+            version: 2,
+            models: HashMap::from([
+                (
+                    "claude-2.1".to_owned(),
+                    ModelCostV2 {
+                        input_per_token: 1.0,
+                        output_per_token: 1.0,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "tts-1".to_owned(),
+                    ModelCostV2 {
+                        per_audio_second: 0.25,
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        normalize_event(
+            &mut event,
+            &NormalizationConfig {
+                ai_model_costs: Some(&global_costs),
+                ai_model_cost_overrides: &[&project_costs, &organization_costs],
+                ..NormalizationConfig::default()
+            },
+        );
+
+        let spans = event.value().unwrap().spans.value().unwrap();
+        let data = |index: usize| {
+            spans
+                .get(index)
+                .and_then(|span| span.value())
+                .and_then(|span| span.data.value())
+                .unwrap()
+        };
+
+        assert_eq!(
+            data(0).gen_ai_cost_input_tokens.value(),
+            Some(&Value::F64(7.0))
+        );
+        assert_eq!(
+            data(0).gen_ai_cost_output_tokens.value(),
+            Some(&Value::F64(20.0))
+        );
+        assert_eq!(
+            data(0).gen_ai_usage_total_cost.value(),
+            Some(&Value::F64(30.5))
+        );
+
+        assert_eq!(
+            data(1).gen_ai_usage_total_cost.value(),
+            Some(&Value::F64(7.5))
+        );
+    }
+
     #[test]
     fn test_apple_high_device_class() {
         let mut event = Event {
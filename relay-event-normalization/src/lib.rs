@@ -35,7 +35,7 @@ pub use remove_other::RemoveOtherProcessor;
 pub use schema::SchemaProcessor;
 pub use timestamp::TimestampProcessor;
 pub use transactions::*;
-pub use trimming::TrimmingProcessor;
+pub use trimming::{TrimmingLimits, TrimmingPolicy, TrimmingProcessor};
 pub use user_agent::*;
 
 pub use self::clock_drift::*;
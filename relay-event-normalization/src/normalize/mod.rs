@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 use std::hash::Hash;
 
+use once_cell::sync::Lazy;
+use regex::Regex;
 use relay_base_schema::metrics::MetricUnit;
 use relay_common::glob2::LazyGlob;
 use relay_event_schema::protocol::{Event, VALID_PLATFORMS};
 use relay_protocol::{FiniteF64, RuleCondition};
 use serde::{Deserialize, Serialize};
 
+use crate::LazyRegex;
+
 pub mod breakdowns;
 pub mod contexts;
 pub mod nel;
@@ -248,7 +252,17 @@ pub struct PerformanceScoreConfig {
 ///       "outputReasoningPerToken": 0.12,
 ///       "inputCachedPerToken": 0.015
 ///     }
-///   }
+///   },
+///   "patterns": [
+///     {
+///       "modelId": {"type": "glob", "value": "claude-3-5-sonnet-*"},
+///       "inputPerToken": 0.000003,
+///       "outputPerToken": 0.000015,
+///       "outputReasoningPerToken": 0.0,
+///       "inputCachedPerToken": 0.0000003,
+///       "inputCacheWritePerToken": 0.00000375
+///     }
+///   ]
 /// }
 /// ```
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -264,6 +278,13 @@ pub struct ModelCosts {
     /// The mappings of model ID => cost as a dictionary (version 2)
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub models: HashMap<String, ModelCostV2>,
+
+    /// Costs for model IDs matching a glob or regex pattern (version 2)
+    ///
+    /// Patterns are only consulted if there is no entry for the model ID in `models`, and are
+    /// evaluated in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<ModelCostPattern>,
 }
 
 impl ModelCosts {
@@ -271,7 +292,8 @@ impl ModelCosts {
 
     /// `true` if the model costs are empty and the version is supported.
     pub fn is_empty(&self) -> bool {
-        (self.costs.is_empty() && self.models.is_empty()) || !self.is_enabled()
+        (self.costs.is_empty() && self.models.is_empty() && self.patterns.is_empty())
+            || !self.is_enabled()
     }
 
     /// `false` if measurement and metrics extraction should be skipped.
@@ -294,17 +316,70 @@ impl ModelCosts {
                             .map_or(0.0, |c| c.cost_per_1k_tokens / 1000.0),
                         output_reasoning_per_token: 0.0, // in v1 this info is not available
                         input_cached_per_token: 0.0,     // in v1 this info is not available
+                        ..Default::default()
                     })
                 } else {
                     None
                 }
             }
-            2 => self.models.get(model_id).copied(),
+            2 => self
+                .models
+                .get(model_id)
+                .or_else(|| self.models.get(strip_model_version(model_id)?))
+                .or_else(|| {
+                    self.patterns
+                        .iter()
+                        .find(|pattern| pattern.model_id.matches(model_id))
+                        .map(|pattern| &pattern.cost)
+                })
+                .cloned(),
             _ => None,
         }
     }
 }
 
+/// Matches version suffixes of model IDs, such as `-2024-08-06`, `-20240620`, `-latest`,
+/// `@20240620` or `:beta`.
+static MODEL_VERSION_SUFFIX_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(-\d{4}-\d{2}-\d{2}|-\d{8}|-latest|@[\w.-]+|:[\w.-]+)$").unwrap());
+
+/// Returns the model ID without its version suffix, if it has one.
+fn strip_model_version(model_id: &str) -> Option<&str> {
+    let suffix = MODEL_VERSION_SUFFIX_REGEX.find(model_id)?;
+    Some(&model_id[..suffix.start()])
+}
+
+/// A pattern matching AI model IDs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ModelIdPattern {
+    /// A glob pattern that must match the entire model ID.
+    Glob(LazyGlob),
+    /// A regular expression that may match anywhere in the model ID.
+    Regex(LazyRegex),
+}
+
+impl ModelIdPattern {
+    /// `true` if the pattern matches the given model ID.
+    pub fn matches(&self, model_id: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob.compiled().is_match(model_id),
+            Self::Regex(regex) => regex.compiled().is_some_and(|r| r.is_match(model_id)),
+        }
+    }
+}
+
+/// Costs for all AI models with an ID matching a pattern.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCostPattern {
+    /// The pattern for model IDs.
+    pub model_id: ModelIdPattern,
+    /// The costs of the matching models.
+    #[serde(flatten)]
+    pub cost: ModelCostV2,
+}
+
 /// A mapping of AI model types (like GPT-4) to their respective costs.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -323,7 +398,7 @@ impl ModelCost {
 
 /// Version 2 of a mapping of AI model types (like GPT-4) to their respective costs.
 /// Version 1 had some limitations, so we're moving to a more flexible format.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelCostV2 {
     /// The cost per input token
@@ -334,6 +409,95 @@ pub struct ModelCostV2 {
     pub output_reasoning_per_token: f64,
     /// The cost per input cached token
     pub input_cached_per_token: f64,
+    /// The cost per input token written to the cache
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub input_cache_write_per_token: f64,
+    /// The cost per image
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub per_image: f64,
+    /// The cost per second of audio
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub per_audio_second: f64,
+    /// The cost per tool call
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub per_tool_call: f64,
+    /// Token costs that apply to requests with large context windows
+    ///
+    /// The tier with the highest `minInputTokens` not exceeding the number of input tokens
+    /// replaces the per-token costs it declares. Costs the tier leaves out fall back to the costs
+    /// of the model.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiers: Vec<ModelCostTier>,
+}
+
+impl ModelCostV2 {
+    /// Returns the token costs for a request with the given number of input tokens.
+    pub fn token_costs(&self, input_tokens: f64) -> TokenCosts {
+        let tier = self
+            .tiers
+            .iter()
+            .filter(|tier| tier.min_input_tokens as f64 <= input_tokens)
+            .max_by_key(|tier| tier.min_input_tokens);
+
+        let tier = tier.copied().unwrap_or_default();
+        TokenCosts {
+            input_per_token: tier.input_per_token.unwrap_or(self.input_per_token),
+            output_per_token: tier.output_per_token.unwrap_or(self.output_per_token),
+            output_reasoning_per_token: tier
+                .output_reasoning_per_token
+                .unwrap_or(self.output_reasoning_per_token),
+            input_cached_per_token: tier
+                .input_cached_per_token
+                .unwrap_or(self.input_cached_per_token),
+            input_cache_write_per_token: tier
+                .input_cache_write_per_token
+                .unwrap_or(self.input_cache_write_per_token),
+        }
+    }
+}
+
+/// Token costs for requests exceeding a number of input tokens.
+///
+/// Costs that are not declared fall back to the costs of the model.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCostTier {
+    /// The minimum number of input tokens for this tier to apply
+    pub min_input_tokens: u64,
+    /// The cost per input token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_per_token: Option<f64>,
+    /// The cost per output token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_per_token: Option<f64>,
+    /// The cost per output reasoning token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_reasoning_per_token: Option<f64>,
+    /// The cost per input cached token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cached_per_token: Option<f64>,
+    /// The cost per input token written to the cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cache_write_per_token: Option<f64>,
+}
+
+/// Costs of an AI model per token.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenCosts {
+    /// The cost per input token
+    pub input_per_token: f64,
+    /// The cost per output token
+    pub output_per_token: f64,
+    /// The cost per output reasoning token
+    pub output_reasoning_per_token: f64,
+    /// The cost per input cached token
+    pub input_cached_per_token: f64,
+    /// The cost per input token written to the cache
+    pub input_cache_write_per_token: f64,
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
 }
 
 #[cfg(test)]
@@ -382,6 +546,7 @@ mod tests {
                 },
             ],
             models: {},
+            patterns: [],
         }
         "#,
         );
@@ -401,8 +566,14 @@ mod tests {
                         output_per_token: 0.06,
                         output_reasoning_per_token: 0.12,
                         input_cached_per_token: 0.015,
+                        input_cache_write_per_token: 0.0,
+                        per_image: 0.0,
+                        per_audio_second: 0.0,
+                        per_tool_call: 0.0,
+                        tiers: [],
                     },
                 },
+                patterns: [],
             }
             "###,
         );
@@ -429,6 +600,7 @@ mod tests {
                 },
             ],
             models: {},
+            patterns: [],
         }
         "#);
 
@@ -450,8 +622,14 @@ mod tests {
                     output_per_token: 0.06,
                     output_reasoning_per_token: 0.12,
                     input_cached_per_token: 0.015,
+                    input_cache_write_per_token: 0.0,
+                    per_image: 0.0,
+                    per_audio_second: 0.0,
+                    per_tool_call: 0.0,
+                    tiers: [],
                 },
             },
+            patterns: [],
         }
         "###);
 
@@ -470,6 +648,7 @@ mod tests {
                 cost_per_1k_tokens: 0.03,
             }],
             models: HashMap::new(),
+            ..Default::default()
         };
         assert!(v1_config.is_enabled());
         let costs = v1_config.cost_per_token("gpt-4-turbo").unwrap();
@@ -494,6 +673,7 @@ mod tests {
                 },
             ],
             models: HashMap::new(),
+            ..Default::default()
         };
         assert!(v1_config.is_enabled());
         let costs = v1_config.cost_per_token("gpt-4").unwrap();
@@ -512,12 +692,14 @@ mod tests {
                 output_per_token: 0.06,
                 output_reasoning_per_token: 0.12,
                 input_cached_per_token: 0.015,
+                ..Default::default()
             },
         );
         let v2_config = ModelCosts {
             version: 2,
             costs: vec![],
             models: models_map,
+            ..Default::default()
         };
         assert!(v2_config.is_enabled());
         let cost = v2_config.cost_per_token("gpt-4").unwrap();
@@ -528,10 +710,72 @@ mod tests {
                 output_per_token: 0.06,
                 output_reasoning_per_token: 0.12,
                 input_cached_per_token: 0.015,
+                ..Default::default()
             }
         );
     }
 
+    #[test]
+    fn test_model_cost_v2_version_suffix_and_patterns() {
+        let json = r#"{
+            "version": 2,
+            "models": {
+                "gpt-4o": {"inputPerToken": 1.0, "outputPerToken": 1.0, "outputReasoningPerToken": 0.0, "inputCachedPerToken": 0.0},
+                "gpt-4o-mini": {"inputPerToken": 2.0, "outputPerToken": 2.0, "outputReasoningPerToken": 0.0, "inputCachedPerToken": 0.0}
+            },
+            "patterns": [
+                {
+                    "modelId": {"type": "glob", "value": "claude-3-5-sonnet*"},
+                    "inputPerToken": 3.0, "outputPerToken": 3.0, "outputReasoningPerToken": 0.0, "inputCachedPerToken": 0.0
+                },
+                {
+                    "modelId": {"type": "regex", "value": "^gemini-1\\.5-(pro|flash)"},
+                    "inputPerToken": 4.0, "outputPerToken": 4.0, "outputReasoningPerToken": 0.0, "inputCachedPerToken": 0.0
+                }
+            ]
+        }"#;
+        let costs: ModelCosts = serde_json::from_str(json).unwrap();
+
+        let input_cost = |model_id| costs.cost_per_token(model_id).map(|c| c.input_per_token);
+
+        assert_eq!(input_cost("gpt-4o"), Some(1.0));
+        assert_eq!(input_cost("gpt-4o-2024-08-06"), Some(1.0));
+        assert_eq!(input_cost("gpt-4o-mini-2024-07-18"), Some(2.0));
+        assert_eq!(input_cost("gpt-4o-latest"), Some(1.0));
+        assert_eq!(input_cost("claude-3-5-sonnet@20240620"), Some(3.0));
+        assert_eq!(input_cost("gemini-1.5-pro-002"), Some(4.0));
+        assert_eq!(input_cost("gemini-1.0-pro"), None);
+        assert_eq!(input_cost("gpt-4"), None);
+    }
+
+    #[test]
+    fn test_model_cost_tiers() {
+        let json = r#"{
+            "inputPerToken": 1.0,
+            "outputPerToken": 2.0,
+            "outputReasoningPerToken": 0.0,
+            "inputCachedPerToken": 0.5,
+            "tiers": [
+                {"minInputTokens": 500000, "inputPerToken": 4.0, "outputPerToken": 8.0},
+                {"minInputTokens": 200000, "inputPerToken": 2.0, "outputPerToken": 4.0, "inputCachedPerToken": 1.0}
+            ]
+        }"#;
+        let cost: ModelCostV2 = serde_json::from_str(json).unwrap();
+
+        let base = cost.token_costs(1000.0);
+        assert_eq!((base.input_per_token, base.output_per_token), (1.0, 2.0));
+        assert_eq!(base.input_cached_per_token, 0.5);
+
+        let tier = cost.token_costs(200000.0);
+        assert_eq!((tier.input_per_token, tier.output_per_token), (2.0, 4.0));
+        assert_eq!(tier.input_cached_per_token, 1.0);
+
+        // Costs the tier leaves out fall back to the base costs.
+        let tier = cost.token_costs(1000000.0);
+        assert_eq!((tier.input_per_token, tier.output_per_token), (4.0, 8.0));
+        assert_eq!(tier.input_cached_per_token, 0.5);
+    }
+
     #[test]
     fn test_model_cost_unknown_version() {
         // Test that unknown versions are handled properly
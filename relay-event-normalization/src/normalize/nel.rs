@@ -2,12 +2,19 @@
 
 use chrono::{DateTime, Duration, Utc};
 use relay_event_schema::protocol::{
-    Attributes, NetworkReportRaw, OurLog, OurLogLevel, Timestamp, TraceId,
+    Attributes, Geo, NetworkReportRaw, OurLog, OurLogLevel, Timestamp, TraceId,
 };
 use relay_protocol::Annotated;
 
 /// Creates a [`OurLog`] from the provided [`NetworkReportRaw`].
-pub fn create_log(nel: Annotated<NetworkReportRaw>, received_at: DateTime<Utc>) -> Option<OurLog> {
+///
+/// If the location of the client that sent the report is known, it is added as `user.geo.*`
+/// attributes.
+pub fn create_log(
+    nel: Annotated<NetworkReportRaw>,
+    received_at: DateTime<Utc>,
+    client_geo: Option<&Geo>,
+) -> Option<OurLog> {
     let nel = nel.into_value()?;
     let body = nel.body.into_value()?;
 
@@ -50,6 +57,13 @@ pub fn create_log(nel: Annotated<NetworkReportRaw>, received_at: DateTime<Utc>)
     add_attribute!("todo.nel.phase", body.phase.map_value(|s| s.to_string()));
     add_attribute!("todo.nel.sampling_fraction", body.sampling_fraction);
 
+    if let Some(geo) = client_geo {
+        add_attribute!("user.geo.country_code", geo.country_code.clone());
+        add_attribute!("user.geo.city", geo.city.clone());
+        add_attribute!("user.geo.subdivision", geo.subdivision.clone());
+        add_attribute!("user.geo.region", geo.region.clone());
+    }
+
     Some(OurLog {
         timestamp: Annotated::new(Timestamp::from(timestamp)),
         trace_id: Annotated::new(TraceId::random()),
@@ -4,41 +4,64 @@ use crate::{ModelCostV2, ModelCosts};
 use relay_event_schema::protocol::{Event, Span, SpanData};
 use relay_protocol::{Annotated, Value};
 
+/// Costs of an AI model call in US dollars.
+#[derive(Debug, PartialEq)]
+struct AiCosts {
+    /// The cost of all input tokens.
+    input: f64,
+    /// The cost of all output tokens.
+    output: f64,
+    /// The total cost, including non-token usage such as images.
+    total: f64,
+}
+
 /// Calculates the cost of an AI model based on the model cost and the tokens used.
 /// Calculated cost is in US dollars.
-fn calculate_ai_model_cost(model_cost: Option<ModelCostV2>, data: &SpanData) -> Option<f64> {
-    let cost_per_token = model_cost?;
-    let input_tokens_used = data
-        .gen_ai_usage_input_tokens
-        .value()
-        .and_then(Value::as_f64);
-
-    let output_tokens_used = data
-        .gen_ai_usage_output_tokens
-        .value()
-        .and_then(Value::as_f64);
-    let output_reasoning_tokens_used = data
-        .gen_ai_usage_output_tokens_reasoning
-        .value()
-        .and_then(Value::as_f64);
-    let input_cached_tokens_used = data
-        .gen_ai_usage_input_tokens_cached
-        .value()
-        .and_then(Value::as_f64);
-
-    if input_tokens_used.is_none() && output_tokens_used.is_none() {
+fn calculate_ai_model_cost(model_cost: Option<ModelCostV2>, data: &SpanData) -> Option<AiCosts> {
+    let model_cost = model_cost?;
+    let usage = |value: &Annotated<Value>| value.value().and_then(Value::as_f64);
+
+    let input_tokens_used = usage(&data.gen_ai_usage_input_tokens);
+    let output_tokens_used = usage(&data.gen_ai_usage_output_tokens);
+    let output_reasoning_tokens_used = usage(&data.gen_ai_usage_output_tokens_reasoning);
+    let input_cached_tokens_used = usage(&data.gen_ai_usage_input_tokens_cached);
+    let input_cache_write_tokens_used = usage(&data.gen_ai_usage_input_tokens_cache_write);
+    let images_used = usage(&data.gen_ai_usage_images);
+    let audio_seconds_used = usage(&data.gen_ai_usage_audio_seconds);
+    let tool_calls_used = usage(&data.gen_ai_usage_tool_calls);
+
+    if input_tokens_used.is_none()
+        && output_tokens_used.is_none()
+        && images_used.is_none()
+        && audio_seconds_used.is_none()
+        && tool_calls_used.is_none()
+    {
         return None;
     }
 
-    let mut result = 0.0;
+    let cost_per_token = model_cost.token_costs(input_tokens_used.unwrap_or(0.0));
+
+    let mut input = 0.0;
+    input += cost_per_token.input_per_token * input_tokens_used.unwrap_or(0.0);
+    input += cost_per_token.input_cached_per_token * input_cached_tokens_used.unwrap_or(0.0);
+    input +=
+        cost_per_token.input_cache_write_per_token * input_cache_write_tokens_used.unwrap_or(0.0);
 
-    result += cost_per_token.input_per_token * input_tokens_used.unwrap_or(0.0);
-    result += cost_per_token.output_per_token * output_tokens_used.unwrap_or(0.0);
-    result +=
+    let mut output = 0.0;
+    output += cost_per_token.output_per_token * output_tokens_used.unwrap_or(0.0);
+    output +=
         cost_per_token.output_reasoning_per_token * output_reasoning_tokens_used.unwrap_or(0.0);
-    result += cost_per_token.input_cached_per_token * input_cached_tokens_used.unwrap_or(0.0);
 
-    Some(result)
+    let mut total = input + output;
+    total += model_cost.per_image * images_used.unwrap_or(0.0);
+    total += model_cost.per_audio_second * audio_seconds_used.unwrap_or(0.0);
+    total += model_cost.per_tool_call * tool_calls_used.unwrap_or(0.0);
+
+    Some(AiCosts {
+        input,
+        output,
+        total,
+    })
 }
 
 /// Maps AI-related measurements (legacy) to span data.
@@ -91,7 +114,14 @@ pub fn map_ai_measurements_to_data(span: &mut Span) {
 }
 
 /// Extract the gen_ai_usage_total_cost data into the span
-pub fn extract_ai_data(span: &mut Span, ai_model_costs: &ModelCosts) {
+///
+/// Model costs from `ai_model_cost_overrides`, such as negotiated rates of a project or
+/// organization, take precedence over `ai_model_costs`. Overrides are consulted in order.
+pub fn extract_ai_data(
+    span: &mut Span,
+    ai_model_costs: Option<&ModelCosts>,
+    ai_model_cost_overrides: &[&ModelCosts],
+) {
     if !is_ai_span(span) {
         return;
     }
@@ -108,25 +138,35 @@ pub fn extract_ai_data(span: &mut Span, ai_model_costs: &ModelCosts) {
         // sentry conventions and standardize what SDKs send
         .or_else(|| data.ai_model_id.value().and_then(|val| val.as_str()))
     {
-        if let Some(total_cost) =
-            calculate_ai_model_cost(ai_model_costs.cost_per_token(model_id), data)
-        {
+        let model_cost = ai_model_cost_overrides
+            .iter()
+            .copied()
+            .chain(ai_model_costs)
+            .find_map(|costs| costs.cost_per_token(model_id));
+
+        if let Some(costs) = calculate_ai_model_cost(model_cost, data) {
             data.gen_ai_usage_total_cost
-                .set_value(Value::F64(total_cost).into());
+                .set_value(Value::F64(costs.total).into());
+            data.gen_ai_cost_input_tokens
+                .set_value(Value::F64(costs.input).into());
+            data.gen_ai_cost_output_tokens
+                .set_value(Value::F64(costs.output).into());
         }
     }
 }
 
 /// Extract the ai data from all of an event's spans
-pub fn enrich_ai_span_data(event: &mut Event, model_costs: Option<&ModelCosts>) {
+pub fn enrich_ai_span_data(
+    event: &mut Event,
+    model_costs: Option<&ModelCosts>,
+    model_cost_overrides: &[&ModelCosts],
+) {
     let spans = event.spans.value_mut().iter_mut().flatten();
     let spans = spans.filter_map(|span| span.value_mut().as_mut());
 
     for span in spans {
         map_ai_measurements_to_data(span);
-        if let Some(model_costs) = model_costs {
-            extract_ai_data(span, model_costs);
-        }
+        extract_ai_data(span, model_costs, model_cost_overrides);
     }
 }
 
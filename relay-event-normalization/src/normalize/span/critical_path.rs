@@ -0,0 +1,163 @@
+//! Critical path computation for span trees.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use relay_event_schema::protocol::{Span, SpanData, SpanId};
+use relay_protocol::Annotated;
+
+/// Marks all spans on the critical path of the segment rooted at `root_span_id`.
+///
+/// The critical path is the chain of spans that determines the duration of the segment. Starting
+/// at the root, it follows the child span that ends last, then the child span that ends last
+/// before the previous one started, and so on. The same is applied recursively to every span on
+/// the path. Spans on the critical path have the `sentry.is_critical_path` attribute set.
+///
+/// Does nothing if the root span is not contained in `spans`.
+pub fn mark_critical_path(spans: &mut [Annotated<Span>], root_span_id: SpanId) {
+    let mut root = None;
+    let mut intervals = Vec::with_capacity(spans.len());
+    let mut children = HashMap::<SpanId, Vec<usize>>::new();
+
+    for (index, span) in spans.iter().enumerate() {
+        let interval = span.value().and_then(|span| {
+            let start = span.start_timestamp.value()?.into_inner();
+            let end = span.timestamp.value()?.into_inner();
+            Some((*span.span_id.value()?, start, end))
+        });
+        intervals.push(interval);
+
+        let Some((span_id, _, _)) = interval else {
+            continue;
+        };
+
+        if span_id == root_span_id {
+            root = Some(index);
+        } else if let Some(parent_span_id) = spans[index]
+            .value()
+            .and_then(|span| span.parent_span_id.value())
+        {
+            children.entry(*parent_span_id).or_default().push(index);
+        }
+    }
+
+    let Some(root) = root else {
+        return;
+    };
+
+    let mut on_path = vec![false; spans.len()];
+    let mut stack: Vec<(usize, DateTime<Utc>)> = vec![(root, DateTime::<Utc>::MAX_UTC)];
+
+    while let Some((index, limit)) = stack.pop() {
+        let Some((span_id, start, end)) = intervals[index] else {
+            continue;
+        };
+        if on_path[index] {
+            continue;
+        }
+        on_path[index] = true;
+
+        let children = children
+            .get(&span_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut cursor = end.min(limit);
+
+        while cursor > start {
+            let next = children
+                .iter()
+                .filter_map(|&child| Some((child, intervals[child]?)))
+                .filter(|(child, (_, child_start, _))| !on_path[*child] && *child_start < cursor)
+                .max_by_key(|(_, (_, _, child_end))| (*child_end).min(cursor));
+
+            let Some((child, (_, child_start, _))) = next else {
+                break;
+            };
+
+            stack.push((child, cursor));
+            cursor = child_start;
+        }
+    }
+
+    for (span, on_path) in spans.iter_mut().zip(on_path) {
+        if let (true, Some(span)) = (on_path, span.value_mut()) {
+            span.data
+                .get_or_insert_with(SpanData::default)
+                .is_critical_path
+                .set_value(Some(true));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_protocol::FromValue;
+
+    use super::*;
+
+    fn critical_path(spans: serde_json::Value) -> Vec<String> {
+        let mut spans = Vec::<Annotated<Span>>::from_value(spans.into())
+            .into_value()
+            .unwrap();
+
+        mark_critical_path(&mut spans, "aaaaaaaaaaaaaaaa".parse().unwrap());
+
+        spans
+            .iter()
+            .filter_map(|span| span.value())
+            .filter(|span| {
+                span.data
+                    .value()
+                    .and_then(|data| data.is_critical_path.value())
+                    .is_some_and(|v| *v)
+            })
+            .map(|span| span.span_id.value().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_sequential_children() {
+        let path = critical_path(serde_json::json!([
+            {"span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 0, "timestamp": 10},
+            // Runs in parallel to `cccccccccccccccc`, but finishes earlier.
+            {"span_id": "bbbbbbbbbbbbbbbb", "parent_span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 1, "timestamp": 4},
+            {"span_id": "cccccccccccccccc", "parent_span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 1, "timestamp": 5},
+            {"span_id": "dddddddddddddddd", "parent_span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 5, "timestamp": 9},
+            // Nested span on the critical path.
+            {"span_id": "eeeeeeeeeeeeeeee", "parent_span_id": "dddddddddddddddd", "start_timestamp": 6, "timestamp": 8},
+        ]));
+
+        assert_eq!(
+            path,
+            [
+                "aaaaaaaaaaaaaaaa",
+                "cccccccccccccccc",
+                "dddddddddddddddd",
+                "eeeeeeeeeeeeeeee",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_child_exceeds_parent() {
+        let path = critical_path(serde_json::json!([
+            {"span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 0, "timestamp": 10},
+            {"span_id": "bbbbbbbbbbbbbbbb", "parent_span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 2, "timestamp": 12},
+            {"span_id": "cccccccccccccccc", "parent_span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 0, "timestamp": 3},
+        ]));
+
+        assert_eq!(
+            path,
+            ["aaaaaaaaaaaaaaaa", "bbbbbbbbbbbbbbbb", "cccccccccccccccc"]
+        );
+    }
+
+    #[test]
+    fn test_missing_root() {
+        let path = critical_path(serde_json::json!([
+            {"span_id": "bbbbbbbbbbbbbbbb", "parent_span_id": "aaaaaaaaaaaaaaaa", "start_timestamp": 1, "timestamp": 4},
+        ]));
+
+        assert!(path.is_empty());
+    }
+}
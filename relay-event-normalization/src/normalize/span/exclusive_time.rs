@@ -89,20 +89,10 @@ fn set_span_exclusive_time(
     span.exclusive_time = Annotated::new(relay_common::time::duration_to_millis(exclusive_time));
 }
 
-/// Computes the exclusive time for all spans in the event.
-pub fn compute_span_exclusive_time(event: &mut Event) {
-    let contexts = match event.contexts.value_mut() {
-        Some(contexts) => contexts,
-        _ => return,
-    };
-
-    let event_interval = match (event.start_timestamp.value(), event.timestamp.value()) {
-        (Some(start), Some(end)) => TimeWindowSpan::new(*start, *end),
-        _ => return,
-    };
-
-    let spans = event.spans.value_mut().get_or_insert_with(Vec::new);
-
+/// Returns the time intervals of all child spans grouped by their parent's span ID.
+///
+/// Intervals are sorted by start time to fulfill the precondition of `interval_exclusive_time`.
+fn child_intervals(spans: &[Annotated<Span>]) -> HashMap<SpanId, Vec<TimeWindowSpan>> {
     let mut span_map = HashMap::new();
     for span in spans.iter() {
         let span = match span.value() {
@@ -125,11 +115,51 @@ pub fn compute_span_exclusive_time(event: &mut Event) {
             .push(interval)
     }
 
-    // Sort intervals to fulfill precondition of `interval_exclusive_time`
     for intervals in span_map.values_mut() {
         intervals.sort_unstable_by_key(|interval| interval.start);
     }
 
+    span_map
+}
+
+/// Computes the exclusive time for standalone spans of a single segment.
+///
+/// Standalone spans can be sent in multiple batches, so the children of a span may not all be
+/// present. Unlike for transactions, exclusive times that are already set on a span are retained,
+/// including the `sentry.exclusive_time` attribute in the span's data.
+pub fn compute_segment_exclusive_time(spans: &mut [Annotated<Span>]) {
+    let span_map = child_intervals(spans);
+
+    for span in spans.iter_mut() {
+        let has_exclusive_time = span.value().is_some_and(|span| {
+            span.exclusive_time.value().is_some()
+                || span
+                    .data
+                    .value()
+                    .is_some_and(|data| data.exclusive_time.value().is_some())
+        });
+
+        if !has_exclusive_time {
+            set_span_exclusive_time(span, &span_map);
+        }
+    }
+}
+
+/// Computes the exclusive time for all spans in the event.
+pub fn compute_span_exclusive_time(event: &mut Event) {
+    let contexts = match event.contexts.value_mut() {
+        Some(contexts) => contexts,
+        _ => return,
+    };
+
+    let event_interval = match (event.start_timestamp.value(), event.timestamp.value()) {
+        (Some(start), Some(end)) => TimeWindowSpan::new(*start, *end),
+        _ => return,
+    };
+
+    let spans = event.spans.value_mut().get_or_insert_with(Vec::new);
+    let span_map = child_intervals(spans);
+
     set_event_exclusive_time(event_interval, contexts, &span_map);
 
     for span in spans.iter_mut() {
@@ -542,4 +572,54 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn test_segment_spans() {
+        let mut spans = vec![
+            make_span(
+                "http.server",
+                "GET /",
+                Utc.timestamp_opt(1609455600, 0).unwrap().into(),
+                Utc.timestamp_opt(1609455605, 0).unwrap().into(),
+                "aaaaaaaaaaaaaaaa",
+                "ffffffffffffffff",
+            ),
+            make_span(
+                "db",
+                "SELECT * FROM table;",
+                Utc.timestamp_opt(1609455601, 0).unwrap().into(),
+                Utc.timestamp_opt(1609455603, 0).unwrap().into(),
+                "bbbbbbbbbbbbbbbb",
+                "aaaaaaaaaaaaaaaa",
+            ),
+            make_span(
+                "db",
+                "SELECT * FROM table;",
+                Utc.timestamp_opt(1609455602, 0).unwrap().into(),
+                Utc.timestamp_opt(1609455604, 0).unwrap().into(),
+                "cccccccccccccccc",
+                "aaaaaaaaaaaaaaaa",
+            ),
+        ];
+
+        // Exclusive times sent by the SDK are retained.
+        let span = spans[2].value_mut().as_mut().unwrap();
+        span.exclusive_time = Annotated::new(42.0);
+
+        compute_segment_exclusive_time(&mut spans);
+
+        let exclusive_times = spans
+            .iter()
+            .map(|span| extract_exclusive_time(span.value().unwrap()))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            exclusive_times,
+            HashMap::from_iter([
+                (&"aaaaaaaaaaaaaaaa".parse().unwrap(), 2000.0),
+                (&"bbbbbbbbbbbbbbbb".parse().unwrap(), 2000.0),
+                (&"cccccccccccccccc".parse().unwrap(), 42.0),
+            ])
+        );
+    }
 }
@@ -5,6 +5,7 @@ use regex::Regex;
 
 pub mod ai;
 pub mod country_subregion;
+pub mod critical_path;
 pub mod description;
 pub mod exclusive_time;
 pub mod reparent_broken_spans;
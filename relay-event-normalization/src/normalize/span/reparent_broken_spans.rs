@@ -3,8 +3,8 @@
 use std::collections::BTreeSet;
 use std::mem;
 
-use relay_event_schema::protocol::{Event, TraceContext};
-use relay_protocol::Error;
+use relay_event_schema::protocol::{Event, Span, SpanId, TraceContext};
+use relay_protocol::{Annotated, Error};
 
 /// Enforce that every span has a valid parent. If any `parent_span_id` is pointing nowhere, the
 /// span is re-parented onto the root span.
@@ -24,15 +24,24 @@ pub fn reparent_broken_spans(event: &mut Event) {
         return;
     };
 
-    let Some(root_span_id) = trace_context.span_id.value() else {
+    let Some(&root_span_id) = trace_context.span_id.value() else {
         return;
     };
 
+    reparent_spans(spans, root_span_id);
+}
+
+/// Re-parents spans whose `parent_span_id` is pointing nowhere onto the given root span.
+///
+/// The root span itself may be contained in `spans`, in which case its parent is left untouched.
+/// This is used for standalone spans, where the segment root usually has a parent in another
+/// service.
+pub fn reparent_spans(spans: &mut [Annotated<Span>], root_span_id: SpanId) {
     let valid_span_ids = spans
         .iter()
         .filter_map(|span| span.value())
         .filter_map(|span| span.span_id.value())
-        .chain(Some(root_span_id))
+        .chain(Some(&root_span_id))
         .cloned()
         .collect::<BTreeSet<_>>();
 
@@ -41,6 +50,10 @@ pub fn reparent_broken_spans(event: &mut Event) {
             continue;
         };
 
+        if span.span_id.value() == Some(&root_span_id) {
+            continue;
+        }
+
         let Some(parent_span_id) = span.parent_span_id.value_mut() else {
             continue;
         };
@@ -49,7 +62,7 @@ pub fn reparent_broken_spans(event: &mut Event) {
             continue;
         };
 
-        let invalid_parent = mem::replace(parent_span_id, *root_span_id);
+        let invalid_parent = mem::replace(parent_span_id, root_span_id);
         let meta = span.parent_span_id.meta_mut();
         meta.add_error(Error::invalid("span ID does not exist"));
         meta.set_original_value(Some(invalid_parent));
@@ -161,4 +174,46 @@ mod tests {
         }
         "###);
     }
+
+    #[test]
+    fn segment_root_in_spans() {
+        let mut spans = Vec::<Annotated<Span>>::from_value(
+            serde_json::json!([
+                {
+                    "span_id": "aaaaaaaaaaaaaaaa",
+                    "parent_span_id": "ffffffffffffffff",
+                },
+                {
+                    "span_id": "bbbbbbbbbbbbbbbb",
+                    "parent_span_id": "aaaaaaaaaaaaaaaa",
+                },
+                {
+                    "span_id": "cccccccccccccccc",
+                    "parent_span_id": "dddddddddddddddd",
+                },
+            ])
+            .into(),
+        )
+        .into_value()
+        .unwrap();
+
+        reparent_spans(&mut spans, "aaaaaaaaaaaaaaaa".parse().unwrap());
+
+        let parents: Vec<_> = spans
+            .iter()
+            .map(|span| {
+                span.value()
+                    .unwrap()
+                    .parent_span_id
+                    .value()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+
+        assert_eq!(
+            parents,
+            ["ffffffffffffffff", "aaaaaaaaaaaaaaaa", "aaaaaaaaaaaaaaaa"]
+        );
+    }
 }
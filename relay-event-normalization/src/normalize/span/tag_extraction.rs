@@ -26,6 +26,7 @@ use crate::span::description::{
 use crate::utils::{
     MAIN_THREAD_NAME, MOBILE_SDKS, extract_transaction_op, http_status_code_from_span,
 };
+use crate::{SpanDescriptionNormalizationRule, normalize_span_description};
 
 /// Render-blocking resources are static files, such as fonts, CSS, and JavaScript that block or
 /// delay the browser from rendering page content to the screen.
@@ -64,6 +65,7 @@ pub(crate) fn extract_span_tags_from_event(
     event: &mut Event,
     max_tag_value_size: usize,
     http_scrubbing_allow_list: &[String],
+    description_rules: &[SpanDescriptionNormalizationRule],
 ) {
     // Temporarily take ownership to pass both an event reference and a mutable span reference to `extract_span_tags`.
     let mut spans = std::mem::take(&mut event.spans);
@@ -75,6 +77,7 @@ pub(crate) fn extract_span_tags_from_event(
         spans_vec.as_mut_slice(),
         max_tag_value_size,
         http_scrubbing_allow_list,
+        description_rules,
     );
 
     event.spans = spans;
@@ -88,6 +91,7 @@ pub fn extract_span_tags(
     spans: &mut [Annotated<Span>],
     max_tag_value_size: usize,
     span_allowed_hosts: &[String],
+    description_rules: &[SpanDescriptionNormalizationRule],
 ) {
     // TODO: To prevent differences between metrics and payloads, we should not extract tags here
     // when they have already been extracted by a downstream relay.
@@ -114,6 +118,7 @@ pub fn extract_span_tags(
             is_mobile,
             start_type,
             span_allowed_hosts,
+            description_rules,
         );
 
         shared_tags.copy_into(&mut tags);
@@ -741,6 +746,7 @@ pub fn extract_tags(
     is_mobile: bool,
     start_type: Option<&str>,
     span_allowed_hosts: &[String],
+    description_rules: &[SpanDescriptionNormalizationRule],
 ) -> SentryTags {
     let mut span_tags = SentryTags::default();
 
@@ -764,7 +770,12 @@ pub fn extract_tags(
 
         let category = category_for_span(span);
 
-        let (scrubbed_description, parsed_sql) = scrub_span_description(span, span_allowed_hosts);
+        // Project-defined rules take precedence over the built-in scrubbers.
+        let (scrubbed_description, parsed_sql) =
+            match normalize_span_description(span, description_rules) {
+                Some(description) => (Some(description), None),
+                None => scrub_span_description(span, span_allowed_hosts),
+            };
         let action = match (category.as_deref(), span_op.as_str(), &scrubbed_description) {
             (Some("http"), _, _) => span
                 .data
@@ -1767,7 +1778,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let spans = event.spans.value().unwrap();
 
@@ -1829,7 +1840,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event.spans.value().unwrap()[0];
 
@@ -1951,7 +1962,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span_1 = &event.spans.value().unwrap()[0];
         let span_2 = &event.spans.value().unwrap()[1];
@@ -2001,7 +2012,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event
             .spans
@@ -2060,7 +2071,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event
             .spans
@@ -2154,7 +2165,7 @@ LIMIT 1
         "#;
 
         let mut event = Annotated::<Event>::from_json(json).unwrap();
-        extract_span_tags_from_event(event.value_mut().as_mut().unwrap(), 200, &[]);
+        extract_span_tags_from_event(event.value_mut().as_mut().unwrap(), 200, &[], &[]);
         insta::assert_snapshot!(event.to_json_pretty().unwrap());
     }
 
@@ -2298,7 +2309,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span_1 = &event.spans.value().unwrap()[0];
         let span_2 = &event.spans.value().unwrap()[1];
@@ -2397,7 +2408,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event.spans.value().unwrap()[0];
 
@@ -2466,7 +2477,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event.spans.value().unwrap()[0];
         let tags = span.value().unwrap().sentry_tags.value().unwrap();
@@ -2491,7 +2502,7 @@ LIMIT 1
             .unwrap()
             .into_value()
             .unwrap();
-        let tags = extract_tags(&span, 200, None, None, false, None, &[]);
+        let tags = extract_tags(&span, 200, None, None, false, None, &[], &[]);
 
         assert_eq!(tags.browser_name.value(), Some(&"Chrome".to_owned()));
     }
@@ -2528,7 +2539,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event.spans.value().unwrap()[0];
         let tags = span.value().unwrap().sentry_tags.value().unwrap();
@@ -2558,7 +2569,7 @@ LIMIT 1
             .unwrap()
             .into_value()
             .unwrap();
-        let tags = extract_tags(&span, 200, None, None, false, None, &[]);
+        let tags = extract_tags(&span, 200, None, None, false, None, &[], &[]);
 
         assert_eq!(
             tags.messaging_destination_name.value(),
@@ -2766,7 +2777,7 @@ LIMIT 1
             .into_value()
             .unwrap();
 
-        extract_span_tags_from_event(&mut event, 200, &[]);
+        extract_span_tags_from_event(&mut event, 200, &[], &[]);
 
         let span = &event.spans.value().unwrap()[0];
         let tags = span.value().unwrap().sentry_tags.value().unwrap();
@@ -2845,6 +2856,44 @@ LIMIT 1
         assert_eq!(statuses, vec!["ok", "invalid_argument"]);
     }
 
+    #[test]
+    fn description_normalization_rules() {
+        let json = r#"{
+            "description": "Users.Get shard-17 request-9d2f",
+            "op": "rpc.client",
+            "data": {
+                "rpc.system": "inhouse"
+            }
+        }"#;
+        let span = Annotated::<Span>::from_json(json)
+            .unwrap()
+            .into_value()
+            .unwrap();
+
+        let rules: Vec<SpanDescriptionNormalizationRule> = serde_json::from_str(
+            r#"[{
+                "condition": {"op": "eq", "name": "span.data.rpc\\.system", "value": "inhouse"},
+                "pattern": {"type": "glob", "value": "* shard-* request-*"},
+                "replacement": "$1"
+            }]"#,
+        )
+        .unwrap();
+
+        let tags = extract_tags(&span, 200, None, None, false, None, &[], &[]);
+        assert_eq!(tags.description.value(), None);
+
+        let tags = extract_tags(&span, 200, None, None, false, None, &[], &rules);
+        assert_eq!(tags.description.as_str(), Some("Users.Get"));
+        assert_eq!(
+            tags.group.as_str(),
+            Some(
+                format!("{:?}", md5::compute("Users.Get"))
+                    .get(..16)
+                    .unwrap()
+            )
+        );
+    }
+
     fn extract_tags_supabase(description: impl Into<String>) -> SentryTags {
         let json = r#"{
             "description": "from(my_table)",
@@ -2864,7 +2913,7 @@ LIMIT 1
             .unwrap();
         span.description.set_value(Some(description.into()));
 
-        extract_tags(&span, 200, None, None, false, None, &[])
+        extract_tags(&span, 200, None, None, false, None, &[], &[])
     }
 
     #[test]
@@ -2919,7 +2968,7 @@ LIMIT 1
             .unwrap()
             .into_value()
             .unwrap();
-        let tags = extract_tags(&span, 200, None, None, false, None, &[]);
+        let tags = extract_tags(&span, 200, None, None, false, None, &[], &[]);
 
         assert_eq!(tags.action.value(), Some(&"FIND".to_owned()));
 
@@ -2946,7 +2995,7 @@ LIMIT 1
             .unwrap()
             .into_value()
             .unwrap();
-        let tags = extract_tags(&span, 200, None, None, false, None, &[]);
+        let tags = extract_tags(&span, 200, None, None, false, None, &[], &[]);
 
         assert_eq!(tags.domain.value(), Some(&"documents_{%s}".to_owned()));
     }
@@ -0,0 +1,416 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use relay_common::glob2::LazyGlob;
+
+use crate::{RedactionRule, TransactionNameRule};
+
+/// Interval after which [`TransactionClusterer::rules`] regenerates the rules, even if no new
+/// high-cardinality segments have been detected.
+///
+/// This refreshes the expiry of rules that are still in use.
+const RULES_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Configuration for the [`TransactionClusterer`].
+#[derive(Clone, Copy, Debug)]
+pub struct TransactionClustererConfig {
+    /// Number of distinct values of a path segment after which the segment is replaced by `*`.
+    pub merge_threshold: usize,
+    /// Maximum number of path segments kept in the tree.
+    ///
+    /// Once this limit is reached, new segments are no longer recorded.
+    pub max_nodes: usize,
+    /// Duration for which a rule remains valid after the last transaction matching it.
+    pub rule_ttl: Duration,
+}
+
+impl Default for TransactionClustererConfig {
+    fn default() -> Self {
+        Self {
+            merge_threshold: 200,
+            max_nodes: 10_000,
+            rule_ttl: Duration::from_secs(90 * 24 * 3600),
+        }
+    }
+}
+
+/// A node in the tree of transaction name segments.
+#[derive(Debug, Default)]
+struct Node {
+    /// Distinct values of the next path segment.
+    children: BTreeMap<String, Node>,
+    /// Replaces all children once the segment has been detected as high-cardinality.
+    wildcard: Option<Box<Node>>,
+    /// The last time a transaction passed through this node.
+    last_seen: DateTime<Utc>,
+}
+
+impl Node {
+    /// Inserts the remaining segments into the tree.
+    ///
+    /// Returns `true` if a segment has been merged into a wildcard.
+    fn insert(
+        &mut self,
+        segments: &[&str],
+        config: &TransactionClustererConfig,
+        budget: &mut usize,
+        now: DateTime<Utc>,
+    ) -> bool {
+        self.last_seen = now;
+
+        let Some((segment, rest)) = segments.split_first() else {
+            return false;
+        };
+
+        if let Some(ref mut wildcard) = self.wildcard {
+            return wildcard.insert(rest, config, budget, now);
+        }
+
+        let child = match self.children.entry((*segment).to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                if *budget == 0 {
+                    return false;
+                }
+                *budget -= 1;
+                entry.insert(Node::default())
+            }
+        };
+
+        let mut merged = child.insert(rest, config, budget, now);
+        if self.children.len() > config.merge_threshold {
+            self.merge_children(config, now);
+            merged = true;
+        }
+
+        merged
+    }
+
+    /// Moves all children into the wildcard node, merging their subtrees.
+    fn merge_children(&mut self, config: &TransactionClustererConfig, now: DateTime<Utc>) {
+        let children = std::mem::take(&mut self.children);
+        let wildcard = self.wildcard.get_or_insert_with(Default::default);
+        wildcard.last_seen = now;
+
+        for child in children.into_values() {
+            wildcard.absorb(child, config, now);
+        }
+    }
+
+    /// Merges another subtree into this node.
+    fn absorb(&mut self, other: Node, config: &TransactionClustererConfig, now: DateTime<Utc>) {
+        self.last_seen = self.last_seen.max(other.last_seen);
+
+        if let Some(other_wildcard) = other.wildcard {
+            self.merge_children(config, now);
+            if let Some(ref mut wildcard) = self.wildcard {
+                wildcard.absorb(*other_wildcard, config, now);
+            }
+        }
+
+        for (segment, child) in other.children {
+            match self.wildcard {
+                Some(ref mut wildcard) => wildcard.absorb(child, config, now),
+                None => self
+                    .children
+                    .entry(segment)
+                    .or_default()
+                    .absorb(child, config, now),
+            }
+        }
+
+        if self.children.len() > config.merge_threshold {
+            self.merge_children(config, now);
+        }
+    }
+
+    /// Returns the number of nodes in this subtree, excluding this node.
+    fn count(&self) -> usize {
+        let children = self.children.values().map(|c| 1 + c.count()).sum::<usize>();
+        let wildcard = self.wildcard.as_ref().map_or(0, |w| 1 + w.count());
+        children + wildcard
+    }
+
+    /// Removes wildcards that have not been seen within the rule TTL.
+    fn prune(&mut self, rule_ttl: Duration, now: DateTime<Utc>) {
+        if self
+            .wildcard
+            .as_ref()
+            .is_some_and(|w| w.last_seen + rule_ttl <= now)
+        {
+            self.wildcard = None;
+        }
+
+        for child in self.children.values_mut() {
+            child.prune(rule_ttl, now);
+        }
+        if let Some(ref mut wildcard) = self.wildcard {
+            wildcard.prune(rule_ttl, now);
+        }
+    }
+
+    /// Appends a rule for every wildcard in this subtree to `rules`.
+    fn collect_rules(
+        &self,
+        path: Option<&str>,
+        rule_ttl: Duration,
+        rules: &mut Vec<TransactionNameRule>,
+    ) {
+        let join = |segment: &str| match path {
+            Some(path) => format!("{path}/{segment}"),
+            None => segment.to_owned(),
+        };
+
+        if let Some(ref wildcard) = self.wildcard {
+            let path = join("*");
+            rules.push(TransactionNameRule {
+                pattern: LazyGlob::new(format!("{path}/**")),
+                expiry: wildcard.last_seen + rule_ttl,
+                redaction: RedactionRule::default(),
+            });
+            wildcard.collect_rules(Some(&path), rule_ttl, rules);
+        }
+
+        for (segment, child) in &self.children {
+            child.collect_rules(Some(&join(segment)), rule_ttl, rules);
+        }
+    }
+}
+
+/// Detects high-cardinality segments in URL transaction names and generates rename rules.
+///
+/// The clusterer splits transaction names by `/` and records them in a tree of path segments. Once
+/// a node has more distinct children than the configured merge threshold, the children are merged
+/// into a single wildcard. Each wildcard results in a [`TransactionNameRule`] which replaces the
+/// segment with `*`, similar to the rules discovered by Sentry's transaction clusterer.
+///
+/// Rules expire after the configured TTL if no more transactions match them.
+#[derive(Debug)]
+pub struct TransactionClusterer {
+    config: TransactionClustererConfig,
+    root: Node,
+    nodes: usize,
+    rules: Arc<[TransactionNameRule]>,
+    dirty: bool,
+    generated_at: DateTime<Utc>,
+}
+
+impl TransactionClusterer {
+    /// Creates a new, empty clusterer.
+    pub fn new(config: TransactionClustererConfig) -> Self {
+        Self {
+            config,
+            root: Node::default(),
+            nodes: 0,
+            rules: Arc::new([]),
+            dirty: false,
+            generated_at: DateTime::default(),
+        }
+    }
+
+    /// Records a URL transaction name.
+    ///
+    /// Names without path segments or with glob meta characters are ignored.
+    pub fn record(&mut self, name: &str, now: DateTime<Utc>) {
+        let name = name.trim_end_matches('/');
+        if !name.contains('/') || name.split('/').any(|s| !is_valid_segment(s)) {
+            return;
+        }
+
+        let segments = name.split('/').collect::<Vec<_>>();
+        let available = self.config.max_nodes.saturating_sub(self.nodes);
+        let mut budget = available;
+
+        if self.root.insert(&segments, &self.config, &mut budget, now) {
+            self.nodes = self.root.count();
+            self.dirty = true;
+        } else {
+            self.nodes += available - budget;
+        }
+    }
+
+    /// Returns the rules for all high-cardinality segments detected so far.
+    ///
+    /// More specific rules are sorted first, so that they take precedence when applied with
+    /// [`apply_transaction_rename_rules`](crate::apply_transaction_rename_rules).
+    pub fn rules(&mut self, now: DateTime<Utc>) -> Arc<[TransactionNameRule]> {
+        if self.dirty || self.generated_at + RULES_REFRESH_INTERVAL <= now {
+            self.root.prune(self.config.rule_ttl, now);
+            self.nodes = self.root.count();
+
+            let mut rules = Vec::new();
+            self.root
+                .collect_rules(None, self.config.rule_ttl, &mut rules);
+            rules.sort_by(|a, b| {
+                let segments = |r: &TransactionNameRule| r.pattern.as_str().matches('/').count();
+                segments(b)
+                    .cmp(&segments(a))
+                    .then_with(|| a.pattern.as_str().cmp(b.pattern.as_str()))
+            });
+
+            self.rules = rules.into();
+            self.dirty = false;
+            self.generated_at = now;
+        }
+
+        Arc::clone(&self.rules)
+    }
+}
+
+/// Returns `true` if the segment can be used literally in a glob pattern.
+fn is_valid_segment(segment: &str) -> bool {
+    segment == "*" || !segment.contains(['*', '?', '[', ']', '{', '}', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn config(merge_threshold: usize) -> TransactionClustererConfig {
+        TransactionClustererConfig {
+            merge_threshold,
+            ..Default::default()
+        }
+    }
+
+    fn patterns(rules: &[TransactionNameRule]) -> Vec<&str> {
+        rules.iter().map(|r| r.pattern.as_str()).collect()
+    }
+
+    #[test]
+    fn test_no_rules_below_threshold() {
+        let mut clusterer = TransactionClusterer::new(config(3));
+        let now = Utc::now();
+
+        for id in ["a", "b", "c"] {
+            clusterer.record(&format!("/users/{id}/settings"), now);
+        }
+
+        assert!(clusterer.rules(now).is_empty());
+    }
+
+    #[test]
+    fn test_merge_high_cardinality_segment() {
+        let mut clusterer = TransactionClusterer::new(config(3));
+        let now = Utc::now();
+
+        for id in ["a", "b", "c", "d"] {
+            clusterer.record(&format!("/users/{id}/settings"), now);
+        }
+        clusterer.record("/health", now);
+
+        let rules = clusterer.rules(now);
+        assert_eq!(patterns(&rules), ["/users/*/**"]);
+
+        let rule = &rules[0];
+        assert_eq!(
+            rule.expiry,
+            now + TransactionClustererConfig::default().rule_ttl
+        );
+        assert_eq!(
+            rule.match_and_apply(Cow::Owned("/users/e/settings".to_owned())),
+            Some("/users/*/settings".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_merge_nested_segments() {
+        let mut clusterer = TransactionClusterer::new(config(2));
+        let now = Utc::now();
+
+        for org in ["a", "b", "c"] {
+            for project in ["x", "y", "z"] {
+                clusterer.record(&format!("/orgs/{org}/projects/{project}/"), now);
+            }
+        }
+
+        let rules = clusterer.rules(now);
+        assert_eq!(patterns(&rules), ["/orgs/*/projects/*/**", "/orgs/*/**"]);
+    }
+
+    #[test]
+    fn test_merge_subtrees() {
+        let mut clusterer = TransactionClusterer::new(config(2));
+        let now = Utc::now();
+
+        clusterer.record("/a/1/x", now);
+        clusterer.record("/a/2/x", now);
+        clusterer.record("/a/2/y", now);
+        clusterer.record("/a/3/z", now);
+
+        // After merging the second segment, the third segment has three distinct values.
+        let rules = clusterer.rules(now);
+        assert_eq!(patterns(&rules), ["/a/*/*/**", "/a/*/**"]);
+    }
+
+    #[test]
+    fn test_new_segments_after_merge() {
+        let mut clusterer = TransactionClusterer::new(config(1));
+        let now = Utc::now();
+
+        clusterer.record("/items/1", now);
+        clusterer.record("/items/2", now);
+        clusterer.record("/items/3", now);
+
+        assert_eq!(patterns(&clusterer.rules(now)), ["/items/*/**"]);
+        assert_eq!(clusterer.nodes, 3);
+    }
+
+    #[test]
+    fn test_max_nodes() {
+        let mut clusterer = TransactionClusterer::new(TransactionClustererConfig {
+            merge_threshold: 1,
+            max_nodes: 3,
+            ..Default::default()
+        });
+        let now = Utc::now();
+
+        clusterer.record("/a/1", now);
+        assert_eq!(clusterer.nodes, 3);
+
+        // The tree is full, so this segment is dropped.
+        clusterer.record("/a/2", now);
+        assert_eq!(clusterer.nodes, 3);
+        assert!(clusterer.rules(now).is_empty());
+    }
+
+    #[test]
+    fn test_ignore_invalid_names() {
+        let mut clusterer = TransactionClusterer::new(config(1));
+        let now = Utc::now();
+
+        clusterer.record("no-segments", now);
+        clusterer.record("/files/a?b", now);
+        clusterer.record("/files/c*", now);
+        assert_eq!(clusterer.nodes, 0);
+
+        clusterer.record("/files/*", now);
+        assert_eq!(clusterer.nodes, 3);
+    }
+
+    #[test]
+    fn test_rule_expiry() {
+        let config = config(1);
+        let mut clusterer = TransactionClusterer::new(config);
+        let now = Utc::now();
+
+        clusterer.record("/items/1", now);
+        clusterer.record("/items/2", now);
+        assert_eq!(clusterer.rules(now).len(), 1);
+
+        // Rules are refreshed while they are in use.
+        let later = now + config.rule_ttl / 2;
+        clusterer.record("/items/3", later);
+        let rules = clusterer.rules(later);
+        assert_eq!(rules[0].expiry, later + config.rule_ttl);
+
+        // Unused rules are removed.
+        let expired = later + config.rule_ttl;
+        assert!(clusterer.rules(expired).is_empty());
+    }
+}
@@ -18,20 +18,33 @@ use crate::regexes::TRANSACTION_NAME_NORMALIZER_REGEX;
 pub struct TransactionNameConfig<'r> {
     /// Rules for identifier replacement that were discovered by Sentry's transaction clusterer.
     pub rules: &'r [TransactionNameRule],
+    /// Rules discovered by Relay's local transaction clusterer.
+    ///
+    /// These rules only apply if none of the [`rules`](Self::rules) match.
+    pub local_rules: &'r [TransactionNameRule],
 }
 
 /// Apply parametrization to transaction.
 pub fn normalize_transaction_name(
     transaction: &mut Annotated<String>,
     rules: &[TransactionNameRule],
+) {
+    normalize_transaction_name_with_local(transaction, rules, &[]);
+}
+
+/// Like [`normalize_transaction_name`], but falls back to rules of the local clusterer.
+fn normalize_transaction_name_with_local(
+    transaction: &mut Annotated<String>,
+    rules: &[TransactionNameRule],
+    local_rules: &[TransactionNameRule],
 ) {
     // Normalize transaction names for URLs and Sanitized transaction sources.
     // This in addition to renaming rules can catch some high cardinality parts.
     scrub_identifiers(transaction);
 
     // Apply rules discovered by the transaction clusterer in sentry.
-    if !rules.is_empty() {
-        apply_transaction_rename_rules(transaction, rules);
+    if !rules.is_empty() || !local_rules.is_empty() {
+        apply_rename_rules(transaction, rules.iter().chain(local_rules));
     }
 }
 
@@ -47,9 +60,16 @@ pub fn normalize_transaction_name(
 pub fn apply_transaction_rename_rules(
     transaction: &mut Annotated<String>,
     rules: &[TransactionNameRule],
+) {
+    apply_rename_rules(transaction, rules);
+}
+
+fn apply_rename_rules<'a>(
+    transaction: &mut Annotated<String>,
+    rules: impl IntoIterator<Item = &'a TransactionNameRule>,
 ) {
     let _ = processor::apply(transaction, |transaction, meta| {
-        let result = rules.iter().find_map(|rule| {
+        let result = rules.into_iter().find_map(|rule| {
             rule.match_and_apply(Cow::Borrowed(transaction))
                 .map(|applied_result| (rule.pattern.compiled().pattern(), applied_result))
         });
@@ -123,7 +143,11 @@ impl<'r> TransactionsProcessor<'r> {
 
     fn normalize_transaction_name(&self, event: &mut Event) {
         if self.treat_transaction_as_url(event) {
-            normalize_transaction_name(&mut event.transaction, self.name_config.rules);
+            normalize_transaction_name_with_local(
+                &mut event.transaction,
+                self.name_config.rules,
+                self.name_config.local_rules,
+            );
 
             // Always mark URL transactions as sanitized, even if no modification were made by
             // clusterer rules or regex matchers. This has the consequence that the transaction name
@@ -978,6 +1002,7 @@ mod tests {
             &mut event,
             &mut TransactionsProcessor::new_name_config(TransactionNameConfig {
                 rules: &[rule1, rule2, rule3],
+                ..Default::default()
             }),
             ProcessingState::root(),
         )
@@ -1059,6 +1084,7 @@ mod tests {
             &mut event,
             &mut TransactionsProcessor::new_name_config(TransactionNameConfig {
                 rules: &[rule1, rule2, rule3],
+                ..Default::default()
             }),
             ProcessingState::root(),
         )
@@ -1126,6 +1152,7 @@ mod tests {
 
         let mut processor = TransactionsProcessor::new_name_config(TransactionNameConfig {
             rules: rules.as_ref(),
+            ..Default::default()
         });
         process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
 
@@ -1241,6 +1268,7 @@ mod tests {
             &mut event,
             &mut TransactionsProcessor::new_name_config(TransactionNameConfig {
                 rules: rules.as_ref(),
+                ..Default::default()
             }),
             ProcessingState::root(),
         )
@@ -1300,6 +1328,7 @@ mod tests {
             &mut event,
             &mut TransactionsProcessor::new_name_config(TransactionNameConfig {
                 rules: rules.as_ref(),
+                ..Default::default()
             }),
             ProcessingState::root(),
         )
@@ -1383,7 +1412,10 @@ mod tests {
 
         process_value(
             &mut event,
-            &mut TransactionsProcessor::new_name_config(TransactionNameConfig { rules: &[rule] }),
+            &mut TransactionsProcessor::new_name_config(TransactionNameConfig {
+                rules: &[rule],
+                ..Default::default()
+            }),
             ProcessingState::root(),
         )
         .unwrap();
@@ -1413,6 +1445,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_local_rules_apply_after_rules() {
+        let rule = |pattern: &str| TransactionNameRule {
+            pattern: LazyGlob::new(pattern.to_owned()),
+            expiry: Utc::now() + Duration::hours(1),
+            redaction: Default::default(),
+        };
+        let rules = [rule("/foo/*/**")];
+        let local_rules = [rule("/*/bar/**"), rule("/baz/*/**")];
+
+        let run = |name: &str| {
+            let mut event = Annotated::<Event>::from_json(&format!(
+                r#"{{"type": "transaction", "transaction": "{name}", "transaction_info": {{"source": "url"}}}}"#
+            ))
+            .unwrap();
+            process_value(
+                &mut event,
+                &mut TransactionsProcessor::new_name_config(TransactionNameConfig {
+                    rules: &rules,
+                    local_rules: &local_rules,
+                }),
+                ProcessingState::root(),
+            )
+            .unwrap();
+            get_value!(event.transaction!).clone()
+        };
+
+        // Only the first matching rule applies, rules from Sentry take precedence.
+        assert_eq!(run("/foo/bar/qux"), "/foo/*/qux");
+        assert_eq!(run("/baz/bar/qux"), "/*/bar/qux");
+        assert_eq!(run("/baz/qux/bar"), "/baz/*/bar");
+    }
+
     #[test]
     fn test_normalize_transaction_names() {
         let should_be_replaced = [
@@ -1594,6 +1659,7 @@ mod tests {
                     expiry: Utc.with_ymd_and_hms(3000, 1, 1, 1, 1, 1).unwrap(),
                     redaction: RedactionRule::default(),
                 }],
+                ..Default::default()
             }),
             ProcessingState::root(),
         )
@@ -1660,6 +1726,7 @@ mod tests {
                     expiry: Utc.with_ymd_and_hms(3000, 1, 1, 1, 1, 1).unwrap(),
                     redaction: RedactionRule::default(),
                 }],
+                ..Default::default()
             }),
             ProcessingState::root(),
         )
@@ -1,8 +1,13 @@
 use std::borrow::Cow;
+use std::fmt;
+use std::sync::OnceLock;
 
 use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use relay_common::glob2::LazyGlob;
-use relay_event_schema::protocol::OperationType;
+use relay_event_schema::protocol::{OperationType, Span};
+use relay_protocol::RuleCondition;
 use serde::{Deserialize, Serialize};
 
 /// Object containing transaction attributes the rules must only be applied to.
@@ -113,6 +118,154 @@ impl SpanDescriptionRule {
     }
 }
 
+/// A regular expression that is compiled on first use.
+///
+/// Invalid expressions are logged and never match.
+pub struct LazyRegex {
+    raw: String,
+    regex: OnceLock<Option<Regex>>,
+}
+
+impl LazyRegex {
+    /// Creates a new [`LazyRegex`] from the raw string.
+    pub fn new(raw: impl Into<String>) -> Self {
+        Self {
+            raw: raw.into(),
+            regex: OnceLock::new(),
+        }
+    }
+
+    /// Returns the compiled regular expression, or `None` if it is invalid.
+    pub fn compiled(&self) -> Option<&Regex> {
+        self.regex
+            .get_or_init(|| match Regex::new(&self.raw) {
+                Ok(regex) => Some(regex),
+                Err(error) => {
+                    relay_log::error!(
+                        error = &error as &dyn std::error::Error,
+                        "invalid regex pattern"
+                    );
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Returns the regular expression as string.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl Clone for LazyRegex {
+    fn clone(&self) -> Self {
+        Self::new(self.raw.clone())
+    }
+}
+
+impl fmt::Debug for LazyRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LazyRegex({:?})", self.raw)
+    }
+}
+
+impl Serialize for LazyRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for LazyRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(LazyRegex::new)
+    }
+}
+
+/// Matches references to glob wildcards in a replacement template, such as `$1`.
+static TEMPLATE_CAPTURE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$(\d+)").unwrap());
+
+/// The pattern of a [`SpanDescriptionNormalizationRule`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SpanDescriptionPattern {
+    /// A glob pattern that must match the entire description.
+    ///
+    /// Single `*` wildcards can be referenced in the replacement as `$1`, `$2`, etc.
+    Glob(LazyGlob),
+    /// A regular expression that may match anywhere in the description.
+    ///
+    /// All matches are replaced. Capture groups can be referenced in the replacement as `$1` or
+    /// `${name}`.
+    Regex(LazyRegex),
+}
+
+/// A project-defined rule for normalizing span descriptions.
+///
+/// These rules apply before the built-in scrubbers. The first matching rule determines the
+/// normalized description of a span, which is used for `sentry.normalized_description` and the
+/// span group.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanDescriptionNormalizationRule {
+    /// Condition on the span, for example on `span.op` or `span.data.db\.system`.
+    ///
+    /// Defaults to matching all spans.
+    #[serde(default = "RuleCondition::all")]
+    pub condition: RuleCondition,
+    /// The pattern matched against the span description.
+    pub pattern: SpanDescriptionPattern,
+    /// The template for the normalized description.
+    pub replacement: String,
+}
+
+impl SpanDescriptionNormalizationRule {
+    /// Returns the normalized description if the rule matches the span.
+    pub fn apply(&self, span: &Span) -> Option<String> {
+        let description = span.description.as_str()?;
+        if !self.condition.matches(span) {
+            return None;
+        }
+
+        match &self.pattern {
+            SpanDescriptionPattern::Glob(glob) => {
+                let captures = glob.compiled().matches(description)?;
+                let replaced =
+                    TEMPLATE_CAPTURE_REGEX.replace_all(&self.replacement, |c: &regex::Captures| {
+                        c[1].parse::<usize>()
+                            .ok()
+                            .and_then(|i| captures.get(i.checked_sub(1)?))
+                            .map_or_else(|| c[0].to_owned(), |capture| (*capture).to_owned())
+                    });
+                Some(replaced.into_owned())
+            }
+            SpanDescriptionPattern::Regex(regex) => {
+                let regex = regex.compiled()?;
+                if !regex.is_match(description) {
+                    return None;
+                }
+                Some(
+                    regex
+                        .replace_all(description, self.replacement.as_str())
+                        .into_owned(),
+                )
+            }
+        }
+    }
+}
+
+/// Returns the description normalized by the first matching rule.
+pub fn normalize_span_description(
+    span: &Span,
+    rules: &[SpanDescriptionNormalizationRule],
+) -> Option<String> {
+    rules.iter().find_map(|rule| rule.apply(span))
+}
+
 /// The rule describes how transaction name should be changed.
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct TransactionNameRule {
@@ -169,6 +322,8 @@ impl TransactionNameRule {
 
 #[cfg(test)]
 mod tests {
+    use relay_protocol::Annotated;
+
     use super::*;
 
     #[test]
@@ -245,6 +400,83 @@ mod tests {
         assert_eq!(result, "/auth/login/test/".to_owned());
     }
 
+    fn description_rule(json: &str) -> SpanDescriptionNormalizationRule {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn span(op: &str, description: &str) -> Span {
+        Span {
+            op: Annotated::new(op.to_owned()),
+            description: Annotated::new(description.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_description_rule_glob() {
+        let rule = description_rule(
+            r#"{
+                "condition": {"op": "eq", "name": "span.op", "value": "rpc.client"},
+                "pattern": {"type": "glob", "value": "call * on *"},
+                "replacement": "$1.$2 $3"
+            }"#,
+        );
+
+        assert_eq!(
+            rule.apply(&span("rpc.client", "call Users.Get on shard-17")),
+            Some("Users.Get.shard-17 $3".to_owned())
+        );
+        assert_eq!(rule.apply(&span("rpc.client", "Users.Get")), None);
+        assert_eq!(
+            rule.apply(&span("http.client", "call Users.Get on shard-17")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_description_rule_regex() {
+        let rule = description_rule(
+            r#"{
+                "pattern": {"type": "regex", "value": "(?P<service>\\w+)/\\d+"},
+                "replacement": "${service}/{id}"
+            }"#,
+        );
+
+        assert_eq!(
+            rule.apply(&span("rpc.client", "GET users/123 and orders/456")),
+            Some("GET users/{id} and orders/{id}".to_owned())
+        );
+        assert_eq!(rule.apply(&span("rpc.client", "GET users")), None);
+    }
+
+    #[test]
+    fn test_description_rule_invalid_regex() {
+        let rule =
+            description_rule(r#"{"pattern": {"type": "regex", "value": "("}, "replacement": "x"}"#);
+        assert_eq!(rule.apply(&span("rpc.client", "(")), None);
+    }
+
+    #[test]
+    fn test_normalize_span_description_first_match() {
+        let rules = [
+            description_rule(
+                r#"{"pattern": {"type": "glob", "value": "a*"}, "replacement": "first"}"#,
+            ),
+            description_rule(
+                r#"{"pattern": {"type": "glob", "value": "*"}, "replacement": "second"}"#,
+            ),
+        ];
+
+        assert_eq!(
+            normalize_span_description(&span("op", "abc"), &rules).as_deref(),
+            Some("first")
+        );
+        assert_eq!(
+            normalize_span_description(&span("op", "xyz"), &rules).as_deref(),
+            Some("second")
+        );
+    }
+
     #[test]
     fn test_rule_format_roundtrip() {
         let json = r#"{
@@ -1,31 +1,97 @@
 use std::borrow::Cow;
+use std::fmt;
 
 use relay_event_schema::processor::{
     self, Chunk, ProcessValue, ProcessingAction, ProcessingResult, ProcessingState, Processor,
     ValueType,
 };
-use relay_event_schema::protocol::{Frame, RawStacktrace, Replay};
-use relay_protocol::{Annotated, Array, Empty, Meta, Object, RemarkType, Value};
+use relay_event_schema::protocol::{Event, Frame, RawStacktrace, Replay};
+use relay_protocol::{Annotated, Array, Empty, Meta, Object, Remark, RemarkType, Value};
+
+/// Limits for a field, overriding the limits declared in the event schema.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrimmingLimits {
+    /// The maximum size in bytes of databags.
+    pub max_bytes: Option<usize>,
+    /// The maximum depth of databags.
+    pub max_depth: Option<usize>,
+    /// The maximum number of characters of strings.
+    pub max_chars: Option<usize>,
+}
+
+/// Project-specific limits applied by the [`TrimmingProcessor`] in addition to the event schema.
+///
+/// Fields that can never be trimmed, such as the event ID, are never affected.
+pub trait TrimmingPolicy: fmt::Debug {
+    /// Returns the limits for the field at the current path, if any.
+    fn limits(&self, state: &ProcessingState<'_>) -> Option<TrimmingLimits>;
+
+    /// Returns the maximum size of the entire event in bytes.
+    ///
+    /// If the event exceeds this budget after trimming, fields are dropped in
+    /// [`drop_steps`](Self::drop_steps) until the event fits.
+    fn max_event_bytes(&self) -> Option<usize>;
+
+    /// Returns the number of steps in which fields are dropped from events exceeding the budget.
+    fn drop_steps(&self) -> usize;
+
+    /// Returns `true` if the field at the current path is dropped in the given step.
+    fn drops(&self, step: usize, state: &ProcessingState<'_>) -> bool;
+}
 
 #[derive(Clone, Debug)]
 struct SizeState {
     max_depth: Option<usize>,
     encountered_at_depth: usize,
     size_remaining: Option<usize>,
+    /// Whether the project configured `max_depth`, which replaces all enclosing depth limits.
+    depth_from_policy: bool,
+    /// Whether the project configured `max_bytes`, which replaces all enclosing size limits.
+    size_from_policy: bool,
 }
 
 /// Limits properties to a maximum size and depth.
 #[derive(Default)]
-pub struct TrimmingProcessor {
+pub struct TrimmingProcessor<'a> {
     size_state: Vec<SizeState>,
+    policy: Option<&'a dyn TrimmingPolicy>,
+    /// Maximum characters configured by the project for the value currently being processed.
+    max_chars: Option<usize>,
 }
 
-impl TrimmingProcessor {
+impl<'a> TrimmingProcessor<'a> {
     /// Creates a new trimming processor.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Creates a trimming processor that applies the given limits in addition to the schema.
+    pub fn with_policy(policy: &'a dyn TrimmingPolicy) -> Self {
+        Self {
+            size_state: Vec::new(),
+            policy: Some(policy),
+            max_chars: None,
+        }
+    }
+
+    fn limits(&self, state: &ProcessingState<'_>) -> Option<TrimmingLimits> {
+        // Fields that cannot be trimmed are never affected by the policy.
+        if !state.attrs().trim {
+            return None;
+        }
+
+        self.policy.and_then(|policy| policy.limits(state))
+    }
+
+    /// Returns the size states that apply to the current value.
+    ///
+    /// Limits configured by the project replace the limits of all enclosing databags, so that the
+    /// project can raise them as well as lower them. `from_policy` selects the limit in question.
+    fn active_size_states(&self, from_policy: impl Fn(&SizeState) -> bool) -> &[SizeState] {
+        let start = self.size_state.iter().rposition(from_policy).unwrap_or(0);
+        &self.size_state[start..]
+    }
+
     fn should_remove_container<T: Empty>(&self, value: &T, state: &ProcessingState<'_>) -> bool {
         // Heuristic to avoid trimming a value like `[1, 1, 1, 1, ...]` into `[null, null, null,
         // null, ...]`, making it take up more space.
@@ -34,7 +100,7 @@ impl TrimmingProcessor {
 
     #[inline]
     fn remaining_depth(&self, state: &ProcessingState<'_>) -> Option<usize> {
-        self.size_state
+        self.active_size_states(|size_state| size_state.depth_from_policy)
             .iter()
             .filter_map(|size_state| {
                 // The current depth in the entire event payload minus the depth at which we found the
@@ -49,41 +115,53 @@ impl TrimmingProcessor {
 
     #[inline]
     fn remaining_size(&self) -> Option<usize> {
-        self.size_state
+        self.active_size_states(|size_state| size_state.size_from_policy)
             .iter()
             .filter_map(|x| x.size_remaining)
             .min()
     }
 }
 
-impl Processor for TrimmingProcessor {
+impl Processor for TrimmingProcessor<'_> {
     fn before_process<T: ProcessValue>(
         &mut self,
-        _: Option<&T>,
-        _: &mut Meta,
+        value: Option<&T>,
+        meta: &mut Meta,
         state: &ProcessingState<'_>,
     ) -> ProcessingResult {
+        // Limits configured for the project take precedence over the schema. They are looked up
+        // once per value, `process_string` reuses the character limit.
+        let limits = self.limits(state);
+        self.max_chars = limits.and_then(|l| l.max_chars);
+
+        let policy_bytes = limits.and_then(|l| l.max_bytes);
+        let policy_depth = limits.and_then(|l| l.max_depth);
+        let max_bytes = policy_bytes.or(state.attrs().max_bytes);
+        let max_depth = policy_depth.or(state.attrs().max_depth);
+
         // If we encounter a max_bytes or max_depth attribute it
         // resets the size and depth that is permitted below it.
         // XXX(iker): test setting only one of the two attributes.
-        if state.attrs().max_bytes.is_some() || state.attrs().max_depth.is_some() {
+        if max_bytes.is_some() || max_depth.is_some() {
             self.size_state.push(SizeState {
-                size_remaining: state.attrs().max_bytes,
+                size_remaining: max_bytes,
                 encountered_at_depth: state.depth(),
-                max_depth: state.attrs().max_depth,
+                max_depth,
+                depth_from_policy: policy_depth.is_some(),
+                size_from_policy: policy_bytes.is_some(),
             });
         }
 
-        if state.attrs().trim {
-            if self.remaining_size() == Some(0) {
-                // TODO: Create remarks (ensure they do not bloat event)
-                return Err(ProcessingAction::DeleteValueHard);
-            }
-            if self.remaining_depth(state) == Some(0) {
-                // TODO: Create remarks (ensure they do not bloat event)
-                return Err(ProcessingAction::DeleteValueHard);
+        if state.attrs().trim
+            && (self.remaining_size() == Some(0) || self.remaining_depth(state) == Some(0))
+        {
+            // Only annotate removals for projects with a policy to keep default payloads unchanged.
+            if value.is_some() && self.policy.is_some() {
+                meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
             }
+            return Err(ProcessingAction::DeleteValueHard);
         }
+
         Ok(())
     }
 
@@ -128,8 +206,13 @@ impl Processor for TrimmingProcessor {
         meta: &mut Meta,
         state: &ProcessingState<'_>,
     ) -> ProcessingResult {
-        if let Some(max_chars) = state.attrs().max_chars {
-            trim_string(value, meta, max_chars, state.attrs().max_chars_allowance);
+        match self.max_chars.take() {
+            Some(max_chars) => trim_string(value, meta, max_chars, 0),
+            None => {
+                if let Some(max_chars) = state.attrs().max_chars {
+                    trim_string(value, meta, max_chars, state.attrs().max_chars_allowance);
+                }
+            }
         }
 
         if !state.attrs().trim {
@@ -263,6 +346,23 @@ impl Processor for TrimmingProcessor {
         Ok(())
     }
 
+    fn process_event(
+        &mut self,
+        event: &mut Event,
+        _meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        event.process_child_values(self, state)?;
+
+        if let Some(policy) = self.policy {
+            if let Some(max_event_bytes) = policy.max_event_bytes() {
+                enforce_event_budget(event, policy, max_event_bytes, state)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn process_replay(
         &mut self,
         replay: &mut Replay,
@@ -298,6 +398,52 @@ impl Processor for TrimmingProcessor {
     }
 }
 
+/// Drops all trimmable fields selected by the policy in the given step.
+struct DropProcessor<'a> {
+    policy: &'a dyn TrimmingPolicy,
+    step: usize,
+}
+
+impl Processor for DropProcessor<'_> {
+    fn before_process<T: ProcessValue>(
+        &mut self,
+        value: Option<&T>,
+        meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        if value.is_none() || !state.attrs().trim {
+            return Ok(());
+        }
+
+        if self.policy.drops(self.step, state) {
+            meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+            return Err(ProcessingAction::DeleteValueHard);
+        }
+
+        Ok(())
+    }
+}
+
+/// Drops fields in the policy's order until the event fits into `max_bytes`.
+///
+/// If the event still exceeds the budget after dropping all fields, it is retained.
+fn enforce_event_budget(
+    event: &mut Event,
+    policy: &dyn TrimmingPolicy,
+    max_bytes: usize,
+    state: &ProcessingState<'_>,
+) -> ProcessingResult {
+    for step in 0..policy.drop_steps() {
+        if relay_protocol::estimate_size(Some(&*event)) <= max_bytes {
+            break;
+        }
+
+        event.process_child_values(&mut DropProcessor { policy, step }, state)?;
+    }
+
+    Ok(())
+}
+
 /// Trims the string to the given maximum length and updates meta data.
 fn trim_string(value: &mut String, meta: &mut Meta, max_chars: usize, max_chars_allowance: usize) {
     let hard_limit = max_chars + max_chars_allowance;
@@ -439,8 +585,8 @@ mod tests {
     use crate::MaxChars;
     use chrono::DateTime;
     use relay_event_schema::protocol::{
-        Breadcrumb, Context, Contexts, Event, Exception, ExtraValue, SentryTags, Span, SpanId,
-        TagEntry, Tags, Timestamp, TraceId, Values,
+        Breadcrumb, Context, Contexts, Event, Exception, ExtraValue, OsContext, SentryTags, Span,
+        SpanId, TagEntry, Tags, Timestamp, TraceId, Values,
     };
     use relay_protocol::{Map, Remark, SerializableAnnotated, get_value};
     use similar_asserts::assert_eq;
@@ -1118,4 +1264,54 @@ mod tests {
         assert!(get_value!(event.spans[1].start_timestamp).is_some());
         assert!(get_value!(event.spans[1].timestamp).is_some());
     }
+
+    /// A policy without limits, which only enables annotations of removed values.
+    #[derive(Debug)]
+    struct EmptyPolicy;
+
+    impl TrimmingPolicy for EmptyPolicy {
+        fn limits(&self, _: &ProcessingState<'_>) -> Option<TrimmingLimits> {
+            None
+        }
+
+        fn max_event_bytes(&self) -> Option<usize> {
+            None
+        }
+
+        fn drop_steps(&self) -> usize {
+            0
+        }
+
+        fn drops(&self, _: usize, _: &ProcessingState<'_>) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_removed_remark_requires_policy() {
+        let mut contexts = Contexts::new();
+        contexts.add(OsContext {
+            name: Annotated::new("a".repeat(5000)),
+            version: Annotated::new("b".repeat(5000)),
+            kernel_version: Annotated::new("c".to_owned()),
+            ..Default::default()
+        });
+        let contexts = Annotated::new(contexts);
+
+        // Without a policy, removed values are not annotated.
+        let mut trimmed = contexts.clone();
+        let mut processor = TrimmingProcessor::new();
+        processor::process_value(&mut trimmed, &mut processor, ProcessingState::root()).unwrap();
+        let os = trimmed.value().unwrap().get::<OsContext>().unwrap();
+        assert!(os.kernel_version.value().is_none());
+        assert!(os.kernel_version.meta().is_empty());
+
+        let mut trimmed = contexts;
+        let mut processor = TrimmingProcessor::with_policy(&EmptyPolicy);
+        processor::process_value(&mut trimmed, &mut processor, ProcessingState::root()).unwrap();
+        let os = trimmed.value().unwrap().get::<OsContext>().unwrap();
+        assert!(os.kernel_version.value().is_none());
+        let remark = os.kernel_version.meta().iter_remarks().next().unwrap();
+        assert_eq!(remark.rule_id(), "!limit");
+    }
 }
@@ -0,0 +1,92 @@
+use relay_protocol::{Annotated, Empty, FromValue, IntoValue, Object, Value};
+
+use crate::processor::ProcessValue;
+use crate::protocol::Timestamp;
+
+/// Clock drift detected between the SDK and Relay.
+///
+/// Relay adds this context when the `sent_at` timestamp of an envelope deviates from the time it
+/// was received. Depending on the project's clock drift policy, the event's timestamps have either
+/// been corrected by the drift or left unchanged, which is indicated by `corrected`.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
+pub struct ClockDriftContext {
+    /// The event's timestamp as sent by the SDK.
+    pub original_timestamp: Annotated<Timestamp>,
+    /// The event's timestamp after applying the drift.
+    ///
+    /// If the drift was not applied, this is the timestamp the event would have been corrected to.
+    pub corrected_timestamp: Annotated<Timestamp>,
+    /// The signed difference between the receive time and the send time in seconds.
+    ///
+    /// Positive values indicate that the SDK's clock was lagging behind.
+    pub drift: Annotated<f64>,
+    /// Whether the event's timestamps have been corrected by the drift.
+    pub corrected: Annotated<bool>,
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = true)]
+    pub other: Object<Value>,
+}
+
+impl super::DefaultContext for ClockDriftContext {
+    fn default_key() -> &'static str {
+        "clock_drift"
+    }
+
+    fn from_context(context: super::Context) -> Option<Self> {
+        match context {
+            super::Context::ClockDrift(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn cast(context: &super::Context) -> Option<&Self> {
+        match context {
+            super::Context::ClockDrift(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn cast_mut(context: &mut super::Context) -> Option<&mut Self> {
+        match context {
+            super::Context::ClockDrift(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn into_context(self) -> super::Context {
+        super::Context::ClockDrift(Box::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::protocol::Context;
+
+    #[test]
+    fn test_clock_drift_context_roundtrip() {
+        let json = r#"{
+  "original_timestamp": 946684800.0,
+  "corrected_timestamp": 946771200.0,
+  "drift": 86400.0,
+  "corrected": true,
+  "type": "clockdrift"
+}"#;
+        let context = Annotated::new(Context::ClockDrift(Box::new(ClockDriftContext {
+            original_timestamp: Annotated::new(
+                Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap().into(),
+            ),
+            corrected_timestamp: Annotated::new(
+                Utc.with_ymd_and_hms(2000, 1, 2, 0, 0, 0).unwrap().into(),
+            ),
+            drift: Annotated::new(86400.0),
+            corrected: Annotated::new(true),
+            other: Object::default(),
+        })));
+
+        assert_eq!(context, Annotated::from_json(json).unwrap());
+        assert_eq!(json, context.to_json_pretty().unwrap());
+    }
+}
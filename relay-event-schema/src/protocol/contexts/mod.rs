@@ -1,6 +1,7 @@
 mod app;
 mod browser;
 mod chromium_stability_report;
+mod clock_drift;
 mod cloud_resource;
 mod device;
 mod flags;
@@ -22,6 +23,7 @@ mod user_report_v2;
 pub use app::*;
 pub use browser::*;
 pub use chromium_stability_report::*;
+pub use clock_drift::*;
 pub use cloud_resource::*;
 pub use device::*;
 pub use gpu::*;
@@ -102,6 +104,8 @@ pub enum Context {
     OTAUpdates(Box<OTAUpdatesContext>),
     /// Chromium Stability Report from minidump.
     ChromiumStabilityReport(Box<StabilityReportContext>),
+    /// Clock drift detected by Relay.
+    ClockDrift(Box<ClockDriftContext>),
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(fallback_variant)]
     Other(#[metastructure(pii = "true")] Object<Value>),
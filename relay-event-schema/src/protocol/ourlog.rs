@@ -1,5 +1,8 @@
-use relay_protocol::{Annotated, Empty, FromValue, IntoValue, Object, SkipSerialization, Value};
+use relay_protocol::{
+    Annotated, Empty, FromValue, Getter, IntoValue, Object, SkipSerialization, Val, Value,
+};
 use std::fmt::{self, Display};
+use std::ops::Deref;
 
 use serde::{Serialize, Serializer};
 
@@ -38,6 +41,48 @@ pub struct OurLog {
     pub other: Object<Value>,
 }
 
+impl OurLog {
+    /// Returns the value of the attribute with the given key.
+    pub fn attribute(&self, key: &str) -> Option<&Value> {
+        self.attributes.value()?.get_value(key)
+    }
+
+    /// Returns the string value of the attribute with the given key.
+    pub fn attribute_str(&self, key: &str) -> Option<&str> {
+        self.attribute(key)?.as_str()
+    }
+}
+
+impl Getter for OurLog {
+    fn get_value(&self, path: &str) -> Option<Val<'_>> {
+        if let Some(log_prefix) = path.strip_prefix("log.") {
+            return Some(match log_prefix {
+                "body" => self.body.as_str()?.into(),
+                "level" => self.level.value()?.as_str().into(),
+                "trace_id" => self.trace_id.value()?.deref().into(),
+                "span_id" => self.span_id.value()?.into(),
+                path => self.attribute(path.strip_prefix("attributes.")?)?.into(),
+            });
+        }
+
+        // For compatibility with event-based rules, such as generic inbound filters, `event.`
+        // fields are mapped to the corresponding log attributes.
+        Some(match path.strip_prefix("event.")? {
+            "release" => self.attribute_str("sentry.release")?.into(),
+            "environment" => self.attribute_str("sentry.environment")?.into(),
+            "sdk.name" => self.attribute_str("sentry.sdk.name")?.into(),
+            "sdk.version" => self.attribute_str("sentry.sdk.version")?.into(),
+            "contexts.browser.name" => self.attribute_str("sentry.browser.name")?.into(),
+            "contexts.browser.version" => self.attribute_str("sentry.browser.version")?.into(),
+            "user.id" => self.attribute_str("user.id")?.into(),
+            "user.email" => self.attribute_str("user.email")?.into(),
+            "user.name" => self.attribute_str("user.name")?.into(),
+            "logentry.formatted" => self.body.as_str()?.into(),
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum OurLogLevel {
     Trace,
@@ -121,6 +166,38 @@ mod tests {
     use super::*;
     use relay_protocol::SerializableAnnotated;
 
+    #[test]
+    fn test_ourlog_getter() {
+        let json = r#"{
+            "timestamp": 1544719860.0,
+            "trace_id": "5b8efff798038103d269b633813fc60c",
+            "level": "warn",
+            "body": "Example log record",
+            "attributes": {
+                "sentry.release": {"value": "1.0", "type": "string"},
+                "sentry.environment": {"value": "prod", "type": "string"},
+                "http.status_code": {"value": 500, "type": "integer"}
+            }
+        }"#;
+
+        let log = Annotated::<OurLog>::from_json(json).unwrap().0.unwrap();
+        assert_eq!(log.get_value("log.level"), Some(Val::String("warn")));
+        assert_eq!(
+            log.get_value("log.body"),
+            Some(Val::String("Example log record"))
+        );
+        assert_eq!(
+            log.get_value("log.attributes.http.status_code"),
+            Some(Val::I64(500))
+        );
+        assert_eq!(log.get_value("event.release"), Some(Val::String("1.0")));
+        assert_eq!(
+            log.get_value("event.environment"),
+            Some(Val::String("prod"))
+        );
+        assert_eq!(log.get_value("event.transaction"), None);
+    }
+
     #[test]
     fn test_ourlog_serialization() {
         let json = r#"{
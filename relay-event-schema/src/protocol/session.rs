@@ -144,6 +144,12 @@ pub struct SessionAttributes {
     /// The user agent of the user.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_agent: Option<String>,
+
+    /// Clock drift in seconds that Relay detected but did not correct.
+    ///
+    /// Positive values indicate that the SDK's clock was lagging behind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_drift: Option<f64>,
 }
 
 fn default_sequence() -> u64 {
@@ -429,6 +435,7 @@ mod tests {
                 environment: None,
                 ip_address: None,
                 user_agent: None,
+                clock_drift: None,
             },
         };
 
@@ -491,6 +498,7 @@ mod tests {
                 environment: Some("production".to_owned()),
                 ip_address: Some(IpAddr::parse("::1").unwrap()),
                 user_agent: Some("Firefox/72.0".to_owned()),
+                clock_drift: None,
             },
         };
 
@@ -490,6 +490,22 @@ pub struct SpanData {
     #[metastructure(field = "gen_ai.usage.output_tokens.reasoning")]
     pub gen_ai_usage_output_tokens_reasoning: Annotated<Value>,
 
+    /// The input tokens used by an LLM call that were written to the cache
+    #[metastructure(field = "gen_ai.usage.input_tokens.cache_write")]
+    pub gen_ai_usage_input_tokens_cache_write: Annotated<Value>,
+
+    /// The number of images processed or generated by an AI model call
+    #[metastructure(field = "gen_ai.usage.images")]
+    pub gen_ai_usage_images: Annotated<Value>,
+
+    /// The seconds of audio processed or generated by an AI model call
+    #[metastructure(field = "gen_ai.usage.audio_seconds")]
+    pub gen_ai_usage_audio_seconds: Annotated<Value>,
+
+    /// The number of tool calls billed for an AI model call
+    #[metastructure(field = "gen_ai.usage.tool_calls")]
+    pub gen_ai_usage_tool_calls: Annotated<Value>,
+
     // Exact model used to generate the response (e.g. gpt-4o-mini-2024-07-18)
     #[metastructure(field = "gen_ai.response.model")]
     pub gen_ai_response_model: Annotated<Value>,
@@ -502,6 +518,14 @@ pub struct SpanData {
     #[metastructure(field = "gen_ai.usage.total_cost", legacy_alias = "ai.total_cost")]
     pub gen_ai_usage_total_cost: Annotated<Value>,
 
+    /// The cost of the input tokens, including cached tokens
+    #[metastructure(field = "gen_ai.cost.input_tokens")]
+    pub gen_ai_cost_input_tokens: Annotated<Value>,
+
+    /// The cost of the output tokens, including reasoning tokens
+    #[metastructure(field = "gen_ai.cost.output_tokens")]
+    pub gen_ai_cost_output_tokens: Annotated<Value>,
+
     /// Prompt passed to LLM (Vercel AI SDK)
     #[metastructure(field = "gen_ai.prompt", pii = "maybe")]
     pub gen_ai_prompt: Annotated<Value>,
@@ -534,6 +558,10 @@ pub struct SpanData {
     #[metastructure(field = "browser.name")]
     pub browser_name: Annotated<String>,
 
+    /// The client's operating system name.
+    #[metastructure(field = "os.name")]
+    pub os_name: Annotated<String>,
+
     /// The source code file name that identifies the code unit as uniquely as possible.
     #[metastructure(field = "code.filepath", pii = "maybe")]
     pub code_filepath: Annotated<Value>,
@@ -738,6 +766,10 @@ pub struct SpanData {
     #[metastructure(field = "sentry.exclusive_time")]
     pub exclusive_time: Annotated<Value>,
 
+    /// Whether the span is on the critical path of its segment.
+    #[metastructure(field = "sentry.is_critical_path")]
+    pub is_critical_path: Annotated<bool>,
+
     /// Profile ID
     #[metastructure(field = "profile_id")]
     pub profile_id: Annotated<Value>,
@@ -862,6 +894,8 @@ impl Getter for SpanData {
             "gen_ai\\.request\\.max_tokens" => self.gen_ai_request_max_tokens.value()?.into(),
             "gen_ai\\.usage\\.total_tokens" => self.gen_ai_usage_total_tokens.value()?.into(),
             "gen_ai\\.usage\\.total_cost" => self.gen_ai_usage_total_cost.value()?.into(),
+            "gen_ai\\.cost\\.input_tokens" => self.gen_ai_cost_input_tokens.value()?.into(),
+            "gen_ai\\.cost\\.output_tokens" => self.gen_ai_cost_output_tokens.value()?.into(),
             "http\\.decoded_response_content_length" => {
                 self.http_decoded_response_content_length.value()?.into()
             }
@@ -873,6 +907,7 @@ impl Getter for SpanData {
             "http\\.response.status_code" | "status_code" => {
                 self.http_response_status_code.value()?.into()
             }
+            "os\\.name" => self.os_name.as_str()?.into(),
             "resource\\.render_blocking_status" => {
                 self.resource_render_blocking_status.value()?.into()
             }
@@ -1317,9 +1352,15 @@ mod tests {
             gen_ai_usage_input_tokens_cached: ~,
             gen_ai_usage_output_tokens: ~,
             gen_ai_usage_output_tokens_reasoning: ~,
+            gen_ai_usage_input_tokens_cache_write: ~,
+            gen_ai_usage_images: ~,
+            gen_ai_usage_audio_seconds: ~,
+            gen_ai_usage_tool_calls: ~,
             gen_ai_response_model: ~,
             gen_ai_request_model: ~,
             gen_ai_usage_total_cost: ~,
+            gen_ai_cost_input_tokens: ~,
+            gen_ai_cost_output_tokens: ~,
             gen_ai_prompt: ~,
             gen_ai_request_messages: ~,
             gen_ai_tool_input: ~,
@@ -1328,6 +1369,7 @@ mod tests {
             gen_ai_response_text: ~,
             gen_ai_response_object: ~,
             browser_name: ~,
+            os_name: ~,
             code_filepath: String(
                 "task.py",
             ),
@@ -1378,6 +1420,7 @@ mod tests {
             user_name: ~,
             user_roles: ~,
             exclusive_time: ~,
+            is_critical_path: ~,
             profile_id: ~,
             replay_id: ~,
             sdk_name: ~,
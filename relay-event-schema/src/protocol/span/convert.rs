@@ -161,9 +161,15 @@ mod tests {
                 gen_ai_usage_input_tokens_cached: ~,
                 gen_ai_usage_output_tokens: ~,
                 gen_ai_usage_output_tokens_reasoning: ~,
+                gen_ai_usage_input_tokens_cache_write: ~,
+                gen_ai_usage_images: ~,
+                gen_ai_usage_audio_seconds: ~,
+                gen_ai_usage_tool_calls: ~,
                 gen_ai_response_model: ~,
                 gen_ai_request_model: ~,
                 gen_ai_usage_total_cost: ~,
+                gen_ai_cost_input_tokens: ~,
+                gen_ai_cost_output_tokens: ~,
                 gen_ai_prompt: ~,
                 gen_ai_request_messages: ~,
                 gen_ai_tool_input: ~,
@@ -172,6 +178,7 @@ mod tests {
                 gen_ai_response_text: ~,
                 gen_ai_response_object: ~,
                 browser_name: "Chrome",
+                os_name: ~,
                 code_filepath: ~,
                 code_lineno: ~,
                 code_function: ~,
@@ -214,6 +221,7 @@ mod tests {
                 user_name: ~,
                 user_roles: ~,
                 exclusive_time: ~,
+                is_critical_path: ~,
                 profile_id: ~,
                 replay_id: ~,
                 sdk_name: "sentry.php",
@@ -3,8 +3,8 @@
 use url::Url;
 
 use relay_event_schema::protocol::{
-    Csp, Event, EventType, Exception, LogEntry, Replay, SessionAggregates, SessionUpdate, Span,
-    Values,
+    Csp, Event, EventType, Exception, LogEntry, NetworkReportRaw, OurLog, Replay,
+    SessionAggregates, SessionUpdate, Span, Values,
 };
 
 /// A data item to which filters can be applied.
@@ -178,6 +178,91 @@ impl Filterable for Span {
     }
 }
 
+impl Filterable for OurLog {
+    fn csp(&self) -> Option<&Csp> {
+        // Only for events.
+        None
+    }
+
+    fn exceptions(&self) -> Option<&Values<Exception>> {
+        // Only for events.
+        None
+    }
+
+    fn ip_addr(&self) -> Option<&str> {
+        self.attribute_str("client.address")
+    }
+
+    fn logentry(&self) -> Option<&LogEntry> {
+        // Only for events.
+        None
+    }
+
+    fn release(&self) -> Option<&str> {
+        self.attribute_str("sentry.release")
+    }
+
+    fn transaction(&self) -> Option<&str> {
+        None
+    }
+
+    fn url(&self) -> Option<Url> {
+        let url_str = self.attribute_str("url.full")?;
+        Url::parse(url_str).ok()
+    }
+
+    fn user_agent(&self) -> Option<&str> {
+        self.attribute_str("user_agent.original")
+    }
+
+    fn header(&self, _: &str) -> Option<&str> {
+        None
+    }
+}
+
+impl Filterable for NetworkReportRaw {
+    fn csp(&self) -> Option<&Csp> {
+        // Only for events.
+        None
+    }
+
+    fn exceptions(&self) -> Option<&Values<Exception>> {
+        // Only for events.
+        None
+    }
+
+    fn ip_addr(&self) -> Option<&str> {
+        // The report only contains the IP of the server, not of the client.
+        None
+    }
+
+    fn logentry(&self) -> Option<&LogEntry> {
+        // Only for events.
+        None
+    }
+
+    fn release(&self) -> Option<&str> {
+        None
+    }
+
+    fn transaction(&self) -> Option<&str> {
+        None
+    }
+
+    fn url(&self) -> Option<Url> {
+        let url_str = self.url.as_str()?;
+        Url::parse(url_str).ok()
+    }
+
+    fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_str()
+    }
+
+    fn header(&self, _: &str) -> Option<&str> {
+        None
+    }
+}
+
 impl Filterable for SessionUpdate {
     fn csp(&self) -> Option<&Csp> {
         None
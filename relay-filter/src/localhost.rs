@@ -1,5 +1,7 @@
 //! Implements filtering for events originating from the localhost
 
+use relay_event_schema::protocol::NetworkReportRaw;
+
 use crate::{FilterConfig, FilterStatKey, Filterable};
 
 const LOCAL_IPS: &[&str] = &["127.0.0.1", "::1"];
@@ -11,10 +13,8 @@ const HOST_HEADER: &str = "Host";
 
 /// Check if the event originates from the local host.
 fn matches<F: Filterable>(item: &F) -> bool {
-    if let Some(ip_addr) = item.ip_addr() {
-        if LOCAL_IPS.contains(&ip_addr) {
-            return true;
-        }
+    if item.ip_addr().is_some_and(is_local_ip) {
+        return true;
     }
 
     if let Some(url) = item.url() {
@@ -45,6 +45,10 @@ fn matches<F: Filterable>(item: &F) -> bool {
     false
 }
 
+fn is_local_ip(ip_addr: &str) -> bool {
+    LOCAL_IPS.contains(&ip_addr)
+}
+
 fn host_matches_or_is_subdomain_of(host: &str, domain: &str) -> bool {
     host.strip_suffix(domain)
         .is_some_and(|s| s.is_empty() || s.ends_with('.'))
@@ -61,10 +65,30 @@ pub fn should_filter<F: Filterable>(item: &F, config: &FilterConfig) -> Result<(
     Ok(())
 }
 
+/// Filters network error reports of requests to a server on the local host.
+///
+/// The server IP is the address the browser connected to. Unlike the client IP, it is not checked
+/// by [`should_filter`].
+pub fn should_filter_server_ip(
+    report: &NetworkReportRaw,
+    config: &FilterConfig,
+) -> Result<(), FilterStatKey> {
+    if !config.is_enabled {
+        return Ok(());
+    }
+
+    let server_ip = report.body.value().and_then(|body| body.server_ip.value());
+    if server_ip.is_some_and(|ip_addr| is_local_ip(ip_addr.as_str())) {
+        return Err(FilterStatKey::Localhost);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use relay_event_schema::protocol::{
-        Event, HeaderName, HeaderValue, Headers, IpAddr, PairList, Request, User,
+        BodyRaw, Event, HeaderName, HeaderValue, Headers, IpAddr, NetworkReportRaw, PairList,
+        Request, User,
     };
     use relay_protocol::Annotated;
 
@@ -254,4 +278,47 @@ mod tests {
             assert_eq!(filter_result, Ok(()))
         }
     }
+
+    #[test]
+    fn test_filter_nel_report_url() {
+        for (url, expected) in [
+            (
+                "http://localhost:3000/index.html",
+                Err(FilterStatKey::Localhost),
+            ),
+            ("https://example.com/index.html", Ok(())),
+        ] {
+            let report = NetworkReportRaw {
+                url: Annotated::new(url.to_owned()),
+                ..Default::default()
+            };
+            let filter_result = should_filter(&report, &FilterConfig { is_enabled: true });
+            assert_eq!(filter_result, expected, "Unexpected result for '{url}'");
+        }
+    }
+
+    #[test]
+    fn test_filter_nel_report_server_ip() {
+        for (server_ip, expected) in [
+            ("127.0.0.1", Err(FilterStatKey::Localhost)),
+            ("133.12.12.1", Ok(())),
+        ] {
+            let report = NetworkReportRaw {
+                url: Annotated::new("https://example.com/index.html".to_owned()),
+                body: Annotated::new(BodyRaw {
+                    server_ip: Annotated::new(IpAddr(server_ip.to_owned())),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let config = FilterConfig { is_enabled: true };
+            assert_eq!(
+                should_filter_server_ip(&report, &config),
+                expected,
+                "Unexpected result for '{server_ip}'"
+            );
+            // The server IP is not the IP address of the client.
+            assert_eq!(should_filter(&report, &config), Ok(()));
+        }
+    }
 }
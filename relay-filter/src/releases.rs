@@ -22,7 +22,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use relay_event_schema::protocol::{Event, LenientString, Span, SpanData};
+    use relay_event_schema::protocol::{Attributes, Event, LenientString, OurLog, Span, SpanData};
     use relay_protocol::Annotated;
 
     use super::*;
@@ -44,6 +44,15 @@ mod tests {
         }
     }
 
+    fn get_log_for_release(release: &str) -> OurLog {
+        let mut attributes = Attributes::new();
+        attributes.insert("sentry.release".to_owned(), release.to_owned());
+        OurLog {
+            attributes: Annotated::new(attributes),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_release_filtering() {
         let examples = &[
@@ -70,6 +79,7 @@ mod tests {
         for &(release, blocked_releases, expected) in examples {
             let event = get_event_for_release(release);
             let span = get_span_for_release(release);
+            let log = get_log_for_release(release);
 
             let config = ReleasesFilterConfig {
                 releases: blocked_releases.iter().map(|&r| r.to_owned()).collect(),
@@ -90,6 +100,14 @@ mod tests {
                 "Release {release} should have {} been filtered by {blocked_releases:?}",
                 if expected { "" } else { "not" },
             );
+
+            let actual = should_filter(&log, &config) != Ok(());
+            assert_eq!(
+                actual,
+                expected,
+                "Release {release} should have {} been filtered by {blocked_releases:?}",
+                if expected { "" } else { "not" },
+            );
         }
     }
 }
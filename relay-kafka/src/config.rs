@@ -1,7 +1,9 @@
 //! Configuration primitives to configure the kafka producer and properly set up the connection.
 
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 
+use relay_protocol::RuleCondition;
 use serde::{Deserialize, Serialize, de};
 use thiserror::Error;
 
@@ -178,11 +180,90 @@ impl<'de> de::Deserialize<'de> for Unused {
 /// `kafka_config`), an object containing keys `topic_name` and `kafka_config_name` for using a
 /// custom kafka cluster, or an array of topic names/configs for sharded topics.
 ///
+/// Additionally, messages can be routed to other topics and enriched with headers. In this case,
+/// the assignment is an object with the keys `default`, containing any of the above, `routes`, and
+/// `headers`. See [`TopicRoute`] and [`TopicHeaders`].
+///
 /// See documentation for `secondary_kafka_configs` for more information.
-#[derive(Debug, Serialize)]
-pub struct TopicAssignment(Vec<TopicConfig>);
+#[derive(Debug)]
+pub struct TopicAssignment {
+    /// The default topic shards.
+    shards: TopicShards,
+    /// Routing rules, evaluated in order.
+    routes: Vec<TopicRoute>,
+    /// Headers added to every message.
+    headers: TopicHeaders,
+}
 
 impl<'de> de::Deserialize<'de> for TopicAssignment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[derive(Deserialize, Debug)]
+        #[serde(untagged)]
+        enum Inner {
+            Shards(TopicShards),
+            Routed {
+                default: TopicShards,
+                #[serde(default)]
+                routes: Vec<TopicRoute>,
+                #[serde(default)]
+                headers: TopicHeaders,
+            },
+        }
+
+        Ok(match Inner::deserialize(deserializer)? {
+            Inner::Shards(shards) => Self {
+                shards,
+                routes: Vec::new(),
+                headers: TopicHeaders::default(),
+            },
+            Inner::Routed {
+                default,
+                routes,
+                headers,
+            } => Self {
+                shards: default,
+                routes,
+                headers,
+            },
+        })
+    }
+}
+
+impl Serialize for TopicAssignment {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct Routed<'a> {
+            default: &'a TopicShards,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            routes: &'a [TopicRoute],
+            #[serde(skip_serializing_if = "TopicHeaders::is_empty")]
+            headers: &'a TopicHeaders,
+        }
+
+        if self.routes.is_empty() && self.headers.is_empty() {
+            return self.shards.serialize(serializer);
+        }
+
+        Routed {
+            default: &self.shards,
+            routes: &self.routes,
+            headers: &self.headers,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// One or more shards of a topic.
+#[derive(Debug, Serialize)]
+pub struct TopicShards(Vec<TopicConfig>);
+
+impl<'de> de::Deserialize<'de> for TopicShards {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: de::Deserializer<'de>,
@@ -214,6 +295,83 @@ impl<'de> de::Deserialize<'de> for TopicAssignment {
     }
 }
 
+impl TopicShards {
+    fn kafka_params<'a>(
+        &'a self,
+        default_config: &'a Vec<KafkaConfigParam>,
+        secondary_configs: &'a BTreeMap<String, Vec<KafkaConfigParam>>,
+    ) -> Result<Vec<KafkaParams<'a>>, ConfigError> {
+        self.0
+            .iter()
+            .map(|tc| {
+                Ok(KafkaParams {
+                    topic_name: tc.topic_name.clone(),
+                    config_name: tc.kafka_config_name.as_deref(),
+                    params: match &tc.kafka_config_name {
+                        Some(config) => secondary_configs
+                            .get(config)
+                            .ok_or(ConfigError::UnknownKafkaConfigName)?,
+                        None => default_config.as_slice(),
+                    },
+                    key_rate_limit: tc.key_rate_limit,
+                    encoding: &tc.encoding,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A rule that routes matching messages to other topics or clusters.
+///
+/// The condition is evaluated against the fields of a message:
+///
+///  - `organization_id`: The organization the message belongs to.
+///  - `organization_id_str`: The organization id formatted as string.
+///  - `project_id`: The project the message belongs to.
+///  - `project_id_str`: The project id formatted as string.
+///  - `item_type`: The type of the message, for example `event`, `attachment` or `span`.
+///  - `event.platform`: The platform of the event. Only available for event messages.
+///  - `event.sdk.name`: The name of the SDK that sent the event. Only available for event
+///    messages.
+///
+/// Ids are numbers and can be matched with `gte` and `lte`, `eq` only matches strings and booleans.
+/// To route a list of organizations or projects, match the string fields with `eq`, for example
+/// `{"op": "eq", "name": "organization_id_str", "value": ["1", "42"]}`.
+///
+/// The first matching route determines the destination, messages that match no route are
+/// produced to the default topic.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TopicRoute {
+    /// The condition messages must match to be routed.
+    pub condition: RuleCondition,
+    /// The destination of matching messages.
+    pub topic: TopicShards,
+}
+
+/// Headers added to every message produced to a topic.
+///
+/// These headers are added to the headers of the message itself. Static headers take precedence
+/// over headers of the message, dynamic headers take precedence over static headers.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TopicHeaders {
+    /// Headers with a fixed value.
+    #[serde(rename = "static", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub static_headers: BTreeMap<String, String>,
+    /// Headers with the value of a message field, keyed by header name.
+    ///
+    /// Fields are the same as in the conditions of [`TopicRoute`]. If the message does not have
+    /// the field, the header is omitted.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dynamic: BTreeMap<String, String>,
+}
+
+impl TopicHeaders {
+    /// Returns `true` if no headers are configured.
+    pub fn is_empty(&self) -> bool {
+        self.static_headers.is_empty() && self.dynamic.is_empty()
+    }
+}
+
 /// Configuration for topic
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TopicConfig {
@@ -228,6 +386,9 @@ pub struct TopicConfig {
     /// Optionally, a rate limit per partition key to protect against partition imbalance.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     key_rate_limit: Option<KeyRateLimit>,
+    /// The encoding of messages produced to this topic.
+    #[serde(default, skip_serializing_if = "TopicEncoding::is_json")]
+    encoding: TopicEncoding,
 }
 
 impl From<String> for TopicConfig {
@@ -236,6 +397,7 @@ impl From<String> for TopicConfig {
             topic_name,
             kafka_config_name: None,
             key_rate_limit: None,
+            encoding: TopicEncoding::Json,
         }
     }
 }
@@ -252,18 +414,101 @@ pub struct KeyRateLimit {
     pub window_secs: u64,
 }
 
+/// Encoding of the messages produced to a topic.
+///
+/// By default, Relay chooses the encoding for every message type, which is JSON for most messages.
+/// With `avro` or `protobuf`, messages are instead encoded with a schema and prefixed with the
+/// Confluent wire format header: a zero magic byte followed by the schema id as big endian `u32`.
+/// Protobuf messages additionally carry the message indexes of the message type in the schema.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum TopicEncoding {
+    /// The default encoding of the message type.
+    #[default]
+    Json,
+    /// Avro binary encoding with an Avro schema.
+    Avro {
+        /// The source of the Avro schema.
+        schema: SchemaSource,
+    },
+    /// Protobuf encoding with a compiled descriptor set, as emitted by `protoc --descriptor_set_out`.
+    Protobuf {
+        /// The source of the descriptor set.
+        schema: SchemaSource,
+        /// The fully qualified name of the message type, for example `sentry.Event`.
+        message: String,
+    },
+}
+
+impl TopicEncoding {
+    /// Returns `true` if this is the default encoding.
+    pub fn is_json(&self) -> bool {
+        matches!(self, Self::Json)
+    }
+}
+
+/// Location of a schema used by a [`TopicEncoding`].
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum SchemaSource {
+    /// A local schema file and the id under which it is registered.
+    File {
+        /// Path to the schema file.
+        path: PathBuf,
+        /// The schema id written into the message header.
+        id: u32,
+    },
+    /// A subject in a local schema registry.
+    ///
+    /// The registry is a directory that mirrors the latest versions of a schema registry. For
+    /// every subject, it contains a `<subject>.json` file with the schema `id` and either the
+    /// inline `schema` or the `path` to the schema file, relative to the registry directory.
+    Registry {
+        /// Path to the registry directory.
+        registry: PathBuf,
+        /// The subject of the schema.
+        ///
+        /// Defaults to `<topic>-value`, where `<topic>` is the name of the Kafka topic.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subject: Option<String>,
+    },
+}
+
 /// A Kafka config for a topic.
 ///
 /// This internally includes configuration for multiple 'physical' Kafka topics,
 /// as Relay can shard to multiple topics at once.
 #[derive(Debug)]
-pub struct KafkaTopicConfig<'a>(Vec<KafkaParams<'a>>);
+pub struct KafkaTopicConfig<'a> {
+    shards: Vec<KafkaParams<'a>>,
+    routes: Vec<KafkaRouteConfig<'a>>,
+    headers: &'a TopicHeaders,
+}
 
 impl<'a> KafkaTopicConfig<'a> {
     /// Kafka params for each psysical shard.
     pub fn topics(&self) -> &[KafkaParams<'a>] {
-        &self.0
+        &self.shards
+    }
+
+    /// Routing rules with the Kafka params of their destinations.
+    pub fn routes(&self) -> &[KafkaRouteConfig<'a>] {
+        &self.routes
     }
+
+    /// Headers added to every message.
+    pub fn headers(&self) -> &'a TopicHeaders {
+        self.headers
+    }
+}
+
+/// A [`TopicRoute`] with the Kafka params of its destination.
+#[derive(Debug)]
+pub struct KafkaRouteConfig<'a> {
+    /// The condition messages must match to be routed.
+    pub condition: &'a RuleCondition,
+    /// Kafka params for each physical shard of the destination.
+    pub topics: Vec<KafkaParams<'a>>,
 }
 
 /// Config for creating a Kafka producer.
@@ -277,11 +522,17 @@ pub struct KafkaParams<'a> {
     pub params: &'a [KafkaConfigParam],
     /// Optionally, a rate limit per partition key to protect against partition imbalance.
     pub key_rate_limit: Option<KeyRateLimit>,
+    /// The encoding of messages produced to the topic.
+    pub encoding: &'a TopicEncoding,
 }
 
 impl From<String> for TopicAssignment {
     fn from(topic_name: String) -> Self {
-        Self(vec![topic_name.into()])
+        Self {
+            shards: TopicShards(vec![topic_name.into()]),
+            routes: Vec::new(),
+            headers: TopicHeaders::default(),
+        }
     }
 }
 
@@ -295,25 +546,28 @@ impl TopicAssignment {
         default_config: &'a Vec<KafkaConfigParam>,
         secondary_configs: &'a BTreeMap<String, Vec<KafkaConfigParam>>,
     ) -> Result<KafkaTopicConfig<'a>, ConfigError> {
-        let configs = self
-            .0
+        let shards = self
+            .shards
+            .kafka_params(default_config, secondary_configs)?;
+
+        let routes = self
+            .routes
             .iter()
-            .map(|tc| {
-                Ok(KafkaParams {
-                    topic_name: tc.topic_name.clone(),
-                    config_name: tc.kafka_config_name.as_deref(),
-                    params: match &tc.kafka_config_name {
-                        Some(config) => secondary_configs
-                            .get(config)
-                            .ok_or(ConfigError::UnknownKafkaConfigName)?,
-                        None => default_config.as_slice(),
-                    },
-                    key_rate_limit: tc.key_rate_limit,
+            .map(|route| {
+                Ok(KafkaRouteConfig {
+                    condition: &route.condition,
+                    topics: route
+                        .topic
+                        .kafka_params(default_config, secondary_configs)?,
                 })
             })
             .collect::<Result<_, _>>()?;
 
-        Ok(KafkaTopicConfig(configs))
+        Ok(KafkaTopicConfig {
+            shards,
+            routes,
+            headers: &self.headers,
+        })
     }
 }
 
@@ -354,143 +608,263 @@ transactions: "ingest-transactions-kafka-topic"
         let topics: TopicAssignments = serde_yaml::from_str(yaml).unwrap();
         insta::assert_debug_snapshot!(topics, @r###"
         TopicAssignments {
-            events: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-events-kafka-topic",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            attachments: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-attachments",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            transactions: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-transactions-kafka-topic",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            outcomes: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "outcomes",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            outcomes_billing: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "outcomes-billing",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            metrics_sessions: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-metrics-3",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            metrics_generic: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-performance-metrics",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            profiles: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-profiles",
-                        kafka_config_name: Some(
-                            "profiles",
-                        ),
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            replay_events: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-replay-events",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            replay_recordings: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-replay-recordings",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            ourlogs: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "snuba-ourlogs",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            monitors: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-monitors",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            spans: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "snuba-spans",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            feedback: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "ingest-feedback-events",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
-            items: TopicAssignment(
-                [
-                    TopicConfig {
-                        topic_name: "snuba-items",
-                        kafka_config_name: None,
-                        key_rate_limit: None,
-                    },
-                ],
-            ),
+            events: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-events-kafka-topic",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            attachments: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-attachments",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            transactions: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-transactions-kafka-topic",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            outcomes: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "outcomes",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            outcomes_billing: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "outcomes-billing",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            metrics_sessions: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-metrics-3",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            metrics_generic: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-performance-metrics",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            profiles: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-profiles",
+                            kafka_config_name: Some(
+                                "profiles",
+                            ),
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            replay_events: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-replay-events",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            replay_recordings: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-replay-recordings",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            ourlogs: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "snuba-ourlogs",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            monitors: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-monitors",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            spans: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "snuba-spans",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            feedback: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "ingest-feedback-events",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
+            items: TopicAssignment {
+                shards: TopicShards(
+                    [
+                        TopicConfig {
+                            topic_name: "snuba-items",
+                            kafka_config_name: None,
+                            key_rate_limit: None,
+                            encoding: Json,
+                        },
+                    ],
+                ),
+                routes: [],
+                headers: TopicHeaders {
+                    static_headers: {},
+                    dynamic: {},
+                },
+            },
             unused: Unused(
                 [],
             ),
@@ -547,8 +921,8 @@ profiles:
             .expect("Kafka config for sharded events topic");
 
         insta::assert_debug_snapshot!(events_configs, @r###"
-        KafkaTopicConfig(
-            [
+        KafkaTopicConfig {
+            shards: [
                 KafkaParams {
                     topic_name: "ingest-events-1",
                     config_name: None,
@@ -559,6 +933,7 @@ profiles:
                         },
                     ],
                     key_rate_limit: None,
+                    encoding: Json,
                 },
                 KafkaParams {
                     topic_name: "ingest-events-2",
@@ -570,9 +945,15 @@ profiles:
                         },
                     ],
                     key_rate_limit: None,
+                    encoding: Json,
                 },
             ],
-        )
+            routes: [],
+            headers: TopicHeaders {
+                static_headers: {},
+                dynamic: {},
+            },
+        }
         "###);
 
         let profiles_configs = topics
@@ -581,8 +962,8 @@ profiles:
             .expect("Kafka config for sharded profiles topic");
 
         insta::assert_debug_snapshot!(profiles_configs, @r###"
-        KafkaTopicConfig(
-            [
+        KafkaTopicConfig {
+            shards: [
                 KafkaParams {
                     topic_name: "ingest-profiles-1",
                     config_name: Some(
@@ -595,6 +976,7 @@ profiles:
                         },
                     ],
                     key_rate_limit: None,
+                    encoding: Json,
                 },
                 KafkaParams {
                     topic_name: "ingest-profiles-2",
@@ -608,9 +990,15 @@ profiles:
                         },
                     ],
                     key_rate_limit: None,
+                    encoding: Json,
                 },
             ],
-        )
+            routes: [],
+            headers: TopicHeaders {
+                static_headers: {},
+                dynamic: {},
+            },
+        }
         "###);
     }
 
@@ -659,8 +1047,8 @@ events:
             .expect("Kafka config for per-shard rate limits");
 
         insta::assert_debug_snapshot!(events_configs, @r###"
-        KafkaTopicConfig(
-            [
+        KafkaTopicConfig {
+            shards: [
                 KafkaParams {
                     topic_name: "shard-0",
                     config_name: Some(
@@ -678,6 +1066,7 @@ events:
                             window_secs: 60,
                         },
                     ),
+                    encoding: Json,
                 },
                 KafkaParams {
                     topic_name: "shard-1",
@@ -696,6 +1085,7 @@ events:
                             window_secs: 120,
                         },
                     ),
+                    encoding: Json,
                 },
                 KafkaParams {
                     topic_name: "shard-2",
@@ -707,9 +1097,130 @@ events:
                         },
                     ],
                     key_rate_limit: None,
+                    encoding: Json,
                 },
             ],
-        )
+            routes: [],
+            headers: TopicHeaders {
+                static_headers: {},
+                dynamic: {},
+            },
+        }
         "###);
     }
+
+    #[test]
+    fn test_topic_encoding() {
+        let yaml = r#"
+events:
+  name: "ingest-events"
+  encoding:
+    format: avro
+    schema:
+      path: "/etc/relay/schemas/events.avsc"
+      id: 17
+transactions:
+  - name: "ingest-transactions-1"
+    encoding:
+      format: protobuf
+      message: "sentry.Transaction"
+      schema:
+        registry: "/etc/relay/registry"
+  - name: "ingest-transactions-2"
+"#;
+
+        let topics: TopicAssignments = serde_yaml::from_str(yaml).unwrap();
+        let def_config = vec![];
+        let second_config = BTreeMap::new();
+
+        let events = topics
+            .events
+            .kafka_configs(&def_config, &second_config)
+            .unwrap();
+        assert_eq!(
+            events.topics()[0].encoding,
+            &TopicEncoding::Avro {
+                schema: SchemaSource::File {
+                    path: "/etc/relay/schemas/events.avsc".into(),
+                    id: 17,
+                },
+            }
+        );
+
+        let transactions = topics
+            .transactions
+            .kafka_configs(&def_config, &second_config)
+            .unwrap();
+        assert_eq!(
+            transactions.topics()[0].encoding,
+            &TopicEncoding::Protobuf {
+                schema: SchemaSource::Registry {
+                    registry: "/etc/relay/registry".into(),
+                    subject: None,
+                },
+                message: "sentry.Transaction".to_owned(),
+            }
+        );
+        assert_eq!(transactions.topics()[1].encoding, &TopicEncoding::Json);
+
+        // The default encoding is not serialized.
+        let serialized = serde_yaml::to_string(&topics.attachments).unwrap();
+        assert_eq!(serialized, "- name: ingest-attachments\n");
+    }
+
+    #[test]
+    fn test_topic_routes() {
+        let yaml = r#"
+events:
+  default: "ingest-events"
+  routes:
+    - condition:
+        op: eq
+        name: organization_id_str
+        value: ["42", "1337"]
+      topic:
+        name: "ingest-events-dedicated"
+        config: "dedicated"
+  headers:
+    static:
+      region: "us"
+    dynamic:
+      project_id: project_id
+      platform: event.platform
+"#;
+
+        let topics: TopicAssignments = serde_yaml::from_str(yaml).unwrap();
+        let def_config = vec![];
+        let mut second_config = BTreeMap::new();
+        second_config.insert("dedicated".to_owned(), vec![]);
+
+        let events = topics
+            .events
+            .kafka_configs(&def_config, &second_config)
+            .unwrap();
+        assert_eq!(events.topics()[0].topic_name, "ingest-events");
+        assert_eq!(events.routes().len(), 1);
+        assert_eq!(
+            events.routes()[0].topics[0].topic_name,
+            "ingest-events-dedicated"
+        );
+        assert_eq!(events.routes()[0].topics[0].config_name, Some("dedicated"));
+        assert_eq!(events.headers().static_headers["region"], "us");
+        assert_eq!(events.headers().dynamic["platform"], "event.platform");
+
+        // Routes must refer to known configs.
+        assert!(matches!(
+            topics.events.kafka_configs(&def_config, &BTreeMap::new()),
+            Err(ConfigError::UnknownKafkaConfigName)
+        ));
+
+        // Topics without routes and headers keep their plain format.
+        let serialized = serde_yaml::to_string(&topics.transactions).unwrap();
+        assert_eq!(serialized, "- name: ingest-transactions\n");
+
+        let serialized = serde_yaml::to_string(&topics.events).unwrap();
+        let roundtrip: TopicAssignment = serde_yaml::from_str(&serialized).unwrap();
+        assert_eq!(roundtrip.routes.len(), 1);
+        assert!(!roundtrip.headers.is_empty());
+    }
 }
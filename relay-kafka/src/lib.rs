@@ -27,6 +27,9 @@
 //!
 //! If the configuration for the [`KafkaTopic`] was not added, attemps to send the message to this
 //! topic will return the error.
+//!
+//! Messages that fail to produce because of transient broker or producer errors can optionally be
+//! written to a [`KafkaSpool`] on disk, from which they are replayed once producing succeeds again.
 #![warn(missing_docs)]
 #![warn(missing_debug_implementations)]
 #![doc(
@@ -41,9 +44,11 @@ mod debounced;
 mod limits;
 #[cfg(feature = "producer")]
 mod producer;
+mod spool;
 #[cfg(feature = "producer")]
 mod statsd;
 
 pub use config::*;
 #[cfg(feature = "producer")]
 pub use producer::*;
+pub use spool::*;
@@ -0,0 +1,479 @@
+//! Avro binary encoding.
+//!
+//! See <https://avro.apache.org/docs/1.11.1/specification/#binary-encoding>. Logical types are
+//! encoded as their underlying type.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value as JsonValue;
+
+use super::{EncodingError, Value};
+
+/// A parsed Avro schema.
+#[derive(Debug)]
+pub struct Schema {
+    root: Type,
+    /// Named types by their full name.
+    named: BTreeMap<String, Type>,
+}
+
+#[derive(Debug)]
+enum Type {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record(Vec<Field>),
+    Enum(Vec<String>),
+    Array(Box<Type>),
+    Map(Box<Type>),
+    Union(Vec<Type>),
+    Fixed(usize),
+    /// Reference to a named type.
+    Named(String),
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ty: Type,
+    default: Option<Value>,
+}
+
+impl Schema {
+    /// Parses a schema from its JSON representation.
+    pub fn parse(schema: &[u8]) -> Result<Self, EncodingError> {
+        let json =
+            serde_json::from_slice(schema).map_err(|e| EncodingError::AvroSchema(e.to_string()))?;
+
+        let mut named = BTreeMap::new();
+        let root = parse_type(&json, None, &mut named)?;
+        Ok(Self { root, named })
+    }
+
+    /// Appends the binary encoding of the value to the buffer.
+    pub(super) fn encode(&self, value: &Value, buf: &mut Vec<u8>) -> Result<(), EncodingError> {
+        self.encode_type(&self.root, value, buf)
+    }
+
+    fn encode_type(
+        &self,
+        ty: &Type,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), EncodingError> {
+        match (ty, value) {
+            (Type::Named(name), _) => {
+                let ty = self.named.get(name).ok_or_else(|| unknown_type(name))?;
+                self.encode_type(ty, value, buf)?;
+            }
+            (Type::Null, Value::Null) => (),
+            (Type::Boolean, Value::Bool(value)) => buf.push(u8::from(*value)),
+            (Type::Int, _) => {
+                let value = value
+                    .as_i64()
+                    .filter(|v| i32::try_from(*v).is_ok())
+                    .ok_or_else(|| EncodingError::mismatch("int", value))?;
+                write_long(value, buf);
+            }
+            (Type::Long, _) => {
+                let value = value
+                    .as_i64()
+                    .ok_or_else(|| EncodingError::mismatch("long", value))?;
+                write_long(value, buf);
+            }
+            (Type::Float, _) => {
+                let value = value
+                    .as_f64()
+                    .ok_or_else(|| EncodingError::mismatch("float", value))?;
+                buf.extend_from_slice(&(value as f32).to_le_bytes());
+            }
+            (Type::Double, _) => {
+                let value = value
+                    .as_f64()
+                    .ok_or_else(|| EncodingError::mismatch("double", value))?;
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            (Type::Bytes, _) => {
+                let value = value
+                    .as_bytes()
+                    .ok_or_else(|| EncodingError::mismatch("bytes", value))?;
+                write_bytes(value, buf);
+            }
+            (Type::String, Value::String(value)) => write_bytes(value.as_bytes(), buf),
+            (Type::Record(fields), Value::Object(object)) => {
+                for field in fields {
+                    let value = object
+                        .get(&field.name)
+                        .or(field.default.as_ref())
+                        .unwrap_or(&Value::Null);
+
+                    self.encode_type(&field.ty, value, buf)
+                        .map_err(|e| e.within(&field.name))?;
+                }
+            }
+            (Type::Enum(symbols), Value::String(symbol)) => {
+                let index = symbols
+                    .iter()
+                    .position(|s| s == symbol)
+                    .ok_or_else(|| EncodingError::mismatch("enum symbol", value))?;
+                write_long(index as i64, buf);
+            }
+            (Type::Array(items), Value::Array(values)) => {
+                if !values.is_empty() {
+                    write_long(values.len() as i64, buf);
+                    for (index, value) in values.iter().enumerate() {
+                        self.encode_type(items, value, buf)
+                            .map_err(|e| e.within(&index.to_string()))?;
+                    }
+                }
+                write_long(0, buf);
+            }
+            (Type::Map(values_ty), Value::Object(object)) => {
+                if !object.is_empty() {
+                    write_long(object.len() as i64, buf);
+                    for (key, value) in object {
+                        write_bytes(key.as_bytes(), buf);
+                        self.encode_type(values_ty, value, buf)
+                            .map_err(|e| e.within(key))?;
+                    }
+                }
+                write_long(0, buf);
+            }
+            (Type::Union(variants), _) => {
+                // The first variant that can encode the value wins, like in most Avro writers.
+                let mut variant_buf = Vec::new();
+                let index = variants.iter().position(|variant| {
+                    variant_buf.clear();
+                    self.encode_type(variant, value, &mut variant_buf).is_ok()
+                });
+
+                let index = index.ok_or_else(|| EncodingError::mismatch("union", value))?;
+                write_long(index as i64, buf);
+                buf.extend_from_slice(&variant_buf);
+            }
+            (Type::Fixed(size), _) => {
+                let value = value
+                    .as_bytes()
+                    .filter(|v| v.len() == *size)
+                    .ok_or_else(|| EncodingError::mismatch("fixed", value))?;
+                buf.extend_from_slice(value);
+            }
+            (ty, value) => return Err(EncodingError::mismatch(ty.name(), value)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Type {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Int => "int",
+            Self::Long => "long",
+            Self::Float => "float",
+            Self::Double => "double",
+            Self::Bytes => "bytes",
+            Self::String => "string",
+            Self::Record(_) => "record",
+            Self::Enum(_) => "enum",
+            Self::Array(_) => "array",
+            Self::Map(_) => "map",
+            Self::Union(_) => "union",
+            Self::Fixed(_) => "fixed",
+            Self::Named(_) => "named type",
+        }
+    }
+}
+
+fn parse_type(
+    json: &JsonValue,
+    namespace: Option<&str>,
+    named: &mut BTreeMap<String, Type>,
+) -> Result<Type, EncodingError> {
+    match json {
+        JsonValue::String(name) => parse_name(name, namespace, named),
+        JsonValue::Array(variants) => variants
+            .iter()
+            .map(|variant| parse_type(variant, namespace, named))
+            .collect::<Result<_, _>>()
+            .map(Type::Union),
+        JsonValue::Object(object) => {
+            let ty = object.get("type").ok_or_else(|| invalid("missing type"))?;
+
+            match ty.as_str() {
+                Some("record" | "error") => {
+                    let (name, namespace) = full_name(object, namespace)?;
+                    // Register the name first, so that recursive records can reference it.
+                    named.insert(name.clone(), Type::Record(Vec::new()));
+
+                    let fields = object
+                        .get("fields")
+                        .and_then(JsonValue::as_array)
+                        .ok_or_else(|| invalid("record without fields"))?
+                        .iter()
+                        .map(|field| parse_field(field, namespace.as_deref(), named))
+                        .collect::<Result<_, _>>()?;
+
+                    named.insert(name.clone(), Type::Record(fields));
+                    Ok(Type::Named(name))
+                }
+                Some("enum") => {
+                    let (name, _) = full_name(object, namespace)?;
+                    let symbols = object
+                        .get("symbols")
+                        .and_then(JsonValue::as_array)
+                        .ok_or_else(|| invalid("enum without symbols"))?
+                        .iter()
+                        .map(|symbol| symbol.as_str().map(str::to_owned))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid("enum symbols must be strings"))?;
+
+                    named.insert(name.clone(), Type::Enum(symbols));
+                    Ok(Type::Named(name))
+                }
+                Some("fixed") => {
+                    let (name, _) = full_name(object, namespace)?;
+                    let size = object
+                        .get("size")
+                        .and_then(JsonValue::as_u64)
+                        .ok_or_else(|| invalid("fixed without size"))?;
+
+                    named.insert(name.clone(), Type::Fixed(size as usize));
+                    Ok(Type::Named(name))
+                }
+                Some("array") => {
+                    let items = object
+                        .get("items")
+                        .ok_or_else(|| invalid("array without items"))?;
+                    Ok(Type::Array(Box::new(parse_type(items, namespace, named)?)))
+                }
+                Some("map") => {
+                    let values = object
+                        .get("values")
+                        .ok_or_else(|| invalid("map without values"))?;
+                    Ok(Type::Map(Box::new(parse_type(values, namespace, named)?)))
+                }
+                // Primitive types with attributes, such as logical types.
+                _ => parse_type(ty, namespace, named),
+            }
+        }
+        _ => Err(invalid("schema must be a string, array or object")),
+    }
+}
+
+fn parse_field(
+    json: &JsonValue,
+    namespace: Option<&str>,
+    named: &mut BTreeMap<String, Type>,
+) -> Result<Field, EncodingError> {
+    let name = json
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid("field without name"))?;
+    let ty = json
+        .get("type")
+        .ok_or_else(|| invalid("field without type"))?;
+
+    let default = match json.get("default") {
+        Some(default) => Some(
+            serde::Deserialize::deserialize(default)
+                .map_err(|e: serde_json::Error| invalid(&e.to_string()))?,
+        ),
+        None => None,
+    };
+
+    Ok(Field {
+        name: name.to_owned(),
+        ty: parse_type(ty, namespace, named)?,
+        default,
+    })
+}
+
+fn parse_name(
+    name: &str,
+    namespace: Option<&str>,
+    named: &BTreeMap<String, Type>,
+) -> Result<Type, EncodingError> {
+    Ok(match name {
+        "null" => Type::Null,
+        "boolean" => Type::Boolean,
+        "int" => Type::Int,
+        "long" => Type::Long,
+        "float" => Type::Float,
+        "double" => Type::Double,
+        "bytes" => Type::Bytes,
+        "string" => Type::String,
+        _ => {
+            let full_name = match namespace {
+                Some(namespace) if !name.contains('.') => format!("{namespace}.{name}"),
+                _ => name.to_owned(),
+            };
+
+            if named.contains_key(&full_name) {
+                Type::Named(full_name)
+            } else if named.contains_key(name) {
+                Type::Named(name.to_owned())
+            } else {
+                return Err(unknown_type(name));
+            }
+        }
+    })
+}
+
+/// Returns the full name of a named type and the namespace for the types it encloses.
+fn full_name(
+    object: &serde_json::Map<String, JsonValue>,
+    namespace: Option<&str>,
+) -> Result<(String, Option<String>), EncodingError> {
+    let name = object
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid("named type without name"))?;
+
+    if let Some((namespace, _)) = name.rsplit_once('.') {
+        return Ok((name.to_owned(), Some(namespace.to_owned())));
+    }
+
+    let namespace = object
+        .get("namespace")
+        .and_then(JsonValue::as_str)
+        .or(namespace)
+        .filter(|namespace| !namespace.is_empty());
+
+    Ok(match namespace {
+        Some(namespace) => (format!("{namespace}.{name}"), Some(namespace.to_owned())),
+        None => (name.to_owned(), None),
+    })
+}
+
+fn invalid(message: &str) -> EncodingError {
+    EncodingError::AvroSchema(message.to_owned())
+}
+
+fn unknown_type(name: &str) -> EncodingError {
+    EncodingError::AvroSchema(format!("unknown type `{name}`"))
+}
+
+/// Writes a zig-zag encoded variable length integer.
+fn write_long(value: i64, buf: &mut Vec<u8>) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_bytes(value: &[u8], buf: &mut Vec<u8>) {
+    write_long(value.len() as i64, buf);
+    buf.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(schema: &str, value: &str) -> Result<Vec<u8>, EncodingError> {
+        let schema = Schema::parse(schema.as_bytes()).unwrap();
+        let value = serde_json::from_str(value).unwrap();
+
+        let mut buf = Vec::new();
+        schema.encode(&value, &mut buf)?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(encode(r#""long""#, "-64").unwrap(), [0x7f]);
+        assert_eq!(encode(r#""long""#, "64").unwrap(), [0x80, 0x01]);
+        assert_eq!(encode(r#""boolean""#, "true").unwrap(), [1]);
+        assert_eq!(encode(r#""double""#, "1").unwrap(), 1f64.to_le_bytes());
+        assert_eq!(encode(r#""string""#, r#""foo""#).unwrap(), b"\x06foo");
+        assert!(encode(r#""int""#, "4294967296").is_err());
+    }
+
+    #[test]
+    fn test_record() {
+        let schema = r#"{
+            "type": "record",
+            "name": "Event",
+            "namespace": "sentry",
+            "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "level", "type": {"type": "enum", "name": "Level", "symbols": ["info", "error"]}},
+                {"name": "release", "type": ["null", "string"], "default": null},
+                {"name": "tags", "type": {"type": "map", "values": "string"}, "default": {}},
+                {"name": "previous", "type": ["null", "Event"], "default": null}
+            ]
+        }"#;
+
+        let value = r#"{
+            "id": 1,
+            "level": "error",
+            "tags": {"a": "b"},
+            "previous": {"id": 2, "level": "info", "release": "1.0", "unknown": true}
+        }"#;
+
+        insta::assert_debug_snapshot!(encode(schema, value).unwrap(), @r"
+        [
+            2,
+            2,
+            0,
+            2,
+            2,
+            97,
+            2,
+            98,
+            0,
+            2,
+            4,
+            0,
+            2,
+            6,
+            49,
+            46,
+            48,
+            0,
+            0,
+        ]
+        ");
+    }
+
+    #[test]
+    fn test_array() {
+        assert_eq!(
+            encode(r#"{"type": "array", "items": "int"}"#, "[1, 2]").unwrap(),
+            [4, 2, 4, 0]
+        );
+        assert_eq!(
+            encode(r#"{"type": "array", "items": "int"}"#, "[]").unwrap(),
+            [0]
+        );
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let schema =
+            r#"{"type": "record", "name": "Event", "fields": [{"name": "id", "type": "long"}]}"#;
+        let error = encode(schema, "{}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "message does not match the schema at `$.id`: expected long, found null"
+        );
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        assert!(matches!(
+            Schema::parse(br#"{"type": "array", "items": "Missing"}"#),
+            Err(EncodingError::AvroSchema(_))
+        ));
+    }
+}
@@ -0,0 +1,526 @@
+//! Schema-based encodings of Kafka messages.
+//!
+//! Messages produced to topics with an Avro or Protobuf [`TopicEncoding`] are decoded from their
+//! default serialization and encoded again with the topic's schema. The encoded payload is
+//! prefixed with the Confluent wire format header.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use thiserror::Error;
+
+use crate::config::{SchemaSource, TopicEncoding};
+use crate::producer::SerializationOutput;
+
+mod avro;
+mod protobuf;
+
+/// The first byte of every message in the Confluent wire format.
+const MAGIC_BYTE: u8 = 0;
+
+/// Errors when loading schemas or encoding messages.
+#[derive(Debug, Error)]
+pub enum EncodingError {
+    /// Failed to read a schema or schema registry file.
+    #[error("failed to read {}", path.display())]
+    Io {
+        /// The file that could not be read.
+        path: PathBuf,
+        /// The underlying I/O error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// A schema registry entry could not be parsed.
+    #[error("invalid schema registry entry {}", path.display())]
+    RegistryEntry {
+        /// The path of the registry entry.
+        path: PathBuf,
+        /// The underlying parse error.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// A schema registry entry contains neither an inline schema nor a schema path.
+    #[error("schema registry entry {} has no schema", path.display())]
+    MissingSchema {
+        /// The path of the registry entry.
+        path: PathBuf,
+    },
+
+    /// The Avro schema is invalid or uses unsupported features.
+    #[error("invalid avro schema: {0}")]
+    AvroSchema(String),
+
+    /// The Protobuf descriptor set could not be decoded.
+    #[error("invalid protobuf descriptor set")]
+    Descriptor(#[source] prost::DecodeError),
+
+    /// The configured message type is not part of the descriptor set.
+    #[error("unknown protobuf message type `{0}`")]
+    UnknownMessage(String),
+
+    /// A Protobuf message has a different type than the message type of the topic.
+    #[error("protobuf message of type `{found}` does not match message type `{expected}`")]
+    MessageType {
+        /// The message type configured for the topic.
+        expected: String,
+        /// The type of the produced message.
+        found: &'static str,
+    },
+
+    /// The serialized message could not be decoded for encoding.
+    #[error("failed to decode message: {0}")]
+    Decode(String),
+
+    /// The message does not match the schema.
+    #[error("message does not match the schema at `{path}`: expected {expected}, found {found}")]
+    Mismatch {
+        /// Path to the mismatching value, starting with `$` for the message itself.
+        path: String,
+        /// The type expected by the schema.
+        expected: &'static str,
+        /// The type of the value in the message.
+        found: &'static str,
+    },
+}
+
+impl EncodingError {
+    fn mismatch(expected: &'static str, value: &Value) -> Self {
+        Self::Mismatch {
+            path: "$".to_owned(),
+            expected,
+            found: value.kind(),
+        }
+    }
+
+    /// Prefixes the path of a mismatch with the name of the field that contains it.
+    fn within(self, field: &str) -> Self {
+        match self {
+            Self::Mismatch {
+                path,
+                expected,
+                found,
+            } => Self::Mismatch {
+                path: format!("$.{field}{}", &path[1..]),
+                expected,
+                found,
+            },
+            other => other,
+        }
+    }
+}
+
+/// A self-describing value decoded from a JSON or MessagePack message.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Decodes the default serialization of a message.
+    fn decode(output: &SerializationOutput<'_>) -> Result<Self, EncodingError> {
+        match output {
+            SerializationOutput::Json(payload) => {
+                serde_json::from_slice(payload).map_err(|e| EncodingError::Decode(e.to_string()))
+            }
+            SerializationOutput::MsgPack(payload) => {
+                rmp_serde::from_slice(payload).map_err(|e| EncodingError::Decode(e.to_string()))
+            }
+            SerializationOutput::Protobuf { .. } => Err(EncodingError::Decode(
+                "protobuf messages can only be produced with protobuf encoding".to_owned(),
+            )),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "boolean",
+            Self::I64(_) | Self::U64(_) => "integer",
+            Self::F64(_) => "float",
+            Self::String(_) => "string",
+            Self::Bytes(_) => "bytes",
+            Self::Array(_) => "array",
+            Self::Object(_) => "object",
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::I64(value) => Some(value),
+            Self::U64(value) => value.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Self::I64(value) => value.try_into().ok(),
+            Self::U64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::I64(value) => Some(value as f64),
+            Self::U64(value) => Some(value as f64),
+            Self::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::String(value) => Some(value.as_bytes()),
+            Self::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("any value")
+            }
+
+            fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+                Ok(Value::Bool(value))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+                Ok(Value::I64(value))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+                Ok(Value::U64(value))
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+                Ok(Value::F64(value))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+                Ok(Value::String(value.to_owned()))
+            }
+
+            fn visit_string<E>(self, value: String) -> Result<Value, E> {
+                Ok(Value::String(value))
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+                Ok(Value::Bytes(value.to_owned()))
+            }
+
+            fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value, E> {
+                Ok(Value::Bytes(value))
+            }
+
+            fn visit_none<E>(self) -> Result<Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Value::deserialize(deserializer)
+            }
+
+            fn visit_unit<E>(self) -> Result<Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut values = Vec::new();
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(Value::Array(values))
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut values = BTreeMap::new();
+                while let Some((key, value)) = map.next_entry()? {
+                    values.insert(key, value);
+                }
+                Ok(Value::Object(values))
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// An entry of a local schema registry, see [`SchemaSource::Registry`].
+#[derive(Debug, serde::Deserialize)]
+struct RegistryEntry {
+    id: u32,
+    schema: Option<String>,
+    path: Option<PathBuf>,
+}
+
+/// Encodes messages according to the [`TopicEncoding`] of a topic.
+#[derive(Debug)]
+pub enum Encoder {
+    /// Produces the default serialization of messages.
+    Json,
+    /// Encodes messages with an Avro schema.
+    Avro {
+        /// The registered id of the schema.
+        id: u32,
+        /// The parsed schema.
+        schema: avro::Schema,
+    },
+    /// Encodes messages with a Protobuf message type.
+    Protobuf {
+        /// The registered id of the schema.
+        id: u32,
+        /// The message type from the descriptor set.
+        message: protobuf::MessageType,
+    },
+}
+
+impl Encoder {
+    /// Loads the schema of the encoding for the given Kafka topic.
+    pub fn new(encoding: &TopicEncoding, topic_name: &str) -> Result<Self, EncodingError> {
+        Ok(match encoding {
+            TopicEncoding::Json => Self::Json,
+            TopicEncoding::Avro { schema } => {
+                let (id, schema) = load_schema(schema, topic_name)?;
+                let schema = avro::Schema::parse(&schema)?;
+                Self::Avro { id, schema }
+            }
+            TopicEncoding::Protobuf { schema, message } => {
+                let (id, schema) = load_schema(schema, topic_name)?;
+                let message = protobuf::MessageType::parse(&schema, message)?;
+                Self::Protobuf { id, message }
+            }
+        })
+    }
+
+    /// Returns the name of the schema-based encoding, or `None` for JSON.
+    pub fn schema_encoding(&self) -> Option<&'static str> {
+        match self {
+            Self::Json => None,
+            Self::Avro { .. } => Some("avro"),
+            Self::Protobuf { .. } => Some("protobuf"),
+        }
+    }
+
+    /// Encodes a serialized message into the payload produced to Kafka.
+    ///
+    /// Messages that are already serialized to Protobuf are passed through if their message type
+    /// matches the message type of the topic, and rejected otherwise.
+    pub fn encode<'a>(
+        &self,
+        output: SerializationOutput<'a>,
+    ) -> Result<Cow<'a, [u8]>, EncodingError> {
+        match self {
+            Self::Json => Ok(output.into_bytes()),
+            Self::Avro { id, schema } => {
+                let value = Value::decode(&output)?;
+                let mut buf = header(*id);
+                schema.encode(&value, &mut buf)?;
+                Ok(Cow::Owned(buf))
+            }
+            Self::Protobuf { id, message } => {
+                let mut buf = header(*id);
+                message.encode_indexes(&mut buf);
+                match output {
+                    SerializationOutput::Protobuf { message_type, .. }
+                        if message_type != message.name() =>
+                    {
+                        return Err(EncodingError::MessageType {
+                            expected: message.name().to_owned(),
+                            found: message_type,
+                        });
+                    }
+                    SerializationOutput::Protobuf { payload, .. } => {
+                        buf.extend_from_slice(&payload)
+                    }
+                    output => message.encode(&Value::decode(&output)?, &mut buf)?,
+                }
+                Ok(Cow::Owned(buf))
+            }
+        }
+    }
+}
+
+/// Creates a buffer with the Confluent wire format header for the given schema id.
+fn header(id: u32) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(256);
+    buf.push(MAGIC_BYTE);
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf
+}
+
+/// Loads the schema id and the contents of the schema.
+fn load_schema(source: &SchemaSource, topic_name: &str) -> Result<(u32, Vec<u8>), EncodingError> {
+    let (registry, subject) = match source {
+        SchemaSource::File { path, id } => return Ok((*id, read(path)?)),
+        SchemaSource::Registry { registry, subject } => (registry, subject),
+    };
+
+    let path = match subject {
+        Some(subject) => registry.join(format!("{subject}.json")),
+        None => registry.join(format!("{topic_name}-value.json")),
+    };
+
+    let entry: RegistryEntry =
+        serde_json::from_slice(&read(&path)?).map_err(|error| EncodingError::RegistryEntry {
+            path: path.clone(),
+            error,
+        })?;
+
+    let schema = match (entry.schema, entry.path) {
+        (Some(schema), _) => schema.into_bytes(),
+        (None, Some(schema_path)) => read(&registry.join(schema_path))?,
+        (None, None) => return Err(EncodingError::MissingSchema { path }),
+    };
+
+    Ok((entry.id, schema))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, EncodingError> {
+    fs::read(path).map_err(|error| EncodingError::Io {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_passthrough() {
+        let encoder = Encoder::new(&TopicEncoding::Json, "events").unwrap();
+        let payload = encoder
+            .encode(SerializationOutput::MsgPack(Cow::Borrowed(b"\x80")))
+            .unwrap();
+        assert_eq!(payload.as_ref(), b"\x80");
+    }
+
+    #[test]
+    fn test_avro_registry() {
+        let registry = tempfile::tempdir().unwrap();
+        fs::write(
+            registry.path().join("events-value.json"),
+            r#"{"id": 258, "schema": "{\"type\": \"record\", \"name\": \"Event\", \"fields\": [{\"name\": \"id\", \"type\": \"long\"}]}"}"#,
+        )
+        .unwrap();
+
+        let encoding = TopicEncoding::Avro {
+            schema: SchemaSource::Registry {
+                registry: registry.path().to_owned(),
+                subject: None,
+            },
+        };
+        let encoder = Encoder::new(&encoding, "events").unwrap();
+
+        let payload = encoder
+            .encode(SerializationOutput::Json(Cow::Borrowed(br#"{"id": 3}"#)))
+            .unwrap();
+        assert_eq!(payload.as_ref(), &[0, 0, 0, 1, 2, 6]);
+
+        // MessagePack is decoded in the same way.
+        let msgpack = rmp_serde::to_vec_named(&BTreeMap::from([("id", 3)])).unwrap();
+        let payload = encoder
+            .encode(SerializationOutput::MsgPack(Cow::Owned(msgpack)))
+            .unwrap();
+        assert_eq!(payload.as_ref(), &[0, 0, 0, 1, 2, 6]);
+    }
+
+    #[test]
+    fn test_registry_schema_path() {
+        let registry = tempfile::tempdir().unwrap();
+        fs::write(registry.path().join("schema.avsc"), r#""string""#).unwrap();
+        fs::write(
+            registry.path().join("custom.json"),
+            r#"{"id": 7, "path": "schema.avsc"}"#,
+        )
+        .unwrap();
+
+        let encoding = TopicEncoding::Avro {
+            schema: SchemaSource::Registry {
+                registry: registry.path().to_owned(),
+                subject: Some("custom".to_owned()),
+            },
+        };
+        let encoder = Encoder::new(&encoding, "events").unwrap();
+
+        let payload = encoder
+            .encode(SerializationOutput::Json(Cow::Borrowed(br#""ab""#)))
+            .unwrap();
+        assert_eq!(payload.as_ref(), &[0, 0, 0, 0, 7, 4, b'a', b'b']);
+    }
+
+    #[test]
+    fn test_missing_schema_file() {
+        let encoding = TopicEncoding::Avro {
+            schema: SchemaSource::File {
+                path: "/does/not/exist.avsc".into(),
+                id: 1,
+            },
+        };
+        assert!(matches!(
+            Encoder::new(&encoding, "events"),
+            Err(EncodingError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_mismatch_path() {
+        let schema = avro::Schema::parse(
+            br#"{
+                "type": "record",
+                "name": "Event",
+                "fields": [{
+                    "name": "user",
+                    "type": {"type": "record", "name": "User", "fields": [{"name": "id", "type": "long"}]}
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let value = Value::decode(&SerializationOutput::Json(Cow::Borrowed(
+            br#"{"user": {"id": "abc"}}"#,
+        )))
+        .unwrap();
+
+        let error = schema.encode(&value, &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "message does not match the schema at `$.user.id`: expected long, found string"
+        );
+    }
+}
@@ -0,0 +1,539 @@
+//! Protobuf encoding based on a compiled descriptor set.
+//!
+//! Values are mapped to fields by their name or JSON name. Unknown values are ignored and null
+//! values are omitted. Repeated scalar fields are encoded unpacked, which every Protobuf parser
+//! accepts.
+
+use std::collections::BTreeMap;
+
+use prost::Message as _;
+use prost::encoding::{self, WireType};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+
+use super::{EncodingError, Value};
+
+/// A message type resolved from a descriptor set.
+#[derive(Debug)]
+pub struct MessageType {
+    /// Fully qualified name of the message type, with a leading `.`.
+    name: String,
+    /// Indexes of the message type within its file, as required by the Confluent wire format.
+    indexes: Vec<i32>,
+    /// All message types in the descriptor set by their fully qualified name.
+    messages: BTreeMap<String, DescriptorProto>,
+    /// All enum types in the descriptor set by their fully qualified name.
+    enums: BTreeMap<String, EnumDescriptorProto>,
+}
+
+impl MessageType {
+    /// Decodes a descriptor set and resolves the message type with the given name.
+    pub fn parse(descriptor_set: &[u8], name: &str) -> Result<Self, EncodingError> {
+        let descriptor_set =
+            FileDescriptorSet::decode(descriptor_set).map_err(EncodingError::Descriptor)?;
+
+        let mut collector = Collector::default();
+        for file in descriptor_set.file {
+            let scope = match file.package() {
+                "" => String::new(),
+                package => format!(".{package}"),
+            };
+
+            collector.collect_enums(&scope, file.enum_type);
+            for (index, message) in file.message_type.into_iter().enumerate() {
+                collector.collect_message(&scope, message, vec![index as i32]);
+            }
+        }
+
+        let name = format!(".{}", name.trim_start_matches('.'));
+        let indexes = collector
+            .indexes
+            .remove(&name)
+            .ok_or_else(|| EncodingError::UnknownMessage(name[1..].to_owned()))?;
+
+        Ok(Self {
+            name,
+            indexes,
+            messages: collector.messages,
+            enums: collector.enums,
+        })
+    }
+
+    /// Returns the fully qualified name of the message type without a leading `.`.
+    pub fn name(&self) -> &str {
+        &self.name[1..]
+    }
+
+    /// Writes the message indexes that follow the schema id in the Confluent wire format.
+    pub fn encode_indexes(&self, buf: &mut Vec<u8>) {
+        // The common case of the first message in the file is abbreviated to a single zero.
+        if self.indexes == [0] {
+            buf.push(0);
+            return;
+        }
+
+        encoding::encode_varint(zigzag(self.indexes.len() as i64), buf);
+        for index in &self.indexes {
+            encoding::encode_varint(zigzag((*index).into()), buf);
+        }
+    }
+
+    /// Appends the Protobuf encoding of the value to the buffer.
+    pub(super) fn encode(&self, value: &Value, buf: &mut Vec<u8>) -> Result<(), EncodingError> {
+        self.encode_message(&self.messages[&self.name], value, buf)
+    }
+
+    fn encode_message(
+        &self,
+        descriptor: &DescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), EncodingError> {
+        let Value::Object(object) = value else {
+            return Err(EncodingError::mismatch("message", value));
+        };
+
+        for field in &descriptor.field {
+            let value = object
+                .get(field.name())
+                .or_else(|| object.get(field.json_name()));
+
+            let Some(value) = value.filter(|value| **value != Value::Null) else {
+                continue;
+            };
+
+            self.encode_field(field, value, buf)
+                .map_err(|e| e.within(field.name()))?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_field(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), EncodingError> {
+        if field.label() != Label::Repeated {
+            return self.encode_single(field, value, buf);
+        }
+
+        if let Some(entry) = self.map_entry(field) {
+            let Value::Object(object) = value else {
+                return Err(EncodingError::mismatch("map", value));
+            };
+
+            let (Some(key_field), Some(value_field)) = (entry.field.first(), entry.field.get(1))
+            else {
+                return Err(EncodingError::mismatch("map entry", value));
+            };
+
+            for (key, value) in object {
+                let mut entry_buf = Vec::new();
+                self.encode_single(key_field, &map_key(key_field, key), &mut entry_buf)
+                    .map_err(|e| e.within(key))?;
+                self.encode_single(value_field, value, &mut entry_buf)
+                    .map_err(|e| e.within(key))?;
+                write_length_delimited(field.number(), &entry_buf, buf);
+            }
+
+            return Ok(());
+        }
+
+        let Value::Array(values) = value else {
+            return Err(EncodingError::mismatch("array", value));
+        };
+
+        for (index, value) in values.iter().enumerate() {
+            self.encode_single(field, value, buf)
+                .map_err(|e| e.within(&index.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_single(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), EncodingError> {
+        let tag = field.number() as u32;
+        let mismatch = |expected| EncodingError::mismatch(expected, value);
+
+        match field.r#type() {
+            Type::Double => {
+                let value = value.as_f64().ok_or_else(|| mismatch("double"))?;
+                encoding::double::encode(tag, &value, buf);
+            }
+            Type::Float => {
+                let value = value.as_f64().ok_or_else(|| mismatch("float"))?;
+                encoding::float::encode(tag, &(value as f32), buf);
+            }
+            Type::Int64 | Type::Sint64 | Type::Sfixed64 => {
+                let value = value.as_i64().ok_or_else(|| mismatch("int64"))?;
+                match field.r#type() {
+                    Type::Sint64 => encoding::sint64::encode(tag, &value, buf),
+                    Type::Sfixed64 => encoding::sfixed64::encode(tag, &value, buf),
+                    _ => encoding::int64::encode(tag, &value, buf),
+                }
+            }
+            Type::Uint64 | Type::Fixed64 => {
+                let value = value.as_u64().ok_or_else(|| mismatch("uint64"))?;
+                match field.r#type() {
+                    Type::Fixed64 => encoding::fixed64::encode(tag, &value, buf),
+                    _ => encoding::uint64::encode(tag, &value, buf),
+                }
+            }
+            Type::Int32 | Type::Sint32 | Type::Sfixed32 => {
+                let value = value
+                    .as_i64()
+                    .and_then(|v| i32::try_from(v).ok())
+                    .ok_or_else(|| mismatch("int32"))?;
+                match field.r#type() {
+                    Type::Sint32 => encoding::sint32::encode(tag, &value, buf),
+                    Type::Sfixed32 => encoding::sfixed32::encode(tag, &value, buf),
+                    _ => encoding::int32::encode(tag, &value, buf),
+                }
+            }
+            Type::Uint32 | Type::Fixed32 => {
+                let value = value
+                    .as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or_else(|| mismatch("uint32"))?;
+                match field.r#type() {
+                    Type::Fixed32 => encoding::fixed32::encode(tag, &value, buf),
+                    _ => encoding::uint32::encode(tag, &value, buf),
+                }
+            }
+            Type::Bool => {
+                let Value::Bool(value) = value else {
+                    return Err(mismatch("bool"));
+                };
+                encoding::bool::encode(tag, value, buf);
+            }
+            Type::String => {
+                let Value::String(value) = value else {
+                    return Err(mismatch("string"));
+                };
+                encoding::string::encode(tag, value, buf);
+            }
+            Type::Bytes => {
+                let value = value.as_bytes().ok_or_else(|| mismatch("bytes"))?;
+                write_length_delimited(field.number(), value, buf);
+            }
+            Type::Enum => {
+                let number = match value {
+                    Value::String(name) => self
+                        .enums
+                        .get(field.type_name())
+                        .and_then(|e| e.value.iter().find(|v| v.name() == name))
+                        .map(|v| v.number()),
+                    value => value.as_i64().and_then(|v| i32::try_from(v).ok()),
+                };
+                let number = number.ok_or_else(|| mismatch("enum value"))?;
+                encoding::int32::encode(tag, &number, buf);
+            }
+            Type::Message => {
+                let descriptor = self
+                    .messages
+                    .get(field.type_name())
+                    .ok_or_else(|| mismatch("message"))?;
+
+                let mut message_buf = Vec::new();
+                self.encode_message(descriptor, value, &mut message_buf)?;
+                write_length_delimited(field.number(), &message_buf, buf);
+            }
+            Type::Group => return Err(mismatch("group")),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the entry type if the field is a map field.
+    fn map_entry(&self, field: &FieldDescriptorProto) -> Option<&DescriptorProto> {
+        if field.r#type() != Type::Message {
+            return None;
+        }
+
+        self.messages
+            .get(field.type_name())
+            .filter(|message| message.options.as_ref().is_some_and(|o| o.map_entry()))
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    messages: BTreeMap<String, DescriptorProto>,
+    enums: BTreeMap<String, EnumDescriptorProto>,
+    indexes: BTreeMap<String, Vec<i32>>,
+}
+
+impl Collector {
+    fn collect_message(&mut self, scope: &str, mut message: DescriptorProto, indexes: Vec<i32>) {
+        let name = format!("{scope}.{}", message.name());
+
+        self.collect_enums(&name, std::mem::take(&mut message.enum_type));
+        for (index, nested) in std::mem::take(&mut message.nested_type)
+            .into_iter()
+            .enumerate()
+        {
+            let mut nested_indexes = indexes.clone();
+            nested_indexes.push(index as i32);
+            self.collect_message(&name, nested, nested_indexes);
+        }
+
+        self.indexes.insert(name.clone(), indexes);
+        self.messages.insert(name, message);
+    }
+
+    fn collect_enums(&mut self, scope: &str, enums: Vec<EnumDescriptorProto>) {
+        for enum_type in enums {
+            self.enums
+                .insert(format!("{scope}.{}", enum_type.name()), enum_type);
+        }
+    }
+}
+
+/// Converts the string key of an object into the type of a map key field.
+fn map_key(field: &FieldDescriptorProto, key: &str) -> Value {
+    let value = match field.r#type() {
+        Type::String => None,
+        Type::Bool => key.parse().ok().map(Value::Bool),
+        Type::Uint32 | Type::Uint64 | Type::Fixed32 | Type::Fixed64 => {
+            key.parse().ok().map(Value::U64)
+        }
+        _ => key.parse().ok().map(Value::I64),
+    };
+
+    value.unwrap_or_else(|| Value::String(key.to_owned()))
+}
+
+fn write_length_delimited(number: i32, value: &[u8], buf: &mut Vec<u8>) {
+    encoding::encode_key(number as u32, WireType::LengthDelimited, buf);
+    encoding::encode_varint(value.len() as u64, buf);
+    buf.extend_from_slice(value);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+
+    use prost_types::{EnumValueDescriptorProto, FileDescriptorProto, MessageOptions};
+
+    use super::*;
+    use crate::producer::SerializationOutput;
+    use crate::producer::encoding::Encoder;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Event {
+        #[prost(string, tag = "1")]
+        event_id: String,
+        #[prost(int64, tag = "2")]
+        timestamp: i64,
+        #[prost(int32, tag = "3")]
+        level: i32,
+        #[prost(message, repeated, tag = "4")]
+        tags: Vec<Tag>,
+        #[prost(map = "string, double", tag = "5")]
+        measurements: HashMap<String, f64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Tag {
+        #[prost(string, tag = "1")]
+        key: String,
+        #[prost(string, tag = "2")]
+        value: String,
+    }
+
+    fn field(name: &str, number: i32, ty: Type, type_name: Option<&str>) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(Label::Optional.into()),
+            r#type: Some(ty.into()),
+            type_name: type_name.map(str::to_owned),
+            ..Default::default()
+        }
+    }
+
+    fn repeated(field: FieldDescriptorProto) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            label: Some(Label::Repeated.into()),
+            ..field
+        }
+    }
+
+    fn descriptor_set() -> Vec<u8> {
+        let tag = DescriptorProto {
+            name: Some("Tag".to_owned()),
+            field: vec![
+                field("key", 1, Type::String, None),
+                field("value", 2, Type::String, None),
+            ],
+            ..Default::default()
+        };
+
+        let measurements_entry = DescriptorProto {
+            name: Some("MeasurementsEntry".to_owned()),
+            field: vec![
+                field("key", 1, Type::String, None),
+                field("value", 2, Type::Double, None),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let level = EnumDescriptorProto {
+            name: Some("Level".to_owned()),
+            value: vec![
+                EnumValueDescriptorProto {
+                    name: Some("INFO".to_owned()),
+                    number: Some(0),
+                    ..Default::default()
+                },
+                EnumValueDescriptorProto {
+                    name: Some("ERROR".to_owned()),
+                    number: Some(4),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let event = DescriptorProto {
+            name: Some("Event".to_owned()),
+            field: vec![
+                field("event_id", 1, Type::String, None),
+                field("timestamp", 2, Type::Int64, None),
+                field("level", 3, Type::Enum, Some(".test.Event.Level")),
+                repeated(field("tags", 4, Type::Message, Some(".test.Event.Tag"))),
+                repeated(field(
+                    "measurements",
+                    5,
+                    Type::Message,
+                    Some(".test.Event.MeasurementsEntry"),
+                )),
+            ],
+            nested_type: vec![tag, measurements_entry],
+            enum_type: vec![level],
+            ..Default::default()
+        };
+
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("test.proto".to_owned()),
+                package: Some("test".to_owned()),
+                message_type: vec![event],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_encode() {
+        let message = MessageType::parse(&descriptor_set(), "test.Event").unwrap();
+        let value = serde_json::from_str(
+            r#"{
+                "event_id": "abc",
+                "timestamp": 1700000000,
+                "level": "ERROR",
+                "tags": [{"key": "a", "value": "b"}],
+                "measurements": {"lcp": 1.5},
+                "release": null,
+                "unknown": 42
+            }"#,
+        )
+        .unwrap();
+
+        let mut buf = Vec::new();
+        message.encode(&value, &mut buf).unwrap();
+
+        assert_eq!(
+            Event::decode(buf.as_slice()).unwrap(),
+            Event {
+                event_id: "abc".to_owned(),
+                timestamp: 1700000000,
+                level: 4,
+                tags: vec![Tag {
+                    key: "a".to_owned(),
+                    value: "b".to_owned(),
+                }],
+                measurements: HashMap::from([("lcp".to_owned(), 1.5)]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut buf = Vec::new();
+        MessageType::parse(&descriptor_set(), "test.Event")
+            .unwrap()
+            .encode_indexes(&mut buf);
+        assert_eq!(buf, [0]);
+
+        let mut buf = Vec::new();
+        MessageType::parse(&descriptor_set(), ".test.Event.Tag")
+            .unwrap()
+            .encode_indexes(&mut buf);
+        assert_eq!(buf, [4, 0, 0]);
+    }
+
+    #[test]
+    fn test_unknown_message() {
+        assert!(matches!(
+            MessageType::parse(&descriptor_set(), "test.Missing"),
+            Err(EncodingError::UnknownMessage(name)) if name == "test.Missing"
+        ));
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let message = MessageType::parse(&descriptor_set(), "test.Event").unwrap();
+        let value = serde_json::from_str(r#"{"tags": [{"key": 1}]}"#).unwrap();
+
+        let error = message.encode(&value, &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "message does not match the schema at `$.tags.0.key`: expected string, found integer"
+        );
+    }
+
+    #[test]
+    fn test_passthrough_message_type() {
+        let encoder = Encoder::Protobuf {
+            id: 1,
+            message: MessageType::parse(&descriptor_set(), "test.Event").unwrap(),
+        };
+
+        let payload = encoder
+            .encode(SerializationOutput::Protobuf {
+                message_type: "test.Event",
+                payload: Cow::Borrowed(b"\x10\x01"),
+            })
+            .unwrap();
+        assert_eq!(payload.as_ref(), [0, 0, 0, 0, 1, 0, 0x10, 0x01]);
+
+        let error = encoder
+            .encode(SerializationOutput::Protobuf {
+                message_type: "test.Other",
+                payload: Cow::Borrowed(b"\x10\x01"),
+            })
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "protobuf message of type `test.Other` does not match message type `test.Event`"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! This module contains the Kafka producer related code.
 
 use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::hash::Hasher as _;
@@ -12,18 +13,25 @@ use hash32::{FnvHasher, Hasher};
 use rdkafka::ClientConfig;
 use rdkafka::message::Header;
 use rdkafka::producer::{BaseRecord, Producer as _};
+use relay_protocol::{Getter, RuleCondition, Val};
 use relay_statsd::metric;
 use thiserror::Error;
 
 use crate::KafkaTopicConfig;
-use crate::config::{KafkaParams, KafkaTopic};
+use crate::config::{KafkaParams, KafkaTopic, TopicHeaders};
 use crate::debounced::Debounced;
 use crate::limits::KafkaRateLimits;
 use crate::producer::utils::KafkaHeaders;
-use crate::statsd::{KafkaCounters, KafkaGauges, KafkaHistograms};
+use crate::spool::{KafkaSpool, SpoolDestination};
+use crate::statsd::{KafkaCounters, KafkaGauges, KafkaHistograms, KafkaTimers};
 
+mod encoding;
+mod replay;
 mod utils;
-use utils::{Context, ThreadedProducer};
+use encoding::Encoder;
+pub use encoding::EncodingError;
+use replay::SpoolReplay;
+use utils::{Context, DeliveryOpaque, MessageOrigin, ThreadedProducer};
 
 #[cfg(feature = "schemas")]
 mod schemas;
@@ -75,6 +83,48 @@ pub enum ClientError {
     /// because the buffer is too small.
     #[error("failed to encode protobuf because the buffer is too small")]
     ProtobufEncodingFailed,
+
+    /// Failed to load the schema for the encoding of a topic.
+    #[error("failed to load schema for topic {0}")]
+    InvalidSchema(String, #[source] EncodingError),
+
+    /// Failed to encode the message with the schema of the topic.
+    #[error("failed to encode kafka message")]
+    EncodingFailed(#[source] EncodingError),
+}
+
+/// The default serialization of a [`Message`].
+#[derive(Debug)]
+pub enum SerializationOutput<'a> {
+    /// A JSON document.
+    Json(Cow<'a, [u8]>),
+    /// A MessagePack document.
+    MsgPack(Cow<'a, [u8]>),
+    /// An encoded Protobuf message.
+    Protobuf {
+        /// Fully qualified name of the message type, such as `sentry_protos.snuba.v1.TraceItem`.
+        message_type: &'static str,
+        /// The encoded message.
+        payload: Cow<'a, [u8]>,
+    },
+}
+
+impl<'a> SerializationOutput<'a> {
+    /// Returns the serialized bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Json(bytes) | Self::MsgPack(bytes) => bytes,
+            Self::Protobuf { payload, .. } => payload,
+        }
+    }
+
+    /// Consumes the output and returns the serialized bytes.
+    pub fn into_bytes(self) -> Cow<'a, [u8]> {
+        match self {
+            Self::Json(bytes) | Self::MsgPack(bytes) => bytes,
+            Self::Protobuf { payload, .. } => payload,
+        }
+    }
 }
 
 /// Describes the type which can be sent using kafka producer provided by this crate.
@@ -88,12 +138,23 @@ pub trait Message {
     /// Return the list of headers to be provided when payload is sent to Kafka.
     fn headers(&self) -> Option<&BTreeMap<String, String>>;
 
-    /// Serializes the message into its binary format.
+    /// Returns the fields of the message used by routing rules and dynamic headers.
+    ///
+    /// See [`TopicRoute`](crate::TopicRoute) for the fields messages should provide. Messages
+    /// without fields are never routed and receive no dynamic headers.
+    fn fields(&self) -> Option<&dyn Getter> {
+        None
+    }
+
+    /// Serializes the message into its default binary format.
+    ///
+    /// Topics with a schema-based [`TopicEncoding`](crate::TopicEncoding) decode this output and
+    /// encode it again with their schema.
     ///
     /// # Errors
     /// Returns the [`ClientError::InvalidMsgPack`] or [`ClientError::InvalidJson`] if the
     /// serialization failed.
-    fn serialize(&self) -> Result<Cow<'_, [u8]>, ClientError>;
+    fn serialize(&self) -> Result<SerializationOutput<'_>, ClientError>;
 }
 
 struct TopicProducers {
@@ -153,54 +214,197 @@ impl TopicProducers {
 
 struct TopicProducer {
     pub topic_name: String,
+    /// Name of the Kafka config the topic is produced to, `None` for the default config.
+    pub config_name: Option<String>,
     pub producer: Arc<ThreadedProducer>,
     pub rate_limiter: Option<KafkaRateLimits>,
+    pub encoder: Encoder,
 }
 
 /// Single kafka producer config with assigned topic.
 struct Producer {
     /// Topic to producer and rate limiter mappings for sharding.
     topic_producers: TopicProducers,
+    /// Routing rules with the producers of their destinations.
+    routes: Vec<(RuleCondition, TopicProducers)>,
+    /// Headers added to every message.
+    headers: TopicHeaders,
     /// Debouncer for metrics.
     metrics: Debounced,
+    /// Spool for messages which failed to be enqueued.
+    spool: Option<Arc<KafkaSpool>>,
 }
 
 impl Producer {
-    fn new(topic_producers: TopicProducers) -> Self {
+    fn new(
+        topic_producers: TopicProducers,
+        routes: Vec<(RuleCondition, TopicProducers)>,
+        headers: TopicHeaders,
+        spool: Option<Arc<KafkaSpool>>,
+    ) -> Self {
         Self {
             topic_producers,
+            routes,
+            headers,
             metrics: Debounced::new(REPORT_FREQUENCY_SECS),
+            spool,
+        }
+    }
+
+    /// Returns the producers of the default topic and of all routes.
+    fn all_topic_producers(&self) -> impl Iterator<Item = &TopicProducers> {
+        std::iter::once(&self.topic_producers).chain(self.routes.iter().map(|(_, tp)| tp))
+    }
+
+    /// Returns the producers of the first route matching the message, or the default producers.
+    fn route(&self, fields: Option<&dyn Getter>) -> &TopicProducers {
+        let Some(fields) = fields.filter(|_| !self.routes.is_empty()) else {
+            return &self.topic_producers;
+        };
+
+        let fields = RouteFields::new(fields);
+        self.routes
+            .iter()
+            .find(|(condition, _)| condition.matches(&fields))
+            .map_or(&self.topic_producers, |(_, topic_producers)| {
+                topic_producers
+            })
+    }
+
+    /// Adds the configured static and dynamic headers to the headers of the message.
+    fn headers<'a>(
+        &self,
+        headers: Option<&'a BTreeMap<String, String>>,
+        fields: Option<&dyn Getter>,
+    ) -> Option<Cow<'a, BTreeMap<String, String>>> {
+        if self.headers.is_empty() {
+            return headers.map(Cow::Borrowed);
         }
+
+        let mut headers = headers.cloned().unwrap_or_default();
+        for (name, value) in &self.headers.static_headers {
+            headers.insert(name.clone(), value.clone());
+        }
+
+        if let Some(fields) = fields {
+            for (name, field) in &self.headers.dynamic {
+                if let Some(value) = fields.get_value(field).and_then(header_value) {
+                    headers.insert(name.clone(), value);
+                }
+            }
+        }
+
+        Some(Cow::Owned(headers))
     }
 }
 
+/// Fields of a message as seen by routing conditions.
+///
+/// In addition to the fields of the message, ids are available as strings with an `_str` suffix,
+/// since `eq` conditions do not match numbers. The strings are only formatted when a condition
+/// accesses them.
+struct RouteFields<'a> {
+    fields: &'a dyn Getter,
+    organization_id: OnceCell<Option<String>>,
+    project_id: OnceCell<Option<String>>,
+}
+
+impl<'a> RouteFields<'a> {
+    fn new(fields: &'a dyn Getter) -> Self {
+        Self {
+            fields,
+            organization_id: OnceCell::new(),
+            project_id: OnceCell::new(),
+        }
+    }
+
+    fn id_str<'b>(&self, cell: &'b OnceCell<Option<String>>, path: &str) -> Option<&'b str> {
+        cell.get_or_init(|| self.fields.get_value(path).and_then(header_value))
+            .as_deref()
+    }
+}
+
+impl Getter for RouteFields<'_> {
+    fn get_value(&self, path: &str) -> Option<Val<'_>> {
+        match path {
+            "organization_id_str" => Some(
+                self.id_str(&self.organization_id, "organization_id")?
+                    .into(),
+            ),
+            "project_id_str" => Some(self.id_str(&self.project_id, "project_id")?.into()),
+            _ => self.fields.get_value(path),
+        }
+    }
+}
+
+/// Formats a scalar field value as header value.
+fn header_value(value: Val<'_>) -> Option<String> {
+    Some(match value {
+        Val::Bool(value) => value.to_string(),
+        Val::I64(value) => value.to_string(),
+        Val::U64(value) => value.to_string(),
+        Val::F64(value) => value.to_string(),
+        Val::String(value) => value.to_owned(),
+        Val::HexId(id) => id.0.iter().map(|byte| format!("{byte:02x}")).collect(),
+        Val::Array(_) | Val::Object(_) => return None,
+    })
+}
+
 impl Producer {
     /// Sends the payload to the correct producer for the current topic.
+    ///
+    /// If the producer fails with a transient error and a spool is configured, the message is
+    /// written to the spool instead and replayed later.
     fn send(
         &self,
         key: Option<[u8; 16]>,
         headers: Option<&BTreeMap<String, String>>,
         variant: &str,
-        payload: &[u8],
+        payload: SerializationOutput<'_>,
+        fields: Option<&dyn Getter>,
     ) -> Result<&str, ClientError> {
         let now = Instant::now();
+        let original_headers = self.headers(headers, fields);
 
         let Some(TopicProducer {
             topic_name,
+            config_name,
             producer,
             rate_limiter,
-        }) = self.topic_producers.select(key)
+            encoder,
+        }) = self.route(fields).select(key)
         else {
             return Err(ClientError::MissingTopic);
         };
 
+        let encode_start = Instant::now();
+        let payload = encoder.encode(payload).map_err(|error| {
+            relay_log::error!(
+                error = &error as &dyn std::error::Error,
+                tags.variant = variant,
+                tags.topic = topic_name,
+                "error encoding kafka message",
+            );
+            ClientError::EncodingFailed(error)
+        })?;
+        let payload = payload.as_ref();
+
+        if let Some(encoding) = encoder.schema_encoding() {
+            metric!(
+                timer(KafkaTimers::ProducerEncode) = encode_start.elapsed(),
+                topic = topic_name,
+                encoding = encoding,
+            );
+        }
+
         metric!(
             histogram(KafkaHistograms::KafkaMessageSize) = payload.len() as u64,
             variant = variant,
             topic = topic_name,
         );
 
-        let mut headers = headers
+        let mut headers = original_headers
+            .as_deref()
             .unwrap_or(&BTreeMap::new())
             .iter()
             .map(|(key, value)| Header {
@@ -209,6 +413,7 @@ impl Producer {
             })
             .collect::<KafkaHeaders>();
 
+        let original_key = key;
         let key = match (key, rate_limiter.as_ref()) {
             (Some(key), Some(limiter)) => {
                 let is_limited = limiter.try_increment(now, key, 1) < 1;
@@ -233,7 +438,14 @@ impl Producer {
             (key, _) => key,
         };
 
-        let mut record = BaseRecord::to(topic_name).payload(payload);
+        // Spool messages with the key and headers they were produced with, not the rate limited ones.
+        let origin = self.spool.as_ref().map(|_| MessageOrigin {
+            key: original_key,
+            headers: original_headers.map(Cow::into_owned).unwrap_or_default(),
+        });
+
+        let mut record =
+            BaseRecord::with_opaque_to(topic_name, DeliveryOpaque::new(origin)).payload(payload);
         if let Some(headers) = headers.into_inner() {
             record = record.headers(headers);
         }
@@ -249,22 +461,41 @@ impl Producer {
             );
         });
 
-        producer.send(record).map_err(|(error, _message)| {
-            relay_log::error!(
-                error = &error as &dyn std::error::Error,
-                tags.variant = variant,
-                tags.topic = topic_name,
-                "error sending kafka message",
-            );
-            metric!(
-                counter(KafkaCounters::ProducerEnqueueError) += 1,
-                variant = variant,
-                topic = topic_name
-            );
-            ClientError::SendFailed(error)
-        })?;
+        let (error, delivery_opaque) = match producer.send(record) {
+            Ok(()) => return Ok(topic_name),
+            Err((error, record)) => (error, record.delivery_opaque),
+        };
 
-        Ok(topic_name)
+        if let Some(spool) = self
+            .spool
+            .as_deref()
+            .filter(|_| replay::is_retriable(&error))
+            && let Some(origin) = delivery_opaque.into_origin()
+            && replay::spill(
+                spool,
+                &SpoolDestination {
+                    cluster: config_name.clone(),
+                    topic: topic_name.clone(),
+                },
+                &origin.into_spooled(payload.to_vec()),
+            )
+        {
+            return Ok(topic_name);
+        }
+
+        relay_log::error!(
+            error = &error as &dyn std::error::Error,
+            tags.variant = variant,
+            tags.topic = topic_name,
+            "error sending kafka message",
+        );
+        metric!(
+            counter(KafkaCounters::ProducerEnqueueError) += 1,
+            variant = variant,
+            topic = topic_name
+        );
+
+        Err(ClientError::SendFailed(error))
     }
 }
 
@@ -289,6 +520,8 @@ pub struct KafkaClient {
     producers: HashMap<KafkaTopic, Producer>,
     #[cfg(feature = "schemas")]
     schema_validator: schemas::Validator,
+    /// Background replay of spooled messages, stopped when the client is dropped.
+    _replay: Option<SpoolReplay>,
 }
 
 impl KafkaClient {
@@ -307,21 +540,24 @@ impl KafkaClient {
     ) -> Result<&str, ClientError> {
         let serialized = message.serialize()?;
         #[cfg(feature = "schemas")]
-        self.schema_validator
-            .validate_message_schema(topic, &serialized)
-            .map_err(ClientError::SchemaValidationFailed)?;
+        if let SerializationOutput::Json(ref payload) = serialized {
+            self.schema_validator
+                .validate_message_schema(topic, payload)
+                .map_err(ClientError::SchemaValidationFailed)?;
+        }
 
-        self.send(
-            topic,
+        self.producer(topic)?.send(
             message.key(),
             message.headers(),
             message.variant(),
-            &serialized,
+            serialized,
+            message.fields(),
         )
     }
 
     /// Sends the payload to the correct producer for the current topic.
     ///
+    /// The payload is encoded with the [`TopicEncoding`](crate::TopicEncoding) of the topic.
     /// Returns the name of the kafka topic to which the message was produced.
     pub fn send(
         &self,
@@ -329,16 +565,19 @@ impl KafkaClient {
         key: Option<[u8; 16]>,
         headers: Option<&BTreeMap<String, String>>,
         variant: &str,
-        payload: &[u8],
+        payload: SerializationOutput<'_>,
     ) -> Result<&str, ClientError> {
-        let producer = self.producers.get(&topic).ok_or_else(|| {
+        self.producer(topic)?
+            .send(key, headers, variant, payload, None)
+    }
+
+    fn producer(&self, topic: KafkaTopic) -> Result<&Producer, ClientError> {
+        self.producers.get(&topic).ok_or_else(|| {
             relay_log::error!(
                 "attempted to send message to {topic:?} using an unconfigured kafka producer",
             );
             ClientError::InvalidTopicName
-        })?;
-
-        producer.send(key, headers, variant, payload)
+        })
     }
 }
 
@@ -347,6 +586,7 @@ impl KafkaClient {
 pub struct KafkaClientBuilder {
     reused_producers: BTreeMap<Option<String>, Arc<ThreadedProducer>>,
     producers: HashMap<KafkaTopic, Producer>,
+    spool: Option<Arc<KafkaSpool>>,
 }
 
 impl KafkaClientBuilder {
@@ -355,31 +595,80 @@ impl KafkaClientBuilder {
         Self::default()
     }
 
+    /// Enables the disk spool for messages that fail to produce because of transient errors.
+    ///
+    /// Spooled messages are replayed in the background with exponential backoff once the
+    /// producer is healthy again. This must be configured before adding topics.
+    pub fn spool(mut self, spool: KafkaSpool) -> Self {
+        debug_assert!(self.producers.is_empty());
+        self.spool = Some(Arc::new(spool));
+        self
+    }
+
     /// Adds topic configuration to the current [`KafkaClientBuilder`], which in return assigns
     /// dedicates producer to the topic which can will be used to send the messages.
     ///
     /// # Errors
     /// Returns [`ClientError::InvalidConfig`] error if the provided configuration is wrong and
-    /// the producer could not be created.
+    /// the producer could not be created, or [`ClientError::InvalidSchema`] if the schema of the
+    /// topic's encoding could not be loaded.
     pub fn add_kafka_topic_config(
         mut self,
         topic: KafkaTopic,
         topic_config: &KafkaTopicConfig<'_>,
         validate_topic: bool,
     ) -> Result<Self, ClientError> {
+        let topic_producers = self.topic_producers(topic_config.topics())?;
+
+        let routes = topic_config
+            .routes()
+            .iter()
+            .map(|route| {
+                Ok((
+                    route.condition.clone(),
+                    self.topic_producers(&route.topics)?,
+                ))
+            })
+            .collect::<Result<_, ClientError>>()?;
+
+        let producer = Producer::new(
+            topic_producers,
+            routes,
+            topic_config.headers().clone(),
+            self.spool.clone(),
+        );
+        if validate_topic {
+            for topic_producers in producer.all_topic_producers() {
+                topic_producers.validate_topic()?;
+            }
+        }
+        self.producers.insert(topic, producer);
+
+        Ok(self)
+    }
+
+    /// Creates the producers for the shards of a topic.
+    fn topic_producers(
+        &mut self,
+        shards: &[KafkaParams<'_>],
+    ) -> Result<TopicProducers, ClientError> {
         let mut topic_producers = TopicProducers::new();
 
         // Process each shard configuration (one KafkaParams per shard)
         // We must preserve the original order from the configuration
         // because hash-based routing depends on shard index positions
-        for params in topic_config.topics() {
+        for params in shards {
             let KafkaParams {
                 topic_name,
                 config_name,
                 params: config_params,
                 key_rate_limit,
+                encoding,
             } = params;
 
+            let encoder = Encoder::new(encoding, topic_name)
+                .map_err(|error| ClientError::InvalidSchema(topic_name.clone(), error))?;
+
             let rate_limiter = key_rate_limit.map(|limit| {
                 KafkaRateLimits::new(
                     limit.limit_per_window,
@@ -401,38 +690,53 @@ impl KafkaClientBuilder {
 
                 let producer = Arc::new(
                     client_config
-                        .create_with_context(Context)
+                        .create_with_context(Context::new(self.spool.clone(), config_name.clone()))
                         .map_err(ClientError::InvalidConfig)?,
                 );
 
                 self.reused_producers
-                    .insert(config_name, Arc::clone(&producer));
+                    .insert(config_name.clone(), Arc::clone(&producer));
 
                 producer
             };
 
             topic_producers.producers.push(TopicProducer {
                 topic_name: topic_name.clone(),
+                config_name,
                 producer: threaded_producer,
                 rate_limiter,
+                encoder,
             });
         }
 
-        let producer = Producer::new(topic_producers);
-        if validate_topic {
-            producer.topic_producers.validate_topic()?;
-        }
-        self.producers.insert(topic, producer);
-
-        Ok(self)
+        Ok(topic_producers)
     }
 
     /// Consumes self and returns the built [`KafkaClient`].
     pub fn build(self) -> KafkaClient {
+        let replay = self.spool.map(|spool| {
+            let producers = self
+                .producers
+                .values()
+                .flat_map(Producer::all_topic_producers)
+                .flat_map(|topic_producers| &topic_producers.producers)
+                .map(|tp| {
+                    let destination = SpoolDestination {
+                        cluster: tp.config_name.clone(),
+                        topic: tp.topic_name.clone(),
+                    };
+                    (destination, Arc::clone(&tp.producer))
+                })
+                .collect();
+
+            SpoolReplay::spawn(spool, producers)
+        });
+
         KafkaClient {
             producers: self.producers,
             #[cfg(feature = "schemas")]
             schema_validator: schemas::Validator::default(),
+            _replay: replay,
         }
     }
 }
@@ -442,6 +746,160 @@ impl fmt::Debug for KafkaClientBuilder {
         f.debug_struct("KafkaClientBuilder")
             .field("reused_producers", &"<CachedProducers>")
             .field("producers", &self.producers)
+            .field("spool", &self.spool)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use relay_protocol::HexId;
+
+    use super::*;
+    use crate::spool::SpooledMessage;
+
+    struct Fields;
+
+    impl Getter for Fields {
+        fn get_value(&self, path: &str) -> Option<Val<'_>> {
+            Some(match path {
+                "organization_id" => 42u64.into(),
+                "event.platform" => "python".into(),
+                "trace_id" => Val::HexId(HexId(&[0xab, 0x01])),
+                _ => return None,
+            })
+        }
+    }
+
+    fn producer(routes: Vec<(RuleCondition, TopicProducers)>, headers: TopicHeaders) -> Producer {
+        Producer::new(TopicProducers::new(), routes, headers, None)
+    }
+
+    #[test]
+    fn test_route() {
+        let producer = producer(
+            vec![
+                (
+                    RuleCondition::gte("organization_id", 1)
+                        & RuleCondition::lte("organization_id", 1),
+                    TopicProducers::new(),
+                ),
+                (
+                    RuleCondition::gte("organization_id", 42)
+                        & RuleCondition::lte("organization_id", 42),
+                    TopicProducers::new(),
+                ),
+            ],
+            TopicHeaders::default(),
+        );
+
+        assert!(std::ptr::eq(
+            producer.route(None),
+            &producer.topic_producers
+        ));
+        assert!(std::ptr::eq(
+            producer.route(Some(&Fields)),
+            &producer.routes[1].1
+        ));
+    }
+
+    #[test]
+    fn test_route_ids() {
+        let routed = producer(
+            vec![(
+                RuleCondition::eq("organization_id_str", vec!["1", "42", "1337"]),
+                TopicProducers::new(),
+            )],
+            TopicHeaders::default(),
+        );
+
+        assert!(std::ptr::eq(
+            routed.route(Some(&Fields)),
+            &routed.routes[0].1
+        ));
+
+        // The numeric field itself is never matched by `eq`.
+        let numeric = producer(
+            vec![(
+                RuleCondition::eq("organization_id", vec!["42"]),
+                TopicProducers::new(),
+            )],
+            TopicHeaders::default(),
+        );
+        assert!(std::ptr::eq(
+            numeric.route(Some(&Fields)),
+            &numeric.topic_producers
+        ));
+    }
+
+    #[test]
+    fn test_headers() {
+        let headers = TopicHeaders {
+            static_headers: BTreeMap::from([
+                ("region".to_owned(), "us".to_owned()),
+                ("namespace".to_owned(), "static".to_owned()),
+            ]),
+            dynamic: BTreeMap::from([
+                ("platform".to_owned(), "event.platform".to_owned()),
+                ("trace".to_owned(), "trace_id".to_owned()),
+                ("missing".to_owned(), "event.release".to_owned()),
+            ]),
+        };
+        let producer = producer(vec![], headers);
+        let message_headers = BTreeMap::from([("namespace".to_owned(), "custom".to_owned())]);
+
+        let merged = producer.headers(Some(&message_headers), Some(&Fields));
+        insta::assert_debug_snapshot!(merged.as_deref(), @r###"
+        Some(
+            {
+                "namespace": "static",
+                "platform": "python",
+                "region": "us",
+                "trace": "ab01",
+            },
+        )
+        "###);
+
+        // Without fields, only static headers are added.
+        let merged = producer.headers(None, None);
+        insta::assert_debug_snapshot!(merged.as_deref(), @r###"
+        Some(
+            {
+                "namespace": "static",
+                "region": "us",
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_delivery_opaque() {
+        use rdkafka::util::IntoOpaque;
+
+        let origin = MessageOrigin {
+            key: Some([1; 16]),
+            headers: BTreeMap::from([("sentry-foo".to_owned(), "bar".to_owned())]),
+        };
+        let ptr = DeliveryOpaque::new(Some(origin)).into_ptr();
+        let spooled = unsafe { DeliveryOpaque::from_ptr(ptr) }
+            .into_origin()
+            .unwrap()
+            .into_spooled(b"payload".to_vec());
+        assert_eq!(
+            spooled,
+            SpooledMessage {
+                key: Some([1; 16]),
+                headers: BTreeMap::from([("sentry-foo".to_owned(), "bar".to_owned())]),
+                payload: b"payload".to_vec(),
+            }
+        );
+
+        let ptr = DeliveryOpaque::new(None).into_ptr();
+        assert!(ptr.is_null());
+        assert!(
+            unsafe { DeliveryOpaque::from_ptr(ptr) }
+                .into_origin()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::Header;
+use rdkafka::producer::BaseRecord;
+use relay_statsd::metric;
+
+use crate::producer::utils::{DeliveryOpaque, KafkaHeaders, MessageOrigin, ThreadedProducer};
+use crate::spool::{KafkaSpool, ReplayStatus, SpoolDestination, SpoolError, SpooledMessage};
+use crate::statsd::{KafkaCounters, KafkaGauges};
+
+/// Returns `true` if producing failed because of a transient error of the producer or broker.
+///
+/// Messages that failed with these errors are written to the spool, all other errors, such as
+/// messages that are too large or unknown topics, would fail again when replayed.
+pub fn is_retriable(error: &KafkaError) -> bool {
+    matches!(
+        error.rdkafka_error_code(),
+        Some(
+            RDKafkaErrorCode::QueueFull
+                | RDKafkaErrorCode::MessageTimedOut
+                | RDKafkaErrorCode::AllBrokersDown
+                | RDKafkaErrorCode::BrokerTransportFailure
+                | RDKafkaErrorCode::OperationTimedOut
+                | RDKafkaErrorCode::RequestTimedOut
+                | RDKafkaErrorCode::NotEnoughReplicas
+                | RDKafkaErrorCode::LeaderNotAvailable
+                | RDKafkaErrorCode::NotLeaderForPartition
+        )
+    )
+}
+
+/// Writes a message that failed to produce to the spool.
+///
+/// Returns `true` if the message was persisted and will be replayed later.
+pub fn spill(spool: &KafkaSpool, destination: &SpoolDestination, message: &SpooledMessage) -> bool {
+    let topic_name = destination.topic.as_str();
+    match spool.push(destination, message) {
+        Ok(()) => {
+            metric!(
+                counter(KafkaCounters::ProducerSpoolWrite) += 1,
+                topic = topic_name
+            );
+            true
+        }
+        Err(error) => {
+            if !matches!(error, SpoolError::Full) {
+                relay_log::error!(
+                    error = &error as &dyn std::error::Error,
+                    tags.topic = topic_name,
+                    "failed to spool kafka message",
+                );
+            }
+            metric!(
+                counter(KafkaCounters::ProducerSpoolDrop) += 1,
+                topic = topic_name
+            );
+            false
+        }
+    }
+}
+
+/// Replays spooled messages in a background thread while the client is alive.
+///
+/// Dropping the replay stops the thread and waits for it to finish.
+#[derive(Debug)]
+pub struct SpoolReplay {
+    /// Dropping the sender stops the replay thread.
+    shutdown: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SpoolReplay {
+    /// Spawns the replay thread for the given producers, keyed by their destination.
+    pub fn spawn(
+        spool: Arc<KafkaSpool>,
+        producers: BTreeMap<SpoolDestination, Arc<ThreadedProducer>>,
+    ) -> Self {
+        let (shutdown, receiver) = mpsc::channel();
+
+        let handle = thread::Builder::new()
+            .name("kafka-spool".to_owned())
+            .spawn(move || run(&spool, &producers, &receiver))
+            .expect("failed to spawn kafka spool thread");
+
+        Self {
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for SpoolReplay {
+    fn drop(&mut self) {
+        self.shutdown.take();
+        if let Some(handle) = self.handle.take()
+            && handle.join().is_err()
+        {
+            relay_log::error!("kafka spool thread panicked");
+        }
+    }
+}
+
+fn run(
+    spool: &KafkaSpool,
+    producers: &BTreeMap<SpoolDestination, Arc<ThreadedProducer>>,
+    shutdown: &mpsc::Receiver<()>,
+) {
+    let config = spool.config();
+    let mut backoff = config.min_backoff;
+    let mut pushed = spool.pushed();
+
+    loop {
+        match shutdown.recv_timeout(backoff) {
+            Err(RecvTimeoutError::Timeout) => (),
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        // New messages in the spool mean that producing still fails, so the producer is not
+        // considered healthy until a full interval passes without failures.
+        let current = spool.pushed();
+        let healthy = current == pushed && replay(spool, producers);
+        pushed = current;
+
+        metric!(gauge(KafkaGauges::SpoolSize) = spool.disk_size());
+
+        backoff = if healthy {
+            config.min_backoff
+        } else {
+            (backoff * 2).min(config.max_backoff)
+        };
+    }
+}
+
+/// Replays all spooled messages until producing a message fails.
+///
+/// Returns `true` if all messages for known destinations were replayed.
+fn replay(
+    spool: &KafkaSpool,
+    producers: &BTreeMap<SpoolDestination, Arc<ThreadedProducer>>,
+) -> bool {
+    for destination in spool.destinations() {
+        let Some(producer) = producers.get(&destination) else {
+            // The topic is no longer configured, keep the messages in case it comes back.
+            continue;
+        };
+        let topic_name = destination.topic.as_str();
+
+        loop {
+            let result = spool.replay(&destination, |message| send(producer, topic_name, message));
+
+            match result {
+                Ok(Some(replay)) => {
+                    metric!(
+                        counter(KafkaCounters::ProducerSpoolReplay) += replay.produced as u64,
+                        topic = topic_name
+                    );
+                    metric!(
+                        counter(KafkaCounters::ProducerSpoolDrop) += replay.dropped as u64,
+                        topic = topic_name
+                    );
+                    if replay.remaining > 0 {
+                        return false;
+                    }
+                }
+                Ok(None) => break,
+                Err(error) => {
+                    relay_log::error!(
+                        error = &error as &dyn std::error::Error,
+                        tags.topic = topic_name,
+                        "failed to replay spooled kafka messages",
+                    );
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Enqueues a spooled message in the producer.
+///
+/// The message is returned back on transient errors. Messages that fail with any other error can
+/// never be produced and are dropped, so that they do not block the replay of their topic.
+fn send(producer: &ThreadedProducer, topic_name: &str, message: SpooledMessage) -> ReplayStatus {
+    let SpooledMessage {
+        key,
+        headers,
+        payload,
+    } = message;
+
+    let kafka_headers = headers
+        .iter()
+        .map(|(key, value)| Header {
+            key,
+            value: Some(value),
+        })
+        .collect::<KafkaHeaders>();
+
+    let origin = MessageOrigin { key, headers };
+    let mut record = BaseRecord::with_opaque_to(topic_name, DeliveryOpaque::new(Some(origin)))
+        .payload(payload.as_slice());
+    if let Some(headers) = kafka_headers.into_inner() {
+        record = record.headers(headers);
+    }
+    if let Some(key) = key.as_ref() {
+        record = record.key(key);
+    }
+
+    match producer.send(record) {
+        Ok(()) => ReplayStatus::Produced,
+        Err((error, record)) if is_retriable(&error) => {
+            let origin = record.delivery_opaque.into_origin().unwrap_or_default();
+            ReplayStatus::Failed(origin.into_spooled(payload))
+        }
+        Err((error, _)) => {
+            relay_log::error!(
+                error = &error as &dyn std::error::Error,
+                tags.topic = topic_name,
+                "dropping spooled kafka message that cannot be produced",
+            );
+            ReplayStatus::Dropped
+        }
+    }
+}
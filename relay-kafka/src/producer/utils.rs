@@ -1,10 +1,17 @@
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::Arc;
 
 use rdkafka::message::{Header, OwnedHeaders, ToBytes};
 use rdkafka::producer::{DeliveryResult, ProducerContext};
+use rdkafka::util::IntoOpaque;
 use rdkafka::{ClientContext, Message};
 use relay_statsd::metric;
 
+use crate::producer::replay;
+use crate::spool::{KafkaSpool, SpoolDestination, SpooledMessage};
 use crate::statsd::{KafkaCounters, KafkaGauges};
 
 /// A thin wrapper around [`OwnedHeaders`].
@@ -72,9 +79,73 @@ where
     }
 }
 
+/// Key and headers of a message as passed to the producer.
+///
+/// Rate limiting can drop the key and add headers before a message is produced. The original
+/// values are kept, so that a message is spooled the same way regardless of whether it fails to
+/// enqueue or fails in the delivery callback.
+#[derive(Debug, Default)]
+pub struct MessageOrigin {
+    /// The partitioning key of the message before rate limiting.
+    pub key: Option<[u8; 16]>,
+    /// Headers of the message before rate limiting.
+    pub headers: BTreeMap<String, String>,
+}
+
+impl MessageOrigin {
+    /// Creates the message to spool for the given payload.
+    pub fn into_spooled(self, payload: Vec<u8>) -> SpooledMessage {
+        SpooledMessage {
+            key: self.key,
+            headers: self.headers,
+            payload,
+        }
+    }
+}
+
+/// Delivery opaque of [`Context`].
+///
+/// Carries the [`MessageOrigin`] of a message if a spool is configured, and nothing otherwise.
+#[derive(Debug, Default)]
+pub struct DeliveryOpaque(Option<Box<MessageOrigin>>);
+
+impl DeliveryOpaque {
+    pub fn new(origin: Option<MessageOrigin>) -> Self {
+        Self(origin.map(Box::new))
+    }
+
+    pub fn into_origin(self) -> Option<MessageOrigin> {
+        self.0.map(|origin| *origin)
+    }
+}
+
+impl IntoOpaque for DeliveryOpaque {
+    fn into_ptr(self) -> *mut c_void {
+        self.0.map_or(ptr::null_mut(), IntoOpaque::into_ptr)
+    }
+
+    unsafe fn from_ptr(ptr: *mut c_void) -> Self {
+        // SAFETY: The pointer was created by `into_ptr`, so it is either null or a boxed origin.
+        Self((!ptr.is_null()).then(|| unsafe { Box::from_ptr(ptr) }))
+    }
+}
+
 /// Kafka client and producer context that logs statistics and producer errors.
+///
+/// If a spool is configured, messages that fail to be delivered because of transient errors are
+/// written to the spool.
 #[derive(Debug)]
-pub struct Context;
+pub struct Context {
+    spool: Option<Arc<KafkaSpool>>,
+    /// Name of the Kafka config of the producer, `None` for the default config.
+    cluster: Option<String>,
+}
+
+impl Context {
+    pub fn new(spool: Option<Arc<KafkaSpool>>, cluster: Option<String>) -> Self {
+        Self { spool, cluster }
+    }
+}
 
 impl ClientContext for Context {
     /// Report client statistics as statsd metrics.
@@ -124,15 +195,31 @@ impl ClientContext for Context {
 }
 
 impl ProducerContext for Context {
-    type DeliveryOpaque = ();
+    type DeliveryOpaque = DeliveryOpaque;
 
     /// This method is called after attempting to send a message to Kafka.
     /// It's called asynchronously for every message, so we want to handle errors explicitly here.
-    fn delivery(&self, result: &DeliveryResult, _delivery_opaque: Self::DeliveryOpaque) {
+    fn delivery(&self, result: &DeliveryResult, delivery_opaque: Self::DeliveryOpaque) {
         // TODO: any `Accepted` outcomes (e.g. spans) should be logged here instead of on the caller side,
         // such that we do not over-report in the error case.
 
         if let Err((error, message)) = result {
+            if let Some(spool) = self
+                .spool
+                .as_deref()
+                .filter(|_| replay::is_retriable(error))
+                && let Some(origin) = delivery_opaque.into_origin()
+            {
+                let destination = SpoolDestination {
+                    cluster: self.cluster.clone(),
+                    topic: message.topic().to_owned(),
+                };
+                let payload = message.payload().unwrap_or_default().to_vec();
+                if replay::spill(spool, &destination, &origin.into_spooled(payload)) {
+                    return;
+                }
+            }
+
             relay_log::error!(
                 error = error as &dyn Error,
                 payload_len = message.payload_len(),
@@ -0,0 +1,690 @@
+//! A disk spool for Kafka messages that could not be produced.
+//!
+//! Messages are stored per cluster and topic in append-only segment files in the configured
+//! directory:
+//!
+//! ```text
+//! <path>/<cluster>/<topic name>/<segment id>.spool
+//! ```
+//!
+//! The cluster directory is `default` for the default Kafka config and `config-<name>` for
+//! secondary Kafka configs.
+//!
+//! Every record in a segment is prefixed with its length, followed by the optional partition key,
+//! the headers and the payload of the message. Segments are replayed in the order they were
+//! created and removed once all of their messages have been produced.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use thiserror::Error;
+
+/// File extension of spool segments.
+const SEGMENT_EXTENSION: &str = "spool";
+/// Size after which a new segment is started for a topic.
+const MAX_SEGMENT_SIZE: u64 = 8 * 1024 * 1024;
+/// Directory of messages for the default Kafka config.
+const DEFAULT_CLUSTER_DIR: &str = "default";
+/// Prefix of directories of messages for secondary Kafka configs.
+const CLUSTER_DIR_PREFIX: &str = "config-";
+
+/// Errors returned by the [`KafkaSpool`].
+#[derive(Debug, Error)]
+pub enum SpoolError {
+    /// The spool reached its maximum disk size.
+    #[error("kafka spool is full")]
+    Full,
+    /// Failed to read or write spool segments.
+    #[error("failed to access kafka spool")]
+    Io(#[from] io::Error),
+}
+
+/// Configuration for the [`KafkaSpool`].
+#[derive(Clone, Debug)]
+pub struct KafkaSpoolConfig {
+    /// Directory in which spooled messages are stored.
+    pub path: PathBuf,
+    /// Maximum size of all spooled messages on disk, in bytes.
+    pub max_disk_size: u64,
+    /// Initial interval between replay attempts.
+    pub min_backoff: Duration,
+    /// Maximum interval between replay attempts while producing keeps failing.
+    pub max_backoff: Duration,
+}
+
+/// The cluster and topic that spooled messages are produced to.
+///
+/// The same topic name can exist on multiple clusters, so messages are spooled and replayed per
+/// destination.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpoolDestination {
+    /// Name of the secondary Kafka config, or `None` for the default config.
+    pub cluster: Option<String>,
+    /// Name of the topic.
+    pub topic: String,
+}
+
+impl SpoolDestination {
+    /// Returns the directory of this destination's segments within the spool directory.
+    fn dir(&self, root: &Path) -> PathBuf {
+        let cluster = match &self.cluster {
+            Some(name) => format!("{CLUSTER_DIR_PREFIX}{name}"),
+            None => DEFAULT_CLUSTER_DIR.to_owned(),
+        };
+        root.join(cluster).join(&self.topic)
+    }
+
+    /// Parses the destination from the names of its cluster and topic directories.
+    fn from_dirs(cluster: &str, topic: String) -> Option<Self> {
+        let cluster = match cluster {
+            DEFAULT_CLUSTER_DIR => None,
+            _ => Some(cluster.strip_prefix(CLUSTER_DIR_PREFIX)?.to_owned()),
+        };
+        Some(Self { cluster, topic })
+    }
+}
+
+/// A Kafka message persisted in the [`KafkaSpool`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpooledMessage {
+    /// The partitioning key of the message.
+    pub key: Option<[u8; 16]>,
+    /// Headers sent along with the message.
+    pub headers: BTreeMap<String, String>,
+    /// The serialized message.
+    pub payload: Vec<u8>,
+}
+
+impl SpooledMessage {
+    /// Appends the length prefixed record of this message to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.extend_from_slice(&[0; 4]);
+
+        match self.key {
+            Some(key) => {
+                buf.push(1);
+                buf.extend_from_slice(&key);
+            }
+            None => buf.push(0),
+        }
+
+        buf.extend_from_slice(&(self.headers.len() as u32).to_le_bytes());
+        for (key, value) in &self.headers {
+            encode_bytes(buf, key.as_bytes());
+            encode_bytes(buf, value.as_bytes());
+        }
+
+        buf.extend_from_slice(&self.payload);
+
+        let len = (buf.len() - start - 4) as u32;
+        buf[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    }
+
+    /// Decodes the record at the start of `data`.
+    ///
+    /// Returns the message and the remaining data, or `None` if the record is incomplete or
+    /// malformed.
+    fn decode(data: &[u8]) -> Option<(Self, &[u8])> {
+        let (len, data) = decode_u32(data)?;
+        let (mut record, rest) = data.split_at_checked(len as usize)?;
+
+        let (&has_key, remaining) = record.split_first()?;
+        record = remaining;
+        let key = match has_key {
+            0 => None,
+            _ => {
+                let (key, remaining) = record.split_first_chunk::<16>()?;
+                record = remaining;
+                Some(*key)
+            }
+        };
+
+        let (count, remaining) = decode_u32(record)?;
+        record = remaining;
+        let mut headers = BTreeMap::new();
+        for _ in 0..count {
+            let (key, remaining) = decode_string(record)?;
+            let (value, remaining) = decode_string(remaining)?;
+            record = remaining;
+            headers.insert(key, value);
+        }
+
+        let message = Self {
+            key,
+            headers,
+            payload: record.to_vec(),
+        };
+
+        Some((message, rest))
+    }
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+fn decode_u32(data: &[u8]) -> Option<(u32, &[u8])> {
+    let (bytes, rest) = data.split_first_chunk::<4>()?;
+    Some((u32::from_le_bytes(*bytes), rest))
+}
+
+fn decode_string(data: &[u8]) -> Option<(String, &[u8])> {
+    let (len, data) = decode_u32(data)?;
+    let (bytes, rest) = data.split_at_checked(len as usize)?;
+    Some((String::from_utf8(bytes.to_vec()).ok()?, rest))
+}
+
+/// Decodes all complete records of a segment.
+///
+/// A trailing incomplete record, for example from a write interrupted by a crash, is skipped.
+fn decode_segment(mut data: &[u8]) -> Vec<SpooledMessage> {
+    let mut messages = Vec::new();
+    while let Some((message, rest)) = SpooledMessage::decode(data) {
+        messages.push(message);
+        data = rest;
+    }
+    messages
+}
+
+/// A segment file of a topic.
+#[derive(Debug)]
+struct Segment {
+    id: u64,
+    size: u64,
+}
+
+/// Spooled segments of a single destination.
+#[derive(Debug, Default)]
+struct TopicSpool {
+    /// Segments ordered from oldest to newest.
+    segments: VecDeque<Segment>,
+    /// Open file of the newest segment, which receives new messages.
+    writer: Option<File>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    topics: BTreeMap<SpoolDestination, TopicSpool>,
+    disk_size: u64,
+    next_id: u64,
+    pushed: u64,
+}
+
+/// Result of replaying a single message with [`KafkaSpool::replay`].
+#[derive(Debug)]
+pub enum ReplayStatus {
+    /// The message was produced and is removed from the spool.
+    Produced,
+    /// The message can never be produced and is removed from the spool.
+    Dropped,
+    /// Producing failed with a transient error, the message remains in the spool.
+    Failed(SpooledMessage),
+}
+
+/// Result of replaying a segment with [`KafkaSpool::replay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Replay {
+    /// Number of messages that were produced.
+    pub produced: usize,
+    /// Number of messages that were dropped because they can never be produced.
+    pub dropped: usize,
+    /// Number of messages that remain in the spool because producing failed.
+    pub remaining: usize,
+}
+
+/// A bounded on-disk spool for Kafka messages which could not be produced.
+///
+/// Messages are written with [`push`](Self::push) and read back in order with
+/// [`replay`](Self::replay). Messages spooled before a restart are picked up again when the
+/// spool is opened.
+#[derive(Debug)]
+pub struct KafkaSpool {
+    config: KafkaSpoolConfig,
+    inner: Mutex<Inner>,
+}
+
+impl KafkaSpool {
+    /// Opens the spool in the configured directory and loads previously spooled segments.
+    pub fn open(config: KafkaSpoolConfig) -> Result<Self, SpoolError> {
+        fs::create_dir_all(&config.path)?;
+
+        let mut topics = BTreeMap::new();
+        let mut disk_size = 0;
+        let mut next_id = 0;
+
+        for cluster in fs::read_dir(&config.path)? {
+            let cluster = cluster?;
+            if !cluster.file_type()?.is_dir() {
+                continue;
+            }
+            let Ok(cluster_name) = cluster.file_name().into_string() else {
+                continue;
+            };
+
+            for entry in fs::read_dir(cluster.path())? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                let Some(destination) = entry
+                    .file_name()
+                    .into_string()
+                    .ok()
+                    .and_then(|topic| SpoolDestination::from_dirs(&cluster_name, topic))
+                else {
+                    continue;
+                };
+
+                let mut segments = Vec::new();
+                for file in fs::read_dir(entry.path())? {
+                    let path = file?.path();
+                    if path.extension().is_none_or(|ext| ext != SEGMENT_EXTENSION) {
+                        continue;
+                    }
+                    let Some(id) = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .and_then(|stem| stem.parse::<u64>().ok())
+                    else {
+                        continue;
+                    };
+
+                    let size = fs::metadata(&path)?.len();
+                    disk_size += size;
+                    next_id = next_id.max(id + 1);
+                    segments.push(Segment { id, size });
+                }
+
+                if !segments.is_empty() {
+                    segments.sort_by_key(|segment| segment.id);
+                    let spool = TopicSpool {
+                        segments: segments.into(),
+                        writer: None,
+                    };
+                    topics.insert(destination, spool);
+                }
+            }
+        }
+
+        Ok(Self {
+            config,
+            inner: Mutex::new(Inner {
+                topics,
+                disk_size,
+                next_id,
+                pushed: 0,
+            }),
+        })
+    }
+
+    /// Returns the configuration of this spool.
+    pub fn config(&self) -> &KafkaSpoolConfig {
+        &self.config
+    }
+
+    /// Returns the size of all spooled messages on disk, in bytes.
+    pub fn disk_size(&self) -> u64 {
+        self.inner.lock().disk_size
+    }
+
+    /// Returns the number of messages pushed into the spool since it was opened.
+    pub fn pushed(&self) -> u64 {
+        self.inner.lock().pushed
+    }
+
+    /// Returns all destinations with spooled messages.
+    pub fn destinations(&self) -> Vec<SpoolDestination> {
+        let inner = self.inner.lock();
+        inner
+            .topics
+            .iter()
+            .filter(|(_, spool)| !spool.segments.is_empty())
+            .map(|(destination, _)| destination.clone())
+            .collect()
+    }
+
+    /// Persists a message for the given destination.
+    ///
+    /// Returns [`SpoolError::Full`] if the message would exceed the maximum disk size.
+    pub fn push(
+        &self,
+        destination: &SpoolDestination,
+        message: &SpooledMessage,
+    ) -> Result<(), SpoolError> {
+        let mut record = Vec::new();
+        message.encode(&mut record);
+        let len = record.len() as u64;
+
+        let mut inner = self.inner.lock();
+        if inner.disk_size + len > self.config.max_disk_size {
+            return Err(SpoolError::Full);
+        }
+
+        let Inner {
+            topics, next_id, ..
+        } = &mut *inner;
+        let spool = topics.entry(destination.clone()).or_default();
+
+        let rotate = spool
+            .segments
+            .back()
+            .is_none_or(|segment| segment.size >= MAX_SEGMENT_SIZE);
+        if spool.writer.is_none() || rotate {
+            let dir = destination.dir(&self.config.path);
+            fs::create_dir_all(&dir)?;
+            let file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(segment_path(&dir, *next_id))?;
+            spool.segments.push_back(Segment {
+                id: *next_id,
+                size: 0,
+            });
+            spool.writer = Some(file);
+            *next_id += 1;
+        }
+
+        let written = match spool.writer.as_mut() {
+            Some(writer) => writer.write_all(&record),
+            None => Ok(()),
+        };
+
+        if let Err(error) = written {
+            // The segment may end with a partial record now, continue in a new segment.
+            spool.writer = None;
+            return Err(error.into());
+        }
+
+        if let Some(segment) = spool.segments.back_mut() {
+            segment.size += len;
+        }
+        inner.disk_size += len;
+        inner.pushed += 1;
+
+        Ok(())
+    }
+
+    /// Replays the oldest segment of a destination.
+    ///
+    /// Calls `produce` for every message in the segment until it fails by returning the message
+    /// back. The failed message and all messages after it stay in the spool for the next replay.
+    ///
+    /// Returns `None` if there are no spooled messages for the destination.
+    pub fn replay<F>(
+        &self,
+        destination: &SpoolDestination,
+        mut produce: F,
+    ) -> Result<Option<Replay>, SpoolError>
+    where
+        F: FnMut(SpooledMessage) -> ReplayStatus,
+    {
+        let segment = {
+            let mut inner = self.inner.lock();
+            let Some(spool) = inner.topics.get_mut(destination) else {
+                return Ok(None);
+            };
+            let Some(segment) = spool.segments.pop_front() else {
+                return Ok(None);
+            };
+            if spool.segments.is_empty() {
+                // New messages must not be appended to the segment while it is replayed.
+                spool.writer = None;
+            }
+            segment
+        };
+
+        let path = segment_path(&destination.dir(&self.config.path), segment.id);
+
+        let messages = match fs::read(&path) {
+            Ok(data) => decode_segment(&data),
+            Err(error) => {
+                self.restore(destination, segment);
+                return Err(error.into());
+            }
+        };
+
+        let mut produced = 0;
+        let mut dropped = 0;
+        let mut remaining = Vec::new();
+        let mut messages = messages.into_iter();
+        for message in messages.by_ref() {
+            match produce(message) {
+                ReplayStatus::Produced => produced += 1,
+                ReplayStatus::Dropped => dropped += 1,
+                ReplayStatus::Failed(message) => {
+                    remaining.push(message);
+                    break;
+                }
+            }
+        }
+        remaining.extend(messages);
+
+        if remaining.is_empty() {
+            let removed = fs::remove_file(&path);
+            self.inner.lock().disk_size -= segment.size;
+            removed?;
+        } else {
+            let mut data = Vec::new();
+            for message in &remaining {
+                message.encode(&mut data);
+            }
+
+            let temp_path = path.with_extension("tmp");
+            if let Err(error) =
+                fs::write(&temp_path, &data).and_then(|_| fs::rename(&temp_path, &path))
+            {
+                // The segment is unchanged, produced messages will be replayed again.
+                self.restore(destination, segment);
+                return Err(error.into());
+            }
+
+            let size = data.len() as u64;
+            self.inner.lock().disk_size -= segment.size - size;
+            self.restore(
+                destination,
+                Segment {
+                    id: segment.id,
+                    size,
+                },
+            );
+        }
+
+        Ok(Some(Replay {
+            produced,
+            dropped,
+            remaining: remaining.len(),
+        }))
+    }
+
+    /// Puts a segment back to the front of the destination's spool.
+    fn restore(&self, destination: &SpoolDestination, segment: Segment) {
+        let mut inner = self.inner.lock();
+        let spool = inner.topics.entry(destination.clone()).or_default();
+        spool.segments.push_front(segment);
+    }
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:016}.{SEGMENT_EXTENSION}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn config(dir: &TempDir, max_disk_size: u64) -> KafkaSpoolConfig {
+        KafkaSpoolConfig {
+            path: dir.path().to_owned(),
+            max_disk_size,
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+
+    fn destination(cluster: Option<&str>, topic: &str) -> SpoolDestination {
+        SpoolDestination {
+            cluster: cluster.map(str::to_owned),
+            topic: topic.to_owned(),
+        }
+    }
+
+    fn events() -> SpoolDestination {
+        destination(None, "ingest-events")
+    }
+
+    fn message(payload: &str) -> SpooledMessage {
+        SpooledMessage {
+            key: Some([7; 16]),
+            headers: BTreeMap::from([("sentry-reshuffled".to_owned(), "1".to_owned())]),
+            payload: payload.as_bytes().to_vec(),
+        }
+    }
+
+    fn replay_all(spool: &KafkaSpool, destination: &SpoolDestination) -> Vec<SpooledMessage> {
+        let mut produced = Vec::new();
+        while let Some(replay) = spool
+            .replay(destination, |message| {
+                produced.push(message);
+                ReplayStatus::Produced
+            })
+            .unwrap()
+        {
+            assert_eq!(replay.remaining, 0);
+        }
+        produced
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let messages = [
+            message("foo"),
+            SpooledMessage {
+                key: None,
+                headers: BTreeMap::new(),
+                payload: Vec::new(),
+            },
+        ];
+
+        let mut data = Vec::new();
+        for message in &messages {
+            message.encode(&mut data);
+        }
+        assert_eq!(decode_segment(&data), messages);
+
+        // A truncated record is skipped.
+        data.truncate(data.len() - 1);
+        assert_eq!(decode_segment(&data), messages[..1]);
+    }
+
+    #[test]
+    fn test_push_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = KafkaSpool::open(config(&dir, 1024 * 1024)).unwrap();
+        let spans = destination(None, "snuba-spans");
+
+        spool.push(&events(), &message("a")).unwrap();
+        spool.push(&events(), &message("b")).unwrap();
+        spool.push(&spans, &message("c")).unwrap();
+
+        assert_eq!(spool.pushed(), 3);
+        assert_eq!(spool.destinations(), [events(), spans.clone()]);
+        assert!(spool.disk_size() > 0);
+
+        assert_eq!(replay_all(&spool, &events()), [message("a"), message("b")]);
+        assert_eq!(replay_all(&spool, &spans), [message("c")]);
+        assert_eq!(spool.disk_size(), 0);
+        assert!(spool.destinations().is_empty());
+
+        // The spool accepts new messages after the replay.
+        spool.push(&events(), &message("d")).unwrap();
+        assert_eq!(replay_all(&spool, &events()), [message("d")]);
+    }
+
+    #[test]
+    fn test_destinations_by_cluster() {
+        let dir = tempfile::tempdir().unwrap();
+        let dedicated = destination(Some("dedicated"), "ingest-events");
+
+        let spool = KafkaSpool::open(config(&dir, 1024 * 1024)).unwrap();
+        spool.push(&events(), &message("a")).unwrap();
+        spool.push(&dedicated, &message("b")).unwrap();
+        drop(spool);
+
+        // The same topic on different clusters is kept apart, also after reopening.
+        let spool = KafkaSpool::open(config(&dir, 1024 * 1024)).unwrap();
+        assert_eq!(spool.destinations(), [events(), dedicated.clone()]);
+        assert_eq!(replay_all(&spool, &events()), [message("a")]);
+        assert_eq!(replay_all(&spool, &dedicated), [message("b")]);
+    }
+
+    #[test]
+    fn test_replay_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = KafkaSpool::open(config(&dir, 1024 * 1024)).unwrap();
+
+        for payload in ["a", "b", "c", "d"] {
+            spool.push(&events(), &message(payload)).unwrap();
+        }
+        let size = spool.disk_size();
+
+        let replay = spool
+            .replay(&events(), |message| match message.payload.as_slice() {
+                b"b" => ReplayStatus::Dropped,
+                b"c" => ReplayStatus::Failed(message),
+                _ => ReplayStatus::Produced,
+            })
+            .unwrap();
+
+        assert_eq!(
+            replay,
+            Some(Replay {
+                produced: 1,
+                dropped: 1,
+                remaining: 2
+            })
+        );
+        assert!(spool.disk_size() < size);
+        assert_eq!(replay_all(&spool, &events()), [message("c"), message("d")]);
+    }
+
+    #[test]
+    fn test_max_disk_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut data = Vec::new();
+        message("a").encode(&mut data);
+
+        let spool = KafkaSpool::open(config(&dir, data.len() as u64)).unwrap();
+        spool.push(&events(), &message("a")).unwrap();
+        assert!(matches!(
+            spool.push(&events(), &message("b")),
+            Err(SpoolError::Full)
+        ));
+
+        replay_all(&spool, &events());
+        spool.push(&events(), &message("b")).unwrap();
+    }
+
+    #[test]
+    fn test_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let spool = KafkaSpool::open(config(&dir, 1024 * 1024)).unwrap();
+        spool.push(&events(), &message("a")).unwrap();
+        let size = spool.disk_size();
+        drop(spool);
+
+        let spool = KafkaSpool::open(config(&dir, 1024 * 1024)).unwrap();
+        assert_eq!(spool.disk_size(), size);
+        spool.push(&events(), &message("b")).unwrap();
+
+        assert_eq!(replay_all(&spool, &events()), [message("a"), message("b")]);
+    }
+}
@@ -1,4 +1,4 @@
-use relay_statsd::{CounterMetric, GaugeMetric, HistogramMetric};
+use relay_statsd::{CounterMetric, GaugeMetric, HistogramMetric, TimerMetric};
 
 pub enum KafkaCounters {
     /// Number of producer errors occurred after an envelope was already enqueued for sending to
@@ -28,6 +28,26 @@ pub enum KafkaCounters {
     /// rate limit does not drop messages, but instead disables semantic partitioning. Everytime
     /// this happens for a message, this counter is incremented.
     ProducerPartitionKeyRateLimit,
+
+    /// Number of messages written to the spool after producing failed with a transient error.
+    ///
+    /// This metric is tagged with:
+    /// - `topic`: The Kafka topic being produced to.
+    ProducerSpoolWrite,
+
+    /// Number of messages that failed to produce and could not be written to the spool.
+    ///
+    /// This happens if the spool is full or the disk is not writable. These messages are lost.
+    ///
+    /// This metric is tagged with:
+    /// - `topic`: The Kafka topic being produced to.
+    ProducerSpoolDrop,
+
+    /// Number of spooled messages that were replayed to the producer.
+    ///
+    /// This metric is tagged with:
+    /// - `topic`: The Kafka topic being produced to.
+    ProducerSpoolReplay,
 }
 
 impl CounterMetric for KafkaCounters {
@@ -36,6 +56,9 @@ impl CounterMetric for KafkaCounters {
             Self::ProcessingProduceError => "processing.produce.error",
             Self::ProducerEnqueueError => "producer.enqueue.error",
             Self::ProducerPartitionKeyRateLimit => "producer.partition_key.rate_limit",
+            Self::ProducerSpoolWrite => "producer.spool.write",
+            Self::ProducerSpoolDrop => "producer.spool.drop",
+            Self::ProducerSpoolReplay => "producer.spool.replay",
         }
     }
 }
@@ -52,6 +75,27 @@ impl HistogramMetric for KafkaHistograms {
         }
     }
 }
+
+pub enum KafkaTimers {
+    /// Time spent encoding a message with the Avro or Protobuf schema of its topic.
+    ///
+    /// This includes decoding the default serialization of the message, which is not needed for
+    /// topics with JSON encoding.
+    ///
+    /// This metric is tagged with:
+    /// - `topic`: The Kafka topic being produced to.
+    /// - `encoding`: The encoding of the topic, either `avro` or `protobuf`.
+    ProducerEncode,
+}
+
+impl TimerMetric for KafkaTimers {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::ProducerEncode => "producer.encode",
+        }
+    }
+}
+
 /// Gauge metrics for the Kafka producer.
 ///
 /// Most of these metrics are taken from the [`rdkafka::statistics`] module.
@@ -112,6 +156,9 @@ pub enum KafkaGauges {
     /// This metric is tagged with:
     /// - `broker_name`: The broker hostname, port, and ID, in the form HOSTNAME:PORT/ID.
     RequestQueueLatency,
+
+    /// The size of all messages in the spool on disk, in bytes.
+    SpoolSize,
 }
 
 impl GaugeMetric for KafkaGauges {
@@ -128,6 +175,7 @@ impl GaugeMetric for KafkaGauges {
             KafkaGauges::Disconnects => "kafka.stats.broker.disconnects",
             KafkaGauges::ProducerQueueLatency => "kafka.stats.broker.int_latency",
             KafkaGauges::RequestQueueLatency => "kafka.stats.broker.outbuf_latency",
+            KafkaGauges::SpoolSize => "kafka.spool.size",
         }
     }
 }
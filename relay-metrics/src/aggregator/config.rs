@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use relay_base_schema::organization::OrganizationId;
 use serde::{Deserialize, Serialize};
 
 /// Configuration value for [`AggregatorConfig::flush_batching`].
@@ -83,6 +86,32 @@ pub struct AggregatorConfig {
     /// Defaults to `None`, i.e. no limit.
     pub max_total_bucket_bytes: Option<u64>,
 
+    /// Maximum amount of bytes used for metrics aggregation per organization.
+    ///
+    /// The limit of an organization is multiplied by its weight in
+    /// [`Self::organization_weights`]. Buckets of projects whose organization is not known yet are
+    /// accounted by project key against the same limit.
+    ///
+    /// Defaults to `None`, i.e. no limit.
+    pub max_organization_bucket_bytes: Option<u64>,
+
+    /// Relative weights of organizations for budgeting the aggregator.
+    ///
+    /// Weights scale [`Self::max_organization_bucket_bytes`] and determine which organization is
+    /// the heaviest for [`Self::fair_share_eviction`]. Organizations that are not listed have a
+    /// weight of `1`.
+    pub organization_weights: BTreeMap<OrganizationId, u32>,
+
+    /// Evicts buckets of the heaviest organization before rejecting buckets over the total limit.
+    ///
+    /// When a bucket exceeds [`Self::max_total_bucket_bytes`], the aggregator flushes the oldest
+    /// buckets of the organization with the highest cost relative to its weight early to make
+    /// room, instead of rejecting the bucket. Buckets of the heaviest organization itself are still
+    /// rejected, so a single organization cannot starve the others.
+    ///
+    /// Defaults to `false`.
+    pub fair_share_eviction: bool,
+
     /// The number of logical partitions that can receive flushed buckets.
     pub flush_partitions: Option<u32>,
 
@@ -108,6 +137,9 @@ impl Default for AggregatorConfig {
             max_secs_in_future: 60,             // 1 minute
             max_project_key_bucket_bytes: None,
             max_total_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: BTreeMap::new(),
+            fair_share_eviction: false,
             flush_batching: FlushBatching::default(),
             flush_partitions: None,
         }
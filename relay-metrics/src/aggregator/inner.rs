@@ -6,15 +6,19 @@ use std::time::Duration;
 use ahash::RandomState;
 use hashbrown::HashMap;
 use hashbrown::hash_map::Entry;
-use relay_base_schema::metrics::MetricName;
+use relay_base_schema::metrics::{MetricName, MetricNamespace};
+use relay_base_schema::organization::OrganizationId;
 use relay_base_schema::project::ProjectKey;
 use relay_common::time::UnixTimestamp;
 
-use crate::aggregator::stats;
+use crate::aggregator::stats::{self, Tenant};
 use crate::aggregator::{AggregateMetricsError, FlushBatching};
 use crate::utils::ByNamespace;
 use crate::{BucketMetadata, BucketValue, DistributionType, SetType};
 
+/// The maximum number of organizations for which bucket costs are reported in [`Stats`].
+const MAX_REPORTED_ORGANIZATIONS: usize = 5;
+
 #[derive(Default)]
 pub struct Partition {
     pub partition_key: u32,
@@ -40,7 +44,6 @@ impl fmt::Debug for Partition {
 #[derive(Default, Debug)]
 pub struct PartitionStats {
     /// Amount of unique buckets in the partition.
-    #[expect(unused, reason = "used for snapshot tests")]
     pub count: u64,
     /// Amount of unique buckets in the partition by namespace.
     pub count_by_namespace: ByNamespace<u64>,
@@ -50,12 +53,21 @@ pub struct PartitionStats {
     /// Amount of times a bucket was merged in the partition by namespace.
     pub merges_by_namespace: ByNamespace<u64>,
     /// Cost of buckets in the partition.
-    #[expect(unused, reason = "used for snapshot tests")]
     pub cost: u64,
     /// Cost of buckets in the partition by namespace.
     pub cost_by_namespace: ByNamespace<u64>,
 }
 
+impl PartitionStats {
+    /// Adds a single bucket with the given cost to the stats.
+    fn add_bucket(&mut self, namespace: MetricNamespace, cost: u64) {
+        self.count += 1;
+        *self.count_by_namespace.get_mut(namespace) += 1;
+        self.cost += cost;
+        *self.cost_by_namespace.get_mut(namespace) += cost;
+    }
+}
+
 impl From<&stats::Slot> for PartitionStats {
     fn from(value: &stats::Slot) -> Self {
         Self {
@@ -81,6 +93,14 @@ pub struct Stats {
     pub cost: u64,
     /// Total bucket cost in the aggregator by namespace.
     pub cost_by_namespace: ByNamespace<u64>,
+    /// Amount of tenants with buckets in the aggregator.
+    pub tenants: u64,
+    /// Bucket cost of the tenant with the highest cost in the aggregator.
+    pub max_tenant_cost: u64,
+    /// Bucket cost of the organizations with the highest cost, in descending order.
+    ///
+    /// Limited to [`MAX_REPORTED_ORGANIZATIONS`] to bound the number of emitted metrics.
+    pub top_organizations: Vec<(OrganizationId, u64)>,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -115,6 +135,8 @@ impl fmt::Debug for BucketKey {
 pub struct BucketData {
     pub value: BucketValue,
     pub metadata: BucketMetadata,
+    /// The tenant the cost of the bucket is accounted to.
+    pub tenant: Tenant,
 }
 
 impl fmt::Debug for BucketData {
@@ -162,6 +184,12 @@ pub struct Config {
     pub max_total_bucket_bytes: Option<u64>,
     /// Maximum amount of bytes the aggregator allows per project key.
     pub max_project_key_bucket_bytes: Option<u64>,
+    /// Maximum amount of bytes the aggregator allows per tenant with a weight of `1`.
+    pub max_organization_bucket_bytes: Option<u64>,
+    /// Weights of organizations, organizations which are not listed have a weight of `1`.
+    pub organization_weights: BTreeMap<OrganizationId, u32>,
+    /// Evict the oldest buckets of the heaviest tenant before rejecting over the total limit.
+    pub fair_share_eviction: bool,
     /// The age in seconds of the oldest allowed bucket timestamp.
     pub max_secs_in_past: Option<u64>,
     /// The time in seconds that a timestamp may be in the future.
@@ -221,6 +249,14 @@ pub struct Inner {
     stats: stats::Total,
     /// Configured limits based on aggregator stats.
     limits: stats::Limits,
+    /// Maximum cost per tenant with a weight of `1`.
+    max_tenant: u64,
+    /// Weights of organizations for tenant limits and eviction.
+    organization_weights: BTreeMap<OrganizationId, u32>,
+    /// Whether to evict buckets of the heaviest tenant when the total limit is exceeded.
+    fair_share_eviction: bool,
+    /// Buckets evicted to make room for other tenants, which have not been taken yet.
+    evicted: Vec<Partition>,
 
     /// The maximum amount of slots (size of a `bucket_interval`) the timestamp is allowed to be
     /// in the past or future.
@@ -294,6 +330,10 @@ impl Inner {
                 max_total: config.max_total_bucket_bytes.unwrap_or(u64::MAX),
                 max_partition_project,
             },
+            max_tenant: config.max_organization_bucket_bytes.unwrap_or(u64::MAX),
+            organization_weights: config.organization_weights,
+            fair_share_eviction: config.fair_share_eviction,
+            evicted: Vec::new(),
             slot_range: slot_diff,
             partition_by: config.partition_by,
             hasher: build_hasher(),
@@ -310,11 +350,39 @@ impl Inner {
         Stats {
             count: self.stats.count,
             count_by_namespace: self.stats.count_by_namespace,
-            cost: self.stats.count,
+            cost: self.stats.cost,
             cost_by_namespace: self.stats.cost_by_namespace,
+            tenants: self.stats.cost_by_tenant.len() as u64,
+            max_tenant_cost: self
+                .stats
+                .cost_by_tenant
+                .values()
+                .max()
+                .copied()
+                .unwrap_or(0),
+            top_organizations: self.top_organizations(),
         }
     }
 
+    /// Returns the organizations with the highest bucket cost.
+    fn top_organizations(&self) -> Vec<(OrganizationId, u64)> {
+        let mut organizations = self
+            .stats
+            .cost_by_tenant
+            .iter()
+            .filter_map(|(tenant, cost)| match tenant {
+                Tenant::Organization(organization_id) => Some((*organization_id, *cost)),
+                Tenant::Project(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        organizations.sort_unstable_by_key(|&(organization_id, cost)| {
+            (std::cmp::Reverse(cost), organization_id)
+        });
+        organizations.truncate(MAX_REPORTED_ORGANIZATIONS);
+        organizations
+    }
+
     /// Returns `true` if the aggregator contains any metric buckets.
     pub fn is_empty(&self) -> bool {
         self.stats.count == 0
@@ -367,7 +435,7 @@ impl Inner {
 
         let slot = self
             .slots
-            .get_mut(index as usize)
+            .get(index as usize)
             .expect("index should always be a valid slot index");
 
         debug_assert_eq!(
@@ -377,23 +445,45 @@ impl Inner {
         );
 
         let key_cost = key.cost() as u64;
+
+        // Merged buckets remain accounted to the tenant they were created with.
+        let (tenant, required_cost) = match slot.buckets.get(&key) {
+            Some(existing) => (existing.tenant, estimate_merge_cost(&value.value)),
+            None => (value.tenant, key_cost + value.value.cost() as u64),
+        };
+
+        if self.stats.tenant_cost(tenant) + required_cost > self.max_tenant_cost(tenant) {
+            return Err(AggregateMetricsError::OrganizationLimitExceeded);
+        }
+
+        let required_total = self.stats.cost + required_cost;
+        if self.fair_share_eviction && required_total > self.limits.max_total {
+            // Evicting flushes buckets early, so only do it if the merge cannot fail otherwise.
+            slot.stats
+                .check_project(project_key, required_cost, &self.limits)?;
+            if let Some(existing) = slot.buckets.get(&key)
+                && mem::discriminant(&existing.value) != mem::discriminant(&value.value)
+            {
+                return Err(AggregateMetricsError::InvalidTypes);
+            }
+
+            self.evict(tenant, required_total - self.limits.max_total)?;
+        }
+
+        let slot = self
+            .slots
+            .get_mut(index as usize)
+            .expect("index should always be a valid slot index");
+
         match slot.buckets.entry(key) {
             Entry::Occupied(occupied_entry) => {
-                let estimated_cost = match &value.value {
-                    // Counters and Gauges aggregate without additional costs.
-                    BucketValue::Counter(_) | BucketValue::Gauge(_) => 0,
-                    // Distributions are an accurate estimation, all values will be added.
-                    BucketValue::Distribution(d) => d.len() * mem::size_of::<DistributionType>(),
-                    // Sets are an upper bound.
-                    BucketValue::Set(s) => s.len() * mem::size_of::<SetType>(),
-                };
-
                 // Reserve for the upper bound of the value.
                 let reservation = slot.stats.reserve(
                     &mut self.stats,
                     project_key,
+                    tenant,
                     namespace,
-                    estimated_cost as u64,
+                    required_cost,
                     &self.limits,
                 )?;
 
@@ -407,8 +497,9 @@ impl Inner {
                 let reservation = slot.stats.reserve(
                     &mut self.stats,
                     project_key,
+                    tenant,
                     namespace,
-                    key_cost + value.value.cost() as u64,
+                    required_cost,
                     &self.limits,
                 )?;
 
@@ -424,6 +515,102 @@ impl Inner {
         Ok(())
     }
 
+    /// Returns the maximum cost of a tenant, scaled by its weight.
+    fn max_tenant_cost(&self, tenant: Tenant) -> u64 {
+        self.max_tenant.saturating_mul(self.tenant_weight(tenant))
+    }
+
+    /// Returns the weight of a tenant.
+    ///
+    /// Tenants without a known organization always have a weight of `1`.
+    fn tenant_weight(&self, tenant: Tenant) -> u64 {
+        match tenant {
+            Tenant::Organization(organization_id) => self
+                .organization_weights
+                .get(&organization_id)
+                .map_or(1, |&weight| u64::from(weight.max(1))),
+            Tenant::Project(_) => 1,
+        }
+    }
+
+    /// Returns the tenant with the highest cost relative to its weight.
+    ///
+    /// Ties are broken by the order of tenants, to make the choice deterministic.
+    fn heaviest_tenant(&self) -> Option<Tenant> {
+        self.stats
+            .cost_by_tenant
+            .iter()
+            .map(|(&tenant, &cost)| (tenant, cost, self.tenant_weight(tenant)))
+            // Compare `cost / weight` without a lossy division.
+            .max_by(|(a, a_cost, a_weight), (b, b_cost, b_weight)| {
+                (u128::from(*a_cost) * u128::from(*b_weight))
+                    .cmp(&(u128::from(*b_cost) * u128::from(*a_weight)))
+                    .then_with(|| a.cmp(b))
+            })
+            .map(|(tenant, _, _)| tenant)
+    }
+
+    /// Evicts buckets of the heaviest tenant to free up `required` bytes for `tenant`.
+    ///
+    /// Buckets are evicted starting from the slot that is flushed next. Nothing is evicted and an
+    /// error is returned if `tenant` is the heaviest tenant itself, or if evicting all buckets of
+    /// the heaviest tenant would not free up enough space. Evicted buckets can be retrieved with
+    /// [`Self::take_evicted`].
+    fn evict(&mut self, tenant: Tenant, mut required: u64) -> Result<(), AggregateMetricsError> {
+        let heaviest = self
+            .heaviest_tenant()
+            .filter(|&heaviest| heaviest != tenant)
+            .filter(|&heaviest| self.stats.tenant_cost(heaviest) >= required)
+            .ok_or(AggregateMetricsError::TotalLimitExceeded)?;
+
+        for slot in self.slots.iter_mut() {
+            if required == 0 {
+                break;
+            }
+            if !slot.stats.cost_by_tenant.contains_key(&heaviest) {
+                continue;
+            }
+
+            let mut partition = Partition {
+                partition_key: slot.partition_key,
+                ..Default::default()
+            };
+
+            // Buckets which are not yielded because of the early exit remain in the slot.
+            let extracted = slot.buckets.extract_if(|_, data| data.tenant == heaviest);
+
+            for (key, data) in extracted {
+                let namespace = key.metric_name.namespace();
+                let cost = (key.cost() + data.value.cost()) as u64;
+                slot.stats.remove_bucket(
+                    &mut self.stats,
+                    key.project_key,
+                    heaviest,
+                    namespace,
+                    cost,
+                );
+                partition.stats.add_bucket(namespace, cost);
+                partition.buckets.insert(key, data);
+
+                required = required.saturating_sub(cost);
+                if required == 0 {
+                    break;
+                }
+            }
+
+            if !partition.buckets.is_empty() {
+                self.evicted.push(partition);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes all partitions of buckets which have been evicted since the last call.
+    pub fn take_evicted(&mut self) -> Vec<Partition> {
+        mem::take(&mut self.evicted)
+    }
+
     /// FLushes the next partition and advances time.
     pub fn flush_next(&mut self) -> Partition {
         let mut slot @ Slot { partition_key, .. } = self
@@ -464,11 +651,13 @@ impl Inner {
 
     /// Consumes the aggregator and returns an iterator over all contained partitions.
     pub fn into_partitions(self) -> impl Iterator<Item = Partition> {
-        self.slots.into_iter().map(|slot| Partition {
+        let slots = self.slots.into_iter().map(|slot| Partition {
             partition_key: slot.partition_key,
             buckets: slot.buckets,
             stats: PartitionStats::from(&slot.stats),
-        })
+        });
+
+        self.evicted.into_iter().chain(slots)
     }
 }
 
@@ -561,6 +750,20 @@ fn sub_rem_euclid(a: u64, b: u64, m: u64) -> u64 {
     ) % m
 }
 
+/// Estimates the cost increase of merging a value into an existing bucket.
+fn estimate_merge_cost(value: &BucketValue) -> u64 {
+    let cost = match value {
+        // Counters and Gauges aggregate without additional costs.
+        BucketValue::Counter(_) | BucketValue::Gauge(_) => 0,
+        // Distributions are an accurate estimation, all values will be added.
+        BucketValue::Distribution(d) => d.len() * mem::size_of::<DistributionType>(),
+        // Sets are an upper bound.
+        BucketValue::Set(s) => s.len() * mem::size_of::<SetType>(),
+    };
+
+    cost as u64
+}
+
 fn build_hasher() -> RandomState {
     // A fixed, consistent seed across all instances of Relay.
     const K0: u64 = 0x06459b7d5da84ed8;
@@ -589,6 +792,7 @@ mod tests {
         BucketData {
             value: BucketValue::counter(value.try_into().unwrap()),
             metadata: Default::default(),
+            tenant: Tenant::Project(ProjectKey::parse("00000000000000000000000000000000").unwrap()),
         }
     }
 
@@ -603,6 +807,9 @@ mod tests {
             max_secs_in_future: None,
             max_total_bucket_bytes: None,
             max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             start: UnixTimestamp::from_secs(70),
             partition_by: FlushBatching::Partition,
         });
@@ -671,8 +878,11 @@ mod tests {
         Stats {
             count: 6,
             count_by_namespace: (unsupported:6),
-            cost: 6,
+            cost: 822,
             cost_by_namespace: (unsupported:822),
+            tenants: 1,
+            max_tenant_cost: 822,
+            top_organizations: [],
         }
         "###);
 
@@ -690,6 +900,9 @@ mod tests {
             max_secs_in_future: None,
             max_total_bucket_bytes: None,
             max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             start: UnixTimestamp::from_secs(70),
             partition_by: FlushBatching::Project,
         });
@@ -745,6 +958,9 @@ mod tests {
             max_total_bucket_bytes: Some(ONE_BUCKET_COST * 2),
             // Enough for one bucket per partition.
             max_project_key_bucket_bytes: Some(ONE_BUCKET_COST * 3),
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             start: UnixTimestamp::from_secs(70),
             partition_by: FlushBatching::Partition,
         });
@@ -805,6 +1021,11 @@ mod tests {
                 count_by_namespace: (unsupported:1),
                 cost: 137,
                 cost_by_namespace: (unsupported:137),
+                cost_by_tenant: {
+                    Project(
+                        ProjectKey("00000000000000000000000000000000"),
+                    ): 137,
+                },
             },
             (
                 80,
@@ -820,6 +1041,11 @@ mod tests {
                         cost_by_project: {
                             ProjectKey("00000000000000000000000000000000"): 137,
                         },
+                        cost_by_tenant: {
+                            Project(
+                                ProjectKey("00000000000000000000000000000000"),
+                            ): 137,
+                        },
                     },
                     buckets: {
                         80-00000000000000000000000000000000-c: Counter(
@@ -837,14 +1063,239 @@ mod tests {
         Stats {
             count: 1,
             count_by_namespace: (unsupported:1),
-            cost: 1,
+            cost: 137,
             cost_by_namespace: (unsupported:137),
+            tenants: 1,
+            max_tenant_cost: 137,
+            top_organizations: [],
         }
         "###);
 
         Ok(())
     }
 
+    fn organization_counter(organization_id: u64, value: f64) -> BucketData {
+        BucketData {
+            tenant: Tenant::Organization(OrganizationId::new(organization_id)),
+            ..counter(value)
+        }
+    }
+
+    #[test]
+    fn test_merge_organization_limits() -> Result<(), AggregateMetricsError> {
+        const ONE_BUCKET_COST: u64 = 137;
+
+        let mut buckets = Inner::new(Config {
+            bucket_interval: 10,
+            num_time_slots: 3,
+            num_partitions: 1,
+            delay: 0,
+            max_secs_in_past: None,
+            max_secs_in_future: None,
+            max_total_bucket_bytes: None,
+            max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: Some(ONE_BUCKET_COST),
+            organization_weights: BTreeMap::from([(OrganizationId::new(2), 2)]),
+            fair_share_eviction: false,
+            start: UnixTimestamp::from_secs(70),
+            partition_by: FlushBatching::Partition,
+        });
+
+        buckets.merge(bucket_key(70, "a"), organization_counter(1, 1.0))?;
+        assert_eq!(
+            buckets
+                .merge(bucket_key(70, "b"), organization_counter(1, 1.0))
+                .unwrap_err(),
+            AggregateMetricsError::OrganizationLimitExceeded
+        );
+        // Merging into an existing counter bucket does not add cost.
+        buckets.merge(bucket_key(70, "a"), organization_counter(1, 1.0))?;
+
+        // The second organization has twice the budget.
+        buckets.merge(bucket_key(70, "b"), organization_counter(2, 1.0))?;
+        buckets.merge(bucket_key(80, "b"), organization_counter(2, 1.0))?;
+        assert_eq!(
+            buckets
+                .merge(bucket_key(90, "b"), organization_counter(2, 1.0))
+                .unwrap_err(),
+            AggregateMetricsError::OrganizationLimitExceeded
+        );
+
+        // Projects without organization are limited by project key.
+        buckets.merge(bucket_key(70, "c"), counter(1.0))?;
+
+        let stats = buckets.stats();
+        assert_eq!(stats.tenants, 3);
+        assert_eq!(stats.max_tenant_cost, ONE_BUCKET_COST * 2);
+        // Tenants without organization are not reported by organization.
+        assert_eq!(
+            stats.top_organizations,
+            [
+                (OrganizationId::new(2), ONE_BUCKET_COST * 2),
+                (OrganizationId::new(1), ONE_BUCKET_COST),
+            ]
+        );
+
+        // Flushing frees up the budget of the organizations.
+        buckets.flush_next();
+        buckets.merge(bucket_key(80, "a"), organization_counter(1, 1.0))?;
+        buckets.merge(bucket_key(90, "b"), organization_counter(2, 1.0))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_fair_share_eviction() -> Result<(), AggregateMetricsError> {
+        const ONE_BUCKET_COST: u64 = 137;
+
+        let mut buckets = Inner::new(Config {
+            bucket_interval: 10,
+            num_time_slots: 3,
+            num_partitions: 1,
+            delay: 0,
+            max_secs_in_past: None,
+            max_secs_in_future: None,
+            max_total_bucket_bytes: Some(ONE_BUCKET_COST * 3),
+            max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: BTreeMap::from([(OrganizationId::new(3), 3)]),
+            fair_share_eviction: true,
+            start: UnixTimestamp::from_secs(70),
+            partition_by: FlushBatching::Partition,
+        });
+
+        buckets.merge(bucket_key(70, "a"), organization_counter(1, 1.0))?;
+        buckets.merge(bucket_key(80, "a"), organization_counter(1, 1.0))?;
+        buckets.merge(bucket_key(80, "b"), organization_counter(1, 1.0))?;
+        assert!(buckets.take_evicted().is_empty());
+
+        // The heaviest organization cannot evict its own buckets.
+        assert_eq!(
+            buckets
+                .merge(bucket_key(90, "a"), organization_counter(1, 1.0))
+                .unwrap_err(),
+            AggregateMetricsError::TotalLimitExceeded
+        );
+        assert!(buckets.take_evicted().is_empty());
+
+        // Another organization evicts the oldest bucket of the heaviest organization.
+        buckets.merge(bucket_key(90, "a"), organization_counter(2, 1.0))?;
+        insta::assert_debug_snapshot!(buckets.take_evicted(), @r###"
+        [
+            Partition {
+                partition_key: 0,
+                stats: PartitionStats {
+                    count: 1,
+                    count_by_namespace: (unsupported:1),
+                    merges: 0,
+                    merges_by_namespace: (0),
+                    cost: 137,
+                    cost_by_namespace: (unsupported:137),
+                },
+                buckets: {
+                    70-00000000000000000000000000000000-a: Counter(
+                        1.0,
+                    ),
+                },
+            },
+        ]
+        "###);
+        assert!(buckets.take_evicted().is_empty());
+
+        // Relative to its weight, the third organization stays lighter than the others. Between
+        // equally heavy organizations, the one with the higher id is evicted first.
+        buckets.merge(bucket_key(90, "b"), organization_counter(3, 1.0))?;
+        buckets.merge(bucket_key(90, "c"), organization_counter(3, 1.0))?;
+        buckets.merge(bucket_key(90, "d"), organization_counter(3, 1.0))?;
+        let evicted = buckets
+            .take_evicted()
+            .into_iter()
+            .flat_map(|partition| partition.buckets.into_values())
+            .map(|data| data.tenant)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            evicted,
+            [1, 2, 1].map(|id| Tenant::Organization(OrganizationId::new(id)))
+        );
+
+        // Now the third organization is the heaviest.
+        assert_eq!(
+            buckets
+                .merge(bucket_key(90, "e"), organization_counter(3, 1.0))
+                .unwrap_err(),
+            AggregateMetricsError::TotalLimitExceeded
+        );
+
+        insta::assert_debug_snapshot!(buckets.stats(), @r###"
+        Stats {
+            count: 3,
+            count_by_namespace: (unsupported:3),
+            cost: 411,
+            cost_by_namespace: (unsupported:411),
+            tenants: 1,
+            max_tenant_cost: 411,
+            top_organizations: [
+                (
+                    OrganizationId(
+                        3,
+                    ),
+                    411,
+                ),
+            ],
+        }
+        "###);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_fair_share_eviction_checks_limits() -> Result<(), AggregateMetricsError> {
+        const ONE_BUCKET_COST: u64 = 137;
+
+        let mut buckets = Inner::new(Config {
+            bucket_interval: 10,
+            num_time_slots: 3,
+            num_partitions: 1,
+            delay: 0,
+            max_secs_in_past: None,
+            max_secs_in_future: None,
+            max_total_bucket_bytes: Some(ONE_BUCKET_COST * 3),
+            // Two buckets per slot.
+            max_project_key_bucket_bytes: Some(ONE_BUCKET_COST * 2 * 3),
+            max_organization_bucket_bytes: None,
+            organization_weights: BTreeMap::new(),
+            fair_share_eviction: true,
+            start: UnixTimestamp::from_secs(70),
+            partition_by: FlushBatching::Partition,
+        });
+
+        buckets.merge(bucket_key(70, "a"), organization_counter(1, 1.0))?;
+        buckets.merge(bucket_key(80, "a"), organization_counter(1, 1.0))?;
+        buckets.merge(bucket_key(80, "b"), organization_counter(1, 1.0))?;
+
+        // The merge exceeds the project limit, so nothing is evicted for it.
+        assert_eq!(
+            buckets
+                .merge(bucket_key(80, "c"), organization_counter(2, 1.0))
+                .unwrap_err(),
+            AggregateMetricsError::ProjectLimitExceeded
+        );
+        assert!(buckets.take_evicted().is_empty());
+        assert_eq!(buckets.stats().count, 3);
+
+        // Eviction stops as soon as enough space has been freed.
+        buckets.merge(bucket_key(90, "c"), organization_counter(2, 1.0))?;
+        let evicted = buckets
+            .take_evicted()
+            .into_iter()
+            .flat_map(|partition| partition.buckets.into_keys())
+            .collect::<Vec<_>>();
+        assert_eq!(evicted, [bucket_key(70, "a")]);
+        assert_eq!(buckets.stats().count, 3);
+
+        Ok(())
+    }
+
     #[test]
     fn test_merge_flush_with_delay() {
         let mut buckets = Inner::new(Config {
@@ -858,6 +1309,9 @@ mod tests {
             delay: 20,
             max_total_bucket_bytes: None,
             max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             max_secs_in_past: None,
             max_secs_in_future: None,
             // Truncated to 60 seconds.
@@ -939,6 +1393,9 @@ mod tests {
             max_secs_in_future: None,
             max_total_bucket_bytes: None,
             max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             start: UnixTimestamp::from_secs(70),
             partition_by: FlushBatching::Partition,
         });
@@ -964,6 +1421,9 @@ mod tests {
             max_secs_in_future: None,
             max_total_bucket_bytes: None,
             max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             start: UnixTimestamp::from_secs(70),
             partition_by: FlushBatching::Partition,
         });
@@ -989,6 +1449,9 @@ mod tests {
             max_secs_in_future: Some(22), // -> Upgraded to 3 slots (30 seconds).
             max_total_bucket_bytes: None,
             max_project_key_bucket_bytes: None,
+            max_organization_bucket_bytes: None,
+            organization_weights: Default::default(),
+            fair_share_eviction: false,
             start: UnixTimestamp::from_secs(70),
             partition_by: FlushBatching::Partition,
         });
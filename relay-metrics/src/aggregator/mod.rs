@@ -4,6 +4,7 @@ use std::time::{Duration, SystemTime};
 
 use hashbrown::HashMap;
 use relay_base_schema::metrics::MetricNamespace;
+use relay_base_schema::organization::OrganizationId;
 use relay_base_schema::project::ProjectKey;
 use relay_common::time::UnixTimestamp;
 
@@ -16,6 +17,7 @@ mod stats;
 
 pub use self::config::*;
 use self::inner::{BucketData, BucketKey};
+use self::stats::Tenant;
 
 /// Default amount of partitions per second when there are no partitions configured.
 const DEFAULT_PARTITIONS_PER_SECOND: u32 = 64;
@@ -32,6 +34,9 @@ pub enum AggregateMetricsError {
     /// A metric bucket is too large for the per-project bytes limit.
     #[error("project metrics limit exceeded")]
     ProjectLimitExceeded,
+    /// A metric bucket is too large for the per-organization bytes limit.
+    #[error("organization metrics limit exceeded")]
+    OrganizationLimitExceeded,
     /// The timestamp is outside the maximum allowed time range.
     #[error("the timestamp '{0}' is outside the maximum allowed time range")]
     InvalidTimestamp(UnixTimestamp),
@@ -84,6 +89,9 @@ impl Aggregator {
                 delay: config.initial_delay,
                 max_total_bucket_bytes: config.max_total_bucket_bytes,
                 max_project_key_bucket_bytes: config.max_project_key_bucket_bytes,
+                max_organization_bucket_bytes: config.max_organization_bucket_bytes,
+                organization_weights: config.organization_weights.clone(),
+                fair_share_eviction: config.fair_share_eviction,
                 max_secs_in_past: Some(config.max_secs_in_past),
                 max_secs_in_future: Some(config.max_secs_in_future),
                 partition_by: config.flush_batching,
@@ -102,11 +110,35 @@ impl Aggregator {
     }
 
     /// Merge a bucket into this aggregator.
+    ///
+    /// The cost of the bucket is accounted to the project key. Use
+    /// [`Self::merge_with_organization`] if the organization of the project is known.
     pub fn merge(
         &mut self,
         project_key: ProjectKey,
         bucket: Bucket,
     ) -> Result<(), AggregateMetricsError> {
+        self.merge_with_organization(None, project_key, bucket)
+    }
+
+    /// Merge a bucket into this aggregator and account its cost to the organization.
+    ///
+    /// The organization is subject to [`AggregatorConfig::max_organization_bucket_bytes`] and
+    /// fair-share eviction. Without an organization, the cost is accounted to the project key.
+    ///
+    /// Buckets evicted to make room for this bucket must be retrieved with
+    /// [`Self::take_evicted`].
+    pub fn merge_with_organization(
+        &mut self,
+        organization_id: Option<OrganizationId>,
+        project_key: ProjectKey,
+        bucket: Bucket,
+    ) -> Result<(), AggregateMetricsError> {
+        let tenant = match organization_id {
+            Some(organization_id) => Tenant::Organization(organization_id),
+            None => Tenant::Project(project_key),
+        };
+
         let key = BucketKey {
             project_key,
             timestamp: bucket.timestamp,
@@ -118,6 +150,7 @@ impl Aggregator {
         let value = BucketData {
             value: bucket.value,
             metadata: bucket.metadata,
+            tenant,
         };
 
         self.inner.merge(key, value)
@@ -149,6 +182,25 @@ impl Aggregator {
         })
     }
 
+    /// Takes all buckets which have been evicted to make room for other organizations.
+    ///
+    /// Evicted buckets should be flushed immediately, see
+    /// [`AggregatorConfig::fair_share_eviction`].
+    pub fn take_evicted(&mut self) -> impl Iterator<Item = Partition> + use<> {
+        let bucket_interval = self.inner.bucket_interval();
+
+        let evicted = self.inner.take_evicted();
+        for partition in &evicted {
+            emit_evicted_partition_stats(&self.name, &partition.stats);
+        }
+
+        evicted.into_iter().map(move |p| Partition {
+            partition_key: p.partition_key,
+            buckets: p.buckets,
+            bucket_interval,
+        })
+    }
+
     /// Returns when the next partition is ready to be flushed using [`Self::try_flush_next`].
     pub fn next_flush_at(&mut self, now: SystemTime) -> Duration {
         let next_flush = SystemTime::UNIX_EPOCH + self.inner.next_flush_at();
@@ -234,6 +286,22 @@ impl std::iter::ExactSizeIterator for PartitionIter {
 impl std::iter::FusedIterator for PartitionIter {}
 
 fn emit_stats(name: &str, stats: inner::Stats) {
+    relay_statsd::metric!(
+        gauge(MetricGauges::Tenants) = stats.tenants,
+        aggregator = name
+    );
+    relay_statsd::metric!(
+        gauge(MetricGauges::BucketsCostMaxTenant) = stats.max_tenant_cost,
+        aggregator = name
+    );
+    for (rank, (_, cost)) in stats.top_organizations.into_iter().enumerate() {
+        relay_statsd::metric!(
+            gauge(MetricGauges::BucketsCostOrganization) = cost,
+            rank = &(rank + 1).to_string(),
+            aggregator = name
+        );
+    }
+
     for namespace in MetricNamespace::all() {
         relay_statsd::metric!(
             gauge(MetricGauges::Buckets) = *stats.count_by_namespace.get(namespace),
@@ -269,3 +337,18 @@ fn emit_flush_partition_stats(name: &str, stats: inner::PartitionStats) {
         );
     }
 }
+
+fn emit_evicted_partition_stats(name: &str, stats: &inner::PartitionStats) {
+    for namespace in MetricNamespace::all() {
+        relay_statsd::metric!(
+            counter(MetricCounters::EvictedCount) += *stats.count_by_namespace.get(namespace),
+            namespace = namespace.as_str(),
+            aggregator = name,
+        );
+        relay_statsd::metric!(
+            counter(MetricCounters::EvictedCost) += *stats.cost_by_namespace.get(namespace),
+            namespace = namespace.as_str(),
+            aggregator = name,
+        );
+    }
+}
@@ -1,10 +1,21 @@
 use hashbrown::HashMap;
+use hashbrown::hash_map::Entry;
 use relay_base_schema::metrics::MetricNamespace;
+use relay_base_schema::organization::OrganizationId;
 use relay_base_schema::project::ProjectKey;
 
 use crate::aggregator::AggregateMetricsError;
 use crate::utils::ByNamespace;
 
+/// The tenant the cost of a bucket is accounted to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Tenant {
+    /// The organization of the project, if known.
+    Organization(OrganizationId),
+    /// The project key, if the organization of the project is not known.
+    Project(ProjectKey),
+}
+
 /// Total stats tracked for the aggregator.
 #[derive(Default, Debug)]
 pub struct Total {
@@ -17,6 +28,8 @@ pub struct Total {
     pub cost: u64,
     /// Total cost of buckets in the aggregator by namespace.
     pub cost_by_namespace: ByNamespace<u64>,
+    /// Total cost of buckets in the aggregator by tenant.
+    pub cost_by_tenant: HashMap<Tenant, u64>,
 }
 
 impl Total {
@@ -26,12 +39,22 @@ impl Total {
             count_by_namespace,
             cost,
             cost_by_namespace,
+            cost_by_tenant,
         } = self;
 
         *count -= slot.count;
         *count_by_namespace -= slot.count_by_namespace;
         *cost -= slot.cost;
         *cost_by_namespace -= slot.cost_by_namespace;
+
+        for (&tenant, &slot_cost) in &slot.cost_by_tenant {
+            remove_cost(cost_by_tenant, tenant, slot_cost);
+        }
+    }
+
+    /// Returns the cost accounted to a tenant.
+    pub fn tenant_cost(&self, tenant: Tenant) -> u64 {
+        self.cost_by_tenant.get(&tenant).copied().unwrap_or(0)
     }
 }
 
@@ -53,6 +76,8 @@ pub struct Slot {
     pub cost_by_namespace: ByNamespace<u64>,
     /// Cost of buckets in this slot by project.
     pub cost_by_project: HashMap<ProjectKey, u64>,
+    /// Cost of buckets in this slot by tenant.
+    pub cost_by_tenant: HashMap<Tenant, u64>,
 }
 
 impl Slot {
@@ -66,6 +91,7 @@ impl Slot {
             cost,
             cost_by_namespace,
             cost_by_project,
+            cost_by_tenant,
         } = self;
 
         *count = 0;
@@ -78,6 +104,8 @@ impl Slot {
         // Keep the allocation around but at the same time make it possible for it to shrink.
         cost_by_project.shrink_to_fit();
         cost_by_project.clear();
+        cost_by_tenant.shrink_to_fit();
+        cost_by_tenant.clear();
     }
 
     /// Increments the count by one.
@@ -94,6 +122,44 @@ impl Slot {
         *self.merges_by_namespace.get_mut(namespace) += 1;
     }
 
+    /// Removes a bucket with the given cost from the slot and the totals.
+    pub fn remove_bucket(
+        &mut self,
+        total: &mut Total,
+        project_key: ProjectKey,
+        tenant: Tenant,
+        namespace: MetricNamespace,
+        cost: u64,
+    ) {
+        self.count -= 1;
+        *self.count_by_namespace.get_mut(namespace) -= 1;
+        total.count -= 1;
+        *total.count_by_namespace.get_mut(namespace) -= 1;
+
+        self.cost -= cost;
+        *self.cost_by_namespace.get_mut(namespace) -= cost;
+        remove_cost(&mut self.cost_by_project, project_key, cost);
+        remove_cost(&mut self.cost_by_tenant, tenant, cost);
+
+        total.cost -= cost;
+        *total.cost_by_namespace.get_mut(namespace) -= cost;
+        remove_cost(&mut total.cost_by_tenant, tenant, cost);
+    }
+
+    /// Checks whether `cost` can be added for a project without exceeding the project limit.
+    pub fn check_project(
+        &self,
+        project_key: ProjectKey,
+        cost: u64,
+        limits: &Limits,
+    ) -> Result<(), AggregateMetricsError> {
+        let project = self.cost_by_project.get(&project_key).copied().unwrap_or(0);
+        if project + cost > limits.max_partition_project {
+            return Err(AggregateMetricsError::ProjectLimitExceeded);
+        }
+        Ok(())
+    }
+
     /// Tries to reserve a certain amount of cost.
     ///
     /// Returns an error if there is not enough budget left.
@@ -101,6 +167,7 @@ impl Slot {
         &'a mut self,
         total: &'a mut Total,
         project_key: ProjectKey,
+        tenant: Tenant,
         namespace: MetricNamespace,
         cost: u64,
         limits: &Limits,
@@ -108,16 +175,16 @@ impl Slot {
         if total.cost + cost > limits.max_total {
             return Err(AggregateMetricsError::TotalLimitExceeded);
         }
+        self.check_project(project_key, cost, limits)?;
         let project = self.cost_by_project.entry(project_key).or_insert(0);
-        if *project + cost > limits.max_partition_project {
-            return Err(AggregateMetricsError::ProjectLimitExceeded);
-        }
 
         Ok(Reservation {
             total,
             cost_partition: &mut self.cost,
             cost_partition_by_namespace: &mut self.cost_by_namespace,
             cost_partition_project: project,
+            cost_partition_tenant: self.cost_by_tenant.entry(tenant).or_insert(0),
+            tenant,
             namespace,
             reserved: cost,
         })
@@ -137,7 +204,9 @@ pub struct Reservation<'a> {
     cost_partition: &'a mut u64,
     cost_partition_by_namespace: &'a mut ByNamespace<u64>,
     cost_partition_project: &'a mut u64,
+    cost_partition_tenant: &'a mut u64,
 
+    tenant: Tenant,
     namespace: MetricNamespace,
 
     reserved: u64,
@@ -154,10 +223,25 @@ impl Reservation<'_> {
         // Update total costs.
         self.total.cost += cost;
         *self.total.cost_by_namespace.get_mut(self.namespace) += cost;
+        *self.total.cost_by_tenant.entry(self.tenant).or_insert(0) += cost;
 
         // Update all partition costs.
         *self.cost_partition += cost;
         *self.cost_partition_by_namespace.get_mut(self.namespace) += cost;
         *self.cost_partition_project += cost;
+        *self.cost_partition_tenant += cost;
+    }
+}
+
+/// Subtracts `cost` from the entry of `key` and removes the entry once it reaches zero.
+fn remove_cost<K>(costs: &mut HashMap<K, u64>, key: K, cost: u64)
+where
+    K: Eq + std::hash::Hash,
+{
+    if let Entry::Occupied(mut entry) = costs.entry(key) {
+        *entry.get_mut() = entry.get().saturating_sub(cost);
+        if *entry.get() == 0 {
+            entry.remove();
+        }
     }
 }
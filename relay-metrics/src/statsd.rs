@@ -25,6 +25,18 @@ pub enum MetricCounters {
     ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
     ///  - `namespace`: The namespace of the metric.
     FlushCost,
+    /// Incremented for every bucket evicted from the aggregator to make room for other tenants.
+    ///
+    /// This metric is tagged with:
+    ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
+    ///  - `namespace`: The namespace of the metric.
+    EvictedCount,
+    /// Incremented with the cost of buckets evicted from the aggregator.
+    ///
+    /// This metric is tagged with:
+    ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
+    ///  - `namespace`: The namespace of the metric.
+    EvictedCost,
 }
 
 impl CounterMetric for MetricCounters {
@@ -34,6 +46,8 @@ impl CounterMetric for MetricCounters {
             Self::MergeMiss => "metrics.buckets.merge.miss",
             Self::FlushCount => "metrics.buckets.flush.count",
             Self::FlushCost => "metrics.buckets.flush.cost",
+            Self::EvictedCount => "metrics.buckets.evicted.count",
+            Self::EvictedCost => "metrics.buckets.evicted.cost",
         }
     }
 }
@@ -52,6 +66,27 @@ pub enum MetricGauges {
     ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
     ///  - `namespace`: The namespace of the metric.
     BucketsCost,
+    /// The number of tenants with metric buckets in Relay's metrics aggregator.
+    ///
+    /// Tenants are organizations, or project keys if the organization is not known.
+    ///
+    /// This metric is tagged with:
+    ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
+    Tenants,
+    /// The storage cost of metric buckets of the tenant with the highest cost.
+    ///
+    /// This metric is tagged with:
+    ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
+    BucketsCostMaxTenant,
+    /// The storage cost of metric buckets of an organization.
+    ///
+    /// Only reported for the five organizations with the highest cost. The organization id is not
+    /// reported, since the organizations with the highest cost change over time.
+    ///
+    /// This metric is tagged with:
+    ///  - `aggregator`: The name of the metrics aggregator (usually `"default"`).
+    ///  - `rank`: The rank of the organization by cost, from `1` for the highest cost to `5`.
+    BucketsCostOrganization,
 }
 
 impl GaugeMetric for MetricGauges {
@@ -59,6 +94,9 @@ impl GaugeMetric for MetricGauges {
         match *self {
             Self::Buckets => "metrics.buckets",
             Self::BucketsCost => "metrics.buckets.cost",
+            Self::Tenants => "metrics.buckets.tenants",
+            Self::BucketsCostMaxTenant => "metrics.buckets.cost.max_tenant",
+            Self::BucketsCostOrganization => "metrics.buckets.cost.organization",
         }
     }
 }
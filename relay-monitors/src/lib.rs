@@ -16,11 +16,15 @@
 )]
 #![warn(missing_docs)]
 
+use std::net::IpAddr;
 use std::sync::OnceLock;
 
 use relay_base_schema::project::ProjectId;
-use relay_event_schema::protocol::{EventId, TraceId};
+use relay_event_schema::protocol::{Csp, EventId, Exception, LogEntry, TraceId, Values};
+use relay_filter::{FilterStatKey, Filterable, GenericFiltersConfig, ProjectFiltersConfig};
+use relay_protocol::{Getter, Val};
 use serde::{Deserialize, Serialize};
+use url::Url;
 use uuid::Uuid;
 
 /// Maximum length of monitor slugs.
@@ -43,6 +47,10 @@ pub enum ProcessCheckInError {
     /// Environment name was invalid.
     #[error("the environment is invalid")]
     InvalidEnvironment,
+
+    /// The check-in was rejected by an inbound filter.
+    #[error("the check-in was filtered: {0}")]
+    Filtered(FilterStatKey),
 }
 
 /// Describes the status of the incoming CheckIn.
@@ -62,6 +70,19 @@ pub enum CheckInStatus {
     Unknown,
 }
 
+impl CheckInStatus {
+    /// Returns the string representation of the status.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Error => "error",
+            Self::InProgress => "in_progress",
+            Self::Missed => "missed",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "type")]
@@ -150,6 +171,10 @@ pub struct CheckIn {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub environment: Option<String>,
 
+    /// The release of the application running the job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+
     /// Duration of this check since it has started in seconds.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub duration: Option<f64>,
@@ -164,6 +189,80 @@ pub struct CheckIn {
     pub contexts: Option<CheckInContexts>,
 }
 
+/// Information about the client that sent a check-in, used by inbound filters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CheckInClient<'a> {
+    /// The IP address of the client.
+    pub ip: Option<IpAddr>,
+    /// The user agent of the client.
+    pub user_agent: Option<&'a str>,
+}
+
+/// A check-in along with its client, exposed to inbound filters.
+struct FilterableCheckIn<'a> {
+    check_in: &'a CheckIn,
+    ip_addr: Option<String>,
+    user_agent: Option<&'a str>,
+}
+
+impl Filterable for FilterableCheckIn<'_> {
+    fn csp(&self) -> Option<&Csp> {
+        None
+    }
+
+    fn exceptions(&self) -> Option<&Values<Exception>> {
+        None
+    }
+
+    fn ip_addr(&self) -> Option<&str> {
+        self.ip_addr.as_deref()
+    }
+
+    fn logentry(&self) -> Option<&LogEntry> {
+        None
+    }
+
+    fn release(&self) -> Option<&str> {
+        self.check_in.release.as_deref()
+    }
+
+    fn transaction(&self) -> Option<&str> {
+        None
+    }
+
+    fn url(&self) -> Option<Url> {
+        None
+    }
+
+    fn user_agent(&self) -> Option<&str> {
+        self.user_agent
+    }
+
+    fn header(&self, _: &str) -> Option<&str> {
+        None
+    }
+}
+
+impl Getter for FilterableCheckIn<'_> {
+    fn get_value(&self, path: &str) -> Option<Val<'_>> {
+        self.check_in.get_value(path)
+    }
+}
+
+impl Getter for CheckIn {
+    fn get_value(&self, path: &str) -> Option<Val<'_>> {
+        Some(match path {
+            "check_in.monitor_slug" => self.monitor_slug.as_str().into(),
+            "check_in.status" => self.status.as_str().into(),
+            "check_in.duration" => self.duration?.into(),
+            // For compatibility with event-based rules, such as generic inbound filters.
+            "event.environment" => self.environment.as_deref()?.into(),
+            "event.release" => self.release.as_deref()?.into(),
+            _ => return None,
+        })
+    }
+}
+
 /// The result from calling process_check_in
 pub struct ProcessedCheckInResult {
     /// The routing key to be used for the check-in payload.
@@ -176,10 +275,16 @@ pub struct ProcessedCheckInResult {
     pub payload: Vec<u8>,
 }
 
-/// Normalizes a monitor check-in payload.
+/// Normalizes a monitor check-in payload and applies inbound filters.
+///
+/// The `client` parameter describes the client that sent the envelope. Its IP address is checked
+/// by the client IP and localhost filters, see [`relay_filter::should_filter`].
 pub fn process_check_in(
     payload: &[u8],
     project_id: ProjectId,
+    client: CheckInClient<'_>,
+    filters: &ProjectFiltersConfig,
+    global_filters: Option<&GenericFiltersConfig>,
 ) -> Result<ProcessedCheckInResult, ProcessCheckInError> {
     let mut check_in = serde_json::from_slice::<CheckIn>(payload)?;
 
@@ -202,6 +307,14 @@ pub fn process_check_in(
         return Err(ProcessCheckInError::InvalidEnvironment);
     }
 
+    let filterable = FilterableCheckIn {
+        check_in: &check_in,
+        ip_addr: client.ip.map(|ip| ip.to_string()),
+        user_agent: client.user_agent,
+    };
+    relay_filter::should_filter(&filterable, client.ip, filters, global_filters)
+        .map_err(ProcessCheckInError::Filtered)?;
+
     static NAMESPACE: OnceLock<Uuid> = OnceLock::new();
     let namespace = NAMESPACE
         .get_or_init(|| Uuid::new_v5(&Uuid::NAMESPACE_URL, b"https://sentry.io/crons/#did"));
@@ -340,7 +453,13 @@ mod tests {
     fn process_simple() {
         let json = r#"{"check_in_id":"a460c25ff2554577b920fcfacae4e5eb","monitor_slug":"my-monitor","status":"ok"}"#;
 
-        let result = process_check_in(json.as_bytes(), ProjectId::new(1));
+        let result = process_check_in(
+            json.as_bytes(),
+            ProjectId::new(1),
+            CheckInClient::default(),
+            &ProjectFiltersConfig::default(),
+            None,
+        );
 
         // The routing_hint should be consistent for the (project_id, monitor_slug)
         let expected_uuid = Uuid::parse_str("66e5c5fa-b1b9-5980-8d85-432c1874521a").unwrap();
@@ -361,7 +480,13 @@ mod tests {
           "status": "in_progress"
         }"#;
 
-        let result = process_check_in(json.as_bytes(), ProjectId::new(1));
+        let result = process_check_in(
+            json.as_bytes(),
+            ProjectId::new(1),
+            CheckInClient::default(),
+            &ProjectFiltersConfig::default(),
+            None,
+        );
         assert!(matches!(result, Err(ProcessCheckInError::EmptySlug)));
     }
 
@@ -374,10 +499,124 @@ mod tests {
           "environment": "1234567890123456789012345678901234567890123456789012345678901234567890"
         }"#;
 
-        let result = process_check_in(json.as_bytes(), ProjectId::new(1));
+        let result = process_check_in(
+            json.as_bytes(),
+            ProjectId::new(1),
+            CheckInClient::default(),
+            &ProjectFiltersConfig::default(),
+            None,
+        );
         assert!(matches!(
             result,
             Err(ProcessCheckInError::InvalidEnvironment)
         ));
     }
+
+    #[test]
+    fn process_filtered() {
+        let json = r#"{
+          "check_in_id": "a460c25ff2554577b920fcfacae4e5eb",
+          "monitor_slug": "test",
+          "status": "ok",
+          "environment": "dev"
+        }"#;
+
+        let filters: ProjectFiltersConfig = serde_json::from_value(serde_json::json!({
+            "clientIps": {"blacklistedIps": ["127.0.0.1"]},
+            "generic": {
+                "version": 1,
+                "filters": [{
+                    "id": "dev-env",
+                    "isEnabled": true,
+                    "condition": {"op": "eq", "name": "event.environment", "value": "dev"}
+                }]
+            }
+        }))
+        .unwrap();
+
+        let result = process_check_in(
+            json.as_bytes(),
+            ProjectId::new(1),
+            CheckInClient {
+                ip: Some("127.0.0.1".parse().unwrap()),
+                ..Default::default()
+            },
+            &ProjectFiltersConfig {
+                generic: GenericFiltersConfig::default(),
+                ..filters.clone()
+            },
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(ProcessCheckInError::Filtered(FilterStatKey::IpAddress))
+        ));
+
+        let result = process_check_in(
+            json.as_bytes(),
+            ProjectId::new(1),
+            CheckInClient::default(),
+            &filters,
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(ProcessCheckInError::Filtered(FilterStatKey::GenericFilter(id))) if id == "dev-env"
+        ));
+    }
+
+    #[test]
+    fn process_filtered_by_client() {
+        let json = r#"{
+          "check_in_id": "a460c25ff2554577b920fcfacae4e5eb",
+          "monitor_slug": "test",
+          "status": "ok",
+          "release": "1.0"
+        }"#;
+
+        let filters: ProjectFiltersConfig = serde_json::from_value(serde_json::json!({
+            "releases": {"releases": ["1.0"]},
+            "localhost": {"isEnabled": true},
+            "webCrawlers": {"isEnabled": true}
+        }))
+        .unwrap();
+
+        let check = |filters: &ProjectFiltersConfig, client| match process_check_in(
+            json.as_bytes(),
+            ProjectId::new(1),
+            client,
+            filters,
+            None,
+        ) {
+            Err(ProcessCheckInError::Filtered(key)) => Some(key),
+            _ => None,
+        };
+
+        let release = ProjectFiltersConfig {
+            releases: filters.releases.clone(),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(&release, CheckInClient::default()),
+            Some(FilterStatKey::ReleaseVersion)
+        );
+
+        let localhost = CheckInClient {
+            ip: Some("127.0.0.1".parse().unwrap()),
+            ..Default::default()
+        };
+        let filters = ProjectFiltersConfig {
+            releases: Default::default(),
+            ..filters
+        };
+        assert_eq!(check(&filters, localhost), Some(FilterStatKey::Localhost));
+
+        let crawler = CheckInClient {
+            user_agent: Some("Googlebot/2.1"),
+            ..Default::default()
+        };
+        assert_eq!(check(&filters, crawler), Some(FilterStatKey::WebCrawlers));
+
+        assert_eq!(check(&filters, CheckInClient::default()), None);
+    }
 }
@@ -8,7 +8,10 @@ use utf16string::{LittleEndian, WStr};
 
 use crate::compiledconfig::RuleRef;
 use crate::regexes::{ReplaceBehavior, get_regex_for_rule_type};
-use crate::{CompiledPiiConfig, JsonScrubError, JsonScrubVisitor, Redaction, transform, utils};
+use crate::{
+    AuditRedaction, CompiledPiiConfig, JsonScrubError, JsonScrubVisitor, PiiAudit, PiiAuditReport,
+    Redaction, transform, utils,
+};
 
 /// The minimum length a string needs to be in a binary blob.
 ///
@@ -405,64 +408,7 @@ impl<'a> PiiAttachmentsProcessor<'a> {
         for (selector, rules) in &self.compiled_config.applications {
             if selector.matches_path(&state.path()) {
                 for rule in rules {
-                    // Note:
-                    //
-                    // - We ignore pattern_type and just treat every regex like a value regex (i.e.
-                    //   redactPair becomes pattern rule). Very unlikely anybody would want that
-                    //   behavior (e.g.  "Remove passwords on **" would remove a file called
-                    //   "passwords.txt", but also "author.txt").  Just use selectors!
-                    //
-                    // - We impose severe restrictions on how redaction methods work, as we must
-                    //   not change the lengths of attachments.
-                    for (_pattern_type, regex, replace_behavior) in
-                        get_regex_for_rule_type(&rule.ty)
-                    {
-                        match encodings {
-                            ScrubEncodings::Utf8 => {
-                                let matches =
-                                    apply_regex_to_utf8_bytes(data, rule, regex, &replace_behavior);
-                                changed |= !(matches.is_empty());
-                            }
-                            ScrubEncodings::Utf16Le => {
-                                changed |= apply_regex_to_utf16le_bytes(
-                                    data,
-                                    rule,
-                                    regex,
-                                    &replace_behavior,
-                                );
-                            }
-                            ScrubEncodings::All => {
-                                let matches =
-                                    apply_regex_to_utf8_bytes(data, rule, regex, &replace_behavior);
-                                changed |= !(matches.is_empty());
-
-                                // Only scrub regions with the UTF-16 scrubber if they haven't been
-                                // scrubbed yet.
-                                let unscrubbed_ranges = matches
-                                    .into_iter()
-                                    .chain(std::iter::once((data.len(), 0)))
-                                    .scan((0usize, 0usize), |previous, current| {
-                                        let start = if previous.1 % 2 == 0 {
-                                            previous.1
-                                        } else {
-                                            previous.1 + 1
-                                        };
-                                        let item = (start, current.0);
-                                        *previous = current;
-                                        Some(item)
-                                    })
-                                    .filter(|(start, end)| end > start);
-                                for (start, end) in unscrubbed_ranges {
-                                    changed |= apply_regex_to_utf16le_bytes(
-                                        &mut data[start..end],
-                                        rule,
-                                        regex,
-                                        &replace_behavior,
-                                    );
-                                }
-                            }
-                        }
-                    }
+                    changed |= scrub_bytes_with_rule(data, rule, &encodings);
                 }
             }
         }
@@ -478,6 +424,33 @@ impl<'a> PiiAttachmentsProcessor<'a> {
         self.scrub_bytes(data, &state, ScrubEncodings::All)
     }
 
+    /// Runs PII rules over a plain attachment and reports which rules matched.
+    ///
+    /// Every rule is applied to a separate copy of the attachment, so the report contains all
+    /// rules that would match, even if their matches overlap. Every modified region of the
+    /// attachment counts as a separate match.
+    pub fn audit_attachment(&self, filename: &str, data: &[u8]) -> PiiAuditReport {
+        let state = self.state(filename, ValueType::Binary);
+        let mut audit = PiiAudit::new();
+
+        for (selector, rules) in &self.compiled_config.applications {
+            if !selector.matches_path(&state.path()) {
+                continue;
+            }
+
+            for rule in rules {
+                let mut scrubbed = data.to_vec();
+                if scrub_bytes_with_rule(&mut scrubbed, rule, &ScrubEncodings::All) {
+                    let redaction = AuditRedaction::from_redaction(&rule.redaction);
+                    let count = count_modified_regions(data, &scrubbed);
+                    audit.record(&rule.origin, filename, redaction, count);
+                }
+            }
+        }
+
+        audit.into_report()
+    }
+
     /// Scrub a filepath, preserving the basename.
     pub fn scrub_utf8_filepath(&self, path: &mut str, state: &ProcessingState<'_>) -> bool {
         if let Some(index) = path.rfind(['/', '\\']) {
@@ -529,6 +502,88 @@ impl<'a> PiiAttachmentsProcessor<'a> {
     }
 }
 
+/// Applies a single PII rule to a plain buffer.
+///
+/// Returns `true`, if the buffer was modified.
+fn scrub_bytes_with_rule(data: &mut [u8], rule: &RuleRef, encodings: &ScrubEncodings) -> bool {
+    let mut changed = false;
+
+    // Note:
+    //
+    // - We ignore pattern_type and just treat every regex like a value regex (i.e.
+    //   redactPair becomes pattern rule). Very unlikely anybody would want that
+    //   behavior (e.g.  "Remove passwords on **" would remove a file called
+    //   "passwords.txt", but also "author.txt").  Just use selectors!
+    //
+    // - We impose severe restrictions on how redaction methods work, as we must
+    //   not change the lengths of attachments.
+    for (_pattern_type, regex, replace_behavior) in get_regex_for_rule_type(&rule.ty) {
+        match encodings {
+            ScrubEncodings::Utf8 => {
+                let matches = apply_regex_to_utf8_bytes(data, rule, regex, &replace_behavior);
+                changed |= !(matches.is_empty());
+            }
+            ScrubEncodings::Utf16Le => {
+                changed |= apply_regex_to_utf16le_bytes(data, rule, regex, &replace_behavior);
+            }
+            ScrubEncodings::All => {
+                let matches = apply_regex_to_utf8_bytes(data, rule, regex, &replace_behavior);
+                changed |= !(matches.is_empty());
+
+                // Only scrub regions with the UTF-16 scrubber if they haven't been
+                // scrubbed yet.
+                let unscrubbed_ranges = matches
+                    .into_iter()
+                    .chain(std::iter::once((data.len(), 0)))
+                    .scan((0usize, 0usize), |previous, current| {
+                        let start = if previous.1 % 2 == 0 {
+                            previous.1
+                        } else {
+                            previous.1 + 1
+                        };
+                        let item = (start, current.0);
+                        *previous = current;
+                        Some(item)
+                    })
+                    .filter(|(start, end)| end > start);
+                for (start, end) in unscrubbed_ranges {
+                    changed |= apply_regex_to_utf16le_bytes(
+                        &mut data[start..end],
+                        rule,
+                        regex,
+                        &replace_behavior,
+                    );
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+/// Counts the regions in which `modified` differs from `original`.
+///
+/// Single unmodified bytes within a region do not split it, since scrubbing UTF-16 text leaves the
+/// upper bytes of ASCII characters intact.
+fn count_modified_regions(original: &[u8], modified: &[u8]) -> usize {
+    let mut count = 0;
+    // Number of unmodified bytes since the last modified byte, `None` before the first one.
+    let mut gap = None;
+
+    for (a, b) in original.iter().zip(modified) {
+        if a != b {
+            if gap.is_none_or(|gap| gap > 1) {
+                count += 1;
+            }
+            gap = Some(0);
+        } else if let Some(gap) = &mut gap {
+            *gap += 1;
+        }
+    }
+
+    count
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::Itertools;
@@ -990,4 +1045,34 @@ mod tests {
             b"h\x00e\x00l\x00l\x00o\x00 \x00t\x00h\x00e\x00r\x00e\x00"
         );
     }
+
+    #[test]
+    fn test_audit_attachment() {
+        let config = serde_json::from_value::<PiiConfig>(serde_json::json!({
+            "applications": {
+                "$binary": ["@ip:replace", "@email:mask"]
+            }
+        }))
+        .unwrap();
+
+        let mut data = b"from 127.0.0.1 and 10.0.0.1 by: ".to_vec();
+        // The same address encoded in UTF-16LE.
+        data.extend("foo@example.com".encode_utf16().flat_map(u16::to_le_bytes));
+
+        let processor = PiiAttachmentsProcessor::new(config.compiled());
+        let report = processor.audit_attachment("log.txt", &data);
+
+        let matches = report
+            .matches
+            .iter()
+            .map(|m| (m.rule_id.as_str(), m.path.as_str(), m.redaction, m.count))
+            .collect_vec();
+        assert_eq!(
+            matches,
+            [
+                ("@email:mask", "log.txt", AuditRedaction::Mask, 1),
+                ("@ip:replace", "log.txt", AuditRedaction::Replace, 2),
+            ]
+        );
+    }
 }
@@ -0,0 +1,421 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use relay_event_schema::processor::{
+    self, FieldAttrs, Pii, ProcessValue, ProcessingAction, ProcessingResult, ProcessingState,
+    Processor, ValueType,
+};
+use relay_event_schema::protocol::{AsPair, Event, OurLog, PairList, Replay, Span};
+use relay_protocol::{Annotated, Meta, RemarkType, Value};
+use serde::Serialize;
+
+use crate::{PiiAttachmentsProcessor, PiiConfig, PiiProcessor, Redaction, utils};
+
+/// The redaction a PII rule applied to a match in a [`PiiAuditReport`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditRedaction {
+    /// The value was removed.
+    Remove,
+    /// The matched section was replaced with a fixed string.
+    Replace,
+    /// The matched section was masked.
+    Mask,
+    /// The matched section was replaced with its hash.
+    Hash,
+}
+
+impl AuditRedaction {
+    /// Returns the redaction that results in a remark of the given type.
+    ///
+    /// Returns `None` for remarks that do not modify the value.
+    fn from_remark_type(ty: RemarkType) -> Option<Self> {
+        match ty {
+            RemarkType::Removed => Some(Self::Remove),
+            RemarkType::Substituted => Some(Self::Replace),
+            RemarkType::Masked => Some(Self::Mask),
+            RemarkType::Pseudonymized => Some(Self::Hash),
+            RemarkType::Annotated | RemarkType::Encrypted => None,
+        }
+    }
+
+    /// Returns the audited redaction for a configured [`Redaction`].
+    pub(crate) fn from_redaction(redaction: &Redaction) -> Self {
+        match redaction {
+            Redaction::Default | Redaction::Remove | Redaction::Other => Self::Remove,
+            Redaction::Replace(_) => Self::Replace,
+            Redaction::Mask => Self::Mask,
+            Redaction::Hash => Self::Hash,
+        }
+    }
+}
+
+/// A PII rule that matched at a specific path.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct PiiAuditMatch {
+    /// The identifier of the rule as referenced in the PII config, for example `@ip`.
+    ///
+    /// Rules nested in a `multiple` or `alias` rule with `hideInner` are reported with the id of
+    /// the outer rule.
+    pub rule_id: String,
+    /// The path of the value the rule matched on, for example `user.ip_address`.
+    pub path: String,
+    /// The redaction applied to the matches.
+    pub redaction: AuditRedaction,
+    /// How many times the rule matched at this path.
+    pub count: usize,
+}
+
+/// Report of all PII rules that matched an item.
+///
+/// Created by [`audit_value`], [`PiiAttachmentsProcessor::audit_attachment`], or manually through
+/// a [`PiiAudit`].
+///
+/// [`PiiAttachmentsProcessor::audit_attachment`]: crate::PiiAttachmentsProcessor::audit_attachment
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct PiiAuditReport {
+    /// All matches sorted by rule id and path.
+    pub matches: Vec<PiiAuditMatch>,
+    /// Whether Relay scrubs items of the audited type.
+    ///
+    /// If `false`, the matches list the rules that would apply, but Relay does not apply them.
+    pub scrubbed: bool,
+}
+
+/// Collects matches of PII rules into a [`PiiAuditReport`].
+#[derive(Clone, Debug, Default)]
+pub struct PiiAudit {
+    counts: BTreeMap<(String, String, AuditRedaction), usize>,
+}
+
+impl PiiAudit {
+    /// Creates an empty audit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `count` matches of a rule at the given path.
+    pub fn record(&mut self, rule_id: &str, path: &str, redaction: AuditRedaction, count: usize) {
+        if count > 0 {
+            *self
+                .counts
+                .entry((rule_id.to_owned(), path.to_owned(), redaction))
+                .or_default() += count;
+        }
+    }
+
+    /// Records all remarks left by PII rules in the given meta.
+    pub fn record_meta(&mut self, path: &str, meta: &Meta) {
+        for remark in meta.iter_remarks() {
+            if let Some(redaction) = AuditRedaction::from_remark_type(remark.ty()) {
+                self.record(remark.rule_id(), path, redaction, 1);
+            }
+        }
+    }
+
+    /// Removes all matches recorded in `other` from this audit.
+    fn subtract(&mut self, other: &Self) {
+        for (key, count) in &other.counts {
+            if let Some(own) = self.counts.get_mut(key) {
+                *own = own.saturating_sub(*count);
+            }
+        }
+
+        self.counts.retain(|_, count| *count > 0);
+    }
+
+    /// Consumes the audit and returns the report.
+    pub fn into_report(self) -> PiiAuditReport {
+        let matches = self
+            .counts
+            .into_iter()
+            .map(|((rule_id, path, redaction), count)| PiiAuditMatch {
+                rule_id,
+                path,
+                redaction,
+                count,
+            })
+            .collect();
+
+        PiiAuditReport {
+            matches,
+            scrubbed: true,
+        }
+    }
+}
+
+/// Walks a value and records all remarks into a [`PiiAudit`].
+struct AuditProcessor<'a> {
+    audit: &'a mut PiiAudit,
+    /// Paths that have been recorded already.
+    ///
+    /// Some values, such as strings in untyped objects, are visited more than once.
+    visited: BTreeSet<String>,
+}
+
+impl Processor for AuditProcessor<'_> {
+    fn before_process<T: ProcessValue>(
+        &mut self,
+        _value: Option<&T>,
+        meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        if meta.iter_remarks().next().is_none() {
+            return Ok(());
+        }
+
+        let path = state.path().to_string();
+        if !self.visited.contains(&path) {
+            self.audit.record_meta(&path, meta);
+            self.visited.insert(path);
+        }
+
+        Ok(())
+    }
+
+    fn process_pairlist<T: ProcessValue + AsPair>(
+        &mut self,
+        value: &mut PairList<T>,
+        _meta: &mut Meta,
+        state: &ProcessingState,
+    ) -> ProcessingResult {
+        utils::process_pairlist(self, value, state)
+    }
+}
+
+fn collect_remarks<T: ProcessValue>(
+    value: &mut Annotated<T>,
+    state: &ProcessingState<'_>,
+) -> Result<PiiAudit, ProcessingAction> {
+    let mut audit = PiiAudit::new();
+    let mut processor = AuditProcessor {
+        audit: &mut audit,
+        visited: BTreeSet::new(),
+    };
+    processor::process_value(value, &mut processor, state)?;
+    Ok(audit)
+}
+
+/// Runs a PII config over a value and reports which rules matched, without modifying the value.
+///
+/// The value is scrubbed in the same way as [`PiiProcessor`] would do, so the report covers all
+/// item types that implement [`ProcessValue`], such as events, spans, logs, and replay events.
+/// Use [`ProcessingState::root`] for typed items. For untyped JSON, pass a root state with
+/// [`Pii::True`](relay_event_schema::processor::Pii::True) attributes, otherwise no rules apply.
+///
+/// Remarks that are already present in the value, for instance because it was scrubbed by another
+/// Relay, are not included in the report.
+pub fn audit_value<T: ProcessValue + Clone>(
+    config: &PiiConfig,
+    value: &Annotated<T>,
+    state: &ProcessingState<'_>,
+) -> Result<PiiAuditReport, ProcessingAction> {
+    let mut value = value.clone();
+    let existing = collect_remarks(&mut value, state)?;
+
+    let mut processor = PiiProcessor::new(config.compiled());
+    processor::process_value(&mut value, &mut processor, state)?;
+
+    let mut audit = collect_remarks(&mut value, state)?;
+    audit.subtract(&existing);
+    Ok(audit.into_report())
+}
+
+/// Error returned by [`audit_item`].
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    /// The item type is not supported.
+    #[error("unsupported item type: {0}")]
+    UnsupportedItemType(String),
+    /// The item could not be parsed.
+    #[error("invalid item")]
+    InvalidItem(#[from] serde_json::Error),
+    /// Scrubbing the item failed.
+    #[error("failed to scrub item")]
+    Processing(#[from] ProcessingAction),
+}
+
+/// Runs a PII config over a serialized item and reports which rules matched.
+///
+/// Supported item types are `event`, `span`, `log`, `replay_event`, `attachment`, and `check_in`.
+/// The `filename` is only used for attachments. Check-ins are not scrubbed by Relay, so their
+/// report lists the rules that would apply to any field and is marked as not
+/// [scrubbed](PiiAuditReport::scrubbed).
+///
+/// Replay recordings are parsed by `relay_replays`, whose `audit::audit_item` extends this
+/// function with the `replay_recording` item type. Prefer that entry point to audit any item.
+pub fn audit_item(
+    config: &PiiConfig,
+    item_type: &str,
+    filename: &str,
+    item: &[u8],
+) -> Result<PiiAuditReport, AuditError> {
+    Ok(match item_type {
+        "event" => audit_json::<Event>(config, item)?,
+        "span" => audit_json::<Span>(config, item)?,
+        "log" => audit_json::<OurLog>(config, item)?,
+        "replay_event" => audit_json::<Replay>(config, item)?,
+        "check_in" => {
+            let value = Annotated::<Value>::from_json_bytes(item)?;
+            let state = ProcessingState::new_root(
+                Some(Cow::Owned(FieldAttrs::new().pii(Pii::True))),
+                [ValueType::Object],
+            );
+            PiiAuditReport {
+                scrubbed: false,
+                ..audit_value(config, &value, &state)?
+            }
+        }
+        "attachment" => {
+            PiiAttachmentsProcessor::new(config.compiled()).audit_attachment(filename, item)
+        }
+        other => return Err(AuditError::UnsupportedItemType(other.to_owned())),
+    })
+}
+
+fn audit_json<T: ProcessValue + Clone>(
+    config: &PiiConfig,
+    item: &[u8],
+) -> Result<PiiAuditReport, AuditError> {
+    let value = Annotated::<T>::from_json_bytes(item)?;
+    Ok(audit_value(config, &value, ProcessingState::root())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PiiConfig {
+        serde_json::from_str(
+            r#"{
+                "rules": {
+                    "remove_secret": {
+                        "type": "pattern",
+                        "pattern": "secret",
+                        "redaction": {"method": "remove"}
+                    }
+                },
+                "applications": {
+                    "$string && !extra.address": ["@ip:replace", "remove_secret"],
+                    "extra.address": ["@ip:hash"]
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_audit_event() {
+        let event = Annotated::<Event>::from_json(
+            r#"{
+                "message": "from 127.0.0.1 and 10.0.0.1",
+                "extra": {"token": "my secret", "address": "127.0.0.1"}
+            }"#,
+        )
+        .unwrap();
+
+        let report = audit_value(&config(), &event, ProcessingState::root()).unwrap();
+        insta::assert_debug_snapshot!(report.matches, @r#"
+        [
+            PiiAuditMatch {
+                rule_id: "@ip:hash",
+                path: "extra.address",
+                redaction: Hash,
+                count: 1,
+            },
+            PiiAuditMatch {
+                rule_id: "@ip:replace",
+                path: "logentry.formatted",
+                redaction: Replace,
+                count: 2,
+            },
+            PiiAuditMatch {
+                rule_id: "remove_secret",
+                path: "extra.token",
+                redaction: Remove,
+                count: 1,
+            },
+        ]
+        "#);
+
+        // The audited value is not modified.
+        let extra = event.value().unwrap().extra.value().unwrap();
+        assert_eq!(
+            extra["address"].value().unwrap().0.as_str(),
+            Some("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_audit_existing_remarks() {
+        let mut event = Annotated::<Event>::from_json(
+            r#"{"message": "from 127.0.0.1", "extra": {"token": "my secret"}}"#,
+        )
+        .unwrap();
+
+        let config = config();
+        let mut processor = PiiProcessor::new(config.compiled());
+        processor::process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let report = audit_value(&config, &event, ProcessingState::root()).unwrap();
+        assert!(report.matches.is_empty());
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let log = Annotated::<OurLog>::from_json(
+            r#"{
+                "timestamp": 1544719860.0,
+                "trace_id": "5b8efff798038103d269b633813fc60c",
+                "level": "info",
+                "body": "login from 127.0.0.1"
+            }"#,
+        )
+        .unwrap();
+
+        let report = audit_value(&config(), &log, ProcessingState::root()).unwrap();
+        assert_eq!(
+            report.matches,
+            [PiiAuditMatch {
+                rule_id: "@ip:replace".to_owned(),
+                path: "body".to_owned(),
+                redaction: AuditRedaction::Replace,
+                count: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_audit_untyped() {
+        let value = Annotated::<Value>::from_json(r#"{"environment": "secret"}"#).unwrap();
+
+        let report = audit_value(&config(), &value, ProcessingState::root()).unwrap();
+        assert!(report.matches.is_empty());
+
+        let state = ProcessingState::new_root(
+            Some(Cow::Owned(FieldAttrs::new().pii(Pii::True))),
+            [ValueType::Object],
+        );
+        let report = audit_value(&config(), &value, &state).unwrap();
+        assert_eq!(report.matches.len(), 1);
+        assert_eq!(report.matches[0].path, "environment");
+    }
+
+    #[test]
+    fn test_audit_item_check_in() {
+        let check_in = br#"{"monitor_slug": "my-monitor", "environment": "secret"}"#;
+
+        let report = audit_item(&config(), "check_in", "", check_in).unwrap();
+        assert!(!report.scrubbed);
+        assert_eq!(report.matches.len(), 1);
+        assert_eq!(report.matches[0].path, "environment");
+
+        let log = br#"{"level": "info", "body": "secret"}"#;
+        let report = audit_item(&config(), "log", "", log).unwrap();
+        assert!(report.scrubbed);
+        assert_eq!(report.matches.len(), 1);
+
+        let err = audit_item(&config(), "session", "", b"{}").unwrap_err();
+        assert!(matches!(err, AuditError::UnsupportedItemType(_)));
+    }
+}
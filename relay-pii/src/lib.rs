@@ -7,6 +7,7 @@
 )]
 
 mod attachments;
+mod audit;
 mod builtin;
 mod compiledconfig;
 mod config;
@@ -24,6 +25,7 @@ mod utils;
 pub mod transform;
 
 pub use self::attachments::*;
+pub use self::audit::*;
 pub use self::compiledconfig::*;
 pub use self::config::*;
 pub use self::generate_selectors::selector_suggestions_from_value;
@@ -197,7 +197,18 @@ impl SelectorSpec {
     /// This walks both the selector and the path starting at the end and towards the root
     /// to determine if the selector matches the current path.
     pub fn matches_path(&self, path: &Path) -> bool {
-        let pii = path.attrs().pii;
+        self.matches_path_with_pii(path, path.attrs().pii)
+    }
+
+    /// Checks if a path matches given selector regardless of the PII attributes of the path.
+    ///
+    /// Use this for applications other than data scrubbing, such as selecting fields for
+    /// trimming. Deep wildcards and value types match as if the path was marked as PII.
+    pub fn matches_path_ignoring_pii(&self, path: &Path) -> bool {
+        self.matches_path_with_pii(path, Pii::True)
+    }
+
+    fn matches_path_with_pii(&self, path: &Path, pii: Pii) -> bool {
         if pii == Pii::False {
             return false;
         }
@@ -254,9 +265,9 @@ impl SelectorSpec {
                     .all(|(state, (i, selector_path))| selector_path.matches_state(pii, i, state))
                     && selector_iter.next().is_none()
             }
-            SelectorSpec::And(ref xs) => xs.iter().all(|x| x.matches_path(path)),
-            SelectorSpec::Or(ref xs) => xs.iter().any(|x| x.matches_path(path)),
-            SelectorSpec::Not(ref x) => !x.matches_path(path),
+            SelectorSpec::And(ref xs) => xs.iter().all(|x| x.matches_path_with_pii(path, pii)),
+            SelectorSpec::Or(ref xs) => xs.iter().any(|x| x.matches_path_with_pii(path, pii)),
+            SelectorSpec::Not(ref x) => !x.matches_path_with_pii(path, pii),
         }
     }
 }
@@ -631,6 +642,30 @@ mod tests {
         assert_not_matches!(foo_state, "($object & $object.*)",);
     }
 
+    #[test]
+    fn test_matching_ignoring_pii() {
+        let event_state = ProcessingState::new_root(None, Some(ValueType::Event));
+        let contexts_state = event_state.enter_static("contexts", None, Some(ValueType::Object));
+        let os_state = contexts_state.enter_static("os", None, Some(ValueType::Object));
+
+        for selector in [
+            "contexts.os",
+            "contexts.*",
+            "$event.**",
+            "contexts.** && !extra",
+        ] {
+            let spec: SelectorSpec = selector.parse().unwrap();
+            assert!(!spec.matches_path(&os_state.path()), "{selector}");
+            assert!(
+                spec.matches_path_ignoring_pii(&os_state.path()),
+                "{selector}"
+            );
+        }
+
+        let spec: SelectorSpec = "extra.*".parse().unwrap();
+        assert!(!spec.matches_path_ignoring_pii(&os_state.path()));
+    }
+
     #[test]
     fn test_attachments_matching() {
         let event_state = ProcessingState::new_root(None, None);
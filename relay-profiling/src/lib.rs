@@ -113,6 +113,7 @@ struct MinimalProfile {
     event_id: ProfileId,
     platform: String,
     release: Option<String>,
+    environment: Option<String>,
     #[serde(default)]
     version: sample::Version,
 }
@@ -159,6 +160,7 @@ impl Getter for MinimalProfile {
     fn get_value(&self, path: &str) -> Option<Val<'_>> {
         match path.strip_prefix("event.")? {
             "release" => self.release.as_deref().map(|release| release.into()),
+            "environment" => self.environment.as_deref().map(|env| env.into()),
             "platform" => Some(self.platform.as_str().into()),
             _ => None,
         }
@@ -414,6 +416,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_filter_profile_chunk_environment() {
+        let payload = br#"{
+            "chunk_id": "751fff80a266467ba6f5eeeef65f4f84",
+            "platform": "python",
+            "environment": "dev",
+            "version": "2"
+        }"#;
+        let chunk = ProfileChunk::new(Bytes::from_static(payload)).unwrap();
+
+        let filters: ProjectFiltersConfig = serde_json::from_value(serde_json::json!({
+            "generic": {
+                "version": 1,
+                "filters": [{
+                    "id": "dev-env",
+                    "isEnabled": true,
+                    "condition": {"op": "eq", "name": "event.environment", "value": "dev"}
+                }]
+            }
+        }))
+        .unwrap();
+
+        let result = chunk.filter(None, &filters, &GlobalConfig::default());
+        assert!(matches!(result, Err(ProfileError::Filtered(_))));
+    }
+
     #[test]
     fn test_expand_profile_without_version() {
         let payload = include_bytes!("../tests/fixtures/android/legacy/roundtrip.json");
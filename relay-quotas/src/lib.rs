@@ -11,9 +11,11 @@
 /// typically happens for disabled keys, projects, or organizations.
 const REJECT_ALL_SECS: u64 = 60;
 
+mod memory;
 mod quota;
 mod rate_limit;
 
+pub use self::memory::MemoryRateLimiter;
 pub use self::quota::*;
 pub use self::rate_limit::*;
 
@@ -0,0 +1,472 @@
+use std::sync::{Arc, Mutex};
+
+use hashbrown::HashMap;
+use relay_base_schema::metrics::MetricNamespace;
+use relay_common::time::UnixTimestamp;
+
+use crate::REJECT_ALL_SECS;
+use crate::quota::{ItemScoping, Quota, QuotaScope};
+use crate::rate_limit::{RateLimit, RateLimits, RetryAfter};
+
+/// Interval in seconds in which expired counters are removed.
+const VACUUM_INTERVAL: u64 = 60;
+
+/// Identifies a counter of a quota within a single window.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct CounterKey {
+    prefix: String,
+    /// The organization of the counter, or `None` for global quotas shared by all organizations.
+    organization_id: Option<u64>,
+    subscope: Option<u64>,
+    namespace: Option<MetricNamespace>,
+    slot: u64,
+}
+
+/// Current consumption of a quota within a single window.
+#[derive(Debug)]
+struct Counter {
+    consumed: u64,
+    expiry: UnixTimestamp,
+}
+
+/// Information required to track a quota in memory.
+///
+/// Windows are computed the same way as for the `RedisRateLimiter`, so that both rate limiters
+/// start and end their windows at the same time.
+struct MemoryQuota<'a> {
+    quota: &'a Quota,
+    scoping: ItemScoping,
+    prefix: &'a str,
+    limit: u64,
+    window: u64,
+    timestamp: UnixTimestamp,
+}
+
+impl<'a> MemoryQuota<'a> {
+    /// Returns `None` if the quota is unlimited or cannot be tracked.
+    fn new(
+        quota: &'a Quota,
+        scoping: ItemScoping,
+        timestamp: UnixTimestamp,
+        limit_fraction: f64,
+    ) -> Option<Self> {
+        let prefix = quota.id.as_deref()?;
+        let window = quota.window.filter(|window| *window > 0)?;
+        let limit = quota.limit?;
+
+        Some(Self {
+            quota,
+            scoping,
+            prefix,
+            // Never round a non-zero limit down to zero, which would reject everything.
+            limit: ((limit as f64 * limit_fraction).ceil() as u64).max(1),
+            window,
+            timestamp,
+        })
+    }
+
+    fn shift(&self) -> u64 {
+        if self.quota.scope == QuotaScope::Global {
+            0
+        } else {
+            self.scoping.organization_id.value() % self.window
+        }
+    }
+
+    fn slot(&self) -> u64 {
+        (self.timestamp.as_secs() - self.shift()) / self.window
+    }
+
+    fn expiry(&self) -> UnixTimestamp {
+        let next_slot = self.slot() + 1;
+        UnixTimestamp::from_secs(next_slot * self.window + self.shift())
+    }
+
+    fn key(&self) -> CounterKey {
+        let (organization_id, subscope) = match self.quota.scope {
+            QuotaScope::Global => (None, None),
+            QuotaScope::Organization => (Some(self.scoping.organization_id.value()), None),
+            scope => (
+                Some(self.scoping.organization_id.value()),
+                self.scoping.scope_id(scope),
+            ),
+        };
+
+        CounterKey {
+            prefix: self.prefix.to_owned(),
+            organization_id,
+            subscope,
+            namespace: self.quota.namespace,
+            slot: self.slot(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    counters: HashMap<CounterKey, Counter>,
+    last_vacuum: u64,
+}
+
+impl State {
+    /// Removes all counters of windows that have ended.
+    fn vacuum(&mut self, now: UnixTimestamp) {
+        if now.as_secs() < self.last_vacuum + VACUUM_INTERVAL {
+            return;
+        }
+
+        self.counters.retain(|_, counter| counter.expiry > now);
+        self.last_vacuum = now.as_secs();
+    }
+}
+
+/// A rate limiter that tracks quotas in the memory of this Relay instance.
+///
+/// This is an alternative to the `RedisRateLimiter` for Relays which do not have access to Redis,
+/// such as static or proxy Relays without an upstream that enforces quotas. Quotas are counted
+/// separately in every instance. When several instances share the traffic of a project, each
+/// instance can be assigned a fraction of the limit with [`limit_fraction`](Self::limit_fraction),
+/// which enforces the limit approximately across all instances.
+///
+/// Cloning the rate limiter shares the counters between the clones.
+#[derive(Clone, Debug)]
+pub struct MemoryRateLimiter {
+    state: Arc<Mutex<State>>,
+    max_limit: Option<u64>,
+    limit_fraction: f64,
+}
+
+impl MemoryRateLimiter {
+    /// Creates a new [`MemoryRateLimiter`] instance.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::default(),
+            max_limit: None,
+            limit_fraction: 1.0,
+        }
+    }
+
+    /// Sets the maximum rate limit in seconds.
+    ///
+    /// By default, this rate limiter will return rate limits based on the quotas' `window` fields.
+    /// If a maximum rate limit is set, the returned rate limit will be bounded by this value.
+    pub fn max_limit(mut self, max_limit: Option<u64>) -> Self {
+        self.max_limit = max_limit;
+        self
+    }
+
+    /// Sets the fraction of every quota's limit that is enforced by this instance.
+    ///
+    /// The fraction is clamped to the range `(0, 1]`. Defaults to `1.0`, which enforces the full
+    /// limit in this instance.
+    pub fn limit_fraction(mut self, fraction: f64) -> Self {
+        self.limit_fraction = if fraction > 0.0 {
+            fraction.min(1.0)
+        } else {
+            1.0
+        };
+        self
+    }
+
+    /// Checks whether any of the quotas in effect have been exceeded and records consumption.
+    ///
+    /// This has the same semantics as `RedisRateLimiter::is_rate_limited`: Consumption is only
+    /// recorded if none of the quotas have been exceeded, `over_accept_once` accepts the item once
+    /// when it would exceed the limit, and a `quantity` of `0` checks whether the limit has been
+    /// reached without consuming quota.
+    pub fn is_rate_limited<'a>(
+        &self,
+        quotas: impl IntoIterator<Item = &'a Quota>,
+        item_scoping: ItemScoping,
+        quantity: usize,
+        over_accept_once: bool,
+    ) -> RateLimits {
+        self.is_rate_limited_at(
+            quotas,
+            item_scoping,
+            quantity,
+            over_accept_once,
+            UnixTimestamp::now(),
+        )
+    }
+
+    fn is_rate_limited_at<'a>(
+        &self,
+        quotas: impl IntoIterator<Item = &'a Quota>,
+        item_scoping: ItemScoping,
+        quantity: usize,
+        over_accept_once: bool,
+        timestamp: UnixTimestamp,
+    ) -> RateLimits {
+        let quantity = quantity as u64;
+        let mut tracked_quotas = Vec::new();
+        let mut rate_limits = RateLimits::new();
+
+        for quota in quotas {
+            if !quota.matches(item_scoping) {
+                // Silently skip all quotas that do not apply to this item.
+            } else if quota.limit == Some(0) {
+                let retry_after = self.retry_after(REJECT_ALL_SECS);
+                rate_limits.add(RateLimit::from_quota(quota, *item_scoping, retry_after));
+            } else if let Some(quota) =
+                MemoryQuota::new(quota, item_scoping, timestamp, self.limit_fraction)
+            {
+                tracked_quotas.push(quota);
+            }
+        }
+
+        if tracked_quotas.is_empty() || rate_limits.is_limited() {
+            return rate_limits;
+        }
+
+        let keys: Vec<_> = tracked_quotas.iter().map(MemoryQuota::key).collect();
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.vacuum(timestamp);
+
+        for (quota, key) in tracked_quotas.iter().zip(&keys) {
+            let consumed = state.counters.get(key).map_or(0, |c| c.consumed);
+            let is_rejected = if quantity == 0 || over_accept_once {
+                consumed >= quota.limit
+            } else {
+                consumed + quantity > quota.limit
+            };
+
+            if is_rejected {
+                let retry_after = self.retry_after((quota.expiry() - timestamp).as_secs());
+                rate_limits.add(RateLimit::from_quota(
+                    quota.quota,
+                    *item_scoping,
+                    retry_after,
+                ));
+            }
+        }
+
+        if !rate_limits.is_limited() && quantity > 0 {
+            for (quota, key) in tracked_quotas.iter().zip(keys) {
+                let counter = state.counters.entry(key).or_insert_with(|| Counter {
+                    consumed: 0,
+                    expiry: quota.expiry(),
+                });
+                counter.consumed += quantity;
+            }
+        }
+
+        rate_limits
+    }
+
+    /// Creates a [`RetryAfter`] value that is bounded by the configured [`max_limit`](Self::max_limit).
+    fn retry_after(&self, mut seconds: u64) -> RetryAfter {
+        if let Some(max_limit) = self.max_limit {
+            seconds = std::cmp::min(seconds, max_limit);
+        }
+
+        RetryAfter::from_secs(seconds)
+    }
+}
+
+impl Default for MemoryRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_base_schema::data_category::DataCategory;
+    use relay_base_schema::organization::OrganizationId;
+    use relay_base_schema::project::{ProjectId, ProjectKey};
+
+    use super::*;
+    use crate::quota::{DataCategories, ReasonCode, Scoping};
+    use crate::{MetricNamespaceScoping, RateLimitScope};
+
+    fn quota(scope: QuotaScope, limit: u64) -> Quota {
+        Quota {
+            id: Some("foo".to_owned()),
+            categories: DataCategories::new(),
+            scope,
+            scope_id: None,
+            limit: Some(limit),
+            window: Some(60),
+            reason_code: Some(ReasonCode::new("get_lost")),
+            namespace: None,
+        }
+    }
+
+    fn scoping(key_id: u64) -> ItemScoping {
+        org_scoping(42, key_id)
+    }
+
+    fn org_scoping(organization_id: u64, key_id: u64) -> ItemScoping {
+        ItemScoping {
+            category: DataCategory::Error,
+            scoping: Scoping {
+                organization_id: OrganizationId::new(organization_id),
+                project_id: ProjectId::new(43),
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                key_id: Some(key_id),
+            },
+            namespace: MetricNamespaceScoping::None,
+        }
+    }
+
+    #[test]
+    fn test_simple_quota() {
+        let quotas = &[quota(QuotaScope::Key, 5)];
+        let timestamp = UnixTimestamp::from_secs(1000);
+        let rate_limiter = MemoryRateLimiter::new();
+
+        for i in 0..10 {
+            let rate_limits: Vec<_> = rate_limiter
+                .is_rate_limited_at(quotas, scoping(44), 1, false, timestamp)
+                .into_iter()
+                .collect();
+
+            if i < 5 {
+                assert_eq!(rate_limits, vec![]);
+            } else {
+                assert_eq!(
+                    rate_limits,
+                    vec![RateLimit {
+                        categories: DataCategories::new(),
+                        scope: RateLimitScope::Key(
+                            ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap()
+                        ),
+                        reason_code: Some(ReasonCode::new("get_lost")),
+                        retry_after: rate_limits[0].retry_after,
+                        namespaces: Default::default(),
+                    }]
+                );
+            }
+        }
+
+        // Other keys are counted separately.
+        let rate_limits = rate_limiter.is_rate_limited_at(quotas, scoping(45), 1, false, timestamp);
+        assert!(rate_limits.is_ok());
+
+        // The next window starts with a fresh counter.
+        let next_window = UnixTimestamp::from_secs(1060);
+        let rate_limits =
+            rate_limiter.is_rate_limited_at(quotas, scoping(44), 1, false, next_window);
+        assert!(rate_limits.is_ok());
+    }
+
+    #[test]
+    fn test_global_quota_shared_by_organizations() {
+        let quotas = &[quota(QuotaScope::Global, 5)];
+        let timestamp = UnixTimestamp::from_secs(1000);
+        let rate_limiter = MemoryRateLimiter::new();
+
+        for _ in 0..3 {
+            let rate_limits =
+                rate_limiter.is_rate_limited_at(quotas, org_scoping(42, 44), 1, false, timestamp);
+            assert!(rate_limits.is_ok());
+        }
+
+        for _ in 0..2 {
+            let rate_limits =
+                rate_limiter.is_rate_limited_at(quotas, org_scoping(43, 45), 1, false, timestamp);
+            assert!(rate_limits.is_ok());
+        }
+
+        // Both organizations consumed the same global limit.
+        for organization_id in [42, 43] {
+            let rate_limits = rate_limiter.is_rate_limited_at(
+                quotas,
+                org_scoping(organization_id, 44),
+                1,
+                false,
+                timestamp,
+            );
+            assert!(rate_limits.is_limited());
+        }
+    }
+
+    #[test]
+    fn test_quantity_and_over_accept_once() {
+        let quotas = &[quota(QuotaScope::Organization, 5)];
+        let timestamp = UnixTimestamp::from_secs(1000);
+        let rate_limiter = MemoryRateLimiter::new();
+
+        let check = |quantity, over_accept_once| {
+            rate_limiter
+                .is_rate_limited_at(quotas, scoping(44), quantity, over_accept_once, timestamp)
+                .is_limited()
+        };
+
+        assert!(!check(4, false));
+        // Exceeds the limit and does not consume.
+        assert!(check(2, false));
+        assert!(!check(0, false));
+        // Accepted once beyond the limit.
+        assert!(!check(2, true));
+        assert!(check(0, false));
+        assert!(check(1, true));
+    }
+
+    #[test]
+    fn test_rejects_atomically() {
+        let quotas = &[
+            quota(QuotaScope::Key, 10),
+            Quota {
+                id: Some("bar".to_owned()),
+                ..quota(QuotaScope::Project, 1)
+            },
+        ];
+        let timestamp = UnixTimestamp::from_secs(1000);
+        let rate_limiter = MemoryRateLimiter::new();
+
+        for _ in 0..5 {
+            rate_limiter.is_rate_limited_at(quotas, scoping(44), 1, false, timestamp);
+        }
+
+        // Only the first item was counted against the key quota.
+        let rate_limits =
+            rate_limiter.is_rate_limited_at(&quotas[..1], scoping(44), 9, false, timestamp);
+        assert!(rate_limits.is_ok());
+    }
+
+    #[test]
+    fn test_limit_fraction() {
+        let quotas = &[quota(QuotaScope::Organization, 10)];
+        let timestamp = UnixTimestamp::from_secs(1000);
+        let rate_limiter = MemoryRateLimiter::new().limit_fraction(0.25);
+
+        let accepted = (0..10)
+            .filter(|_| {
+                rate_limiter
+                    .is_rate_limited_at(quotas, scoping(44), 1, false, timestamp)
+                    .is_ok()
+            })
+            .count();
+
+        // A quarter of 10 is rounded up.
+        assert_eq!(accepted, 3);
+    }
+
+    #[test]
+    fn test_zero_size_and_unlimited_quotas() {
+        let rate_limiter = MemoryRateLimiter::new().max_limit(Some(30));
+        let timestamp = UnixTimestamp::from_secs(1000);
+
+        let unlimited = &[Quota {
+            limit: None,
+            ..quota(QuotaScope::Organization, 0)
+        }];
+        for _ in 0..10 {
+            let rate_limits =
+                rate_limiter.is_rate_limited_at(unlimited, scoping(44), 1, false, timestamp);
+            assert!(rate_limits.is_ok());
+        }
+
+        let zero = &[quota(QuotaScope::Organization, 0)];
+        let rate_limits = rate_limiter.is_rate_limited_at(zero, scoping(44), 1, false, timestamp);
+        let limit = rate_limits.longest().unwrap();
+        assert_eq!(
+            limit.scope,
+            RateLimitScope::Organization(OrganizationId::new(42))
+        );
+        assert_eq!(limit.retry_after.remaining_seconds(), 30);
+    }
+}
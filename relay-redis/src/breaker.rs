@@ -0,0 +1,247 @@
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::config::RedisCircuitBreakerConfig;
+
+/// State of a [`CircuitBreaker`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CircuitState {
+    /// Redis is healthy and all requests are sent to it.
+    Closed,
+    /// Redis recovers from failures, a limited number of probe requests is sent to it.
+    HalfOpen,
+    /// Redis is failing and requests are rejected without contacting it.
+    Open,
+}
+
+impl CircuitState {
+    /// Returns the name of the state as used in metrics and logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::HalfOpen => "half_open",
+            Self::Open => "open",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed {
+        /// Number of consecutive failures.
+        failures: u32,
+    },
+    HalfOpen {
+        /// Number of probes admitted since `since`.
+        probes: u32,
+        /// Time the current round of probes started.
+        since: Instant,
+    },
+    Open {
+        /// Time from which probes are admitted again.
+        until: Instant,
+    },
+}
+
+/// A circuit breaker shared by all connections of a Redis client.
+///
+/// The breaker opens after a configured number of consecutive failures to reach Redis. While it is
+/// open, requests fail immediately instead of waiting for connection or command timeouts. After the
+/// retry interval, the breaker admits a limited number of probe requests. A successful probe
+/// closes the breaker, a failed probe opens it again.
+///
+/// Only failures to reach Redis count towards the threshold. Errors returned by Redis itself,
+/// such as script errors, indicate that Redis is available.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: RedisCircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker.
+    pub fn new(config: RedisCircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner::Closed { failures: 0 }),
+        }
+    }
+
+    /// Returns the current state of the breaker.
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            Inner::Closed { .. } => CircuitState::Closed,
+            Inner::HalfOpen { .. } => CircuitState::HalfOpen,
+            Inner::Open { until } if Instant::now() >= until => CircuitState::HalfOpen,
+            Inner::Open { .. } => CircuitState::Open,
+        }
+    }
+
+    /// Returns `true` if a request may be sent to Redis.
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Records the outcome of a request that was sent to Redis.
+    pub fn record(&self, success: bool) {
+        self.record_at(success, Instant::now());
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let mut inner = self.lock();
+        match *inner {
+            Inner::Closed { .. } => true,
+            Inner::Open { until } if now < until => false,
+            Inner::Open { .. } => {
+                *inner = Inner::HalfOpen {
+                    probes: 1,
+                    since: now,
+                };
+                true
+            }
+            Inner::HalfOpen {
+                ref mut probes,
+                since,
+            } => {
+                if *probes < self.config.half_open_probes {
+                    *probes += 1;
+                    true
+                } else if now.duration_since(since) >= self.retry_interval() {
+                    // The admitted probes did not report back, admit a new round.
+                    *inner = Inner::HalfOpen {
+                        probes: 1,
+                        since: now,
+                    };
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn record_at(&self, success: bool, now: Instant) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut inner = self.lock();
+        match (&mut *inner, success) {
+            (Inner::Closed { failures }, true) => *failures = 0,
+            (Inner::Closed { failures }, false) => {
+                *failures += 1;
+                if *failures >= self.config.failure_threshold {
+                    relay_log::error!(
+                        failures = *failures,
+                        "redis circuit breaker opened after consecutive failures",
+                    );
+                    *inner = Inner::Open {
+                        until: now + self.retry_interval(),
+                    };
+                }
+            }
+            (Inner::HalfOpen { .. }, true) => {
+                relay_log::info!("redis circuit breaker closed");
+                *inner = Inner::Closed { failures: 0 };
+            }
+            (Inner::HalfOpen { .. }, false) => {
+                *inner = Inner::Open {
+                    until: now + self.retry_interval(),
+                };
+            }
+            // Outcomes of requests sent before the breaker opened do not change the state.
+            (Inner::Open { .. }, _) => (),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.failure_threshold > 0
+    }
+
+    fn retry_interval(&self) -> Duration {
+        Duration::from_secs(self.config.retry_interval)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(failure_threshold: u32, half_open_probes: u32) -> CircuitBreaker {
+        CircuitBreaker::new(RedisCircuitBreakerConfig {
+            failure_threshold,
+            retry_interval: 5,
+            half_open_probes,
+        })
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = breaker(3, 1);
+        let now = Instant::now();
+
+        breaker.record_at(false, now);
+        breaker.record_at(false, now);
+        // A success resets the consecutive failures.
+        breaker.record_at(true, now);
+        breaker.record_at(false, now);
+        breaker.record_at(false, now);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire_at(now));
+
+        breaker.record_at(false, now);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.try_acquire_at(now));
+        assert!(!breaker.try_acquire_at(now + Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn test_half_open_probes() {
+        let breaker = breaker(1, 2);
+        let now = Instant::now();
+
+        breaker.record_at(false, now);
+        assert!(!breaker.try_acquire_at(now));
+
+        let later = now + Duration::from_secs(5);
+        assert!(breaker.try_acquire_at(later));
+        assert!(breaker.try_acquire_at(later));
+        assert!(!breaker.try_acquire_at(later));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // Probes that never report back do not keep the breaker half open forever.
+        assert!(breaker.try_acquire_at(later + Duration::from_secs(5)));
+
+        // A failed probe opens the breaker again.
+        breaker.record_at(false, later);
+        assert!(!breaker.try_acquire_at(later + Duration::from_secs(1)));
+
+        // A successful probe closes it.
+        let much_later = later + Duration::from_secs(10);
+        assert!(breaker.try_acquire_at(much_later));
+        breaker.record_at(true, much_later);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire_at(much_later));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let breaker = breaker(0, 1);
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            breaker.record_at(false, now);
+        }
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire_at(now));
+    }
+}
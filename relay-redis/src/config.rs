@@ -1,5 +1,66 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
+/// TLS configuration for connections to Redis.
+///
+/// TLS is enabled by using `rediss://` urls. The certificates configured here are used to verify
+/// the server and to authenticate the client. Disabling certificate verification is rejected.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisTlsConfig {
+    /// Path to a PEM file with the certificate authorities used to verify the server.
+    ///
+    /// Defaults to the root certificates of the operating system.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    /// Path to a PEM file with the client certificate chain for mutual TLS.
+    ///
+    /// Requires `client_key` to be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// Path to a PEM file with the private key of the client certificate.
+    ///
+    /// Requires `client_cert` to be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Server name sent with SNI and used to verify the server certificate.
+    ///
+    /// Defaults to the host of the url. When set, the name is used for all connections of the
+    /// client, including connections to nodes discovered from a cluster or a sentinel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+}
+
+/// Configuration of the circuit breaker of a redis client.
+///
+/// The circuit breaker stops sending requests to Redis after consecutive failures to reach it and
+/// lets consumers fall back to their degraded behavior immediately.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct RedisCircuitBreakerConfig {
+    /// Number of consecutive failures after which the circuit breaker opens.
+    ///
+    /// Failures are connection errors, timeouts and I/O errors. Set to `0` to disable the circuit
+    /// breaker.
+    pub failure_threshold: u32,
+    /// Time in seconds after which an open circuit breaker admits probe requests.
+    pub retry_interval: u64,
+    /// Maximum number of probe requests admitted per retry interval while the circuit breaker is
+    /// half-open.
+    pub half_open_probes: u32,
+}
+
+impl Default for RedisCircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 20,
+            retry_interval: 5,
+            half_open_probes: 1,
+        }
+    }
+}
+
 /// Additional configuration options for a redis client.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct RedisConfigOptions {
@@ -33,6 +94,10 @@ pub struct RedisConfigOptions {
     /// A frequency of 1, means that the connection will check whether it is active every time it
     /// is recycled.
     pub recycle_check_frequency: usize,
+    /// TLS configuration for connections using `rediss://` urls.
+    pub tls: Option<RedisTlsConfig>,
+    /// Configuration of the circuit breaker.
+    pub circuit_breaker: RedisCircuitBreakerConfig,
 }
 
 impl Default for RedisConfigOptions {
@@ -44,6 +109,8 @@ impl Default for RedisConfigOptions {
             recycle_timeout: Some(2),
             wait_timeout: None,
             recycle_check_frequency: 100,
+            tls: None,
+            circuit_breaker: RedisCircuitBreakerConfig::default(),
         }
     }
 }
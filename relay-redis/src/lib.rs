@@ -12,6 +12,11 @@
 mod config;
 pub use self::config::*;
 
+#[cfg(feature = "impl")]
+mod breaker;
+#[cfg(feature = "impl")]
+pub use self::breaker::*;
+
 #[cfg(feature = "impl")]
 mod pool;
 
@@ -25,6 +30,9 @@ mod scripts;
 #[cfg(feature = "impl")]
 pub use self::scripts::*;
 
+#[cfg(feature = "impl")]
+mod tls;
+
 #[cfg(not(feature = "impl"))]
 mod noop;
 
@@ -39,4 +39,15 @@ impl AsyncRedisClient {
     pub async fn single(_server: &str, _opts: &RedisConfigOptions) -> Result<Self, RedisError> {
         Ok(Self)
     }
+
+    /// Creates a [`AsyncRedisClient`] for a master monitored by Redis Sentinel.
+    ///
+    /// Always returns `Ok(Self)`
+    pub async fn sentinel<'a>(
+        _master_name: &str,
+        _sentinels: impl IntoIterator<Item = &'a str>,
+        _opts: &RedisConfigOptions,
+    ) -> Result<Self, RedisError> {
+        Ok(Self)
+    }
 }
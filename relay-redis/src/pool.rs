@@ -1,15 +1,16 @@
 use deadpool::managed::{Manager, Metrics, Object, Pool, RecycleError, RecycleResult};
-use deadpool_redis::Manager as SingleManager;
-use deadpool_redis::cluster::Manager as ClusterManager;
 use futures::FutureExt;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::Mutex;
 
 use crate::redis;
 use crate::redis::aio::MultiplexedConnection;
-use crate::redis::cluster_async::ClusterConnection;
-use crate::redis::{
-    Cmd, IntoConnectionInfo, Pipeline, RedisError, RedisFuture, RedisResult, Value,
-};
+use crate::redis::cluster::ClusterClient;
+use crate::redis::sentinel::SentinelClient;
+use crate::redis::{Client, Cmd, ErrorKind, Pipeline, RedisError, RedisFuture, Value};
+use crate::tls::{self, AnyClusterConnection, ServerNameSentinel, ServerNameTls};
 
 /// A connection pool for Redis cluster deployments.
 pub type CustomClusterPool = Pool<CustomClusterManager, CustomClusterConnection>;
@@ -17,6 +18,9 @@ pub type CustomClusterPool = Pool<CustomClusterManager, CustomClusterConnection>
 /// A connection pool for single Redis instance deployments.
 pub type CustomSinglePool = Pool<CustomSingleManager, CustomSingleConnection>;
 
+/// A connection pool for Redis deployments monitored by Redis Sentinel.
+pub type CustomSentinelPool = Pool<CustomSentinelManager, CustomSentinelConnection>;
+
 /// A wrapper for a connection that can be tracked with metadata.
 ///
 /// A connection is considered detached as soon as it is marked as detached and it can't be
@@ -37,9 +41,10 @@ impl<C> TrackedConnection<C> {
     /// Returns `true` when a [`RedisError`] should lead to the [`TrackedConnection`] being detached
     /// from the pool.
     ///
-    /// An error leads to the connection being detached if it is unrecoverable.
+    /// An error leads to the connection being detached if it is unrecoverable or if the node no
+    /// longer accepts writes, which happens when a master is demoted to a replica on failover.
     fn should_be_detached(error: &RedisError) -> bool {
-        error.is_unrecoverable_error()
+        error.is_unrecoverable_error() || error.kind() == ErrorKind::ReadOnly
     }
 }
 
@@ -133,40 +138,59 @@ impl redis::aio::ConnectionLike for CustomClusterConnection {
 /// This manager handles the creation and recycling of Redis cluster connections,
 /// ensuring proper connection health through periodic PING checks. It supports both
 /// primary and replica nodes, with optional read-from-replicas functionality.
-#[derive(Debug)]
 pub struct CustomClusterManager {
-    manager: ClusterManager,
+    client: ClusterClient,
+    tls_slot: Option<usize>,
+    ping_number: AtomicUsize,
     recycle_check_frequency: usize,
 }
 
 impl CustomClusterManager {
-    /// Creates a new cluster manager for the specified Redis nodes.
+    /// Creates a new cluster manager for the specified Redis cluster client.
     ///
-    /// The manager will attempt to connect to each node in the provided list and
-    /// maintain connections to the Redis cluster.
-    pub fn new<T: IntoConnectionInfo>(
-        params: Vec<T>,
-        read_from_replicas: bool,
+    /// The manager will use the client to connect to the nodes of the cluster and
+    /// maintain connections to the Redis cluster. If a TLS slot is given, the nodes are connected
+    /// with the TLS connector registered in that slot.
+    pub fn new(
+        client: ClusterClient,
+        tls_slot: Option<usize>,
         recycle_check_frequency: usize,
-    ) -> RedisResult<Self> {
-        Ok(Self {
-            manager: ClusterManager::new(params, read_from_replicas)?,
+    ) -> Self {
+        Self {
+            client,
+            tls_slot,
+            ping_number: AtomicUsize::new(0),
             recycle_check_frequency,
-        })
+        }
+    }
+}
+
+impl std::fmt::Debug for CustomClusterManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomClusterManager")
+            .field("tls_slot", &self.tls_slot)
+            .field("ping_number", &self.ping_number)
+            .field("recycle_check_frequency", &self.recycle_check_frequency)
+            .finish()
     }
 }
 
 impl Manager for CustomClusterManager {
-    type Type = TrackedConnection<ClusterConnection>;
+    type Type = TrackedConnection<AnyClusterConnection>;
     type Error = RedisError;
 
-    async fn create(&self) -> Result<TrackedConnection<ClusterConnection>, RedisError> {
-        self.manager.create().await.map(TrackedConnection::from)
+    async fn create(&self) -> Result<TrackedConnection<AnyClusterConnection>, RedisError> {
+        let connection = match self.tls_slot {
+            Some(slot) => tls::connect_cluster(&self.client, slot).await?,
+            None => self.client.get_async_connection().await?.into(),
+        };
+
+        Ok(TrackedConnection::from(connection))
     }
 
     async fn recycle(
         &self,
-        conn: &mut TrackedConnection<ClusterConnection>,
+        conn: &mut TrackedConnection<AnyClusterConnection>,
         metrics: &Metrics,
     ) -> RecycleResult<RedisError> {
         // If the connection is marked to be detached, we return and error, signaling that this
@@ -183,7 +207,13 @@ impl Manager for CustomClusterManager {
             return Ok(());
         }
 
-        self.manager.recycle(conn, metrics).await
+        let ping_number = self.ping_number.fetch_add(1, Ordering::Relaxed).to_string();
+        let response = redis::cmd("PING")
+            .arg(&ping_number)
+            .query_async::<String>(&mut conn.connection)
+            .await?;
+
+        check_ping_response(&response, &ping_number)
     }
 }
 
@@ -223,24 +253,31 @@ impl redis::aio::ConnectionLike for CustomSingleConnection {
 /// This manager handles the creation and recycling of Redis connections,
 /// ensuring proper connection health through periodic PING checks. It supports
 /// multiplexed connections for efficient handling of multiple operations.
+#[derive(Debug)]
 pub struct CustomSingleManager {
-    manager: SingleManager,
+    client: Client,
+    tls: Option<Arc<ServerNameTls>>,
+    ping_number: AtomicUsize,
     recycle_check_frequency: usize,
 }
 
 impl CustomSingleManager {
     /// Creates a new manager for a single Redis instance.
     ///
-    /// The manager will establish and maintain connections to the specified Redis
-    /// instance, handling connection lifecycle and health checks.
-    pub fn new<T: IntoConnectionInfo>(
-        params: T,
+    /// The manager will establish and maintain connections to the Redis instance of the
+    /// client, handling connection lifecycle and health checks. If a TLS connector is given, it
+    /// is used instead of the TLS configuration of the client.
+    pub fn new(
+        client: Client,
+        tls: Option<Arc<ServerNameTls>>,
         recycle_check_frequency: usize,
-    ) -> RedisResult<Self> {
-        Ok(Self {
-            manager: SingleManager::new(params)?,
+    ) -> Self {
+        Self {
+            client,
+            tls,
+            ping_number: AtomicUsize::new(0),
             recycle_check_frequency,
-        })
+        }
     }
 }
 
@@ -249,7 +286,15 @@ impl Manager for CustomSingleManager {
     type Error = RedisError;
 
     async fn create(&self) -> Result<TrackedConnection<MultiplexedConnection>, RedisError> {
-        self.manager.create().await.map(TrackedConnection::from)
+        let connection = match &self.tls {
+            Some(tls) => {
+                let info = self.client.get_connection_info();
+                tls.connect(info, Default::default()).await?
+            }
+            None => self.client.get_multiplexed_async_connection().await?,
+        };
+
+        Ok(TrackedConnection::from(connection))
     }
 
     async fn recycle(
@@ -271,7 +316,7 @@ impl Manager for CustomSingleManager {
             return Ok(());
         }
 
-        self.manager.recycle(conn, metrics).await
+        recycle_multiplexed(&mut conn.connection, &self.ping_number).await
     }
 }
 
@@ -280,3 +325,152 @@ impl From<Object<CustomSingleManager>> for CustomSingleConnection {
         Self(conn)
     }
 }
+
+/// A managed connection to the master of a Redis Sentinel deployment that implements
+/// [`redis::aio::ConnectionLike`].
+///
+/// This type provides access to the underlying Redis connection while managing its lifecycle.
+/// It is designed to be used with connection pools to efficiently handle Redis operations.
+pub struct CustomSentinelConnection(Object<CustomSentinelManager>);
+
+impl redis::aio::ConnectionLike for CustomSentinelConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        self.0.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.get_db()
+    }
+}
+
+/// Manages connections to the master of a Redis Sentinel deployment and their lifecycle.
+///
+/// The address of the master is queried from the sentinels for every new connection. After a
+/// failover, connections to the previous master are detached from the pool as soon as they fail
+/// and are replaced by connections to the newly elected master.
+pub struct CustomSentinelManager {
+    client: SentinelMaster,
+    ping_number: AtomicUsize,
+    recycle_check_frequency: usize,
+}
+
+impl CustomSentinelManager {
+    /// Creates a new manager for the master monitored by the sentinel client.
+    ///
+    /// The manager will discover the master through the sentinels and maintain connections
+    /// to it, handling connection lifecycle and health checks.
+    pub fn new(client: SentinelClient, recycle_check_frequency: usize) -> Self {
+        Self {
+            client: SentinelMaster::Client(Mutex::new(client)),
+            ping_number: AtomicUsize::new(0),
+            recycle_check_frequency,
+        }
+    }
+
+    /// Creates a new manager for the master monitored by sentinels that are connected with a
+    /// TLS server name.
+    pub fn with_server_name(client: ServerNameSentinel, recycle_check_frequency: usize) -> Self {
+        Self {
+            client: SentinelMaster::ServerName(client),
+            ping_number: AtomicUsize::new(0),
+            recycle_check_frequency,
+        }
+    }
+}
+
+/// The client used by [`CustomSentinelManager`] to discover and connect to the master.
+enum SentinelMaster {
+    /// The sentinel client of the Redis crate.
+    Client(Mutex<SentinelClient>),
+    /// A sentinel client that connects with a TLS server name.
+    ServerName(ServerNameSentinel),
+}
+
+impl std::fmt::Debug for CustomSentinelManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomSentinelManager")
+            .field("ping_number", &self.ping_number)
+            .field("recycle_check_frequency", &self.recycle_check_frequency)
+            .finish()
+    }
+}
+
+impl Manager for CustomSentinelManager {
+    type Type = TrackedConnection<MultiplexedConnection>;
+    type Error = RedisError;
+
+    async fn create(&self) -> Result<TrackedConnection<MultiplexedConnection>, RedisError> {
+        let connection = match &self.client {
+            SentinelMaster::Client(client) => client.lock().await.get_async_connection().await?,
+            SentinelMaster::ServerName(client) => client.connect().await?,
+        };
+
+        Ok(TrackedConnection::from(connection))
+    }
+
+    async fn recycle(
+        &self,
+        conn: &mut TrackedConnection<MultiplexedConnection>,
+        metrics: &Metrics,
+    ) -> RecycleResult<RedisError> {
+        // If the connection is marked to be detached, we return and error, signaling that this
+        // connection must be detached from the pool.
+        if conn.detach {
+            return Err(RecycleError::Message(
+                "the tracked connection was marked as detached".into(),
+            ));
+        }
+
+        // If the interval has been reached, we optimistically assume the connection is active
+        // without doing an actual `PING`.
+        if metrics.recycle_count % self.recycle_check_frequency != 0 {
+            return Ok(());
+        }
+
+        recycle_multiplexed(&mut conn.connection, &self.ping_number).await
+    }
+}
+
+impl From<Object<CustomSentinelManager>> for CustomSentinelConnection {
+    fn from(conn: Object<CustomSentinelManager>) -> Self {
+        Self(conn)
+    }
+}
+
+/// Checks whether a multiplexed connection is still alive with a `PING`.
+///
+/// Also resets pending `WATCH` commands, so that the connection can be safely reused.
+async fn recycle_multiplexed(
+    conn: &mut MultiplexedConnection,
+    ping_number: &AtomicUsize,
+) -> RecycleResult<RedisError> {
+    let ping_number = ping_number.fetch_add(1, Ordering::Relaxed).to_string();
+    // Using a pipeline avoids a roundtrip for the `UNWATCH`.
+    let (response,) = redis::Pipeline::with_capacity(2)
+        .cmd("UNWATCH")
+        .ignore()
+        .cmd("PING")
+        .arg(&ping_number)
+        .query_async::<(String,)>(conn)
+        .await?;
+
+    check_ping_response(&response, &ping_number)
+}
+
+/// Verifies that the server echoed the payload of a `PING`.
+fn check_ping_response(response: &str, ping_number: &str) -> RecycleResult<RedisError> {
+    if response == ping_number {
+        Ok(())
+    } else {
+        Err(RecycleError::message("invalid PING response"))
+    }
+}
@@ -1,11 +1,20 @@
-use deadpool::managed::{BuildError, Manager, Metrics, Object, Pool, PoolError};
+use deadpool::managed::{BuildError, Manager, Metrics, Object, Pool, PoolError, TimeoutType};
 use deadpool_redis::{ConfigError, Runtime};
-use redis::{Cmd, Pipeline, RedisFuture, Value};
+use redis::cluster::ClusterClientBuilder;
+use redis::sentinel::{SentinelClientBuilder, SentinelServerType};
+use redis::{
+    ClientTlsConfig, Cmd, ConnectionAddr, ConnectionInfo, IntoConnectionInfo, Pipeline,
+    RedisConnectionInfo, RedisFuture, TlsCertificates, TlsMode, Value,
+};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
-use crate::config::RedisConfigOptions;
+use crate::breaker::{CircuitBreaker, CircuitState};
+use crate::config::{RedisConfigOptions, RedisTlsConfig};
 use crate::pool;
+use crate::tls::{self, ServerNameSentinel, ServerNameTls};
 
 pub use redis;
 
@@ -34,6 +43,28 @@ pub enum RedisError {
     /// An error that occurs when configuring Redis.
     #[error("failed to configure redis: {0}")]
     ConfigError(#[from] ConfigError),
+
+    /// The circuit breaker of the client is open and Redis is not contacted.
+    #[error("redis is unavailable, the circuit breaker is open")]
+    CircuitOpen,
+
+    /// An error that occurs when loading the TLS certificates for Redis.
+    #[error("failed to load redis tls certificate {path}: {error}")]
+    Certificate {
+        /// The path of the certificate file.
+        path: String,
+        /// The error that occurred when reading the file.
+        #[source]
+        error: std::io::Error,
+    },
+}
+
+impl RedisError {
+    /// Returns `true` if the request was rejected because the circuit breaker of the client is
+    /// open.
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self, Self::CircuitOpen)
+    }
 }
 
 /// A collection of Redis clients used by Relay for different purposes.
@@ -67,18 +98,32 @@ pub struct RedisClientStats {
     ///
     /// This number increases when there are not enough connections in the pool.
     pub waiting_for_connection: u32,
+    /// The state of the circuit breaker of the client.
+    pub circuit_state: CircuitState,
 }
 
-/// A connection client that can manage either a single Redis instance or a Redis cluster.
-///
-/// This enum provides a unified interface for Redis operations, supporting both
-/// single-instance and cluster configurations.
+/// A connection pool to either a single Redis instance, a Redis cluster or a sentinel master.
 #[derive(Clone)]
-pub enum AsyncRedisClient {
+enum RedisPool {
     /// Contains a connection pool to a Redis cluster.
     Cluster(pool::CustomClusterPool),
     /// Contains a connection pool to a single Redis instance.
     Single(pool::CustomSinglePool),
+    /// Contains a connection pool to the master of a Redis Sentinel deployment.
+    Sentinel(pool::CustomSentinelPool),
+}
+
+/// A connection client that can manage either a single Redis instance or a Redis cluster.
+///
+/// This type provides a unified interface for Redis operations, supporting single-instance,
+/// cluster and sentinel configurations.
+///
+/// All clones of the client share a [`CircuitBreaker`]. While the breaker is open,
+/// [`get_connection`](Self::get_connection) fails immediately with [`RedisError::CircuitOpen`].
+#[derive(Clone)]
+pub struct AsyncRedisClient {
+    pool: RedisPool,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl AsyncRedisClient {
@@ -89,6 +134,8 @@ impl AsyncRedisClient {
     ///
     /// The client uses a custom cluster manager that implements a specific connection recycling
     /// strategy, ensuring optimal performance and reliability in cluster environments.
+    ///
+    /// A TLS server name applies to all nodes, including the nodes discovered from the cluster.
     pub fn cluster<'a>(
         servers: impl IntoIterator<Item = &'a str>,
         opts: &RedisConfigOptions,
@@ -98,14 +145,25 @@ impl AsyncRedisClient {
             .map(|s| s.to_owned())
             .collect::<Vec<_>>();
 
+        let mut tls_slot = None;
+        let mut builder = ClusterClientBuilder::new(servers);
+        if let Some(server_name) = ServerNameTls::from_config(opts.tls.as_ref())? {
+            // The cluster connects to its nodes through the registered TLS connector.
+            let config = opts.tls.as_ref().ok_or(RedisError::Configuration)?;
+            tls_slot = Some(tls::register_cluster_tls(config, server_name)?);
+        } else if let Some(certificates) = load_certificates(opts.tls.as_ref())? {
+            builder = builder.certs(certificates);
+        }
+        let client = builder.build().map_err(RedisError::Redis)?;
+
         // We use our custom cluster manager which performs recycling in a different way from the
         // default manager.
-        let manager = pool::CustomClusterManager::new(servers, false, opts.recycle_check_frequency)
-            .map_err(RedisError::Redis)?;
+        let manager =
+            pool::CustomClusterManager::new(client, tls_slot, opts.recycle_check_frequency);
 
         let pool = Self::build_pool(manager, opts)?;
 
-        Ok(AsyncRedisClient::Cluster(pool))
+        Ok(Self::new(RedisPool::Cluster(pool), opts))
     }
 
     /// Creates a new connection client for a single Redis instance.
@@ -116,31 +174,163 @@ impl AsyncRedisClient {
     /// The client uses a custom single manager that implements a specific connection recycling
     /// strategy, ensuring optimal performance and reliability in single-instance environments.
     pub fn single(server: &str, opts: &RedisConfigOptions) -> Result<Self, RedisError> {
+        let server_name = ServerNameTls::from_config(opts.tls.as_ref())?;
+        let certificates = match server_name {
+            Some(_) => None,
+            None => load_certificates(opts.tls.as_ref())?,
+        };
+
+        let client = match certificates {
+            Some(certificates) => redis::Client::build_with_tls(server, certificates),
+            None => redis::Client::open(server),
+        }
+        .map_err(RedisError::Redis)?;
+
         // We use our custom single manager which performs recycling in a different way from the
         // default manager.
-        let manager = pool::CustomSingleManager::new(server, opts.recycle_check_frequency)
+        let manager =
+            pool::CustomSingleManager::new(client, server_name, opts.recycle_check_frequency);
+
+        let pool = Self::build_pool(manager, opts)?;
+
+        Ok(Self::new(RedisPool::Single(pool), opts))
+    }
+
+    /// Creates a new connection client for a Redis master monitored by Redis Sentinel.
+    ///
+    /// This method initializes a connection client that discovers the address of the master with
+    /// the given name through the specified sentinels. The address is queried again for every new
+    /// connection, so the client follows the master when the sentinels fail over to a replica.
+    ///
+    /// Credentials and the database of the first sentinel url are used for both the sentinels and
+    /// the master. The master is connected with TLS if the sentinel urls use `rediss://`. A TLS
+    /// server name applies to both the sentinels and the master.
+    pub fn sentinel<'a>(
+        master_name: &str,
+        sentinels: impl IntoIterator<Item = &'a str>,
+        opts: &RedisConfigOptions,
+    ) -> Result<Self, RedisError> {
+        let sentinels = sentinels
+            .into_iter()
+            .map(|s| s.into_connection_info())
+            .collect::<Result<Vec<_>, _>>()
             .map_err(RedisError::Redis)?;
 
+        let info = sentinels
+            .first()
+            .map(|s| s.redis.clone())
+            .unwrap_or_default();
+
+        if let Some(server_name) = ServerNameTls::from_config(opts.tls.as_ref())? {
+            let sentinel_info = RedisConnectionInfo {
+                db: 0,
+                ..info.clone()
+            };
+            let sentinels = sentinels
+                .into_iter()
+                .map(|s| ConnectionInfo {
+                    addr: s.addr,
+                    redis: sentinel_info.clone(),
+                })
+                .collect();
+
+            let client =
+                ServerNameSentinel::new(server_name, sentinels, master_name.to_owned(), info);
+            let manager =
+                pool::CustomSentinelManager::with_server_name(client, opts.recycle_check_frequency);
+
+            let pool = Self::build_pool(manager, opts)?;
+
+            return Ok(Self::new(RedisPool::Sentinel(pool), opts));
+        }
+        let tls_mode = sentinels.first().and_then(|s| match s.addr {
+            ConnectionAddr::TcpTls { insecure, .. } => Some(match insecure {
+                true => TlsMode::Insecure,
+                false => TlsMode::Secure,
+            }),
+            _ => None,
+        });
+
+        let mut builder = SentinelClientBuilder::new(
+            sentinels.into_iter().map(|s| s.addr),
+            master_name.to_owned(),
+            SentinelServerType::Master,
+        )
+        .map_err(RedisError::Redis)?
+        .set_client_to_redis_db(info.db);
+
+        if let Some(username) = info.username {
+            builder = builder
+                .set_client_to_redis_username(username.clone())
+                .set_client_to_sentinel_username(username);
+        }
+        if let Some(password) = info.password {
+            builder = builder
+                .set_client_to_redis_password(password.clone())
+                .set_client_to_sentinel_password(password);
+        }
+        if let Some(tls_mode) = tls_mode {
+            builder = builder.set_client_to_redis_tls_mode(tls_mode);
+        }
+        if let Some(certificates) = load_certificates(opts.tls.as_ref())? {
+            builder = builder
+                .set_client_to_redis_certificates(certificates.clone())
+                .set_client_to_sentinel_certificates(certificates);
+        }
+
+        let client = builder.build().map_err(RedisError::Redis)?;
+
+        // We use our custom sentinel manager which performs recycling in a different way from the
+        // default manager.
+        let manager = pool::CustomSentinelManager::new(client, opts.recycle_check_frequency);
+
         let pool = Self::build_pool(manager, opts)?;
 
-        Ok(AsyncRedisClient::Single(pool))
+        Ok(Self::new(RedisPool::Sentinel(pool), opts))
+    }
+
+    fn new(pool: RedisPool, opts: &RedisConfigOptions) -> Self {
+        Self {
+            pool,
+            breaker: Arc::new(CircuitBreaker::new(opts.circuit_breaker.clone())),
+        }
     }
 
     /// Acquires a connection from the pool.
     ///
     /// Returns a new [`AsyncRedisConnection`] that can be used to execute Redis commands.
     /// The connection is automatically returned to the pool when dropped.
+    ///
+    /// Fails with [`RedisError::CircuitOpen`] without contacting Redis if the circuit breaker is
+    /// open.
     pub async fn get_connection(&self) -> Result<AsyncRedisConnection, RedisError> {
-        let connection = match self {
-            Self::Cluster(pool) => {
-                AsyncRedisConnection::Cluster(pool.get().await.map_err(RedisError::Pool)?)
-            }
-            Self::Single(pool) => {
-                AsyncRedisConnection::Single(pool.get().await.map_err(RedisError::Pool)?)
-            }
+        if !self.breaker.try_acquire() {
+            return Err(RedisError::CircuitOpen);
+        }
+
+        let result = match &self.pool {
+            RedisPool::Cluster(pool) => pool.get().await.map(RedisConnection::Cluster),
+            RedisPool::Single(pool) => pool.get().await.map(RedisConnection::Single),
+            RedisPool::Sentinel(pool) => pool.get().await.map(RedisConnection::Sentinel),
         };
 
-        Ok(connection)
+        match result {
+            Ok(connection) => Ok(AsyncRedisConnection {
+                connection,
+                breaker: Arc::clone(&self.breaker),
+            }),
+            Err(error) => {
+                if is_pool_failure(&error) {
+                    self.breaker.record(false);
+                }
+                Err(RedisError::Pool(error))
+            }
+        }
+    }
+
+    /// Returns the current state of the circuit breaker.
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.state()
     }
 
     /// Returns statistics about the current state of the connection pool.
@@ -148,9 +338,10 @@ impl AsyncRedisClient {
     /// Provides information about the number of active and idle connections in the pool,
     /// which can be useful for monitoring and debugging purposes.
     pub fn stats(&self) -> RedisClientStats {
-        let status = match self {
-            Self::Cluster(pool) => pool.status(),
-            Self::Single(pool) => pool.status(),
+        let status = match &self.pool {
+            RedisPool::Cluster(pool) => pool.status(),
+            RedisPool::Single(pool) => pool.status(),
+            RedisPool::Sentinel(pool) => pool.status(),
         };
 
         RedisClientStats {
@@ -158,6 +349,7 @@ impl AsyncRedisClient {
             connections: status.size as u32,
             max_connections: status.max_size as u32,
             waiting_for_connection: status.waiting as u32,
+            circuit_state: self.breaker.state(),
         }
     }
 
@@ -165,11 +357,14 @@ impl AsyncRedisClient {
     ///
     /// If the `predicate` returns `false` the object will be removed from pool.
     pub fn retain(&self, mut predicate: impl FnMut(Metrics) -> bool) {
-        match self {
-            Self::Cluster(pool) => {
+        match &self.pool {
+            RedisPool::Cluster(pool) => {
                 pool.retain(|_, metrics| predicate(metrics));
             }
-            Self::Single(pool) => {
+            RedisPool::Single(pool) => {
+                pool.retain(|_, metrics| predicate(metrics));
+            }
+            RedisPool::Sentinel(pool) => {
                 pool.retain(|_, metrics| predicate(metrics));
             }
         }
@@ -207,31 +402,43 @@ impl AsyncRedisClient {
 
 impl std::fmt::Debug for AsyncRedisClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AsyncRedisClient::Cluster(_) => write!(f, "AsyncRedisPool::Cluster"),
-            AsyncRedisClient::Single(_) => write!(f, "AsyncRedisPool::Single"),
+        match self.pool {
+            RedisPool::Cluster(_) => write!(f, "AsyncRedisPool::Cluster"),
+            RedisPool::Single(_) => write!(f, "AsyncRedisPool::Single"),
+            RedisPool::Sentinel(_) => write!(f, "AsyncRedisPool::Sentinel"),
         }
     }
 }
 
-/// A connection to either a single Redis instance or a Redis cluster.
-///
-/// This enum provides a unified interface for Redis operations, abstracting away the
-/// differences between single-instance and cluster connections. It implements the
-/// [`redis::aio::ConnectionLike`] trait, allowing it to be used with Redis commands
-/// regardless of the underlying connection type.
-pub enum AsyncRedisConnection {
+/// A connection to a single Redis instance, a Redis cluster or a master monitored by Sentinel.
+enum RedisConnection {
     /// A connection to a Redis cluster.
     Cluster(pool::CustomClusterConnection),
     /// A connection to a single Redis instance.
     Single(pool::CustomSingleConnection),
+    /// A connection to the master of a Redis Sentinel deployment.
+    Sentinel(pool::CustomSentinelConnection),
+}
+
+/// A connection to a single Redis instance, a Redis cluster or a master monitored by Sentinel.
+///
+/// This type provides a unified interface for Redis operations, abstracting away the
+/// differences between single-instance, cluster and sentinel connections. It implements the
+/// [`redis::aio::ConnectionLike`] trait, allowing it to be used with Redis commands
+/// regardless of the underlying connection type.
+///
+/// The outcome of every command is recorded in the circuit breaker of the client.
+pub struct AsyncRedisConnection {
+    connection: RedisConnection,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl std::fmt::Debug for AsyncRedisConnection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            Self::Cluster(_) => "Cluster",
-            Self::Single(_) => "Single",
+        let name = match self.connection {
+            RedisConnection::Cluster(_) => "Cluster",
+            RedisConnection::Single(_) => "Single",
+            RedisConnection::Sentinel(_) => "Sentinel",
         };
         f.debug_tuple(name).finish()
     }
@@ -239,10 +446,13 @@ impl std::fmt::Debug for AsyncRedisConnection {
 
 impl redis::aio::ConnectionLike for AsyncRedisConnection {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
-        match self {
-            Self::Cluster(conn) => conn.req_packed_command(cmd),
-            Self::Single(conn) => conn.req_packed_command(cmd),
-        }
+        let future = match &mut self.connection {
+            RedisConnection::Cluster(conn) => conn.req_packed_command(cmd),
+            RedisConnection::Single(conn) => conn.req_packed_command(cmd),
+            RedisConnection::Sentinel(conn) => conn.req_packed_command(cmd),
+        };
+
+        record(&self.breaker, future)
     }
 
     fn req_packed_commands<'a>(
@@ -251,16 +461,90 @@ impl redis::aio::ConnectionLike for AsyncRedisConnection {
         offset: usize,
         count: usize,
     ) -> RedisFuture<'a, Vec<Value>> {
-        match self {
-            Self::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
-            Self::Single(conn) => conn.req_packed_commands(cmd, offset, count),
-        }
+        let future = match &mut self.connection {
+            RedisConnection::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+            RedisConnection::Single(conn) => conn.req_packed_commands(cmd, offset, count),
+            RedisConnection::Sentinel(conn) => conn.req_packed_commands(cmd, offset, count),
+        };
+
+        record(&self.breaker, future)
     }
 
     fn get_db(&self) -> i64 {
-        match self {
-            Self::Cluster(conn) => conn.get_db(),
-            Self::Single(conn) => conn.get_db(),
+        match &self.connection {
+            RedisConnection::Cluster(conn) => conn.get_db(),
+            RedisConnection::Single(conn) => conn.get_db(),
+            RedisConnection::Sentinel(conn) => conn.get_db(),
         }
     }
 }
+
+/// Records the outcome of a command in the circuit breaker once it completes.
+fn record<'a, T: Send + 'a>(
+    breaker: &'a CircuitBreaker,
+    future: RedisFuture<'a, T>,
+) -> RedisFuture<'a, T> {
+    Box::pin(async move {
+        let result = future.await;
+        match &result {
+            Ok(_) => breaker.record(true),
+            Err(error) if is_failure(error) => breaker.record(false),
+            // Redis responded with an error, so it is reachable.
+            Err(_) => breaker.record(true),
+        }
+        result
+    })
+}
+
+/// Returns `true` if the error indicates that Redis could not be reached.
+fn is_failure(error: &redis::RedisError) -> bool {
+    error.is_io_error()
+        || error.is_timeout()
+        || error.is_connection_dropped()
+        || error.is_connection_refusal()
+        || error.is_unrecoverable_error()
+}
+
+/// Returns `true` if the pool failed to provide a connection because Redis could not be reached.
+fn is_pool_failure(error: &PoolError<redis::RedisError>) -> bool {
+    match error {
+        // Waiting for a connection times out when the pool is exhausted, not when Redis fails.
+        PoolError::Timeout(timeout) => !matches!(timeout, TimeoutType::Wait),
+        PoolError::Backend(error) => is_failure(error),
+        _ => false,
+    }
+}
+
+/// Loads the certificates referenced by the TLS configuration.
+///
+/// Returns `None` if there is no TLS configuration, in which case `rediss://` urls are verified
+/// with the root certificates of the operating system.
+fn load_certificates(tls: Option<&RedisTlsConfig>) -> Result<Option<TlsCertificates>, RedisError> {
+    let Some(tls) = tls else {
+        return Ok(None);
+    };
+
+    let client_tls = match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => Some(ClientTlsConfig {
+            client_cert: read_certificate(cert)?,
+            client_key: read_certificate(key)?,
+        }),
+        (None, None) => None,
+        // A client certificate cannot be used without its key and vice versa.
+        _ => return Err(RedisError::Configuration),
+    };
+
+    let root_cert = tls.ca_cert.as_deref().map(read_certificate).transpose()?;
+
+    Ok(Some(TlsCertificates {
+        client_tls,
+        root_cert,
+    }))
+}
+
+pub(crate) fn read_certificate(path: &Path) -> Result<Vec<u8>, RedisError> {
+    std::fs::read(path).map_err(|error| RedisError::Certificate {
+        path: path.display().to_string(),
+        error,
+    })
+}
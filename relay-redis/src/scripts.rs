@@ -6,9 +6,10 @@ pub struct RedisScripts;
 
 impl RedisScripts {
     /// Returns all [`Script`]s.
-    pub fn all() -> [&'static Script; 3] {
+    pub fn all() -> [&'static Script; 4] {
         [
             Self::load_cardinality(),
+            Self::load_cardinality_hll(),
             Self::load_global_quota(),
             Self::load_is_rate_limited(),
         ]
@@ -20,6 +21,12 @@ impl RedisScripts {
         SCRIPT.get_or_init(|| Script::new(include_str!("scripts/cardinality.lua")))
     }
 
+    /// Loads the HyperLogLog based cardinality Redis script.
+    pub fn load_cardinality_hll() -> &'static Script {
+        static SCRIPT: OnceLock<Script> = OnceLock::new();
+        SCRIPT.get_or_init(|| Script::new(include_str!("scripts/cardinality_hll.lua")))
+    }
+
     /// Loads the global quota Redis script.
     pub fn load_global_quota() -> &'static Script {
         static SCRIPT: OnceLock<Script> = OnceLock::new();
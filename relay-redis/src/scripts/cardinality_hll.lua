@@ -0,0 +1,176 @@
+-- Check the cardinality of a list of hashes using HyperLogLog sketches and return wether the item
+-- should be dropped.
+--
+-- This script has the same interface as `cardinality.lua`, but stores HyperLogLog sketches instead
+-- of sets. Cardinalities are approximate. Since a sketch cannot tell whether it has seen a hash,
+-- every sketch is passed with two additional keys tracking the hashes it contains:
+--  * members: an exact set of hashes, used while it holds only a few hashes
+--  * bloom: a Bloom filter sized for the max cardinality, replacing the set once it grows beyond
+--    `sparse_max` hashes
+--
+-- The Bloom filter has a false positive rate of about 1% once it holds as many hashes as the max
+-- cardinality, which is the fraction of new hashes falsely accepted after the limit has been reached.
+--
+--
+-- ``KEYS``: A list of cardinality sketches, each followed by its members and bloom key. The first
+-- sketch 'working set' is used to check cardinality, hashes will be updated in all passed keys.
+--
+-- ``ARGV``:
+--  * [number] Max cardinality.
+--  * [number] Sketch expiry.
+--  * [string...] List of hashes.
+--
+--  Returns a table, the first element in the table contains the new estimated cardinality and for
+--  every passed hash (in order) the table contains `0` if the hash was rejected and `1` if it was accepted.
+--
+--
+-- The script applies the following logic for every hash passed as an argument:
+--  * if the hash is contained in the members of the 'working set', the item is marked as accepted
+--  * if the cardinality has not been reached yet, the hash is added to all sketches and members
+--    and the item is marked as accepted
+--  * otherwise the item is marked as rejected
+--
+-- Afterwards if any sketch was modified the expiry of all sketches is bumped with the passed expiry.
+
+local ACCEPTED = true
+local REJECTED = false
+local HASHES_OFFSET = 2 -- arg1: max_cardinality, arg2: expiry
+local KEYS_PER_SKETCH = 3 -- sketch, members, bloom
+
+-- Number of bits per hash of max cardinality in the Bloom filter, results in a false positive rate
+-- of about 1% with `BLOOM_NUM_HASHES` bits per hash.
+local BLOOM_BITS_PER_HASH = 9.6
+local BLOOM_NUM_HASHES = 7
+-- Approximate number of bytes a hash takes in the members set.
+local SPARSE_BYTES_PER_HASH = 48
+
+local working_set = KEYS[1]
+local max_cardinality = tonumber(ARGV[1])
+local expiry = tonumber(ARGV[2])
+
+local bloom_bits = math.max(64, math.ceil(max_cardinality * BLOOM_BITS_PER_HASH))
+-- Switch to the Bloom filter once the set takes more memory than the filter.
+local sparse_max = math.ceil(bloom_bits / 8 / SPARSE_BYTES_PER_HASH)
+
+-- Returns the offsets of all bits set for a hash in the Bloom filter using double hashing.
+local function bloom_offsets(value)
+    local digest = redis.sha1hex(value)
+    local h1 = tonumber(string.sub(digest, 1, 8), 16)
+    local h2 = tonumber(string.sub(digest, 9, 16), 16)
+
+    local offsets = {}
+    for i = 0, BLOOM_NUM_HASHES - 1 do
+        table.insert(offsets, (h1 + i * h2) % bloom_bits)
+    end
+    return offsets
+end
+
+-- Returns the members of the sketch at the passed index in `KEYS`.
+local function members_of(sketch_index)
+    local bloom = KEYS[sketch_index + 2]
+    return {
+        set = KEYS[sketch_index + 1],
+        bloom = bloom,
+        dense = redis.call('EXISTS', bloom) == 1,
+    }
+end
+
+local function members_contain(members, value)
+    if not members.dense then
+        return redis.call('SISMEMBER', members.set, value) == 1
+    end
+
+    for _, offset in ipairs(bloom_offsets(value)) do
+        if redis.call('GETBIT', members.bloom, offset) == 0 then
+            return false
+        end
+    end
+    return true
+end
+
+local function bloom_add(members, value)
+    local modified = false
+    for _, offset in ipairs(bloom_offsets(value)) do
+        if redis.call('SETBIT', members.bloom, offset, 1) == 0 then
+            modified = true
+        end
+    end
+    return modified
+end
+
+-- Adds a hash to the members and returns whether the members were modified.
+local function members_add(members, value)
+    if members.dense then
+        return bloom_add(members, value)
+    end
+
+    if redis.call('SADD', members.set, value) == 0 then
+        return false
+    end
+
+    if redis.call('SCARD', members.set) > sparse_max then
+        for _, member in ipairs(redis.call('SMEMBERS', members.set)) do
+            bloom_add(members, member)
+        end
+        redis.call('DEL', members.set)
+        members.dense = true
+    end
+
+    return true
+end
+
+local results = {
+    0, -- total cardinality
+}
+
+local current_cardinality = redis.call('PFCOUNT', working_set)
+local working_members = members_of(1)
+local accepted = {}
+local any_modifications = false
+
+for arg_i = HASHES_OFFSET + 1, #ARGV do
+    local value = ARGV[arg_i]
+
+    if members_contain(working_members, value) then
+        table.insert(results, ACCEPTED)
+        table.insert(accepted, value)
+    elseif current_cardinality < max_cardinality then
+        members_add(working_members, value)
+        redis.call('PFADD', working_set, value)
+        -- Counting the added hashes avoids re-estimating the cardinality after every insertion.
+        current_cardinality = current_cardinality + 1
+        any_modifications = true
+
+        table.insert(results, ACCEPTED)
+        table.insert(accepted, value)
+    else
+        table.insert(results, REJECTED)
+    end
+end
+
+-- Make sure the accepted items are inserted into new granules.
+for i = 1 + KEYS_PER_SKETCH, #KEYS, KEYS_PER_SKETCH do
+    local members = members_of(i)
+    for _, value in ipairs(accepted) do
+        if members_add(members, value) then
+            any_modifications = true
+        end
+    end
+
+    for from = 1, #accepted, 7000 do
+        local to = math.min(from + 7000 - 1, #accepted)
+        if redis.call('PFADD', KEYS[i], unpack(accepted, from, to)) == 1 then
+            any_modifications = true
+        end
+    end
+end
+
+if any_modifications then
+    for _, key in ipairs(KEYS) do
+        redis.call('EXPIRE', key, expiry)
+    end
+end
+
+results[1] = redis.call('PFCOUNT', working_set)
+
+return results
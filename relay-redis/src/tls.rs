@@ -0,0 +1,602 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use futures::FutureExt;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+use crate::config::RedisTlsConfig;
+use crate::real::RedisError;
+use crate::redis;
+use crate::redis::aio::{ConnectionLike, MultiplexedConnection};
+use crate::redis::cluster::ClusterClient;
+use crate::redis::cluster_async::{ClusterConnection, Connect};
+use crate::redis::{
+    AsyncConnectionConfig, Cmd, ConnectionAddr, ConnectionInfo, ErrorKind, FromRedisValue,
+    IntoConnectionInfo, Pipeline, RedisConnectionInfo, RedisFuture, RedisResult, Value,
+};
+
+/// Maximum number of distinct TLS configurations with a server name used by cluster clients.
+const CLUSTER_TLS_SLOTS: usize = 4;
+
+/// TLS connectors of cluster clients, looked up by the slot of [`ClusterNode`].
+///
+/// The cluster connection creates connections to its nodes through [`Connect`], which receives
+/// nothing but the address of the node. The connector is therefore passed through the type.
+static CLUSTER_TLS: Mutex<Vec<(RedisTlsConfig, Arc<ServerNameTls>)>> = Mutex::new(Vec::new());
+
+/// Connects to Redis over TLS with a server name that differs from the host of the url.
+///
+/// The configured server name is sent as SNI and used to verify the server certificate for every
+/// connection, including connections to nodes discovered from a cluster or a sentinel.
+pub struct ServerNameTls {
+    connector: TlsConnector,
+    server_name: ServerName<'static>,
+}
+
+impl ServerNameTls {
+    /// Creates a connector if the TLS configuration overrides the server name.
+    pub fn from_config(tls: Option<&RedisTlsConfig>) -> Result<Option<Arc<Self>>, RedisError> {
+        let Some(tls) = tls else {
+            return Ok(None);
+        };
+        let Some(server_name) = &tls.server_name else {
+            return Ok(None);
+        };
+
+        let server_name =
+            ServerName::try_from(server_name.clone()).map_err(|_| RedisError::Configuration)?;
+
+        let mut roots = RootCertStore::empty();
+        match &tls.ca_cert {
+            Some(path) => {
+                for cert in read_certificates(path)? {
+                    roots.add(cert).map_err(|_| RedisError::Configuration)?;
+                }
+            }
+            None => {
+                roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+            }
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|_| RedisError::Configuration)?
+            .with_root_certificates(roots);
+
+        let config = match (&tls.client_cert, &tls.client_key) {
+            (Some(cert), Some(key)) => builder
+                .with_client_auth_cert(read_certificates(cert)?, read_private_key(key)?)
+                .map_err(|_| RedisError::Configuration)?,
+            (None, None) => builder.with_no_client_auth(),
+            // A client certificate cannot be used without its key and vice versa.
+            _ => return Err(RedisError::Configuration),
+        };
+
+        Ok(Some(Arc::new(Self {
+            connector: TlsConnector::from(Arc::new(config)),
+            server_name,
+        })))
+    }
+
+    /// Opens a multiplexed connection to the address of `info`.
+    ///
+    /// `rediss://` addresses are connected over TLS with the configured server name, `redis://`
+    /// addresses without TLS.
+    pub async fn connect(
+        &self,
+        info: &ConnectionInfo,
+        config: AsyncConnectionConfig,
+    ) -> RedisResult<MultiplexedConnection> {
+        match &info.addr {
+            ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure: false,
+                ..
+            } => {
+                let stream = TcpStream::connect((host.as_str(), *port)).await?;
+                let stream = self
+                    .connector
+                    .connect(self.server_name.clone(), stream)
+                    .await?;
+                multiplexed(&info.redis, stream, config).await
+            }
+            ConnectionAddr::Tcp(host, port) => {
+                let stream = TcpStream::connect((host.as_str(), *port)).await?;
+                multiplexed(&info.redis, stream, config).await
+            }
+            _ => Err(redis::RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "address not supported with a tls server name",
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Debug for ServerNameTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerNameTls")
+            .field("server_name", &self.server_name)
+            .finish()
+    }
+}
+
+/// Sets up a multiplexed connection on the stream and spawns its driver.
+async fn multiplexed<S>(
+    info: &RedisConnectionInfo,
+    stream: S,
+    config: AsyncConnectionConfig,
+) -> RedisResult<MultiplexedConnection>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (connection, driver) = MultiplexedConnection::new_with_config(info, stream, config).await?;
+    relay_system::spawn!(driver);
+    Ok(connection)
+}
+
+/// Discovers the master of a Redis Sentinel deployment and connects to it with a TLS server name.
+pub struct ServerNameSentinel {
+    tls: Arc<ServerNameTls>,
+    sentinels: Vec<ConnectionInfo>,
+    master_name: String,
+    master: RedisConnectionInfo,
+}
+
+impl ServerNameSentinel {
+    /// Creates a new sentinel client for the master with the given name.
+    ///
+    /// The master is connected with the credentials and database of `master` and with TLS if the
+    /// sentinels are.
+    pub fn new(
+        tls: Arc<ServerNameTls>,
+        sentinels: Vec<ConnectionInfo>,
+        master_name: String,
+        master: RedisConnectionInfo,
+    ) -> Self {
+        Self {
+            tls,
+            sentinels,
+            master_name,
+            master,
+        }
+    }
+
+    /// Connects to the current master, asking the sentinels in order until one succeeds.
+    pub async fn connect(&self) -> RedisResult<MultiplexedConnection> {
+        let mut last_error = None;
+
+        for sentinel in &self.sentinels {
+            match self.connect_master(sentinel).await {
+                Ok(connection) => return Ok(connection),
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            redis::RedisError::from((ErrorKind::InvalidClientConfig, "no sentinels configured"))
+        }))
+    }
+
+    async fn connect_master(
+        &self,
+        sentinel: &ConnectionInfo,
+    ) -> RedisResult<MultiplexedConnection> {
+        let mut connection = self.tls.connect(sentinel, Default::default()).await?;
+        let (host, port) = redis::cmd("SENTINEL")
+            .arg("GET-MASTER-ADDR-BY-NAME")
+            .arg(&self.master_name)
+            .query_async::<(String, u16)>(&mut connection)
+            .await?;
+
+        let addr = match &sentinel.addr {
+            ConnectionAddr::TcpTls {
+                insecure,
+                tls_params,
+                ..
+            } => ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure: *insecure,
+                tls_params: tls_params.clone(),
+            },
+            _ => ConnectionAddr::Tcp(host, port),
+        };
+        let info = ConnectionInfo {
+            addr,
+            redis: self.master.clone(),
+        };
+
+        // The sentinels may report a master that has been demoted in the meantime.
+        let mut master = self.tls.connect(&info, Default::default()).await?;
+        let role = redis::cmd("ROLE")
+            .query_async::<Vec<Value>>(&mut master)
+            .await?;
+        match role.first().map(String::from_redis_value) {
+            Some(Ok(role)) if role == "master" => Ok(master),
+            _ => Err(redis::RedisError::from((
+                ErrorKind::ResponseError,
+                "sentinel reported a node that is not a master",
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Debug for ServerNameSentinel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerNameSentinel")
+            .field("tls", &self.tls)
+            .field("master_name", &self.master_name)
+            .finish()
+    }
+}
+
+/// Registers the TLS connector of a cluster client and returns its slot.
+///
+/// Clients with equal TLS configurations share a slot.
+pub fn register_cluster_tls(
+    config: &RedisTlsConfig,
+    tls: Arc<ServerNameTls>,
+) -> Result<usize, RedisError> {
+    let mut slots = CLUSTER_TLS.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(slot) = slots.iter().position(|(c, _)| c == config) {
+        return Ok(slot);
+    }
+    if slots.len() >= CLUSTER_TLS_SLOTS {
+        return Err(RedisError::Configuration);
+    }
+
+    slots.push((config.clone(), tls));
+    Ok(slots.len() - 1)
+}
+
+/// Connects to a Redis cluster, using the TLS connector registered in `slot` for all nodes.
+pub async fn connect_cluster(
+    client: &ClusterClient,
+    slot: usize,
+) -> RedisResult<AnyClusterConnection> {
+    Ok(match slot {
+        0 => AnyClusterConnection::new(
+            client
+                .get_async_generic_connection::<ClusterNode<0>>()
+                .await?,
+        ),
+        1 => AnyClusterConnection::new(
+            client
+                .get_async_generic_connection::<ClusterNode<1>>()
+                .await?,
+        ),
+        2 => AnyClusterConnection::new(
+            client
+                .get_async_generic_connection::<ClusterNode<2>>()
+                .await?,
+        ),
+        3 => AnyClusterConnection::new(
+            client
+                .get_async_generic_connection::<ClusterNode<3>>()
+                .await?,
+        ),
+        _ => unreachable!("cluster tls slots are bounded by {CLUSTER_TLS_SLOTS}"),
+    })
+}
+
+/// A connection to a node of a Redis cluster, using the TLS connector registered in `SLOT`.
+#[derive(Clone)]
+struct ClusterNode<const SLOT: usize>(MultiplexedConnection);
+
+impl<const SLOT: usize> Connect for ClusterNode<SLOT> {
+    fn connect_with_config<'a, T>(info: T, config: AsyncConnectionConfig) -> RedisFuture<'a, Self>
+    where
+        T: IntoConnectionInfo + Send + 'a,
+    {
+        async move {
+            let info = info.into_connection_info()?;
+            let tls = CLUSTER_TLS
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(SLOT)
+                .map(|(_, tls)| Arc::clone(tls))
+                .ok_or_else(|| {
+                    redis::RedisError::from((
+                        ErrorKind::ClientError,
+                        "cluster tls connector not registered",
+                    ))
+                })?;
+
+            tls.connect(&info, config).await.map(Self)
+        }
+        .boxed()
+    }
+}
+
+impl<const SLOT: usize> ConnectionLike for ClusterNode<SLOT> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        self.0.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.get_db()
+    }
+}
+
+/// A connection to a Redis cluster, regardless of how it connects to the nodes.
+pub struct AnyClusterConnection(Box<dyn ConnectionLike + Send + Sync>);
+
+impl AnyClusterConnection {
+    fn new(connection: impl ConnectionLike + Send + Sync + 'static) -> Self {
+        Self(Box::new(connection))
+    }
+}
+
+impl From<ClusterConnection> for AnyClusterConnection {
+    fn from(connection: ClusterConnection) -> Self {
+        Self::new(connection)
+    }
+}
+
+impl ConnectionLike for AnyClusterConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        self.0.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.get_db()
+    }
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, RedisError> {
+    let pem = crate::real::read_certificate(path)?;
+    rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<_, _>>()
+        .map_err(|error| certificate_error(path, error))
+}
+
+fn read_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, RedisError> {
+    let pem = crate::real::read_certificate(path)?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .and_then(|key| {
+            key.ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "no private key")
+            })
+        })
+        .map_err(|error| certificate_error(path, error))
+}
+
+fn certificate_error(path: &Path, error: std::io::Error) -> RedisError {
+    RedisError::Certificate {
+        path: path.display().to_string(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+
+    use rustls::ServerConfig;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+
+    use crate::{AsyncRedisClient, RedisConfigOptions};
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    /// A Redis server that only answers the commands needed to connect and records the server
+    /// names sent by its clients.
+    ///
+    /// The server reports itself as `localhost` for cluster slots and sentinel masters, so that
+    /// discovered nodes are connected with a different host than the configured one.
+    struct FakeRedis {
+        addr: SocketAddr,
+        server_names: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeRedis {
+        async fn start() -> Self {
+            let certs = read_certificates(&fixture("server.pem")).unwrap();
+            let key = read_private_key(&fixture("server.key")).unwrap();
+            let config = ServerConfig::builder_with_provider(Arc::new(
+                rustls::crypto::ring::default_provider(),
+            ))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .unwrap();
+            let acceptor = TlsAcceptor::from(Arc::new(config));
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server_names = Arc::new(Mutex::new(Vec::new()));
+
+            let names = Arc::clone(&server_names);
+            relay_system::spawn!(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let acceptor = acceptor.clone();
+                    let names = Arc::clone(&names);
+                    relay_system::spawn!(async move {
+                        let Ok(stream) = acceptor.accept(stream).await else {
+                            return;
+                        };
+                        let name = stream.get_ref().1.server_name().unwrap_or_default();
+                        names.lock().unwrap().push(name.to_owned());
+                        serve(stream, addr.port()).await;
+                    });
+                }
+            });
+
+            Self { addr, server_names }
+        }
+
+        fn url(&self) -> String {
+            format!("rediss://{}", self.addr)
+        }
+
+        fn server_names(&self) -> Vec<String> {
+            self.server_names.lock().unwrap().clone()
+        }
+    }
+
+    async fn serve<S: AsyncRead + AsyncWrite + Unpin>(stream: S, port: u16) {
+        let mut stream = BufReader::new(stream);
+
+        while let Some(command) = read_command(&mut stream).await {
+            let name = command.first().map(|c| c.to_ascii_uppercase());
+            let reply = match (name.as_deref(), command.get(1)) {
+                (Some("PING"), Some(payload)) => format!("${}\r\n{payload}\r\n", payload.len()),
+                (Some("PING"), None) => "+PONG\r\n".to_owned(),
+                (Some("CLUSTER"), _) => {
+                    format!("*1\r\n*3\r\n:0\r\n:16383\r\n*2\r\n$9\r\nlocalhost\r\n:{port}\r\n")
+                }
+                (Some("SENTINEL"), _) => {
+                    let port = port.to_string();
+                    format!("*2\r\n$9\r\nlocalhost\r\n${}\r\n{port}\r\n", port.len())
+                }
+                (Some("ROLE"), _) => "*3\r\n$6\r\nmaster\r\n:0\r\n*0\r\n".to_owned(),
+                _ => "+OK\r\n".to_owned(),
+            };
+
+            if stream.write_all(reply.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn read_command<S: AsyncRead + Unpin>(stream: &mut BufReader<S>) -> Option<Vec<String>> {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.ok()?;
+        let count = line.strip_prefix('*')?.trim().parse().ok()?;
+
+        let mut command = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            stream.read_line(&mut line).await.ok()?;
+            let len: usize = line.strip_prefix('$')?.trim().parse().ok()?;
+
+            let mut arg = vec![0; len + 2];
+            stream.read_exact(&mut arg).await.ok()?;
+            arg.truncate(len);
+            command.push(String::from_utf8(arg).ok()?);
+        }
+
+        Some(command)
+    }
+
+    fn options(server_name: Option<&str>) -> RedisConfigOptions {
+        RedisConfigOptions {
+            max_connections: 1,
+            tls: Some(RedisTlsConfig {
+                ca_cert: Some(fixture("ca.pem")),
+                server_name: server_name.map(str::to_owned),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn assert_ping(client: &AsyncRedisClient) {
+        let mut connection = client.get_connection().await.unwrap();
+        let pong = redis::cmd("PING")
+            .arg("hello")
+            .query_async::<String>(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(pong, "hello");
+    }
+
+    #[tokio::test]
+    async fn test_server_name_single() {
+        let server = FakeRedis::start().await;
+        let opts = options(Some("redis.internal"));
+
+        let client = AsyncRedisClient::single(&server.url(), &opts).unwrap();
+        assert_ping(&client).await;
+
+        assert_eq!(server.server_names(), ["redis.internal"]);
+    }
+
+    #[tokio::test]
+    async fn test_server_name_cluster() {
+        let server = FakeRedis::start().await;
+        let opts = options(Some("redis.internal"));
+
+        let client = AsyncRedisClient::cluster([server.url().as_str()], &opts).unwrap();
+        assert_ping(&client).await;
+
+        // The initial node and the `localhost` node discovered from the slots.
+        let names = server.server_names();
+        assert!(names.len() >= 2, "{names:?}");
+        assert!(
+            names.iter().all(|name| name == "redis.internal"),
+            "{names:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_name_sentinel() {
+        let server = FakeRedis::start().await;
+        let opts = options(Some("redis.internal"));
+
+        let client =
+            AsyncRedisClient::sentinel("mymaster", [server.url().as_str()], &opts).unwrap();
+        assert_ping(&client).await;
+
+        // The sentinel and the `localhost` master reported by the sentinel.
+        assert_eq!(server.server_names(), ["redis.internal", "redis.internal"]);
+    }
+
+    #[tokio::test]
+    async fn test_server_name_required() {
+        let server = FakeRedis::start().await;
+        let opts = options(None);
+
+        // The server certificate is not valid for the ip address of the url.
+        let client = AsyncRedisClient::single(&server.url(), &opts).unwrap();
+        assert!(client.get_connection().await.is_err());
+    }
+
+    #[test]
+    fn test_server_name_invalid() {
+        let tls = RedisTlsConfig {
+            server_name: Some("not a name".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            ServerNameTls::from_config(Some(&tls)),
+            Err(RedisError::Configuration)
+        ));
+    }
+}
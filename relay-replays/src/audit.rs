@@ -0,0 +1,114 @@
+//! PII audits for all item types, including replay recordings.
+
+use std::fmt;
+
+use relay_pii::{PiiAuditReport, PiiConfig};
+
+use crate::recording::{ParseRecordingError, RecordingScrubber};
+
+/// Error returned from [`audit_item`].
+#[derive(Debug)]
+pub enum AuditError {
+    /// Auditing an item handled by [`relay_pii::audit_item`] failed.
+    Item(relay_pii::AuditError),
+    /// The replay recording could not be parsed.
+    Recording(ParseRecordingError),
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::Item(error) => write!(f, "{error}"),
+            AuditError::Recording(error) => write!(f, "invalid replay recording: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AuditError::Item(e) => e.source(),
+            AuditError::Recording(e) => Some(e),
+        }
+    }
+}
+
+impl From<relay_pii::AuditError> for AuditError {
+    fn from(err: relay_pii::AuditError) -> Self {
+        AuditError::Item(err)
+    }
+}
+
+impl From<ParseRecordingError> for AuditError {
+    fn from(err: ParseRecordingError) -> Self {
+        AuditError::Recording(err)
+    }
+}
+
+/// Runs a PII config over a serialized item and reports which rules matched.
+///
+/// This extends [`relay_pii::audit_item`] with `replay_recording` items, which cannot be audited
+/// by `relay_pii` itself since recording parsing lives in this crate. All other item types are
+/// passed through.
+pub fn audit_item(
+    config: &PiiConfig,
+    item_type: &str,
+    filename: &str,
+    item: &[u8],
+) -> Result<PiiAuditReport, AuditError> {
+    Ok(match item_type {
+        "replay_recording" => {
+            RecordingScrubber::new(usize::MAX, Some(config), None).audit_recording(item)?
+        }
+        item_type => relay_pii::audit_item(config, item_type, filename, item)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_pii::DataScrubbingConfig;
+
+    use super::*;
+
+    fn config() -> PiiConfig {
+        let mut scrubbing_config = DataScrubbingConfig::default();
+        scrubbing_config.scrub_data = true;
+        scrubbing_config.scrub_defaults = true;
+        scrubbing_config.pii_config_uncached().unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_audit_replay_recording() {
+        let payload = include_bytes!("../tests/fixtures/rrweb-performance-navigation.json");
+        let mut bytes = b"{}\n".to_vec();
+        bytes.extend_from_slice(payload);
+
+        let report = audit_item(&config(), "replay_recording", "", &bytes).unwrap();
+        assert_eq!(report.matches.len(), 1);
+        assert_eq!(report.matches[0].rule_id, "@creditcard:filter");
+    }
+
+    #[test]
+    fn test_audit_invalid_replay_recording() {
+        let result = audit_item(&config(), "replay_recording", "", b"");
+        assert!(matches!(result, Err(AuditError::Recording(_))));
+    }
+
+    #[test]
+    fn test_audit_event() {
+        let event = br#"{"extra": {"card": "4571234567890111"}}"#;
+        let report = audit_item(&config(), "event", "", event).unwrap();
+        assert_eq!(report.matches.len(), 1);
+    }
+
+    #[test]
+    fn test_audit_unsupported_item_type() {
+        let result = audit_item(&config(), "nope", "", b"{}");
+        assert!(matches!(
+            result,
+            Err(AuditError::Item(
+                relay_pii::AuditError::UnsupportedItemType(_)
+            ))
+        ));
+    }
+}
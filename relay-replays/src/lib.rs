@@ -13,4 +13,5 @@
 )]
 #![warn(missing_docs)]
 
+pub mod audit;
 pub mod recording;
@@ -22,7 +22,7 @@ use flate2::bufread::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use once_cell::sync::Lazy;
 use relay_event_schema::processor::{FieldAttrs, Pii, ProcessingState, Processor, ValueType};
-use relay_pii::{PiiConfig, PiiProcessor};
+use relay_pii::{PiiAudit, PiiAuditReport, PiiConfig, PiiProcessor};
 use relay_protocol::Meta;
 use serde::{Deserializer, de, ser};
 use serde_json::value::RawValue;
@@ -103,6 +103,8 @@ struct ScrubberTransform<'a> {
     /// The current path. This is redundant with `state`, which also contains the full path,
     /// but easier to match on.
     path: Vec<String>,
+    /// Collects matching rules if the scrubber runs in audit mode.
+    audit: Option<PiiAudit>,
 }
 
 impl ScrubberTransform<'_> {
@@ -116,6 +118,14 @@ impl ScrubberTransform<'_> {
     }
 }
 
+impl ScrubberTransform<'_> {
+    fn record_audit(&mut self, meta: &Meta) {
+        if let Some(ref mut audit) = self.audit {
+            audit.record_meta(&self.state.path().to_string(), meta);
+        }
+    }
+}
+
 impl<'de> Transform<'de> for &'_ mut ScrubberTransform<'_> {
     fn push_path(&mut self, key: &'de str) {
         self.path.push(key.to_owned());
@@ -145,24 +155,29 @@ impl<'de> Transform<'de> for &'_ mut ScrubberTransform<'_> {
     }
 
     fn transform_string(&mut self, mut value: String) -> Cow<'static, str> {
+        let mut meta = Meta::default();
+
         if let Some(ref mut processor) = self.processor1 {
             if processor
-                .process_string(&mut value, &mut Meta::default(), &self.state)
+                .process_string(&mut value, &mut meta, &self.state)
                 .is_err()
             {
+                self.record_audit(&meta);
                 return Cow::Borrowed("");
             }
         }
 
         if let Some(ref mut processor) = self.processor2 {
             if processor
-                .process_string(&mut value, &mut Meta::default(), &self.state)
+                .process_string(&mut value, &mut meta, &self.state)
                 .is_err()
             {
+                self.record_audit(&meta);
                 return Cow::Borrowed("");
             }
         }
 
+        self.record_audit(&meta);
         Cow::Owned(value)
     }
 }
@@ -315,6 +330,7 @@ impl<'a> RecordingScrubber<'a> {
                 processor2: config2.map(|c| PiiProcessor::new(c.compiled())),
                 state: ProcessingState::new_root(None, None),
                 path: vec![],
+                audit: None,
             })),
         }
     }
@@ -405,6 +421,18 @@ impl<'a> RecordingScrubber<'a> {
 
         Ok(output)
     }
+
+    /// Runs data scrubbers over a replay recording payload and reports which rules matched.
+    ///
+    /// The payload is processed in the same way as by [`process_recording`](Self::process_recording)
+    /// and is subject to the same errors. The scrubbed output is discarded.
+    pub fn audit_recording(&mut self, bytes: &[u8]) -> Result<PiiAuditReport, ParseRecordingError> {
+        self.transform.borrow_mut().audit = Some(PiiAudit::new());
+        let result = self.process_recording(bytes);
+        let audit = self.transform.borrow_mut().audit.take().unwrap_or_default();
+
+        result.map(|_| audit.into_report())
+    }
 }
 
 #[cfg(test)]
@@ -543,6 +571,23 @@ mod tests {
         assert!(parsed.contains("https://sentry.io?credit-card=[Filtered]"));
     }
 
+    #[test]
+    fn test_audit_recording() {
+        let payload = include_bytes!("../tests/fixtures/rrweb-performance-navigation.json");
+        let mut bytes = b"{}\n".to_vec();
+        bytes.extend_from_slice(payload);
+
+        let config = default_pii_config();
+        let report = scrubber(&config).audit_recording(&bytes).unwrap();
+
+        let rules = report
+            .matches
+            .iter()
+            .map(|m| (m.rule_id.as_str(), m.path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(rules, [("@creditcard:filter", "data.payload.description")]);
+    }
+
     #[test]
     fn test_scrub_pii_resource() {
         let payload = include_bytes!("../tests/fixtures/rrweb-performance-resource.json");
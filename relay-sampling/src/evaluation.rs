@@ -14,7 +14,7 @@ use rand_pcg::Pcg32;
 use relay_base_schema::organization::OrganizationId;
 use relay_protocol::Getter;
 #[cfg(feature = "redis")]
-use relay_redis::AsyncRedisClient;
+use relay_redis::{AsyncRedisClient, RedisError};
 use serde::Serialize;
 use uuid::Uuid;
 
@@ -122,6 +122,13 @@ impl ReservoirEvaluator<'_> {
             let key = ReservoirRuleKey::new(org_id, rule);
             let redis_count = match self.redis_incr(&key, client, _rule_expiry).await {
                 Ok(redis_count) => redis_count,
+                // Redis is unavailable, count the rule in this Relay only.
+                Err(e)
+                    if e.downcast_ref::<RedisError>()
+                        .is_some_and(RedisError::is_circuit_open) =>
+                {
+                    return self.incr_local(rule, limit);
+                }
                 Err(e) => {
                     relay_log::error!(error = &*e, "failed to increment reservoir rule");
                     return false;
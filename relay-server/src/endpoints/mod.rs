@@ -15,6 +15,7 @@ mod health_check;
 mod minidump;
 mod monitor;
 mod nel;
+mod outcomes;
 #[cfg(sentry)]
 mod playstation;
 mod project_configs;
@@ -42,6 +43,7 @@ pub fn routes(config: &Config) -> Router<ServiceState>{
         .route("/api/relay/healthcheck/{kind}/", get(health_check::handle))
         .route("/api/relay/events/{event_id}/", get(events::handle))
         .route("/api/relay/autoscaling/", get(autoscaling::handle))
+        .route("/api/relay/outcomes/", get(outcomes::handle))
         // Fallback route, but with a name, and just on `/api/relay/*`.
         .route("/api/relay/{*not_found}", any(statics::not_found));
 
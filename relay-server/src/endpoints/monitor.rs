@@ -46,6 +46,7 @@ async fn handle(
             monitor_slug: path.monitor_slug,
             status: query.status,
             environment: query.environment,
+            release: None,
             duration: query.duration,
             monitor_config: None,
             contexts: None,
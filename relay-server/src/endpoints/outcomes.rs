@@ -0,0 +1,121 @@
+//! Returns outcomes from the local outcome store.
+
+use axum::Json;
+use axum::extract::Query;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+use crate::endpoints::common::ServiceUnavailable;
+use crate::service::ServiceState;
+use crate::services::outcome_store::{QueryOutcomes, StoredOutcome};
+use crate::utils::ApiErrorResponse;
+
+#[derive(Debug, Serialize)]
+struct OutcomesResponse {
+    outcomes: Vec<StoredOutcome>,
+}
+
+/// Queries aggregated outcomes of this Relay.
+///
+/// The filters of [`QueryOutcomes`] are passed as query parameters, for example
+/// `/api/relay/outcomes/?start=2025-01-01T00:00:00Z&end=2025-01-02T00:00:00Z`.
+///
+/// Responds with `404 Not Found` if the outcome store is not enabled. Since the response contains
+/// outcomes of all projects, requests must pass the configured query token as bearer token in the
+/// `Authorization` header. Without a configured token, all requests are rejected.
+pub async fn handle(
+    state: ServiceState,
+    headers: HeaderMap,
+    Query(query): Query<QueryOutcomes>,
+) -> Result<Response, ServiceUnavailable> {
+    let Some(outcome_store) = state.outcome_store() else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let token = state.config().outcome_store().query_token.as_deref();
+    if !is_authorized(token, &headers) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(match outcome_store.send(query).await? {
+        Ok(outcomes) => Json(OutcomesResponse { outcomes }).into_response(),
+        Err(error) => {
+            relay_log::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to query outcomes"
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiErrorResponse::from_error(&error),
+            )
+                .into_response()
+        }
+    })
+}
+
+/// Returns `true` if the request carries the configured token as bearer token.
+fn is_authorized(token: Option<&str>, headers: &HeaderMap) -> bool {
+    let Some(token) = token else {
+        return false;
+    };
+
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    // Compare all bytes, so that the response time does not reveal a matching prefix.
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderValue, Uri};
+
+    use super::*;
+
+    fn headers(authorization: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static(authorization),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized(Some("secret"), &headers("Bearer secret")));
+        assert!(!is_authorized(Some("secret"), &headers("Bearer secre")));
+        assert!(!is_authorized(Some("secret"), &headers("Bearer other")));
+        assert!(!is_authorized(Some("secret"), &headers("secret")));
+        assert!(!is_authorized(Some("secret"), &HeaderMap::new()));
+        assert!(!is_authorized(None, &headers("Bearer secret")));
+    }
+
+    #[test]
+    fn test_query_parameters() {
+        let uri: Uri = "/api/relay/outcomes/?start=2025-01-01T00:00:00Z&end=2025-01-02T00:00:00Z&project_id=42&outcome=rate_limited"
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<QueryOutcomes>::try_from_uri(&uri).unwrap();
+
+        assert_eq!(
+            query.start.unwrap().to_rfc3339(),
+            "2025-01-01T00:00:00+00:00"
+        );
+        assert_eq!(query.end.unwrap().to_rfc3339(), "2025-01-02T00:00:00+00:00");
+        assert_eq!(query.project_id, Some(42));
+        assert_eq!(query.outcome.as_deref(), Some("rate_limited"));
+        assert_eq!(query.category, None);
+    }
+}
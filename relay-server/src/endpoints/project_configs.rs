@@ -190,10 +190,9 @@ async fn inner(
         // If public key is known (even if rate-limited, which is Some(false)), it has
         // access to the project config
         let has_access = relay.internal
-            || project_info
-                .config
-                .trusted_relays
-                .contains(&relay.public_key);
+            || relay
+                .public_keys()
+                .any(|key| project_info.config.trusted_relays.contains(key));
 
         if has_access {
             let full = relay.internal && inner.full_config;
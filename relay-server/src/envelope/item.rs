@@ -313,11 +313,17 @@ impl Item {
 
     /// Returns the associated platform.
     ///
-    /// Note: this is currently only used for [`ItemType::ProfileChunk`].
+    /// Note: this is currently only used for [`ItemType::ProfileChunk`] and for routing events
+    /// in Kafka.
     pub fn platform(&self) -> Option<&str> {
         self.headers.platform.as_deref()
     }
 
+    /// Sets the associated platform.
+    pub fn set_platform(&mut self, platform: String) {
+        self.headers.platform = Some(platform);
+    }
+
     /// Returns the associated profile type of a profile chunk.
     ///
     /// This primarily uses the profile type set via [`Self::set_profile_type`],
@@ -731,9 +737,10 @@ pub struct ItemHeaders {
 
     /// The platform this item was produced for.
     ///
-    /// Currently only used for [`ItemType::ProfileChunk`].
-    /// It contains the same platform as specified in the profile chunk payload,
-    /// hoisted into the header to be able to determine the correct data category.
+    /// Used for [`ItemType::ProfileChunk`] and for events.
+    /// It contains the same platform as specified in the payload, hoisted into the header to be
+    /// able to determine the correct data category of profile chunks, and to route events in
+    /// Kafka without parsing their payload.
     ///
     /// This is currently considered optional for profile chunks, but may change
     /// to required in the future.
@@ -168,6 +168,11 @@ impl<M> EnvelopeHeaders<M> {
     pub fn meta(&self) -> &M {
         &self.meta
     }
+
+    /// Returns the timestamp at which the envelope was sent, according to the SDK.
+    pub fn sent_at(&self) -> Option<DateTime<Utc>> {
+        self.sent_at
+    }
 }
 
 #[doc(hidden)]
@@ -90,7 +90,7 @@ impl FromRequest<ServiceState> for SignedBytes {
             .ok_or(SignatureError::UnknownRelay)?;
 
         let body = Bytes::from_request(request, state).await?;
-        if !relay.public_key.verify(&body, &signature) {
+        if !relay.verify(&body, &signature) {
             Err(SignatureError::BadSignature(UnpackError::BadSignature))
         } else {
             Ok(SignedBytes { body, relay })
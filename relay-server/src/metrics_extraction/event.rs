@@ -3,6 +3,7 @@ use std::collections::BTreeMap;
 use relay_base_schema::project::ProjectId;
 use relay_common::time::UnixTimestamp;
 use relay_dynamic_config::CombinedMetricExtractionConfig;
+use relay_event_normalization::SpanDescriptionNormalizationRule;
 use relay_event_schema::protocol::{Event, Span};
 use relay_metrics::{Bucket, BucketMetadata, BucketValue};
 use relay_quotas::DataCategory;
@@ -56,6 +57,7 @@ pub fn extract_metrics(
     sampling_decision: SamplingDecision,
     target_project_id: ProjectId,
     max_tag_value_size: usize,
+    span_description_rules: &[SpanDescriptionNormalizationRule],
     extract_spans: bool,
 ) -> ExtractedMetrics {
     let mut metrics = ExtractedMetrics {
@@ -70,6 +72,7 @@ pub fn extract_metrics(
             sampling_decision,
             target_project_id,
             max_tag_value_size,
+            span_description_rules,
             &mut metrics,
         );
     }
@@ -83,12 +86,15 @@ fn extract_span_metrics_for_event(
     sampling_decision: SamplingDecision,
     target_project_id: ProjectId,
     max_tag_value_size: usize,
+    span_description_rules: &[SpanDescriptionNormalizationRule],
     output: &mut ExtractedMetrics,
 ) {
     relay_statsd::metric!(timer(RelayTimers::EventProcessingSpanMetricsExtraction), {
         let mut span_count = 0;
 
-        if let Some(transaction_span) = extract_transaction_span(event, max_tag_value_size, &[]) {
+        if let Some(transaction_span) =
+            extract_transaction_span(event, max_tag_value_size, &[], span_description_rules)
+        {
             let metrics = generic::extract_metrics(&transaction_span, config);
             output.project_metrics.extend(metrics);
             span_count += 1;
@@ -1273,6 +1279,7 @@ mod tests {
             SamplingDecision::Keep,
             ProjectId::new(4711),
             200,
+            &[],
             true,
         )
     }
@@ -1491,6 +1498,7 @@ mod tests {
             SamplingDecision::Keep,
             ProjectId::new(4711),
             200,
+            &[],
             true,
         );
         insta::assert_debug_snapshot!((&event.value().unwrap().spans, metrics.project_metrics));
@@ -1549,6 +1557,7 @@ mod tests {
             SamplingDecision::Keep,
             ProjectId::new(4711),
             200,
+            &[],
             true,
         );
 
@@ -1588,6 +1597,7 @@ mod tests {
             SamplingDecision::Keep,
             ProjectId::new(4711),
             200,
+            &[],
             true,
         );
 
@@ -1891,6 +1901,7 @@ mod tests {
             SamplingDecision::Keep,
             ProjectId::new(4711),
             200,
+            &[],
             true,
         )
         .project_metrics;
@@ -2,7 +2,10 @@ use std::borrow::Cow;
 use std::collections::BTreeMap;
 
 use relay_common::time::UnixTimestamp;
-use relay_dynamic_config::{CombinedMetricExtractionConfig, TagMapping, TagSource, TagSpec};
+use relay_dynamic_config::{
+    CombinedMetricExtractionConfig, ErrorBoundary, GlobalConfig, ProjectConfig, TagMapping,
+    TagSource, TagSpec,
+};
 use relay_metrics::{Bucket, BucketMetadata, BucketValue, MetricResourceIdentifier, MetricType};
 use relay_protocol::{FiniteF64, Getter, Val};
 use relay_quotas::DataCategory;
@@ -16,6 +19,29 @@ pub trait Extractable: Getter {
     fn timestamp(&self) -> Option<UnixTimestamp>;
 }
 
+/// Returns the combined metric extraction config for a project.
+///
+/// Returns `None` if the project has no enabled metric extraction config or if the global config
+/// failed to parse, in which case no generic metrics should be extracted.
+pub fn combined_config<'a>(
+    global_config: &'a GlobalConfig,
+    project_config: &'a ProjectConfig,
+) -> Option<CombinedMetricExtractionConfig<'a>> {
+    let config = match &project_config.metric_extraction {
+        ErrorBoundary::Ok(config) if config.is_enabled() => config,
+        _ => return None,
+    };
+
+    let ErrorBoundary::Ok(global_metrics_config) = &global_config.metric_extraction else {
+        return None;
+    };
+
+    Some(CombinedMetricExtractionConfig::new(
+        global_metrics_config,
+        config,
+    ))
+}
+
 /// Extract metrics from any type that implements both [`Extractable`] and [`Getter`].
 ///
 /// The instance must have a valid timestamp; if the timestamp is missing or invalid, no metrics are
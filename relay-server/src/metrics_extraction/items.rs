@@ -0,0 +1,276 @@
+//! Generic metric extraction for standalone items, such as logs, check-ins and replays.
+
+use relay_common::time::UnixTimestamp;
+use relay_event_schema::protocol::{OurLog, Replay};
+#[cfg(feature = "processing")]
+use relay_monitors::CheckIn;
+#[cfg(feature = "processing")]
+use relay_protocol::{Getter, Val};
+use relay_quotas::DataCategory;
+
+use crate::metrics_extraction::generic::Extractable;
+
+impl Extractable for OurLog {
+    fn category(&self) -> DataCategory {
+        DataCategory::LogItem
+    }
+
+    fn timestamp(&self) -> Option<UnixTimestamp> {
+        self.timestamp
+            .value()
+            .and_then(|ts| UnixTimestamp::from_datetime(ts.0))
+    }
+}
+
+impl Extractable for Replay {
+    fn category(&self) -> DataCategory {
+        DataCategory::Replay
+    }
+
+    fn timestamp(&self) -> Option<UnixTimestamp> {
+        self.timestamp
+            .value()
+            .and_then(|ts| UnixTimestamp::from_datetime(ts.0))
+    }
+}
+
+/// A monitor check-in along with the time it was received.
+///
+/// Check-in payloads do not carry a timestamp, so metrics extracted from them are associated with
+/// the time Relay received the envelope.
+#[cfg(feature = "processing")]
+#[derive(Debug)]
+pub struct ReceivedCheckIn<'a> {
+    /// The normalized check-in payload.
+    pub check_in: &'a CheckIn,
+    /// The time at which the check-in was received.
+    pub received_at: UnixTimestamp,
+}
+
+#[cfg(feature = "processing")]
+impl Getter for ReceivedCheckIn<'_> {
+    fn get_value(&self, path: &str) -> Option<Val<'_>> {
+        self.check_in.get_value(path)
+    }
+}
+
+#[cfg(feature = "processing")]
+impl Extractable for ReceivedCheckIn<'_> {
+    fn category(&self) -> DataCategory {
+        DataCategory::Monitor
+    }
+
+    fn timestamp(&self) -> Option<UnixTimestamp> {
+        Some(self.received_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_dynamic_config::{CombinedMetricExtractionConfig, MetricExtractionConfig};
+    use relay_protocol::{Annotated, FromValue};
+    use serde_json::json;
+
+    use super::*;
+    use crate::metrics_extraction::generic::extract_metrics;
+
+    fn extraction_config() -> MetricExtractionConfig {
+        serde_json::from_value(json!({
+            "version": 1,
+            "metrics": [
+                {
+                    "category": "log_item",
+                    "mri": "c:custom/log_lines@none",
+                    "tags": [
+                        {"key": "severity", "field": "log.level"},
+                        {"key": "service", "field": "log.attributes.service.name"},
+                        {
+                            "key": "noisy",
+                            "condition": {"op": "eq", "name": "log.level", "value": "debug"},
+                            "value": "true"
+                        }
+                    ]
+                },
+                {
+                    "category": "log_item",
+                    "mri": "d:custom/queue_size@none",
+                    "field": "log.attributes.queue.size"
+                },
+                {
+                    "category": "monitor",
+                    "mri": "d:custom/check_in_duration@second",
+                    "field": "check_in.duration",
+                    "tags": [{"key": "status", "field": "check_in.status"}]
+                },
+                {
+                    "category": "replay",
+                    "mri": "s:custom/replay_users@none",
+                    "field": "event.user.id",
+                    "condition": {"op": "eq", "name": "event.environment", "value": "prod"}
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_extract_log_metrics() {
+        let log = Annotated::<OurLog>::from_json(
+            r#"{
+                "timestamp": 1597976302.0,
+                "trace_id": "5b8efff798038103d269b633813fc60c",
+                "level": "error",
+                "body": "queue is full",
+                "attributes": {
+                    "service.name": {"type": "string", "value": "worker"},
+                    "queue.size": {"type": "integer", "value": 42}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let config = extraction_config();
+        let metrics = extract_metrics(
+            log.value().unwrap(),
+            CombinedMetricExtractionConfig::from(&config),
+        );
+
+        insta::assert_debug_snapshot!(metrics, @r###"
+        [
+            Bucket {
+                timestamp: UnixTimestamp(1597976302),
+                width: 0,
+                name: MetricName(
+                    "c:custom/log_lines@none",
+                ),
+                value: Counter(
+                    1.0,
+                ),
+                tags: {
+                    "service": "worker",
+                    "severity": "error",
+                },
+                metadata: BucketMetadata {
+                    merges: 1,
+                    received_at: Some(
+                        UnixTimestamp(0),
+                    ),
+                    extracted_from_indexed: false,
+                },
+            },
+            Bucket {
+                timestamp: UnixTimestamp(1597976302),
+                width: 0,
+                name: MetricName(
+                    "d:custom/queue_size@none",
+                ),
+                value: Distribution(
+                    [
+                        42.0,
+                    ],
+                ),
+                tags: {},
+                metadata: BucketMetadata {
+                    merges: 1,
+                    received_at: Some(
+                        UnixTimestamp(0),
+                    ),
+                    extracted_from_indexed: false,
+                },
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_extract_check_in_metrics() {
+        let check_in: CheckIn = serde_json::from_value(json!({
+            "check_in_id": "a460c25ff2554577b920fcfacae4e5eb",
+            "monitor_slug": "my-monitor",
+            "status": "ok",
+            "duration": 21.5
+        }))
+        .unwrap();
+
+        let config = extraction_config();
+        let metrics = extract_metrics(
+            &ReceivedCheckIn {
+                check_in: &check_in,
+                received_at: UnixTimestamp::from_secs(1597976302),
+            },
+            CombinedMetricExtractionConfig::from(&config),
+        );
+
+        insta::assert_debug_snapshot!(metrics, @r###"
+        [
+            Bucket {
+                timestamp: UnixTimestamp(1597976302),
+                width: 0,
+                name: MetricName(
+                    "d:custom/check_in_duration@second",
+                ),
+                value: Distribution(
+                    [
+                        21.5,
+                    ],
+                ),
+                tags: {
+                    "status": "ok",
+                },
+                metadata: BucketMetadata {
+                    merges: 1,
+                    received_at: Some(
+                        UnixTimestamp(0),
+                    ),
+                    extracted_from_indexed: false,
+                },
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_extract_replay_metrics() {
+        let replay = Replay::from_value(
+            json!({
+                "type": "replay_event",
+                "timestamp": 1597976302.0,
+                "environment": "prod",
+                "user": {"id": "123"}
+            })
+            .into(),
+        );
+
+        let config = extraction_config();
+        let metrics = extract_metrics(
+            replay.value().unwrap(),
+            CombinedMetricExtractionConfig::from(&config),
+        );
+
+        insta::assert_debug_snapshot!(metrics, @r###"
+        [
+            Bucket {
+                timestamp: UnixTimestamp(1597976302),
+                width: 0,
+                name: MetricName(
+                    "s:custom/replay_users@none",
+                ),
+                value: Set(
+                    {
+                        1916298011,
+                    },
+                ),
+                tags: {},
+                metadata: BucketMetadata {
+                    merges: 1,
+                    received_at: Some(
+                        UnixTimestamp(0),
+                    ),
+                    extracted_from_indexed: false,
+                },
+            },
+        ]
+        "###);
+    }
+}
@@ -3,6 +3,8 @@ use relay_metrics::Bucket;
 
 pub mod event;
 pub mod generic;
+pub mod items;
+pub mod nel;
 pub mod sessions;
 pub mod transactions;
 
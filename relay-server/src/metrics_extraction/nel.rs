@@ -0,0 +1,264 @@
+//! Metrics extracted from Network Error Logging (NEL) reports.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use relay_common::time::UnixTimestamp;
+use relay_event_normalization::span::country_subregion::Subregion;
+use relay_event_schema::protocol::{Geo, NetworkReportRaw};
+use relay_metrics::{
+    Bucket, BucketMetadata, BucketValue, DistributionType, DurationUnit, MetricNamespace,
+    MetricResourceIdentifier, MetricUnit,
+};
+
+use crate::metrics_extraction::IntoMetric;
+
+/// Enumerates the metrics extracted from NEL reports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NelMetric {
+    /// The number of reports, including reports of successful requests.
+    ///
+    /// Error rates are computed from the ratio of reports with a type other than `ok`.
+    Reports { tags: NelTags },
+    /// A distribution of the time between the start of a request and its completion or failure.
+    ElapsedTime {
+        value: DistributionType,
+        tags: NelTags,
+    },
+}
+
+/// Tags that are set on all NEL metrics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NelTags {
+    /// The phase of the request in which the error occurred, or `application` on success.
+    pub phase: Option<String>,
+    /// The type of the network error, or `ok` on success.
+    pub ty: Option<String>,
+    /// The IP address of the server that handled the request.
+    pub server_ip: Option<String>,
+    /// The HTTP protocol and version of the request.
+    pub protocol: Option<String>,
+    /// The country of the client that sent the report.
+    pub country_code: Option<String>,
+    /// The subregion of the client that sent the report.
+    pub subregion: Option<String>,
+}
+
+impl NelTags {
+    fn new(report: &NetworkReportRaw, client_geo: Option<&Geo>) -> Self {
+        let body = report.body.value();
+        let country_code = client_geo.and_then(|geo| geo.country_code.value());
+
+        Self {
+            phase: body
+                .and_then(|body| body.phase.value())
+                .map(|phase| phase.as_str().to_owned()),
+            ty: body.and_then(|body| body.ty.value()).cloned(),
+            server_ip: body
+                .and_then(|body| body.server_ip.value())
+                .map(|ip| ip.to_string()),
+            protocol: body.and_then(|body| body.protocol.value()).cloned(),
+            country_code: country_code.cloned(),
+            subregion: country_code
+                .and_then(|code| Subregion::from_iso2(code))
+                .map(|subregion| (subregion as u8).to_string()),
+        }
+    }
+}
+
+impl From<NelTags> for BTreeMap<String, String> {
+    fn from(value: NelTags) -> Self {
+        let mut map = BTreeMap::new();
+
+        let tags = [
+            ("nel.phase", value.phase),
+            ("nel.type", value.ty),
+            ("server.address", value.server_ip),
+            ("network.protocol", value.protocol),
+            ("user.geo.country_code", value.country_code),
+            ("user.geo.subregion", value.subregion),
+        ];
+
+        for (key, value) in tags {
+            if let Some(value) = value {
+                map.insert(key.to_owned(), value);
+            }
+        }
+
+        map
+    }
+}
+
+impl IntoMetric for NelMetric {
+    fn into_metric(self, timestamp: UnixTimestamp) -> Bucket {
+        let name = self.to_string();
+
+        let (value, unit, tags) = match self {
+            Self::Reports { tags } => (BucketValue::counter(1.into()), MetricUnit::None, tags),
+            Self::ElapsedTime { value, tags } => (
+                BucketValue::distribution(value),
+                MetricUnit::Duration(DurationUnit::MilliSecond),
+                tags,
+            ),
+        };
+
+        let mri = MetricResourceIdentifier {
+            ty: value.ty(),
+            namespace: MetricNamespace::Spans,
+            name: name.into(),
+            unit,
+        };
+
+        // For extracted metrics we assume the `received_at` timestamp is equivalent to the time
+        // in which the metric is extracted.
+        let received_at = if cfg!(not(test)) {
+            UnixTimestamp::now()
+        } else {
+            UnixTimestamp::from_secs(0)
+        };
+
+        Bucket {
+            timestamp,
+            width: 0,
+            name: mri.to_string().into(),
+            value,
+            tags: tags.into(),
+            metadata: BucketMetadata::new(received_at),
+        }
+    }
+}
+
+impl Display for NelMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reports { .. } => write!(f, "nel.reports"),
+            Self::ElapsedTime { .. } => write!(f, "nel.elapsed_time"),
+        }
+    }
+}
+
+/// Extracts metrics from a NEL report.
+///
+/// `client_geo` is the location of the client that sent the report, if it could be determined.
+pub fn extract_metrics(
+    report: &NetworkReportRaw,
+    client_geo: Option<&Geo>,
+    timestamp: UnixTimestamp,
+) -> Vec<Bucket> {
+    let tags = NelTags::new(report, client_geo);
+    let mut metrics = vec![NelMetric::Reports { tags: tags.clone() }];
+
+    let elapsed_time = report
+        .body
+        .value()
+        .and_then(|body| body.elapsed_time.value())
+        .and_then(|&elapsed_time| DistributionType::new(elapsed_time as f64));
+
+    if let Some(value) = elapsed_time {
+        metrics.push(NelMetric::ElapsedTime { value, tags });
+    }
+
+    metrics
+        .into_iter()
+        .map(|metric| metric.into_metric(timestamp))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_protocol::Annotated;
+
+    use super::*;
+
+    #[test]
+    fn test_extract_metrics() {
+        let report = Annotated::<NetworkReportRaw>::from_json(
+            r#"{
+                "age": 31042,
+                "body": {
+                    "elapsed_time": 823,
+                    "method": "GET",
+                    "phase": "connection",
+                    "protocol": "http/1.1",
+                    "referrer": "https://example.com/nel/",
+                    "sampling_fraction": 1.0,
+                    "server_ip": "123.123.123.123",
+                    "status_code": 0,
+                    "type": "tcp.refused"
+                },
+                "type": "network-error",
+                "url": "https://example.com/index.html",
+                "user_agent": "Mozilla/5.0"
+            }"#,
+        )
+        .unwrap();
+
+        let geo = Geo {
+            country_code: Annotated::new("AT".to_owned()),
+            ..Default::default()
+        };
+
+        let metrics = extract_metrics(
+            report.value().unwrap(),
+            Some(&geo),
+            UnixTimestamp::from_secs(1000),
+        );
+
+        insta::assert_debug_snapshot!(metrics, @r###"
+        [
+            Bucket {
+                timestamp: UnixTimestamp(1000),
+                width: 0,
+                name: MetricName(
+                    "c:spans/nel.reports@none",
+                ),
+                value: Counter(
+                    1.0,
+                ),
+                tags: {
+                    "nel.phase": "connection",
+                    "nel.type": "tcp.refused",
+                    "network.protocol": "http/1.1",
+                    "server.address": "123.123.123.123",
+                    "user.geo.country_code": "AT",
+                    "user.geo.subregion": "155",
+                },
+                metadata: BucketMetadata {
+                    merges: 1,
+                    received_at: Some(
+                        UnixTimestamp(0),
+                    ),
+                    extracted_from_indexed: false,
+                },
+            },
+            Bucket {
+                timestamp: UnixTimestamp(1000),
+                width: 0,
+                name: MetricName(
+                    "d:spans/nel.elapsed_time@millisecond",
+                ),
+                value: Distribution(
+                    [
+                        823.0,
+                    ],
+                ),
+                tags: {
+                    "nel.phase": "connection",
+                    "nel.type": "tcp.refused",
+                    "network.protocol": "http/1.1",
+                    "server.address": "123.123.123.123",
+                    "user.geo.country_code": "AT",
+                    "user.geo.subregion": "155",
+                },
+                metadata: BucketMetadata {
+                    merges: 1,
+                    received_at: Some(
+                        UnixTimestamp(0),
+                    ),
+                    extracted_from_indexed: false,
+                },
+            },
+        ]
+        "###);
+    }
+}
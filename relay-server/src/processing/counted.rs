@@ -1,5 +1,3 @@
-use relay_event_schema::protocol::OurLog;
-use relay_protocol::Annotated;
 use relay_quotas::DataCategory;
 use smallvec::SmallVec;
 
@@ -76,18 +74,6 @@ impl Counted for Box<Envelope> {
     }
 }
 
-impl Counted for Annotated<OurLog> {
-    fn quantities(&self) -> Quantities {
-        smallvec::smallvec![
-            (DataCategory::LogItem, 1),
-            (
-                DataCategory::LogByte,
-                crate::processing::logs::DUMMY_LOG_SIZE
-            )
-        ]
-    }
-}
-
 impl<T> Counted for &T
 where
     T: Counted,
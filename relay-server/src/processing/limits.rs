@@ -1,42 +1,40 @@
-use relay_quotas::{ItemScoping, Quota, RateLimits};
+use relay_quotas::{ItemScoping, MemoryRateLimiter, Quota, RateLimits};
 
 use crate::processing::{Context, Counted, Managed, Rejected};
+use crate::services::projects::cache::{Project, ProjectCacheHandle};
 
 #[cfg(feature = "processing")]
-use crate::services::{
-    global_rate_limits::GlobalRateLimitsServiceHandle, projects::cache::ProjectCacheHandle,
-};
+use crate::services::global_rate_limits::GlobalRateLimitsServiceHandle;
 
 #[cfg(feature = "processing")]
 type Redis = relay_quotas::RedisRateLimiter<GlobalRateLimitsServiceHandle>;
 
 /// A quota based rate limiter for Relay's new processing pipeline.
 ///
-/// The rate limiter can enforce in memory/cached quotas as well as enforce quotas consistently
-/// with Redis.
+/// The rate limiter can enforce cached quotas as well as enforce quotas consistently with Redis
+/// or locally in memory.
 pub struct QuotaRateLimiter {
-    #[cfg(feature = "processing")]
     project_cache: ProjectCacheHandle,
     #[cfg(feature = "processing")]
     redis: Option<Redis>,
+    local: Option<MemoryRateLimiter>,
 }
 
 impl QuotaRateLimiter {
     /// Creates a new [`Self`].
-    #[cfg(feature = "processing")]
-    pub fn new(project_cache: ProjectCacheHandle, redis: Option<Redis>) -> Self {
+    pub fn new(
+        project_cache: ProjectCacheHandle,
+        #[cfg(feature = "processing")] redis: Option<Redis>,
+        local: Option<MemoryRateLimiter>,
+    ) -> Self {
         Self {
             project_cache,
+            #[cfg(feature = "processing")]
             redis,
+            local,
         }
     }
 
-    /// Creates a new [`Self`].
-    #[cfg(not(feature = "processing"))]
-    pub fn new() -> Self {
-        Self {}
-    }
-
     /// Enforces quotas for the passed item.
     pub async fn enforce_quotas<T>(
         &self,
@@ -57,6 +55,14 @@ impl QuotaRateLimiter {
             quotas,
         };
 
+        let local = self.local.as_ref().map(|local| LocalRateLimiter {
+            local,
+            quotas,
+            limits: RateLimits::new(),
+            project: self.project_cache.get(data.scoping().project_key),
+        });
+        let limiter = CombinedRateLimiter(limiter, local);
+
         #[cfg(feature = "processing")]
         let limiter = {
             let redis = self.redis.as_ref().map(|redis| redis::RedisRateLimiter {
@@ -65,7 +71,7 @@ impl QuotaRateLimiter {
                 limits: RateLimits::new(),
                 project: self.project_cache.get(data.scoping().project_key),
             });
-            redis::CombinedRateLimiter(limiter, redis)
+            CombinedRateLimiter(limiter, redis)
         };
 
         data.enforce(limiter, ctx).await
@@ -128,10 +134,53 @@ impl RateLimiter for CachedRateLimiter<'_> {
     }
 }
 
+/// A [`RateLimiter`] implementation which enforces quotas in the memory of this Relay.
+struct LocalRateLimiter<'a> {
+    local: &'a MemoryRateLimiter,
+    quotas: CombinedQuotas<'a>,
+    limits: RateLimits,
+    project: Project<'a>,
+}
+
+impl RateLimiter for LocalRateLimiter<'_> {
+    async fn try_consume(&mut self, scope: ItemScoping, quantity: usize) -> RateLimits {
+        let limits = self
+            .local
+            .is_rate_limited(self.quotas, scope, quantity, false);
+
+        self.limits.merge(limits.clone());
+        limits
+    }
+}
+
+impl Drop for LocalRateLimiter<'_> {
+    fn drop(&mut self) {
+        let limits = std::mem::take(&mut self.limits);
+        self.project.rate_limits().merge(limits);
+    }
+}
+
+/// A [`RateLimiter`] which only consults the second rate limiter if the first one does not
+/// return any rate limits.
+struct CombinedRateLimiter<T, S>(T, S);
+
+impl<T, S> RateLimiter for CombinedRateLimiter<T, S>
+where
+    T: RateLimiter,
+    S: RateLimiter,
+{
+    async fn try_consume(&mut self, scope: ItemScoping, quantity: usize) -> RateLimits {
+        let limits = self.0.try_consume(scope, quantity).await;
+        if !limits.is_empty() {
+            return limits;
+        }
+
+        self.1.try_consume(scope, quantity).await
+    }
+}
+
 #[cfg(feature = "processing")]
 mod redis {
-    use crate::services::projects::cache::Project;
-
     use super::*;
     use relay_quotas::GlobalLimiter;
 
@@ -173,23 +222,6 @@ mod redis {
             self.project.rate_limits().merge(limits);
         }
     }
-
-    pub struct CombinedRateLimiter<T, S>(pub T, pub S);
-
-    impl<T, S> RateLimiter for CombinedRateLimiter<T, S>
-    where
-        T: RateLimiter,
-        S: RateLimiter,
-    {
-        async fn try_consume(&mut self, scope: ItemScoping, quantity: usize) -> RateLimits {
-            let limits = self.0.try_consume(scope, quantity).await;
-            if !limits.is_empty() {
-                return limits;
-            }
-
-            self.1.try_consume(scope, quantity).await
-        }
-    }
 }
 
 /// Container for global and project level [`Quota`].
@@ -1,7 +1,12 @@
+use std::net::IpAddr;
+
 use relay_dynamic_config::Feature;
+use relay_event_schema::protocol::OurLog;
+use relay_protocol::Annotated;
 
-use crate::processing::Context;
-use crate::processing::logs::{Error, Result};
+use crate::envelope::ItemContainer;
+use crate::processing::logs::{Error, ExpandedLogs, Result, SerializedLogs, process};
+use crate::processing::{Context, Managed};
 use crate::utils::{PickResult, sample};
 
 pub fn feature_flag(ctx: Context<'_>) -> Result<()> {
@@ -19,3 +24,72 @@ pub fn sampled(ctx: Context<'_>) -> Result<()> {
         PickResult::Keep => Ok(()),
     }
 }
+
+/// Applies inbound filters to all expanded logs and removes matching logs.
+pub fn inbound_filters(logs: &mut Managed<ExpandedLogs>, ctx: Context<'_>) {
+    logs.modify(|logs, records| {
+        let client_ip = logs.headers.meta().client_addr();
+        logs.logs.retain(|log| {
+            records.or_default(inbound_filter(&log.log, client_ip, ctx).map(|_| true), log)
+        });
+    });
+}
+
+/// Applies inbound filters to logs in their serialized state and removes matching logs.
+///
+/// Containers without matching logs are kept unchanged and forwarded as they were received. Logs
+/// which cannot be parsed are kept as well, they are rejected by the processing Relay.
+pub fn inbound_filters_serialized(logs: &mut Managed<SerializedLogs>, ctx: Context<'_>) {
+    let received_at = logs.received_at();
+    logs.modify(|logs, records| {
+        let client_ip = logs.headers.meta().client_addr();
+
+        logs.otel_logs.retain(|item| {
+            let Ok(log) = process::expand_otel_log(item, received_at) else {
+                return true;
+            };
+            records.or_default(inbound_filter(&log, client_ip, ctx).map(|_| true), item)
+        });
+
+        for item in &mut logs.logs {
+            let Ok(container) = ItemContainer::<OurLog>::parse(item) else {
+                continue;
+            };
+
+            let container = container.into_items();
+            let count = container.len();
+            let mut expanded: Vec<_> = process::split_size(container, item.len()).collect();
+            expanded.retain(|log| {
+                records.or_default(inbound_filter(&log.log, client_ip, ctx).map(|_| true), log)
+            });
+
+            if expanded.len() < count {
+                let container: Vec<_> = expanded.into_iter().map(|log| log.log).collect();
+                // Writing fewer logs than were parsed from the same item cannot overflow.
+                let _ = ItemContainer::from(container)
+                    .write_to(item)
+                    .inspect_err(|err| relay_log::error!("failed to serialize logs: {err}"));
+            }
+        }
+
+        logs.logs.retain(|item| item.item_count() != Some(0));
+    });
+}
+
+fn inbound_filter(
+    log: &Annotated<OurLog>,
+    client_ip: Option<IpAddr>,
+    ctx: Context<'_>,
+) -> Result<()> {
+    let Some(log) = log.value() else {
+        return Ok(());
+    };
+
+    relay_filter::should_filter(
+        log,
+        client_ip,
+        &ctx.project_info.config.filter_settings,
+        ctx.global_config.filters(),
+    )
+    .map_err(Error::Filtered)
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use relay_event_schema::processor::ProcessingAction;
 use relay_event_schema::protocol::OurLog;
+use relay_filter::FilterStatKey;
 use relay_pii::PiiConfigError;
 use relay_protocol::Annotated;
 use relay_quotas::{DataCategory, RateLimits};
@@ -21,11 +22,6 @@ mod process;
 mod store;
 mod validate;
 
-/// Temporary byte count of a single log.
-///
-/// This will be addressed at a later stage: #4824.
-pub const DUMMY_LOG_SIZE: usize = 500;
-
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Debug, thiserror::Error)]
@@ -39,6 +35,9 @@ pub enum Error {
     /// Events filtered either due to a global sampling rule.
     #[error("logs dropped due to sampling")]
     FilterSampling,
+    /// The log matched an inbound filter.
+    #[error("log filtered")]
+    Filtered(FilterStatKey),
     /// The logs are rate limited.
     #[error("rate limited")]
     RateLimited(RateLimits),
@@ -61,6 +60,7 @@ impl OutcomeError for Error {
             Self::DuplicateContainer => Some(Outcome::Invalid(DiscardReason::DuplicateItem)),
             Self::FilterFeatureFlag => None,
             Self::FilterSampling => None,
+            Self::Filtered(key) => Some(Outcome::Filtered(key.clone())),
             Self::RateLimited(limits) => {
                 let reason_code = limits.longest().and_then(|limit| limit.reason_code.clone());
                 Some(Outcome::RateLimited(reason_code))
@@ -126,23 +126,35 @@ impl processing::Processor for LogsProcessor {
 
         filter::sampled(ctx).reject(&logs)?;
 
+        if !ctx.is_processing() {
+            filter::inbound_filters_serialized(&mut logs, ctx);
+            self.limiter.enforce_quotas(&mut logs, ctx).await?;
+            return Ok(Output::just(LogOutput::NotProcessed(logs)));
+        }
+
+        // Like events, logs are filtered before quotas are enforced and before they are
+        // scrubbed, so filtered logs do not consume quota and filters see the original values.
+        let mut logs = process::expand(logs, ctx);
+        filter::inbound_filters(&mut logs, ctx);
+
         self.limiter.enforce_quotas(&mut logs, ctx).await?;
 
-        if ctx.is_processing() {
-            let mut logs = process::expand(logs, ctx);
-            process::process(&mut logs, ctx);
+        process::process(&mut logs, ctx);
+        let metrics = process::extract_metrics(&logs, ctx);
 
-            Ok(Output::just(LogOutput::Processed(logs)))
-        } else {
-            Ok(Output::just(LogOutput::NotProcessed(logs)))
-        }
+        Ok(Output {
+            main: LogOutput::Processed(logs),
+            metrics,
+        })
     }
 }
 
 /// Output produced by [`LogsProcessor`].
 #[derive(Debug)]
 pub enum LogOutput {
+    /// Logs which have only been rate limited and are forwarded in their serialized state.
     NotProcessed(Managed<SerializedLogs>),
+    /// Logs which have been fully processed.
     Processed(Managed<ExpandedLogs>),
 }
 
@@ -283,23 +295,80 @@ pub struct ExpandedLogs {
     #[cfg(feature = "processing")]
     retention: Option<u16>,
     /// Expanded and parsed logs.
-    logs: Vec<Annotated<OurLog>>,
+    logs: Vec<ExpandedLog>,
+}
+
+impl ExpandedLogs {
+    /// Returns the sum of the serialized sizes of all contained logs.
+    fn bytes(&self) -> usize {
+        self.logs.iter().map(|log| log.size).sum()
+    }
 }
 
 impl Counted for ExpandedLogs {
     fn quantities(&self) -> Quantities {
         smallvec::smallvec![
             (DataCategory::LogItem, self.logs.len()),
-            (DataCategory::LogByte, DUMMY_LOG_SIZE * self.logs.len())
+            (DataCategory::LogByte, self.bytes())
+        ]
+    }
+}
+
+/// A single log parsed from its serialized state.
+#[derive(Debug)]
+pub struct ExpandedLog {
+    /// The parsed log.
+    log: Annotated<OurLog>,
+    /// Size of the log in its serialized state, as it was received.
+    ///
+    /// Logs sent in a container share the size of the container evenly.
+    size: usize,
+}
+
+impl Counted for ExpandedLog {
+    fn quantities(&self) -> Quantities {
+        smallvec::smallvec![
+            (DataCategory::LogItem, 1),
+            (DataCategory::LogByte, self.size)
         ]
     }
 }
 
+impl RateLimited for Managed<ExpandedLogs> {
+    type Error = Error;
+
+    async fn enforce<T>(
+        &mut self,
+        mut rate_limiter: T,
+        _ctx: Context<'_>,
+    ) -> Result<(), Rejected<Self::Error>>
+    where
+        T: RateLimiter,
+    {
+        let scoping = self.scoping();
+
+        let items = rate_limiter
+            .try_consume(scoping.item(DataCategory::LogItem), self.logs.len())
+            .await;
+        let bytes = rate_limiter
+            .try_consume(scoping.item(DataCategory::LogByte), self.bytes())
+            .await;
+
+        let limits = items.merge_with(bytes);
+        if !limits.is_empty() {
+            return Err(self.reject_err(Error::RateLimited(limits)));
+        }
+
+        Ok(())
+    }
+}
+
 impl ExpandedLogs {
     fn serialize(self) -> Result<SerializedLogs, ContainerWriteError> {
         let mut item = Item::new(ItemType::Log);
 
-        ItemContainer::from(self.logs)
+        let logs: Vec<_> = self.logs.into_iter().map(|log| log.log).collect();
+        ItemContainer::from(logs)
             .write_to(&mut item)
             .inspect_err(|err| relay_log::error!("failed to serialize logs: {err}"))?;
 
@@ -310,3 +379,274 @@ impl ExpandedLogs {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use relay_config::Config;
+    use relay_dynamic_config::{GlobalConfig, ProjectConfig};
+    use relay_filter::FilterStatKey;
+    use relay_quotas::MemoryRateLimiter;
+    use relay_system::Addr;
+
+    use super::*;
+    use crate::envelope::ContentType;
+    use crate::extractors::RequestMeta;
+    use crate::processing::Processor as _;
+    use crate::services::projects::cache::ProjectCacheHandle;
+    use crate::services::projects::project::ProjectInfo;
+
+    /// Byte count previously estimated for a single log, before logs were counted by size.
+    const DUMMY_LOG_SIZE: usize = 500;
+
+    fn log_envelope(releases: &[&str]) -> Box<Envelope> {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+        let mut envelope = Envelope::from_request(None, RequestMeta::new(dsn));
+
+        let items = releases
+            .iter()
+            .map(|release| {
+                serde_json::json!({
+                    "timestamp": 1544719860.0,
+                    "trace_id": "5b8efff798038103d269b633813fc60c",
+                    "level": "info",
+                    "body": "hello",
+                    "attributes": {
+                        "sentry.release": {"type": "string", "value": release}
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut item = Item::new(ItemType::Log);
+        item.set_payload_with_item_count(
+            ContentType::LogContainer,
+            serde_json::to_vec(&serde_json::json!({ "items": items })).unwrap(),
+            releases.len() as u32,
+        );
+        envelope.add_item(item);
+        envelope
+    }
+
+    fn processing_config(processing: bool) -> Config {
+        Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": processing,
+                "kafka_config": []
+            }
+        }))
+        .unwrap()
+    }
+
+    fn test_processor() -> LogsProcessor {
+        LogsProcessor::new(Arc::new(QuotaRateLimiter::new(
+            ProjectCacheHandle::for_test(),
+            #[cfg(feature = "processing")]
+            None,
+            Some(MemoryRateLimiter::new()),
+        )))
+    }
+
+    #[tokio::test]
+    async fn test_filtered_logs_do_not_consume_quota() {
+        let project_info = ProjectInfo {
+            config: serde_json::from_value::<ProjectConfig>(serde_json::json!({
+                "features": ["organizations:ourlogs-ingestion"],
+                "filterSettings": {"releases": {"releases": ["1.0"]}},
+                "quotas": [{
+                    "id": "local",
+                    "categories": ["log_item"],
+                    "scope": "organization",
+                    "limit": 1,
+                    "window": 60,
+                    "reasonCode": "local"
+                }]
+            }))
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let config = processing_config(true);
+        let global_config = GlobalConfig::default();
+        let rate_limits = RateLimits::new();
+        let ctx = Context {
+            config: &config,
+            global_config: &global_config,
+            project_info: &project_info,
+            rate_limits: &rate_limits,
+        };
+
+        let processor = test_processor();
+        let (outcome_aggregator, mut outcomes) = Addr::custom();
+        let (test_store, _) = Addr::custom();
+
+        let process = async |envelope: Box<Envelope>| {
+            let mut envelope =
+                ManagedEnvelope::new(envelope, outcome_aggregator.clone(), test_store.clone());
+            let logs = processor.prepare_envelope(&mut envelope).unwrap();
+            envelope.accept();
+            processor.process(logs, ctx).await
+        };
+
+        // The filtered log does not count towards the quota of one log.
+        let envelope = log_envelope(&["1.0", "2.0"]);
+        let size = envelope.items().next().unwrap().len();
+        let output = process(envelope).await.unwrap();
+        let LogOutput::Processed(logs) = output.main else {
+            panic!("logs must be processed");
+        };
+        assert_eq!(logs.logs.len(), 1);
+        let accepted_bytes = logs.bytes();
+        logs.accept(|_| ());
+
+        let outcome = outcomes.recv().await.unwrap();
+        assert_eq!(outcome.category, DataCategory::LogItem);
+        assert_eq!(
+            outcome.outcome,
+            Outcome::Filtered(FilterStatKey::ReleaseVersion)
+        );
+        // The filtered log is accounted with its share of the container size.
+        let outcome = outcomes.recv().await.unwrap();
+        assert_eq!(outcome.category, DataCategory::LogByte);
+        assert_eq!(outcome.quantity as usize + accepted_bytes, size);
+
+        // The accepted log consumed the quota.
+        assert!(process(log_envelope(&["2.0"])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_filter_logs_without_processing() {
+        let project_info = ProjectInfo {
+            config: serde_json::from_value::<ProjectConfig>(serde_json::json!({
+                "features": ["organizations:ourlogs-ingestion"],
+                "filterSettings": {"releases": {"releases": ["1.0"]}}
+            }))
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let config = processing_config(false);
+        let global_config = GlobalConfig::default();
+        let rate_limits = RateLimits::new();
+        let ctx = Context {
+            config: &config,
+            global_config: &global_config,
+            project_info: &project_info,
+            rate_limits: &rate_limits,
+        };
+
+        let processor = test_processor();
+        let (outcome_aggregator, mut outcomes) = Addr::custom();
+        let (test_store, _) = Addr::custom();
+
+        let process = async |envelope: Box<Envelope>| {
+            let mut envelope =
+                ManagedEnvelope::new(envelope, outcome_aggregator.clone(), test_store.clone());
+            let logs = processor.prepare_envelope(&mut envelope).unwrap();
+            envelope.accept();
+            processor.process(logs, ctx).await
+        };
+
+        // Containers without filtered logs are forwarded unchanged.
+        let envelope = log_envelope(&["2.0", "3.0"]);
+        let output = process(envelope.clone()).await.unwrap();
+        let LogOutput::NotProcessed(logs) = output.main else {
+            panic!("logs must not be processed");
+        };
+        assert_eq!(
+            logs.logs[0].payload(),
+            envelope.items().next().unwrap().payload()
+        );
+        logs.accept(|_| ());
+
+        // Filtered logs are removed from their container.
+        let output = process(log_envelope(&["1.0", "2.0"])).await.unwrap();
+        let LogOutput::NotProcessed(logs) = output.main else {
+            panic!("logs must not be processed");
+        };
+        assert_eq!(logs.count(), 1);
+        let container = ItemContainer::<OurLog>::parse(&logs.logs[0]).unwrap();
+        let release = container.into_items()[0]
+            .value()
+            .and_then(|log| log.attribute_str("sentry.release").map(str::to_owned));
+        assert_eq!(release.as_deref(), Some("2.0"));
+        logs.accept(|_| ());
+
+        let outcome = outcomes.recv().await.unwrap();
+        assert_eq!(outcome.category, DataCategory::LogItem);
+        assert_eq!(
+            outcome.outcome,
+            Outcome::Filtered(FilterStatKey::ReleaseVersion)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_byte_quota_uses_serialized_size() {
+        let envelope = log_envelope(&["1.0", "1.0", "1.0"]);
+        let size = envelope.items().next().unwrap().len();
+        // Three logs are well below the estimated size of three logs.
+        assert!(size < 3 * DUMMY_LOG_SIZE);
+
+        let project_info = ProjectInfo {
+            config: serde_json::from_value::<ProjectConfig>(serde_json::json!({
+                "features": ["organizations:ourlogs-ingestion"],
+                "quotas": [{
+                    "id": "local",
+                    "categories": ["log_byte"],
+                    "scope": "organization",
+                    "limit": size,
+                    "window": 60,
+                    "reasonCode": "local"
+                }]
+            }))
+            .unwrap(),
+            ..Default::default()
+        };
+
+        for processing in [false, true] {
+            let config = processing_config(processing);
+            let global_config = GlobalConfig::default();
+            let rate_limits = RateLimits::new();
+            let ctx = Context {
+                config: &config,
+                global_config: &global_config,
+                project_info: &project_info,
+                rate_limits: &rate_limits,
+            };
+
+            let processor = test_processor();
+            let (outcome_aggregator, _outcomes) = Addr::custom();
+            let (test_store, _) = Addr::custom();
+
+            let process = async |envelope: Box<Envelope>| {
+                let mut envelope =
+                    ManagedEnvelope::new(envelope, outcome_aggregator.clone(), test_store.clone());
+                let logs = processor.prepare_envelope(&mut envelope).unwrap();
+                envelope.accept();
+                processor.process(logs, ctx).await
+            };
+
+            let output = process(envelope.clone()).await.unwrap();
+            match output.main {
+                // Logs are forwarded unchanged by non-processing Relays.
+                LogOutput::NotProcessed(logs) if !processing => {
+                    assert_eq!(logs.bytes(), size);
+                    assert_eq!(
+                        logs.logs[0].payload(),
+                        envelope.items().next().unwrap().payload()
+                    );
+                    logs.accept(|_| ());
+                }
+                LogOutput::Processed(logs) if processing => {
+                    assert_eq!(logs.bytes(), size);
+                    logs.accept(|_| ());
+                }
+                _ => panic!("unexpected output for processing={processing}"),
+            }
+
+            // The logs consumed the entire byte quota.
+            assert!(process(log_envelope(&["1.0"])).await.is_err());
+        }
+    }
+}
@@ -1,18 +1,21 @@
 use chrono::{DateTime, Utc};
 use relay_event_normalization::{
-    ClientHints, FromUserAgentInfo as _, RawUserAgentInfo, SchemaProcessor,
+    ClientHints, ClockDriftItemType, ClockDriftProcessor, FromUserAgentInfo as _, RawUserAgentInfo,
+    SchemaProcessor,
 };
 use relay_event_schema::processor::{ProcessingState, process_value};
-use relay_event_schema::protocol::{AttributeType, BrowserContext, OurLog};
+use relay_event_schema::protocol::{AttributeType, Attributes, BrowserContext, OsContext, OurLog};
 use relay_ourlogs::OtelLog;
 use relay_pii::PiiProcessor;
 use relay_protocol::{Annotated, ErrorKind, Value};
 
 use crate::envelope::{ContainerItems, Item, ItemContainer};
 use crate::extractors::RequestMeta;
-use crate::processing::logs::{Error, ExpandedLogs, Result, SerializedLogs};
+use crate::metrics_extraction::generic;
+use crate::processing::logs::{Error, ExpandedLog, ExpandedLogs, Result, SerializedLogs};
 use crate::processing::{Context, Managed};
 use crate::services::outcome::DiscardReason;
+use crate::services::processor::ProcessingExtractedMetrics;
 
 pub fn expand(logs: Managed<SerializedLogs>, _ctx: Context<'_>) -> Managed<ExpandedLogs> {
     let received_at = logs.received_at();
@@ -20,14 +23,18 @@ pub fn expand(logs: Managed<SerializedLogs>, _ctx: Context<'_>) -> Managed<Expan
         let mut all_logs = Vec::with_capacity(logs.count());
 
         for logs in logs.logs {
+            let size = logs.len();
             let expanded = expand_log_container(&logs, received_at);
             let expanded = records.or_default(expanded, logs);
-            all_logs.extend(expanded);
+            all_logs.extend(split_size(expanded, size));
         }
 
         for otel_log in logs.otel_logs {
             match expand_otel_log(&otel_log, received_at) {
-                Ok(log) => all_logs.push(log),
+                Ok(log) => all_logs.push(ExpandedLog {
+                    log,
+                    size: otel_log.len(),
+                }),
                 Err(err) => {
                     records.reject_err(err, otel_log);
                     continue;
@@ -44,7 +51,20 @@ pub fn expand(logs: Managed<SerializedLogs>, _ctx: Context<'_>) -> Managed<Expan
     })
 }
 
-fn expand_otel_log(item: &Item, received_at: DateTime<Utc>) -> Result<Annotated<OurLog>> {
+/// Distributes the serialized size of a container evenly across all logs it contains.
+pub fn split_size(logs: ContainerItems<OurLog>, size: usize) -> impl Iterator<Item = ExpandedLog> {
+    let count = logs.len().max(1);
+    let remainder = size % count;
+
+    logs.into_iter()
+        .enumerate()
+        .map(move |(i, log)| ExpandedLog {
+            log,
+            size: size / count + usize::from(i < remainder),
+        })
+}
+
+pub fn expand_otel_log(item: &Item, received_at: DateTime<Utc>) -> Result<Annotated<OurLog>> {
     let log = serde_json::from_slice::<OtelLog>(&item.payload()).map_err(|err| {
         relay_log::debug!("failed to parse OTel Log: {err}");
         Error::Invalid(DiscardReason::InvalidJson)
@@ -74,14 +94,57 @@ fn expand_log_container(item: &Item, received_at: DateTime<Utc>) -> Result<Conta
 }
 
 pub fn process(logs: &mut Managed<ExpandedLogs>, ctx: Context<'_>) {
+    let received_at = logs.received_at();
     logs.modify(|logs, records| {
+        // Logs are only corrected for clock drift if the project's policy applies to them.
+        let mut clock_drift = ctx
+            .project_info
+            .config
+            .clock_drift
+            .as_ref()
+            .filter(|c| c.applies_to(ClockDriftItemType::Log))
+            .map(|config| {
+                ClockDriftProcessor::new(logs.headers.sent_at(), received_at)
+                    .policy(Some(config), ClockDriftItemType::Log)
+            });
+
         let meta = logs.headers.meta();
-        logs.logs
-            .retain_mut(|log| records.or_default(process_log(log, meta, ctx).map(|_| true), &*log));
+        logs.logs.retain_mut(|log| {
+            let result = process_log(&mut log.log, meta, clock_drift.as_mut(), ctx);
+            records.or_default(result.map(|_| true), &*log)
+        });
     });
 }
 
-fn process_log(log: &mut Annotated<OurLog>, meta: &RequestMeta, ctx: Context<'_>) -> Result<()> {
+/// Extracts metrics from logs as configured in the project's metric extraction config.
+pub fn extract_metrics(
+    logs: &Managed<ExpandedLogs>,
+    ctx: Context<'_>,
+) -> ProcessingExtractedMetrics {
+    let mut extracted_metrics = ProcessingExtractedMetrics::new();
+
+    let Some(config) = generic::combined_config(ctx.global_config, &ctx.project_info.config) else {
+        return extracted_metrics;
+    };
+
+    for log in logs.logs.iter().filter_map(|log| log.log.value()) {
+        let metrics = generic::extract_metrics(log, config);
+        extracted_metrics.extend_project_metrics(metrics, None);
+    }
+
+    extracted_metrics
+}
+
+fn process_log(
+    log: &mut Annotated<OurLog>,
+    meta: &RequestMeta,
+    clock_drift: Option<&mut ClockDriftProcessor>,
+    ctx: Context<'_>,
+) -> Result<()> {
+    if let Some(processor) = clock_drift {
+        correct_clock_drift(log, processor)?;
+    }
+
     scrub(log, ctx).inspect_err(|err| {
         relay_log::debug!("failed to scrub pii from log: {err}");
     })?;
@@ -93,6 +156,19 @@ fn process_log(log: &mut Annotated<OurLog>, meta: &RequestMeta, ctx: Context<'_>
     Ok(())
 }
 
+fn correct_clock_drift(
+    log: &mut Annotated<OurLog>,
+    processor: &mut ClockDriftProcessor,
+) -> Result<()> {
+    process_value(log, processor, ProcessingState::root())?;
+
+    if let Some(log) = log.value_mut() {
+        processor.annotate(&mut log.timestamp);
+    }
+
+    Ok(())
+}
+
 fn scrub(log: &mut Annotated<OurLog>, ctx: Context<'_>) -> Result<()> {
     let pii_config = ctx
         .project_info
@@ -127,22 +203,43 @@ fn normalize(log: &mut Annotated<OurLog>, meta: &RequestMeta) -> Result<()> {
     Ok(())
 }
 
-fn populate_ua_fields(log: &mut OurLog, user_agent: Option<&str>, client_hints: ClientHints<&str>) {
+fn populate_ua_fields(
+    log: &mut OurLog,
+    request_user_agent: Option<&str>,
+    mut client_hints: ClientHints<&str>,
+) {
     let attributes = log.attributes.get_or_insert_with(Default::default);
-    if let Some(context) = BrowserContext::from_hints_or_ua(&RawUserAgentInfo {
-        user_agent,
-        client_hints,
-    }) {
-        if !attributes.contains_key("sentry.browser.name") {
-            if let Some(name) = context.name.value() {
-                attributes.insert("sentry.browser.name".to_owned(), name.to_owned());
-            }
+
+    // A user agent in the log attributes takes precedence over the request's user agent and
+    // client hints.
+    let user_agent = match attributes.get_value("user_agent.original") {
+        Some(Value::String(user_agent)) => {
+            client_hints = ClientHints::default();
+            Some(user_agent.to_owned())
         }
+        _ => request_user_agent.map(str::to_owned),
+    };
 
-        if !attributes.contains_key("sentry.browser.version") {
-            if let Some(version) = context.version.value() {
-                attributes.insert("sentry.browser.version".to_owned(), version.to_owned());
-            }
+    let user_agent_info = RawUserAgentInfo {
+        user_agent: user_agent.as_deref(),
+        client_hints,
+    };
+
+    if let Some(context) = BrowserContext::from_hints_or_ua(&user_agent_info) {
+        insert_missing(attributes, "sentry.browser.name", context.name);
+        insert_missing(attributes, "sentry.browser.version", context.version);
+    }
+
+    if let Some(context) = OsContext::from_hints_or_ua(&user_agent_info) {
+        insert_missing(attributes, "sentry.os.name", context.name);
+        insert_missing(attributes, "sentry.os.version", context.version);
+    }
+}
+
+fn insert_missing(attributes: &mut Attributes, key: &str, value: Annotated<String>) {
+    if !attributes.contains_key(key) {
+        if let Some(value) = value.into_value() {
+            attributes.insert(key.to_owned(), value);
         }
     }
 }
@@ -195,7 +292,8 @@ fn process_attribute_types(ourlog: &mut OurLog) {
 
 #[cfg(test)]
 mod tests {
-    use relay_protocol::SerializableAnnotated;
+    use relay_event_normalization::ClockDriftConfig;
+    use relay_protocol::{SerializableAnnotated, get_value};
 
     use super::*;
 
@@ -389,5 +487,70 @@ mod tests {
             attributes.get_value("sentry.browser.version").unwrap(),
             &Value::String("131.0.0".to_owned()),
         );
+        assert_eq!(
+            attributes.get_value("sentry.os.name").unwrap(),
+            &Value::String("Mac OS X".to_owned()),
+        );
+    }
+
+    #[test]
+    fn test_populate_ua_fields_from_attributes() {
+        let mut log = Annotated::<OurLog>::from_json(
+            r#"{
+                "attributes": {
+                    "user_agent.original": {
+                        "type": "string",
+                        "value": "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        populate_ua_fields(
+            log.value_mut().as_mut().unwrap(),
+            Some(
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+            ),
+            ClientHints {
+                sec_ch_ua_platform: Some(r#""macOS""#),
+                ..Default::default()
+            },
+        );
+
+        let attributes = get_value!(log.attributes!);
+        assert_eq!(
+            attributes.get_value("sentry.browser.name").unwrap(),
+            &Value::String("Firefox".to_owned()),
+        );
+        assert_eq!(
+            attributes.get_value("sentry.os.name").unwrap(),
+            &Value::String("Linux".to_owned()),
+        );
+    }
+
+    #[test]
+    fn test_correct_clock_drift() {
+        let mut log = Annotated::<OurLog>::from_json(
+            r#"{
+                "timestamp": 946684800.0,
+                "trace_id": "5b8efff798038103d269b633813fc60c",
+                "level": "info",
+                "body": "Test log message"
+            }"#,
+        )
+        .unwrap();
+
+        let sent_at = DateTime::from_timestamp(946684800, 0).unwrap();
+        let received_at = DateTime::from_timestamp(946771200, 0).unwrap();
+        let config = ClockDriftConfig::default();
+        let mut processor = ClockDriftProcessor::new(Some(sent_at), received_at)
+            .policy(Some(&config), ClockDriftItemType::Log);
+
+        correct_clock_drift(&mut log, &mut processor).unwrap();
+
+        let timestamp = &log.value().unwrap().timestamp;
+        assert_eq!(timestamp.value().unwrap().into_inner(), received_at);
+        assert!(timestamp.meta().has_errors());
     }
 }
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use chrono::{DateTime, Utc};
 use prost_types::Timestamp;
-use relay_event_schema::protocol::{Attributes, OurLog};
+use relay_event_schema::protocol::Attributes;
 use relay_protocol::{Annotated, Value};
 use relay_quotas::Scoping;
 use sentry_protos::snuba::v1::{AnyValue, TraceItem, TraceItemType, any_value};
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::constants::DEFAULT_EVENT_RETENTION;
 use crate::processing::Counted;
-use crate::processing::logs::{Error, Result};
+use crate::processing::logs::{Error, ExpandedLog, Result};
 use crate::services::outcome::DiscardReason;
 use crate::services::store::StoreLog;
 
@@ -40,10 +40,10 @@ pub struct Context {
     pub retention: Option<u16>,
 }
 
-pub fn convert(log: Annotated<OurLog>, ctx: &Context) -> Result<StoreLog> {
+pub fn convert(log: ExpandedLog, ctx: &Context) -> Result<StoreLog> {
     let quantities = log.quantities();
 
-    let log = required!(log);
+    let log = required!(log.log);
     let timestamp = required!(log.timestamp);
     let attrs = log.attributes.0.unwrap_or_default();
 
@@ -15,6 +15,7 @@ use crate::services::health_check::{HealthCheck, HealthCheckService};
 use crate::services::metrics::RouterService;
 use crate::services::outcome::{OutcomeProducer, OutcomeProducerService, TrackOutcome};
 use crate::services::outcome_aggregator::OutcomeAggregator;
+use crate::services::outcome_store::{OutcomeStore, OutcomeStoreService};
 use crate::services::processor::{
     self, EnvelopeProcessor, EnvelopeProcessorService, EnvelopeProcessorServicePool,
 };
@@ -27,9 +28,7 @@ use crate::services::store::{StoreService, StoreServicePool};
 use crate::services::test_store::{TestStore, TestStoreService};
 use crate::services::upstream::{UpstreamRelay, UpstreamRelayService};
 use crate::utils::{MemoryChecker, MemoryStat, ThreadKind};
-#[cfg(feature = "processing")]
-use anyhow::Context;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
 use relay_cogs::Cogs;
@@ -42,7 +41,9 @@ use relay_redis::AsyncRedisClient;
 use relay_redis::redis::Script;
 #[cfg(feature = "processing")]
 use relay_redis::{RedisClients, RedisError, RedisScripts};
-use relay_system::{Addr, Service, ServiceSpawn, ServiceSpawnExt as _, channel};
+use relay_system::{
+    Addr, RestartPolicy, Service, ServiceSpawn, ServiceSpawnExt as _, Supervision, channel,
+};
 
 /// Indicates the type of failure of the server.
 #[derive(Debug, thiserror::Error)]
@@ -60,6 +61,10 @@ pub enum ServiceError {
     #[cfg(feature = "processing")]
     #[error("could not initialize redis client during startup")]
     Redis,
+
+    /// Initializing the outcome store failed.
+    #[error("could not initialize the outcome store")]
+    OutcomeStore,
 }
 
 #[derive(Clone, Debug)]
@@ -67,6 +72,7 @@ pub struct Registry {
     pub health_check: Addr<HealthCheck>,
     pub outcome_producer: Addr<OutcomeProducer>,
     pub outcome_aggregator: Addr<TrackOutcome>,
+    pub outcome_store: Option<Addr<OutcomeStore>>,
     pub processor: Addr<EnvelopeProcessor>,
     pub test_store: Addr<TestStore>,
     pub relay_cache: Addr<RelayCache>,
@@ -77,6 +83,23 @@ pub struct Registry {
     pub autoscaling: Addr<AutoscalingMetrics>,
 }
 
+/// Restart policy for auxiliary services, which Relay can continue to operate without.
+const AUXILIARY_RESTART_POLICY: RestartPolicy = RestartPolicy::Backoff {
+    initial: Duration::from_secs(1),
+    max: Duration::from_secs(60),
+    max_restarts: 10,
+};
+
+/// Restart policy for critical services, which Relay cannot operate without.
+///
+/// While a critical service restarts, the readiness health check reports Relay as unhealthy. Once
+/// the restarts are exhausted, the panic takes down the process.
+const CRITICAL_RESTART_POLICY: RestartPolicy = RestartPolicy::Backoff {
+    initial: Duration::from_millis(100),
+    max: Duration::from_secs(5),
+    max_restarts: 3,
+};
+
 /// Constructs a Tokio [`relay_system::Runtime`] configured for running [services](relay_system::Service).
 pub fn create_runtime(name: &'static str, threads: usize) -> relay_system::Runtime {
     relay_system::Runtime::builder(name)
@@ -156,7 +179,13 @@ impl ServiceState {
         services: &dyn ServiceSpawn,
         config: Arc<Config>,
     ) -> Result<Self> {
-        let upstream_relay = services.start(UpstreamRelayService::new(config.clone()));
+        let upstream_relay = services.start_supervised(
+            {
+                let config = config.clone();
+                move || UpstreamRelayService::new(config.clone())
+            },
+            Supervision::new(CRITICAL_RESTART_POLICY).critical(true),
+        );
         let test_store = services.start(TestStoreService::new(config.clone()));
 
         #[cfg(feature = "processing")]
@@ -185,10 +214,19 @@ impl ServiceState {
         // Create an address for the `EnvelopeProcessor`, which can be injected into the
         // other services.
         let (processor, processor_rx) = channel(EnvelopeProcessorService::name());
+        let outcome_store = if config.outcome_store().enabled {
+            let outcome_store = OutcomeStoreService::create(&config)
+                .await
+                .context(ServiceError::OutcomeStore)?;
+            Some(services.start(outcome_store))
+        } else {
+            None
+        };
         let outcome_producer = services.start(OutcomeProducerService::create(
             config.clone(),
             upstream_relay.clone(),
             processor.clone(),
+            outcome_store.clone(),
         )?);
         let outcome_aggregator =
             services.start(OutcomeAggregator::new(&config, outcome_producer.clone()));
@@ -243,12 +281,23 @@ impl ServiceState {
                     outcome_aggregator.clone(),
                     metric_outcomes.clone(),
                 )
-                .map(|s| services.start(s))
+                .map(|store| {
+                    services.start_supervised(
+                        move || store.clone(),
+                        Supervision::new(CRITICAL_RESTART_POLICY).critical(true),
+                    )
+                })
             })
             .transpose()?;
 
-        let cogs = CogsService::new(&config);
-        let cogs = Cogs::new(CogsServiceRecorder::new(&config, services.start(cogs)));
+        let cogs = services.start_supervised(
+            {
+                let config = config.clone();
+                move || CogsService::new(&config)
+            },
+            Supervision::new(AUXILIARY_RESTART_POLICY),
+        );
+        let cogs = Cogs::new(CogsServiceRecorder::new(&config, cogs));
 
         #[cfg(feature = "processing")]
         let global_rate_limits = redis_clients
@@ -256,27 +305,29 @@ impl ServiceState {
             .map(|p| services.start(GlobalRateLimitsService::new(p.quotas.clone())));
 
         let processor_pool = create_processor_pool(&config)?;
-        services.start_with(
-            EnvelopeProcessorService::new(
-                processor_pool.clone(),
-                config.clone(),
-                global_config_handle,
-                project_cache_handle.clone(),
-                cogs,
+        let processor_service = EnvelopeProcessorService::new(
+            processor_pool.clone(),
+            config.clone(),
+            global_config_handle,
+            project_cache_handle.clone(),
+            cogs,
+            #[cfg(feature = "processing")]
+            redis_clients.clone(),
+            processor::Addrs {
+                outcome_aggregator: outcome_aggregator.clone(),
+                upstream_relay: upstream_relay.clone(),
+                test_store: test_store.clone(),
                 #[cfg(feature = "processing")]
-                redis_clients.clone(),
-                processor::Addrs {
-                    outcome_aggregator: outcome_aggregator.clone(),
-                    upstream_relay: upstream_relay.clone(),
-                    test_store: test_store.clone(),
-                    #[cfg(feature = "processing")]
-                    store_forwarder: store.clone(),
-                    aggregator: aggregator.clone(),
-                    #[cfg(feature = "processing")]
-                    global_rate_limits,
-                },
-                metric_outcomes.clone(),
-            ),
+                store_forwarder: store.clone(),
+                aggregator: aggregator.clone(),
+                #[cfg(feature = "processing")]
+                global_rate_limits,
+            },
+            metric_outcomes.clone(),
+        );
+        services.start_supervised_with(
+            move || processor_service.clone(),
+            Supervision::new(CRITICAL_RESTART_POLICY).critical(true),
             processor_rx,
         );
 
@@ -292,20 +343,50 @@ impl ServiceState {
             services,
         );
 
-        let health_check = services.start(HealthCheckService::new(
-            config.clone(),
-            MemoryChecker::new(memory_stat.clone(), config.clone()),
-            aggregator_handle,
-            upstream_relay.clone(),
-            envelope_buffer.clone(),
-        ));
+        // Without the health check, the load balancer stops routing traffic to this Relay.
+        // Restart it, but take the process down if it keeps failing.
+        let health_check = services.start_supervised(
+            {
+                let config = config.clone();
+                let memory_stat = memory_stat.clone();
+                let handle = handle.clone();
+                let upstream_relay = upstream_relay.clone();
+                let envelope_buffer = envelope_buffer.clone();
+                #[cfg(feature = "processing")]
+                let redis_clients = redis_clients.clone();
+                move || {
+                    HealthCheckService::new(
+                        config.clone(),
+                        MemoryChecker::new(memory_stat.clone(), config.clone()),
+                        aggregator_handle.clone(),
+                        upstream_relay.clone(),
+                        envelope_buffer.clone(),
+                        handle.clone(),
+                        #[cfg(feature = "processing")]
+                        redis_clients.clone(),
+                    )
+                }
+            },
+            Supervision::new(AUXILIARY_RESTART_POLICY).critical(true),
+        );
 
-        let autoscaling = services.start(AutoscalingMetricService::new(
-            memory_stat.clone(),
-            envelope_buffer.clone(),
-            handle.clone(),
-            processor_pool.clone(),
-        ));
+        let autoscaling = services.start_supervised(
+            {
+                let memory_stat = memory_stat.clone();
+                let envelope_buffer = envelope_buffer.clone();
+                let handle = handle.clone();
+                let processor_pool = processor_pool.clone();
+                move || {
+                    AutoscalingMetricService::new(
+                        memory_stat.clone(),
+                        envelope_buffer.clone(),
+                        handle.clone(),
+                        processor_pool.clone(),
+                    )
+                }
+            },
+            Supervision::new(AUXILIARY_RESTART_POLICY),
+        );
 
         services.start(RelayStats::new(
             config.clone(),
@@ -328,6 +409,7 @@ impl ServiceState {
             health_check,
             outcome_producer,
             outcome_aggregator,
+            outcome_store,
             test_store,
             relay_cache,
             global_config,
@@ -413,6 +495,11 @@ impl ServiceState {
     pub fn outcome_aggregator(&self) -> &Addr<TrackOutcome> {
         &self.inner.registry.outcome_aggregator
     }
+
+    /// Returns the address of the [`OutcomeStore`] service, if the store is enabled.
+    pub fn outcome_store(&self) -> Option<&Addr<OutcomeStore>> {
+        self.inner.registry.outcome_store.as_ref()
+    }
 }
 
 /// Creates Redis clients from the given `configs`.
@@ -460,6 +547,15 @@ fn create_async_redis_client(config: &RedisConfigRef<'_>) -> Result<AsyncRedisCl
             options,
         } => AsyncRedisClient::cluster(cluster_nodes.iter().map(|s| s.as_str()), options),
         RedisConfigRef::Single { server, options } => AsyncRedisClient::single(server, options),
+        RedisConfigRef::Sentinel {
+            master_name,
+            sentinel_nodes,
+            options,
+        } => AsyncRedisClient::sentinel(
+            master_name,
+            sentinel_nodes.iter().map(|s| s.as_str()),
+            options,
+        ),
     }
 }
 
@@ -480,7 +576,7 @@ async fn initialize_redis_scripts_for_client(
 #[cfg(feature = "processing")]
 async fn initialize_redis_scripts(
     client: &AsyncRedisClient,
-    scripts: &[&Script; 3],
+    scripts: &[&Script; 4],
 ) -> Result<(), RedisError> {
     let mut connection = client.get_connection().await?;
 
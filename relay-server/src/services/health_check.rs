@@ -1,7 +1,13 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use relay_config::Config;
-use relay_system::{Addr, AsyncResponse, Controller, FromMessage, Interface, Sender, Service};
+#[cfg(feature = "processing")]
+use relay_redis::{CircuitState, RedisClients};
+use relay_system::{
+    Addr, AsyncResponse, Controller, FromMessage, Handle, Interface, Sender, Service, ServiceId,
+    SupervisionStatus,
+};
 use std::future::Future;
 use tokio::sync::watch;
 use tokio::time::{Instant, timeout};
@@ -9,7 +15,7 @@ use tokio::time::{Instant, timeout};
 use crate::services::buffer::PartitionedEnvelopeBuffer;
 use crate::services::metrics::RouterHandle;
 use crate::services::upstream::{IsAuthenticated, UpstreamRelay};
-use crate::statsd::RelayTimers;
+use crate::statsd::{RelayGauges, RelayTimers};
 use crate::utils::{MemoryCheck, MemoryChecker};
 
 /// Checks whether Relay is alive and healthy based on its variant.
@@ -79,6 +85,64 @@ impl StatusUpdate {
     }
 }
 
+/// Checks the supervision state of all services.
+///
+/// Relay is unhealthy while a critical service is not running. Failed non-critical services are
+/// reported, but do not affect the health of Relay.
+///
+/// Changes of the state are logged once, using the last seen states in `states`. The current state
+/// of every service is reported with [`RelayGauges::ServiceSupervisionStatus`].
+fn services_status(handle: &Handle, states: &mut BTreeMap<ServiceId, SupervisionStatus>) -> Status {
+    let mut status = Status::Healthy;
+
+    for (service, metrics) in handle.current_services_metrics().iter() {
+        let Some(supervision) = metrics.supervision else {
+            continue;
+        };
+
+        relay_statsd::metric!(
+            gauge(RelayGauges::ServiceSupervisionStatus) = match supervision.status {
+                SupervisionStatus::Running => 0,
+                SupervisionStatus::Restarting => 1,
+                SupervisionStatus::Failed => 2,
+            },
+            service = service.name(),
+            critical = if supervision.critical {
+                "true"
+            } else {
+                "false"
+            },
+        );
+
+        let previous = states
+            .insert(service, supervision.status)
+            .unwrap_or(SupervisionStatus::Running);
+        let changed = previous != supervision.status;
+
+        if supervision.status == SupervisionStatus::Running {
+            if changed {
+                relay_log::info!("Service {} is running again", service.name());
+            }
+            continue;
+        }
+
+        if supervision.critical {
+            if changed {
+                relay_log::error!(
+                    "Critical service {} is {:?}",
+                    service.name(),
+                    supervision.status,
+                );
+            }
+            status = Status::Unhealthy;
+        } else if changed && supervision.status == SupervisionStatus::Failed {
+            relay_log::warn!("Service {} has failed", service.name());
+        }
+    }
+
+    status
+}
+
 /// Service implementing the [`HealthCheck`] interface.
 #[derive(Debug)]
 pub struct HealthCheckService {
@@ -87,6 +151,10 @@ pub struct HealthCheckService {
     aggregator: RouterHandle,
     upstream_relay: Addr<UpstreamRelay>,
     envelope_buffer: PartitionedEnvelopeBuffer,
+    handle: Handle,
+    service_states: BTreeMap<ServiceId, SupervisionStatus>,
+    #[cfg(feature = "processing")]
+    redis_clients: Option<RedisClients>,
 }
 
 impl HealthCheckService {
@@ -97,6 +165,8 @@ impl HealthCheckService {
         aggregator: RouterHandle,
         upstream_relay: Addr<UpstreamRelay>,
         envelope_buffer: PartitionedEnvelopeBuffer,
+        handle: Handle,
+        #[cfg(feature = "processing")] redis_clients: Option<RedisClients>,
     ) -> Self {
         Self {
             config,
@@ -104,6 +174,10 @@ impl HealthCheckService {
             aggregator,
             upstream_relay,
             envelope_buffer,
+            handle,
+            service_states: BTreeMap::new(),
+            #[cfg(feature = "processing")]
+            redis_clients,
         }
     }
 
@@ -155,6 +229,40 @@ impl HealthCheckService {
         }
     }
 
+    fn services_probe(&mut self) -> Status {
+        services_status(&self.handle, &mut self.service_states)
+    }
+
+    #[cfg(not(feature = "processing"))]
+    async fn redis_probe(&self) -> Status {
+        Status::Healthy
+    }
+
+    #[cfg(feature = "processing")]
+    async fn redis_probe(&self) -> Status {
+        if !self.config.health_redis_circuit_breaker() {
+            return Status::Healthy;
+        }
+
+        let Some(clients) = &self.redis_clients else {
+            return Status::Healthy;
+        };
+
+        let mut status = Status::Healthy;
+        for (name, client) in [
+            ("project_configs", &clients.project_configs),
+            ("cardinality", &clients.cardinality),
+            ("quotas", &clients.quotas),
+        ] {
+            if client.circuit_state() == CircuitState::Open {
+                relay_log::error!("Redis circuit breaker of client {name} is open");
+                status = Status::Unhealthy;
+            }
+        }
+
+        status
+    }
+
     async fn probe(&self, name: &'static str, fut: impl Future<Output = Status>) -> Status {
         match timeout(self.config.health_probe_timeout(), fut).await {
             Err(_) => {
@@ -170,17 +278,21 @@ impl HealthCheckService {
     }
 
     async fn check_readiness(&mut self) -> Status {
-        // System memory is sync and requires mutable access, but we still want to log errors.
+        // System memory and services are sync and require mutable access, but we still want to
+        // log errors.
         let sys_mem = self.system_memory_probe();
+        let services = self.services_probe();
 
-        let (sys_mem, auth, agg, proj) = tokio::join!(
+        let (sys_mem, auth, agg, proj, services, redis) = tokio::join!(
             self.probe("system memory", async { sys_mem }),
             self.probe("auth", self.auth_probe()),
             self.probe("aggregator", self.aggregator_probe()),
             self.probe("spool health", self.spool_health_probe()),
+            self.probe("services", async { services }),
+            self.probe("redis", self.redis_probe()),
         );
 
-        Status::from_iter([sys_mem, auth, agg, proj])
+        Status::from_iter([sys_mem, auth, agg, proj, services, redis])
     }
 }
 
@@ -193,39 +305,56 @@ impl Service for HealthCheckService {
         // Add 10% buffer to the internal timeouts to avoid race conditions.
         let status_timeout = (check_interval + self.config.health_probe_timeout()).mul_f64(1.1);
 
-        relay_system::spawn!(async move {
+        // Probes run as part of the service rather than in a detached task, so that a panicking
+        // probe is caught by the supervisor of the service.
+        let checks = async {
             let shutdown = Controller::shutdown_handle();
 
             while shutdown.get().is_none() {
-                let _ = update_tx.send(StatusUpdate::new(relay_statsd::metric!(
+                let update = StatusUpdate::new(relay_statsd::metric!(
                     timer(RelayTimers::HealthCheckDuration),
                     type = "readiness",
                     { self.check_readiness().await }
-                )));
+                ));
 
+                update_tx.send(update).ok();
                 tokio::time::sleep(check_interval).await;
             }
 
             // Shutdown marks readiness health check as unhealthy.
             update_tx.send(StatusUpdate::new(Status::Unhealthy)).ok();
-        });
 
-        while let Some(HealthCheck(message, sender)) = rx.recv().await {
-            let update = update_rx.borrow();
+            // Keep answering health checks until the service shuts down.
+            std::future::pending::<()>().await;
+        };
+
+        let requests = async {
+            while let Some(HealthCheck(message, sender)) = rx.recv().await {
+                let update = update_rx.borrow();
+
+                sender.send(if matches!(message, IsHealthy::Liveness) {
+                    Status::Healthy
+                } else if update.instant.elapsed() >= status_timeout {
+                    Status::Unhealthy
+                } else {
+                    update.status
+                });
+            }
+        };
 
-            sender.send(if matches!(message, IsHealthy::Liveness) {
-                Status::Healthy
-            } else if update.instant.elapsed() >= status_timeout {
-                Status::Unhealthy
-            } else {
-                update.status
-            });
+        tokio::select! {
+            _ = checks => {},
+            _ = requests => {},
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use relay_system::{RestartPolicy, ServiceSpawnExt as _, Supervision};
+
     use super::*;
 
     #[test]
@@ -258,4 +387,106 @@ mod tests {
         let s = [].into_iter().collect();
         assert!(matches!(s, Status::Healthy));
     }
+
+    /// Service that panics on every message.
+    struct PanickingService;
+
+    struct Panic(Sender<()>);
+
+    impl Interface for Panic {}
+
+    impl FromMessage<()> for Panic {
+        type Response = AsyncResponse<()>;
+
+        fn from_message(_: (), sender: Sender<()>) -> Self {
+            Self(sender)
+        }
+    }
+
+    impl Service for PanickingService {
+        type Interface = Panic;
+
+        async fn run(self, mut rx: relay_system::Receiver<Self::Interface>) {
+            // Hold on to the sender, so the response fails when the service panics.
+            if let Some(Panic(_sender)) = rx.recv().await {
+                panic!("test panic");
+            }
+        }
+    }
+
+    /// Waits until the supervisor has handled the panic of the service.
+    async fn wait_for_status(handle: &Handle, status: SupervisionStatus) {
+        for _ in 0..100 {
+            let metrics = handle.current_services_metrics();
+            if metrics
+                .iter()
+                .filter_map(|(_, metrics)| metrics.supervision)
+                .any(|supervision| supervision.status == status)
+            {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        panic!("no service reached status {status:?}");
+    }
+
+    #[test]
+    fn test_services_status_non_critical_failed() {
+        let rt = relay_system::Runtime::builder("test")
+            .worker_threads(1)
+            .build();
+        let handle = rt.handle().clone();
+
+        rt.block_on(async {
+            let mut states = BTreeMap::new();
+            let addr = handle
+                .start_supervised(|| PanickingService, Supervision::new(RestartPolicy::Never));
+            assert!(matches!(
+                services_status(&handle, &mut states),
+                Status::Healthy
+            ));
+
+            assert!(addr.send(()).await.is_err());
+            wait_for_status(&handle, SupervisionStatus::Failed).await;
+
+            assert!(matches!(
+                services_status(&handle, &mut states),
+                Status::Healthy
+            ));
+            assert!(states.values().any(|&s| s == SupervisionStatus::Failed));
+        });
+    }
+
+    #[test]
+    fn test_services_status_critical_restarting() {
+        let rt = relay_system::Runtime::builder("test")
+            .worker_threads(1)
+            .build();
+        let handle = rt.handle().clone();
+
+        rt.block_on(async {
+            let mut states = BTreeMap::new();
+            let addr = handle.start_supervised(
+                || PanickingService,
+                Supervision::new(RestartPolicy::Backoff {
+                    initial: Duration::from_secs(3600),
+                    max: Duration::from_secs(3600),
+                    max_restarts: 1,
+                })
+                .critical(true),
+            );
+            assert!(matches!(
+                services_status(&handle, &mut states),
+                Status::Healthy
+            ));
+
+            assert!(addr.send(()).await.is_err());
+            wait_for_status(&handle, SupervisionStatus::Restarting).await;
+
+            assert!(matches!(
+                services_status(&handle, &mut states),
+                Status::Unhealthy
+            ));
+        });
+    }
 }
@@ -226,12 +226,21 @@ impl AggregatorService {
             buckets,
         } = msg;
 
+        // Account the cost of buckets to the organization, once the project is known.
+        let organization_id = match self.project_cache.get(project_key).state() {
+            ProjectState::Enabled(info) => info.organization_id,
+            ProjectState::Disabled | ProjectState::Pending => None,
+        };
+
         for mut bucket in buckets.into_iter() {
             if !validate_bucket(&mut bucket, &self.config) {
                 continue;
             };
 
-            match self.aggregator.merge(project_key, bucket) {
+            match self
+                .aggregator
+                .merge_with_organization(organization_id, project_key, bucket)
+            {
                 // Ignore invalid timestamp errors and drop the bucket.
                 Err(AggregateMetricsError::InvalidTimestamp(_)) => {}
                 Err(AggregateMetricsError::TotalLimitExceeded) => {
@@ -250,6 +259,14 @@ impl AggregatorService {
                     );
                     break;
                 }
+                Err(AggregateMetricsError::OrganizationLimitExceeded) => {
+                    relay_log::error!(
+                        tags.aggregator = self.aggregator.name(),
+                        tags.project_key = project_key.as_str(),
+                        "organization metrics limit exceeded for project {project_key}"
+                    );
+                    break;
+                }
                 Err(error) => {
                     relay_log::error!(
                         tags.aggregator = self.aggregator.name(),
@@ -261,6 +278,11 @@ impl AggregatorService {
                 Ok(()) => {}
             };
         }
+
+        // Buckets evicted to make room for this project are flushed right away.
+        for partition in self.aggregator.take_evicted() {
+            self.flush_partition(partition);
+        }
     }
 
     fn handle_message(&mut self, message: Aggregator) {
@@ -282,7 +304,7 @@ impl AggregatorService {
                 bucket_interval: 1,
                 aggregator_size: 1,
                 initial_delay: 0,
-                ..self.config.aggregator
+                ..self.config.aggregator.clone()
             },
         );
 
@@ -15,6 +15,7 @@ use std::{fmt, mem};
 use crate::envelope::{AttachmentType, ItemType};
 #[cfg(feature = "processing")]
 use crate::service::ServiceError;
+use crate::services::outcome_store::OutcomeStore;
 use crate::services::processor::{EnvelopeProcessor, SubmitClientReports};
 use crate::services::upstream::{Method, SendQuery, UpstreamQuery, UpstreamRelay};
 use crate::statsd::RelayCounters;
@@ -28,7 +29,7 @@ use relay_dynamic_config::Feature;
 use relay_event_schema::protocol::{ClientReport, DiscardedEvent, EventId};
 use relay_filter::FilterStatKey;
 #[cfg(feature = "processing")]
-use relay_kafka::{ClientError, KafkaClient, KafkaTopic};
+use relay_kafka::{ClientError, KafkaClient, KafkaTopic, SerializationOutput};
 use relay_quotas::{DataCategory, ReasonCode, Scoping};
 use relay_sampling::config::RuleId;
 use relay_sampling::evaluation::MatchedRuleIds;
@@ -86,6 +87,20 @@ impl OutcomeId {
     pub fn as_u8(self) -> u8 {
         self.0
     }
+
+    /// Returns the name of the outcome as used in metrics and the outcomes query API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutcomeId::ACCEPTED => "accepted",
+            OutcomeId::FILTERED => "filtered",
+            OutcomeId::RATE_LIMITED => "rate_limited",
+            OutcomeId::INVALID => "invalid",
+            OutcomeId::ABUSE => "abuse",
+            OutcomeId::CLIENT_DISCARD => "client_discard",
+            OutcomeId::CARDINALITY_LIMITED => "cardinality_limited",
+            _ => "<unknown>",
+        }
+    }
 }
 
 trait TrackOutcomeLike {
@@ -97,16 +112,7 @@ trait TrackOutcomeLike {
 
     /// TODO: Doc
     fn tag_name(&self) -> &'static str {
-        match self.outcome_id() {
-            OutcomeId::ACCEPTED => "accepted",
-            OutcomeId::FILTERED => "filtered",
-            OutcomeId::RATE_LIMITED => "rate_limited",
-            OutcomeId::INVALID => "invalid",
-            OutcomeId::ABUSE => "abuse",
-            OutcomeId::CLIENT_DISCARD => "client_discard",
-            OutcomeId::CARDINALITY_LIMITED => "cardinality_limited",
-            _ => "<unknown>",
-        }
+        self.outcome_id().as_str()
     }
 }
 
@@ -1156,7 +1162,7 @@ impl OutcomeBroker {
             Some(key.into_bytes()),
             None,
             "outcome",
-            payload.as_bytes(),
+            SerializationOutput::Json(Cow::Borrowed(payload.as_bytes())),
         );
 
         match result {
@@ -1230,10 +1236,14 @@ impl ProducerInner {
 }
 
 /// Service implementing the [`OutcomeProducer`] interface.
+///
+/// Additionally to the configured backend, outcomes tracked by this Relay are sent to the
+/// [`OutcomeStore`] if it is enabled.
 #[derive(Debug)]
 pub struct OutcomeProducerService {
     config: Arc<Config>,
     inner: ProducerInner,
+    outcome_store: Option<Addr<OutcomeStore>>,
 }
 
 impl OutcomeProducerService {
@@ -1241,6 +1251,7 @@ impl OutcomeProducerService {
         config: Arc<Config>,
         upstream_relay: Addr<UpstreamRelay>,
         envelope_processor: Addr<EnvelopeProcessor>,
+        outcome_store: Option<Addr<OutcomeStore>>,
     ) -> anyhow::Result<Self> {
         let inner = match config.emit_outcomes() {
             #[cfg(feature = "processing")]
@@ -1270,7 +1281,11 @@ impl OutcomeProducerService {
             }
         };
 
-        Ok(Self { config, inner })
+        Ok(Self {
+            config,
+            inner,
+            outcome_store,
+        })
     }
 }
 
@@ -1278,12 +1293,21 @@ impl Service for OutcomeProducerService {
     type Interface = OutcomeProducer;
 
     async fn run(self, mut rx: relay_system::Receiver<Self::Interface>) {
-        let Self { config, inner } = self;
+        let Self {
+            config,
+            inner,
+            outcome_store,
+        } = self;
 
         let broker = inner.start();
 
         relay_log::info!("OutcomeProducer started.");
         while let Some(message) = rx.recv().await {
+            if let (Some(outcome_store), OutcomeProducer::TrackOutcome(outcome)) =
+                (&outcome_store, &message)
+            {
+                outcome_store.send(outcome.clone());
+            }
             broker.handle_message(message, &config);
         }
         relay_log::info!("OutcomeProducer stopped.");
@@ -50,7 +50,9 @@ pub struct OutcomeAggregator {
 
 impl OutcomeAggregator {
     pub fn new(config: &Config, outcome_producer: Addr<OutcomeProducer>) -> Self {
-        let disabled = matches!(config.emit_outcomes(), EmitOutcomes::None);
+        // The outcome store receives outcomes even if they are not emitted.
+        let disabled =
+            matches!(config.emit_outcomes(), EmitOutcomes::None) && !config.outcome_store().enabled;
 
         Self {
             disabled,
@@ -0,0 +1,599 @@
+//! This module contains the outcome store, which keeps aggregated outcomes of this Relay in a
+//! local SQLite database and serves queries for them.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use relay_config::Config;
+use relay_system::{
+    AsyncResponse, Controller, FromMessage, Interface, NoResponse, Sender, Service, Shutdown,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow, SqliteSynchronous,
+};
+use sqlx::{Pool, QueryBuilder, Row, Sqlite};
+use tokio::time::MissedTickBehavior;
+
+use crate::services::outcome::TrackOutcome;
+use crate::statsd::RelayCounters;
+
+/// Creates the outcomes table.
+///
+/// Outcomes without organization id are stored with id `0`, and outcomes without reason with an
+/// empty reason, since `NULL` values are never equal in the primary key.
+const CREATE_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS outcomes (
+    timestamp INTEGER NOT NULL,
+    org_id INTEGER NOT NULL,
+    project_id INTEGER NOT NULL,
+    category TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    PRIMARY KEY (timestamp, org_id, project_id, category, outcome, reason)
+) WITHOUT ROWID;
+";
+
+/// Adds the quantity of an aggregated outcome to the stored outcome.
+const UPSERT_OUTCOME: &str = "
+INSERT INTO outcomes (timestamp, org_id, project_id, category, outcome, reason, quantity)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+ON CONFLICT (timestamp, org_id, project_id, category, outcome, reason)
+DO UPDATE SET quantity = quantity + excluded.quantity;
+";
+
+/// The maximum number of outcomes returned by a single query.
+const MAX_QUERY_LIMIT: usize = 10_000;
+
+/// The maximum number of aggregated outcomes kept in memory until they are written.
+///
+/// Outcomes for new buckets are dropped once this is reached, which only happens if writing to the
+/// database fails repeatedly.
+const MAX_PENDING_BUCKETS: usize = 100_000;
+
+/// An error returned by the [`OutcomeStoreService`].
+#[derive(Debug, thiserror::Error)]
+pub enum OutcomeStoreError {
+    /// Neither a store path nor an envelope spool path is configured.
+    #[error("no file path for the outcome store was provided")]
+    NoFilePath,
+
+    /// The directory of the database could not be created.
+    #[error("failed to create the outcome store directory: {0}")]
+    FileSetupError(std::io::Error),
+
+    /// The database could not be opened or initialized.
+    #[error("failed to setup the outcome store database: {0}")]
+    SetupFailed(sqlx::Error),
+
+    /// Writing outcomes to the database failed.
+    #[error("failed to write outcomes: {0}")]
+    WriteError(sqlx::Error),
+
+    /// Reading outcomes from the database failed.
+    #[error("failed to read outcomes: {0}")]
+    FetchError(sqlx::Error),
+}
+
+/// Queries stored outcomes.
+///
+/// All filters are optional. The time range applies to the start of the stored time buckets, where
+/// `start` is inclusive and `end` is exclusive. At most [`MAX_QUERY_LIMIT`] outcomes are returned.
+#[derive(Debug, Default, Deserialize)]
+pub struct QueryOutcomes {
+    /// Only returns outcomes in buckets starting at or after this time.
+    pub start: Option<DateTime<Utc>>,
+    /// Only returns outcomes in buckets starting before this time.
+    pub end: Option<DateTime<Utc>>,
+    /// Only returns outcomes of this project.
+    pub project_id: Option<u64>,
+    /// Only returns outcomes of this data category, for example `error`.
+    pub category: Option<String>,
+    /// Only returns outcomes of this type, for example `rate_limited`.
+    pub outcome: Option<String>,
+    /// Only returns outcomes with this reason, for example `usage_exceeded`.
+    pub reason: Option<String>,
+    /// Returns at most this many outcomes, capped at [`MAX_QUERY_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+/// An aggregated outcome returned by [`QueryOutcomes`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StoredOutcome {
+    /// The start of the time bucket.
+    pub timestamp: DateTime<Utc>,
+    /// The organization id, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<u64>,
+    /// The project id.
+    pub project_id: u64,
+    /// The name of the data category.
+    pub category: String,
+    /// The name of the outcome.
+    pub outcome: String,
+    /// The reason for the outcome.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The summed quantity of all outcomes in the bucket.
+    pub quantity: u64,
+}
+
+impl StoredOutcome {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let timestamp: i64 = row.try_get("timestamp")?;
+        let org_id: i64 = row.try_get("org_id")?;
+        let project_id: i64 = row.try_get("project_id")?;
+        let reason: String = row.try_get("reason")?;
+        let quantity: i64 = row.try_get("quantity")?;
+
+        Ok(Self {
+            timestamp: DateTime::from_timestamp(timestamp, 0).unwrap_or_default(),
+            org_id: (org_id != 0).then_some(org_id as u64),
+            project_id: project_id as u64,
+            category: row.try_get("category")?,
+            outcome: row.try_get("outcome")?,
+            reason: (!reason.is_empty()).then_some(reason),
+            quantity: quantity as u64,
+        })
+    }
+}
+
+/// Stores outcomes and serves queries for them.
+///
+/// See [`OutcomeStoreService`] for more information.
+#[derive(Debug)]
+pub enum OutcomeStore {
+    Track(TrackOutcome),
+    Query(
+        QueryOutcomes,
+        Sender<Result<Vec<StoredOutcome>, OutcomeStoreError>>,
+    ),
+}
+
+impl Interface for OutcomeStore {}
+
+impl FromMessage<TrackOutcome> for OutcomeStore {
+    type Response = NoResponse;
+
+    fn from_message(message: TrackOutcome, _: ()) -> Self {
+        Self::Track(message)
+    }
+}
+
+impl FromMessage<QueryOutcomes> for OutcomeStore {
+    type Response = AsyncResponse<Result<Vec<StoredOutcome>, OutcomeStoreError>>;
+
+    fn from_message(
+        message: QueryOutcomes,
+        sender: Sender<Result<Vec<StoredOutcome>, OutcomeStoreError>>,
+    ) -> Self {
+        Self::Query(message, sender)
+    }
+}
+
+/// Contains everything to identify a stored outcome, except quantity.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct BucketKey {
+    /// The start of the time bucket in seconds since epoch.
+    timestamp: u64,
+    org_id: u64,
+    project_id: u64,
+    category: &'static str,
+    outcome: &'static str,
+    reason: String,
+}
+
+/// Keeps aggregated outcomes of this Relay in a local SQLite database.
+///
+/// Outcomes are aggregated in memory per project, data category, outcome and reason into time
+/// buckets of the configured width, and are periodically added to the database. Outcomes older
+/// than the configured retention are removed on every flush.
+pub struct OutcomeStoreService {
+    db: Pool<Sqlite>,
+    /// The width of each time bucket in seconds.
+    bucket_interval: u64,
+    /// The interval between writes to the database.
+    flush_interval: Duration,
+    /// The number of days to keep outcomes.
+    retention_days: u16,
+    /// Mapping from bucket key to the quantity which has not been written yet.
+    buckets: HashMap<BucketKey, u64>,
+}
+
+impl OutcomeStoreService {
+    /// Opens the database of the outcome store and creates the outcomes table if needed.
+    pub async fn create(config: &Config) -> Result<Self, OutcomeStoreError> {
+        let path = config
+            .outcome_store_path()
+            .ok_or(OutcomeStoreError::NoFilePath)?;
+
+        relay_log::info!("outcome store file {}", path.to_string_lossy());
+        Self::create_directory(&path).await?;
+
+        let options = SqliteConnectOptions::new()
+            .filename(&path)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .create_if_missing(true);
+
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(OutcomeStoreError::SetupFailed)?;
+
+        sqlx::query(CREATE_TABLE)
+            .execute(&db)
+            .await
+            .map_err(OutcomeStoreError::SetupFailed)?;
+
+        let store_config = config.outcome_store();
+        Ok(Self {
+            db,
+            bucket_interval: store_config.bucket_interval.max(1),
+            flush_interval: Duration::from_secs(store_config.flush_interval.max(1)),
+            retention_days: store_config.retention_days,
+            buckets: HashMap::new(),
+        })
+    }
+
+    /// Creates the directories for the database file.
+    async fn create_directory(path: &Path) -> Result<(), OutcomeStoreError> {
+        let Some(parent) = path.parent() else {
+            return Ok(());
+        };
+
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(OutcomeStoreError::FileSetupError)?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_track_outcome(&mut self, message: TrackOutcome) {
+        let timestamp = message.timestamp.timestamp().max(0) as u64;
+
+        let bucket_key = BucketKey {
+            timestamp: timestamp - timestamp % self.bucket_interval,
+            org_id: message.scoping.organization_id.value(),
+            project_id: message.scoping.project_id.value(),
+            category: message.category.name(),
+            outcome: message.outcome.to_outcome_id().as_str(),
+            reason: message
+                .outcome
+                .to_reason()
+                .map(|reason| reason.into_owned())
+                .unwrap_or_default(),
+        };
+
+        let pending = self.buckets.len();
+        match self.buckets.entry(bucket_key) {
+            Entry::Occupied(mut entry) => *entry.get_mut() += u64::from(message.quantity),
+            Entry::Vacant(_) if pending >= MAX_PENDING_BUCKETS => {
+                relay_statsd::metric!(counter(RelayCounters::OutcomeStoreDropped) += 1);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(u64::from(message.quantity));
+            }
+        }
+    }
+
+    async fn handle_query(
+        &mut self,
+        query: QueryOutcomes,
+    ) -> Result<Vec<StoredOutcome>, OutcomeStoreError> {
+        // Write pending outcomes first, so that queries include the most recent outcomes.
+        self.flush().await;
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT timestamp, org_id, project_id, category, outcome, reason, quantity \
+            FROM outcomes WHERE 1 = 1",
+        );
+
+        if let Some(start) = query.start {
+            builder
+                .push(" AND timestamp >= ")
+                .push_bind(start.timestamp());
+        }
+        if let Some(end) = query.end {
+            builder.push(" AND timestamp < ").push_bind(end.timestamp());
+        }
+        if let Some(project_id) = query.project_id {
+            builder
+                .push(" AND project_id = ")
+                .push_bind(project_id as i64);
+        }
+        if let Some(category) = query.category {
+            builder.push(" AND category = ").push_bind(category);
+        }
+        if let Some(outcome) = query.outcome {
+            builder.push(" AND outcome = ").push_bind(outcome);
+        }
+        if let Some(reason) = query.reason {
+            builder.push(" AND reason = ").push_bind(reason);
+        }
+
+        let limit = query.limit.unwrap_or(MAX_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+        builder
+            .push(" ORDER BY timestamp, project_id, category, outcome, reason LIMIT ")
+            .push_bind(limit as i64);
+
+        let rows = builder
+            .build()
+            .fetch_all(&self.db)
+            .await
+            .map_err(OutcomeStoreError::FetchError)?;
+
+        rows.iter()
+            .map(StoredOutcome::from_row)
+            .collect::<Result<_, _>>()
+            .map_err(OutcomeStoreError::FetchError)
+    }
+
+    /// Writes all pending outcomes to the database and removes expired outcomes.
+    async fn flush(&mut self) {
+        if let Err(error) = self.write().await {
+            relay_log::error!(
+                error = &error as &dyn Error,
+                "failed to write to the outcome store"
+            );
+        }
+    }
+
+    /// Writes pending outcomes in a single transaction.
+    ///
+    /// Pending outcomes are only discarded once the transaction is committed, so that they are
+    /// written with the next flush if writing fails.
+    async fn write(&mut self) -> Result<(), OutcomeStoreError> {
+        let expiry = Utc::now().timestamp() - i64::from(self.retention_days) * 24 * 3600;
+
+        let mut transaction = self
+            .db
+            .begin()
+            .await
+            .map_err(OutcomeStoreError::WriteError)?;
+
+        for (key, quantity) in &self.buckets {
+            sqlx::query(UPSERT_OUTCOME)
+                .bind(key.timestamp as i64)
+                .bind(key.org_id as i64)
+                .bind(key.project_id as i64)
+                .bind(key.category)
+                .bind(key.outcome)
+                .bind(key.reason.as_str())
+                .bind(*quantity as i64)
+                .execute(&mut *transaction)
+                .await
+                .map_err(OutcomeStoreError::WriteError)?;
+        }
+
+        sqlx::query("DELETE FROM outcomes WHERE timestamp < ?")
+            .bind(expiry)
+            .execute(&mut *transaction)
+            .await
+            .map_err(OutcomeStoreError::WriteError)?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(OutcomeStoreError::WriteError)?;
+
+        self.buckets.clear();
+        Ok(())
+    }
+
+    async fn handle_shutdown(&mut self, message: Shutdown) {
+        if message.timeout.is_some() {
+            self.flush().await;
+        }
+    }
+
+    async fn handle_message(&mut self, message: OutcomeStore) {
+        match message {
+            OutcomeStore::Track(message) => self.handle_track_outcome(message),
+            OutcomeStore::Query(query, sender) => sender.send(self.handle_query(query).await),
+        }
+    }
+}
+
+impl Service for OutcomeStoreService {
+    type Interface = OutcomeStore;
+
+    async fn run(mut self, mut rx: relay_system::Receiver<Self::Interface>) {
+        let mut shutdown = Controller::shutdown_handle();
+        let mut ticker = tokio::time::interval(self.flush_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        relay_log::info!("outcome store started");
+
+        loop {
+            tokio::select! {
+                // Prioritize flush over receiving messages to prevent starving.
+                biased;
+
+                _ = ticker.tick() => self.flush().await,
+                Some(message) = rx.recv() => self.handle_message(message).await,
+                shutdown = shutdown.notified() => self.handle_shutdown(shutdown).await,
+                else => break,
+            }
+        }
+
+        relay_log::info!("outcome store stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use relay_base_schema::organization::OrganizationId;
+    use relay_base_schema::project::{ProjectId, ProjectKey};
+    use relay_quotas::{DataCategory, ReasonCode, Scoping};
+
+    use super::*;
+    use crate::services::outcome::Outcome;
+
+    fn track_outcome(timestamp: DateTime<Utc>, outcome: Outcome, quantity: u32) -> TrackOutcome {
+        TrackOutcome {
+            timestamp,
+            scoping: Scoping {
+                organization_id: OrganizationId::new(1),
+                project_id: ProjectId::new(42),
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                key_id: None,
+            },
+            outcome,
+            event_id: None,
+            remote_addr: None,
+            category: DataCategory::Error,
+            quantity,
+        }
+    }
+
+    async fn store(dir: &Path) -> OutcomeStoreService {
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "store": {
+                    "enabled": true,
+                    "path": dir.join("outcomes.db"),
+                }
+            }
+        }))
+        .unwrap();
+
+        OutcomeStoreService::create(&config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_store_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store(dir.path()).await;
+
+        let now = Utc::now();
+        let rate_limited = Outcome::RateLimited(Some(ReasonCode::new("usage_exceeded")));
+
+        store.handle_track_outcome(track_outcome(now, Outcome::Accepted, 3));
+        store.handle_track_outcome(track_outcome(now, rate_limited.clone(), 2));
+        store.flush().await;
+        // Quantities are added to the stored outcomes.
+        store.handle_track_outcome(track_outcome(now, rate_limited, 5));
+
+        let outcomes = store
+            .handle_query(QueryOutcomes {
+                outcome: Some("rate_limited".to_owned()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let bucket = now.timestamp() - now.timestamp() % 3600;
+        assert_eq!(
+            outcomes,
+            vec![StoredOutcome {
+                timestamp: DateTime::from_timestamp(bucket, 0).unwrap(),
+                org_id: Some(1),
+                project_id: 42,
+                category: "error".to_owned(),
+                outcome: "rate_limited".to_owned(),
+                reason: Some("usage_exceeded".to_owned()),
+                quantity: 7,
+            }]
+        );
+
+        let outcomes = store
+            .handle_query(QueryOutcomes {
+                project_id: Some(42),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(outcomes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_time_range_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store(dir.path()).await;
+
+        let recent = Utc::now() - chrono::Duration::days(1);
+        let expired = Utc::now() - chrono::Duration::days(31);
+        store.handle_track_outcome(track_outcome(recent, Outcome::Accepted, 1));
+        store.handle_track_outcome(track_outcome(expired, Outcome::Accepted, 1));
+
+        let outcomes = store.handle_query(QueryOutcomes::default()).await.unwrap();
+        assert_eq!(outcomes.len(), 1);
+
+        let outcomes = store
+            .handle_query(QueryOutcomes {
+                start: Some(Utc.timestamp_opt(0, 0).unwrap()),
+                end: Some(recent - chrono::Duration::hours(1)),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(outcomes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store(dir.path()).await;
+
+        let now = Utc::now();
+        for reason in ["a", "b", "c"] {
+            let outcome = Outcome::RateLimited(Some(ReasonCode::new(reason)));
+            store.handle_track_outcome(track_outcome(now, outcome, 1));
+        }
+
+        let outcomes = store
+            .handle_query(QueryOutcomes {
+                limit: Some(2),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[1].reason.as_deref(), Some("b"));
+    }
+
+    #[tokio::test]
+    async fn test_keep_outcomes_on_write_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store(dir.path()).await;
+
+        store.handle_track_outcome(track_outcome(Utc::now(), Outcome::Accepted, 1));
+        store.db.close().await;
+
+        assert!(store.write().await.is_err());
+        assert_eq!(store.buckets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_drop_outcomes_over_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = store(dir.path()).await;
+
+        let now = Utc::now();
+        for i in 0..MAX_PENDING_BUCKETS {
+            let outcome = Outcome::RateLimited(Some(ReasonCode::new(i.to_string())));
+            store.handle_track_outcome(track_outcome(now, outcome, 1));
+        }
+
+        // Outcomes for new buckets are dropped, existing buckets are still updated.
+        store.handle_track_outcome(track_outcome(now, Outcome::Accepted, 1));
+        assert_eq!(store.buckets.len(), MAX_PENDING_BUCKETS);
+
+        let outcome = Outcome::RateLimited(Some(ReasonCode::new("0")));
+        store.handle_track_outcome(track_outcome(now, outcome, 1));
+        assert_eq!(
+            store.buckets.values().sum::<u64>(),
+            MAX_PENDING_BUCKETS as u64 + 1
+        );
+    }
+}
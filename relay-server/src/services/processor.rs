@@ -6,7 +6,6 @@ use std::future::Future;
 use std::io::Write;
 use std::pin::Pin;
 use std::sync::{Arc, Once};
-use std::time::Duration;
 
 use anyhow::Context;
 use brotli::CompressorWriter as BrotliEncoder;
@@ -22,9 +21,9 @@ use relay_common::time::UnixTimestamp;
 use relay_config::{Config, HttpEncoding, NormalizationLevel, RelayMode};
 use relay_dynamic_config::{CombinedMetricExtractionConfig, ErrorBoundary, Feature, GlobalConfig};
 use relay_event_normalization::{
-    ClockDriftProcessor, CombinedMeasurementsConfig, EventValidationConfig, GeoIpLookup,
-    MeasurementsConfig, NormalizationConfig, RawUserAgentInfo, TransactionNameConfig,
-    normalize_event, validate_event,
+    ClockDriftProcessor, CombinedMeasurementsConfig, DEFAULT_CLOCK_DRIFT_THRESHOLD,
+    EventValidationConfig, GeoIpLookup, MeasurementsConfig, NormalizationConfig, RawUserAgentInfo,
+    TransactionNameConfig, TrimmingPolicy, normalize_event, validate_event,
 };
 use relay_event_schema::processor::ProcessingAction;
 use relay_event_schema::protocol::{
@@ -34,7 +33,7 @@ use relay_filter::FilterStatKey;
 use relay_metrics::{Bucket, BucketMetadata, BucketView, BucketsView, MetricNamespace};
 use relay_pii::PiiConfigError;
 use relay_protocol::{Annotated, Empty};
-use relay_quotas::{DataCategory, Quota, RateLimits, Scoping};
+use relay_quotas::{DataCategory, MemoryRateLimiter, Quota, RateLimits, Scoping};
 use relay_sampling::evaluation::{ReservoirCounters, ReservoirEvaluator, SamplingDecision};
 use relay_statsd::metric;
 use relay_system::{Addr, FromMessage, NoResponse, Service};
@@ -71,15 +70,18 @@ use relay_base_schema::organization::OrganizationId;
 use relay_threading::AsyncPool;
 #[cfg(feature = "processing")]
 use {
+    crate::metrics_extraction::generic,
+    crate::metrics_extraction::items::ReceivedCheckIn,
     crate::services::global_rate_limits::{GlobalRateLimits, GlobalRateLimitsServiceHandle},
     crate::services::processor::nnswitch::SwitchProcessingError,
     crate::services::store::{Store, StoreEnvelope},
     crate::utils::{Enforcement, ItemAction},
     itertools::Itertools,
     relay_cardinality::{
-        CardinalityLimit, CardinalityLimiter, CardinalityLimitsSplit, RedisSetLimiter,
-        RedisSetLimiterOptions,
+        CardinalityBackend, CardinalityLimit, CardinalityLimiter, CardinalityLimitsSplit,
+        MemoryLimiter, MemoryLimiterOptions, RedisSetLimiter, RedisSetLimiterOptions, RedisStorage,
     },
+    relay_config::CardinalityLimiterBackend,
     relay_dynamic_config::{CardinalityLimiterMode, MetricExtractionGroups},
     relay_quotas::{RateLimitingError, RedisRateLimiter},
     relay_redis::{AsyncRedisClient, RedisClients},
@@ -131,9 +133,6 @@ macro_rules! if_processing {
     };
 }
 
-/// The minimum clock drift for correction to apply.
-const MINIMUM_CLOCK_DRIFT: Duration = Duration::from_secs(55 * 60);
-
 #[derive(Debug)]
 pub struct GroupTypeError;
 
@@ -1168,7 +1167,9 @@ struct InnerProcessor {
     addrs: Addrs,
     #[cfg(feature = "processing")]
     rate_limiter: Option<Arc<RedisRateLimiter<GlobalRateLimitsServiceHandle>>>,
+    local_rate_limiter: Option<MemoryRateLimiter>,
     geoip_lookup: Option<GeoIpLookup>,
+    transaction_clusterers: Option<transaction::TransactionClusterers>,
     #[cfg(feature = "processing")]
     cardinality_limiter: Option<CardinalityLimiter>,
     metric_outcomes: MetricOutcomes,
@@ -1202,6 +1203,16 @@ impl EnvelopeProcessorService {
             }
         });
 
+        if let Some(path) = config.user_agent_regexes_path() {
+            match relay_ua::init_parser_from_file(path) {
+                Ok(()) => relay_log::info!("loaded user agent regexes from {path:?}"),
+                Err(err) => relay_log::error!(
+                    error = &err as &dyn std::error::Error,
+                    "failed to load user agent regexes from {path:?}, using built-in database"
+                ),
+            }
+        }
+
         #[cfg(feature = "processing")]
         let (cardinality, quotas) = match redis {
             Some(RedisClients {
@@ -1223,11 +1234,17 @@ impl EnvelopeProcessorService {
             _ => None,
         };
 
+        let local_rate_limiter = config.local_quota_fraction().map(|fraction| {
+            MemoryRateLimiter::new()
+                .limit_fraction(fraction)
+                .max_limit(config.max_rate_limit())
+        });
+
         let quota_limiter = Arc::new(QuotaRateLimiter::new(
-            #[cfg(feature = "processing")]
             project_cache.clone(),
             #[cfg(feature = "processing")]
             rate_limiter.clone(),
+            local_rate_limiter.clone(),
         ));
         #[cfg(feature = "processing")]
         let rate_limiter = rate_limiter.map(Arc::new);
@@ -1241,20 +1258,14 @@ impl EnvelopeProcessorService {
             quotas_client: quotas.clone(),
             #[cfg(feature = "processing")]
             rate_limiter,
+            local_rate_limiter,
             addrs,
             geoip_lookup,
+            transaction_clusterers: transaction::TransactionClusterers::from_config(
+                config.transaction_clustering(),
+            ),
             #[cfg(feature = "processing")]
-            cardinality_limiter: cardinality
-                .map(|cardinality| {
-                    RedisSetLimiter::new(
-                        RedisSetLimiterOptions {
-                            cache_vacuum_interval: config
-                                .cardinality_limiter_cache_vacuum_interval(),
-                        },
-                        cardinality,
-                    )
-                })
-                .map(CardinalityLimiter::new),
+            cardinality_limiter: create_cardinality_limiter(&config, cardinality),
             metric_outcomes,
             processing: Processing {
                 logs: LogsProcessor::new(quota_limiter),
@@ -1267,24 +1278,43 @@ impl EnvelopeProcessorService {
         }
     }
 
-    /// Normalize monitor check-ins and remove invalid ones.
+    /// Normalize monitor check-ins and remove invalid or filtered ones.
     #[cfg(feature = "processing")]
     fn normalize_checkins(
         &self,
         managed_envelope: &mut TypedEnvelope<CheckInGroup>,
         project_id: ProjectId,
+        project_info: &ProjectInfo,
     ) {
+        let meta = managed_envelope.envelope().meta();
+        let client_ip = meta.client_addr();
+        let user_agent = meta.user_agent().map(str::to_owned);
+        let filter_settings = &project_info.config.filter_settings;
+        let global_config = self.inner.global_config.current();
+
         managed_envelope.retain_items(|item| {
             if item.ty() != &ItemType::CheckIn {
                 return ItemAction::Keep;
             }
 
-            match relay_monitors::process_check_in(&item.payload(), project_id) {
+            match relay_monitors::process_check_in(
+                &item.payload(),
+                project_id,
+                relay_monitors::CheckInClient {
+                    ip: client_ip,
+                    user_agent: user_agent.as_deref(),
+                },
+                filter_settings,
+                global_config.filters(),
+            ) {
                 Ok(result) => {
                     item.set_routing_hint(result.routing_hint);
                     item.set_payload(ContentType::Json, result.payload);
                     ItemAction::Keep
                 }
+                Err(relay_monitors::ProcessCheckInError::Filtered(filter_stat_key)) => {
+                    ItemAction::Drop(Outcome::Filtered(filter_stat_key))
+                }
                 Err(error) => {
                     // TODO: Track an outcome.
                     relay_log::debug!(
@@ -1297,6 +1327,44 @@ impl EnvelopeProcessorService {
         })
     }
 
+    /// Extracts metrics from normalized monitor check-ins.
+    #[cfg(feature = "processing")]
+    fn extract_checkin_metrics(
+        &self,
+        managed_envelope: &TypedEnvelope<CheckInGroup>,
+        project_info: &ProjectInfo,
+    ) -> ProcessingExtractedMetrics {
+        let mut extracted_metrics = ProcessingExtractedMetrics::new();
+
+        let global_config = self.inner.global_config.current();
+        let Some(config) = generic::combined_config(&global_config, &project_info.config) else {
+            return extracted_metrics;
+        };
+
+        let received_at = UnixTimestamp::from_datetime(managed_envelope.received_at())
+            .unwrap_or_else(UnixTimestamp::now);
+
+        for item in managed_envelope.envelope().items() {
+            if item.ty() != &ItemType::CheckIn {
+                continue;
+            }
+
+            let Ok(check_in) = serde_json::from_slice::<relay_monitors::CheckIn>(&item.payload())
+            else {
+                continue;
+            };
+
+            let check_in = ReceivedCheckIn {
+                check_in: &check_in,
+                received_at,
+            };
+            let metrics = generic::extract_metrics(&check_in, config);
+            extracted_metrics.extend_project_metrics(metrics, None);
+        }
+
+        extracted_metrics
+    }
+
     async fn enforce_quotas<Group>(
         &self,
         managed_envelope: &mut TypedEnvelope<Group>,
@@ -1319,35 +1387,38 @@ impl EnvelopeProcessorService {
             )
             .await?;
 
-        if_processing!(self.inner.config, {
-            let rate_limiter = match self.inner.rate_limiter.clone() {
-                Some(rate_limiter) => rate_limiter,
-                None => return Ok(cached_result.event),
-            };
+        let rate_limiter = if_processing!(self.inner.config, {
+            self.inner.rate_limiter.clone().map(RateLimiter::Consistent)
+        } else {
+            self.inner.local_rate_limiter.clone().map(RateLimiter::Local)
+        });
 
-            // Enforce all quotas consistently with Redis.
-            let consistent_result = RateLimiter::Consistent(rate_limiter)
-                .enforce(
-                    managed_envelope,
-                    cached_result.event,
-                    extracted_metrics,
-                    &global_config,
-                    project_info,
-                    rate_limits,
-                )
-                .await?;
+        let Some(rate_limiter) = rate_limiter else {
+            return Ok(cached_result.event);
+        };
 
-            // Update cached rate limits with the freshly computed ones.
-            if !consistent_result.rate_limits.is_empty() {
-                self.inner
-                    .project_cache
-                    .get(managed_envelope.scoping().project_key)
-                    .rate_limits()
-                    .merge(consistent_result.rate_limits);
-            }
+        // Enforce all quotas consistently with Redis, or in memory if local quotas are enabled.
+        let consistent_result = rate_limiter
+            .enforce(
+                managed_envelope,
+                cached_result.event,
+                extracted_metrics,
+                &global_config,
+                project_info,
+                rate_limits,
+            )
+            .await?;
+
+        // Update cached rate limits with the freshly computed ones.
+        if !consistent_result.rate_limits.is_empty() {
+            self.inner
+                .project_cache
+                .get(managed_envelope.scoping().project_key)
+                .rate_limits()
+                .merge(consistent_result.rate_limits);
+        }
 
-            Ok(consistent_result.event)
-        } else { Ok(cached_result.event) })
+        Ok(consistent_result.event)
     }
 
     /// Extract transaction metrics.
@@ -1440,6 +1511,7 @@ impl EnvelopeProcessorService {
                 .config
                 .aggregator_config_for(MetricNamespace::Spans)
                 .max_tag_value_length,
+            &project_info.config.span_description_normalization_rules,
             extract_spans,
         );
 
@@ -1502,6 +1574,7 @@ impl EnvelopeProcessorService {
 
         let global_config = self.inner.global_config.current();
         let ai_model_costs = global_config.ai_model_costs.clone().ok();
+        let ai_model_cost_overrides = project_info.ai_model_cost_overrides(&global_config);
         let http_span_allowed_hosts = global_config.options.http_span_allowed_hosts.as_slice();
 
         let retention_days: i64 = project_info
@@ -1510,6 +1583,17 @@ impl EnvelopeProcessorService {
             .unwrap_or(DEFAULT_EVENT_RETENTION)
             .into();
 
+        // Rules discovered by the local clusterer apply after the rules from the project config.
+        let clusterer = self
+            .inner
+            .transaction_clusterers
+            .as_ref()
+            .map(|clusterers| clusterers.get(project_id));
+        let local_rules = match clusterer {
+            Some(ref clusterer) => clusterer.rules(),
+            None => Arc::new([]),
+        };
+
         utils::log_transaction_name_metrics(event, |event| {
             let event_validation_config = EventValidationConfig {
                 received_at: Some(managed_envelope.received_at()),
@@ -1559,6 +1643,7 @@ impl EnvelopeProcessorService {
                 normalize_user_agent: Some(true),
                 transaction_name_config: TransactionNameConfig {
                     rules: &project_info.config.tx_name_rules,
+                    local_rules: &local_rules,
                 },
                 device_class_synthesis_config: project_info
                     .has_feature(Feature::DeviceClassSynthesis),
@@ -1573,9 +1658,18 @@ impl EnvelopeProcessorService {
                 remove_other: full_normalization,
                 emit_event_errors: full_normalization,
                 span_description_rules: project_info.config.span_description_rules.as_ref(),
+                span_description_normalization_rules: &project_info
+                    .config
+                    .span_description_normalization_rules,
                 geoip_lookup: self.inner.geoip_lookup.as_ref(),
                 ai_model_costs: ai_model_costs.as_ref(),
+                ai_model_cost_overrides: &ai_model_cost_overrides,
                 enable_trimming: true,
+                trimming: project_info
+                    .config
+                    .trimming
+                    .as_ref()
+                    .map(|trimming| trimming as &dyn TrimmingPolicy),
                 measurements: Some(CombinedMeasurementsConfig::new(
                     project_info.config().measurements.as_ref(),
                     global_config.measurements.as_ref(),
@@ -1601,6 +1695,10 @@ impl EnvelopeProcessorService {
             })
         })?;
 
+        if let Some(clusterer) = clusterer {
+            clusterer.record(event);
+        }
+
         event_fully_normalized.0 |= full_normalization;
 
         Ok(event_fully_normalized)
@@ -1666,6 +1764,7 @@ impl EnvelopeProcessorService {
             managed_envelope,
             &mut event,
             &mut metrics,
+            &project_info,
             &self.inner.config,
         )?;
         event_fully_normalized = self.normalize_event(
@@ -1797,6 +1896,7 @@ impl EnvelopeProcessorService {
                 managed_envelope,
                 &mut event,
                 &mut metrics,
+                &project_info,
                 &self.inner.config,
             )?;
         });
@@ -1938,6 +2038,7 @@ impl EnvelopeProcessorService {
                     managed_envelope,
                     &event,
                     &global_config,
+                    &project_info.config,
                     config,
                     server_sample_rate,
                     event_metrics_extracted,
@@ -2122,6 +2223,7 @@ impl EnvelopeProcessorService {
             &config,
             &project_info,
             self.inner.geoip_lookup.as_ref(),
+            &mut extracted_metrics,
         )?;
 
         self.enforce_quotas(
@@ -2154,7 +2256,9 @@ impl EnvelopeProcessorService {
         .await?;
 
         if_processing!(self.inner.config, {
-            self.normalize_checkins(managed_envelope, _project_id);
+            self.normalize_checkins(managed_envelope, _project_id, &project_info);
+            let extracted_metrics = self.extract_checkin_metrics(managed_envelope, &project_info);
+            return Ok(Some(extracted_metrics));
         });
 
         Ok(None)
@@ -2165,8 +2269,29 @@ impl EnvelopeProcessorService {
         mut managed_envelope: ManagedEnvelope,
         ctx: processing::Context<'_>,
     ) -> Result<ProcessingResult, ProcessingError> {
-        nel::convert_to_logs(&mut managed_envelope);
-        self.process_logs(managed_envelope, ctx).await
+        let metrics = nel::convert_to_logs(
+            &mut managed_envelope,
+            &ctx.project_info.config.filter_settings,
+            self.inner.geoip_lookup.as_ref(),
+            ctx.project_info.has_feature(Feature::NelMetricsExtraction),
+        );
+
+        // All reports may have been filtered or invalid, in which case there are no logs left.
+        if managed_envelope.envelope().is_empty() {
+            let mut extracted_metrics = ProcessingExtractedMetrics::new();
+            extracted_metrics.extend_project_metrics(metrics, None);
+
+            return Ok(ProcessingResult::Envelope {
+                managed_envelope: managed_envelope.into_processed(),
+                extracted_metrics,
+            });
+        }
+
+        let mut result = self.process_logs(managed_envelope, ctx).await?;
+        if let ProcessingResult::Logs(ref mut output) = result {
+            output.metrics.extend_project_metrics(metrics, None);
+        }
+        Ok(result)
     }
 
     /// Process logs
@@ -2603,7 +2728,7 @@ impl EnvelopeProcessorService {
         cogs.update(relay_metrics::cogs::BySize(&buckets));
 
         let clock_drift_processor =
-            ClockDriftProcessor::new(sent_at, received_at).at_least(MINIMUM_CLOCK_DRIFT);
+            ClockDriftProcessor::new(sent_at, received_at).at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD);
 
         buckets.retain_mut(|bucket| {
             if let Err(error) = relay_metrics::normalize_bucket(bucket) {
@@ -2868,6 +2993,15 @@ impl EnvelopeProcessorService {
                 .await
             {
                 Ok(limits) => limits,
+                Err(RateLimitingError::Redis(err)) if err.is_circuit_open() => {
+                    // Redis is unavailable, accept the buckets without checking quotas.
+                    metric!(
+                        counter(RelayCounters::RedisDegraded) += 1,
+                        consumer = "rate_limits",
+                        behavior = "accept",
+                    );
+                    break;
+                }
                 Err(err) => {
                     relay_log::error!(
                         error = &err as &dyn std::error::Error,
@@ -2935,6 +3069,14 @@ impl EnvelopeProcessorService {
                             is_limited = limits.is_limited();
                             rate_limits.merge(limits)
                         }
+                        Err(RateLimitingError::Redis(e)) if e.is_circuit_open() => {
+                            // Redis is unavailable, accept the buckets without checking quotas.
+                            metric!(
+                                counter(RelayCounters::RedisDegraded) += 1,
+                                consumer = "rate_limits",
+                                behavior = "accept",
+                            );
+                        }
                         Err(e) => relay_log::error!(error = &e as &dyn Error),
                     }
                 }
@@ -3004,6 +3146,17 @@ impl EnvelopeProcessorService {
             .await
         {
             Ok(limits) => limits,
+            Err((buckets, relay_cardinality::Error::RedisError(error)))
+                if error.is_circuit_open() =>
+            {
+                // Redis is unavailable, accept the buckets without checking cardinality.
+                metric!(
+                    counter(RelayCounters::RedisDegraded) += 1,
+                    consumer = "cardinality_limits",
+                    behavior = "accept",
+                );
+                return buckets;
+            }
             Err((buckets, error)) => {
                 relay_log::error!(
                     error = &error as &dyn std::error::Error,
@@ -3370,7 +3523,6 @@ impl Service for EnvelopeProcessorService {
 /// within the [`Annotated`].
 struct EnforcementResult {
     event: Annotated<Event>,
-    #[cfg_attr(not(feature = "processing"), expect(dead_code))]
     rate_limits: RateLimits,
 }
 
@@ -3381,11 +3533,42 @@ impl EnforcementResult {
     }
 }
 
+/// Creates the cardinality limiter with the backend configured for this Relay.
+///
+/// Returns `None` if the configured backend requires Redis, but no Redis is configured.
+#[cfg(feature = "processing")]
+fn create_cardinality_limiter(
+    config: &Config,
+    redis: Option<AsyncRedisClient>,
+) -> Option<CardinalityLimiter> {
+    let vacuum_interval = config.cardinality_limiter_cache_vacuum_interval();
+
+    let storage = match config.cardinality_limiter_backend() {
+        CardinalityLimiterBackend::RedisSet => RedisStorage::Set,
+        CardinalityLimiterBackend::RedisHyperloglog => RedisStorage::HyperLogLog,
+        CardinalityLimiterBackend::Memory => {
+            let limiter = MemoryLimiter::new(MemoryLimiterOptions { vacuum_interval });
+            return Some(CardinalityLimiter::new(CardinalityBackend::Memory(limiter)));
+        }
+    };
+
+    let limiter = RedisSetLimiter::new(
+        RedisSetLimiterOptions {
+            cache_vacuum_interval: vacuum_interval,
+            storage,
+        },
+        redis?,
+    );
+
+    Some(CardinalityLimiter::new(CardinalityBackend::Redis(limiter)))
+}
+
 #[derive(Clone)]
 enum RateLimiter {
     Cached,
     #[cfg(feature = "processing")]
     Consistent(Arc<RedisRateLimiter<GlobalRateLimitsServiceHandle>>),
+    Local(MemoryRateLimiter),
 }
 
 impl RateLimiter {
@@ -3424,12 +3607,25 @@ impl RateLimiter {
                 async move {
                     match this {
                         #[cfg(feature = "processing")]
-                        RateLimiter::Consistent(rate_limiter) => Ok::<_, ProcessingError>(
-                            rate_limiter
-                                .is_rate_limited(quotas, item_scope, _quantity, false)
-                                .await?,
+                        RateLimiter::Consistent(rate_limiter) => match rate_limiter
+                            .is_rate_limited(quotas, item_scope, _quantity, false)
+                            .await
+                        {
+                            // Redis is unavailable, enforce the rate limits cached in the project.
+                            Err(RateLimitingError::Redis(error)) if error.is_circuit_open() => {
+                                metric!(
+                                    counter(RelayCounters::RedisDegraded) += 1,
+                                    consumer = "rate_limits",
+                                    behavior = "cached",
+                                );
+                                Ok(rate_limits_clone.check_with_quotas(quotas, item_scope))
+                            }
+                            result => Ok(result?),
+                        },
+                        RateLimiter::Local(rate_limiter) => Ok::<_, ProcessingError>(
+                            rate_limiter.is_rate_limited(quotas, item_scope, _quantity, false),
                         ),
-                        _ => Ok::<_, ProcessingError>(
+                        RateLimiter::Cached => Ok::<_, ProcessingError>(
                             rate_limits_clone.check_with_quotas(quotas, item_scope),
                         ),
                     }
@@ -3985,6 +4181,66 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_local_quotas() {
+        let config = Config::from_json_value(serde_json::json!({
+            "local_quotas": {
+                "enabled": true,
+            }
+        }))
+        .unwrap();
+        let processor = create_test_processor(config).await;
+        let (outcome_aggregator, test_store) = testutils::processor_services();
+
+        let mut project_config = ProjectConfig::default();
+        project_config.quotas.push(Quota {
+            id: Some("local".into()),
+            categories: smallvec::smallvec![DataCategory::Error],
+            scope: relay_quotas::QuotaScope::Organization,
+            scope_id: None,
+            limit: Some(1),
+            window: Some(60),
+            reason_code: Some(relay_quotas::ReasonCode::new("local")),
+            namespace: None,
+        });
+        let project_info = Arc::new(ProjectInfo {
+            config: project_config,
+            ..Default::default()
+        });
+
+        let process = async || {
+            let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+                .parse()
+                .unwrap();
+            let mut envelope = Envelope::from_request(None, RequestMeta::new(dsn));
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Event);
+                item.set_payload(ContentType::Json, r#"{"message": "hello"}"#);
+                item
+            });
+
+            let (group, envelope) = ProcessingGroup::split_envelope(*envelope).pop().unwrap();
+            let message = ProcessEnvelopeGrouped {
+                group,
+                envelope: ManagedEnvelope::new(
+                    envelope,
+                    outcome_aggregator.clone(),
+                    test_store.clone(),
+                ),
+                project_info: project_info.clone(),
+                rate_limits: Default::default(),
+                sampling_project_info: None,
+                reservoir_counters: ReservoirCounters::default(),
+            };
+
+            processor.process(&mut Token::noop(), message).await
+        };
+
+        assert!(matches!(process().await, Ok(Some(Submit::Envelope(_)))));
+        // The second event exceeds the quota of this Relay.
+        assert!(matches!(process().await, Ok(None)));
+    }
+
     #[tokio::test]
     async fn test_browser_version_extraction_with_pii_like_data() {
         let processor = create_test_processor(Default::default()).await;
@@ -4211,6 +4467,7 @@ mod tests {
                                 substitution: "*".to_owned(),
                             },
                         }],
+                        ..Default::default()
                     },
                     ..Default::default()
                 };
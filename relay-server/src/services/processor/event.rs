@@ -8,7 +8,9 @@ use relay_auth::RelayVersion;
 use relay_base_schema::events::EventType;
 use relay_config::Config;
 use relay_dynamic_config::GlobalConfig;
-use relay_event_normalization::ClockDriftProcessor;
+use relay_event_normalization::{
+    ClockDriftItemType, ClockDriftProcessor, DEFAULT_CLOCK_DRIFT_THRESHOLD,
+};
 use relay_event_schema::processor::{self, ProcessingState};
 use relay_event_schema::protocol::{
     Breadcrumb, Csp, Event, ExpectCt, ExpectStaple, Hpkp, LenientString, Metrics, OtelContext,
@@ -24,8 +26,8 @@ use crate::envelope::{AttachmentType, ContentType, Envelope, Item, ItemType};
 use crate::extractors::RequestMeta;
 use crate::services::outcome::Outcome;
 use crate::services::processor::{
-    EventFullyNormalized, EventMetricsExtracted, EventProcessing, ExtractedEvent,
-    MINIMUM_CLOCK_DRIFT, ProcessingError, SpansExtracted, event_category, event_type,
+    EventFullyNormalized, EventMetricsExtracted, EventProcessing, ExtractedEvent, ProcessingError,
+    SpansExtracted, event_category, event_type,
 };
 use crate::services::projects::project::ProjectInfo;
 use crate::statsd::{PlatformTag, RelayCounters, RelayHistograms, RelayTimers};
@@ -150,6 +152,7 @@ pub fn finalize<Group: EventProcessing>(
     managed_envelope: &mut TypedEnvelope<Group>,
     event: &mut Annotated<Event>,
     metrics: &mut Metrics,
+    project_info: &ProjectInfo,
     config: &Config,
 ) -> Result<(), ProcessingError> {
     let envelope = managed_envelope.envelope_mut();
@@ -250,7 +253,11 @@ pub fn finalize<Group: EventProcessing>(
 
     let mut processor =
         ClockDriftProcessor::new(envelope.sent_at(), managed_envelope.received_at())
-            .at_least(MINIMUM_CLOCK_DRIFT);
+            .at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD)
+            .policy(
+                project_info.config.clock_drift.as_ref(),
+                ClockDriftItemType::Event,
+            );
     processor::process_value(event, &mut processor, ProcessingState::root())
         .map_err(|_| ProcessingError::InvalidTransaction)?;
 
@@ -384,6 +391,9 @@ pub fn serialize<Group: EventProcessing>(
     let event_type = event_type(event).unwrap_or_default();
     let mut event_item = Item::new(ItemType::from_event_type(event_type));
     event_item.set_payload(ContentType::Json, data);
+    if let Some(platform) = event.value().and_then(|event| event.platform.as_str()) {
+        event_item.set_platform(platform.to_owned());
+    }
 
     // TODO: The state should simply maintain & update an `ItemHeaders` object.
     // If transaction metrics were extracted, set the corresponding item header
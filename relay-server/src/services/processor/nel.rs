@@ -1,22 +1,44 @@
-use relay_event_normalization::nel;
-use relay_event_schema::protocol::{NetworkReportError, OurLog};
+use std::net::IpAddr;
+
+use relay_common::time::UnixTimestamp;
+use relay_event_normalization::{GeoIpLookup, nel};
+use relay_event_schema::protocol::{Geo, NetworkReportError, NetworkReportRaw};
+use relay_filter::{FilterStatKey, ProjectFiltersConfig};
+use relay_metrics::Bucket;
 use relay_protocol::Annotated;
+use relay_quotas::DataCategory;
 
 use crate::envelope::{ContainerItems, Item, ItemContainer, ItemType};
 use crate::extractors::RequestMeta;
+use crate::metrics_extraction;
+use crate::services::outcome::Outcome;
 use crate::services::processor::ProcessingError;
 use crate::utils::ManagedEnvelope;
 
-pub fn convert_to_logs(envelope: &mut ManagedEnvelope) {
+/// Converts all NEL reports in the envelope into logs.
+///
+/// Reports matching the client IP or localhost inbound filters are dropped with a filtered outcome
+/// for the logs they would have been converted into. The reports are
+/// enriched with the location of the client, and if `extract_metrics` is set, metrics are extracted
+/// from all remaining reports and returned.
+pub fn convert_to_logs(
+    envelope: &mut ManagedEnvelope,
+    filter_settings: &ProjectFiltersConfig,
+    geoip_lookup: Option<&GeoIpLookup>,
+    extract_metrics: bool,
+) -> Vec<Bucket> {
     let items = envelope
         .envelope_mut()
         .take_items_by(|item| item.ty() == &ItemType::Nel);
     let mut logs = ContainerItems::new();
+    let mut metrics = Vec::new();
+
+    let client_ip = envelope.meta().client_addr();
+    let client_geo = client_ip.and_then(|ip| lookup_geo(ip, geoip_lookup?));
 
     for item in items {
-        match log_from_nel_item(&item, envelope.meta()) {
-            Ok(Some(log)) => logs.push(log),
-            Ok(None) => {}
+        let report = match parse_nel_item(&item) {
+            Ok(report) => report,
             Err(error) => {
                 let mut payload = item.payload();
                 relay_log::with_scope(
@@ -36,32 +58,203 @@ pub fn convert_to_logs(envelope: &mut ManagedEnvelope) {
                         )
                     },
                 );
+                continue;
             }
         };
+
+        if let Some(report) = report.value() {
+            if let Err(filter_stat_key) = inbound_filter(report, client_ip, filter_settings) {
+                relay_log::trace!(
+                    filter = %filter_stat_key,
+                    "filtering NEL report that matched an inbound filter"
+                );
+                let outcome = Outcome::Filtered(filter_stat_key);
+                envelope.track_outcome(outcome.clone(), DataCategory::LogItem, 1);
+                envelope.track_outcome(outcome, DataCategory::LogByte, item.len().max(1));
+                continue;
+            }
+
+            if extract_metrics {
+                let timestamp = report_timestamp(report, envelope.meta());
+                metrics.extend(metrics_extraction::nel::extract_metrics(
+                    report,
+                    client_geo.as_ref(),
+                    timestamp,
+                ));
+            }
+        }
+
+        if let Some(log) =
+            nel::create_log(report, envelope.meta().received_at(), client_geo.as_ref())
+        {
+            logs.push(Annotated::new(log));
+        }
     }
 
     if logs.is_empty() {
-        return;
+        return metrics;
     }
 
     let mut item = Item::new(ItemType::Log);
     if let Ok(()) = ItemContainer::from(logs).write_to(&mut item) {
         envelope.envelope_mut().add_item(item);
     }
+
+    metrics
+}
+
+/// Applies the inbound filters that are supported for NEL reports.
+fn inbound_filter(
+    report: &NetworkReportRaw,
+    client_ip: Option<IpAddr>,
+    filter_settings: &ProjectFiltersConfig,
+) -> Result<(), FilterStatKey> {
+    relay_filter::client_ips::should_filter(client_ip, &filter_settings.client_ips)?;
+    relay_filter::localhost::should_filter(report, &filter_settings.localhost)?;
+    relay_filter::localhost::should_filter_server_ip(report, &filter_settings.localhost)?;
+    Ok(())
+}
+
+fn lookup_geo(client_ip: IpAddr, geoip_lookup: &GeoIpLookup) -> Option<Geo> {
+    geoip_lookup.lookup(&client_ip.to_string()).ok().flatten()
+}
+
+/// Returns the time at which the report was collected by the browser.
+fn report_timestamp(report: &NetworkReportRaw, meta: &RequestMeta) -> UnixTimestamp {
+    let age = chrono::Duration::milliseconds(*report.age.value().unwrap_or(&0));
+    let received_at = meta.received_at();
+    let collected_at = received_at.checked_sub_signed(age).unwrap_or(received_at);
+    UnixTimestamp::from_datetime(collected_at).unwrap_or_else(UnixTimestamp::now)
 }
 
-fn log_from_nel_item(
-    item: &Item,
-    meta: &RequestMeta,
-) -> Result<Option<Annotated<OurLog>>, ProcessingError> {
+fn parse_nel_item(item: &Item) -> Result<Annotated<NetworkReportRaw>, ProcessingError> {
     let payload = item.payload();
 
-    let report = Annotated::from_json_bytes(&payload)
+    Annotated::<NetworkReportRaw>::from_json_bytes(&payload)
         .map_err(NetworkReportError::InvalidJson)
-        .map_err(ProcessingError::InvalidNelReport)?;
-    let Some(log) = nel::create_log(report, meta.received_at()) else {
-        return Ok(None);
-    };
+        .map_err(ProcessingError::InvalidNelReport)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use relay_filter::FilterConfig;
+    use relay_system::Addr;
+
+    use crate::envelope::Envelope;
+    use crate::services::outcome::TrackOutcome;
+
+    use super::*;
+
+    fn envelope() -> ManagedEnvelope {
+        envelope_with_outcomes(Addr::dummy())
+    }
+
+    fn envelope_with_outcomes(outcome_aggregator: Addr<TrackOutcome>) -> ManagedEnvelope {
+        let bytes = Bytes::from(
+            r#"{"dsn":"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42","forwarded_for":"2.125.160.216"}
+{"type":"nel"}
+{"age":500,"type":"network-error","url":"https://example.com/","body":{"elapsed_time":120,"phase":"dns","type":"dns.name_not_resolved"}}
+{"type":"nel"}
+{"age":500,"type":"network-error","url":"http://localhost:3000/","body":{"elapsed_time":80,"phase":"connection","type":"tcp.refused"}}
+"#,
+        );
+
+        let envelope = Envelope::parse_bytes(bytes).unwrap();
+        ManagedEnvelope::new(envelope, outcome_aggregator, Addr::dummy())
+    }
 
-    Ok(Some(Annotated::new(log)))
+    #[test]
+    fn test_convert_to_logs_with_metrics() {
+        let mut envelope = envelope();
+        let geoip_lookup = GeoIpLookup::open(
+            "../relay-event-normalization/tests/fixtures/GeoIP2-Enterprise-Test.mmdb",
+        )
+        .unwrap();
+
+        let filter_settings = ProjectFiltersConfig {
+            localhost: FilterConfig { is_enabled: true },
+            ..Default::default()
+        };
+
+        let metrics = convert_to_logs(&mut envelope, &filter_settings, Some(&geoip_lookup), true);
+
+        // The report from localhost is filtered.
+        let names: Vec<_> = metrics.iter().map(|bucket| bucket.name.as_ref()).collect();
+        assert_eq!(
+            names,
+            [
+                "c:spans/nel.reports@none",
+                "d:spans/nel.elapsed_time@millisecond"
+            ]
+        );
+        assert_eq!(metrics[0].tags["nel.type"], "dns.name_not_resolved");
+        assert_eq!(metrics[0].tags["user.geo.country_code"], "GB");
+
+        let items: Vec<_> = envelope.envelope().items().collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].ty(), &ItemType::Log);
+        assert_eq!(items[0].item_count(), Some(1));
+    }
+
+    #[test]
+    fn test_convert_to_logs_without_metrics() {
+        let mut envelope = envelope();
+
+        let metrics = convert_to_logs(&mut envelope, &ProjectFiltersConfig::default(), None, false);
+
+        assert!(metrics.is_empty());
+        let items: Vec<_> = envelope.envelope().items().collect();
+        assert_eq!(items[0].item_count(), Some(2));
+    }
+
+    #[test]
+    fn test_inbound_filter_server_ip() {
+        let report = Annotated::<NetworkReportRaw>::from_json(
+            r#"{"age":500,"type":"network-error","url":"https://example.com/","body":{"elapsed_time":80,"phase":"connection","server_ip":"127.0.0.1","type":"tcp.refused"}}"#,
+        )
+        .unwrap();
+        let report = report.value().unwrap();
+
+        let filter_settings = ProjectFiltersConfig {
+            localhost: FilterConfig { is_enabled: true },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            inbound_filter(report, None, &filter_settings),
+            Err(FilterStatKey::Localhost)
+        );
+        assert_eq!(
+            inbound_filter(report, None, &ProjectFiltersConfig::default()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_convert_to_logs_filtered_outcome() {
+        let (outcome_aggregator, mut outcomes) = Addr::custom();
+        let mut envelope = envelope_with_outcomes(outcome_aggregator);
+
+        let filter_settings = ProjectFiltersConfig {
+            localhost: FilterConfig { is_enabled: true },
+            ..Default::default()
+        };
+
+        convert_to_logs(&mut envelope, &filter_settings, None, false);
+
+        let outcome = outcomes.try_recv().unwrap();
+        assert_eq!(outcome.outcome, Outcome::Filtered(FilterStatKey::Localhost));
+        assert_eq!(
+            (outcome.category, outcome.quantity),
+            (DataCategory::LogItem, 1)
+        );
+
+        let outcome = outcomes.try_recv().unwrap();
+        assert_eq!(outcome.outcome, Outcome::Filtered(FilterStatKey::Localhost));
+        assert_eq!(outcome.category, DataCategory::LogByte);
+
+        assert!(outcomes.try_recv().is_err());
+        envelope.accept();
+    }
 }
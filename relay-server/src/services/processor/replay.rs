@@ -3,8 +3,11 @@ use std::error::Error;
 use std::net::IpAddr;
 
 use crate::envelope::{ContentType, ItemType};
+use crate::metrics_extraction::generic;
 use crate::services::outcome::DiscardReason;
-use crate::services::processor::{ProcessingError, ReplayGroup, should_filter};
+use crate::services::processor::{
+    ProcessingError, ProcessingExtractedMetrics, ReplayGroup, should_filter,
+};
 use crate::services::projects::project::ProjectInfo;
 use crate::statsd::{RelayCounters, RelayTimers};
 use crate::utils::{TypedEnvelope, sample};
@@ -12,7 +15,7 @@ use bytes::Bytes;
 use relay_base_schema::organization::OrganizationId;
 use relay_base_schema::project::ProjectId;
 use relay_config::Config;
-use relay_dynamic_config::{Feature, GlobalConfig, ProjectConfig};
+use relay_dynamic_config::{CombinedMetricExtractionConfig, Feature, GlobalConfig, ProjectConfig};
 use relay_event_normalization::replay::{self, ReplayError};
 use relay_event_normalization::{GeoIpLookup, RawUserAgentInfo};
 use relay_event_schema::processor::{self, ProcessingState};
@@ -30,6 +33,7 @@ pub fn process(
     config: &Config,
     project_info: &ProjectInfo,
     geoip_lookup: Option<&GeoIpLookup>,
+    extracted_metrics: &mut ProcessingExtractedMetrics,
 ) -> Result<(), ProcessingError> {
     // If the replay feature is not enabled drop the items silently.
     if should_filter(config, project_info, Feature::SessionReplay) {
@@ -53,6 +57,10 @@ pub fn process(
             config: &project_info.config,
             global_config,
             geoip_lookup,
+            metric_extraction: match config.processing_enabled() {
+                true => generic::combined_config(global_config, &project_info.config),
+                false => None,
+            },
             event_id: managed_envelope.envelope().event_id(),
             project_id: project_info.project_id,
             organization_id: project_info.organization_id,
@@ -84,7 +92,8 @@ pub fn process(
     for item in managed_envelope.envelope_mut().items_mut() {
         match item.ty() {
             ItemType::ReplayEvent => {
-                let replay_event = handle_replay_event_item(item.payload(), &rpc)?;
+                let replay_event =
+                    handle_replay_event_item(item.payload(), &rpc, extracted_metrics)?;
                 item.set_payload(ContentType::Json, replay_event);
             }
             ItemType::ReplayRecording => {
@@ -93,8 +102,12 @@ pub fn process(
                 item.set_payload(ContentType::OctetStream, replay_recording);
             }
             ItemType::ReplayVideo => {
-                let replay_video =
-                    handle_replay_video_item(item.payload(), scrubber.as_mut(), &rpc)?;
+                let replay_video = handle_replay_video_item(
+                    item.payload(),
+                    scrubber.as_mut(),
+                    &rpc,
+                    extracted_metrics,
+                )?;
                 item.set_payload(ContentType::OctetStream, replay_video);
             }
             _ => {}
@@ -109,6 +122,7 @@ struct ReplayProcessingConfig<'a> {
     pub config: &'a ProjectConfig,
     pub global_config: &'a GlobalConfig,
     pub geoip_lookup: Option<&'a GeoIpLookup>,
+    pub metric_extraction: Option<CombinedMetricExtractionConfig<'a>>,
     pub event_id: Option<EventId>,
     pub project_id: Option<ProjectId>,
     pub organization_id: Option<OrganizationId>,
@@ -121,6 +135,7 @@ struct ReplayProcessingConfig<'a> {
 fn handle_replay_event_item(
     payload: Bytes,
     config: &ReplayProcessingConfig<'_>,
+    extracted_metrics: &mut ProcessingExtractedMetrics,
 ) -> Result<Bytes, ProcessingError> {
     match process_replay_event(&payload, config) {
         Ok(replay) => {
@@ -133,6 +148,11 @@ fn handle_replay_event_item(
                 )
                 .map_err(ProcessingError::ReplayFiltered)?;
 
+                if let Some(metric_extraction) = config.metric_extraction {
+                    let metrics = generic::extract_metrics(replay_type, metric_extraction);
+                    extracted_metrics.extend_project_metrics(metrics, None);
+                }
+
                 // Log segments that exceed the hour limit so we can diagnose errant SDKs
                 // or exotic customer implementations.
                 if let Some(segment_id) = replay_type.segment_id.value() {
@@ -320,6 +340,7 @@ fn handle_replay_video_item(
     payload: Bytes,
     scrubber: Option<&mut RecordingScrubber>,
     config: &ReplayProcessingConfig<'_>,
+    extracted_metrics: &mut ProcessingExtractedMetrics,
 ) -> Result<Bytes, ProcessingError> {
     let ReplayVideoEvent {
         replay_event,
@@ -329,7 +350,7 @@ fn handle_replay_video_item(
         .map_err(|_| ProcessingError::InvalidReplay(DiscardReason::InvalidReplayVideoEvent))?;
 
     // Process as a replay-event envelope item.
-    let replay_event = handle_replay_event_item(replay_event, config)?;
+    let replay_event = handle_replay_event_item(replay_event, config, extracted_metrics)?;
 
     // Process as a replay-recording envelope item.
     let replay_recording = handle_replay_recording_item(replay_recording, scrubber, config)?;
@@ -6,7 +6,7 @@ use std::error::Error;
 use chrono::{Duration as SignedDuration, Utc};
 use relay_common::time::UnixTimestamp;
 use relay_config::Config;
-use relay_event_normalization::ClockDriftProcessor;
+use relay_event_normalization::{ClockDriftProcessor, DEFAULT_CLOCK_DRIFT_THRESHOLD};
 use relay_event_schema::protocol::{ClientReport, UserReport};
 use relay_filter::FilterStatKey;
 use relay_quotas::ReasonCode;
@@ -16,7 +16,7 @@ use relay_system::Addr;
 use crate::constants::DEFAULT_EVENT_RETENTION;
 use crate::envelope::{ContentType, ItemType};
 use crate::services::outcome::{DiscardReason, Outcome, RuleCategories, TrackOutcome};
-use crate::services::processor::{ClientReportGroup, MINIMUM_CLOCK_DRIFT};
+use crate::services::processor::ClientReportGroup;
 use crate::services::projects::project::ProjectInfo;
 use crate::utils::{ItemAction, TypedEnvelope};
 
@@ -66,7 +66,7 @@ pub fn process_client_reports(
 
     let clock_drift_processor =
         ClockDriftProcessor::new(managed_envelope.envelope().sent_at(), received)
-            .at_least(MINIMUM_CLOCK_DRIFT);
+            .at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD);
 
     // we're going through all client reports but we're effectively just merging
     // them into the first one.
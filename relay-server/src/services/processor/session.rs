@@ -6,7 +6,9 @@ use std::net;
 use chrono::{DateTime, Duration as SignedDuration, Utc};
 use relay_config::Config;
 use relay_dynamic_config::{GlobalConfig, SessionMetricsConfig};
-use relay_event_normalization::ClockDriftProcessor;
+use relay_event_normalization::{
+    ClockDriftItemType, ClockDriftProcessor, DEFAULT_CLOCK_DRIFT_THRESHOLD,
+};
 use relay_event_schema::protocol::{
     IpAddr, SessionAggregates, SessionAttributes, SessionStatus, SessionUpdate,
 };
@@ -15,7 +17,7 @@ use relay_metrics::Bucket;
 use relay_statsd::metric;
 
 use crate::envelope::{ContentType, Item, ItemType};
-use crate::services::processor::{MINIMUM_CLOCK_DRIFT, ProcessingExtractedMetrics, SessionGroup};
+use crate::services::processor::{ProcessingExtractedMetrics, SessionGroup};
 use crate::services::projects::project::ProjectInfo;
 use crate::statsd::RelayTimers;
 use crate::utils::{ItemAction, TypedEnvelope};
@@ -48,8 +50,12 @@ pub fn process(
     let client = envelope.meta().client().map(|x| x.to_owned());
     let client_addr = envelope.meta().client_addr();
 
-    let clock_drift_processor =
-        ClockDriftProcessor::new(envelope.sent_at(), received).at_least(MINIMUM_CLOCK_DRIFT);
+    let clock_drift_processor = ClockDriftProcessor::new(envelope.sent_at(), received)
+        .at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD)
+        .policy(
+            project_info.config().clock_drift.as_ref(),
+            ClockDriftItemType::Session,
+        );
 
     let spc = SessionProcessingConfig {
         global_config,
@@ -185,11 +191,15 @@ fn process_session(
         return false;
     };
 
-    if clock_drift_processor.is_drifted() {
+    if clock_drift_processor.is_correcting() {
         relay_log::trace!("applying clock drift correction to session");
         clock_drift_processor.process_datetime(&mut session.started);
         clock_drift_processor.process_datetime(&mut session.timestamp);
         changed = true;
+    } else if let Some(drift) = clock_drift_processor.drift() {
+        relay_log::trace!("annotating clock drift on session");
+        session.attributes.clock_drift = Some(drift.num_milliseconds() as f64 / 1000.0);
+        changed = true;
     }
 
     if session.timestamp < session.started {
@@ -312,12 +322,16 @@ fn process_session_aggregates(
         }
     };
 
-    if clock_drift_processor.is_drifted() {
+    if clock_drift_processor.is_correcting() {
         relay_log::trace!("applying clock drift correction to session");
         for aggregate in &mut session.aggregates {
             clock_drift_processor.process_datetime(&mut aggregate.started);
         }
         changed = true;
+    } else if let Some(drift) = clock_drift_processor.drift() {
+        relay_log::trace!("annotating clock drift on session");
+        session.attributes.clock_drift = Some(drift.num_milliseconds() as f64 / 1000.0);
+        changed = true;
     }
 
     // Validate timestamps
@@ -392,6 +406,8 @@ fn process_session_aggregates(
 mod tests {
     use std::str::FromStr;
 
+    use relay_event_normalization::{ClockDriftConfig, ClockDriftMode};
+
     use super::*;
 
     struct TestProcessSessionArguments<'a> {
@@ -506,4 +522,22 @@ mod tests {
         args.item.set_metrics_extracted(true);
         assert!(!args.run_session_producer());
     }
+
+    #[test]
+    fn test_process_session_annotate_clock_drift() {
+        let mut args = TestProcessSessionArguments::default();
+        let config = ClockDriftConfig {
+            mode: ClockDriftMode::Annotate,
+            ..Default::default()
+        };
+        let sent_at = args.received - SignedDuration::hours(2);
+        args.clock_drift_processor = ClockDriftProcessor::new(Some(sent_at), args.received)
+            .at_least(DEFAULT_CLOCK_DRIFT_THRESHOLD)
+            .policy(Some(&config), ClockDriftItemType::Session);
+        assert!(args.run_session_producer());
+
+        let session = SessionUpdate::parse(&args.item.payload()).unwrap();
+        assert_eq!(session.timestamp, args.received);
+        assert_eq!(session.attributes.clock_drift, Some(7200.0));
+    }
 }
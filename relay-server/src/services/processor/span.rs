@@ -6,6 +6,7 @@ use opentelemetry_proto::tonic::common::v1::any_value::Value;
 use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
 use prost::Message;
 use relay_dynamic_config::Feature;
+use relay_event_normalization::SpanDescriptionNormalizationRule;
 use relay_event_normalization::span::tag_extraction;
 use relay_event_schema::protocol::{Event, Span, SpanV2};
 use relay_protocol::Annotated;
@@ -221,10 +222,17 @@ pub fn extract_transaction_span(
     event: &Event,
     max_tag_value_size: usize,
     span_allowed_hosts: &[String],
+    description_rules: &[SpanDescriptionNormalizationRule],
 ) -> Option<Span> {
     let mut spans = [Span::from(event).into()];
 
-    tag_extraction::extract_span_tags(event, &mut spans, max_tag_value_size, span_allowed_hosts);
+    tag_extraction::extract_span_tags(
+        event,
+        &mut spans,
+        max_tag_value_size,
+        span_allowed_hosts,
+        description_rules,
+    );
     tag_extraction::extract_segment_span_tags(event, &mut spans);
 
     spans.into_iter().next().and_then(Annotated::into_value)
@@ -1,5 +1,6 @@
 //! Contains the processing-only functionality.
 
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::sync::Arc;
 
@@ -21,16 +22,21 @@ use relay_dynamic_config::{
     CombinedMetricExtractionConfig, ErrorBoundary, Feature, GlobalConfig, ProjectConfig,
 };
 use relay_event_normalization::span::ai::{extract_ai_data, map_ai_measurements_to_data};
+use relay_event_normalization::span::critical_path::mark_critical_path;
+use relay_event_normalization::span::exclusive_time::compute_segment_exclusive_time;
+use relay_event_normalization::span::reparent_broken_spans::reparent_spans;
 use relay_event_normalization::{
-    BorrowedSpanOpDefaults, ClientHints, CombinedMeasurementsConfig, FromUserAgentInfo,
-    GeoIpLookup, MeasurementsConfig, ModelCosts, PerformanceScoreConfig, RawUserAgentInfo,
-    SchemaProcessor, TimestampProcessor, TransactionNameRule, TransactionsProcessor,
-    TrimmingProcessor, normalize_measurements, normalize_performance_score,
-    normalize_transaction_name, span::tag_extraction, validate_span,
+    BorrowedSpanOpDefaults, ClientHints, ClockDriftConfig, ClockDriftItemType, ClockDriftProcessor,
+    CombinedMeasurementsConfig, FromUserAgentInfo, GeoIpLookup, MeasurementsConfig, ModelCosts,
+    PerformanceScoreConfig, RawUserAgentInfo, SchemaProcessor, SpanDescriptionNormalizationRule,
+    TimestampProcessor, TransactionNameRule, TransactionsProcessor, TrimmingProcessor,
+    normalize_measurements, normalize_performance_score, normalize_transaction_name,
+    span::tag_extraction, validate_span,
 };
 use relay_event_schema::processor::{ProcessingAction, ProcessingState, process_value};
 use relay_event_schema::protocol::{
-    BrowserContext, Event, EventId, IpAddr, Measurement, Measurements, Span, SpanData,
+    BrowserContext, Event, EventId, IpAddr, Measurement, Measurements, OsContext, Span, SpanData,
+    SpanId,
 };
 use relay_log::protocol::{Attachment, AttachmentType};
 use relay_metrics::{FractionUnit, MetricNamespace, MetricUnit, UnixTimestamp};
@@ -79,7 +85,7 @@ pub async fn process(
     let normalize_span_config = NormalizeSpanConfig::new(
         &config,
         global_config,
-        project_info.config(),
+        &project_info,
         managed_envelope,
         managed_envelope
             .envelope()
@@ -93,30 +99,17 @@ pub async fn process(
     let filter_settings = &project_info.config.filter_settings;
     let sampling_decision = sampling_result.decision();
 
+    let mut spans = parse_spans(managed_envelope);
+    normalize_segments(&mut spans);
+    let mut spans = spans.into_iter();
+
     let mut span_count = 0;
     managed_envelope.retain_items(|item| {
         let mut annotated_span = match item.ty() {
-            ItemType::OtelSpan => match serde_json::from_slice::<OtelSpan>(&item.payload()) {
-                Ok(otel_span) => match relay_spans::otel_to_sentry_span(otel_span) {
-                    Ok(span) => Annotated::new(span),
-                    Err(err) => {
-                        relay_log::debug!("failed to convert OTel span to Sentry span: {:?}", err);
-                        return ItemAction::Drop(Outcome::Invalid(DiscardReason::InvalidJson));
-                    }
-                },
-                Err(err) => {
-                    relay_log::debug!("failed to parse OTel span: {}", err);
-                    return ItemAction::Drop(Outcome::Invalid(DiscardReason::InvalidJson));
-                }
-            },
-            ItemType::Span => match Annotated::<Span>::from_json_bytes(&item.payload()) {
-                Ok(span) => span,
-                Err(err) => {
-                    relay_log::debug!("failed to parse span: {}", err);
-                    return ItemAction::Drop(Outcome::Invalid(DiscardReason::InvalidJson));
-                }
+            ItemType::OtelSpan | ItemType::Span => match spans.next().flatten() {
+                Some(span) => span,
+                None => return ItemAction::Drop(Outcome::Invalid(DiscardReason::InvalidJson)),
             },
-
             _ => return ItemAction::Keep,
         };
 
@@ -257,6 +250,98 @@ pub async fn process(
     }
 }
 
+/// Parses all span items in the envelope in order of their appearance.
+///
+/// Returns `None` for items that cannot be parsed or converted from OTel.
+fn parse_spans(managed_envelope: &TypedEnvelope<SpanGroup>) -> Vec<Option<Annotated<Span>>> {
+    managed_envelope
+        .envelope()
+        .items()
+        .filter_map(|item| match item.ty() {
+            ItemType::OtelSpan => Some(parse_otel_span(item)),
+            ItemType::Span => Some(match Annotated::<Span>::from_json_bytes(&item.payload()) {
+                Ok(span) => Some(span),
+                Err(err) => {
+                    relay_log::debug!("failed to parse span: {}", err);
+                    None
+                }
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_otel_span(item: &Item) -> Option<Annotated<Span>> {
+    let otel_span = match serde_json::from_slice::<OtelSpan>(&item.payload()) {
+        Ok(otel_span) => otel_span,
+        Err(err) => {
+            relay_log::debug!("failed to parse OTel span: {}", err);
+            return None;
+        }
+    };
+
+    match relay_spans::otel_to_sentry_span(otel_span) {
+        Ok(span) => Some(Annotated::new(span)),
+        Err(err) => {
+            relay_log::debug!("failed to convert OTel span to Sentry span: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Reconstructs the span trees of all segments contained in the envelope.
+///
+/// Spans are grouped by their segment. If the segment span is part of the envelope, spans with
+/// broken parent links are attached to it and the critical path of the segment is marked.
+/// Segments may be split across multiple envelopes, so exclusive times are computed from the
+/// children present in this envelope and never overwrite exclusive times sent by the SDK.
+///
+/// Spans that failed to parse are skipped.
+fn normalize_segments(spans: &mut [Option<Annotated<Span>>]) {
+    let mut segments = BTreeMap::<SpanId, Vec<usize>>::new();
+
+    for (index, annotated_span) in spans.iter().enumerate() {
+        let segment_id =
+            annotated_span
+                .as_ref()
+                .and_then(Annotated::value)
+                .and_then(
+                    |span| match (span.segment_id.value(), span.parent_span_id.value()) {
+                        (Some(segment_id), _) => Some(*segment_id),
+                        (None, None) => span.span_id.value().copied(),
+                        (None, Some(_)) => None,
+                    },
+                );
+
+        if let Some(segment_id) = segment_id {
+            segments.entry(segment_id).or_default().push(index);
+        }
+    }
+
+    for (segment_id, indices) in segments {
+        let mut segment: Vec<_> = indices
+            .iter()
+            .map(|&index| spans[index].take().unwrap_or_default())
+            .collect();
+
+        let has_root = segment.iter().any(|span| {
+            span.value()
+                .and_then(|span| span.span_id.value())
+                .is_some_and(|span_id| *span_id == segment_id)
+        });
+
+        if has_root {
+            reparent_spans(&mut segment, segment_id);
+            mark_critical_path(&mut segment, segment_id);
+        }
+        compute_segment_exclusive_time(&mut segment);
+
+        for (index, span) in indices.into_iter().zip(segment) {
+            spans[index] = Some(span);
+        }
+    }
+}
+
 fn add_sample_rate(measurements: &mut Annotated<Measurements>, name: &str, value: Option<f64>) {
     let value = match value {
         Some(value) if value > 0.0 => value,
@@ -278,6 +363,7 @@ pub fn extract_from_event(
     managed_envelope: &mut TypedEnvelope<TransactionGroup>,
     event: &Annotated<Event>,
     global_config: &GlobalConfig,
+    project_config: &ProjectConfig,
     config: Arc<Config>,
     server_sample_rate: Option<f64>,
     event_metrics_extracted: EventMetricsExtracted,
@@ -368,6 +454,7 @@ pub fn extract_from_event(
             .aggregator_config_for(MetricNamespace::Spans)
             .max_tag_value_length,
         &[],
+        &project_config.span_description_normalization_rules,
     ) else {
         return spans_extracted;
     };
@@ -420,6 +507,12 @@ pub fn maybe_discard_transaction(
 struct NormalizeSpanConfig<'a> {
     /// The time at which the event was received in this Relay.
     received_at: DateTime<Utc>,
+    /// The time at which the envelope was sent by the SDK.
+    sent_at: Option<DateTime<Utc>>,
+    /// The project's clock drift policy.
+    ///
+    /// Standalone spans are only corrected for clock drift if the policy applies to spans.
+    clock_drift: Option<&'a ClockDriftConfig>,
     /// Allowed time range for spans.
     timestamp_range: std::ops::Range<UnixTimestamp>,
     /// The maximum allowed size of tag values in bytes. Longer values will be cropped.
@@ -434,6 +527,9 @@ struct NormalizeSpanConfig<'a> {
     measurements: Option<CombinedMeasurementsConfig<'a>>,
     /// Configuration for AI model cost calculation
     ai_model_costs: Option<&'a ModelCosts>,
+    /// Project- and organization-specific AI model costs, which take precedence over
+    /// `ai_model_costs`.
+    ai_model_cost_overrides: Vec<&'a ModelCosts>,
     /// The maximum length for names of custom measurements.
     ///
     /// Measurements with longer names are removed from the transaction event and replaced with a
@@ -447,6 +543,8 @@ struct NormalizeSpanConfig<'a> {
     client_hints: ClientHints<String>,
     /// Hosts that are not replaced by "*" in HTTP span grouping.
     allowed_hosts: &'a [String],
+    /// Project-defined rules for normalizing span descriptions.
+    description_rules: &'a [SpanDescriptionNormalizationRule],
     /// The IP address of the SDK that sent the event.
     ///
     /// When `{{auto}}` is specified and there is no other IP address in the payload, such as in the
@@ -461,15 +559,18 @@ impl<'a> NormalizeSpanConfig<'a> {
     fn new(
         config: &'a Config,
         global_config: &'a GlobalConfig,
-        project_config: &'a ProjectConfig,
+        project_info: &'a ProjectInfo,
         managed_envelope: &ManagedEnvelope,
         client_ip: Option<IpAddr>,
         geo_lookup: Option<&'a GeoIpLookup>,
     ) -> Self {
+        let project_config = &project_info.config;
         let aggregator_config = config.aggregator_config_for(MetricNamespace::Spans);
 
         Self {
             received_at: managed_envelope.received_at(),
+            sent_at: managed_envelope.envelope().sent_at(),
+            clock_drift: project_config.clock_drift.as_ref(),
             timestamp_range: aggregator_config.timestamp_range(),
             max_tag_value_size: aggregator_config.max_tag_value_length,
             performance_score: project_config.performance_score.as_ref(),
@@ -481,6 +582,7 @@ impl<'a> NormalizeSpanConfig<'a> {
                 ErrorBoundary::Err(_) => None,
                 ErrorBoundary::Ok(costs) => Some(costs),
             },
+            ai_model_cost_overrides: project_info.ai_model_cost_overrides(global_config),
             max_name_and_unit_len: aggregator_config
                 .max_name_length
                 .saturating_sub(MeasurementsConfig::MEASUREMENT_MRI_OVERHEAD),
@@ -493,6 +595,7 @@ impl<'a> NormalizeSpanConfig<'a> {
                 .map(String::from),
             client_hints: managed_envelope.meta().client_hints().clone(),
             allowed_hosts: global_config.options.http_span_allowed_hosts.as_slice(),
+            description_rules: &project_config.span_description_normalization_rules,
             client_ip,
             geo_lookup,
             span_op_defaults: global_config.span_op_defaults.borrow(),
@@ -538,16 +641,20 @@ fn normalize(
 ) -> Result<(), ProcessingError> {
     let NormalizeSpanConfig {
         received_at,
+        sent_at,
+        clock_drift,
         timestamp_range,
         max_tag_value_size,
         performance_score,
         measurements,
         ai_model_costs,
+        ai_model_cost_overrides,
         max_name_and_unit_len,
         tx_name_rules,
         user_agent,
         client_hints,
         allowed_hosts,
+        description_rules,
         client_ip,
         geo_lookup,
         span_op_defaults,
@@ -563,6 +670,16 @@ fn normalize(
         ProcessingState::root(),
     )?;
 
+    if let Some(clock_drift) = clock_drift.filter(|c| c.applies_to(ClockDriftItemType::Span)) {
+        let mut processor = ClockDriftProcessor::new(sent_at, received_at)
+            .policy(Some(clock_drift), ClockDriftItemType::Span);
+        process_value(annotated_span, &mut processor, ProcessingState::root())?;
+
+        if let Some(span) = annotated_span.value_mut() {
+            processor.annotate(&mut span.timestamp);
+        }
+    }
+
     process_value(
         annotated_span,
         &mut TimestampProcessor,
@@ -643,15 +760,14 @@ fn normalize(
         is_mobile,
         None,
         allowed_hosts,
+        description_rules,
     );
     span.sentry_tags = Annotated::new(tags);
 
     normalize_performance_score(span, performance_score);
 
     map_ai_measurements_to_data(span);
-    if let Some(model_costs_config) = ai_model_costs {
-        extract_ai_data(span, model_costs_config);
-    }
+    extract_ai_data(span, ai_model_costs, &ai_model_cost_overrides);
 
     tag_extraction::extract_measurements(span, is_mobile);
 
@@ -680,14 +796,22 @@ fn populate_ua_fields(
         client_hints = ClientHints::default();
     }
 
+    let user_agent_info = RawUserAgentInfo {
+        user_agent: user_agent.as_deref(),
+        client_hints,
+    };
+
     if data.browser_name.value().is_none() {
-        if let Some(context) = BrowserContext::from_hints_or_ua(&RawUserAgentInfo {
-            user_agent: user_agent.as_deref(),
-            client_hints,
-        }) {
+        if let Some(context) = BrowserContext::from_hints_or_ua(&user_agent_info) {
             data.browser_name = context.name;
         }
     }
+
+    if data.os_name.value().is_none() {
+        if let Some(context) = OsContext::from_hints_or_ua(&user_agent_info) {
+            data.os_name = context.name;
+        }
+    }
 }
 
 /// Promotes some fields from span.data as there are predefined places for certain fields.
@@ -881,11 +1005,12 @@ mod tests {
         let global_config = GlobalConfig::default();
         let config = Arc::new(Config::default());
         assert!(global_config.options.span_extraction_sample_rate.is_none());
-        let (mut managed_envelope, event, _) = params();
+        let (mut managed_envelope, event, project_info) = params();
         extract_from_event(
             &mut managed_envelope,
             &event,
             &global_config,
+            &project_info.config,
             config,
             None,
             EventMetricsExtracted(false),
@@ -906,11 +1031,12 @@ mod tests {
         let mut global_config = GlobalConfig::default();
         global_config.options.span_extraction_sample_rate = Some(1.0);
         let config = Arc::new(Config::default());
-        let (mut managed_envelope, event, _) = params();
+        let (mut managed_envelope, event, project_info) = params();
         extract_from_event(
             &mut managed_envelope,
             &event,
             &global_config,
+            &project_info.config,
             config,
             None,
             EventMetricsExtracted(false),
@@ -931,11 +1057,12 @@ mod tests {
         let mut global_config = GlobalConfig::default();
         global_config.options.span_extraction_sample_rate = Some(0.0);
         let config = Arc::new(Config::default());
-        let (mut managed_envelope, event, _) = params();
+        let (mut managed_envelope, event, project_info) = params();
         extract_from_event(
             &mut managed_envelope,
             &event,
             &global_config,
+            &project_info.config,
             config,
             None,
             EventMetricsExtracted(false),
@@ -956,11 +1083,12 @@ mod tests {
         let mut global_config = GlobalConfig::default();
         global_config.options.span_extraction_sample_rate = Some(1.0); // force enable
         let config = Arc::new(Config::default());
-        let (mut managed_envelope, event, _) = params(); // client sample rate is 0.2
+        let (mut managed_envelope, event, project_info) = params(); // client sample rate is 0.2
         extract_from_event(
             &mut managed_envelope,
             &event,
             &global_config,
+            &project_info.config,
             config,
             Some(0.1),
             EventMetricsExtracted(false),
@@ -998,6 +1126,49 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn extract_project_description_rules() {
+        let mut global_config = GlobalConfig::default();
+        global_config.options.span_extraction_sample_rate = Some(1.0);
+        let config = Arc::new(Config::default());
+        let (mut managed_envelope, mut event, project_info) = params();
+
+        let event_mut = event.value_mut().as_mut().unwrap();
+        event_mut.transaction = Annotated::new("/users/123/profile".to_owned());
+        if let Some(trace) = event_mut.context_mut::<TraceContext>() {
+            trace.op = Annotated::new("http.server".to_owned());
+        }
+
+        let mut project_config = project_info.config.clone();
+        project_config.span_description_normalization_rules =
+            serde_json::from_value(serde_json::json!([{
+                "pattern": {"type": "glob", "value": "/users/*/profile"},
+                "replacement": "user profile"
+            }]))
+            .unwrap();
+
+        extract_from_event(
+            &mut managed_envelope,
+            &event,
+            &global_config,
+            &project_config,
+            config,
+            None,
+            EventMetricsExtracted(false),
+            SpansExtracted(false),
+        );
+
+        let span = managed_envelope
+            .envelope()
+            .items()
+            .find(|item| item.ty() == &ItemType::Span)
+            .unwrap();
+
+        let span = Annotated::<Span>::from_json_bytes(&span.payload()).unwrap();
+        let description = get_value!(span.sentry_tags.description!);
+        assert_eq!(description, "user profile");
+    }
+
     #[test]
     fn segment_no_overwrite() {
         let mut span: Annotated<Span> = Annotated::from_json(
@@ -1240,6 +1411,21 @@ mod tests {
         assert_eq!(get_value!(span.data.browser_name!), "Opera");
     }
 
+    #[test]
+    fn derive_os_name() {
+        let mut span: Annotated<Span> = Annotated::from_json(r#"{}"#).unwrap();
+        populate_ua_fields(
+            span.value_mut().as_mut().unwrap(),
+            None,
+            ClientHints {
+                sec_ch_ua_platform: Some(r#""macOS""#),
+                sec_ch_ua_platform_version: Some(r#""14.0.0""#),
+                ..Default::default()
+            },
+        );
+        assert_eq!(get_value!(span.data.os_name!), "macOS");
+    }
+
     static GEO_LOOKUP: Lazy<GeoIpLookup> = Lazy::new(|| {
         GeoIpLookup::open("../relay-event-normalization/tests/fixtures/GeoIP2-Enterprise-Test.mmdb")
             .unwrap()
@@ -1248,6 +1434,8 @@ mod tests {
     fn normalize_config() -> NormalizeSpanConfig<'static> {
         NormalizeSpanConfig {
             received_at: DateTime::from_timestamp_nanos(0),
+            sent_at: None,
+            clock_drift: None,
             timestamp_range: UnixTimestamp::from_datetime(
                 DateTime::<Utc>::from_timestamp_millis(1000).unwrap(),
             )
@@ -1257,11 +1445,13 @@ mod tests {
             performance_score: None,
             measurements: None,
             ai_model_costs: None,
+            ai_model_cost_overrides: Vec::new(),
             max_name_and_unit_len: 200,
             tx_name_rules: &[],
             user_agent: None,
             client_hints: ClientHints::default(),
             allowed_hosts: &[],
+            description_rules: &[],
             client_ip: Some(IpAddr("2.125.160.216".to_owned())),
             geo_lookup: Some(&GEO_LOOKUP),
             span_op_defaults: Default::default(),
@@ -1429,4 +1619,48 @@ mod tests {
             &EventId("480ffcc911174ade9106b40ffbd822f5".parse().unwrap())
         );
     }
+
+    #[test]
+    fn normalize_segment_tree() {
+        let bytes = Bytes::from(
+            r#"{"dsn":"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"}
+{"type":"span"}
+{"span_id":"aaaaaaaaaaaaaaaa","segment_id":"aaaaaaaaaaaaaaaa","is_segment":true,"start_timestamp":0,"timestamp":10}
+{"type":"span"}
+{"span_id":"bbbbbbbbbbbbbbbb","parent_span_id":"aaaaaaaaaaaaaaaa","segment_id":"aaaaaaaaaaaaaaaa","start_timestamp":6,"timestamp":8}
+{"type":"span"}
+{"span_id":"cccccccccccccccc","parent_span_id":"dddddddddddddddd","segment_id":"aaaaaaaaaaaaaaaa","start_timestamp":5,"timestamp":9}
+"#,
+        );
+
+        let envelope = Envelope::parse_bytes(bytes).unwrap();
+        let managed_envelope = ManagedEnvelope::new(envelope, Addr::dummy(), Addr::dummy());
+        let managed_envelope: TypedEnvelope<SpanGroup> = (managed_envelope, ProcessingGroup::Span)
+            .try_into()
+            .unwrap();
+
+        let mut spans = parse_spans(&managed_envelope);
+        normalize_segments(&mut spans);
+
+        let spans: Vec<_> = spans.into_iter().map(Option::unwrap).collect();
+
+        let [root, b, c] = spans.as_slice() else {
+            panic!("unexpected spans");
+        };
+
+        assert_eq!(*get_value!(root.exclusive_time!), 6000.0);
+        assert_eq!(*get_value!(b.exclusive_time!), 2000.0);
+        assert_eq!(*get_value!(c.exclusive_time!), 4000.0);
+
+        // The broken parent link is attached to the segment span, and `b` runs in parallel to `c`.
+        assert_eq!(
+            get_value!(c.parent_span_id!).to_string(),
+            "aaaaaaaaaaaaaaaa"
+        );
+
+        for span in [root, c] {
+            assert_eq!(get_value!(span.data.is_critical_path!), &true);
+        }
+        assert!(get_value!(b.data).is_none());
+    }
 }
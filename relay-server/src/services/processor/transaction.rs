@@ -1,6 +1,19 @@
 //! Processing logic specific to transaction envelopes.
 
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use relay_base_schema::events::EventType;
+use relay_base_schema::project::ProjectId;
+use relay_config::TransactionClustering;
 use relay_dynamic_config::GlobalConfig;
+use relay_event_normalization::{
+    TransactionClusterer, TransactionClustererConfig, TransactionNameRule,
+};
+use relay_event_schema::protocol::{Event, TransactionSource};
+use relay_protocol::Annotated;
 
 use crate::envelope::ItemType;
 use crate::services::outcome::{DiscardReason, Outcome};
@@ -17,3 +30,191 @@ pub fn drop_invalid_items(envelope: &mut ManagedEnvelope, global_config: &Global
         });
     }
 }
+
+/// Interval at which clusterers of idle projects are evicted.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Local transaction name clusterers for all projects.
+///
+/// See [`TransactionClusterer`] for more information.
+#[derive(Debug)]
+pub struct TransactionClusterers {
+    config: TransactionClustererConfig,
+    projects: Mutex<ProjectClusterers>,
+}
+
+impl TransactionClusterers {
+    /// Creates the clusterers if transaction clustering is enabled in the config.
+    pub fn from_config(config: &TransactionClustering) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+
+        Some(Self {
+            config: TransactionClustererConfig {
+                merge_threshold: config.merge_threshold,
+                max_nodes: config.max_nodes,
+                rule_ttl: Duration::from_secs(config.rule_ttl),
+            },
+            projects: Mutex::new(ProjectClusterers {
+                clusterers: HashMap::new(),
+                last_eviction: Instant::now(),
+            }),
+        })
+    }
+
+    /// Returns the clusterer of the given project, creating it if necessary.
+    ///
+    /// Clusterers of projects that have not been used for longer than the rule TTL are evicted,
+    /// since all of their rules have expired.
+    pub fn get(&self, project_id: ProjectId) -> ProjectTransactionClusterer {
+        let mut projects = self.projects.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let now = Instant::now();
+        if now.duration_since(projects.last_eviction) >= EVICTION_INTERVAL {
+            let ttl = self.config.rule_ttl;
+            projects
+                .clusterers
+                .retain(|_, entry| now.duration_since(entry.last_used) < ttl);
+            projects.last_eviction = now;
+        }
+
+        let entry =
+            projects
+                .clusterers
+                .entry(project_id)
+                .or_insert_with(|| ProjectClustererEntry {
+                    clusterer: ProjectTransactionClusterer(Arc::new(Mutex::new(
+                        TransactionClusterer::new(self.config),
+                    ))),
+                    last_used: now,
+                });
+        entry.last_used = now;
+        entry.clusterer.clone()
+    }
+}
+
+#[derive(Debug)]
+struct ProjectClusterers {
+    clusterers: HashMap<ProjectId, ProjectClustererEntry>,
+    last_eviction: Instant,
+}
+
+#[derive(Debug)]
+struct ProjectClustererEntry {
+    clusterer: ProjectTransactionClusterer,
+    last_used: Instant,
+}
+
+/// The local transaction name clusterer of a single project.
+///
+/// Obtained once per event from [`TransactionClusterers::get`].
+#[derive(Clone, Debug)]
+pub struct ProjectTransactionClusterer(Arc<Mutex<TransactionClusterer>>);
+
+impl ProjectTransactionClusterer {
+    /// Returns the rules discovered for the project.
+    pub fn rules(&self) -> Arc<[TransactionNameRule]> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .rules(Utc::now())
+    }
+
+    /// Records the name of a normalized transaction event.
+    ///
+    /// Only transactions with a URL source are recorded, which are marked as sanitized during
+    /// normalization.
+    pub fn record(&self, event: &Annotated<Event>) {
+        let Some(event) = event.value() else {
+            return;
+        };
+
+        let source = event
+            .transaction_info
+            .value()
+            .and_then(|info| info.source.value());
+        if event.ty.value() != Some(&EventType::Transaction)
+            || source != Some(&TransactionSource::Sanitized)
+        {
+            return;
+        }
+
+        let Some(name) = event.transaction.as_str() else {
+            return;
+        };
+
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(name, Utc::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(source: &str) -> Annotated<Event> {
+        Annotated::from_json(&format!(
+            r#"{{"type": "transaction", "transaction": "/users/{source}/", "transaction_info": {{"source": "{source}"}}}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_disabled() {
+        assert!(TransactionClusterers::from_config(&TransactionClustering::default()).is_none());
+    }
+
+    #[test]
+    fn test_record_sanitized_transactions() {
+        let clusterers = TransactionClusterers::from_config(&TransactionClustering {
+            enabled: true,
+            merge_threshold: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let clusterer = clusterers.get(ProjectId::new(42));
+
+        clusterer.record(&transaction("route"));
+        clusterer.record(&transaction("sanitized"));
+        assert!(clusterer.rules().is_empty());
+
+        clusterer.record(&transaction("component"));
+        clusterer.record(&transaction("sanitized"));
+        clusterer.record(
+            &Annotated::from_json(
+                r#"{"type": "transaction", "transaction": "/users/other/", "transaction_info": {"source": "sanitized"}}"#,
+            )
+            .unwrap(),
+        );
+
+        let rules = clusterers.get(ProjectId::new(42)).rules();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].pattern.as_str(), "/users/*/**");
+        assert!(clusterers.get(ProjectId::new(21)).rules().is_empty());
+    }
+
+    #[test]
+    fn test_evict_idle_projects() {
+        let clusterers = TransactionClusterers::from_config(&TransactionClustering {
+            enabled: true,
+            rule_ttl: 0,
+            ..Default::default()
+        })
+        .unwrap();
+
+        clusterers.get(ProjectId::new(42));
+        clusterers.get(ProjectId::new(21));
+        assert_eq!(clusterers.projects.lock().unwrap().clusterers.len(), 2);
+
+        // Pretend that the last eviction was long ago.
+        clusterers.projects.lock().unwrap().last_eviction -= EVICTION_INTERVAL;
+
+        clusterers.get(ProjectId::new(21));
+        let projects = clusterers.projects.lock().unwrap();
+        assert_eq!(projects.clusterers.len(), 1);
+        assert!(projects.clusterers.contains_key(&ProjectId::new(21)));
+    }
+}
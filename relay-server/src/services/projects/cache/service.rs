@@ -5,7 +5,7 @@ use futures::future::BoxFuture;
 use relay_base_schema::project::ProjectKey;
 use relay_config::Config;
 use relay_statsd::metric;
-use relay_system::{Service, ServiceSpawn, ServiceSpawnExt as _};
+use relay_system::{RestartPolicy, Service, ServiceSpawn, ServiceSpawnExt as _, Supervision};
 use tokio::sync::broadcast;
 
 use crate::services::projects::cache::handle::ProjectCacheHandle;
@@ -103,7 +103,14 @@ impl ProjectCacheService {
             project_changes: self.project_events_tx.clone(),
         };
 
-        services.start_with(self, addr_rx);
+        // Restarting the cache would lose track of pending fetches and leave projects pending
+        // forever. As a critical service, a panic takes down the process instead.
+        let mut service = Some(self);
+        services.start_supervised_with(
+            move || service.take().expect("project cache is never restarted"),
+            Supervision::new(RestartPolicy::Never).critical(true),
+            addr_rx,
+        );
 
         handle
     }
@@ -9,7 +9,8 @@ use relay_cardinality::CardinalityLimit;
 use relay_config::Config;
 #[cfg(feature = "processing")]
 use relay_dynamic_config::ErrorBoundary;
-use relay_dynamic_config::{Feature, LimitedProjectConfig, ProjectConfig};
+use relay_dynamic_config::{Feature, GlobalConfig, LimitedProjectConfig, ProjectConfig};
+use relay_event_normalization::ModelCosts;
 use relay_filter::matches_any_origin;
 use relay_quotas::{Quota, Scoping};
 use serde::{Deserialize, Serialize};
@@ -92,6 +93,24 @@ impl ProjectInfo {
         &self.config
     }
 
+    /// Returns the AI model costs that take precedence over the global AI model costs.
+    ///
+    /// Costs from the project config take precedence over costs of the organization.
+    pub fn ai_model_cost_overrides<'a>(
+        &'a self,
+        global_config: &'a GlobalConfig,
+    ) -> Vec<&'a ModelCosts> {
+        let project_costs = self.config.ai_model_costs.as_ref().ok();
+        let organization_costs = self
+            .organization_id
+            .and_then(|organization_id| global_config.ai_model_cost_overrides(organization_id));
+
+        project_costs
+            .into_iter()
+            .chain(organization_costs)
+            .collect()
+    }
+
     /// Determines whether the given envelope should be accepted or discarded.
     ///
     /// Returns `Ok(())` if the envelope should be accepted. Returns `Err(DiscardReason)` if the
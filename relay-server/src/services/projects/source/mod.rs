@@ -13,10 +13,12 @@ pub mod upstream;
 
 use crate::services::projects::project::{ProjectState, Revision};
 use crate::services::upstream::UpstreamRelay;
+#[cfg(feature = "processing")]
+use crate::statsd::RelayCounters;
 
 use self::local::{LocalProjectSource, LocalProjectSourceService};
 #[cfg(feature = "processing")]
-use self::redis::RedisProjectSource;
+use self::redis::{RedisProjectError, RedisProjectSource};
 use self::upstream::{UpstreamProjectSource, UpstreamProjectSourceService};
 
 /// Helper type that contains all configured sources for project cache fetching.
@@ -102,6 +104,14 @@ impl ProjectSource {
                 // Redis reported that we're holding an up-to-date version of the state already,
                 // refresh the state and return the old cached state again.
                 Ok(SourceProjectState::NotModified) => return Ok(SourceProjectState::NotModified),
+                // Redis is unavailable, fall back to fetching from the upstream.
+                Err(RedisProjectError::Redis(error)) if error.is_circuit_open() => {
+                    relay_statsd::metric!(
+                        counter(RelayCounters::RedisDegraded) += 1,
+                        consumer = "project_config",
+                        behavior = "upstream",
+                    );
+                }
                 Err(error) => {
                     relay_log::error!(
                         error = &error as &dyn std::error::Error,
@@ -125,7 +125,7 @@ impl UpstreamQuery for GetRelays {
 #[derive(Debug)]
 enum RelayState {
     Exists {
-        relay: RelayInfo,
+        relay: Box<RelayInfo>,
         checked_at: Instant,
     },
     DoesNotExist {
@@ -160,7 +160,7 @@ impl RelayState {
     fn from_option(option: Option<RelayInfo>) -> Self {
         match option {
             Some(relay) => RelayState::Exists {
-                relay,
+                relay: Box::new(relay),
                 checked_at: Instant::now(),
             },
             None => RelayState::DoesNotExist {
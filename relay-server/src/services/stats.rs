@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use relay_config::{Config, RelayMode};
 #[cfg(feature = "processing")]
-use relay_redis::{AsyncRedisClient, RedisClientStats, RedisClients};
+use relay_redis::{AsyncRedisClient, CircuitState, RedisClientStats, RedisClients};
 use relay_statsd::metric;
 use relay_system::{Addr, Handle, RuntimeMetrics, Service};
 use relay_threading::AsyncPool;
@@ -174,6 +174,14 @@ impl RelayStats {
                 u64::from(stats.waiting_for_connection),
             pool = name
         );
+        metric!(
+            gauge(RelayGauges::RedisCircuitBreakerState) = match stats.circuit_state {
+                CircuitState::Closed => 0,
+                CircuitState::HalfOpen => 1,
+                CircuitState::Open => 2,
+            },
+            pool = name
+        );
     }
 
     #[cfg(not(feature = "processing"))]
@@ -26,12 +26,12 @@ use relay_base_schema::project::ProjectId;
 use relay_common::time::UnixTimestamp;
 use relay_config::Config;
 use relay_event_schema::protocol::{EventId, VALID_PLATFORMS};
-use relay_kafka::{ClientError, KafkaClient, KafkaTopic, Message};
+use relay_kafka::{ClientError, KafkaClient, KafkaSpool, KafkaTopic, Message, SerializationOutput};
 use relay_metrics::{
     Bucket, BucketView, BucketViewValue, BucketsView, ByNamespace, GaugeValue, MetricName,
     MetricNamespace, SetView,
 };
-use relay_protocol::FiniteF64;
+use relay_protocol::{FiniteF64, Getter, Val};
 use relay_quotas::Scoping;
 use relay_statsd::metric;
 use relay_system::{Addr, FromMessage, Interface, NoResponse, Service};
@@ -68,14 +68,21 @@ impl OutcomeError for StoreError {
     }
 }
 
+#[derive(Clone)]
 struct Producer {
-    client: KafkaClient,
+    client: Arc<KafkaClient>,
 }
 
 impl Producer {
     pub fn create(config: &Config) -> anyhow::Result<Self> {
         let mut client_builder = KafkaClient::builder();
 
+        if let Some(spool_config) = config.kafka_spool() {
+            let spool =
+                KafkaSpool::open(spool_config).map_err(|e| ServiceError::Kafka(e.to_string()))?;
+            client_builder = client_builder.spool(spool);
+        }
+
         for topic in KafkaTopic::iter().filter(|t| {
             // Outcomes should not be sent from the store forwarder.
             // See `KafkaOutcomesProducer`.
@@ -88,7 +95,7 @@ impl Producer {
         }
 
         Ok(Self {
-            client: client_builder.build(),
+            client: Arc::new(client_builder.build()),
         })
     }
 }
@@ -183,6 +190,9 @@ impl FromMessage<Managed<StoreLog>> for Store {
 }
 
 /// Service implementing the [`Store`] interface.
+///
+/// Clones share the same Kafka producer, which allows the service to be restarted cheaply.
+#[derive(Clone)]
 pub struct StoreService {
     pool: StoreServicePool,
     config: Arc<Config>,
@@ -280,7 +290,7 @@ impl StoreService {
                     debug_assert!(topic == KafkaTopic::Attachments);
                     if let Some(attachment) = self.produce_attachment(
                         event_id.ok_or(StoreError::NoEventId)?,
-                        scoping.project_id,
+                        scoping,
                         item,
                         send_individual_attachments,
                     )? {
@@ -291,7 +301,7 @@ impl StoreService {
                     debug_assert!(topic == KafkaTopic::Attachments);
                     self.produce_user_report(
                         event_id.ok_or(StoreError::NoEventId)?,
-                        scoping.project_id,
+                        scoping,
                         received_at,
                         item,
                     )?;
@@ -300,10 +310,11 @@ impl StoreService {
                     let remote_addr = envelope.meta().client_addr().map(|addr| addr.to_string());
                     self.produce_user_report_v2(
                         event_id.ok_or(StoreError::NoEventId)?,
-                        scoping.project_id,
+                        scoping,
                         received_at,
                         item,
                         remote_addr,
+                        sdk_name(envelope.meta().client()),
                     )?;
                 }
                 ItemType::Profile => self.produce_profile(
@@ -330,7 +341,7 @@ impl StoreService {
                     replay_event = Some(item);
                     self.produce_replay_event(
                         event_id.ok_or(StoreError::NoEventId)?,
-                        scoping.project_id,
+                        scoping,
                         received_at,
                         retention,
                         &item.payload(),
@@ -338,7 +349,7 @@ impl StoreService {
                 }
                 ItemType::CheckIn => {
                     let client = envelope.meta().client();
-                    self.produce_check_in(scoping.project_id, received_at, client, retention, item)?
+                    self.produce_check_in(scoping, received_at, client, retention, item)?
                 }
                 ItemType::Span => {
                     self.produce_span(scoping, received_at, event_id, retention, item)?
@@ -421,7 +432,6 @@ impl StoreService {
 
         if let Some(event_item) = event_item {
             let event_id = event_id.ok_or(StoreError::NoEventId)?;
-            let project_id = scoping.project_id;
             let remote_addr = envelope.meta().client_addr().map(|addr| addr.to_string());
 
             self.produce(
@@ -430,9 +440,12 @@ impl StoreService {
                     payload: event_item.payload(),
                     start_time: safe_timestamp(received_at),
                     event_id,
-                    project_id,
+                    org_id: scoping.organization_id,
+                    project_id: scoping.project_id,
                     remote_addr,
                     attachments,
+                    platform: event_item.platform().map(str::to_owned),
+                    sdk_name: sdk_name(envelope.meta().client()),
                 }),
             )?;
         } else {
@@ -675,7 +688,7 @@ impl StoreService {
     fn produce_attachment(
         &self,
         event_id: EventId,
-        project_id: ProjectId,
+        scoping: Scoping,
         item: &Item,
         send_individual_attachments: bool,
     ) -> Result<Option<ChunkedAttachment>, StoreError> {
@@ -700,7 +713,8 @@ impl StoreService {
                 let chunk_message = AttachmentChunkKafkaMessage {
                     payload: payload.slice(offset..offset + chunk_size),
                     event_id,
-                    project_id,
+                    org_id: scoping.organization_id,
+                    project_id: scoping.project_id,
                     id: id.clone(),
                     chunk_index,
                 };
@@ -737,7 +751,8 @@ impl StoreService {
         if send_individual_attachments {
             let message = KafkaMessage::Attachment(AttachmentKafkaMessage {
                 event_id,
-                project_id,
+                org_id: scoping.organization_id,
+                project_id: scoping.project_id,
                 attachment,
             });
             self.produce(KafkaTopic::Attachments, message)?;
@@ -750,12 +765,13 @@ impl StoreService {
     fn produce_user_report(
         &self,
         event_id: EventId,
-        project_id: ProjectId,
+        scoping: Scoping,
         received_at: DateTime<Utc>,
         item: &Item,
     ) -> Result<(), StoreError> {
         let message = KafkaMessage::UserReport(UserReportKafkaMessage {
-            project_id,
+            org_id: scoping.organization_id,
+            project_id: scoping.project_id,
             event_id,
             start_time: safe_timestamp(received_at),
             payload: item.payload(),
@@ -767,18 +783,22 @@ impl StoreService {
     fn produce_user_report_v2(
         &self,
         event_id: EventId,
-        project_id: ProjectId,
+        scoping: Scoping,
         received_at: DateTime<Utc>,
         item: &Item,
         remote_addr: Option<String>,
+        sdk_name: Option<String>,
     ) -> Result<(), StoreError> {
         let message = KafkaMessage::Event(EventKafkaMessage {
-            project_id,
+            org_id: scoping.organization_id,
+            project_id: scoping.project_id,
             event_id,
             payload: item.payload(),
             start_time: safe_timestamp(received_at),
             remote_addr,
             attachments: vec![],
+            platform: item.platform().map(str::to_owned),
+            sdk_name,
         });
         self.produce(KafkaTopic::Feedback, message)
     }
@@ -833,14 +853,15 @@ impl StoreService {
     fn produce_replay_event(
         &self,
         replay_id: EventId,
-        project_id: ProjectId,
+        scoping: Scoping,
         received_at: DateTime<Utc>,
         retention_days: u16,
         payload: &[u8],
     ) -> Result<(), StoreError> {
         let message = ReplayEventKafkaMessage {
             replay_id,
-            project_id,
+            org_id: scoping.organization_id,
+            project_id: scoping.project_id,
             retention_days,
             start_time: safe_timestamp(received_at),
             payload,
@@ -940,7 +961,7 @@ impl StoreService {
 
         self.produce_replay_event(
             event_id.ok_or(StoreError::NoEventId)?,
-            scoping.project_id,
+            scoping,
             received_at,
             retention,
             replay_event,
@@ -959,7 +980,7 @@ impl StoreService {
 
     fn produce_check_in(
         &self,
-        project_id: ProjectId,
+        scoping: Scoping,
         received_at: DateTime<Utc>,
         client: Option<&str>,
         retention_days: u16,
@@ -967,7 +988,8 @@ impl StoreService {
     ) -> Result<(), StoreError> {
         let message = KafkaMessage::CheckIn(CheckInKafkaMessage {
             message_type: CheckInMessageType::CheckIn,
-            project_id,
+            org_id: scoping.organization_id,
+            project_id: scoping.project_id,
             retention_days,
             start_time: safe_timestamp(received_at),
             sdk: client.map(str::to_owned),
@@ -1414,12 +1436,21 @@ struct EventKafkaMessage {
     start_time: u64,
     /// The event id.
     event_id: EventId,
+    /// The organization id, used for routing.
+    #[serde(skip)]
+    org_id: OrganizationId,
     /// The project id for the current event.
     project_id: ProjectId,
     /// The client ip address.
     remote_addr: Option<String>,
     /// Attachments that are potentially relevant for processing.
     attachments: Vec<ChunkedAttachment>,
+    /// The platform of the event, used for routing.
+    #[serde(skip)]
+    platform: Option<String>,
+    /// The name of the SDK that sent the event, used for routing.
+    #[serde(skip)]
+    sdk_name: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1430,6 +1461,9 @@ struct ReplayEventKafkaMessage<'a> {
     start_time: u64,
     /// The event id.
     replay_id: EventId,
+    /// The organization id, used for routing.
+    #[serde(skip)]
+    org_id: OrganizationId,
     /// The project id for the current event.
     project_id: ProjectId,
     // Number of days to retain.
@@ -1443,6 +1477,9 @@ struct AttachmentChunkKafkaMessage {
     payload: Bytes,
     /// The event id.
     event_id: EventId,
+    /// The organization id, used for routing.
+    #[serde(skip)]
+    org_id: OrganizationId,
     /// The project id for the current event.
     project_id: ProjectId,
     /// The attachment ID within the event.
@@ -1461,6 +1498,9 @@ struct AttachmentChunkKafkaMessage {
 struct AttachmentKafkaMessage {
     /// The event id.
     event_id: EventId,
+    /// The organization id, used for routing.
+    #[serde(skip)]
+    org_id: OrganizationId,
     /// The project id for the current event.
     project_id: ProjectId,
     /// The attachment.
@@ -1488,6 +1528,9 @@ struct ReplayRecordingNotChunkedKafkaMessage<'a> {
 /// Is always independent of an event and can be sent as part of any envelope.
 #[derive(Debug, Serialize)]
 struct UserReportKafkaMessage {
+    /// The organization id, used for routing.
+    #[serde(skip)]
+    org_id: OrganizationId,
     /// The project id for the current event.
     project_id: ProjectId,
     start_time: u64,
@@ -1582,6 +1625,9 @@ struct CheckInKafkaMessage {
     start_time: u64,
     /// The SDK client which produced the event.
     sdk: Option<String>,
+    /// The organization id, used for routing.
+    #[serde(skip)]
+    org_id: OrganizationId,
     /// The project id for the current event.
     project_id: ProjectId,
     /// Number of days to retain.
@@ -1905,32 +1951,106 @@ impl Message for KafkaMessage<'_> {
         }
     }
 
+    fn fields(&self) -> Option<&dyn Getter> {
+        Some(self)
+    }
+
     /// Serializes the message into its binary format.
-    fn serialize(&self) -> Result<Cow<'_, [u8]>, ClientError> {
+    fn serialize(&self) -> Result<SerializationOutput<'_>, ClientError> {
         match self {
             KafkaMessage::Metric { message, .. } => serde_json::to_vec(message)
-                .map(Cow::Owned)
+                .map(|payload| SerializationOutput::Json(Cow::Owned(payload)))
                 .map_err(ClientError::InvalidJson),
             KafkaMessage::ReplayEvent(message) => serde_json::to_vec(message)
-                .map(Cow::Owned)
+                .map(|payload| SerializationOutput::Json(Cow::Owned(payload)))
                 .map_err(ClientError::InvalidJson),
             KafkaMessage::Span { message, .. } => serde_json::to_vec(message)
-                .map(Cow::Owned)
+                .map(|payload| SerializationOutput::Json(Cow::Owned(payload)))
                 .map_err(ClientError::InvalidJson),
             KafkaMessage::Item { message, .. } => {
                 let mut payload = Vec::new();
                 if message.encode(&mut payload).is_err() {
                     return Err(ClientError::ProtobufEncodingFailed);
                 }
-                Ok(Cow::Owned(payload))
+                Ok(SerializationOutput::Protobuf {
+                    message_type: "sentry_protos.snuba.v1.TraceItem",
+                    payload: Cow::Owned(payload),
+                })
             }
             _ => rmp_serde::to_vec_named(&self)
-                .map(Cow::Owned)
+                .map(|payload| SerializationOutput::MsgPack(Cow::Owned(payload)))
                 .map_err(ClientError::InvalidMsgPack),
         }
     }
 }
 
+impl KafkaMessage<'_> {
+    fn organization_id(&self) -> u64 {
+        match self {
+            Self::Event(message) => message.org_id.value(),
+            Self::Attachment(message) => message.org_id.value(),
+            Self::AttachmentChunk(message) => message.org_id.value(),
+            Self::UserReport(message) => message.org_id.value(),
+            Self::Metric { message, .. } => message.org_id.value(),
+            Self::Profile(message) => message.organization_id.value(),
+            Self::ReplayEvent(message) => message.org_id.value(),
+            Self::ReplayRecordingNotChunked(message) => message.org_id.value(),
+            Self::CheckIn(message) => message.org_id.value(),
+            Self::Item { message, .. } => message.organization_id,
+            Self::Span { message, .. } => message.organization_id,
+            Self::ProfileChunk(message) => message.organization_id.value(),
+        }
+    }
+
+    fn project_id(&self) -> u64 {
+        match self {
+            Self::Event(message) => message.project_id.value(),
+            Self::Attachment(message) => message.project_id.value(),
+            Self::AttachmentChunk(message) => message.project_id.value(),
+            Self::UserReport(message) => message.project_id.value(),
+            Self::Metric { message, .. } => message.project_id.value(),
+            Self::Profile(message) => message.project_id.value(),
+            Self::ReplayEvent(message) => message.project_id.value(),
+            Self::ReplayRecordingNotChunked(message) => message.project_id.value(),
+            Self::CheckIn(message) => message.project_id.value(),
+            Self::Item { message, .. } => message.project_id,
+            Self::Span { message, .. } => message.project_id,
+            Self::ProfileChunk(message) => message.project_id.value(),
+        }
+    }
+}
+
+/// Exposes the fields used by Kafka routing rules and dynamic headers.
+///
+/// Event fields are read from the event payload, which is only parsed if a rule or header
+/// refers to it.
+impl Getter for KafkaMessage<'_> {
+    fn get_value(&self, path: &str) -> Option<Val<'_>> {
+        Some(match path {
+            "organization_id" => self.organization_id().into(),
+            "project_id" => self.project_id().into(),
+            "item_type" => self.variant().into(),
+            "event.platform" => match self {
+                Self::Event(message) => message.platform.as_deref()?.into(),
+                _ => return None,
+            },
+            "event.sdk.name" => match self {
+                Self::Event(message) => message.sdk_name.as_deref()?.into(),
+                _ => return None,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// Returns the name of the SDK from the client identifier of a request.
+///
+/// The client is formatted as `"sdk/version"`, for example `"sentry.python/2.0.0"`.
+fn sdk_name(client: Option<&str>) -> Option<String> {
+    let (name, _) = client?.split_once('/')?;
+    Some(name.to_owned())
+}
+
 /// Determines if the given item is considered slow.
 ///
 /// Slow items must be routed to the `Attachments` topic.
@@ -1965,11 +2085,53 @@ mod tests {
         let producer = Producer::create(&config).unwrap();
 
         for topic in [KafkaTopic::Outcomes, KafkaTopic::OutcomesBilling] {
-            let res = producer
-                .client
-                .send(topic, Some(*b"0123456789abcdef"), None, "foo", b"");
+            let res = producer.client.send(
+                topic,
+                Some(*b"0123456789abcdef"),
+                None,
+                "foo",
+                SerializationOutput::Json(Cow::Borrowed(b"")),
+            );
 
             assert!(matches!(res, Err(ClientError::InvalidTopicName)));
         }
     }
+
+    #[test]
+    fn test_routing_fields() {
+        let message = KafkaMessage::Event(EventKafkaMessage {
+            payload: Bytes::new(),
+            start_time: 0,
+            event_id: EventId::new(),
+            org_id: OrganizationId::new(42),
+            project_id: ProjectId::new(21),
+            remote_addr: None,
+            attachments: vec![],
+            platform: Some("python".to_owned()),
+            sdk_name: sdk_name(Some("sentry.python/2.0.0")),
+        });
+
+        assert_eq!(message.get_value("organization_id"), Some(Val::U64(42)));
+        assert_eq!(message.get_value("project_id"), Some(Val::U64(21)));
+        assert_eq!(message.get_value("item_type"), Some(Val::String("event")));
+        assert_eq!(
+            message.get_value("event.platform"),
+            Some(Val::String("python"))
+        );
+        assert_eq!(
+            message.get_value("event.sdk.name"),
+            Some(Val::String("sentry.python"))
+        );
+        assert_eq!(message.get_value("event.release"), None);
+
+        // Event fields are only available for event messages.
+        let message = KafkaMessage::UserReport(UserReportKafkaMessage {
+            org_id: OrganizationId::new(42),
+            project_id: ProjectId::new(21),
+            start_time: 0,
+            payload: Bytes::new(),
+            event_id: EventId::new(),
+        });
+        assert_eq!(message.get_value("event.platform"), None);
+    }
 }
@@ -15,7 +15,7 @@ use std::time::Duration;
 use bytes::Bytes;
 use itertools::Itertools;
 use relay_auth::{RegisterChallenge, RegisterRequest, RegisterResponse, Registration};
-use relay_config::{Config, Credentials, RelayMode};
+use relay_config::{Config, Credentials, HttpProtocol, RelayMode};
 use relay_quotas::{
     DataCategories, QuotaScope, RateLimit, RateLimitScope, RateLimits, ReasonCode, RetryAfter,
     Scoping,
@@ -31,7 +31,7 @@ use tokio::sync::mpsc;
 use tokio::time::Instant;
 
 use crate::http::{HttpError, Request, RequestBuilder, Response, StatusCode};
-use crate::statsd::{RelayHistograms, RelayTimers};
+use crate::statsd::{RelayGauges, RelayHistograms, RelayTimers};
 use crate::utils::{self, ApiErrorResponse, RelayErrorAction, RetryBackoff};
 
 /// Rate limits returned by the upstream.
@@ -225,7 +225,7 @@ pub struct IsNetworkOutage;
 /// Priority of an upstream request.
 ///
 /// See [`UpstreamRequest::priority`] for more information.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RequestPriority {
     /// High priority, low volume messages (e.g. ProjectConfig, ProjectStates, Registration messages).
     High,
@@ -730,26 +730,60 @@ impl UpstreamQuery for RegisterResponse {
 #[derive(Debug, Clone)]
 struct SharedClient {
     config: Arc<Config>,
-    reqwest: reqwest::Client,
+    /// Client for high priority requests.
+    high: reqwest::Client,
+    /// Client for low priority requests.
+    ///
+    /// This shares the connection pool with `high` unless `http.priority_pools` is enabled.
+    low: reqwest::Client,
 }
 
 impl SharedClient {
     /// Creates a new `SharedClient` instance.
     pub fn build(config: Arc<Config>) -> Self {
-        let reqwest = reqwest::ClientBuilder::new()
+        let high = Self::build_reqwest(&config);
+        let low = match config.http_priority_pools() {
+            true => Self::build_reqwest(&config),
+            false => high.clone(),
+        };
+
+        Self { config, high, low }
+    }
+
+    /// Creates a `reqwest` client with its own connection pool.
+    fn build_reqwest(config: &Config) -> reqwest::Client {
+        let mut builder = reqwest::ClientBuilder::new()
             .connect_timeout(config.http_connection_timeout())
             .timeout(config.http_timeout())
+            .pool_idle_timeout(config.http_pool_idle_timeout())
             // In the forward endpoint, this means that content negotiation is done twice, and the
             // response body is first decompressed by the client, then re-compressed by the server.
             .gzip(true)
             // Enables async resolver through the `hickory-dns` crate, which uses an LRU cache for
             // the resolved entries. This helps to limit the amount of requests made to upstream DNS
             // server (important for K8s infrastructure).
-            .hickory_dns(true)
-            .build()
-            .unwrap();
+            .hickory_dns(true);
+
+        if let Some(max_idle) = config.http_pool_max_idle_per_host() {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+
+        builder = match config.http_protocol() {
+            HttpProtocol::Http1 => builder.http1_only(),
+            // HTTP/2 is negotiated via ALPN during the TLS handshake.
+            HttpProtocol::Auto => builder,
+            HttpProtocol::Http2 => builder.http2_prior_knowledge(),
+        };
 
-        Self { config, reqwest }
+        builder.build().unwrap()
+    }
+
+    /// Returns the client for requests of the given priority.
+    fn client(&self, priority: RequestPriority) -> &reqwest::Client {
+        match priority {
+            RequestPriority::High => &self.high,
+            RequestPriority::Low => &self.low,
+        }
     }
 
     /// Builds the request in a non-blocking fashion.
@@ -759,6 +793,7 @@ impl SharedClient {
     /// asynchronous runtime.
     fn build_request(
         &self,
+        client: &reqwest::Client,
         request: &mut dyn UpstreamRequest,
     ) -> Result<reqwest::Request, UpstreamRequestError> {
         tokio::task::block_in_place(|| {
@@ -772,7 +807,7 @@ impl SharedClient {
                 .http_host_header()
                 .unwrap_or_else(|| self.config.upstream_descriptor().host());
 
-            let mut builder = RequestBuilder::reqwest(self.reqwest.request(request.method(), url));
+            let mut builder = RequestBuilder::reqwest(client.request(request.method(), url));
             builder.header("Host", host_header.as_bytes());
 
             if request.set_relay_id() {
@@ -856,17 +891,23 @@ impl SharedClient {
     }
 
     /// Builds and sends a request to the upstream, returning either a response or the error.
+    ///
+    /// The request is sent through the connection pool of the given priority.
     pub async fn send(
         &self,
         request: &mut dyn UpstreamRequest,
+        priority: RequestPriority,
     ) -> Result<Response, UpstreamRequestError> {
         request.configure(&self.config);
-        let client_request = self.build_request(request)?;
-        let response = self.reqwest.execute(client_request).await?;
+        let client = self.client(priority);
+        let client_request = self.build_request(client, request)?;
+        let response = client.execute(client_request).await?;
         self.transform_response(request, Response(response)).await
     }
 
     /// Convenience method to send a query to the upstream and await the result.
+    ///
+    /// Queries sent directly through the client always use the high priority connection pool.
     pub async fn send_query<T>(&self, query: T) -> Result<T::Response, UpstreamRequestError>
     where
         T: UpstreamQuery + 'static,
@@ -874,7 +915,7 @@ impl SharedClient {
         let (sender, receiver) = AsyncResponse::channel();
 
         let mut request = Box::new(UpstreamQueryRequest::new(query, sender));
-        let result = self.send(request.as_mut()).await;
+        let result = self.send(request.as_mut(), RequestPriority::High).await;
         request.respond(result).await;
 
         receiver
@@ -895,6 +936,8 @@ struct Entry {
     /// This starts with `0` and is incremented every time a request is placed back into the queue
     /// following a network error.
     pub retries: usize,
+    /// The time at which the entry was last placed into the queue.
+    pub enqueued_at: Instant,
 }
 
 impl Entry {
@@ -903,6 +946,7 @@ impl Entry {
         Self {
             request,
             retries: 0,
+            enqueued_at: Instant::now(),
         }
     }
 }
@@ -973,7 +1017,8 @@ impl UpstreamQueue {
     ///
     /// It also schedules the next retry time, based on the retry back off. The
     /// retry queue is not dequeued until the next retry has elapsed.
-    pub fn retry(&mut self, entry: Entry) {
+    pub fn retry(&mut self, mut entry: Entry) {
+        entry.enqueued_at = Instant::now();
         let priority = entry.request.priority();
         match priority {
             RequestPriority::High => self.retry_high.push_back(entry),
@@ -992,19 +1037,31 @@ impl UpstreamQueue {
     /// Dequeues the entry with highest priority.
     ///
     /// Highest priority entry is determined by (1) request priority and (2)
-    /// retries first.
-    pub fn dequeue(&mut self) -> Option<Entry> {
+    /// retries first. Low priority entries are only dequeued if `allow_low` is
+    /// `true`.
+    pub fn dequeue(&mut self, allow_low: bool) -> Option<Entry> {
         let should_retry = self.next_retry <= Instant::now();
+        let should_retry_low = should_retry && allow_low;
 
-        if let Some(Some(entry)) = should_retry.then(|| self.retry_high.pop_front()) {
-            Some(entry)
+        let entry = if let Some(Some(entry)) = should_retry.then(|| self.retry_high.pop_front()) {
+            entry
         } else if let Some(entry) = self.high.pop_front() {
-            Some(entry)
-        } else if let Some(Some(entry)) = should_retry.then(|| self.retry_low.pop_front()) {
-            Some(entry)
+            entry
+        } else if let Some(Some(entry)) = should_retry_low.then(|| self.retry_low.pop_front()) {
+            entry
+        } else if allow_low {
+            self.low.pop_front()?
         } else {
-            self.low.pop_front()
-        }
+            return None;
+        };
+
+        relay_statsd::metric!(
+            timer(RelayTimers::UpstreamQueueWaitDuration) = entry.enqueued_at.elapsed(),
+            priority = entry.request.priority().name(),
+            attempt = if entry.retries == 0 { "first" } else { "retry" }
+        );
+
+        Some(entry)
     }
 
     /// Starts retrying queued requests.
@@ -1076,11 +1133,11 @@ enum Action {
     ///
     /// The entry is placed on the front of the [`UpstreamQueue`].
     Retry(Entry),
-    /// Notifies completion of a request with a given outcome.
+    /// Notifies completion of a request with a given priority and outcome.
     ///
     /// Dropped request that need retries will additionally invoke the [`Retry`](Self::Retry)
     /// action.
-    Complete(RequestOutcome),
+    Complete(RequestPriority, RequestOutcome),
     /// Previously lost connection has been regained.
     ///
     /// This message is delivered to the [`ConnectionMonitor`] instance.
@@ -1155,7 +1212,12 @@ impl AuthMonitor {
             AuthState::Registering
         })?;
 
-        let request = RegisterRequest::new(&credentials.id, &credentials.public_key);
+        let next_public_key = credentials
+            .next
+            .as_ref()
+            .map(|next| next.public_key.clone());
+        let request = RegisterRequest::new(&credentials.id, &credentials.public_key)
+            .with_next_public_key(next_public_key);
         let challenge = self.client.send_query(request).await?;
         relay_log::debug!(token = challenge.token(), "got register challenge");
 
@@ -1316,7 +1378,10 @@ impl ConnectionMonitor {
             relay_log::warn!("network outage, scheduling another check in {next_backoff:?}");
 
             tokio::time::sleep(next_backoff).await;
-            match client.send(&mut GetHealthCheck).await {
+            match client
+                .send(&mut GetHealthCheck, RequestPriority::High)
+                .await
+            {
                 // All errors that are not connection errors are considered a successful attempt
                 Err(e) if e.is_network_error() => continue,
                 _ => break,
@@ -1374,6 +1439,14 @@ struct UpstreamBroker {
     auth_state: AuthState,
     conn: ConnectionMonitor,
     permits: usize,
+    /// Remaining slots for concurrent low priority requests.
+    ///
+    /// Low priority requests additionally consume one of the overall `permits`.
+    low_permits: usize,
+    /// Number of high priority requests in flight, used for metrics.
+    high_in_flight: usize,
+    /// Number of low priority requests in flight, used for metrics.
+    low_in_flight: usize,
     action_tx: ActionTx,
 }
 
@@ -1382,7 +1455,8 @@ impl UpstreamBroker {
     ///
     /// This returns `None` in any of the following conditions:
     ///  - Maximum request concurrency has been reached. A slot will be reclaimed through
-    ///    [`Action::Complete`].
+    ///    [`Action::Complete`]. Once the concurrency limit for low priority requests is reached,
+    ///    only high priority requests are returned.
     ///  - The connection is in outage state and all outgoing requests are suspended. Outage state
     ///    will be reset through [`Action::Connected`].
     ///  - Relay is not authenticated, including failed renewals. Auth state will be updated through
@@ -1394,8 +1468,19 @@ impl UpstreamBroker {
             return None;
         }
 
-        let entry = self.queue.dequeue()?;
+        let entry = self.queue.dequeue(self.low_permits > 0)?;
+        let priority = entry.request.priority();
+
         self.permits -= 1;
+        match priority {
+            RequestPriority::High => self.high_in_flight += 1,
+            RequestPriority::Low => {
+                self.low_permits -= 1;
+                self.low_in_flight += 1;
+            }
+        }
+        self.emit_in_flight(priority);
+
         Some(entry)
     }
 
@@ -1433,8 +1518,9 @@ impl UpstreamBroker {
         let action_tx = self.action_tx.clone();
 
         relay_system::spawn!(async move {
+            let priority = entry.request.priority();
             let send_start = Instant::now();
-            let result = client.send(entry.request.as_mut()).await;
+            let result = client.send(entry.request.as_mut(), priority).await;
             emit_response_metrics(send_start, &entry, &result);
 
             let status = match result {
@@ -1453,13 +1539,21 @@ impl UpstreamBroker {
             // Send an action back to the action channel of the broker, which will invoke
             // `handle_action`. This is to let the broker know in a synchronized fashion that the
             // request has finished and may need to be retried (above).
-            action_tx.send(Action::Complete(status)).ok();
+            action_tx.send(Action::Complete(priority, status)).ok();
         });
     }
 
     /// Marks completion of a running request and reclaims its slot.
-    fn complete(&mut self, status: RequestOutcome) {
+    fn complete(&mut self, priority: RequestPriority, status: RequestOutcome) {
         self.permits += 1;
+        match priority {
+            RequestPriority::High => self.high_in_flight -= 1,
+            RequestPriority::Low => {
+                self.low_permits += 1;
+                self.low_in_flight -= 1;
+            }
+        }
+        self.emit_in_flight(priority);
 
         match status {
             RequestOutcome::Dropped => self.conn.notify_error(&self.action_tx),
@@ -1470,11 +1564,24 @@ impl UpstreamBroker {
         }
     }
 
+    /// Emits the number of requests in flight for the given priority.
+    fn emit_in_flight(&self, priority: RequestPriority) {
+        let in_flight = match priority {
+            RequestPriority::High => self.high_in_flight,
+            RequestPriority::Low => self.low_in_flight,
+        };
+
+        relay_statsd::metric!(
+            gauge(RelayGauges::UpstreamRequestsInFlight) = in_flight as u64,
+            priority = priority.name()
+        );
+    }
+
     /// Handler of the internal action channel.
     fn handle_action(&mut self, action: Action) {
         match action {
             Action::Retry(request) => self.queue.retry(request),
-            Action::Complete(status) => self.complete(status),
+            Action::Complete(priority, status) => self.complete(priority, status),
             Action::Connected => self.conn.reset_error(),
             Action::UpdateAuth(state) => self.auth_state = state,
         }
@@ -1525,6 +1632,9 @@ impl Service for UpstreamRelayService {
             auth_state: AuthState::init(&config),
             conn: ConnectionMonitor::new(client),
             permits: config.max_concurrent_requests(),
+            low_permits: config.max_concurrent_low_priority_requests(),
+            high_in_flight: 0,
+            low_in_flight: 0,
             action_tx,
         };
 
@@ -1541,3 +1651,105 @@ impl Service for UpstreamRelayService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestRequest(RequestPriority);
+
+    impl UpstreamRequest for TestRequest {
+        fn method(&self) -> Method {
+            Method::GET
+        }
+
+        fn path(&self) -> Cow<'_, str> {
+            Cow::Borrowed("/")
+        }
+
+        fn priority(&self) -> RequestPriority {
+            self.0
+        }
+
+        fn route(&self) -> &'static str {
+            "test"
+        }
+
+        fn respond(
+            self: Box<Self>,
+            _result: Result<Response, UpstreamRequestError>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> {
+            Box::pin(async {})
+        }
+    }
+
+    fn entry(priority: RequestPriority) -> Entry {
+        Entry::new(Box::new(TestRequest(priority)))
+    }
+
+    #[tokio::test]
+    async fn test_dequeue_low_priority_limit() {
+        let mut queue = UpstreamQueue::new(Duration::ZERO);
+        queue.enqueue(entry(RequestPriority::Low));
+        queue.enqueue(entry(RequestPriority::High));
+        queue.retry(entry(RequestPriority::Low));
+
+        let next = queue.dequeue(false).unwrap();
+        assert_eq!(next.request.priority(), RequestPriority::High);
+
+        // Both low priority entries remain queued while low priority requests are not allowed.
+        assert!(queue.dequeue(false).is_none());
+        assert_eq!(queue.len(), 2);
+
+        assert!(queue.dequeue(true).is_some());
+        assert!(queue.dequeue(true).is_some());
+        assert!(queue.dequeue(true).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_not_starved() {
+        let config = Arc::new(
+            Config::from_json_value(serde_json::json!({
+                "relay": {
+                    "mode": "proxy"
+                },
+                "limits": {
+                    "max_concurrent_requests": 5
+                }
+            }))
+            .unwrap(),
+        );
+
+        let client = SharedClient::build(config.clone());
+        let (action_tx, _action_rx) = mpsc::unbounded_channel();
+        let mut broker = UpstreamBroker {
+            client: client.clone(),
+            queue: UpstreamQueue::new(Duration::ZERO),
+            auth_state: AuthState::init(&config),
+            conn: ConnectionMonitor::new(client),
+            permits: config.max_concurrent_requests(),
+            low_permits: config.max_concurrent_low_priority_requests(),
+            high_in_flight: 0,
+            low_in_flight: 0,
+            action_tx,
+        };
+
+        for _ in 0..10 {
+            broker.queue.enqueue(entry(RequestPriority::Low));
+        }
+
+        // Low priority requests saturate their limit, but not all permits.
+        let mut low = 0;
+        while let Some(next) = broker.next_request().await {
+            assert_eq!(next.request.priority(), RequestPriority::Low);
+            low += 1;
+        }
+        assert_eq!(low, 4);
+        assert_eq!(broker.queue.len(), 6);
+
+        broker.queue.enqueue(entry(RequestPriority::High));
+        let next = broker.next_request().await.unwrap();
+        assert_eq!(next.request.priority(), RequestPriority::High);
+    }
+}
@@ -56,6 +56,15 @@ pub enum RelayGauges {
     /// The number of futures waiting to grab a connection.
     #[cfg(feature = "processing")]
     RedisPoolWaitingForConnection,
+    /// The state of the circuit breaker of a Redis client.
+    ///
+    /// The value is `0` if the breaker is closed, `1` if it is half-open and probes Redis and `2`
+    /// if it is open and requests are not sent to Redis.
+    ///
+    /// This metric is tagged with:
+    ///  - `pool`: the name of the Redis client.
+    #[cfg(feature = "processing")]
+    RedisCircuitBreakerState,
     /// The number of notifications in the broadcast channel of the project cache.
     ProjectCacheNotificationChannel,
     /// The number of scheduled and in progress fetches in the project cache.
@@ -77,6 +86,24 @@ pub enum RelayGauges {
     /// - `service`: the service name.
     /// - `instance_id`: a for the service name unique identifier for the running service
     ServiceUtilization,
+    /// The supervision state of a service.
+    ///
+    /// The value is `0` if the service is running, `1` if it panicked and is waiting to be
+    /// restarted and `2` if it failed and will not be restarted.
+    ///
+    /// This metric is tagged with:
+    /// - `service`: the service name.
+    /// - `critical`: `true` if Relay is unhealthy while the service is not running.
+    ServiceSupervisionStatus,
+    /// The number of upstream requests currently in flight.
+    ///
+    /// The number of concurrent requests can be configured with:
+    ///  - `limits.max_concurrent_requests` for the overall number of requests
+    ///  - `limits.max_concurrent_low_priority_requests` for the number of low priority requests
+    ///
+    /// This metric is tagged with:
+    ///  - `priority`: The queueing priority of the request, either `"high"` or `"low"`.
+    UpstreamRequestsInFlight,
 }
 
 impl GaugeMetric for RelayGauges {
@@ -98,6 +125,8 @@ impl GaugeMetric for RelayGauges {
             RelayGauges::RedisPoolMaxConnections => "redis.pool.max_connections",
             #[cfg(feature = "processing")]
             RelayGauges::RedisPoolWaitingForConnection => "redis.pool.waiting_for_connection",
+            #[cfg(feature = "processing")]
+            RelayGauges::RedisCircuitBreakerState => "redis.circuit_breaker.state",
             RelayGauges::ProjectCacheNotificationChannel => {
                 "project_cache.notification_channel.size"
             }
@@ -106,6 +135,8 @@ impl GaugeMetric for RelayGauges {
             #[cfg(feature = "processing")]
             RelayGauges::MetricDelayMax => "metrics.delay.max",
             RelayGauges::ServiceUtilization => "service.utilization",
+            RelayGauges::ServiceSupervisionStatus => "service.supervision.status",
+            RelayGauges::UpstreamRequestsInFlight => "upstream.requests.in_flight",
         }
     }
 }
@@ -500,6 +531,16 @@ pub enum RelayTimers {
     ///   - `status-code`: The status code of the request when available, otherwise "-".
     ///   - `retries`: Number of retries bucket 0, 1, 2, few (3 - 10), many (more than 10).
     UpstreamRequestsDuration,
+    /// Time an upstream request waited in the queue before it was sent.
+    ///
+    /// Requests wait in the queue while the concurrency limits for upstream requests are reached,
+    /// during network outages, and while Relay is not authenticated.
+    ///
+    /// This metric is tagged with:
+    ///
+    ///   - `priority`: The queueing priority of the request, either `"high"` or `"low"`.
+    ///   - `attempt`: `"first"` for new requests and `"retry"` for retried requests.
+    UpstreamQueueWaitDuration,
     /// The delay between the timestamp stated in a payload and the receive time.
     ///
     /// SDKs cannot transmit payloads immediately in all cases. Sometimes, crashes require that
@@ -620,6 +661,7 @@ impl TimerMetric for RelayTimers {
             RelayTimers::ViewHierarchyScrubbing => "scrubbing.view_hierarchy_scrubbing.duration",
             RelayTimers::AttachmentScrubbing => "scrubbing.attachments.duration",
             RelayTimers::UpstreamRequestsDuration => "upstream.requests.duration",
+            RelayTimers::UpstreamQueueWaitDuration => "upstream.requests.queue_wait",
             RelayTimers::TimestampDelay => "requests.timestamp_delay",
             RelayTimers::OutcomeAggregatorFlushTime => "outcomes.aggregator.flush_time",
             RelayTimers::ReplayRecordingProcessing => "replay.recording.process",
@@ -730,6 +772,11 @@ pub enum RelayCounters {
     ///  - `invalid`: Data was considered invalid and could not be recovered. The reason indicates
     ///    the validation that failed.
     Outcomes,
+    /// Number of outcomes dropped by the local outcome store.
+    ///
+    /// Outcomes are dropped if the store keeps too many aggregated outcomes in memory because
+    /// writing to its database keeps failing. The metric counts outcome messages, not quantities.
+    OutcomeStoreDropped,
     /// Number of project state HTTP requests.
     ///
     /// Relay updates projects in batches. Every update cycle, Relay requests
@@ -750,6 +797,18 @@ pub enum RelayCounters {
     ///     - `false`: the request will be sent to the sentry endpoint.
     #[cfg(feature = "processing")]
     ProjectStateRedis,
+    /// Number of times a consumer of Redis fell back to its degraded behavior because the circuit
+    /// breaker of its Redis client is open.
+    ///
+    /// This metric is tagged with:
+    ///  - `consumer`: The consumer of Redis, one of `rate_limits`, `cardinality_limits` or
+    ///    `project_config`.
+    ///  - `behavior`: The degraded behavior, one of:
+    ///     - `cached`: rate limits cached in the project are enforced instead.
+    ///     - `accept`: the data is accepted without checking limits.
+    ///     - `upstream`: the project config is fetched from the upstream instead.
+    #[cfg(feature = "processing")]
+    RedisDegraded,
     /// Number of times a project had a fetch scheduled.
     ProjectCacheSchedule,
     /// Number of times an upstream request for a project config is completed.
@@ -926,9 +985,12 @@ impl CounterMetric for RelayCounters {
             RelayCounters::BufferProjectChangedEvent => "buffer.project_changed_event",
             RelayCounters::BufferProjectPending => "buffer.project_pending",
             RelayCounters::Outcomes => "events.outcomes",
+            RelayCounters::OutcomeStoreDropped => "outcomes.store.dropped",
             RelayCounters::ProjectStateRequest => "project_state.request",
             #[cfg(feature = "processing")]
             RelayCounters::ProjectStateRedis => "project_state.redis.requests",
+            #[cfg(feature = "processing")]
+            RelayCounters::RedisDegraded => "redis.degraded",
             RelayCounters::ProjectUpstreamCompleted => "project_upstream.completed",
             RelayCounters::ProjectUpstreamFailed => "project_upstream.failed",
             RelayCounters::ProjectCacheSchedule => "project_cache.schedule",
@@ -0,0 +1,1019 @@
+//! Parsing of Apple crash reports into native events.
+//!
+//! Two formats are supported:
+//!
+//!  - The JSON-based `.ips` format written since iOS 15 and macOS 12. It consists of a single line
+//!    JSON header followed by a JSON payload.
+//!  - The legacy plain text `.crash` format, which is also used as payload of older `.ips` files.
+//!
+//! Both formats are parsed into an [`AppleCrashReport`], which is then written into the event by
+//! [`write_apple_crash_report`]. The event retains the `applecrashreport` mechanism so that Sentry
+//! still symbolicates the report.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use relay_event_schema::protocol::{
+    Addr, AppContext, Contexts, DebugImage, DebugMeta, DeviceContext, Event, Exception, Frame,
+    JsonLenientString, MachException, Mechanism, MechanismMeta, NativeDebugImage, NativeImagePath,
+    OsContext, PosixSignal, RawStacktrace, RegVal, Stacktrace, Thread, ThreadId, Values,
+};
+use relay_protocol::{Annotated, Object};
+use serde::Deserialize;
+
+/// Date format of timestamps in both the JSON and the text format.
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f %z";
+
+/// An error returned when parsing an Apple crash report.
+#[derive(Debug, thiserror::Error)]
+pub enum AppleCrashReportError {
+    /// The crash report is not valid UTF-8.
+    #[error("crash report is not valid UTF-8")]
+    InvalidUtf8(#[from] std::str::Utf8Error),
+    /// The JSON header or payload of an `.ips` file could not be parsed.
+    #[error("invalid ips crash report")]
+    InvalidJson(#[from] serde_json::Error),
+    /// The crash report does not contain any threads.
+    #[error("crash report does not contain threads")]
+    NoThreads,
+}
+
+/// An Apple crash report, independent of the format it was parsed from.
+#[derive(Debug, Default)]
+pub struct AppleCrashReport {
+    /// Time of the crash.
+    timestamp: Option<DateTime<Utc>>,
+    /// Name of the crashed process.
+    process_name: Option<String>,
+    /// Bundle identifier of the application.
+    bundle_id: Option<String>,
+    /// User-visible version of the application (`CFBundleShortVersionString`).
+    app_version: Option<String>,
+    /// Build number of the application (`CFBundleVersion`).
+    app_build: Option<String>,
+    /// Operating system name, for example `iOS`.
+    os_name: Option<String>,
+    /// Operating system version, for example `16.4.1`.
+    os_version: Option<String>,
+    /// Operating system build, for example `20E252`.
+    os_build: Option<String>,
+    /// Hardware model identifier, for example `iPhone14,5`.
+    model: Option<String>,
+    /// Normalized CPU architecture of the process.
+    arch: Option<String>,
+    /// The exception that terminated the process.
+    exception: Option<CrashException>,
+    /// Application specific information, such as the message of a failed assertion.
+    application_info: Option<String>,
+    /// All threads at the time of the crash.
+    threads: Vec<CrashThread>,
+    /// Binary images loaded into the process.
+    images: Vec<BinaryImage>,
+}
+
+#[derive(Debug, Default)]
+struct CrashException {
+    /// Name of the Mach exception, for example `EXC_BAD_ACCESS`.
+    ty: String,
+    /// Name of the signal, for example `SIGSEGV`.
+    signal: Option<String>,
+    /// Exception code and subcode.
+    codes: Vec<u64>,
+}
+
+#[derive(Debug, Default)]
+struct CrashThread {
+    /// Index of the thread in the crash report.
+    index: u64,
+    /// Name of the thread or the dispatch queue it was running.
+    name: Option<String>,
+    /// Whether this thread triggered the crash.
+    crashed: bool,
+    /// Stack frames starting with the innermost frame.
+    frames: Vec<CrashFrame>,
+    /// Register values, only available for the crashed thread.
+    registers: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+struct CrashFrame {
+    instruction_addr: u64,
+    image_addr: Option<u64>,
+    package: Option<String>,
+    function: Option<String>,
+    filename: Option<String>,
+    lineno: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct BinaryImage {
+    addr: u64,
+    size: u64,
+    name: Option<String>,
+    path: Option<String>,
+    arch: Option<String>,
+    uuid: Option<String>,
+}
+
+impl BinaryImage {
+    /// Returns the path of the image, falling back to its name.
+    fn code_file(&self) -> Option<&str> {
+        self.path.as_deref().or(self.name.as_deref())
+    }
+}
+
+/// Returns the image containing the given address.
+fn find_image(images: &[BinaryImage], addr: u64) -> Option<&BinaryImage> {
+    images
+        .iter()
+        .find(|image| addr >= image.addr && addr - image.addr < image.size)
+}
+
+impl AppleCrashReport {
+    /// Parses an Apple crash report in either the `.ips` or the `.crash` format.
+    pub fn parse(data: &[u8]) -> Result<Self, AppleCrashReportError> {
+        let text = std::str::from_utf8(data)?.trim_start_matches('\u{feff}');
+
+        let report = if text.trim_start().starts_with('{') {
+            parse_ips(text.trim_start())?
+        } else {
+            parse_text(text)
+        };
+
+        if report.threads.is_empty() {
+            return Err(AppleCrashReportError::NoThreads);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Header line of an `.ips` file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IpsHeader {
+    app_name: Option<String>,
+    app_version: Option<String>,
+    build_version: Option<String>,
+    #[serde(rename = "bundleID")]
+    bundle_id: Option<String>,
+    os_version: Option<String>,
+    timestamp: Option<String>,
+}
+
+/// Payload of an `.ips` file with `bug_type` 309.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct IpsPayload {
+    capture_time: Option<String>,
+    model_code: Option<String>,
+    cpu_type: Option<String>,
+    proc_name: Option<String>,
+    os_version: Option<IpsOsVersion>,
+    bundle_info: Option<IpsBundleInfo>,
+    exception: Option<IpsException>,
+    asi: Option<BTreeMap<String, Vec<String>>>,
+    faulting_thread: Option<usize>,
+    threads: Vec<IpsThread>,
+    used_images: Vec<IpsImage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IpsOsVersion {
+    train: Option<String>,
+    build: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IpsBundleInfo {
+    #[serde(rename = "CFBundleShortVersionString")]
+    short_version: Option<String>,
+    #[serde(rename = "CFBundleVersion")]
+    version: Option<String>,
+    #[serde(rename = "CFBundleIdentifier")]
+    identifier: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct IpsException {
+    #[serde(rename = "type")]
+    ty: Option<String>,
+    signal: Option<String>,
+    raw_codes: Vec<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct IpsThread {
+    name: Option<String>,
+    queue: Option<String>,
+    triggered: bool,
+    frames: Vec<IpsFrame>,
+    thread_state: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct IpsFrame {
+    image_offset: u64,
+    image_index: Option<usize>,
+    symbol: Option<String>,
+    source_file: Option<String>,
+    source_line: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IpsImage {
+    base: u64,
+    size: u64,
+    uuid: Option<String>,
+    path: Option<String>,
+    name: Option<String>,
+    arch: Option<String>,
+}
+
+/// Parses the JSON-based `.ips` format.
+///
+/// Older `.ips` files contain a JSON header followed by a crash report in the text format, which
+/// is also supported.
+fn parse_ips(text: &str) -> Result<AppleCrashReport, AppleCrashReportError> {
+    let (header, body) = text.split_once('\n').unwrap_or((text, ""));
+    let header: IpsHeader = serde_json::from_str(header)?;
+
+    let mut report = if body.trim_start().starts_with('{') {
+        let payload: IpsPayload = serde_json::from_str(body)?;
+        convert_ips_payload(payload)
+    } else {
+        parse_text(body)
+    };
+
+    report.timestamp = report
+        .timestamp
+        .or_else(|| parse_timestamp(header.timestamp.as_deref()?));
+    report.process_name = report.process_name.or(header.app_name);
+    report.bundle_id = report.bundle_id.or(header.bundle_id);
+    report.app_version = report.app_version.or(header.app_version);
+    report.app_build = report.app_build.or(header.build_version);
+
+    if report.os_name.is_none()
+        && let Some((name, version, build)) = header.os_version.as_deref().map(parse_os_version)
+    {
+        report.os_name = name;
+        report.os_version = version;
+        report.os_build = build;
+    }
+
+    Ok(report)
+}
+
+fn convert_ips_payload(payload: IpsPayload) -> AppleCrashReport {
+    let images: Vec<_> = payload
+        .used_images
+        .into_iter()
+        .map(|image| BinaryImage {
+            addr: image.base,
+            size: image.size,
+            name: image.name,
+            path: image.path,
+            arch: image.arch,
+            uuid: image.uuid,
+        })
+        .collect();
+
+    let threads = payload
+        .threads
+        .into_iter()
+        .enumerate()
+        .map(|(index, thread)| {
+            let crashed = thread.triggered || payload.faulting_thread == Some(index);
+
+            let frames = thread
+                .frames
+                .into_iter()
+                .map(|frame| {
+                    let image = frame.image_index.and_then(|i| images.get(i));
+                    CrashFrame {
+                        // Offsets are untrusted, an out of range address must not panic.
+                        instruction_addr: image
+                            .map_or(0, |i| i.addr)
+                            .wrapping_add(frame.image_offset),
+                        image_addr: image.map(|i| i.addr),
+                        package: image.and_then(|i| i.code_file()).map(str::to_owned),
+                        function: frame.symbol,
+                        filename: frame.source_file,
+                        lineno: frame.source_line,
+                    }
+                })
+                .collect();
+
+            CrashThread {
+                index: index as u64,
+                name: thread.name.or(thread.queue),
+                crashed,
+                frames,
+                registers: ips_registers(&thread.thread_state),
+            }
+        })
+        .collect();
+
+    let (os_name, os_version) = match payload.os_version.as_ref().and_then(|v| v.train.as_ref()) {
+        Some(train) => {
+            let (name, version, _) = parse_os_version(train);
+            (name, version)
+        }
+        None => (None, None),
+    };
+
+    let bundle_info = payload.bundle_info.unwrap_or_default();
+
+    AppleCrashReport {
+        timestamp: payload.capture_time.as_deref().and_then(parse_timestamp),
+        process_name: payload.proc_name,
+        bundle_id: bundle_info.identifier,
+        app_version: bundle_info.short_version,
+        app_build: bundle_info.version,
+        os_name,
+        os_version,
+        os_build: payload.os_version.and_then(|v| v.build),
+        model: payload.model_code,
+        arch: payload.cpu_type.as_deref().map(normalize_arch),
+        exception: payload.exception.and_then(|exception| {
+            Some(CrashException {
+                ty: exception.ty?,
+                signal: exception.signal,
+                codes: exception.raw_codes,
+            })
+        }),
+        application_info: payload
+            .asi
+            .map(|asi| asi.into_values().flatten().collect::<Vec<_>>().join("\n"))
+            .filter(|info| !info.is_empty()),
+        threads,
+        images,
+    }
+}
+
+/// Extracts register values from the `threadState` of an `.ips` thread.
+///
+/// General purpose registers on ARM are listed in an array `x`, all other registers are objects
+/// with a `value` field.
+fn ips_registers(state: &BTreeMap<String, serde_json::Value>) -> BTreeMap<String, u64> {
+    let mut registers = BTreeMap::new();
+
+    for (name, value) in state {
+        if let Some(values) = value.as_array() {
+            for (index, value) in values.iter().enumerate() {
+                if let Some(value) = value.get("value").and_then(|v| v.as_u64()) {
+                    registers.insert(format!("{name}{index}"), value);
+                }
+            }
+        } else if let Some(value) = value.get("value").and_then(|v| v.as_u64()) {
+            registers.insert(name.clone(), value);
+        }
+    }
+
+    registers
+}
+
+/// Section of a text crash report that is currently being parsed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Section {
+    Header,
+    ApplicationInfo,
+    Thread,
+    Registers,
+    BinaryImages,
+    Other,
+}
+
+static THREAD_NAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Thread (\d+) name:\s*(?:Dispatch queue:\s*)?(.*)$").unwrap());
+static THREAD_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Thread (\d+)( Crashed)?:\s*$").unwrap());
+static THREAD_STATE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Thread (\d+) crashed with .* Thread State").unwrap());
+static FRAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\d+\s+(\S.*?)\s+(0x[0-9a-fA-F]+)\s+(.*?)\s*$").unwrap());
+static FRAME_OFFSET_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(0x[0-9a-fA-F]+) \+ \d+$").unwrap());
+static FRAME_SYMBOL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(.+?) \+ \d+(?: \((.+):(\d+)\))?$").unwrap());
+static REGISTER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([a-z][a-z0-9]*):\s*(0x[0-9a-fA-F]+)").unwrap());
+static IMAGE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(0x[0-9a-fA-F]+)\s*-\s*(0x[0-9a-fA-F]+)\s+\+?(.+?)\s+(?:(arm64e|arm64_32|arm64|armv7[a-z]*|x86_64h?|i386)\s+)?<([0-9a-fA-F-]+)>\s*(.*)$",
+    )
+    .unwrap()
+});
+
+/// Parses the legacy plain text `.crash` format.
+fn parse_text(text: &str) -> AppleCrashReport {
+    let mut report = AppleCrashReport::default();
+    let mut headers = BTreeMap::new();
+    let mut thread_names = BTreeMap::new();
+    let mut crashed_thread = None;
+    let mut application_info = Vec::new();
+    let mut registers = BTreeMap::new();
+    let mut section = Section::Header;
+
+    for line in text.lines() {
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            if section != Section::Header {
+                section = Section::Other;
+            }
+            continue;
+        }
+
+        if let Some(captures) = THREAD_NAME_REGEX.captures(line) {
+            let index: u64 = captures[1].parse().unwrap_or_default();
+            thread_names.insert(index, captures[2].trim().to_owned());
+            continue;
+        }
+
+        if let Some(captures) = THREAD_REGEX.captures(line) {
+            let index = captures[1].parse().unwrap_or_default();
+            report.threads.push(CrashThread {
+                index,
+                crashed: captures.get(2).is_some(),
+                ..Default::default()
+            });
+            section = Section::Thread;
+            continue;
+        }
+
+        if let Some(captures) = THREAD_STATE_REGEX.captures(line) {
+            crashed_thread = captures[1].parse().ok();
+            section = Section::Registers;
+            continue;
+        }
+
+        match line {
+            "Application Specific Information:" => {
+                section = Section::ApplicationInfo;
+                continue;
+            }
+            "Binary Images:" => {
+                section = Section::BinaryImages;
+                continue;
+            }
+            _ if line.ends_with(':') && !line.starts_with(' ') => {
+                // Unknown sections, such as the last exception backtrace or a thread state that
+                // did not match above.
+                section = Section::Other;
+                continue;
+            }
+            _ => (),
+        }
+
+        match section {
+            Section::Header => {
+                if let Some((key, value)) = line.split_once(':') {
+                    headers.insert(key.trim(), value.trim());
+                }
+            }
+            Section::ApplicationInfo => application_info.push(line.trim()),
+            Section::Thread => {
+                if let (Some(thread), Some(frame)) = (report.threads.last_mut(), parse_frame(line))
+                {
+                    thread.frames.push(frame);
+                }
+            }
+            Section::Registers => {
+                for captures in REGISTER_REGEX.captures_iter(line) {
+                    if let Some(value) = parse_hex(&captures[2]) {
+                        registers.insert(captures[1].to_owned(), value);
+                    }
+                }
+            }
+            Section::BinaryImages => {
+                if let Some(image) = parse_image(line) {
+                    report.images.push(image);
+                }
+            }
+            Section::Other => (),
+        }
+    }
+
+    let triggered_by = ["Triggered by Thread", "Crashed Thread"]
+        .iter()
+        .filter_map(|key| headers.get(key))
+        .find_map(|value| value.split_whitespace().next()?.parse::<u64>().ok());
+
+    for thread in &mut report.threads {
+        thread.name = thread_names.remove(&thread.index);
+        thread.crashed |= triggered_by == Some(thread.index);
+        if thread.crashed && (crashed_thread.is_none() || crashed_thread == Some(thread.index)) {
+            thread.registers = std::mem::take(&mut registers);
+        }
+    }
+
+    // Frames in the text format contain absolute addresses. Resolve the containing image to fill
+    // in the image address and package.
+    for thread in &mut report.threads {
+        for frame in &mut thread.frames {
+            if let Some(image) = find_image(&report.images, frame.instruction_addr) {
+                frame.image_addr = Some(image.addr);
+                frame.package = image.code_file().map(str::to_owned);
+            }
+        }
+    }
+
+    if let Some(os_version) = headers.get("OS Version") {
+        let (name, version, build) = parse_os_version(os_version);
+        report.os_name = name;
+        report.os_version = version;
+        report.os_build = build;
+    }
+
+    report.timestamp = headers.get("Date/Time").and_then(|v| parse_timestamp(v));
+    report.process_name = headers
+        .get("Process")
+        .and_then(|v| v.split(" [").next())
+        .map(str::to_owned);
+    report.bundle_id = headers.get("Identifier").map(|v| (*v).to_owned());
+    report.model = headers.get("Hardware Model").map(|v| (*v).to_owned());
+    report.arch = headers
+        .get("Code Type")
+        .and_then(|v| v.split_whitespace().next())
+        .map(normalize_arch);
+
+    if let Some(version) = headers.get("Version") {
+        let (first, second) = split_parenthesized(version);
+        // iOS reports list the build number first, macOS reports list the version first.
+        if is_mobile_os(report.os_name.as_deref()) {
+            report.app_build = first;
+            report.app_version = second;
+        } else {
+            report.app_version = first;
+            report.app_build = second;
+        }
+    }
+
+    if let Some(exception_type) = headers.get("Exception Type") {
+        let (ty, signal) = split_parenthesized(exception_type);
+        let codes = headers
+            .get("Exception Codes")
+            .map(|codes| {
+                codes
+                    .split(',')
+                    .filter_map(|c| parse_hex(c.trim()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        report.exception = ty.map(|ty| CrashException { ty, signal, codes });
+    }
+
+    if !application_info.is_empty() {
+        report.application_info = Some(application_info.join("\n"));
+    }
+
+    report
+}
+
+/// Parses a single stack frame line of a text crash report.
+fn parse_frame(line: &str) -> Option<CrashFrame> {
+    let captures = FRAME_REGEX.captures(line)?;
+    let mut frame = CrashFrame {
+        instruction_addr: parse_hex(&captures[2])?,
+        package: Some(captures[1].to_owned()),
+        ..Default::default()
+    };
+
+    let location = &captures[3];
+    if let Some(offset) = FRAME_OFFSET_REGEX.captures(location) {
+        frame.image_addr = parse_hex(&offset[1]);
+    } else if let Some(symbol) = FRAME_SYMBOL_REGEX.captures(location) {
+        frame.function = Some(symbol[1].to_owned());
+        frame.filename = symbol.get(2).map(|m| m.as_str().to_owned());
+        frame.lineno = symbol.get(3).and_then(|m| m.as_str().parse().ok());
+    }
+
+    Some(frame)
+}
+
+/// Parses a single line of the binary images section of a text crash report.
+fn parse_image(line: &str) -> Option<BinaryImage> {
+    let captures = IMAGE_REGEX.captures(line)?;
+    let addr = parse_hex(&captures[1])?;
+    let end = parse_hex(&captures[2])?;
+    let path = captures[6].trim();
+
+    Some(BinaryImage {
+        addr,
+        size: end.checked_sub(addr)?.checked_add(1)?,
+        name: Some(captures[3].to_owned()),
+        path: (!path.is_empty()).then(|| path.to_owned()),
+        arch: captures.get(4).map(|m| m.as_str().to_owned()),
+        uuid: Some(captures[5].to_owned()),
+    })
+}
+
+/// Parses a hexadecimal number with a `0x` prefix.
+fn parse_hex(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(value.trim(), DATE_FORMAT)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Splits a value of the form `first (second)`.
+fn split_parenthesized(value: &str) -> (Option<String>, Option<String>) {
+    match value.split_once(" (") {
+        Some((first, second)) => (
+            Some(first.trim().to_owned()),
+            second.strip_suffix(')').map(|s| s.trim().to_owned()),
+        ),
+        None => (Some(value.trim().to_owned()), None),
+    }
+}
+
+/// Parses an OS version string like `iPhone OS 16.4.1 (20E252)` into name, version, and build.
+fn parse_os_version(value: &str) -> (Option<String>, Option<String>, Option<String>) {
+    let (name_version, build) = split_parenthesized(value);
+    let Some(name_version) = name_version else {
+        return (None, None, build);
+    };
+
+    let (name, version) = match name_version.rsplit_once(' ') {
+        Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+            (name, Some(version.to_owned()))
+        }
+        _ => (name_version.as_str(), None),
+    };
+
+    let name = match name {
+        "iPhone OS" | "iOS" => "iOS",
+        "iPadOS" => "iPadOS",
+        "Mac OS X" | "macOS" | "OS X" => "macOS",
+        "Watch OS" | "watchOS" => "watchOS",
+        "Apple TVOS" | "tvOS" => "tvOS",
+        "xrOS" | "visionOS" => "visionOS",
+        other => other,
+    };
+
+    (Some(name.to_owned()), version, build)
+}
+
+fn is_mobile_os(name: Option<&str>) -> bool {
+    matches!(
+        name,
+        Some("iOS" | "iPadOS" | "watchOS" | "tvOS" | "visionOS")
+    )
+}
+
+/// Converts a code type like `ARM-64` into the architecture name used by Sentry.
+fn normalize_arch(code_type: &str) -> String {
+    match code_type {
+        "ARM-64" => "arm64",
+        "ARM" => "arm",
+        "X86-64" => "x86_64",
+        "X86" => "x86",
+        other => return other.to_lowercase(),
+    }
+    .to_owned()
+}
+
+/// Returns the number of a Mach exception by its name.
+fn mach_exception_number(name: &str) -> Option<i64> {
+    Some(match name {
+        "EXC_BAD_ACCESS" => 1,
+        "EXC_BAD_INSTRUCTION" => 2,
+        "EXC_ARITHMETIC" => 3,
+        "EXC_EMULATION" => 4,
+        "EXC_SOFTWARE" => 5,
+        "EXC_BREAKPOINT" => 6,
+        "EXC_SYSCALL" => 7,
+        "EXC_MACH_SYSCALL" => 8,
+        "EXC_RPC_ALERT" => 9,
+        "EXC_CRASH" => 10,
+        "EXC_RESOURCE" => 11,
+        "EXC_GUARD" => 12,
+        "EXC_CORPSE_NOTIFY" => 13,
+        _ => return None,
+    })
+}
+
+/// Returns the number of a POSIX signal on Darwin by its name.
+fn signal_number(name: &str) -> Option<i64> {
+    Some(match name {
+        "SIGHUP" => 1,
+        "SIGINT" => 2,
+        "SIGQUIT" => 3,
+        "SIGILL" => 4,
+        "SIGTRAP" => 5,
+        "SIGABRT" => 6,
+        "SIGEMT" => 7,
+        "SIGFPE" => 8,
+        "SIGKILL" => 9,
+        "SIGBUS" => 10,
+        "SIGSEGV" => 11,
+        "SIGSYS" => 12,
+        "SIGPIPE" => 13,
+        "SIGALRM" => 14,
+        "SIGTERM" => 15,
+        _ => return None,
+    })
+}
+
+/// Sets the value of an empty field.
+fn set_default<T>(field: &mut Annotated<T>, value: Option<T>) {
+    if field.value().is_none()
+        && let Some(value) = value
+    {
+        field.set_value(Some(value));
+    }
+}
+
+fn convert_stacktrace(thread: &CrashThread) -> Stacktrace {
+    // Crash reports list the innermost frame first, Sentry expects it last.
+    let frames = thread
+        .frames
+        .iter()
+        .rev()
+        .map(|frame| {
+            Annotated::new(Frame {
+                instruction_addr: Annotated::new(Addr(frame.instruction_addr)),
+                image_addr: Annotated::from(frame.image_addr.map(Addr)),
+                package: Annotated::from(frame.package.clone()),
+                function: Annotated::from(frame.function.clone()),
+                filename: Annotated::from(frame.filename.clone().map(NativeImagePath)),
+                lineno: Annotated::from(frame.lineno),
+                ..Frame::default()
+            })
+        })
+        .collect();
+
+    let registers: Object<RegVal> = thread
+        .registers
+        .iter()
+        .map(|(name, value)| (name.clone(), Annotated::new(RegVal(*value))))
+        .collect();
+
+    Stacktrace(RawStacktrace {
+        frames: Annotated::new(frames),
+        registers: match registers.is_empty() {
+            true => Annotated::empty(),
+            false => Annotated::new(registers),
+        },
+        ..RawStacktrace::default()
+    })
+}
+
+fn convert_image(image: &BinaryImage) -> Option<DebugImage> {
+    let debug_id = image.uuid.as_deref()?.parse().ok()?;
+    let code_file = image.code_file()?;
+
+    if image.size == 0 {
+        return None;
+    }
+
+    Some(DebugImage::MachO(Box::new(NativeDebugImage {
+        code_file: Annotated::new(NativeImagePath(code_file.to_owned())),
+        debug_id: Annotated::new(debug_id),
+        arch: Annotated::from(image.arch.clone()),
+        image_addr: Annotated::new(Addr(image.addr)),
+        image_size: Annotated::new(image.size),
+        ..NativeDebugImage::default()
+    })))
+}
+
+/// Writes the contents of a parsed crash report into the event.
+///
+/// This replaces the exception and threads of the event. The timestamp, contexts and debug images
+/// are only added where the event does not contain them already.
+pub fn write_apple_crash_report(event: &mut Event, report: &AppleCrashReport) {
+    if event.timestamp.value().is_none() {
+        event.timestamp.set_value(report.timestamp.map(Into::into));
+    }
+
+    let crashed_thread = report
+        .threads
+        .iter()
+        .find(|thread| thread.crashed)
+        .or_else(|| report.threads.first());
+
+    // The crashed thread's stack trace is attached to the exception instead of the thread.
+    let threads = report
+        .threads
+        .iter()
+        .map(|thread| {
+            let is_crashed = crashed_thread.is_some_and(|t| t.index == thread.index);
+            Annotated::new(Thread {
+                id: Annotated::new(ThreadId::Int(thread.index)),
+                name: Annotated::from(thread.name.clone()),
+                crashed: Annotated::new(is_crashed),
+                current: Annotated::new(is_crashed),
+                stacktrace: match is_crashed {
+                    true => Annotated::empty(),
+                    false => Annotated::new(convert_stacktrace(thread)),
+                },
+                ..Thread::default()
+            })
+        })
+        .collect();
+    event.threads.set_value(Some(Values::new(threads)));
+
+    let mut meta = MechanismMeta::default();
+    let (ty, value) = match report.exception {
+        Some(ref exception) => {
+            meta.mach_exception.set_value(Some(MachException {
+                ty: Annotated::from(mach_exception_number(&exception.ty)),
+                code: Annotated::from(exception.codes.first().copied()),
+                subcode: Annotated::from(exception.codes.get(1).copied()),
+                name: Annotated::new(exception.ty.clone()),
+            }));
+
+            if let Some(ref signal) = exception.signal {
+                meta.signal.set_value(Some(PosixSignal {
+                    number: Annotated::from(signal_number(signal)),
+                    name: Annotated::new(signal.clone()),
+                    ..PosixSignal::default()
+                }));
+            }
+
+            let value = report
+                .application_info
+                .clone()
+                .or_else(|| exception.signal.clone());
+            (Some(exception.ty.clone()), value)
+        }
+        None => (None, report.application_info.clone()),
+    };
+
+    if let Some(exception) = event
+        .exceptions
+        .value_mut()
+        .as_mut()
+        .and_then(|values| values.values.value_mut().as_mut())
+        .and_then(|values| values.first_mut())
+        .and_then(|exception| exception.value_mut().as_mut())
+    {
+        update_exception(exception, ty, value, meta, crashed_thread);
+    }
+
+    let images: Vec<_> = report
+        .images
+        .iter()
+        .filter_map(convert_image)
+        .map(Annotated::new)
+        .collect();
+    if !images.is_empty() {
+        let debug_meta = event.debug_meta.get_or_insert_with(DebugMeta::default);
+        set_default(&mut debug_meta.images, Some(images));
+    }
+
+    let contexts = event.contexts.get_or_insert_with(Contexts::new);
+
+    let os = contexts.get_or_default::<OsContext>();
+    set_default(&mut os.name, report.os_name.clone());
+    set_default(&mut os.version, report.os_version.clone());
+    set_default(&mut os.build, report.os_build.clone().map(Into::into));
+
+    let device = contexts.get_or_default::<DeviceContext>();
+    set_default(&mut device.model, report.model.clone());
+    set_default(&mut device.arch, report.arch.clone());
+
+    let app = contexts.get_or_default::<AppContext>();
+    set_default(&mut app.app_identifier, report.bundle_id.clone());
+    set_default(&mut app.app_name, report.process_name.clone());
+    set_default(&mut app.app_version, report.app_version.clone());
+    set_default(&mut app.app_build, report.app_build.clone().map(Into::into));
+}
+
+fn update_exception(
+    exception: &mut Exception,
+    ty: Option<String>,
+    value: Option<String>,
+    meta: MechanismMeta,
+    crashed_thread: Option<&CrashThread>,
+) {
+    if let Some(ty) = ty {
+        exception.ty.set_value(Some(ty));
+    }
+    if let Some(value) = value {
+        exception.value.set_value(Some(JsonLenientString(value)));
+    }
+
+    if let Some(thread) = crashed_thread {
+        exception
+            .thread_id
+            .set_value(Some(ThreadId::Int(thread.index)));
+        exception
+            .stacktrace
+            .set_value(Some(convert_stacktrace(thread)));
+    }
+
+    let mechanism = exception.mechanism.get_or_insert_with(Mechanism::default);
+    mechanism.synthetic.set_value(None);
+    mechanism.meta.set_value(Some(meta));
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use relay_protocol::Annotated;
+
+    use super::*;
+
+    fn process(data: &[u8]) -> Event {
+        let mut event = Event {
+            exceptions: Annotated::new(Values::new(vec![Annotated::new(Exception {
+                ty: Annotated::new("AppleCrashReport".to_owned()),
+                mechanism: Annotated::new(Mechanism {
+                    ty: Annotated::new("applecrashreport".to_owned()),
+                    handled: Annotated::new(false),
+                    synthetic: Annotated::new(true),
+                    ..Mechanism::default()
+                }),
+                ..Exception::default()
+            })])),
+            ..Event::default()
+        };
+
+        let report = AppleCrashReport::parse(data).unwrap();
+        write_apple_crash_report(&mut event, &report);
+        event
+    }
+
+    #[test]
+    fn test_parse_ips() {
+        let data =
+            include_bytes!("../../../tests/integration/fixtures/native/apple_crash_report.ips");
+        let event = process(data);
+        insta::assert_snapshot!(Annotated::new(event).to_json_pretty().unwrap());
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let data =
+            include_bytes!("../../../tests/integration/fixtures/native/apple_crash_report.crash");
+        let event = process(data);
+        insta::assert_snapshot!(Annotated::new(event).to_json_pretty().unwrap());
+    }
+
+    #[test]
+    fn test_parse_text_find_image() {
+        let data =
+            include_bytes!("../../../tests/integration/fixtures/native/apple_crash_report.crash");
+        let report = AppleCrashReport::parse(data).unwrap();
+
+        let image = find_image(&report.images, 0x104a3c1a4).unwrap();
+        assert_eq!(image.name.as_deref(), Some("CrashProbe"));
+        assert_eq!(report.app_version.as_deref(), Some("1.4.0"));
+        assert_eq!(report.app_build.as_deref(), Some("142"));
+    }
+
+    #[test]
+    fn test_parse_address_overflow() {
+        let ips = br#"{"app_name": "CrashProbe", "bug_type": "309"}
+{
+  "threads": [{"triggered": true, "frames": [{"imageOffset": 16, "imageIndex": 0}]}],
+  "usedImages": [{"base": 18446744073709551615, "size": 16}]
+}"#;
+        let report = AppleCrashReport::parse(ips).unwrap();
+        assert_eq!(report.threads[0].frames[0].instruction_addr, 15);
+
+        let line = "0x0 - 0xffffffffffffffff CrashProbe arm64 <5c37f74b2a8e3a9da8a1a3e1d4e1c0b6> /CrashProbe";
+        assert!(parse_image(line).is_none());
+    }
+
+    #[test]
+    fn test_keep_event_timestamp() {
+        let data =
+            include_bytes!("../../../tests/integration/fixtures/native/apple_crash_report.ips");
+        let report = AppleCrashReport::parse(data).unwrap();
+
+        let timestamp = Utc.with_ymd_and_hms(2023, 5, 12, 9, 0, 0).unwrap();
+        let mut event = Event {
+            timestamp: Annotated::new(timestamp.into()),
+            ..Event::default()
+        };
+        write_apple_crash_report(&mut event, &report);
+        assert_eq!(event.timestamp.value().unwrap().into_inner(), timestamp);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(matches!(
+            AppleCrashReport::parse(b"not a crash report"),
+            Err(AppleCrashReportError::NoThreads)
+        ));
+        assert!(matches!(
+            AppleCrashReport::parse(b"{\"app_name\": \n{"),
+            Err(AppleCrashReportError::InvalidJson(_))
+        ));
+    }
+}
@@ -1,4 +1,7 @@
 mod api;
+// The parser has no processing dependencies, so it is also built and tested without them.
+#[cfg_attr(not(feature = "processing"), allow(dead_code))]
+mod apple_crash_report;
 mod dynamic_sampling;
 mod managed_envelope;
 mod multipart;
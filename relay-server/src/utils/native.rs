@@ -16,6 +16,8 @@ use relay_event_schema::protocol::{
 };
 use relay_protocol::{Annotated, Value};
 
+use super::apple_crash_report::{AppleCrashReport, write_apple_crash_report};
+
 type Minidump<'a> = minidump::Minidump<'a, &'a [u8]>;
 
 /// Placeholder payload fragments indicating a native event.
@@ -249,13 +251,26 @@ pub fn process_minidump(event: &mut Event, data: &[u8]) {
     }
 }
 
-/// Writes minimal information into the event to indicate it is associated with an Apple Crash
-/// Report.
-pub fn process_apple_crash_report(event: &mut Event, _data: &[u8]) {
+/// Extracts information from the Apple Crash Report and writes it into the given event.
+///
+/// Both the JSON-based `.ips` format and the legacy plain text format are supported. Like
+/// [`process_minidump`], this function operates at best-effort and always attaches the
+/// placeholder, even if the crash report cannot be parsed.
+pub fn process_apple_crash_report(event: &mut Event, data: &[u8]) {
     let placeholder = NativePlaceholder {
         exception_type: "AppleCrashReport",
         exception_value: "Invalid Apple Crash Report",
         mechanism_type: "applecrashreport",
     };
     write_native_placeholder(event, placeholder);
+
+    match AppleCrashReport::parse(data) {
+        Ok(report) => write_apple_crash_report(event, &report),
+        Err(err) => {
+            relay_log::debug!(
+                error = &err as &dyn Error,
+                "failed to parse apple crash report"
+            );
+        }
+    }
 }
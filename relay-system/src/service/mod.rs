@@ -17,12 +17,15 @@ use crate::{TaskId, spawn};
 
 mod registry;
 mod status;
+mod supervisor;
 
 pub(crate) use self::registry::Registry as ServiceRegistry;
 pub use self::registry::{ServiceId, ServiceMetrics, ServicesMetrics};
 pub use self::status::{
     ServiceError, ServiceJoinHandle, ServiceStatusError, ServiceStatusJoinHandle,
 };
+pub(crate) use self::supervisor::SupervisionState;
+pub use self::supervisor::{RestartPolicy, Supervision, SupervisionMetrics, SupervisionStatus};
 
 /// Interval for recording backlog metrics on service channels.
 const BACKLOG_INTERVAL: Duration = Duration::from_secs(1);
@@ -786,6 +789,14 @@ impl<I: Interface> Addr<I> {
     pub fn dummy() -> Self {
         Self::custom().0
     }
+
+    /// Forwards a message received with [`Receiver::recv_raw`] to the service.
+    ///
+    /// The message is already counted in the queue size, which is shared with the receiver it was
+    /// received from. Like [`Addr::send`], the message remains counted if the service has shut down.
+    fn send_raw(&self, message: I) {
+        self.tx.send(message).ok(); // it's ok to drop, the response will fail
+    }
 }
 
 impl<I: Interface> fmt::Debug for Addr<I> {
@@ -868,6 +879,32 @@ impl<I: Interface> Receiver<I> {
     }
 }
 
+impl<I: Interface> Receiver<I> {
+    /// Receives the next value without updating the queue size or recording back pressure metrics.
+    ///
+    /// Used by supervisors, which forward messages to the inner receiver of the service. The inner
+    /// channel shares the queue size with this receiver, see [`channel_with_queue_size`], so the
+    /// message remains counted until the service receives it.
+    async fn recv_raw(&mut self) -> Option<I> {
+        self.rx.recv().await
+    }
+
+    /// Returns a new channel sharing the queue size with this receiver.
+    fn inner_channel(&self) -> (Addr<I>, Receiver<I>) {
+        channel_with_queue_size(self.name, self.queue_size.clone())
+    }
+}
+
+impl<I: Interface> Drop for Receiver<I> {
+    fn drop(&mut self) {
+        // Queued messages are never handled, remove them from the queue size.
+        self.rx.close();
+        while self.rx.try_recv().is_ok() {
+            self.queue_size.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
 impl<I: Interface> fmt::Debug for Receiver<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Receiver")
@@ -882,7 +919,14 @@ impl<I: Interface> fmt::Debug for Receiver<I> {
 /// The `Addr` as the sending part provides public access to the service, while the `Receiver`
 /// should remain internal to the service.
 pub fn channel<I: Interface>(name: &'static str) -> (Addr<I>, Receiver<I>) {
-    let queue_size = Arc::new(AtomicU64::new(0));
+    channel_with_queue_size(name, Arc::new(AtomicU64::new(0)))
+}
+
+/// Creates a channel that counts its messages in the given queue size.
+fn channel_with_queue_size<I: Interface>(
+    name: &'static str,
+    queue_size: Arc<AtomicU64>,
+) -> (Addr<I>, Receiver<I>) {
     let (tx, rx) = mpsc::unbounded_channel();
 
     let addr = Addr {
@@ -1045,6 +1089,27 @@ pub trait ServiceSpawnExt {
 
     /// Starts a service and starts tracking its join handle, given a predefined receiver.
     fn start_with<S: Service>(&self, service: S, rx: Receiver<S::Interface>);
+
+    /// Starts a supervised service, which is restarted according to its [`Supervision`] options.
+    ///
+    /// The `factory` creates a new instance of the service for every (re)start. The returned
+    /// [`Addr`] stays valid across restarts.
+    fn start_supervised<S, F>(&self, factory: F, supervision: Supervision) -> Addr<S::Interface>
+    where
+        S: Service,
+        F: FnMut() -> S + Send + 'static;
+
+    /// Starts a supervised service, given a predefined receiver.
+    ///
+    /// See [`start_supervised`](Self::start_supervised).
+    fn start_supervised_with<S, F>(
+        &self,
+        factory: F,
+        supervision: Supervision,
+        rx: Receiver<S::Interface>,
+    ) where
+        S: Service,
+        F: FnMut() -> S + Send + 'static;
 }
 
 impl<T: ServiceSpawn + ?Sized> ServiceSpawnExt for T {
@@ -1057,6 +1122,28 @@ impl<T: ServiceSpawn + ?Sized> ServiceSpawnExt for T {
     fn start_with<S: Service>(&self, service: S, rx: Receiver<S::Interface>) {
         self.start_obj(ServiceObj::new(service, rx));
     }
+
+    fn start_supervised<S, F>(&self, factory: F, supervision: Supervision) -> Addr<S::Interface>
+    where
+        S: Service,
+        F: FnMut() -> S + Send + 'static,
+    {
+        let (addr, rx) = crate::channel(S::name());
+        self.start_supervised_with(factory, supervision, rx);
+        addr
+    }
+
+    fn start_supervised_with<S, F>(
+        &self,
+        factory: F,
+        supervision: Supervision,
+        rx: Receiver<S::Interface>,
+    ) where
+        S: Service,
+        F: FnMut() -> S + Send + 'static,
+    {
+        self.start_obj(ServiceObj::supervised(factory, supervision, rx));
+    }
 }
 
 /// Type erased [`Service`].
@@ -1066,6 +1153,7 @@ impl<T: ServiceSpawn + ?Sized> ServiceSpawnExt for T {
 pub struct ServiceObj {
     name: &'static str,
     future: BoxFuture<'static, ()>,
+    supervision: Option<Arc<SupervisionState>>,
 }
 
 impl ServiceObj {
@@ -1074,6 +1162,28 @@ impl ServiceObj {
         Self {
             name: S::name(),
             future: service.run(rx).boxed(),
+            supervision: None,
+        }
+    }
+
+    /// Creates a new bundled type erased supervised [`Service`].
+    ///
+    /// See [`ServiceSpawnExt::start_supervised`].
+    pub fn supervised<S, F>(
+        factory: F,
+        supervision: Supervision,
+        rx: Receiver<S::Interface>,
+    ) -> Self
+    where
+        S: Service,
+        F: FnMut() -> S + Send + 'static,
+    {
+        let (future, state) = self::supervisor::supervise(factory, supervision, rx);
+
+        Self {
+            name: S::name(),
+            future: future.boxed(),
+            supervision: Some(state),
         }
     }
 
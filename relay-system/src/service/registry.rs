@@ -6,7 +6,7 @@ use std::time::Duration;
 use crate::monitor::MonitoredFuture;
 
 use crate::service::status::{ServiceJoinHandle, ServiceStatusJoinHandle};
-use crate::{RawMetrics, ServiceObj, TaskId};
+use crate::{RawMetrics, ServiceObj, SupervisionMetrics, SupervisionState, TaskId};
 
 /// A point in time snapshot of all started services and their [`ServiceMetrics`].
 pub struct ServicesMetrics(BTreeMap<ServiceId, ServiceMetrics>);
@@ -38,6 +38,11 @@ pub struct ServiceMetrics {
     /// The measure is only updated when the service is polled. A service which
     /// spends a long time idle may not have this measure updated for a long time.
     pub utilization: u8,
+    /// Supervision state of the service.
+    ///
+    /// `None` if the service was not started with
+    /// [`start_supervised`](crate::ServiceSpawnExt::start_supervised).
+    pub supervision: Option<SupervisionMetrics>,
 }
 
 /// A per runtime unique identifier for a started service.
@@ -114,7 +119,7 @@ impl Inner {
         let task_handle = crate::runtime::spawn_in(handle, task_id, future);
         let (status_handle, handle) = crate::service::status::split(task_handle);
 
-        group.add(metrics, status_handle);
+        group.add(metrics, service.supervision, status_handle);
 
         handle
     }
@@ -133,6 +138,7 @@ impl Inner {
                         service.metrics.total_duration_ns.load(Ordering::Relaxed),
                     ),
                     utilization: service.metrics.utilization.load(Ordering::Relaxed),
+                    supervision: service.supervision.as_ref().map(|s| s.metrics()),
                 };
 
                 (id, metrics)
@@ -164,7 +170,12 @@ struct ServiceGroup {
 
 impl ServiceGroup {
     /// Adds a started service to the service group.
-    pub fn add(&mut self, metrics: Arc<RawMetrics>, handle: ServiceStatusJoinHandle) {
+    pub fn add(
+        &mut self,
+        metrics: Arc<RawMetrics>,
+        supervision: Option<Arc<SupervisionState>>,
+        handle: ServiceStatusJoinHandle,
+    ) {
         // Cleanup the group, evicting all finished services, while we're at it.
         self.instances.retain(|s| !s.handle.is_finished());
 
@@ -174,6 +185,7 @@ impl ServiceGroup {
         let service = ServiceInstance {
             instance_id,
             metrics,
+            supervision,
             handle,
         };
 
@@ -196,6 +208,8 @@ struct ServiceInstance {
     /// The handle gives raw access to all tracked metrics, these metrics
     /// should be treated as **read-only**.
     metrics: Arc<RawMetrics>,
+    /// Shared supervision state, if the service is supervised.
+    supervision: Option<Arc<SupervisionState>>,
     /// A handle to the service instance.
     ///
     /// The handle has information about the completion status of the service.
@@ -0,0 +1,425 @@
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+use std::time::Duration;
+
+use futures::FutureExt as _;
+
+use crate::statsd::SystemCounters;
+use crate::{Receiver, Service};
+
+/// Determines whether and how a supervised service is restarted after it panicked.
+///
+/// See [`ServiceSpawnExt::start_supervised`](crate::ServiceSpawnExt::start_supervised).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The service is never restarted.
+    ///
+    /// This is the behavior of services that are not supervised.
+    #[default]
+    Never,
+    /// The service is always restarted immediately.
+    Always,
+    /// The service is restarted with an exponentially increasing delay.
+    ///
+    /// The first restart happens after `initial` and the delay doubles with every following
+    /// restart until it reaches `max`. Once the service was restarted `max_restarts` times, it is
+    /// no longer restarted.
+    Backoff {
+        /// Delay before the first restart.
+        initial: Duration,
+        /// Upper bound for the delay between restarts.
+        max: Duration,
+        /// Maximum number of restarts over the lifetime of the service.
+        max_restarts: u32,
+    },
+}
+
+impl RestartPolicy {
+    /// Returns the delay before the next restart, or `None` if the service must not be restarted.
+    ///
+    /// `restarts` is the amount of times the service has already been restarted.
+    fn next_delay(&self, restarts: u32) -> Option<Duration> {
+        match *self {
+            Self::Never => None,
+            Self::Always => Some(Duration::ZERO),
+            Self::Backoff {
+                initial,
+                max,
+                max_restarts,
+            } => {
+                if restarts >= max_restarts {
+                    return None;
+                }
+
+                let factor = 2u32.saturating_pow(restarts);
+                Some(initial.saturating_mul(factor).min(max))
+            }
+        }
+    }
+}
+
+/// Supervision options for a service.
+///
+/// Created with [`Supervision::new`] and passed to
+/// [`ServiceSpawnExt::start_supervised`](crate::ServiceSpawnExt::start_supervised).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Supervision {
+    policy: RestartPolicy,
+    critical: bool,
+}
+
+impl Supervision {
+    /// Creates supervision options for a non-critical service with the given restart policy.
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            critical: false,
+        }
+    }
+
+    /// Marks the service as critical.
+    ///
+    /// When a critical service panics and its [`RestartPolicy`] does not allow another restart,
+    /// the panic is propagated, which terminates the [`ServiceSet`](crate::ServiceSet) and with it
+    /// usually the process. Non-critical services are instead marked as
+    /// [failed](SupervisionStatus::Failed) and messages sent to them are discarded.
+    pub fn critical(mut self, critical: bool) -> Self {
+        self.critical = critical;
+        self
+    }
+}
+
+/// The current state of a supervised service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupervisionStatus {
+    /// The service is running.
+    Running,
+    /// The service panicked and is waiting to be restarted.
+    Restarting,
+    /// The service panicked and will not be restarted.
+    Failed,
+}
+
+impl SupervisionStatus {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Running,
+            1 => Self::Restarting,
+            _ => Self::Failed,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::Running => 0,
+            Self::Restarting => 1,
+            Self::Failed => 2,
+        }
+    }
+}
+
+/// Supervision state of a single service, part of its [`ServiceMetrics`](crate::ServiceMetrics).
+#[derive(Debug, Clone, Copy)]
+pub struct SupervisionMetrics {
+    /// Whether the service was marked as [critical](Supervision::critical).
+    pub critical: bool,
+    /// Amount of times the service has been restarted after a panic.
+    pub restarts: u32,
+    /// The current status of the service.
+    pub status: SupervisionStatus,
+}
+
+/// Shared supervision state between the supervisor and the service registry.
+#[derive(Debug)]
+pub(crate) struct SupervisionState {
+    critical: bool,
+    restarts: AtomicU32,
+    status: AtomicU8,
+}
+
+impl SupervisionState {
+    fn new(critical: bool) -> Self {
+        Self {
+            critical,
+            restarts: AtomicU32::new(0),
+            status: AtomicU8::new(SupervisionStatus::Running.to_u8()),
+        }
+    }
+
+    fn set_status(&self, status: SupervisionStatus) {
+        self.status.store(status.to_u8(), Ordering::Relaxed);
+    }
+
+    /// Returns a point in time snapshot of the supervision state.
+    pub fn metrics(&self) -> SupervisionMetrics {
+        SupervisionMetrics {
+            critical: self.critical,
+            restarts: self.restarts.load(Ordering::Relaxed),
+            status: SupervisionStatus::from_u8(self.status.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Creates the main future of a supervised service and its shared supervision state.
+///
+/// The returned future owns the receiver of the service. Every incarnation of the service gets a
+/// fresh inner channel, to which messages from `rx` are forwarded. Messages queued in the inner
+/// channel of a panicking service are lost, messages sent while the service waits for a restart
+/// are delivered to the next incarnation.
+///
+/// The inner channels share the queue size with `rx`, so the length of the service's
+/// [`Addr`](crate::Addr) includes forwarded messages until the service has received them.
+pub(crate) fn supervise<S, F>(
+    mut factory: F,
+    supervision: Supervision,
+    mut rx: Receiver<S::Interface>,
+) -> (
+    impl Future<Output = ()> + Send + 'static,
+    Arc<SupervisionState>,
+)
+where
+    S: Service,
+    F: FnMut() -> S + Send + 'static,
+{
+    let state = Arc::new(SupervisionState::new(supervision.critical));
+    let shared_state = Arc::clone(&state);
+
+    let future = async move {
+        loop {
+            let (addr, inner_rx) = rx.inner_channel();
+            let mut addr = Some(addr);
+            let mut service =
+                std::pin::pin!(AssertUnwindSafe(factory().run(inner_rx)).catch_unwind());
+            state.set_status(SupervisionStatus::Running);
+
+            let result = loop {
+                tokio::select! {
+                    biased;
+
+                    result = &mut service => break result,
+                    message = rx.recv_raw(), if addr.is_some() => match message {
+                        Some(message) => {
+                            if let Some(addr) = &addr {
+                                addr.send_raw(message);
+                            }
+                        }
+                        // All outer addresses are gone, close the inner channel to signal the
+                        // service to shut down.
+                        None => addr = None,
+                    }
+                }
+            };
+
+            let Err(panic) = result else {
+                return;
+            };
+
+            let restarts = state.restarts.load(Ordering::Relaxed);
+            let Some(delay) = supervision.policy.next_delay(restarts) else {
+                if state.critical {
+                    std::panic::resume_unwind(panic);
+                }
+
+                relay_log::error!(
+                    service = S::name(),
+                    restarts = restarts,
+                    "non-critical service panicked and will not be restarted"
+                );
+                state.set_status(SupervisionStatus::Failed);
+
+                // Keep the service registered, so its failure remains visible, but discard
+                // messages until all addresses are dropped.
+                while rx.recv().await.is_some() {}
+                return;
+            };
+
+            // Without an address, the service would immediately shut down after restarting.
+            if addr.is_none() {
+                return;
+            }
+
+            relay_log::error!(
+                service = S::name(),
+                restarts = restarts,
+                "service panicked, restarting in {}ms",
+                delay.as_millis()
+            );
+            relay_statsd::metric!(
+                counter(SystemCounters::ServiceRestarted) += 1,
+                service = S::name()
+            );
+
+            state.set_status(SupervisionStatus::Restarting);
+            state.restarts.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(delay).await;
+        }
+    };
+
+    (future, shared_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::{AsyncResponse, FromMessage, Interface, Sender, ServiceSpawnExt as _};
+
+    #[test]
+    fn test_backoff_delay() {
+        let policy = RestartPolicy::Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_millis(350),
+            max_restarts: 4,
+        };
+
+        let delays: Vec<_> = (0..5).map(|i| policy.next_delay(i)).collect();
+        assert_eq!(
+            delays,
+            [
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(350)),
+                Some(Duration::from_millis(350)),
+                None,
+            ]
+        );
+
+        assert_eq!(RestartPolicy::Never.next_delay(0), None);
+        assert_eq!(RestartPolicy::Always.next_delay(1000), Some(Duration::ZERO));
+    }
+
+    /// Message that panics the service when `true` and otherwise responds with the incarnation.
+    struct Ping(bool, Sender<usize>);
+
+    impl Interface for Ping {}
+
+    impl FromMessage<bool> for Ping {
+        type Response = AsyncResponse<usize>;
+
+        fn from_message(message: bool, sender: Sender<usize>) -> Self {
+            Self(message, sender)
+        }
+    }
+
+    struct PanickyService(usize);
+
+    impl Service for PanickyService {
+        type Interface = Ping;
+
+        async fn run(self, mut rx: Receiver<Self::Interface>) {
+            while let Some(Ping(panic, sender)) = rx.recv().await {
+                assert!(!panic, "test panic");
+                sender.send(self.0);
+            }
+        }
+    }
+
+    fn factory() -> impl FnMut() -> PanickyService + Send + 'static {
+        let counter = Arc::new(AtomicUsize::new(0));
+        move || PanickyService(counter.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn supervision_metrics(handle: &crate::Handle) -> SupervisionMetrics {
+        handle
+            .current_services_metrics()
+            .iter()
+            .find_map(|(_, metrics)| metrics.supervision)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_restart_after_panic() {
+        let rt = crate::Runtime::builder("test").worker_threads(1).build();
+        let handle = rt.handle().clone();
+
+        rt.block_on(async {
+            let addr = handle.start_supervised(
+                factory(),
+                Supervision::new(RestartPolicy::Backoff {
+                    initial: Duration::from_millis(1),
+                    max: Duration::from_millis(1),
+                    max_restarts: 1,
+                }),
+            );
+
+            assert_eq!(addr.send(false).await.unwrap(), 0);
+            assert!(addr.send(true).await.is_err());
+            assert_eq!(addr.send(false).await.unwrap(), 1);
+
+            let metrics = supervision_metrics(&handle);
+            assert_eq!(metrics.restarts, 1);
+            assert_eq!(metrics.status, SupervisionStatus::Running);
+
+            // The restart budget is exhausted, the non-critical service is marked as failed.
+            assert!(addr.send(true).await.is_err());
+            assert!(addr.send(false).await.is_err());
+
+            let metrics = supervision_metrics(&handle);
+            assert_eq!(metrics.restarts, 1);
+            assert_eq!(metrics.status, SupervisionStatus::Failed);
+        });
+    }
+
+    /// Service that handles every message only after a permit was added to the semaphore.
+    struct BlockedService(Arc<tokio::sync::Semaphore>);
+
+    impl Service for BlockedService {
+        type Interface = Ping;
+
+        async fn run(self, mut rx: Receiver<Self::Interface>) {
+            while let Some(Ping(_, sender)) = rx.recv().await {
+                self.0.acquire().await.unwrap().forget();
+                sender.send(0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_queue_size_under_backpressure() {
+        let rt = crate::Runtime::builder("test").worker_threads(1).build();
+        let handle = rt.handle().clone();
+
+        rt.block_on(async {
+            let permits = Arc::new(tokio::sync::Semaphore::new(0));
+            let service_permits = Arc::clone(&permits);
+            let addr = handle.start_supervised(
+                move || BlockedService(Arc::clone(&service_permits)),
+                Supervision::new(RestartPolicy::Always),
+            );
+
+            let responses: Vec<_> = (0..3).map(|_| addr.send(false)).collect();
+
+            // The supervisor forwards all messages, but the service only received the first one.
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert_eq!(addr.len(), 2);
+
+            permits.add_permits(3);
+            for response in responses {
+                response.await.unwrap();
+            }
+            assert_eq!(addr.len(), 0);
+        });
+    }
+
+    #[test]
+    fn test_critical_panic_propagates() {
+        let rt = crate::Runtime::builder("test").worker_threads(1).build();
+        let handle = rt.handle().clone();
+
+        rt.block_on(async {
+            let mut services = handle.service_set();
+            let addr = services.start_supervised(
+                factory(),
+                Supervision::new(RestartPolicy::Never).critical(true),
+            );
+
+            assert!(addr.send(true).await.is_err());
+
+            let result = AssertUnwindSafe(services.join()).catch_unwind().await;
+            let panic = result.unwrap_err();
+            assert_eq!(panic.downcast_ref::<&str>(), Some(&"test panic"));
+        });
+    }
+}
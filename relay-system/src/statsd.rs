@@ -18,6 +18,11 @@ pub enum SystemCounters {
     ///  - `file`: The source filename where the task is created.
     ///  - `line`: The source line where the task is created within the file.
     RuntimeTaskTerminated,
+    /// Number of times a supervised service was restarted after a panic.
+    ///
+    /// This metric is tagged with:
+    ///  - `service`: The fully qualified type name of the service implementation.
+    ServiceRestarted,
 }
 
 impl CounterMetric for SystemCounters {
@@ -25,6 +30,7 @@ impl CounterMetric for SystemCounters {
         match self {
             Self::RuntimeTaskCreated => "runtime.task.spawn.created",
             Self::RuntimeTaskTerminated => "runtime.task.spawn.terminated",
+            Self::ServiceRestarted => "service.restarted",
         }
     }
 }
@@ -6,22 +6,50 @@
 //! this, integration tests could fail. To fix this, you will need to add a timeout to your
 //! consumer.
 
-use once_cell::sync::Lazy;
+use std::path::Path;
+use std::sync::OnceLock;
+
 use uaparser::{Parser, UserAgentParser};
 
 #[doc(inline)]
 pub use uaparser::{Device, OS, UserAgent};
 
-/// The global [`UserAgentParser`] already configured with a user agent database.
+/// The global [`UserAgentParser`] configured with a user agent database.
 ///
-/// For usage, see [`Parser`].
-static UA_PARSER: Lazy<UserAgentParser> = Lazy::new(|| {
-    let ua_regexes = include_bytes!("../uap-core/regexes.yaml");
+/// Use [`parser`] to access it, which falls back to the embedded database if no other database
+/// has been loaded.
+static UA_PARSER: OnceLock<UserAgentParser> = OnceLock::new();
+
+/// An error returned by [`init_parser_from_file`].
+#[derive(Debug, thiserror::Error)]
+pub enum ParserError {
+    /// The regexes file could not be read or compiled.
+    #[error("failed to load user agent regexes")]
+    Regexes(#[from] uaparser::Error),
+    /// The parser has already been initialized before loading the file.
+    #[error("user agent parser already initialized")]
+    AlreadyInitialized,
+}
+
+fn build_parser(ua_regexes: &[u8]) -> Result<UserAgentParser, uaparser::Error> {
     UserAgentParser::builder()
         .with_unicode_support(false)
         .build_from_bytes(ua_regexes)
+}
+
+fn load_parser(path: &Path) -> Result<UserAgentParser, uaparser::Error> {
+    let ua_regexes = std::fs::read(path)?;
+    build_parser(&ua_regexes)
+}
+
+fn embedded_parser() -> UserAgentParser {
+    build_parser(include_bytes!("../uap-core/regexes.yaml"))
         .expect("Could not create UserAgent. You are probably using a bad build of relay.")
-});
+}
+
+fn parser() -> &'static UserAgentParser {
+    UA_PARSER.get_or_init(embedded_parser)
+}
 
 /// Initializes the user agent parser.
 ///
@@ -29,26 +57,81 @@ static UA_PARSER: Lazy<UserAgentParser> = Lazy::new(|| {
 /// agent parser initializes on-demand when using one of the parse methods. This function forces
 /// initialization at a convenient point without introducing unwanted delays.
 pub fn init_parser() {
-    Lazy::force(&UA_PARSER);
+    parser();
+}
+
+/// Initializes the user agent parser from a `regexes.yaml` file in the uap-core format.
+///
+/// This allows to update the user agent database without upgrading Relay. If the file cannot be
+/// loaded, the parser is initialized with the embedded database instead and an error is returned.
+/// The database can only be loaded once, before any user agent has been parsed.
+pub fn init_parser_from_file(path: &Path) -> Result<(), ParserError> {
+    if UA_PARSER.get().is_some() {
+        return Err(ParserError::AlreadyInitialized);
+    }
+
+    match load_parser(path) {
+        Ok(parser) => UA_PARSER
+            .set(parser)
+            .map_err(|_| ParserError::AlreadyInitialized),
+        Err(error) => {
+            init_parser();
+            Err(error.into())
+        }
+    }
 }
 
 /// Returns the family and version of a user agent client.
 ///
 /// Defaults to an empty user agent.
 pub fn parse_user_agent(user_agent: &str) -> UserAgent {
-    UA_PARSER.parse_user_agent(user_agent)
+    parser().parse_user_agent(user_agent)
 }
 
 /// Returns the family, brand, and model of the device of the requesting client.
 ///
 /// Defaults to an empty device.
 pub fn parse_device(user_agent: &str) -> Device {
-    UA_PARSER.parse_device(user_agent)
+    parser().parse_device(user_agent)
 }
 
 /// Returns the family and version of the operating system of the requesting client.
 ///
 /// Defaults to an empty operating system.
 pub fn parse_os(user_agent: &str) -> OS {
-    UA_PARSER.parse_os(user_agent)
+    parser().parse_os(user_agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_parser_from_file() {
+        let path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/uap-core/regexes.yaml"
+        ));
+        let parser = load_parser(path).unwrap();
+        let user_agent = parser.parse_user_agent(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+        );
+        assert_eq!(user_agent.family, "Firefox");
+    }
+
+    #[test]
+    fn test_load_parser_missing_file() {
+        assert!(load_parser(Path::new("does/not/exist/regexes.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_init_parser_fallback() {
+        let result = init_parser_from_file(Path::new("does/not/exist/regexes.yaml"));
+        assert!(result.is_err());
+
+        let user_agent = parse_user_agent(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+        );
+        assert_eq!(user_agent.family, "Firefox");
+    }
 }
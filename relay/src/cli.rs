@@ -1,5 +1,5 @@
 use std::path::{Path, PathBuf};
-use std::{env, io};
+use std::{env, fs, io};
 
 use anyhow::{Result, anyhow, bail};
 use clap::ArgMatches;
@@ -8,6 +8,7 @@ use dialoguer::{Confirm, Select};
 use relay_config::{
     Config, ConfigError, ConfigErrorKind, Credentials, MinimalConfig, OverridableConfig, RelayMode,
 };
+use relay_pii::PiiConfig;
 use uuid::Uuid;
 
 use crate::cliapp::make_app;
@@ -44,6 +45,8 @@ pub fn execute() -> Result<()> {
         }
     } else if let Some(matches) = matches.subcommand_matches("generate-completions") {
         return generate_completions(matches);
+    } else if let Some(matches) = matches.subcommand_matches("pii") {
+        return manage_pii(matches);
     }
 
     // Commands that need a loaded config:
@@ -198,6 +201,10 @@ pub fn manage_credentials(mut config: Config, matches: &ArgMatches) -> Result<()
                     }
                 }
             },
+            next: config.credentials().and_then(|x| x.next.clone()),
+            previous_public_key: config
+                .credentials()
+                .and_then(|x| x.previous_public_key.clone()),
         }))?;
         if !changed {
             println!("Nothing was changed");
@@ -221,6 +228,8 @@ pub fn manage_credentials(mut config: Config, matches: &ArgMatches) -> Result<()
         } else {
             println!("No credentials");
         }
+    } else if let Some(matches) = matches.subcommand_matches("rotate") {
+        rotate_credentials(config, matches)?;
     } else if matches.subcommand_matches("show").is_some() {
         if !config.has_credentials() {
             bail!("no stored credentials");
@@ -235,6 +244,46 @@ pub fn manage_credentials(mut config: Config, matches: &ArgMatches) -> Result<()
     Ok(())
 }
 
+/// Rotates the key pair of the stored credentials in three steps.
+///
+/// The next key must be registered with the upstream after staging and before activation. The
+/// previous key can be removed from the upstream after retiring it.
+fn rotate_credentials(mut config: Config, matches: &ArgMatches) -> Result<()> {
+    let Some(mut credentials) = config.credentials().cloned() else {
+        bail!("no stored credentials");
+    };
+
+    if let Some(matches) = matches.subcommand_matches("stage") {
+        if credentials.next.is_some() && !matches.get_flag("overwrite") {
+            bail!("aborting because a key is already staged. Pass --overwrite to force.");
+        }
+        let next_key = credentials.stage_next().public_key.clone();
+        config.replace_credentials(Some(credentials))?;
+        println!("Staged next key: {next_key}");
+        println!(
+            "Add this public key to the upstream, then run `relay credentials rotate activate`."
+        );
+    } else if matches.subcommand_matches("activate").is_some() {
+        if !credentials.activate_next() {
+            bail!("no staged key. Run `relay credentials rotate stage` first.");
+        }
+        config.replace_credentials(Some(credentials))?;
+        println!("Activated next key, restart Relay to sign with it:");
+        setup::dump_credentials(&config);
+    } else if matches.subcommand_matches("retire").is_some() {
+        let Some(previous_key) = credentials.retire_previous() else {
+            bail!("no previous key to retire");
+        };
+        config.replace_credentials(Some(credentials))?;
+        println!("Retired previous key: {previous_key}");
+        println!("This public key can now be removed from the upstream.");
+    } else {
+        unreachable!();
+    }
+
+    Ok(())
+}
+
 pub fn manage_config(config: &Config, matches: &ArgMatches) -> Result<()> {
     if let Some(matches) = matches.subcommand_matches("init") {
         init_config(config.path(), matches)
@@ -349,6 +398,34 @@ pub fn generate_completions(matches: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+pub fn manage_pii(matches: &ArgMatches) -> Result<()> {
+    if let Some(matches) = matches.subcommand_matches("audit") {
+        audit_pii(matches)
+    } else {
+        unreachable!();
+    }
+}
+
+fn audit_pii(matches: &ArgMatches) -> Result<()> {
+    let config_path = matches.get_one::<PathBuf>("pii_config").unwrap();
+    let config: PiiConfig = serde_json::from_slice(&fs::read(config_path)?)?;
+    config.compiled().force_compile()?;
+
+    let item_path = matches.get_one::<PathBuf>("item").unwrap();
+    let item = fs::read(item_path)?;
+
+    let item_type = matches.get_one::<String>("type").unwrap();
+    let filename = item_path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let report = relay_replays::audit::audit_item(&config, item_type, &filename, &item)?;
+
+    serde_json::to_writer_pretty(io::stdout().lock(), &report)?;
+    println!();
+    Ok(())
+}
+
 pub fn run(config: Config, _matches: &ArgMatches) -> Result<()> {
     setup::dump_spawn_infos(&config);
     setup::check_config(&config)?;
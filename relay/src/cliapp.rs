@@ -170,8 +170,8 @@ pub fn make_app() -> Command {
                      \n\
                      Multiple relays can share the same public/secret key pair for as \
                      long as they use different relay IDs.  Once a relay (as identified \
-                     by the ID) has signed in with a certain key it cannot be changed \
-                     any more.",
+                     by the ID) has signed in with a certain key it can only be changed \
+                     through a key rotation.",
                 )
                 .subcommand(
                     Command::new("generate")
@@ -209,6 +209,38 @@ pub fn make_app() -> Command {
                                 .help("Do not prompt for confirmation"),
                         ),
                 )
+                .subcommand(
+                    Command::new("rotate")
+                        .subcommand_required(true)
+                        .about("Rotate the key pair of the stored credentials")
+                        .after_help(
+                            "Rotating keys happens in three steps.  First, 'stage' \
+                             generates the next key pair, whose public key has to be \
+                             added to the upstream.  The upstream accepts signatures of \
+                             both keys.  Then, 'activate' makes the staged key pair the \
+                             current one.  Once all Relays sign with the new key, 'retire' \
+                             removes the previous public key, which can then be removed \
+                             from the upstream.",
+                        )
+                        .subcommand(
+                            Command::new("stage")
+                                .about("Generate and stage the next key pair")
+                                .arg(
+                                    Arg::new("overwrite")
+                                        .long("overwrite")
+                                        .action(ArgAction::SetTrue)
+                                        .help("Overwrite an already staged key pair"),
+                                ),
+                        )
+                        .subcommand(
+                            Command::new("activate")
+                                .about("Replace the current key pair with the staged key pair"),
+                        )
+                        .subcommand(
+                            Command::new("retire")
+                                .about("Remove the previous public key after activation"),
+                        ),
+                )
                 .subcommand(
                     Command::new("show")
                         .about("Show currently stored credentials.")
@@ -292,6 +324,59 @@ pub fn make_app() -> Command {
                         ),
                 ),
         )
+        .subcommand(
+            Command::new("pii")
+                .about("Inspect data scrubbing rules")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("audit")
+                        .about("Report which PII rules match an item")
+                        .after_help(
+                            "This runs a PII config over a single item and prints a JSON \
+                             report of the rules that matched, the paths they matched \
+                             at, how often, and which redaction they apply.  The item \
+                             itself is not modified.  Replay recordings and attachments \
+                             are read as raw bytes, all other item types as JSON.  This \
+                             command does not require a Relay config.",
+                        )
+                        .arg(
+                            Arg::new("pii_config")
+                                .long("pii-config")
+                                .value_name("PATH")
+                                .required(true)
+                                .value_hint(ValueHint::FilePath)
+                                .value_parser(ValueParser::path_buf())
+                                .help("The path to a PII config in JSON format"),
+                        )
+                        .arg(
+                            Arg::new("type")
+                                .long("type")
+                                .short('t')
+                                .value_parser([
+                                    "event",
+                                    "span",
+                                    "log",
+                                    "replay_event",
+                                    "replay_recording",
+                                    "attachment",
+                                    "check_in",
+                                ])
+                                .default_value("event")
+                                .help("The type of the item"),
+                        )
+                        .arg(
+                            Arg::new("item")
+                                .value_name("ITEM")
+                                .required(true)
+                                .value_hint(ValueHint::FilePath)
+                                .value_parser(ValueParser::path_buf())
+                                .help(
+                                    "The path to the item.  For attachments, the file name \
+                                     is used to match selectors.",
+                                ),
+                        ),
+                ),
+        )
         .subcommand(
             Command::new("generate-completions")
                 .about("Generate shell completion file")
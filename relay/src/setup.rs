@@ -93,9 +93,18 @@ pub fn dump_credentials(config: &Config) {
         None => println!("  relay id: -"),
     };
     match config.public_key() {
-        Some(key) => println!("  public key: {key}"),
+        Some(key) => println!("  public key: {key} (key id: {})", key.key_id()),
         None => println!("  public key: -"),
     };
+    if let Some(credentials) = config.credentials() {
+        if let Some(next) = &credentials.next {
+            let key = &next.public_key;
+            println!("  next public key: {key} (key id: {})", key.key_id());
+        }
+        if let Some(key) = &credentials.previous_public_key {
+            println!("  previous public key: {key} (key id: {})", key.key_id());
+        }
+    }
 }
 
 /// Initialize the metric system.